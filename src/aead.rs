@@ -0,0 +1,469 @@
+//! 认证加密
+//!
+//! 定义统一的 Aead 接口和按算法ID索引的注册表，密文头部可以记录所用的算法，
+//! 解密时按头部中的ID动态选择算法。
+//!
+//! 内置 AES-128/256-GCM、ChaCha20-Poly1305 和 SM4-GCM，seal/open 为一次性加解密函数，
+//! AeadContext 保存算法和密钥，用于同一密钥的多次加解密
+
+use ring::aead::{self as raead, Aad, LessSafeKey, Nonce, UnboundKey};
+
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+use crate::sm4::Sm4Gcm;
+
+/// AES-128-GCM 的算法ID
+pub const AES_128_GCM_ID: u16 = 1;
+/// AES-256-GCM 的算法ID
+pub const AES_256_GCM_ID: u16 = 2;
+/// SM4-GCM 的算法ID
+pub const SM4_GCM_ID: u16 = 3;
+/// ChaCha20-Poly1305 的算法ID
+pub const CHACHA20_POLY1305_ID: u16 = 4;
+
+/// 带辅助数据的认证加密算法
+///
+/// 算法对象本身不保存密钥，每次调用时传入
+pub trait Aead: Sync {
+    /// 算法ID，写入密文头部
+    fn id(&self) -> u16;
+
+    /// 算法名
+    fn name(&self) -> &'static str;
+
+    /// 密钥长度
+    fn key_size(&self) -> usize;
+
+    /// nonce长度
+    fn nonce_size(&self) -> usize;
+
+    /// 认证标签长度
+    fn tag_size(&self) -> usize;
+
+    /// 原地加密，认证标签附加在密文之后
+    ///
+    /// key: 密钥
+    /// nonce: 同一密钥下不能重复使用
+    /// aad: 辅助数据
+    /// in_out: 输入明文，输出密文和认证标签
+    fn seal_in_place(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut Vec<u8>,
+    ) -> Result<(), String>;
+
+    /// 原地解密，输入为附加了认证标签的密文
+    ///
+    /// 认证失败时 in_out 的内容未定义，不能使用
+    fn open_in_place(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut Vec<u8>,
+    ) -> Result<(), String>;
+
+    /// 原地加密，返回单独的认证标签
+    fn seal_detached(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+    ) -> Result<Vec<u8>, String> {
+        let mut buf = in_out.to_vec();
+        self.seal_in_place(key, nonce, aad, &mut buf)?;
+        let tag = buf.split_off(in_out.len());
+        in_out.copy_from_slice(&buf);
+        Ok(tag)
+    }
+
+    /// 原地解密，认证标签单独传入
+    fn open_detached(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+        tag: &[u8],
+    ) -> Result<(), String> {
+        if tag.len() != self.tag_size() {
+            return Err("aead: bad tag size".to_string());
+        }
+        let mut buf = in_out.to_vec();
+        buf.extend_from_slice(tag);
+        self.open_in_place(key, nonce, aad, &mut buf)?;
+        in_out.copy_from_slice(&buf);
+        Ok(())
+    }
+}
+
+/// 基于 ring 实现的算法
+pub struct RingAead {
+    id: u16,
+    name: &'static str,
+    alg: &'static raead::Algorithm,
+}
+
+/// AES-128-GCM
+pub static AES_128_GCM: RingAead = RingAead {
+    id: AES_128_GCM_ID,
+    name: "AES-128-GCM",
+    alg: &raead::AES_128_GCM,
+};
+
+/// AES-256-GCM
+pub static AES_256_GCM: RingAead = RingAead {
+    id: AES_256_GCM_ID,
+    name: "AES-256-GCM",
+    alg: &raead::AES_256_GCM,
+};
+
+/// ChaCha20-Poly1305 (RFC 8439)，没有AES硬件加速的平台上比 AES-GCM 快
+pub static CHACHA20_POLY1305: RingAead = RingAead {
+    id: CHACHA20_POLY1305_ID,
+    name: "ChaCha20-Poly1305",
+    alg: &raead::CHACHA20_POLY1305,
+};
+
+/// SM4-GCM
+pub static SM4_GCM: Sm4Gcm = Sm4Gcm;
+
+impl RingAead {
+    fn key(&self, key: &[u8]) -> Result<LessSafeKey, String> {
+        UnboundKey::new(self.alg, key)
+            .map(LessSafeKey::new)
+            .map_err(|_| format!("aead: {} key must be {} bytes", self.name, self.key_size()))
+    }
+}
+
+fn ring_nonce(nonce: &[u8]) -> Result<Nonce, String> {
+    Nonce::try_assume_unique_for_key(nonce).map_err(|_| "aead: bad nonce size".to_string())
+}
+
+impl Aead for RingAead {
+    fn id(&self) -> u16 {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn key_size(&self) -> usize {
+        self.alg.key_len()
+    }
+
+    fn nonce_size(&self) -> usize {
+        self.alg.nonce_len()
+    }
+
+    fn tag_size(&self) -> usize {
+        self.alg.tag_len()
+    }
+
+    fn seal_in_place(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        self.key(key)?
+            .seal_in_place_append_tag(ring_nonce(nonce)?, Aad::from(aad), in_out)
+            .map_err(|_| "aead: seal failed".to_string())
+    }
+
+    fn open_in_place(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut Vec<u8>,
+    ) -> Result<(), String> {
+        let len = self
+            .key(key)?
+            .open_in_place(ring_nonce(nonce)?, Aad::from(aad), in_out)
+            .map_err(|_| "aead: authentication failed".to_string())?
+            .len();
+        in_out.truncate(len);
+        Ok(())
+    }
+
+    fn seal_detached(
+        &self,
+        key: &[u8],
+        nonce: &[u8],
+        aad: &[u8],
+        in_out: &mut [u8],
+    ) -> Result<Vec<u8>, String> {
+        self.key(key)?
+            .seal_in_place_separate_tag(ring_nonce(nonce)?, Aad::from(aad), in_out)
+            .map(|tag| tag.as_ref().to_vec())
+            .map_err(|_| "aead: seal failed".to_string())
+    }
+}
+
+/// 按ID查找内置算法
+pub fn lookup(id: u16) -> Option<&'static dyn Aead> {
+    match id {
+        AES_128_GCM_ID => Some(&AES_128_GCM),
+        AES_256_GCM_ID => Some(&AES_256_GCM),
+        SM4_GCM_ID => Some(&SM4_GCM),
+        CHACHA20_POLY1305_ID => Some(&CHACHA20_POLY1305),
+        _ => None,
+    }
+}
+
+/// 一次性加密，返回密文和附加在其后的认证标签
+///
+/// alg: 算法
+/// key: 密钥
+/// nonce: 同一密钥下不能重复使用
+/// aad: 辅助数据
+/// plaintext: 明文
+pub fn seal(
+    alg: &dyn Aead,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, String> {
+    let mut in_out = plaintext.to_vec();
+    alg.seal_in_place(key, nonce, aad, &mut in_out)?;
+    Ok(in_out)
+}
+
+/// 一次性解密，输入为附加了认证标签的密文
+pub fn open(
+    alg: &dyn Aead,
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, String> {
+    if ciphertext.len() < alg.tag_size() {
+        return Err("aead: ciphertext too short".to_string());
+    }
+    let mut in_out = ciphertext.to_vec();
+    alg.open_in_place(key, nonce, aad, &mut in_out)?;
+    Ok(in_out)
+}
+
+/// 绑定了算法和密钥的加解密上下文
+pub struct AeadContext {
+    alg: &'static dyn Aead,
+    key: SecretBytes,
+}
+
+impl AeadContext {
+    /// 构建上下文，密钥长度必须与算法匹配
+    ///
+    /// alg: 算法
+    /// key: 密钥
+    pub fn new(alg: &'static dyn Aead, key: &[u8]) -> Result<Self, String> {
+        if key.len() != alg.key_size() {
+            return Err(format!(
+                "aead: {} key must be {} bytes",
+                alg.name(),
+                alg.key_size()
+            ));
+        }
+        Ok(AeadContext {
+            alg,
+            key: SecretBytes::from(key),
+        })
+    }
+
+    /// 算法
+    pub fn algorithm(&self) -> &'static dyn Aead {
+        self.alg
+    }
+
+    /// 使用调用方给出的nonce加密
+    pub fn seal(&self, nonce: &[u8], aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        seal(self.alg, &self.key, nonce, aad, plaintext)
+    }
+
+    /// 使用调用方给出的nonce解密
+    pub fn open(&self, nonce: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        open(self.alg, &self.key, nonce, aad, ciphertext)
+    }
+
+    /// 使用随机nonce加密，输出 nonce | 密文 | 认证标签
+    ///
+    /// 12字节的随机nonce在同一密钥下加密约 2^32 条消息后碰撞概率不可忽略，需要在此之前更换密钥
+    pub fn seal_with_random_nonce(&self, aad: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = genSecureRandBytes(self.alg.nonce_size());
+        let mut sealed = self.seal(&out, aad, plaintext)?;
+        out.append(&mut sealed);
+        Ok(out)
+    }
+
+    /// 解密 seal_with_random_nonce 的输出
+    pub fn open_with_nonce_prefix(&self, aad: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() < self.alg.nonce_size() + self.alg.tag_size() {
+            return Err("aead: ciphertext too short".to_string());
+        }
+        let (nonce, ciphertext) = data.split_at(self.alg.nonce_size());
+        self.open(nonce, aad, ciphertext)
+    }
+}
+
+/// 算法注册表
+///
+/// 带头部的密文格式: 算法ID(2字节大端) | nonce | 密文 | 认证标签，
+/// 算法ID同时作为辅助数据的前缀参与认证
+pub struct AeadRegistry {
+    algorithms: Vec<&'static dyn Aead>,
+}
+
+impl AeadRegistry {
+    /// 构建包含所有内置算法的注册表
+    pub fn new() -> Self {
+        AeadRegistry {
+            algorithms: vec![&AES_128_GCM, &AES_256_GCM, &SM4_GCM, &CHACHA20_POLY1305],
+        }
+    }
+
+    /// 注册算法，ID已存在时返回错误
+    pub fn register(&mut self, alg: &'static dyn Aead) -> Result<(), String> {
+        if self.get(alg.id()).is_some() {
+            return Err(format!(
+                "aead: algorithm id {} already registered",
+                alg.id()
+            ));
+        }
+        self.algorithms.push(alg);
+        Ok(())
+    }
+
+    /// 按ID查找算法
+    pub fn get(&self, id: u16) -> Option<&'static dyn Aead> {
+        self.algorithms.iter().find(|alg| alg.id() == id).copied()
+    }
+
+    /// 使用随机nonce加密，输出带算法头部的密文
+    ///
+    /// id: 算法ID
+    /// key: 密钥
+    /// aad: 辅助数据
+    /// plaintext: 明文
+    pub fn seal(
+        &self,
+        id: u16,
+        key: &[u8],
+        aad: &[u8],
+        plaintext: &[u8],
+    ) -> Result<Vec<u8>, String> {
+        let alg = self
+            .get(id)
+            .ok_or_else(|| format!("aead: unknown algorithm id {}", id))?;
+        let nonce = genSecureRandBytes(alg.nonce_size());
+        let mut in_out = plaintext.to_vec();
+        alg.seal_in_place(key, &nonce, &header_aad(id, aad), &mut in_out)?;
+
+        let mut out = id.to_be_bytes().to_vec();
+        out.extend_from_slice(&nonce);
+        out.append(&mut in_out);
+        Ok(out)
+    }
+
+    /// 按头部中的算法ID解密
+    pub fn open(&self, key: &[u8], aad: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        if ciphertext.len() < 2 {
+            return Err("aead: ciphertext too short".to_string());
+        }
+        let id = u16::from_be_bytes([ciphertext[0], ciphertext[1]]);
+        let alg = self
+            .get(id)
+            .ok_or_else(|| format!("aead: unknown algorithm id {}", id))?;
+        let body = &ciphertext[2..];
+        if body.len() < alg.nonce_size() + alg.tag_size() {
+            return Err("aead: ciphertext too short".to_string());
+        }
+        let (nonce, sealed) = body.split_at(alg.nonce_size());
+        let mut in_out = sealed.to_vec();
+        alg.open_in_place(key, nonce, &header_aad(id, aad), &mut in_out)?;
+        Ok(in_out)
+    }
+}
+
+fn header_aad(id: u16, aad: &[u8]) -> Vec<u8> {
+    let mut out = id.to_be_bytes().to_vec();
+    out.extend_from_slice(aad);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_aead() {
+        // test case 3 from: The Galois/Counter Mode of Operation (GCM), McGrew & Viega
+        let key = Vec::from_hex("feffe9928665731c6d6a8f9467308308").unwrap();
+        let nonce = Vec::from_hex("cafebabefacedbaddecaf888").unwrap();
+        let plain = Vec::from_hex("d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b391aafd255").unwrap();
+        let cipher = Vec::from_hex("42831ec2217774244b7221b784d0d49ce3aa212f2c02a4e035c17e2329aca12e21d514b25466931c7d8f6a5aac84aa051ba30b396a0aac973d58e091473f5985").unwrap();
+        let tag = Vec::from_hex("4d5c2af327cd64a62cf35abd2ba6fab4").unwrap();
+
+        let alg = lookup(AES_128_GCM_ID).unwrap();
+        let mut in_out = plain.clone();
+        assert_eq!(
+            alg.seal_detached(&key, &nonce, &[], &mut in_out).unwrap(),
+            tag
+        );
+        assert_eq!(in_out, cipher);
+        alg.open_detached(&key, &nonce, &[], &mut in_out, &tag)
+            .unwrap();
+        assert_eq!(in_out, plain);
+
+        let mut in_out = plain.clone();
+        alg.seal_in_place(&key, &nonce, &[], &mut in_out).unwrap();
+        assert_eq!(&in_out[cipher.len()..], &tag[..]);
+        alg.open_in_place(&key, &nonce, &[], &mut in_out).unwrap();
+        assert_eq!(in_out, plain);
+
+        let mut registry = AeadRegistry::new();
+        assert!(registry.register(&AES_256_GCM).is_err());
+        let key = [7u8; 32];
+        let sealed = registry
+            .seal(AES_256_GCM_ID, &key, b"aad", b"hello")
+            .unwrap();
+        assert_eq!(registry.open(&key, b"aad", &sealed).unwrap(), b"hello");
+        assert!(registry.open(&key, b"other", &sealed).is_err());
+
+        // 篡改算法ID时无法解密
+        let mut tampered = sealed;
+        tampered[1] = AES_128_GCM_ID as u8;
+        assert!(registry.open(&key[..16], b"aad", &tampered).is_err());
+    }
+
+    #[test]
+    fn test_chacha20_poly1305() {
+        // test vector from: https://tools.ietf.org/html/rfc8439#section-2.8.2
+        let key = Vec::from_hex("808182838485868788898a8b8c8d8e8f909192939495969798999a9b9c9d9e9f")
+            .unwrap();
+        let nonce = Vec::from_hex("070000004041424344454647").unwrap();
+        let aad = Vec::from_hex("50515253c0c1c2c3c4c5c6c7").unwrap();
+        let plain = b"Ladies and Gentlemen of the class of '99: If I could offer you only one tip for the future, sunscreen would be it.";
+        let sealed = Vec::from_hex("d31a8d34648e60db7b86afbc53ef7ec2a4aded51296e08fea9e2b5a736ee62d63dbea45e8ca9671282fafb69da92728b1a71de0a9e060b2905d6a5b67ecd3b3692ddbd7f2d778b8c9803aee328091b58fab324e4fad675945585808b4831d7bc3ff4def08e4b7a9de576d26586cec64b61161ae10b594f09e26a7e902ecbd0600691").unwrap();
+
+        let alg = lookup(CHACHA20_POLY1305_ID).unwrap();
+        assert_eq!(seal(alg, &key, &nonce, &aad, plain).unwrap(), sealed);
+        assert_eq!(open(alg, &key, &nonce, &aad, &sealed).unwrap(), &plain[..]);
+        assert!(open(alg, &key, &nonce, b"", &sealed).is_err());
+
+        let ctx = AeadContext::new(&CHACHA20_POLY1305, &key).unwrap();
+        assert_eq!(ctx.open(&nonce, &aad, &sealed).unwrap(), &plain[..]);
+        let data = ctx.seal_with_random_nonce(&aad, plain).unwrap();
+        assert_eq!(data.len(), 12 + plain.len() + 16);
+        assert_eq!(ctx.open_with_nonce_prefix(&aad, &data).unwrap(), &plain[..]);
+        assert!(ctx.open_with_nonce_prefix(&aad, &data[..27]).is_err());
+        assert!(AeadContext::new(&AES_256_GCM, &key[..16]).is_err());
+    }
+}
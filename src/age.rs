@@ -0,0 +1,513 @@
+//! age 文件加密格式
+//!
+//! 兼容 age-encryption.org/v1 格式，支持 X25519 接收者和 scrypt 口令接收者，
+//! 加密后的文件可以直接用 age/rage 等工具解密，反之亦然
+
+use std::io::{BufRead, BufReader, Read, Write};
+
+use crypto::scrypt::{scrypt, ScryptParams};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305};
+use ring::{hkdf, hmac};
+
+use crate::ct::ct_eq;
+use crate::encoding::bech32::{self, Variant};
+use crate::random::genSecureRandBytes;
+use crate::stream::{self, TAG_SIZE};
+use crate::x25519;
+
+const VERSION_LINE: &str = "age-encryption.org/v1";
+const X25519_INFO: &[u8] = b"age-encryption.org/v1/X25519";
+const SCRYPT_LABEL: &[u8] = b"age-encryption.org/v1/scrypt";
+const FILE_KEY_SIZE: usize = 16;
+const COLUMNS: usize = 64;
+/// 解密时允许的最大scrypt工作因子，防止恶意文件消耗过多资源
+const MAX_SCRYPT_LOG_N: u8 = 22;
+
+/// X25519 接收者(公钥)，文本形式为 "age1..."
+#[derive(Debug, Clone)]
+pub struct X25519Recipient([u8; 32]);
+
+impl PartialEq for X25519Recipient {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for X25519Recipient {}
+
+impl X25519Recipient {
+    /// 从 "age1..." 形式的字符串解析接收者
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (hrp, data, variant) = bech32::decode_bytes(s)?;
+        if hrp != "age" || variant != Variant::Bech32 || data.len() != 32 {
+            return Err("age: malformed X25519 recipient".to_string());
+        }
+        let mut pk = [0u8; 32];
+        pk.copy_from_slice(&data);
+        Ok(X25519Recipient(pk))
+    }
+
+    /// 编码为 "age1..." 形式的字符串
+    pub fn to_bech32(&self) -> String {
+        bech32::encode_bytes("age", &self.0, Variant::Bech32).expect("age: valid hrp")
+    }
+}
+
+/// X25519 身份(私钥)，文本形式为 "AGE-SECRET-KEY-1..."
+#[derive(Clone)]
+pub struct X25519Identity([u8; 32]);
+
+impl PartialEq for X25519Identity {
+    /// 常量时间比较私钥
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for X25519Identity {}
+
+impl X25519Identity {
+    /// 生成新的随机身份
+    pub fn generate() -> Self {
+        let mut sk = [0u8; 32];
+        sk.copy_from_slice(&genSecureRandBytes(32));
+        X25519Identity(sk)
+    }
+
+    /// 从 "AGE-SECRET-KEY-1..." 形式的字符串解析身份
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (hrp, data, variant) = bech32::decode_bytes(s)?;
+        if hrp != "age-secret-key-" || variant != Variant::Bech32 || data.len() != 32 {
+            return Err("age: malformed X25519 identity".to_string());
+        }
+        let mut sk = [0u8; 32];
+        sk.copy_from_slice(&data);
+        Ok(X25519Identity(sk))
+    }
+
+    /// 编码为 "AGE-SECRET-KEY-1..." 形式的字符串
+    pub fn to_bech32(&self) -> String {
+        bech32::encode_bytes("age-secret-key-", &self.0, Variant::Bech32)
+            .expect("age: valid hrp")
+            .to_ascii_uppercase()
+    }
+
+    /// 获取对应的接收者
+    pub fn recipient(&self) -> X25519Recipient {
+        X25519Recipient(x25519::scalarmult_base(&self.0))
+    }
+}
+
+/// 加密时的接收者
+pub enum Recipient {
+    /// X25519 公钥接收者，可以有多个
+    X25519(X25519Recipient),
+    /// scrypt 口令接收者，必须是唯一的接收者
+    ///
+    /// work_factor: scrypt 参数N的以2为底的对数，age 默认为18
+    Scrypt { passphrase: String, work_factor: u8 },
+}
+
+/// 解密时的身份
+pub enum Identity {
+    X25519(X25519Identity),
+    Scrypt(String),
+}
+
+/// 加密数据为 age 格式
+///
+/// recipients: 接收者列表
+/// reader: 明文
+/// writer: 输出的 age 格式密文
+pub fn encrypt<R: Read, W: Write>(
+    recipients: &[Recipient],
+    reader: R,
+    mut writer: W,
+) -> Result<(), String> {
+    if recipients.is_empty() {
+        return Err("age: no recipients".to_string());
+    }
+    if recipients.len() > 1
+        && recipients
+            .iter()
+            .any(|r| matches!(r, Recipient::Scrypt { .. }))
+    {
+        return Err("age: scrypt recipient must be the only recipient".to_string());
+    }
+
+    let file_key = genSecureRandBytes(FILE_KEY_SIZE);
+    let mut header = format!("{}\n", VERSION_LINE);
+    for recipient in recipients {
+        let stanza = match recipient {
+            Recipient::X25519(pk) => wrap_x25519(&file_key, pk)?,
+            Recipient::Scrypt {
+                passphrase,
+                work_factor,
+            } => wrap_scrypt(&file_key, passphrase, *work_factor)?,
+        };
+        header.push_str(&stanza);
+    }
+    header.push_str("---");
+    let mac = header_mac(&file_key, header.as_bytes());
+    header.push(' ');
+    header.push_str(&base64::encode_config(&mac, base64::STANDARD_NO_PAD));
+    header.push('\n');
+    writer
+        .write_all(header.as_bytes())
+        .map_err(|e| e.to_string())?;
+
+    let nonce = genSecureRandBytes(16);
+    writer.write_all(&nonce).map_err(|e| e.to_string())?;
+    let payload_key = hkdf_sha256(&file_key, &nonce, b"payload");
+    stream::seal(&payload_key, &[], reader, writer)
+}
+
+/// 解密 age 格式的数据
+///
+/// identities: 用于尝试解密的身份列表
+/// reader: age 格式密文
+/// writer: 输出的明文
+pub fn decrypt<R: Read, W: Write>(
+    identities: &[Identity],
+    reader: R,
+    writer: W,
+) -> Result<(), String> {
+    let mut reader = BufReader::new(reader);
+    let mut header = Vec::new();
+
+    if read_line(&mut reader, &mut header)? != VERSION_LINE {
+        return Err("age: unsupported format version".to_string());
+    }
+
+    let mut stanzas = Vec::new();
+    let mac_line = loop {
+        let line = read_line(&mut reader, &mut header)?;
+        if let Some(args) = line.strip_prefix("-> ") {
+            let args = args.split(' ').map(|s| s.to_string()).collect::<Vec<_>>();
+            let mut body = String::new();
+            loop {
+                let body_line = read_line(&mut reader, &mut header)?;
+                let len = body_line.len();
+                if len > COLUMNS {
+                    return Err("age: malformed stanza body".to_string());
+                }
+                body.push_str(&body_line);
+                if len < COLUMNS {
+                    break;
+                }
+            }
+            let body =
+                base64::decode_config(&body, base64::STANDARD_NO_PAD).map_err(|e| e.to_string())?;
+            stanzas.push((args, body));
+        } else if line.starts_with("---") {
+            break line;
+        } else {
+            return Err("age: malformed header".to_string());
+        }
+    };
+
+    if stanzas.is_empty() {
+        return Err("age: no recipient stanzas".to_string());
+    }
+    let has_scrypt = stanzas.iter().any(|(args, _)| args[0] == "scrypt");
+    if has_scrypt && stanzas.len() != 1 {
+        return Err("age: scrypt stanza must be the only stanza".to_string());
+    }
+
+    let mut file_key = None;
+    'outer: for identity in identities {
+        for (args, body) in &stanzas {
+            let key = match identity {
+                Identity::X25519(sk) => unwrap_x25519(args, body, sk)?,
+                Identity::Scrypt(passphrase) => unwrap_scrypt(args, body, passphrase)?,
+            };
+            if key.is_some() {
+                file_key = key;
+                break 'outer;
+            }
+        }
+    }
+    let file_key = file_key.ok_or_else(|| "age: no identity matched".to_string())?;
+
+    // MAC 覆盖到 "---" 为止，不包括其后的空格和MAC本身
+    let mac_len = header.len() - (mac_line.len() + 1) + 3;
+    let mac = match mac_line.strip_prefix("--- ") {
+        Some(mac) => {
+            base64::decode_config(mac, base64::STANDARD_NO_PAD).map_err(|e| e.to_string())?
+        }
+        None => return Err("age: malformed header mac".to_string()),
+    };
+    let mac_key = hkdf_sha256(&file_key, &[], b"header");
+    hmac::verify(
+        &hmac::Key::new(hmac::HMAC_SHA256, &mac_key),
+        &header[..mac_len],
+        &mac,
+    )
+    .map_err(|_| "age: header mac mismatch".to_string())?;
+
+    let mut nonce = [0u8; 16];
+    reader
+        .read_exact(&mut nonce)
+        .map_err(|_| "age: missing payload nonce".to_string())?;
+    let payload_key = hkdf_sha256(&file_key, &nonce, b"payload");
+    stream::open(&payload_key, &[], reader, writer)
+}
+
+fn wrap_x25519(file_key: &[u8], recipient: &X25519Recipient) -> Result<String, String> {
+    let mut ephemeral = [0u8; 32];
+    ephemeral.copy_from_slice(&genSecureRandBytes(32));
+    let share = x25519::scalarmult_base(&ephemeral);
+    let shared = x25519::scalarmult(&ephemeral, &recipient.0)
+        .map_err(|_| "age: low order X25519 recipient".to_string())?;
+    let mut salt = share.to_vec();
+    salt.extend_from_slice(&recipient.0);
+    let wrap_key = hkdf_sha256(&shared, &salt, X25519_INFO);
+    let body = seal_file_key(&wrap_key, file_key)?;
+    Ok(format_stanza(
+        &[
+            "X25519",
+            &base64::encode_config(share, base64::STANDARD_NO_PAD),
+        ],
+        &body,
+    ))
+}
+
+fn unwrap_x25519(
+    args: &[String],
+    body: &[u8],
+    identity: &X25519Identity,
+) -> Result<Option<Vec<u8>>, String> {
+    if args[0] != "X25519" {
+        return Ok(None);
+    }
+    if args.len() != 2 {
+        return Err("age: malformed X25519 stanza".to_string());
+    }
+    let share =
+        base64::decode_config(&args[1], base64::STANDARD_NO_PAD).map_err(|e| e.to_string())?;
+    if share.len() != 32 || body.len() != FILE_KEY_SIZE + TAG_SIZE {
+        return Err("age: malformed X25519 stanza".to_string());
+    }
+    let mut point = [0u8; 32];
+    point.copy_from_slice(&share);
+    let shared = x25519::scalarmult(&identity.0, &point)
+        .map_err(|_| "age: low order X25519 share".to_string())?;
+    let mut salt = share;
+    salt.extend_from_slice(&identity.recipient().0);
+    let wrap_key = hkdf_sha256(&shared, &salt, X25519_INFO);
+    Ok(open_file_key(&wrap_key, body))
+}
+
+fn wrap_scrypt(file_key: &[u8], passphrase: &str, work_factor: u8) -> Result<String, String> {
+    if work_factor == 0 || work_factor > MAX_SCRYPT_LOG_N {
+        return Err("age: invalid scrypt work factor".to_string());
+    }
+    let salt = genSecureRandBytes(16);
+    let wrap_key = scrypt_key(passphrase, &salt, work_factor);
+    let body = seal_file_key(&wrap_key, file_key)?;
+    Ok(format_stanza(
+        &[
+            "scrypt",
+            &base64::encode_config(&salt, base64::STANDARD_NO_PAD),
+            &work_factor.to_string(),
+        ],
+        &body,
+    ))
+}
+
+fn unwrap_scrypt(
+    args: &[String],
+    body: &[u8],
+    passphrase: &str,
+) -> Result<Option<Vec<u8>>, String> {
+    if args[0] != "scrypt" {
+        return Ok(None);
+    }
+    if args.len() != 3 || body.len() != FILE_KEY_SIZE + TAG_SIZE {
+        return Err("age: malformed scrypt stanza".to_string());
+    }
+    let salt =
+        base64::decode_config(&args[1], base64::STANDARD_NO_PAD).map_err(|e| e.to_string())?;
+    if salt.len() != 16 {
+        return Err("age: malformed scrypt stanza".to_string());
+    }
+    let work_factor = args[2]
+        .parse::<u8>()
+        .map_err(|_| "age: malformed scrypt work factor".to_string())?;
+    if work_factor == 0 || work_factor > MAX_SCRYPT_LOG_N {
+        return Err("age: scrypt work factor too large".to_string());
+    }
+    let wrap_key = scrypt_key(passphrase, &salt, work_factor);
+    Ok(open_file_key(&wrap_key, body))
+}
+
+fn scrypt_key(passphrase: &str, salt: &[u8], work_factor: u8) -> Vec<u8> {
+    let mut label_salt = SCRYPT_LABEL.to_vec();
+    label_salt.extend_from_slice(salt);
+    let mut key = vec![0u8; 32];
+    scrypt(
+        passphrase.as_bytes(),
+        &label_salt,
+        &ScryptParams::new(work_factor, 8, 1),
+        &mut key,
+    );
+    key
+}
+
+fn seal_file_key(wrap_key: &[u8], file_key: &[u8]) -> Result<Vec<u8>, String> {
+    let key = chacha_key(wrap_key);
+    let mut body = file_key.to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key([0u8; 12]),
+        Aad::empty(),
+        &mut body,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(body)
+}
+
+fn open_file_key(wrap_key: &[u8], body: &[u8]) -> Option<Vec<u8>> {
+    let key = chacha_key(wrap_key);
+    let mut body = body.to_vec();
+    key.open_in_place(
+        Nonce::assume_unique_for_key([0u8; 12]),
+        Aad::empty(),
+        &mut body,
+    )
+    .ok()
+    .map(|plain| plain.to_vec())
+}
+
+fn format_stanza(args: &[&str], body: &[u8]) -> String {
+    let mut stanza = format!("-> {}\n", args.join(" "));
+    let encoded = base64::encode_config(body, base64::STANDARD_NO_PAD);
+    let mut rest = encoded.as_str();
+    loop {
+        let n = rest.len().min(COLUMNS);
+        stanza.push_str(&rest[..n]);
+        stanza.push('\n');
+        rest = &rest[n..];
+        // 最后一行必须短于64列，整行结尾时需要补一个空行
+        if n < COLUMNS {
+            break;
+        }
+    }
+    stanza
+}
+
+fn header_mac(file_key: &[u8], header: &[u8]) -> Vec<u8> {
+    let mac_key = hkdf_sha256(file_key, &[], b"header");
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &mac_key), header)
+        .as_ref()
+        .to_vec()
+}
+
+fn read_line<R: BufRead>(reader: &mut R, header: &mut Vec<u8>) -> Result<String, String> {
+    let mut line = Vec::new();
+    reader
+        .read_until(b'\n', &mut line)
+        .map_err(|e| e.to_string())?;
+    if line.pop() != Some(b'\n') {
+        return Err("age: unexpected end of header".to_string());
+    }
+    header.extend_from_slice(&line);
+    header.push(b'\n');
+    String::from_utf8(line).map_err(|_| "age: header is not valid utf-8".to_string())
+}
+
+struct HkdfLen(usize);
+
+impl hkdf::KeyType for HkdfLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+fn hkdf_sha256(ikm: &[u8], salt: &[u8], info: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; 32];
+    hkdf::Salt::new(hkdf::HKDF_SHA256, salt)
+        .extract(ikm)
+        .expand(&[info], HkdfLen(32))
+        .and_then(|okm| okm.fill(&mut out))
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    out
+}
+
+fn chacha_key(key: &[u8]) -> LessSafeKey {
+    LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, key).expect("key must be 32 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_x25519_roundtrip() {
+        let identity = X25519Identity::generate();
+        let recipient = X25519Recipient::parse(&identity.recipient().to_bech32()).unwrap();
+        let identity = X25519Identity::parse(&identity.to_bech32()).unwrap();
+
+        let plain = vec![7u8; stream::CHUNK_SIZE * 2 + 5];
+        let mut encrypted = Vec::new();
+        encrypt(&[Recipient::X25519(recipient)], &plain[..], &mut encrypted).unwrap();
+
+        let mut decrypted = Vec::new();
+        let identities = [Identity::X25519(identity)];
+        decrypt(&identities, &encrypted[..], &mut decrypted).unwrap();
+        assert_eq!(plain, decrypted);
+
+        let other = [Identity::X25519(X25519Identity::generate())];
+        assert!(decrypt(&other, &encrypted[..], &mut Vec::new()).is_err());
+
+        encrypted.truncate(encrypted.len() - 10);
+        assert!(decrypt(&identities, &encrypted[..], &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_scrypt_roundtrip() {
+        let recipient = Recipient::Scrypt {
+            passphrase: "correct horse battery staple".to_string(),
+            work_factor: 10,
+        };
+        let mut encrypted = Vec::new();
+        encrypt(&[recipient], &b""[..], &mut encrypted).unwrap();
+
+        let mut decrypted = Vec::new();
+        let identity = Identity::Scrypt("correct horse battery staple".to_string());
+        decrypt(&[identity], &encrypted[..], &mut decrypted).unwrap();
+        assert!(decrypted.is_empty());
+
+        let wrong = Identity::Scrypt("wrong".to_string());
+        assert!(decrypt(&[wrong], &encrypted[..], &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_age_interop() {
+        // 由参考实现 rage 的 age 库 (0.11) 生成
+        const PLAIN: &[u8] = b"pi_crypto age interop vector\n";
+        let key = include_str!("../tests/age-x25519-key.txt");
+        let identity = X25519Identity::parse(key.lines().nth(1).unwrap()).unwrap();
+        assert_eq!(
+            identity.recipient().to_bech32(),
+            "age1jqsqcx48mf8mgyhe9nylj9436xk5d67jzjewfju3ynkperxncewsjflq6d"
+        );
+        let mut decrypted = Vec::new();
+        decrypt(
+            &[Identity::X25519(identity)],
+            &include_bytes!("../tests/age-x25519.age")[..],
+            &mut decrypted,
+        )
+        .unwrap();
+        assert_eq!(decrypted, PLAIN);
+
+        let mut decrypted = Vec::new();
+        let identity = Identity::Scrypt("correct horse battery staple".to_string());
+        decrypt(
+            &[identity],
+            &include_bytes!("../tests/age-scrypt.age")[..],
+            &mut decrypted,
+        )
+        .unwrap();
+        assert_eq!(decrypted, PLAIN);
+    }
+}
@@ -7,6 +7,12 @@ use std::ops::Drop;
 use std::ptr::null;
 use std::sync::Arc;
 
+use crate::ct::ct_eq;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+use crate::signature::{Signer, Verifier};
+
 #[link(name = "blsc")]
 extern "C" {
     fn blscInit(curve: c_int) -> c_int;
@@ -91,10 +97,16 @@ extern "C" {
         size: size_t,
     ) -> c_int;
     fn blscFree(ptr: *const c_void);
+    // blsc 链接的 mcl bls 库接口，控制反序列化时是否检查点在素数阶子群中
+    fn blsPublicKeyVerifyOrder(doVerify: c_int);
+    fn blsSignatureVerifyOrder(doVerify: c_int);
 }
 
 /**
 * BLS算法的曲线类型
+*
+* 其中没有 SM9 使用的 256 位 BN 曲线(GM/T 0044)，blsc 也只导出 BLS 签名接口，
+* 不提供 G1/G2/GT 运算和配对本身，SM9 由 sm9 模块 (sm9 特性) 基于 sm9_core 的配对实现
 */
 pub enum Curve {
     MclBnCurveFp254BNb = 0x0,
@@ -120,6 +132,8 @@ impl Drop for BlsId {
 
 /**
 * BLS算法的私钥
+*
+* 私钥内存由 blsc 分配和释放，blsc 没有提供擦除接口，这里序列化出的私钥副本都用 SecretBytes 承载
 */
 pub struct BlsSecretKey(*const c_void, bool);
 
@@ -131,6 +145,25 @@ impl Drop for BlsSecretKey {
     }
 }
 
+// 序列化私钥时使用的缓冲大小，足够容纳所有支持的曲线
+const SECRET_KEY_BUF_SIZE: usize = 128;
+
+impl PartialEq for BlsSecretKey {
+    /**
+     * 序列化后常量时间比较私钥，避免计时侧信道
+     */
+    fn eq(&self, other: &Self) -> bool {
+        let lhs = bls_secret_key_serialize(SECRET_KEY_BUF_SIZE, self).map(SecretBytes::new);
+        let rhs = bls_secret_key_serialize(SECRET_KEY_BUF_SIZE, other).map(SecretBytes::new);
+        match (lhs, rhs) {
+            (Ok(lhs), Ok(rhs)) => ct_eq(&lhs, &rhs),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for BlsSecretKey {}
+
 /**
 * BLS算法的公钥
 */
@@ -227,52 +260,100 @@ impl BlsSigVec {
 
 /**
 * BLS算法初始化环境，只需要初始化一次
+*
+* 初始化后显式开启公钥和签名反序列化时的子群检查，不依赖 mcl 版本的默认值
 * @param curve BLS算法的曲线类型
-* @returns 返回初始化是否成功
+* @returns 初始化失败时返回错误
 */
-pub fn bls_init(curve: Curve) -> bool {
+pub fn bls_init(curve: Curve) -> Result<(), CryptoError> {
     unsafe {
         if blscInit(curve as i32) != 0 {
-            return false;
+            return Err(failed("init"));
         }
-        true
     }
+    bls_set_verify_order(true);
+    Ok(())
+}
+
+/**
+* 设置反序列化公钥和签名时是否检查子群
+*
+* bls_init 已经开启检查。关闭后不在子群中的点也能反序列化，只应在输入已经校验过时为了速度关闭
+* @param verify 是否检查
+*/
+pub fn bls_set_verify_order(verify: bool) {
+    unsafe {
+        blsPublicKeyVerifyOrder(verify as c_int);
+        blsSignatureVerifyOrder(verify as c_int);
+    }
+}
+
+fn failed(what: &str) -> CryptoError {
+    CryptoError::OperationFailed(format!("bls: {} failed", what))
+}
+
+fn invalid_input(msg: &str) -> CryptoError {
+    CryptoError::InvalidInput(format!("bls: {}", msg))
+}
+
+/**
+* BLS12-381 上 G1 和 G2 点的压缩序列化长度
+*/
+pub const BLS12_381_G1_SIZE: usize = 48;
+pub const BLS12_381_G2_SIZE: usize = 96;
+
+/**
+* 使用 BLS12-381 曲线初始化环境，并检查公钥的序列化长度
+*
+* blsc 固定把公钥放在 G2、签名放在 G1 (最小签名长度，公钥96字节，签名48字节)，
+* 使用 mcl 默认的压缩序列化。以太坊2.0使用公钥在 G1 的最小公钥长度方案和 ZCash 序列化格式，
+* 切换这两者需要 mcl 的 ETH 模式，blsc 没有导出，所以这里的公钥和签名不能直接与以太坊互通，
+* 需要互通时使用 bls12381 模块 (bls12381 特性)
+* @returns 初始化失败或公钥长度不符时返回错误
+*/
+pub fn bls_init_bls12_381() -> Result<(), CryptoError> {
+    bls_init(Curve::MclBls12CurveFp381)?;
+    let buf = bls_public_key_serialize(POINT_BUF_SIZE, &bls_get_generator_of_g2())?;
+    if buf.len() != BLS12_381_G2_SIZE {
+        return Err(failed("BLS12-381 public key size check"));
+    }
+    Ok(())
 }
 
 pub fn bls_get_op_unit_size() -> usize {
     unsafe { blscGetOpUnitSize() }
 }
 
-pub fn bls_get_curve_order(max_buf_size: usize) -> Option<String> {
+pub fn bls_get_curve_order(max_buf_size: usize) -> Result<String, CryptoError> {
     unsafe {
         let mut buf: Vec<u8> = Vec::with_capacity(max_buf_size);
         buf.resize(max_buf_size, 0);
         let len = blscGetCurveOrder(buf.as_mut_ptr(), max_buf_size);
         if len == 0 {
-            return None;
+            return Err(failed("get curve order"));
         }
 
         buf.truncate(len as usize);
         match String::from_utf8(buf) {
-            Ok(string) => Some(string),
-            _ => None,
+            Ok(string) => Ok(string),
+            _ => Err(failed("get curve order")),
         }
     }
 }
 
-pub fn bls_get_field_order(max_buf_size: usize) -> Option<String> {
+pub fn bls_get_field_order(max_buf_size: usize) -> Result<String, CryptoError> {
     unsafe {
         let mut buf: Vec<u8> = Vec::with_capacity(max_buf_size);
         buf.resize(max_buf_size, 0);
         let len = blscGetFieldOrder(buf.as_mut_ptr(), max_buf_size);
         if len == 0 {
-            return None;
+            return Err(failed("get field order"));
         }
 
         buf.truncate(len as usize);
         match String::from_utf8(buf) {
-            Ok(string) => Some(string),
-            _ => None,
+            Ok(string) => Ok(string),
+            _ => Err(failed("get field order")),
         }
     }
 }
@@ -293,32 +374,42 @@ pub fn bls_id_set_int(x: i32) -> BlsId {
 /**
 * 指定唯一的Dec字节串，获取BLS算法的成员唯一id
 * @param buf 唯一的Dec字符串
-* @returns 返回BLS算法的成员唯一id，可为空
+* @returns 返回BLS算法的成员唯一id，失败时返回错误
 */
-pub fn bls_id_set_dec_str(buf: String) -> Option<BlsId> {
+pub fn bls_id_set_dec_str(buf: String) -> Result<BlsId, CryptoError> {
     unsafe {
         let buf_size = buf.len();
-        let ptr = blscIdSetDecStr(CString::new(buf).unwrap().as_ptr(), buf_size);
+        // 含有0字节的字符串不是合法的id
+        let buf = match CString::new(buf) {
+            Ok(buf) => buf,
+            Err(_) => return Err(invalid_input("malformed decimal id")),
+        };
+        let ptr = blscIdSetDecStr(buf.as_ptr(), buf_size);
         if ptr.is_null() {
-            return None;
+            return Err(invalid_input("malformed decimal id"));
         }
-        Some(BlsId(ptr, true))
+        Ok(BlsId(ptr, true))
     }
 }
 
 /**
 * 指定唯一的Hex字节串，获取BLS算法的成员唯一id
 * @param buf 唯一的Hex字符串
-* @returns 返回BLS算法的成员唯一id，可为空
+* @returns 返回BLS算法的成员唯一id，失败时返回错误
 */
-pub fn bls_id_set_hex_str(buf: String) -> Option<BlsId> {
+pub fn bls_id_set_hex_str(buf: String) -> Result<BlsId, CryptoError> {
     unsafe {
         let buf_size = buf.len();
-        let ptr = blscIdSetHexStr(CString::new(buf).unwrap().as_ptr(), buf_size);
+        // 含有0字节的字符串不是合法的id
+        let buf = match CString::new(buf) {
+            Ok(buf) => buf,
+            Err(_) => return Err(invalid_input("malformed hex id")),
+        };
+        let ptr = blscIdSetHexStr(buf.as_ptr(), buf_size);
         if ptr.is_null() {
-            return None;
+            return Err(invalid_input("malformed hex id"));
         }
-        Some(BlsId(ptr, true))
+        Ok(BlsId(ptr, true))
     }
 }
 
@@ -326,11 +417,11 @@ pub fn bls_id_set_hex_str(buf: String) -> Option<BlsId> {
 * 通过BLS算法的成员唯一id，获取唯一Dec字符串
 * @param max_buf_size 最大的缓冲大小
 * @param id BLS算法的成员唯一id
-* @returns 返回唯一Dec字符串，可为空
+* @returns 返回唯一Dec字符串，失败时返回错误
 */
-pub fn bls_id_get_dec_str(max_buf_size: usize, id: &BlsId) -> Option<String> {
+pub fn bls_id_get_dec_str(max_buf_size: usize, id: &BlsId) -> Result<String, CryptoError> {
     if max_buf_size == 0 {
-        return None;
+        return Err(failed("get id decimal string"));
     }
 
     unsafe {
@@ -338,13 +429,13 @@ pub fn bls_id_get_dec_str(max_buf_size: usize, id: &BlsId) -> Option<String> {
         buf.resize(max_buf_size, 0);
         let len = blscIdGetDecStr(buf.as_mut_ptr(), max_buf_size, id.0);
         if len == 0 {
-            return None;
+            return Err(failed("get id decimal string"));
         }
 
         buf.truncate(len as usize);
         match String::from_utf8(buf) {
-            Ok(string) => Some(string),
-            _ => None,
+            Ok(string) => Ok(string),
+            _ => Err(failed("get id decimal string")),
         }
     }
 }
@@ -353,11 +444,11 @@ pub fn bls_id_get_dec_str(max_buf_size: usize, id: &BlsId) -> Option<String> {
 * 通过BLS算法的成员唯一id，获取唯一Hex字符串
 * @param max_buf_size 最大的缓冲大小
 * @param id BLS算法的成员唯一id
-* @returns 返回唯一Hex字符串，可为空
+* @returns 返回唯一Hex字符串，失败时返回错误
 */
-pub fn bls_id_get_hex_str(max_buf_size: usize, id: &BlsId) -> Option<String> {
+pub fn bls_id_get_hex_str(max_buf_size: usize, id: &BlsId) -> Result<String, CryptoError> {
     if max_buf_size == 0 {
-        return None;
+        return Err(failed("get id hex string"));
     }
 
     unsafe {
@@ -365,13 +456,13 @@ pub fn bls_id_get_hex_str(max_buf_size: usize, id: &BlsId) -> Option<String> {
         buf.resize(max_buf_size, 0);
         let len = blscIdGetHexStr(buf.as_mut_ptr(), max_buf_size, id.0);
         if len == 0 {
-            return None;
+            return Err(failed("get id hex string"));
         }
 
         buf.truncate(len as usize);
         match String::from_utf8(buf) {
-            Ok(string) => Some(string),
-            _ => None,
+            Ok(string) => Ok(string),
+            _ => Err(failed("get id hex string")),
         }
     }
 }
@@ -379,45 +470,45 @@ pub fn bls_id_get_hex_str(max_buf_size: usize, id: &BlsId) -> Option<String> {
 /**
 * 生成指定种子的BLS私钥
 * @param buf 种子的二进制数据
-* @returns 返回BLS私钥，可为空
+* @returns 返回BLS私钥，失败时返回错误
 */
-pub fn bls_hash_to_secret_key(buf: Vec<u8>) -> Option<BlsSecretKey> {
+pub fn bls_hash_to_secret_key(buf: Vec<u8>) -> Result<BlsSecretKey, CryptoError> {
     unsafe {
         let ptr = blscHashToSecretKey(buf.as_ptr() as *const c_void, buf.len());
         if ptr.is_null() {
-            return None;
+            return Err(failed("hash to secret key"));
         }
-        Some(BlsSecretKey(ptr, true))
+        Ok(BlsSecretKey(ptr, true))
     }
 }
 
 /**
 * 获取指定BLS私钥的公钥
 * @param sec_key BLS私钥
-* @returns 返回BLS公钥，可为空
+* @returns 返回BLS公钥，失败时返回错误
 */
-pub fn bls_get_public_key(sec_key: &BlsSecretKey) -> Option<BlsPublicKey> {
+pub fn bls_get_public_key(sec_key: &BlsSecretKey) -> Result<BlsPublicKey, CryptoError> {
     unsafe {
         let ptr = blscGetPublicKey(sec_key.0);
         if ptr.is_null() {
-            return None;
+            return Err(failed("get public key"));
         }
-        Some(BlsPublicKey(ptr, true))
+        Ok(BlsPublicKey(ptr, true))
     }
 }
 
 /**
 * 获取指定BLS私钥的签名
 * @param sec_key BLS私钥
-* @returns 返回签名，可为空
+* @returns 返回签名，失败时返回错误
 */
-pub fn bls_get_pop(sec_key: &BlsSecretKey) -> Option<BlsSignature> {
+pub fn bls_get_pop(sec_key: &BlsSecretKey) -> Result<BlsSignature, CryptoError> {
     unsafe {
         let ptr = blscGetPop(sec_key.0);
         if ptr.is_null() {
-            return None;
+            return Err(failed("get proof of possession"));
         }
-        Some(BlsSignature(ptr, true))
+        Ok(BlsSignature(ptr, true))
     }
 }
 
@@ -440,18 +531,18 @@ pub fn bls_verify_pop(sig: &BlsSignature, pub_key: &BlsPublicKey) -> bool {
 * 序列化BLS算法的成员唯一id
 * @param max_buf_size 最大的缓冲大小
 * @param id BLS算法的成员唯一id
-* @returns 返回序列化数据，可为空
+* @returns 返回序列化数据，失败时返回错误
 */
-pub fn bls_id_serialize(max_buf_size: usize, id: &BlsId) -> Option<Vec<u8>> {
+pub fn bls_id_serialize(max_buf_size: usize, id: &BlsId) -> Result<Vec<u8>, CryptoError> {
     unsafe {
         let mut buf: Vec<u8> = Vec::with_capacity(max_buf_size);
         buf.resize(max_buf_size, 0);
         let len = blscIdSerialize(buf.as_mut_ptr() as *mut c_void, max_buf_size, id.0);
         if len == 0 {
-            return None;
+            return Err(failed("serialize id"));
         }
         buf.truncate(len as usize);
-        Some(buf)
+        Ok(buf)
     }
 }
 
@@ -459,19 +550,22 @@ pub fn bls_id_serialize(max_buf_size: usize, id: &BlsId) -> Option<Vec<u8>> {
 * 序列化BLS私钥
 * @param max_buf_size 最大的缓冲大小
 * @param sec_key BLS私钥
-* @returns 返回序列化数据，可为空
+* @returns 返回序列化数据，失败时返回错误
 */
-pub fn bls_secret_key_serialize(max_buf_size: usize, sec_key: &BlsSecretKey) -> Option<Vec<u8>> {
+pub fn bls_secret_key_serialize(
+    max_buf_size: usize,
+    sec_key: &BlsSecretKey,
+) -> Result<Vec<u8>, CryptoError> {
     unsafe {
         let mut buf: Vec<u8> = Vec::with_capacity(max_buf_size);
         buf.resize(max_buf_size, 0);
         let len = blscSecretKeySerialize(buf.as_mut_ptr() as *mut c_void, max_buf_size, sec_key.0);
         if len == 0 {
-            return None;
+            return Err(failed("serialize secret key"));
         }
 
         buf.truncate(len as usize);
-        Some(buf)
+        Ok(buf)
     }
 }
 
@@ -479,19 +573,22 @@ pub fn bls_secret_key_serialize(max_buf_size: usize, sec_key: &BlsSecretKey) ->
 * 序列化BLS公钥
 * @param max_buf_size 最大的缓冲大小
 * @param pub_key BLS公钥
-* @returns 返回序列化数据，可为空
+* @returns 返回序列化数据，失败时返回错误
 */
-pub fn bls_public_key_serialize(max_buf_size: usize, pub_key: &BlsPublicKey) -> Option<Vec<u8>> {
+pub fn bls_public_key_serialize(
+    max_buf_size: usize,
+    pub_key: &BlsPublicKey,
+) -> Result<Vec<u8>, CryptoError> {
     unsafe {
         let mut buf: Vec<u8> = Vec::with_capacity(max_buf_size);
         buf.resize(max_buf_size, 0);
         let len = blscPublicKeySerialize(buf.as_mut_ptr() as *mut c_void, max_buf_size, pub_key.0);
         if len == 0 {
-            return None;
+            return Err(failed("serialize public key"));
         }
 
         buf.truncate(len as usize);
-        Some(buf)
+        Ok(buf)
     }
 }
 
@@ -499,79 +596,86 @@ pub fn bls_public_key_serialize(max_buf_size: usize, pub_key: &BlsPublicKey) ->
 * 序列化BLS签名
 * @param max_buf_size 最大的缓冲大小
 * @param sig BLS签名
-* @returns 返回序列化数据，可为空
+* @returns 返回序列化数据，失败时返回错误
 */
-pub fn bls_signature_serialize(max_buf_size: usize, sig: &BlsSignature) -> Option<Vec<u8>> {
+pub fn bls_signature_serialize(
+    max_buf_size: usize,
+    sig: &BlsSignature,
+) -> Result<Vec<u8>, CryptoError> {
     unsafe {
         let mut buf: Vec<u8> = Vec::with_capacity(max_buf_size);
         buf.resize(max_buf_size, 0);
         let len = blscSignatureSerialize(buf.as_mut_ptr() as *mut c_void, max_buf_size, sig.0);
         if len == 0 {
-            return None;
+            return Err(failed("serialize signature"));
         }
 
         buf.truncate(len as usize);
-        Some(buf)
+        Ok(buf)
     }
 }
 
 /**
 * 反序列化BLS算法的成员唯一id
 * @param buf 序列化数据
-* @returns 返回BLS算法的成员唯一id，可为空
+* @returns 返回BLS算法的成员唯一id，失败时返回错误
 */
-pub fn bls_id_deserialize(buf: Vec<u8>) -> Option<BlsId> {
+pub fn bls_id_deserialize(buf: Vec<u8>) -> Result<BlsId, CryptoError> {
     unsafe {
         let ptr = blscIdDeserialize(buf.as_ptr() as *const c_void, buf.len());
         if ptr.is_null() {
-            return None;
+            return Err(invalid_input("malformed id"));
         }
-        Some(BlsId(ptr, false))
+        Ok(BlsId(ptr, false))
     }
 }
 
 /**
 * 反序列化BLS私钥
 * @param buf 序列化数据
-* @returns 返回BLS私钥，可为空
+* @returns 返回BLS私钥，失败时返回错误
 */
-pub fn bls_secret_key_deserialize(buf: Vec<u8>) -> Option<BlsSecretKey> {
+pub fn bls_secret_key_deserialize(buf: Vec<u8>) -> Result<BlsSecretKey, CryptoError> {
     unsafe {
         let ptr = blscSecretKeyDeserialize(buf.as_ptr() as *const c_void, buf.len());
         if ptr.is_null() {
-            return None;
+            return Err(CryptoError::InvalidKey(
+                "bls: malformed secret key".to_string(),
+            ));
         }
-        Some(BlsSecretKey(ptr, false))
+        Ok(BlsSecretKey(ptr, false))
     }
 }
 
 /**
 * 反序列化BLS公钥
 * @param buf 序列化数据
-* @returns 返回BLS公钥，可为空
+* @returns 返回BLS公钥，失败时返回错误
 */
-pub fn bls_public_key_deserialize(buf: Vec<u8>) -> Option<BlsPublicKey> {
+pub fn bls_public_key_deserialize(buf: Vec<u8>) -> Result<BlsPublicKey, CryptoError> {
     unsafe {
         let ptr = blscPublicKeyDeserialize(buf.as_ptr() as *const c_void, buf.len());
         if ptr.is_null() {
-            return None;
+            return Err(CryptoError::InvalidKey(
+                "bls: malformed public key".to_string(),
+            ));
         }
-        Some(BlsPublicKey(ptr, false))
+        Ok(BlsPublicKey(ptr, false))
     }
 }
 
 /**
 * 反序列化BLS签名
 * @param buf 序列化数据
-* @returns 返回BLS签名，可为空
+* @returns 返回BLS签名，失败时返回错误
 */
-pub fn bls_signature_deserialize(buf: Vec<u8>) -> Option<BlsSignature> {
+pub fn bls_signature_deserialize(buf: Vec<u8>) -> Result<BlsSignature, CryptoError> {
     unsafe {
         let ptr = blscSignatureDeserialize(buf.as_ptr() as *const c_void, buf.len());
         if ptr.is_null() {
-            return None;
+            return Err(invalid_input("malformed signature"));
         }
-        Some(BlsSignature(ptr, false))
+        Ok(BlsSignature(ptr, false))
     }
 }
 
@@ -673,15 +777,19 @@ pub fn bls_signature_add(sig: &BlsSignature, rhs: &BlsSignature) {
 * @param src_key BLS主私钥
 * @param k 共享人数
 * @param id BLS算法的成员唯一id
-* @returns 返回共享的BLS私钥，可为空
+* @returns 返回共享的BLS私钥，失败时返回错误
 */
-pub fn bls_secret_key_share(src_key: &BlsSecretKey, k: usize, id: &BlsId) -> Option<BlsSecretKey> {
+pub fn bls_secret_key_share(
+    src_key: &BlsSecretKey,
+    k: usize,
+    id: &BlsId,
+) -> Result<BlsSecretKey, CryptoError> {
     unsafe {
         let ptr = blscSecretKeyShare(src_key.0, k, id.0);
         if ptr.is_null() {
-            return None;
+            return Err(failed("share secret key"));
         }
-        Some(BlsSecretKey(ptr, true))
+        Ok(BlsSecretKey(ptr, true))
     }
 }
 
@@ -690,15 +798,19 @@ pub fn bls_secret_key_share(src_key: &BlsSecretKey, k: usize, id: &BlsId) -> Opt
 * @param src_key BLS主公钥
 * @param k 共享人数
 * @param id BLS算法的成员唯一id
-* @returns 返回共享的BLS公钥，可为空
+* @returns 返回共享的BLS公钥，失败时返回错误
 */
-pub fn bls_public_key_share(src_key: &BlsPublicKey, k: usize, id: &BlsId) -> Option<BlsPublicKey> {
+pub fn bls_public_key_share(
+    src_key: &BlsPublicKey,
+    k: usize,
+    id: &BlsId,
+) -> Result<BlsPublicKey, CryptoError> {
     unsafe {
         let ptr = blscPublicKeyShare(src_key.0, k, id.0);
         if ptr.is_null() {
-            return None;
+            return Err(failed("share public key"));
         }
-        Some(BlsPublicKey(ptr, true))
+        Ok(BlsPublicKey(ptr, true))
     }
 }
 
@@ -706,15 +818,15 @@ pub fn bls_public_key_share(src_key: &BlsPublicKey, k: usize, id: &BlsId) -> Opt
 * 获取成员唯一id向量中指定序号的成员唯一id
 * @param vec BLS算法的成员唯一id向量
 * @param index 序号
-* @returns 返回BLS算法的成员唯一id，可为空
+* @returns 返回BLS算法的成员唯一id，失败时返回错误
 */
-pub fn bls_get_id_from_vec(vec: &BlsIdVec, index: usize) -> Option<BlsId> {
+pub fn bls_get_id_from_vec(vec: &BlsIdVec, index: usize) -> Result<BlsId, CryptoError> {
     unsafe {
         let ptr = blscGetIdFromVec(vec.0, index);
         if ptr.is_null() {
-            return None;
+            return Err(failed("get id from vector"));
         }
-        Some(BlsId(ptr, false))
+        Ok(BlsId(ptr, false))
     }
 }
 
@@ -737,15 +849,18 @@ pub fn bls_add_id_to_vec(vec: &mut BlsIdVec, id: &BlsId) {
 * 获取私钥向量中指定序号的私钥
 * @param vec BLS私钥向量
 * @param index 序号
-* @returns 返回BLS私钥，可为空
+* @returns 返回BLS私钥，失败时返回错误
 */
-pub fn bls_get_secret_key_from_vec(vec: &BlsSecKeyVec, index: usize) -> Option<BlsSecretKey> {
+pub fn bls_get_secret_key_from_vec(
+    vec: &BlsSecKeyVec,
+    index: usize,
+) -> Result<BlsSecretKey, CryptoError> {
     unsafe {
         let ptr = blscGetSecretKeyFromVec(vec.0, index);
         if ptr.is_null() {
-            return None;
+            return Err(failed("get secret key from vector"));
         }
-        Some(BlsSecretKey(ptr, false))
+        Ok(BlsSecretKey(ptr, false))
     }
 }
 
@@ -767,14 +882,14 @@ pub fn bls_add_secret_key_to_vec(vec: &mut BlsSecKeyVec, sec_key: &BlsSecretKey)
 /**
 * 获取指定私钥向量的组合私钥
 * @param vec BLS私钥向量
-* @returns 返回组合私钥，可为空
+* @returns 返回组合私钥，失败时返回错误
 */
-pub fn bls_get_secret_key_vec(vec: &BlsSecKeyVec) -> Option<BlsSecretKey> {
+pub fn bls_get_secret_key_vec(vec: &BlsSecKeyVec) -> Result<BlsSecretKey, CryptoError> {
     unsafe {
         if vec.0.is_null() {
-            return None;
+            return Err(invalid_input("empty secret key vector"));
         }
-        Some(BlsSecretKey(blscGetSecretKeyVec(vec.0), false))
+        Ok(BlsSecretKey(blscGetSecretKeyVec(vec.0), false))
     }
 }
 
@@ -782,15 +897,18 @@ pub fn bls_get_secret_key_vec(vec: &BlsSecKeyVec) -> Option<BlsSecretKey> {
 * 获取公钥向量中指定序号的公钥
 * @param vec BLS公钥向量
 * @param index 序号
-* @returns 返回BLS公钥，可为空
+* @returns 返回BLS公钥，失败时返回错误
 */
-pub fn bls_get_public_key_from_vec(vec: &BlsPubKeyVec, index: usize) -> Option<BlsPublicKey> {
+pub fn bls_get_public_key_from_vec(
+    vec: &BlsPubKeyVec,
+    index: usize,
+) -> Result<BlsPublicKey, CryptoError> {
     unsafe {
         let ptr = blscGetPublicKeyFromVec(vec.0, index);
         if ptr.is_null() {
-            return None;
+            return Err(failed("get public key from vector"));
         }
-        Some(BlsPublicKey(ptr, false))
+        Ok(BlsPublicKey(ptr, false))
     }
 }
 
@@ -812,14 +930,14 @@ pub fn bls_add_public_key_to_vec(vec: &mut BlsPubKeyVec, pub_key: &BlsPublicKey)
 /**
 * 获取指定公钥向量的组合公钥
 * @param vec BLS公钥向量
-* @returns 返回组合公钥，可为空
+* @returns 返回组合公钥，失败时返回错误
 */
-pub fn bls_get_public_key_vec(vec: &BlsPubKeyVec) -> Option<BlsPublicKey> {
+pub fn bls_get_public_key_vec(vec: &BlsPubKeyVec) -> Result<BlsPublicKey, CryptoError> {
     unsafe {
         if vec.0.is_null() {
-            return None;
+            return Err(invalid_input("empty public key vector"));
         }
-        Some(BlsPublicKey(blscGetPublicKeyVec(vec.0), false))
+        Ok(BlsPublicKey(blscGetPublicKeyVec(vec.0), false))
     }
 }
 
@@ -827,15 +945,18 @@ pub fn bls_get_public_key_vec(vec: &BlsPubKeyVec) -> Option<BlsPublicKey> {
 * 获取签名向量中指定序号的签名
 * @param vec BLS签名向量
 * @param index 序号
-* @returns 返回BLS签名，可为空
+* @returns 返回BLS签名，失败时返回错误
 */
-pub fn bls_get_signature_from_vec(vec: &BlsSigVec, index: usize) -> Option<BlsSignature> {
+pub fn bls_get_signature_from_vec(
+    vec: &BlsSigVec,
+    index: usize,
+) -> Result<BlsSignature, CryptoError> {
     unsafe {
         let ptr = blscGetSignatureFromVec(vec.0, index);
         if ptr.is_null() {
-            return None;
+            return Err(failed("get signature from vector"));
         }
-        Some(BlsSignature(ptr, false))
+        Ok(BlsSignature(ptr, false))
     }
 }
 
@@ -857,14 +978,14 @@ pub fn bls_add_signature_to_vec(vec: &mut BlsSigVec, sig: &BlsSignature) {
 /**
 * 获取指定签名向量的组合签名
 * @param vec BLS签名向量
-* @returns 返回组合签名，可为空
+* @returns 返回组合签名，失败时返回错误
 */
-pub fn bls_get_signature_key_vec(vec: &BlsSigVec) -> Option<BlsSignature> {
+pub fn bls_get_signature_key_vec(vec: &BlsSigVec) -> Result<BlsSignature, CryptoError> {
     unsafe {
         if vec.0.is_null() {
-            return None;
+            return Err(invalid_input("empty signature vector"));
         }
-        Some(BlsSignature(blscGetSignatureVec(vec.0), false))
+        Ok(BlsSignature(blscGetSignatureVec(vec.0), false))
     }
 }
 
@@ -873,19 +994,19 @@ pub fn bls_get_signature_key_vec(vec: &BlsSigVec) -> Option<BlsSignature> {
 * @param sec_key_vec BLS私钥向量
 * @param id_vec BLS算法的成员唯一id向量
 * @param n 向量的长度
-* @returns 返回主私钥，可为空
+* @returns 返回主私钥，失败时返回错误
 */
 pub fn bls_secret_key_recover(
     sec_key_vec: &BlsSecKeyVec,
     id_vec: &BlsIdVec,
     n: usize,
-) -> Option<BlsSecretKey> {
+) -> Result<BlsSecretKey, CryptoError> {
     unsafe {
         let ptr = blscSecretKeyRecover(sec_key_vec.0, id_vec.0, n);
         if ptr.is_null() {
-            return None;
+            return Err(failed("recover secret key"));
         }
-        Some(BlsSecretKey(ptr, true))
+        Ok(BlsSecretKey(ptr, true))
     }
 }
 
@@ -894,19 +1015,19 @@ pub fn bls_secret_key_recover(
 * @param pub_key_vec BLS公钥向量
 * @param id_vec BLS算法的成员唯一id向量
 * @param n 向量的长度
-* @returns 返回主公钥，可为空
+* @returns 返回主公钥，失败时返回错误
 */
 pub fn bls_public_key_recover(
     pub_key_vec: &BlsPubKeyVec,
     id_vec: &BlsIdVec,
     n: usize,
-) -> Option<BlsPublicKey> {
+) -> Result<BlsPublicKey, CryptoError> {
     unsafe {
         let ptr = blscPublicKeyRecover(pub_key_vec.0, id_vec.0, n);
         if ptr.is_null() {
-            return None;
+            return Err(failed("recover public key"));
         }
-        Some(BlsPublicKey(ptr, true))
+        Ok(BlsPublicKey(ptr, true))
     }
 }
 
@@ -915,19 +1036,19 @@ pub fn bls_public_key_recover(
 * @param sec_key_vec BLS签名向量
 * @param id_vec BLS算法的成员唯一id向量
 * @param n 向量的长度
-* @returns 返回主签名，可为空
+* @returns 返回主签名，失败时返回错误
 */
 pub fn bls_signature_recover(
     sig_vec: &BlsSigVec,
     id_vec: &BlsIdVec,
     n: usize,
-) -> Option<BlsSignature> {
+) -> Result<BlsSignature, CryptoError> {
     unsafe {
         let ptr = blscSignatureRecover(sig_vec.0, id_vec.0, n);
         if ptr.is_null() {
-            return None;
+            return Err(failed("recover signature"));
         }
-        Some(BlsSignature(ptr, true))
+        Ok(BlsSignature(ptr, true))
     }
 }
 
@@ -935,15 +1056,15 @@ pub fn bls_signature_recover(
 * BLS签名
 * @param sec_key BLS私钥
 * @param data 待签名的数据
-* @returns 返回签名，可为空
+* @returns 返回签名，失败时返回错误
 */
-pub fn bls_sign(sec_key: &BlsSecretKey, data: Arc<Vec<u8>>) -> Option<BlsSignature> {
+pub fn bls_sign(sec_key: &BlsSecretKey, data: Arc<Vec<u8>>) -> Result<BlsSignature, CryptoError> {
     unsafe {
         let ptr = blscSign(sec_key.0, data.as_ptr() as *const c_void, data.len());
         if ptr.is_null() {
-            return None;
+            return Err(failed("sign"));
         }
-        Some(BlsSignature(ptr, true))
+        Ok(BlsSignature(ptr, true))
     }
 }
 
@@ -955,10 +1076,299 @@ pub fn bls_sign(sec_key: &BlsSecretKey, data: Arc<Vec<u8>>) -> Option<BlsSignatu
 * @returns 返回验证签名是否成功
 */
 pub fn bls_verify(sig: &BlsSignature, pub_key: &BlsPublicKey, data: Arc<Vec<u8>>) -> bool {
+    bls_verify_detailed(sig, pub_key, data).is_ok()
+}
+
+/**
+* 验证BLS签名，失败时返回原因
+* @param sig BLS签名
+* @param pub_key BLS公钥
+* @param data 已签名数据
+*/
+pub fn bls_verify_detailed(
+    sig: &BlsSignature,
+    pub_key: &BlsPublicKey,
+    data: Arc<Vec<u8>>,
+) -> Result<(), VerifyFailure> {
     unsafe {
         if blscVerify(sig.0, pub_key.0, data.as_ptr() as *const c_void, data.len()) != 1 {
-            return false;
+            return Err(VerifyFailure::SignatureInvalid);
         }
-        true
+        Ok(())
+    }
+}
+
+/**
+* 检查序列化数据是否为无穷远点
+*
+* mcl 默认序列化中无穷远点为全0；以太坊兼容的序列化中首字节带有压缩和无穷远标志位，其余为0
+*/
+fn is_infinity_encoding(buf: &[u8]) -> bool {
+    match buf.split_first() {
+        Some((first, rest)) => first & 0x3f == 0 && rest.iter().all(|b| *b == 0),
+        None => true,
+    }
+}
+
+/**
+* 校验并反序列化BLS公钥，用于来自不可信来源的公钥
+*
+* 子群检查由 blsc 在反序列化时完成(bls_init 开启了 mcl 的 verifyOrder)，不在子群中的点反序列化失败；
+* 无穷远点可以通过子群检查，但会让任何签名在聚合验证中被抵消，这里单独拒绝
+* @param buf 序列化数据
+* @returns 返回BLS公钥，校验失败时返回错误
+*/
+pub fn bls_validate_public_key(buf: &[u8]) -> Result<BlsPublicKey, CryptoError> {
+    if is_infinity_encoding(buf) {
+        return Err(CryptoError::InvalidKey(
+            "bls: public key is the point at infinity".to_string(),
+        ));
+    }
+    bls_public_key_deserialize(buf.to_vec()).map_err(|_| {
+        CryptoError::InvalidKey("bls: public key not on curve or not in subgroup".to_string())
+    })
+}
+
+/**
+* 校验并反序列化BLS签名，用于来自不可信来源的签名
+*
+* 检查与 bls_validate_public_key 相同
+* @param buf 序列化数据
+* @returns 返回BLS签名，校验失败时返回错误
+*/
+pub fn bls_validate_signature(buf: &[u8]) -> Result<BlsSignature, CryptoError> {
+    if is_infinity_encoding(buf) {
+        return Err(CryptoError::InvalidInput(
+            "bls: signature is the point at infinity".to_string(),
+        ));
+    }
+    bls_signature_deserialize(buf.to_vec()).map_err(|_| {
+        CryptoError::InvalidInput("bls: signature not on curve or not in subgroup".to_string())
+    })
+}
+
+/**
+* 校验序列化的公钥和签名后验证BLS签名，聚合验证前应对每个输入的公钥和签名做同样的校验
+* @param sig 序列化的BLS签名
+* @param pub_key 序列化的BLS公钥
+* @param data 已签名数据
+* @returns 返回校验和验证签名是否都成功
+*/
+pub fn bls_verify_serialized(sig: &[u8], pub_key: &[u8], data: Arc<Vec<u8>>) -> bool {
+    bls_verify_serialized_detailed(sig, pub_key, data).is_ok()
+}
+
+/**
+* 校验序列化的公钥和签名后验证BLS签名，失败时返回原因
+* @param sig 序列化的BLS签名
+* @param pub_key 序列化的BLS公钥
+* @param data 已签名数据
+*/
+pub fn bls_verify_serialized_detailed(
+    sig: &[u8],
+    pub_key: &[u8],
+    data: Arc<Vec<u8>>,
+) -> Result<(), VerifyFailure> {
+    let pub_key = bls_validate_public_key(pub_key).map_err(|_| VerifyFailure::MalformedKey)?;
+    let sig = bls_validate_signature(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+    bls_verify_detailed(&sig, &pub_key, data)
+}
+
+/**
+* 通用签名接口，签名为序列化的BLS签名
+*/
+impl Signer for BlsSecretKey {
+    type PublicKey = BlsPublicKey;
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let sig = bls_sign(self, Arc::new(msg.to_vec()))?;
+        bls_signature_serialize(POINT_BUF_SIZE, &sig)
+    }
+
+    fn public_key(&self) -> Result<BlsPublicKey, CryptoError> {
+        bls_get_public_key(self)
+    }
+}
+
+/**
+* 通用验证接口，签名按 bls_validate_signature 校验后再验证
+*/
+impl Verifier for BlsPublicKey {
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+        let sig = bls_validate_signature(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+        bls_verify_detailed(&sig, self, Arc::new(msg.to_vec()))
+    }
+}
+
+// 复制点时使用的序列化缓冲大小，足够容纳所有支持曲线的 G1/G2 点
+const POINT_BUF_SIZE: usize = 512;
+
+/**
+* 聚合多个BLS签名
+*
+* blsc 的加法会原地修改左值，这里先通过序列化复制第一个签名，不修改输入
+* @param sigs BLS签名
+* @returns 返回聚合签名，输入为空时返回错误
+*/
+pub fn bls_aggregate_signatures(sigs: &[&BlsSignature]) -> Result<BlsSignature, CryptoError> {
+    let (first, rest) = sigs
+        .split_first()
+        .ok_or_else(|| invalid_input("no signatures to aggregate"))?;
+    let agg = bls_signature_deserialize(bls_signature_serialize(POINT_BUF_SIZE, first)?)?;
+    for sig in rest {
+        bls_signature_add(&agg, sig);
+    }
+    Ok(agg)
+}
+
+/**
+* 聚合多个BLS公钥
+* @param pub_keys BLS公钥
+* @returns 返回聚合公钥，输入为空时返回错误
+*/
+pub fn bls_aggregate_public_keys(pub_keys: &[&BlsPublicKey]) -> Result<BlsPublicKey, CryptoError> {
+    let (first, rest) = pub_keys
+        .split_first()
+        .ok_or_else(|| invalid_input("no public keys to aggregate"))?;
+    let agg = bls_public_key_deserialize(bls_public_key_serialize(POINT_BUF_SIZE, first)?)?;
+    for pub_key in rest {
+        bls_public_key_add(&agg, pub_key);
+    }
+    Ok(agg)
+}
+
+/**
+* 验证多个公钥对同一消息的聚合签名
+*
+* 直接聚合公钥会受到恶意公钥攻击，每个公钥必须事先用 bls_verify_pop 验证过持有证明。
+* 对不同消息的聚合验证需要计算多个配对的乘积，blsc 没有导出配对运算，这里不提供，
+* 需要时使用 bls12381 模块的 verify_aggregate_distinct
+* @param sig 聚合签名
+* @param pub_keys 签名者的公钥
+* @param data 已签名数据
+* @returns 返回验证签名是否成功，公钥为空时失败
+*/
+pub fn bls_verify_aggregate(
+    sig: &BlsSignature,
+    pub_keys: &[&BlsPublicKey],
+    data: Arc<Vec<u8>>,
+) -> bool {
+    match bls_aggregate_public_keys(pub_keys) {
+        Ok(agg) => bls_verify(sig, &agg, data),
+        Err(_) => false,
+    }
+}
+
+/**
+* t-of-n 门限BLS密钥，secret_shares 和 public_shares 与生成时给出的成员id一一对应
+*/
+pub struct BlsThresholdKeys {
+    pub secret_key: BlsSecretKey,
+    pub public_key: BlsPublicKey,
+    pub secret_shares: Vec<BlsSecretKey>,
+    pub public_shares: Vec<BlsPublicKey>,
+}
+
+/**
+* 使用 Shamir 秘密共享生成门限BLS密钥，多项式的常数项为主私钥，其余 threshold-1 个系数随机生成
+*
+* 生成方知道主私钥，需要无可信方时应使用分布式密钥生成协议
+* @param threshold 恢复签名需要的份额数
+* @param ids 成员唯一id，不能为0或重复
+* @returns 返回主密钥和每个成员的私钥份额与公钥份额，参数无效时返回错误
+*/
+pub fn bls_generate_key_shares(
+    threshold: usize,
+    ids: &[&BlsId],
+) -> Result<BlsThresholdKeys, CryptoError> {
+    if threshold == 0 || ids.len() < threshold {
+        return Err(invalid_input(
+            "threshold must be between 1 and the number of ids",
+        ));
+    }
+    let zero = bls_id_set_int(0);
+    for (i, id) in ids.iter().enumerate() {
+        if bls_id_is_equal(id, &zero) || ids[..i].iter().any(|other| bls_id_is_equal(id, other)) {
+            return Err(invalid_input("ids must be non-zero and distinct"));
+        }
+    }
+
+    let mut sec_vec = BlsSecKeyVec::new(threshold);
+    let mut pub_vec = BlsPubKeyVec::new(threshold);
+    let mut secret_key = None;
+    for _ in 0..threshold {
+        let coefficient = bls_hash_to_secret_key(genSecureRandBytes(32))?;
+        bls_add_secret_key_to_vec(&mut sec_vec, &coefficient);
+        bls_add_public_key_to_vec(&mut pub_vec, &bls_get_public_key(&coefficient)?);
+        secret_key.get_or_insert(coefficient);
+    }
+    let secret_key = secret_key.ok_or_else(|| failed("generate secret key"))?;
+    let public_key = bls_get_public_key(&secret_key)?;
+    let msk = bls_get_secret_key_vec(&sec_vec)?;
+    let mpk = bls_get_public_key_vec(&pub_vec)?;
+
+    let mut secret_shares = Vec::with_capacity(ids.len());
+    let mut public_shares = Vec::with_capacity(ids.len());
+    for id in ids {
+        secret_shares.push(bls_secret_key_share(&msk, threshold, id)?);
+        public_shares.push(bls_public_key_share(&mpk, threshold, id)?);
+    }
+    Ok(BlsThresholdKeys {
+        secret_key,
+        public_key,
+        secret_shares,
+        public_shares,
+    })
+}
+
+/**
+* 使用私钥份额生成部分签名
+* @param secret_share 成员的私钥份额
+* @param data 待签名的数据
+* @returns 返回部分签名，失败时返回错误
+*/
+pub fn bls_partial_sign(
+    secret_share: &BlsSecretKey,
+    data: Arc<Vec<u8>>,
+) -> Result<BlsSignature, CryptoError> {
+    bls_sign(secret_share, data)
+}
+
+/**
+* 使用成员的公钥份额验证部分签名，恢复签名前应先丢弃验证失败的份额
+* @param sig 部分签名
+* @param public_share 成员的公钥份额
+* @param data 已签名数据
+* @returns 返回验证签名是否成功
+*/
+pub fn bls_verify_partial(
+    sig: &BlsSignature,
+    public_share: &BlsPublicKey,
+    data: Arc<Vec<u8>>,
+) -> bool {
+    bls_verify(sig, public_share, data)
+}
+
+/**
+* 通过拉格朗日插值从 threshold 个部分签名恢复主签名，恢复结果可用主公钥验证
+* @param ids 部分签名对应的成员唯一id
+* @param sigs 部分签名，与 ids 一一对应
+* @param threshold 门限，只使用前 threshold 个份额
+* @returns 返回主签名，份额不足时返回错误
+*/
+pub fn bls_recover_signature(
+    ids: &[&BlsId],
+    sigs: &[&BlsSignature],
+    threshold: usize,
+) -> Result<BlsSignature, CryptoError> {
+    if threshold == 0 || ids.len() != sigs.len() || ids.len() < threshold {
+        return Err(invalid_input("not enough signature shares"));
+    }
+    let mut id_vec = BlsIdVec::new(threshold);
+    let mut sig_vec = BlsSigVec::new(threshold);
+    for (id, sig) in ids.iter().zip(sigs.iter()).take(threshold) {
+        bls_add_id_to_vec(&mut id_vec, id);
+        bls_add_signature_to_vec(&mut sig_vec, sig);
     }
+    bls_signature_recover(&sig_vec, &id_vec, threshold)
 }
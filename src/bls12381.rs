@@ -0,0 +1,598 @@
+//! 纯 Rust 实现的 BLS12-381 签名，与 Eth2 及 IETF BLS 签名草案互通
+//!
+//! 提供两种变体：MinPk 的公钥在 G1 (48字节)、签名在 G2 (96字节)，即 Eth2 使用的方案；
+//! MinSig 的公钥在 G2、签名在 G1 (48字节)。点按 ZCash / Eth2 格式序列化，
+//! 压缩格式的 G1/G2 点分别为48/96字节，非压缩格式为96/192字节，解析时都会检查点在曲线上且属于素数阶子群。
+//!
+//! 消息按 RFC 9380 的 SSWU 方法哈希到曲线，域分离标签使用草案的 proof-of-possession 方案，
+//! 与 bls 模块 (blsc 绑定) 的序列化格式不兼容
+
+use bls12_381::hash_to_curve::{ExpandMsgXmd, HashToCurve};
+use bls12_381::{
+    multi_miller_loop, G1Affine, G1Projective, G2Affine, G2Prepared, G2Projective, Gt, Scalar,
+};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+use crate::ct::wipe;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::kdf::hkdf;
+use crate::secret::SecretBytes;
+
+/// 私钥长度，大端序
+pub const SECRET_KEY_SIZE: usize = 32;
+/// 压缩格式的 G1 点长度
+pub const G1_COMPRESSED_SIZE: usize = 48;
+/// 非压缩格式的 G1 点长度
+pub const G1_UNCOMPRESSED_SIZE: usize = 96;
+/// 压缩格式的 G2 点长度
+pub const G2_COMPRESSED_SIZE: usize = 96;
+/// 非压缩格式的 G2 点长度
+pub const G2_UNCOMPRESSED_SIZE: usize = 192;
+
+/// MinPk 变体的域分离标签，与 Eth2 相同
+pub const DST_MIN_PK: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// MinSig 变体的域分离标签
+pub const DST_MIN_SIG: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_POP_";
+
+/// 签名变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// 公钥在 G1，签名在 G2，公钥最短
+    MinPk,
+    /// 公钥在 G2，签名在 G1，签名最短
+    MinSig,
+}
+
+impl Variant {
+    /// 压缩格式的公钥长度
+    pub fn public_key_size(self) -> usize {
+        match self {
+            Variant::MinPk => G1_COMPRESSED_SIZE,
+            Variant::MinSig => G2_COMPRESSED_SIZE,
+        }
+    }
+
+    /// 压缩格式的签名长度
+    pub fn signature_size(self) -> usize {
+        match self {
+            Variant::MinPk => G2_COMPRESSED_SIZE,
+            Variant::MinSig => G1_COMPRESSED_SIZE,
+        }
+    }
+
+    /// 签名时使用的域分离标签
+    pub fn dst(self) -> &'static [u8] {
+        match self {
+            Variant::MinPk => DST_MIN_PK,
+            Variant::MinSig => DST_MIN_SIG,
+        }
+    }
+}
+
+/// BLS12-381 私钥，两种变体共用，内存在释放时擦除
+pub struct SecretKey(SecretBytes);
+
+impl SecretKey {
+    /// 按 IETF BLS 签名草案的 KeyGen 由种子派生私钥，与 EIP-2333 的主私钥相同
+    ///
+    /// ikm: 种子，至少32字节
+    pub fn key_gen(ikm: &[u8]) -> Result<Self, CryptoError> {
+        if ikm.len() < 32 {
+            return Err(CryptoError::InvalidInput(
+                "bls12381: ikm must be at least 32 bytes".to_string(),
+            ));
+        }
+        let mut ikm = ikm.to_vec();
+        ikm.push(0);
+        let mut salt = Sha256::digest(b"BLS-SIG-KEYGEN-SALT-");
+        loop {
+            // OKM 长度 L = 48，按大端序取值后模 r
+            let okm = hkdf(&salt, &ikm, &[0, 48], 48).map_err(CryptoError::InvalidInput)?;
+            let mut wide = [0u8; 64];
+            for (dst, src) in wide.iter_mut().zip(okm.iter().rev()) {
+                *dst = *src;
+            }
+            let scalar = Scalar::from_bytes_wide(&wide);
+            if scalar != Scalar::zero() {
+                return Ok(SecretKey::from_scalar(&scalar));
+            }
+            salt = Sha256::digest(&salt);
+        }
+    }
+
+    /// 解析32字节大端序的私钥，为0或不小于群阶时返回错误
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let key = SecretKey(SecretBytes::from(bytes));
+        match key.scalar() {
+            Some(s) if s != Scalar::zero() => Ok(key),
+            _ => Err(CryptoError::InvalidKey(
+                "bls12381: invalid secret key".to_string(),
+            )),
+        }
+    }
+
+    /// 32字节大端序的私钥
+    pub fn to_bytes(&self) -> SecretBytes {
+        self.0.clone()
+    }
+
+    /// 由私钥计算压缩格式的公钥
+    pub fn public_key(&self, variant: Variant) -> Vec<u8> {
+        let s = self.expect_scalar();
+        match variant {
+            Variant::MinPk => G1Affine::from(G1Projective::generator() * s)
+                .to_compressed()
+                .to_vec(),
+            Variant::MinSig => G2Affine::from(G2Projective::generator() * s)
+                .to_compressed()
+                .to_vec(),
+        }
+    }
+
+    fn from_scalar(scalar: &Scalar) -> Self {
+        let mut bytes = scalar.to_bytes();
+        bytes.reverse();
+        let key = SecretKey(SecretBytes::from(&bytes[..]));
+        wipe(&mut bytes);
+        key
+    }
+
+    fn scalar(&self) -> Option<Scalar> {
+        if self.0.len() != SECRET_KEY_SIZE {
+            return None;
+        }
+        let mut le = [0u8; SECRET_KEY_SIZE];
+        for (dst, src) in le.iter_mut().zip(self.0.iter().rev()) {
+            *dst = *src;
+        }
+        let scalar = Option::from(Scalar::from_bytes(&le));
+        wipe(&mut le);
+        scalar
+    }
+
+    // 构造时已检查过私钥
+    fn expect_scalar(&self) -> Scalar {
+        self.scalar()
+            .expect("bls12381: secret key checked on construction")
+    }
+}
+
+/// 签名，返回压缩格式的签名，使用变体默认的域分离标签
+///
+/// variant: 签名变体
+/// sk: 私钥
+/// msg: 任意长度的消息
+pub fn sign(variant: Variant, sk: &SecretKey, msg: &[u8]) -> Vec<u8> {
+    sign_with_dst(variant, sk, msg, variant.dst()).expect("bls12381: default dst is valid")
+}
+
+/// 使用指定的域分离标签签名，返回压缩格式的签名
+///
+/// variant: 签名变体
+/// sk: 私钥
+/// msg: 任意长度的消息
+/// dst: 域分离标签，不能为空，如与其他链互通时使用对方协议规定的标签
+pub fn sign_with_dst(
+    variant: Variant,
+    sk: &SecretKey,
+    msg: &[u8],
+    dst: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    check_dst(dst)?;
+    let s = sk.expect_scalar();
+    Ok(match variant {
+        Variant::MinPk => G2Affine::from(hash_g2(msg, dst) * s)
+            .to_compressed()
+            .to_vec(),
+        Variant::MinSig => G1Affine::from(hash_g1(msg, dst) * s)
+            .to_compressed()
+            .to_vec(),
+    })
+}
+
+/// 验证签名，失败时返回原因，使用变体默认的域分离标签
+///
+/// 公钥和签名可以是压缩或非压缩格式，无穷远点视为无效
+/// variant: 签名变体
+/// pk: 公钥
+/// msg: 消息
+/// sig: 签名
+pub fn verify(variant: Variant, pk: &[u8], msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+    verify_with_dst(variant, pk, msg, sig, variant.dst())
+}
+
+/// 使用指定的域分离标签验证签名，dst 为空时视为签名无效
+pub fn verify_with_dst(
+    variant: Variant,
+    pk: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+    dst: &[u8],
+) -> Result<(), VerifyFailure> {
+    let ok = match variant {
+        Variant::MinPk => {
+            let pk = decode_g1(pk).map_err(|_| VerifyFailure::MalformedKey)?;
+            let sig = decode_g2(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+            check_dst(dst).map_err(|_| VerifyFailure::SignatureInvalid)?;
+            let h = G2Affine::from(hash_g2(msg, dst));
+            // e(pk, H(m)) == e(g1, sig)
+            pairing_check(&[(pk, h), (-G1Affine::generator(), sig)])
+        }
+        Variant::MinSig => {
+            let pk = decode_g2(pk).map_err(|_| VerifyFailure::MalformedKey)?;
+            let sig = decode_g1(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+            check_dst(dst).map_err(|_| VerifyFailure::SignatureInvalid)?;
+            let h = G1Affine::from(hash_g1(msg, dst));
+            // e(H(m), pk) == e(sig, g2)
+            pairing_check(&[(h, pk), (sig, -G2Affine::generator())])
+        }
+    };
+    if !ok {
+        return Err(VerifyFailure::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// RFC 9380 的 BLS12381G1_XMD:SHA-256_SSWU_RO_ 套件，把消息哈希到 G1，返回96字节非压缩格式的点
+///
+/// 内部使用 expand_message_xmd (与 digest::expand_message_xmd 相同) 和简化 SWU 映射，
+/// 超过255字节的标签按 RFC 9380 5.3.3 先哈希
+/// msg: 消息
+/// dst: 域分离标签，不能为空
+pub fn hash_to_g1(msg: &[u8], dst: &[u8]) -> Result<[u8; G1_UNCOMPRESSED_SIZE], CryptoError> {
+    check_dst(dst)?;
+    Ok(G1Affine::from(hash_g1(msg, dst)).to_uncompressed())
+}
+
+/// RFC 9380 的 BLS12381G2_XMD:SHA-256_SSWU_RO_ 套件，把消息哈希到 G2，返回192字节非压缩格式的点
+///
+/// msg: 消息
+/// dst: 域分离标签，不能为空
+pub fn hash_to_g2(msg: &[u8], dst: &[u8]) -> Result<[u8; G2_UNCOMPRESSED_SIZE], CryptoError> {
+    check_dst(dst)?;
+    Ok(G2Affine::from(hash_g2(msg, dst)).to_uncompressed())
+}
+
+/// 聚合多个签名，返回压缩格式的聚合签名，输入为空或签名无法解析时返回错误
+///
+/// variant: 签名变体
+/// sigs: 签名，可以是压缩或非压缩格式
+pub fn aggregate_signatures(variant: Variant, sigs: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+    match variant {
+        Variant::MinPk => aggregate_g2(sigs),
+        Variant::MinSig => aggregate_g1(sigs),
+    }
+}
+
+/// 聚合多个公钥，返回压缩格式的聚合公钥，输入为空或公钥无法解析时返回错误
+///
+/// variant: 签名变体
+/// pks: 公钥，可以是压缩或非压缩格式
+pub fn aggregate_public_keys(variant: Variant, pks: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+    match variant {
+        Variant::MinPk => aggregate_g1(pks),
+        Variant::MinSig => aggregate_g2(pks),
+    }
+}
+
+/// 验证多个公钥对同一消息的聚合签名，即 Eth2 的 FastAggregateVerify
+///
+/// 为防止恶意公钥攻击，调用方必须事先验证过每个公钥的持有证明 (proof of possession)
+/// variant: 签名变体
+/// pks: 签名者的公钥
+/// msg: 所有签名者签名的同一消息
+/// sig: 聚合签名
+pub fn verify_aggregate(
+    variant: Variant,
+    pks: &[&[u8]],
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<(), VerifyFailure> {
+    let pk = aggregate_public_keys(variant, pks).map_err(|_| VerifyFailure::MalformedKey)?;
+    verify(variant, &pk, msg, sig)
+}
+
+/// 验证多个公钥分别对不同消息的聚合签名，即 IETF BLS 签名草案的 AggregateVerify
+///
+/// 消息必须两两不同，出现重复消息时视为签名无效，以免同一消息上的聚合被拆分伪造
+/// variant: 签名变体
+/// pks: 签名者的公钥
+/// msgs: 与公钥一一对应的消息
+/// sig: 聚合签名
+pub fn verify_aggregate_distinct(
+    variant: Variant,
+    pks: &[&[u8]],
+    msgs: &[&[u8]],
+    sig: &[u8],
+) -> Result<(), VerifyFailure> {
+    if pks.len() != msgs.len() {
+        return Err(VerifyFailure::WrongLength {
+            expected: pks.len(),
+            actual: msgs.len(),
+        });
+    }
+    let mut seen = HashSet::with_capacity(msgs.len());
+    if pks.is_empty() || !msgs.iter().all(|m| seen.insert(*m)) {
+        return Err(VerifyFailure::SignatureInvalid);
+    }
+    let dst = variant.dst();
+    let mut terms = Vec::with_capacity(pks.len() + 1);
+    match variant {
+        Variant::MinPk => {
+            let sig = decode_g2(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+            for (pk, msg) in pks.iter().zip(msgs) {
+                let pk = decode_g1(pk).map_err(|_| VerifyFailure::MalformedKey)?;
+                terms.push((pk, G2Affine::from(hash_g2(msg, dst))));
+            }
+            terms.push((-G1Affine::generator(), sig));
+        }
+        Variant::MinSig => {
+            let sig = decode_g1(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+            for (pk, msg) in pks.iter().zip(msgs) {
+                let pk = decode_g2(pk).map_err(|_| VerifyFailure::MalformedKey)?;
+                terms.push((G1Affine::from(hash_g1(msg, dst)), pk));
+            }
+            terms.push((sig, -G2Affine::generator()));
+        }
+    }
+    if !pairing_check(&terms) {
+        return Err(VerifyFailure::SignatureInvalid);
+    }
+    Ok(())
+}
+
+/// 把 G1 点 (48或96字节) 转换为48字节压缩格式
+pub fn g1_compress(point: &[u8]) -> Result<[u8; G1_COMPRESSED_SIZE], CryptoError> {
+    Ok(decode_g1(point)?.to_compressed())
+}
+
+/// 把 G1 点 (48或96字节) 转换为96字节非压缩格式
+pub fn g1_decompress(point: &[u8]) -> Result<[u8; G1_UNCOMPRESSED_SIZE], CryptoError> {
+    Ok(decode_g1(point)?.to_uncompressed())
+}
+
+/// 把 G2 点 (96或192字节) 转换为96字节压缩格式
+pub fn g2_compress(point: &[u8]) -> Result<[u8; G2_COMPRESSED_SIZE], CryptoError> {
+    Ok(decode_g2(point)?.to_compressed())
+}
+
+/// 把 G2 点 (96或192字节) 转换为192字节非压缩格式
+pub fn g2_decompress(point: &[u8]) -> Result<[u8; G2_UNCOMPRESSED_SIZE], CryptoError> {
+    Ok(decode_g2(point)?.to_uncompressed())
+}
+
+// RFC 9380 3.1 要求域分离标签非空
+fn check_dst(dst: &[u8]) -> Result<(), CryptoError> {
+    if dst.is_empty() {
+        return Err(CryptoError::InvalidInput(
+            "bls12381: empty domain separation tag".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn hash_g1(msg: &[u8], dst: &[u8]) -> G1Projective {
+    <G1Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(msg, dst)
+}
+
+fn hash_g2(msg: &[u8], dst: &[u8]) -> G2Projective {
+    <G2Projective as HashToCurve<ExpandMsgXmd<Sha256>>>::hash_to_curve(msg, dst)
+}
+
+// 解析 G1 点并拒绝无穷远点，from_compressed / from_uncompressed 已做子群检查
+fn decode_g1(bytes: &[u8]) -> Result<G1Affine, CryptoError> {
+    let point: Option<G1Affine> = if bytes.len() == G1_COMPRESSED_SIZE {
+        let mut buf = [0u8; G1_COMPRESSED_SIZE];
+        buf.copy_from_slice(bytes);
+        G1Affine::from_compressed(&buf).into()
+    } else if bytes.len() == G1_UNCOMPRESSED_SIZE {
+        let mut buf = [0u8; G1_UNCOMPRESSED_SIZE];
+        buf.copy_from_slice(bytes);
+        G1Affine::from_uncompressed(&buf).into()
+    } else {
+        return Err(CryptoError::InvalidInput(format!(
+            "bls12381: invalid G1 point length {}",
+            bytes.len()
+        )));
+    };
+    match point {
+        Some(p) if !bool::from(p.is_identity()) => Ok(p),
+        Some(_) => Err(CryptoError::InvalidInput(
+            "bls12381: G1 point is the identity".to_string(),
+        )),
+        None => Err(CryptoError::InvalidInput(
+            "bls12381: invalid G1 point".to_string(),
+        )),
+    }
+}
+
+fn decode_g2(bytes: &[u8]) -> Result<G2Affine, CryptoError> {
+    let point: Option<G2Affine> = if bytes.len() == G2_COMPRESSED_SIZE {
+        let mut buf = [0u8; G2_COMPRESSED_SIZE];
+        buf.copy_from_slice(bytes);
+        G2Affine::from_compressed(&buf).into()
+    } else if bytes.len() == G2_UNCOMPRESSED_SIZE {
+        let mut buf = [0u8; G2_UNCOMPRESSED_SIZE];
+        buf.copy_from_slice(bytes);
+        G2Affine::from_uncompressed(&buf).into()
+    } else {
+        return Err(CryptoError::InvalidInput(format!(
+            "bls12381: invalid G2 point length {}",
+            bytes.len()
+        )));
+    };
+    match point {
+        Some(p) if !bool::from(p.is_identity()) => Ok(p),
+        Some(_) => Err(CryptoError::InvalidInput(
+            "bls12381: G2 point is the identity".to_string(),
+        )),
+        None => Err(CryptoError::InvalidInput(
+            "bls12381: invalid G2 point".to_string(),
+        )),
+    }
+}
+
+fn aggregate_g1(points: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+    if points.is_empty() {
+        return Err(CryptoError::InvalidInput(
+            "bls12381: nothing to aggregate".to_string(),
+        ));
+    }
+    let mut sum = G1Projective::identity();
+    for p in points {
+        sum += decode_g1(p)?;
+    }
+    Ok(G1Affine::from(sum).to_compressed().to_vec())
+}
+
+fn aggregate_g2(points: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+    if points.is_empty() {
+        return Err(CryptoError::InvalidInput(
+            "bls12381: nothing to aggregate".to_string(),
+        ));
+    }
+    let mut sum = G2Projective::identity();
+    for p in points {
+        sum += decode_g2(p)?;
+    }
+    Ok(G2Affine::from(sum).to_compressed().to_vec())
+}
+
+// 检查各配对之积为单位元，只做一次最终幂运算
+fn pairing_check(terms: &[(G1Affine, G2Affine)]) -> bool {
+    let prepared: Vec<(G1Affine, G2Prepared)> = terms
+        .iter()
+        .map(|(p, q)| (*p, G2Prepared::from(*q)))
+        .collect();
+    let refs: Vec<(&G1Affine, &G2Prepared)> = prepared.iter().map(|(p, q)| (p, q)).collect();
+    multi_miller_loop(&refs).final_exponentiation() == Gt::identity()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_bls12381() {
+        // EIP-2333 test case 0 的主私钥
+        let seed = Vec::from_hex("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04").unwrap();
+        assert_eq!(
+            SecretKey::key_gen(&seed).unwrap().to_bytes().to_vec(),
+            Vec::from_hex("0d7359d57963ab8fbbde1852dcf553fedbc31f464d80ee7d40ae683122b45070")
+                .unwrap()
+        );
+        assert!(SecretKey::key_gen(&seed[..31]).is_err());
+
+        // Eth2 共识层规范的 bls/sign 和 bls/verify 测试向量
+        let sk = SecretKey::from_bytes(
+            &Vec::from_hex("263dbd792f5b1be47ed85f8938c0f29586af0d3ac7b977f21c278fe1462040e3")
+                .unwrap(),
+        )
+        .unwrap();
+        let pk = sk.public_key(Variant::MinPk);
+        assert_eq!(pk, Vec::from_hex("a491d1b0ecd9bb917989f0e74f0dea0422eac4a873e5e2644f368dffb9a6e20fd6e10c1b77654d067c0618f6e5a7f79a").unwrap());
+        let msg = [0x56u8; 32];
+        let sig = sign(Variant::MinPk, &sk, &msg);
+        assert_eq!(sig, Vec::from_hex("882730e5d03f6b42c3abc26d3372625034e1d871b65a8a6b900a56dae22da98abbe1b68f85e49fe7652a55ec3d0591c20767677e33e5cbb1207315c41a9ac03be39c2e7668edc043d6cb1d9fd93033caa8a1c5b0e84bedaeb6c64972503a43eb").unwrap());
+        assert!(verify(Variant::MinPk, &pk, &msg, &sig).is_ok());
+        assert_eq!(
+            verify(Variant::MinPk, &pk, &[0u8; 32], &sig),
+            Err(VerifyFailure::SignatureInvalid)
+        );
+
+        // 非压缩格式与压缩格式可以互相转换，验证时均可使用
+        let pk_full = g1_decompress(&pk).unwrap();
+        let sig_full = g2_decompress(&sig).unwrap();
+        assert_eq!(g1_compress(&pk_full).unwrap().to_vec(), pk);
+        assert_eq!(g2_compress(&sig_full).unwrap().to_vec(), sig);
+        assert!(verify(Variant::MinPk, &pk_full, &msg, &sig_full).is_ok());
+
+        // MinSig 变体
+        let pk2 = sk.public_key(Variant::MinSig);
+        let sig2 = sign(Variant::MinSig, &sk, &msg);
+        assert_eq!(pk2.len(), Variant::MinSig.public_key_size());
+        assert_eq!(sig2.len(), Variant::MinSig.signature_size());
+        assert!(verify(Variant::MinSig, &pk2, &msg, &sig2).is_ok());
+        assert_eq!(
+            verify(Variant::MinPk, &pk2, &msg, &sig2),
+            Err(VerifyFailure::MalformedKey)
+        );
+
+        // 无穷远点的公钥 (压缩格式 0xc0 || 0*) 和0私钥被拒绝
+        let mut identity = [0u8; G1_COMPRESSED_SIZE];
+        identity[0] = 0xc0;
+        assert_eq!(
+            verify(Variant::MinPk, &identity, &msg, &sig),
+            Err(VerifyFailure::MalformedKey)
+        );
+        assert!(SecretKey::from_bytes(&[0u8; SECRET_KEY_SIZE]).is_err());
+        assert!(SecretKey::from_bytes(&[0xffu8; SECRET_KEY_SIZE]).is_err());
+
+        // 自定义域分离标签的签名不能用默认标签验证
+        let dst = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+        let sig3 = sign_with_dst(Variant::MinPk, &sk, &msg, dst).unwrap();
+        assert!(verify_with_dst(Variant::MinPk, &pk, &msg, &sig3, dst).is_ok());
+        assert!(verify(Variant::MinPk, &pk, &msg, &sig3).is_err());
+        assert!(sign_with_dst(Variant::MinPk, &sk, &msg, b"").is_err());
+
+        // RFC 9380 J.9.1 和 J.10.1 的测试向量，非压缩格式依次为 x、y，
+        // G2 的坐标 x0 + x1 * I 按 x1、x0 的顺序排列
+        let dst = b"QUUX-V01-CS02-with-BLS12381G1_XMD:SHA-256_SSWU_RO_";
+        assert_eq!(hash_to_g1(b"", dst).unwrap().to_vec(), Vec::from_hex("052926add2207b76ca4fa57a8734416c8dc95e24501772c814278700eed6d1e4e8cf62d9c09db0fac349612b759e79a108ba738453bfed09cb546dbb0783dbb3a5f1f566ed67bb6be0e8c67e2e81a4cc68ee29813bb7994998f3eae0c9c6a265").unwrap());
+        assert_eq!(hash_to_g1(b"abc", dst).unwrap().to_vec(), Vec::from_hex("03567bc5ef9c690c2ab2ecdf6a96ef1c139cc0b2f284dca0a9a7943388a49a3aee664ba5379a7655d3c68900be2f69030b9c15f3fe6e5cf4211f346271d7b01c8f3b28be689c8429c85b67af215533311f0b8dfaaa154fa6b88176c229f2885d").unwrap());
+        let dst = b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+        assert_eq!(hash_to_g2(b"", dst).unwrap().to_vec(), Vec::from_hex("05cb8437535e20ecffaef7752baddf98034139c38452458baeefab379ba13dff5bf5dd71b72418717047f5b0f37da03d0141ebfbdca40eb85b87142e130ab689c673cf60f1a3e98d69335266f30d9b8d4ac44c1038e9dcdd5393faf5c41fb78a12424ac32561493f3fe3c260708a12b7c620e7be00099a974e259ddc7d1f6395c3c811cdd19f1e8dbf3e9ecfdcbab8d60503921d7f6a12805e72940b963c0cf3471c7b2a524950ca195d11062ee75ec076daf2d4bc358c4b190c0c98064fdd92").unwrap());
+        assert_eq!(hash_to_g2(b"abc", dst).unwrap().to_vec(), Vec::from_hex("139cddbccdc5e91b9623efd38c49f81a6f83f175e80b06fc374de9eb4b41dfe4ca3a230ed250fbe3a2acf73a41177fd802c2d18e033b960562aae3cab37a27ce00d80ccd5ba4b7fe0e7a210245129dbec7780ccc7954725f4168aff2787776e600aa65dae3c8d732d10ecd2c50f8a1baf3001578f71c694e03866e9f3d49ac1e1ce70dd94a733534f106d4cec0eddd161787327b68159716a37440985269cf584bcb1e621d3a7202be6ea05c4cfe244aeb197642555a0645fb87bf7466b2ba48").unwrap());
+        assert!(hash_to_g2(b"abc", b"").is_err());
+
+        // 聚合签名
+        for variant in [Variant::MinPk, Variant::MinSig].iter().copied() {
+            let keys: Vec<SecretKey> = (1u8..=3)
+                .map(|i| SecretKey::key_gen(&[i; 32]).unwrap())
+                .collect();
+            let pks: Vec<Vec<u8>> = keys.iter().map(|k| k.public_key(variant)).collect();
+            let pks: Vec<&[u8]> = pks.iter().map(|p| &p[..]).collect();
+
+            let sigs: Vec<Vec<u8>> = keys.iter().map(|k| sign(variant, k, b"block")).collect();
+            let sigs: Vec<&[u8]> = sigs.iter().map(|s| &s[..]).collect();
+            let agg = aggregate_signatures(variant, &sigs).unwrap();
+            assert!(verify_aggregate(variant, &pks, b"block", &agg).is_ok());
+            assert!(verify_aggregate(variant, &pks[..2], b"block", &agg).is_err());
+            assert!(verify_aggregate(variant, &[], b"block", &agg).is_err());
+
+            let msgs: Vec<&[u8]> = vec![b"a", b"b", b"c"];
+            let sigs: Vec<Vec<u8>> = keys
+                .iter()
+                .zip(&msgs)
+                .map(|(k, m)| sign(variant, k, m))
+                .collect();
+            let sigs: Vec<&[u8]> = sigs.iter().map(|s| &s[..]).collect();
+            let agg = aggregate_signatures(variant, &sigs).unwrap();
+            assert!(verify_aggregate_distinct(variant, &pks, &msgs, &agg).is_ok());
+            assert_eq!(
+                verify_aggregate_distinct(variant, &pks, &[b"a", b"b", b"x"], &agg),
+                Err(VerifyFailure::SignatureInvalid)
+            );
+            assert_eq!(
+                verify_aggregate_distinct(variant, &pks, &msgs[..2], &agg),
+                Err(VerifyFailure::WrongLength {
+                    expected: 3,
+                    actual: 2
+                })
+            );
+
+            // 重复的消息被拒绝，即使聚合签名本身有效
+            let sigs: Vec<Vec<u8>> = keys[..2].iter().map(|k| sign(variant, k, b"a")).collect();
+            let sigs: Vec<&[u8]> = sigs.iter().map(|s| &s[..]).collect();
+            let agg = aggregate_signatures(variant, &sigs).unwrap();
+            assert!(verify_aggregate(variant, &pks[..2], b"a", &agg).is_ok());
+            assert_eq!(
+                verify_aggregate_distinct(variant, &pks[..2], &[b"a", b"a"], &agg),
+                Err(VerifyFailure::SignatureInvalid)
+            );
+        }
+        assert!(aggregate_signatures(Variant::MinPk, &[]).is_err());
+    }
+}
@@ -0,0 +1,345 @@
+//! Brainpool 曲线 (RFC 5639) 上的 ECDSA 和 ECDH
+//!
+//! 支持 brainpoolP256r1 和 brainpoolP384r1，分别使用 SHA256 和 SHA384，接口形式与 signature 模块的
+//! NIST 曲线 ECDSA 相同：私钥为大端整数，公钥为未压缩点 04 | x | y，签名为DER编码的 (r, s)。
+//! sign_prehashed 和 brainpool_verify_prehashed 接受调用方计算好的摘要。
+//!
+//! 曲线运算基于 ec 模块的固定迭代次数阶梯，底层的 num-bigint 运算不是常量时间的。
+//! 私钥以 SecretBytes 保存，密钥对离开作用域时擦除
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::Zero;
+use ring::digest;
+use simple_asn1::{from_der, to_der, ASN1Block};
+
+use crate::ec::Curve;
+use crate::random::{OsRng, SecureRandom};
+use crate::secret::SecretBytes;
+
+/// Brainpool 曲线
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrainpoolCurve {
+    /// brainpoolP256r1，签名使用 SHA256
+    P256r1,
+    /// brainpoolP384r1，签名使用 SHA384
+    P384r1,
+}
+
+impl BrainpoolCurve {
+    fn curve(self) -> Curve {
+        match self {
+            BrainpoolCurve::P256r1 => Curve::new(
+                "A9FB57DBA1EEA9BC3E660A909D838D726E3BF623D52620282013481D1F6E5377",
+                "7D5A0975FC2C3057EEF67530417AFFE7FB8055C126DC5C6CE94A4B44F330B5D9",
+                "26DC5C6CE94A4B44F330B5D9BBD77CBF958416295CF7E1CE6BCCDC18FF8C07B6",
+                "A9FB57DBA1EEA9BC3E660A909D838D718C397AA3B561A6F7901E0E82974856A7",
+                "8BD2AEB9CB7E57CB2C4B482FFC81B7AFB9DE27E1E3BD23C23A4453BD9ACE3262",
+                "547EF835C3DAC4FD97F8461A14611DC9C27745132DED8E545C1D54C72F046997",
+            ),
+            BrainpoolCurve::P384r1 => Curve::new(
+                "8CB91E82A3386D280F5D6F7E50E641DF152F7109ED5456B412B1DA197FB71123ACD3A729901D1A71874700133107EC53",
+                "7BC382C63D8C150C3C72080ACE05AFA0C2BEA28E4FB22787139165EFBA91F90F8AA5814A503AD4EB04A8C7DD22CE2826",
+                "04A8C7DD22CE28268B39B55416F0447C2FB77DE107DCD2A62E880EA53EEB62D57CB4390295DBC9943AB78696FA504C11",
+                "8CB91E82A3386D280F5D6F7E50E641DF152F7109ED5456B31F166E6CAC0425A7CF3AB6AF6B7FC3103B883202E9046565",
+                "1D1C64F068CF45FFA2A63A81B7C13F6B8847A3E77EF14FE3DB7FCAFE0CBD10E8E826E03436D646AAEF87B2E247D4AF1E",
+                "8ABE1D7520F9C2A45CB1EB8E95CFD55262B70B29FEEC5864E19C054FF99129280E4646217791811142820341263C5315",
+            ),
+        }
+    }
+
+    fn digest_alg(self) -> &'static digest::Algorithm {
+        match self {
+            BrainpoolCurve::P256r1 => &digest::SHA256,
+            BrainpoolCurve::P384r1 => &digest::SHA384,
+        }
+    }
+
+    fn hash(self, msg: &[u8]) -> BigUint {
+        // 哈希长度与阶的位数相同，不需要截断
+        BigUint::from_bytes_be(digest::digest(self.digest_alg(), msg).as_ref())
+    }
+
+    fn prehashed(self, digest: &[u8]) -> Result<BigUint, String> {
+        let len = self.digest_alg().output_len;
+        if digest.len() != len {
+            return Err(format!("brainpool: digest must be {} bytes", len));
+        }
+        Ok(BigUint::from_bytes_be(digest))
+    }
+}
+
+/// Brainpool 曲线上的密钥对
+pub struct BrainpoolKeyPair {
+    curve: BrainpoolCurve,
+    // 定长大端编码的私钥，离开作用域时擦除
+    d: SecretBytes,
+    public_key: Vec<u8>,
+}
+
+impl PartialEq for BrainpoolKeyPair {
+    fn eq(&self, other: &Self) -> bool {
+        self.curve == other.curve && self.public_key == other.public_key
+    }
+}
+
+impl Eq for BrainpoolKeyPair {}
+
+impl BrainpoolKeyPair {
+    /// 生成随机密钥对
+    pub fn generate(curve: BrainpoolCurve) -> Self {
+        BrainpoolKeyPair::generate_with_rng(curve, &OsRng)
+    }
+
+    /// 使用指定的随机数生成器生成密钥对
+    pub fn generate_with_rng(curve: BrainpoolCurve, rng: &dyn SecureRandom) -> Self {
+        let c = curve.curve();
+        let d = c.random_scalar(&c.n, rng);
+        let public_key = c.encode(&c.mul_g(&d));
+        BrainpoolKeyPair {
+            curve,
+            d: SecretBytes::new(c.to_bytes(&d)),
+            public_key,
+        }
+    }
+
+    /// 从私钥构建
+    ///
+    /// curve: 曲线
+    /// priv_key: 大端编码的私钥，长度为32或48字节
+    pub fn from_private_key(curve: BrainpoolCurve, priv_key: &[u8]) -> Result<Self, String> {
+        let c = curve.curve();
+        let d = BigUint::from_bytes_be(priv_key);
+        if priv_key.len() != c.size || d.is_zero() || d >= c.n {
+            return Err("brainpool: invalid private key".to_string());
+        }
+        let public_key = c.encode(&c.mul_g(&d));
+        Ok(BrainpoolKeyPair {
+            curve,
+            d: SecretBytes::from(priv_key),
+            public_key,
+        })
+    }
+
+    /// 私钥
+    pub fn private_key(&self) -> Vec<u8> {
+        self.d.to_vec()
+    }
+
+    /// 公钥
+    pub fn public_key(&self) -> Vec<u8> {
+        self.public_key.clone()
+    }
+
+    /// 签名，返回DER编码的签名
+    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+        self.sign_with_rng(msg, &OsRng)
+    }
+
+    /// 使用指定的随机数生成器签名，返回DER编码的签名
+    pub fn sign_with_rng(&self, msg: &[u8], rng: &dyn SecureRandom) -> Vec<u8> {
+        self.sign_hash(self.curve.hash(msg), rng)
+    }
+
+    /// 对调用方计算好的摘要签名，返回DER编码的签名
+    ///
+    /// digest: 消息的摘要，P256r1 为32字节的 SHA256，P384r1 为48字节的 SHA384
+    pub fn sign_prehashed(&self, digest: &[u8]) -> Result<Vec<u8>, String> {
+        Ok(self.sign_hash(self.curve.prehashed(digest)?, &OsRng))
+    }
+
+    fn sign_hash(&self, e: BigUint, rng: &dyn SecureRandom) -> Vec<u8> {
+        let c = self.curve.curve();
+        let n = &c.n;
+        let d = BigUint::from_bytes_be(&self.d);
+        loop {
+            let k = c.random_scalar(n, rng);
+            let r = match c.to_affine(&c.mul_g(&k)) {
+                Some((x, _)) => x % n,
+                None => continue,
+            };
+            if r.is_zero() {
+                continue;
+            }
+            let s = (c.inv_n(&k) * ((&e + &r * &d) % n)) % n;
+            if s.is_zero() {
+                continue;
+            }
+            let seq = ASN1Block::Sequence(
+                0,
+                vec![
+                    ASN1Block::Integer(0, BigInt::from(r)),
+                    ASN1Block::Integer(0, BigInt::from(s)),
+                ],
+            );
+            return to_der(&seq).expect("brainpool: encode signature");
+        }
+    }
+
+    /// ECDH 密钥协商，返回共享点的 x 坐标
+    ///
+    /// peer_pub_key: 对方的未压缩公钥，必须在同一曲线上
+    pub fn ecdh(&self, peer_pub_key: &[u8]) -> Result<Vec<u8>, String> {
+        let c = self.curve.curve();
+        let peer = c
+            .decode(peer_pub_key)
+            .map_err(|e| format!("brainpool: {}", e))?;
+        // 余因子为1，曲线上的点都在素数阶子群中
+        let (x, _) = c
+            .to_affine(&c.mul(&BigUint::from_bytes_be(&self.d), &peer))
+            .ok_or_else(|| "brainpool: shared point is infinity".to_string())?;
+        Ok(c.to_bytes(&x))
+    }
+}
+
+/// 验证 Brainpool 曲线上的ECDSA签名
+///
+/// curve: 曲线
+/// pub_key: 未压缩公钥
+/// msg: 已签名的数据
+/// sig: DER编码的签名
+pub fn brainpool_verify(curve: BrainpoolCurve, pub_key: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    verify_hash(curve, pub_key, curve.hash(msg), sig)
+}
+
+/// 使用调用方计算好的摘要验证签名
+///
+/// curve: 曲线
+/// pub_key: 未压缩公钥
+/// digest: 已签名消息的摘要
+/// sig: DER编码的签名
+pub fn brainpool_verify_prehashed(
+    curve: BrainpoolCurve,
+    pub_key: &[u8],
+    digest: &[u8],
+    sig: &[u8],
+) -> bool {
+    match curve.prehashed(digest) {
+        Ok(e) => verify_hash(curve, pub_key, e, sig),
+        Err(_) => false,
+    }
+}
+
+fn verify_hash(curve: BrainpoolCurve, pub_key: &[u8], e: BigUint, sig: &[u8]) -> bool {
+    let c = curve.curve();
+    let q = match c.decode(pub_key) {
+        Ok(q) => q,
+        Err(_) => return false,
+    };
+    let (r, s) = match parse_signature(sig) {
+        Some(rs) => rs,
+        None => return false,
+    };
+    let n = &c.n;
+    if r.is_zero() || s.is_zero() || r >= *n || s >= *n {
+        return false;
+    }
+    let w = c.inv_n(&s);
+    let u1 = (e * &w) % n;
+    let u2 = (&r * &w) % n;
+    match c.to_affine(&c.add(&c.mul_g(&u1), &c.mul(&u2, &q))) {
+        Some((x, _)) => x % n == r,
+        None => false,
+    }
+}
+
+fn parse_signature(sig: &[u8]) -> Option<(BigUint, BigUint)> {
+    match from_der(sig).ok()?.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [ASN1Block::Integer(_, r), ASN1Block::Integer(_, s)] => {
+                Some((r.to_biguint()?, s.to_biguint()?))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_brainpool_ecdh() {
+        // test vectors from: https://tools.ietf.org/html/rfc7027#appendix-A.1
+        let a = BrainpoolKeyPair::from_private_key(
+            BrainpoolCurve::P256r1,
+            &Vec::from_hex("81DB1EE100150FF2EA338D708271BE38300CB54241D79950F77B063039804F1D")
+                .unwrap(),
+        )
+        .unwrap();
+        let b = BrainpoolKeyPair::from_private_key(
+            BrainpoolCurve::P256r1,
+            &Vec::from_hex("55E40BC41E37E3E2AD25C3C6654511FFA8474A91A0032087593852D3E7D76BD3")
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(a.public_key(), Vec::from_hex("0444106E913F92BC02A1705D9953A8414DB95E1AAA49E81D9E85F929A8E3100BE58AB4846F11CACCB73CE49CBDD120F5A900A69FD32C272223F789EF10EB089BDC").unwrap());
+        let z = Vec::from_hex("89AFC39D41D3B327814B80940B042590F96556EC91E6AE7939BCE31F3A18BF2B")
+            .unwrap();
+        assert_eq!(a.ecdh(&b.public_key()).unwrap(), z);
+        assert_eq!(b.ecdh(&a.public_key()).unwrap(), z);
+        assert!(a.ecdh(&b.public_key()[1..]).is_err());
+    }
+
+    #[test]
+    fn test_brainpool_ecdsa() {
+        // pyca/cryptography 生成的 "abc" 的签名
+        let pk = Vec::from_hex("0444106e913f92bc02a1705d9953a8414db95e1aaa49e81d9e85f929a8e3100be58ab4846f11caccb73ce49cbdd120f5a900a69fd32c272223f789ef10eb089bdc").unwrap();
+        let sig = Vec::from_hex("304402200b4dfee7b78085f8e7ae0cb0d9fa4bc7831dd2a23678f0b8e4a4147188709f5d0220683bf1db05ce83521c3f75871c78c6a8ea0f4c75f77f10fc6a1d155eb9b8965d").unwrap();
+        assert!(brainpool_verify(BrainpoolCurve::P256r1, &pk, b"abc", &sig));
+        assert!(!brainpool_verify(BrainpoolCurve::P256r1, &pk, b"abd", &sig));
+        let abc = digest::digest(&digest::SHA256, b"abc");
+        assert!(brainpool_verify_prehashed(
+            BrainpoolCurve::P256r1,
+            &pk,
+            abc.as_ref(),
+            &sig
+        ));
+        assert!(!brainpool_verify_prehashed(
+            BrainpoolCurve::P256r1,
+            &pk,
+            &abc.as_ref()[1..],
+            &sig
+        ));
+
+        let pk = Vec::from_hex("0468b665dd91c195800650cdd363c625f4e742e8134667b767b1b476793588f885ab698c852d4a6e77a252d6380fcaf06855bc91a39c9ec01dee36017b7d673a931236d2f1f5c83942d049e3fa20607493e0d038ff2fd30c2ab67d15c85f7faa59").unwrap();
+        let sig = Vec::from_hex("3064023061d1d5d9f4addde2234c5ac68a7dad02c455c3c4d1067b1b7a7de2cff2b16624e498d884e441251b9c8464f6b3a181da02306f067429dedeb757ebffc3dd52bf55de0a5bfa42a820189ac0884df43123df9a226f6af3ac643b80c6c07fa2ddc55be3").unwrap();
+        assert!(brainpool_verify(BrainpoolCurve::P384r1, &pk, b"abc", &sig));
+        assert!(!brainpool_verify(BrainpoolCurve::P256r1, &pk, b"abc", &sig));
+
+        let key_pair = BrainpoolKeyPair::generate(BrainpoolCurve::P384r1);
+        let sig = key_pair.sign(b"hello");
+        assert!(brainpool_verify(
+            BrainpoolCurve::P384r1,
+            &key_pair.public_key(),
+            b"hello",
+            &sig
+        ));
+        let copy =
+            BrainpoolKeyPair::from_private_key(BrainpoolCurve::P384r1, &key_pair.private_key())
+                .unwrap();
+        assert!(copy == key_pair);
+
+        let hello = digest::digest(&digest::SHA384, b"hello");
+        let sig = key_pair.sign_prehashed(hello.as_ref()).unwrap();
+        assert!(brainpool_verify(
+            BrainpoolCurve::P384r1,
+            &key_pair.public_key(),
+            b"hello",
+            &sig
+        ));
+        assert!(key_pair.sign_prehashed(&[0u8; 32]).is_err());
+
+        // 固定的随机数得到相同的签名
+        let rng = ring::test::rand::FixedByteRandom { byte: 1 };
+        let sig = key_pair.sign_with_rng(b"hello", &rng);
+        assert_eq!(key_pair.sign_with_rng(b"hello", &rng), sig);
+        assert!(brainpool_verify(
+            BrainpoolCurve::P384r1,
+            &key_pair.public_key(),
+            b"hello",
+            &sig
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+//! 确定性 CBOR 编码 (RFC 8949 4.2.1 节) 和签名
+//!
+//! 整数和长度总是使用最短编码，只用定长编码，map 的键按编码后的字节序排列且不能重复，
+//! 同一个值只有一种编码，签名方和验证方不需要约定序列化细节。
+//! 不支持浮点数和 undefined 等简单值，解码时拒绝任何非确定性的编码
+
+use crate::framing::Reader;
+use crate::signature::{scheme_verify, SignatureScheme};
+
+/// 解码时允许的最大嵌套深度
+const MAX_DEPTH: usize = 64;
+
+/// CBOR 数据项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CborValue {
+    /// 非负整数
+    Unsigned(u64),
+    /// 负整数，值为 -1 - n
+    Negative(u64),
+    Bytes(Vec<u8>),
+    Text(String),
+    Array(Vec<CborValue>),
+    /// 键值对，编码时排序
+    Map(Vec<(CborValue, CborValue)>),
+    Tag(u64, Box<CborValue>),
+    Bool(bool),
+    Null,
+}
+
+impl CborValue {
+    /// 从有符号整数构建
+    pub fn int(v: i64) -> Self {
+        if v < 0 {
+            CborValue::Negative(!v as u64)
+        } else {
+            CborValue::Unsigned(v as u64)
+        }
+    }
+
+    /// 确定性编码，map 中有重复的键时返回错误
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        self.encode(&mut out)?;
+        Ok(out)
+    }
+
+    /// 解码，只接受确定性编码
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut decoder = Decoder(Reader::new("cbor", data));
+        let value = decoder.value(0)?;
+        decoder.0.finish()?;
+        Ok(value)
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) -> Result<(), String> {
+        match self {
+            CborValue::Unsigned(n) => write_head(out, 0, *n),
+            CborValue::Negative(n) => write_head(out, 1, *n),
+            CborValue::Bytes(b) => {
+                write_head(out, 2, b.len() as u64);
+                out.extend_from_slice(b);
+            }
+            CborValue::Text(s) => {
+                write_head(out, 3, s.len() as u64);
+                out.extend_from_slice(s.as_bytes());
+            }
+            CborValue::Array(items) => {
+                write_head(out, 4, items.len() as u64);
+                for item in items {
+                    item.encode(out)?;
+                }
+            }
+            CborValue::Map(entries) => {
+                let mut encoded = entries
+                    .iter()
+                    .map(|(k, v)| Ok((k.to_bytes()?, v.to_bytes()?)))
+                    .collect::<Result<Vec<_>, String>>()?;
+                encoded.sort_by(|a, b| a.0.cmp(&b.0));
+                if encoded.windows(2).any(|w| w[0].0 == w[1].0) {
+                    return Err("cbor: duplicate map key".to_string());
+                }
+                write_head(out, 5, encoded.len() as u64);
+                for (k, v) in encoded {
+                    out.extend_from_slice(&k);
+                    out.extend_from_slice(&v);
+                }
+            }
+            CborValue::Tag(tag, value) => {
+                write_head(out, 6, *tag);
+                value.encode(out)?;
+            }
+            CborValue::Bool(b) => out.push(if *b { 0xf5 } else { 0xf4 }),
+            CborValue::Null => out.push(0xf6),
+        }
+        Ok(())
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= 0xff {
+        out.push(major | 24);
+        out.push(n as u8);
+    } else if n <= 0xffff {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= 0xffff_ffff {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+struct Decoder<'a>(Reader<'a>);
+
+impl<'a> Decoder<'a> {
+    /// 读取头部，返回(主类型, 附加信息, 参数)，拒绝非最短编码和不定长编码
+    fn head(&mut self) -> Result<(u8, u8, u64), String> {
+        let b = self.0.u8()?;
+        let (major, info) = (b >> 5, b & 0x1f);
+        let n = match info {
+            0..=23 => return Ok((major, info, u64::from(info))),
+            24 => u64::from(self.0.u8()?),
+            25 => u64::from(self.0.u16()?),
+            26 => u64::from(self.0.u32()?),
+            27 => self.0.u64()?,
+            _ => return Err("cbor: indefinite length or reserved encoding".to_string()),
+        };
+        let min = match info {
+            24 => 24,
+            25 => 0x100,
+            26 => 0x1_0000,
+            _ => 0x1_0000_0000,
+        };
+        if n < min {
+            return Err("cbor: non-shortest integer encoding".to_string());
+        }
+        Ok((major, info, n))
+    }
+
+    fn len(&self, n: u64) -> Result<usize, String> {
+        if n > self.0.remaining() as u64 {
+            return Err("cbor: unexpected end of data".to_string());
+        }
+        Ok(n as usize)
+    }
+
+    fn value(&mut self, depth: usize) -> Result<CborValue, String> {
+        if depth > MAX_DEPTH {
+            return Err("cbor: nesting too deep".to_string());
+        }
+        let (major, info, n) = self.head()?;
+        Ok(match major {
+            0 => CborValue::Unsigned(n),
+            1 => CborValue::Negative(n),
+            2 => {
+                let len = self.len(n)?;
+                CborValue::Bytes(self.0.take(len)?.to_vec())
+            }
+            3 => {
+                let len = self.len(n)?;
+                let text = std::str::from_utf8(self.0.take(len)?)
+                    .map_err(|_| "cbor: invalid utf-8 text".to_string())?;
+                CborValue::Text(text.to_string())
+            }
+            4 => {
+                let len = self.len(n)?;
+                let mut items = Vec::with_capacity(len);
+                for _ in 0..len {
+                    items.push(self.value(depth + 1)?);
+                }
+                CborValue::Array(items)
+            }
+            5 => {
+                let len = self.len(n)?;
+                let mut entries = Vec::with_capacity(len);
+                let mut last_key: Option<&[u8]> = None;
+                for _ in 0..len {
+                    let start = self.0.position();
+                    let key = self.value(depth + 1)?;
+                    let key_bytes = self.0.since(start);
+                    if let Some(last) = last_key {
+                        if key_bytes <= last {
+                            return Err("cbor: map keys not sorted or duplicated".to_string());
+                        }
+                    }
+                    last_key = Some(key_bytes);
+                    entries.push((key, self.value(depth + 1)?));
+                }
+                CborValue::Map(entries)
+            }
+            6 => CborValue::Tag(n, Box::new(self.value(depth + 1)?)),
+            _ => match info {
+                20 => CborValue::Bool(false),
+                21 => CborValue::Bool(true),
+                22 => CborValue::Null,
+                _ => return Err("cbor: unsupported simple value or float".to_string()),
+            },
+        })
+    }
+}
+
+/// 对确定性编码签名
+///
+/// value: 待签名的数据项
+/// sign: 签名函数，参数为确定性编码
+pub fn sign_with<F>(value: &CborValue, sign: F) -> Result<Vec<u8>, String>
+where
+    F: FnOnce(&[u8]) -> Vec<u8>,
+{
+    Ok(sign(&value.to_bytes()?))
+}
+
+/// 验证签名，data 必须是确定性编码
+///
+/// scheme: 签名方案
+/// pk: 公钥
+/// data: 收到的 CBOR 编码
+/// sig: 签名
+pub fn verify(scheme: SignatureScheme, pk: &[u8], data: &[u8], sig: &[u8]) -> bool {
+    CborValue::from_bytes(data).is_ok() && scheme_verify(scheme, pk, data, sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ed25519;
+    use hex::FromHex;
+
+    fn text(s: &str) -> CborValue {
+        CborValue::Text(s.to_string())
+    }
+
+    #[test]
+    fn test_cbor() {
+        // test vectors from: https://tools.ietf.org/html/rfc8949#appendix-A
+        let cases = vec![
+            (CborValue::int(0), "00"),
+            (CborValue::int(23), "17"),
+            (CborValue::int(24), "1818"),
+            (CborValue::int(1000), "1903e8"),
+            (CborValue::int(1000000), "1a000f4240"),
+            (CborValue::Unsigned(u64::MAX), "1bffffffffffffffff"),
+            (CborValue::int(-1), "20"),
+            (CborValue::int(-1000), "3903e7"),
+            (CborValue::Bytes(vec![]), "40"),
+            (text("IETF"), "6449455446"),
+            (text("\u{fc}"), "62c3bc"),
+            (
+                CborValue::Array(vec![
+                    CborValue::int(1),
+                    CborValue::Array(vec![CborValue::int(2), CborValue::int(3)]),
+                    CborValue::Array(vec![CborValue::int(4), CborValue::int(5)]),
+                ]),
+                "8301820203820405",
+            ),
+            (
+                CborValue::Map(vec![
+                    (
+                        text("b"),
+                        CborValue::Array(vec![CborValue::int(2), CborValue::int(3)]),
+                    ),
+                    (text("a"), CborValue::int(1)),
+                ]),
+                "a26161016162820203",
+            ),
+            (
+                CborValue::Tag(1, Box::new(CborValue::int(1363896240))),
+                "c11a514b67b0",
+            ),
+            (CborValue::Bool(true), "f5"),
+            (CborValue::Null, "f6"),
+        ];
+        for (value, hex) in cases {
+            let encoded = value.to_bytes().unwrap();
+            assert_eq!(encoded, Vec::from_hex(hex).unwrap());
+            if let CborValue::Map(_) = value {
+                continue;
+            }
+            assert_eq!(CborValue::from_bytes(&encoded).unwrap(), value);
+        }
+
+        // https://tools.ietf.org/html/rfc8949#section-4.2.1 键的排序
+        let keys = vec![
+            CborValue::Bool(false),
+            CborValue::Array(vec![CborValue::int(-1)]),
+            CborValue::Array(vec![CborValue::int(100)]),
+            text("aa"),
+            text("z"),
+            CborValue::int(-1),
+            CborValue::int(100),
+            CborValue::int(10),
+        ];
+        let map = CborValue::Map(keys.into_iter().map(|k| (k, CborValue::Null)).collect());
+        let encoded = map.to_bytes().unwrap();
+        assert_eq!(
+            encoded,
+            Vec::from_hex("a80af61864f620f6617af6626161f6811864f68120f6f4f6").unwrap()
+        );
+        assert!(CborValue::from_bytes(&encoded).is_ok());
+
+        // 非确定性编码被拒绝
+        for bad in [
+            "1800",
+            "1900ff",
+            "a2616201616102",
+            "a261610161610f",
+            "5f40ff",
+            "f93c00",
+        ]
+        .iter()
+        {
+            assert!(CborValue::from_bytes(&Vec::from_hex(bad).unwrap()).is_err());
+        }
+        assert!(CborValue::Map(vec![
+            (text("a"), CborValue::Null),
+            (text("a"), CborValue::Null)
+        ])
+        .to_bytes()
+        .is_err());
+    }
+
+    #[test]
+    fn test_cbor_signature() {
+        let (sk, pk) = ed25519::keypair(&[3u8; 32]);
+        let value = CborValue::Map(vec![
+            (CborValue::int(1), text("pi")),
+            (CborValue::int(-7), CborValue::Bytes(vec![1, 2, 3])),
+        ]);
+        let sig = sign_with(&value, |data| ed25519::signature(data, &sk).to_vec()).unwrap();
+        let data = value.to_bytes().unwrap();
+        assert!(verify(SignatureScheme::Ed25519, &pk, &data, &sig));
+        assert!(!verify(SignatureScheme::Ed25519, &pk, &data[1..], &sig));
+    }
+}
@@ -0,0 +1,159 @@
+//! AES-CBC 和 AES-CTR 分组密码模式
+//!
+//! 用于与只支持这些模式的旧系统互通。两种模式都不提供完整性保护，新协议应使用 aead 模块；
+//! 必须使用 CBC 时应对密文另外计算MAC，并且不能让对方区分填充错误和MAC错误
+
+use crypto::aessafe::{
+    AesSafe128Decryptor, AesSafe128Encryptor, AesSafe192Decryptor, AesSafe192Encryptor,
+    AesSafe256Decryptor, AesSafe256Encryptor,
+};
+use crypto::symmetriccipher::{BlockDecryptor, BlockEncryptor};
+
+const BLOCK_SIZE: usize = 16;
+
+pub(crate) fn encryptor(key: &[u8]) -> Result<Box<dyn BlockEncryptor>, String> {
+    match key.len() {
+        16 => Ok(Box::new(AesSafe128Encryptor::new(key))),
+        24 => Ok(Box::new(AesSafe192Encryptor::new(key))),
+        32 => Ok(Box::new(AesSafe256Encryptor::new(key))),
+        _ => Err("cipher: key must be 16, 24 or 32 bytes".to_string()),
+    }
+}
+
+pub(crate) fn decryptor(key: &[u8]) -> Result<Box<dyn BlockDecryptor>, String> {
+    match key.len() {
+        16 => Ok(Box::new(AesSafe128Decryptor::new(key))),
+        24 => Ok(Box::new(AesSafe192Decryptor::new(key))),
+        32 => Ok(Box::new(AesSafe256Decryptor::new(key))),
+        _ => Err("cipher: key must be 16, 24 or 32 bytes".to_string()),
+    }
+}
+
+fn to_block(iv: &[u8]) -> Result<[u8; BLOCK_SIZE], String> {
+    if iv.len() != BLOCK_SIZE {
+        return Err("cipher: iv must be 16 bytes".to_string());
+    }
+    let mut block = [0u8; BLOCK_SIZE];
+    block.copy_from_slice(iv);
+    Ok(block)
+}
+
+/// AES-CBC 加密，使用 PKCS#7 填充
+///
+/// key: 16、24或32字节的AES密钥
+/// iv: 16字节IV，必须不可预测，每次加密使用新的随机值
+/// plaintext: 明文
+pub fn aes_cbc_encrypt(key: &[u8], iv: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = encryptor(key)?;
+    let mut prev = to_block(iv)?;
+    let pad = BLOCK_SIZE - plaintext.len() % BLOCK_SIZE;
+    let mut out = plaintext.to_vec();
+    out.resize(plaintext.len() + pad, pad as u8);
+    let mut block = [0u8; BLOCK_SIZE];
+    for chunk in out.chunks_mut(BLOCK_SIZE) {
+        for (p, c) in prev.iter_mut().zip(chunk.iter()) {
+            *p ^= c;
+        }
+        cipher.encrypt_block(&prev, &mut block);
+        chunk.copy_from_slice(&block);
+        prev = block;
+    }
+    Ok(out)
+}
+
+/// AES-CBC 解密并去除 PKCS#7 填充
+///
+/// key: 16、24或32字节的AES密钥
+/// iv: 加密时使用的IV
+/// ciphertext: 密文，长度为16的倍数
+pub fn aes_cbc_decrypt(key: &[u8], iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = decryptor(key)?;
+    let mut prev = to_block(iv)?;
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(BLOCK_SIZE) {
+        return Err("cipher: bad ciphertext length".to_string());
+    }
+    let mut out = vec![0u8; ciphertext.len()];
+    for (chunk, o) in ciphertext
+        .chunks(BLOCK_SIZE)
+        .zip(out.chunks_mut(BLOCK_SIZE))
+    {
+        cipher.decrypt_block(chunk, o);
+        for (b, p) in o.iter_mut().zip(prev.iter()) {
+            *b ^= p;
+        }
+        prev.copy_from_slice(chunk);
+    }
+    let pad = out[out.len() - 1] as usize;
+    if pad == 0 || pad > BLOCK_SIZE || out[out.len() - pad..].iter().any(|&b| b as usize != pad) {
+        return Err("cipher: bad padding".to_string());
+    }
+    out.truncate(out.len() - pad);
+    Ok(out)
+}
+
+/// AES-CTR 加密，计数器为整个16字节的大端整数 (NIST SP 800-38A)
+///
+/// 同一密钥下计数器区间不能重叠，否则会泄露明文的异或
+///
+/// key: 16、24或32字节的AES密钥
+/// iv: 16字节的初始计数器
+/// plaintext: 明文，任意长度
+pub fn aes_ctr_encrypt(key: &[u8], iv: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = encryptor(key)?;
+    let mut counter = u128::from_be_bytes(to_block(iv)?);
+    let mut out = plaintext.to_vec();
+    let mut keystream = [0u8; BLOCK_SIZE];
+    for chunk in out.chunks_mut(BLOCK_SIZE) {
+        cipher.encrypt_block(&counter.to_be_bytes(), &mut keystream);
+        for (b, k) in chunk.iter_mut().zip(keystream.iter()) {
+            *b ^= k;
+        }
+        counter = counter.wrapping_add(1);
+    }
+    Ok(out)
+}
+
+/// AES-CTR 解密，与加密相同
+///
+/// key: 16、24或32字节的AES密钥
+/// iv: 加密时使用的初始计数器
+/// ciphertext: 密文
+pub fn aes_ctr_decrypt(key: &[u8], iv: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    aes_ctr_encrypt(key, iv, ciphertext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_aes_cbc_ctr() {
+        // test vectors from: NIST SP 800-38A F.2.1 and F.5.1
+        let key = Vec::from_hex("2b7e151628aed2a6abf7158809cf4f3c").unwrap();
+        let plain = Vec::from_hex("6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411e5fbc1191a0a52eff69f2445df4f9b17ad2b417be66c3710").unwrap();
+
+        let iv = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let cbc = Vec::from_hex("7649abac8119b246cee98e9b12e9197d5086cb9b507219ee95db113a917678b273bed6b8e3c1743b7116e69e222295163ff1caa1681fac09120eca307586e1a7").unwrap();
+        let sealed = aes_cbc_encrypt(&key, &iv, &plain).unwrap();
+        // 明文是整块时追加一个完整的填充块
+        assert_eq!(sealed.len(), plain.len() + 16);
+        assert_eq!(&sealed[..plain.len()], &cbc[..]);
+        assert_eq!(aes_cbc_decrypt(&key, &iv, &sealed).unwrap(), plain);
+        assert_eq!(
+            aes_cbc_decrypt(&key, &iv, &cbc),
+            Err("cipher: bad padding".to_string())
+        );
+        assert!(aes_cbc_decrypt(&key, &iv, &sealed[1..]).is_err());
+        assert!(aes_cbc_encrypt(&key[1..], &iv, &plain).is_err());
+
+        let counter = Vec::from_hex("f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff").unwrap();
+        let ctr = Vec::from_hex("874d6191b620e3261bef6864990db6ce9806f66b7970fdff8617187bb9fffdff5ae4df3edbd5d35e5b4f09020db03eab1e031dda2fbe03d1792170a0f3009cee").unwrap();
+        assert_eq!(aes_ctr_encrypt(&key, &counter, &plain).unwrap(), ctr);
+        assert_eq!(
+            aes_ctr_decrypt(&key, &counter, &ctr[..37]).unwrap(),
+            &plain[..37]
+        );
+        assert!(aes_ctr_encrypt(&key, &counter[1..], &plain).is_err());
+    }
+}
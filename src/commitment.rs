@@ -0,0 +1,202 @@
+//! secp256k1 上的 Pedersen 承诺
+//!
+//! C = v·H + r·G，v 为金额，r 为32字节盲化因子。H 的 x 坐标为 SHA256(G 的未压缩编码)，y 为偶数，
+//! 与 Elements / Grin 使用的生成元相同，没有人知道 H 相对 G 的离散对数。
+//! 承诺对金额和盲化因子都是加法同态的：输入承诺之和减去输出承诺之和为无穷远点，
+//! 当且仅当(在不知道离散对数的前提下)金额和盲化因子分别平衡
+
+use secp256k1::curve::{Affine, Jacobian, Scalar, ECMULT_CONTEXT};
+
+use crate::schnorr::{lift_x, mul_gen, point_bytes};
+
+/// 承诺的编码长度
+pub const COMMITMENT_SIZE: usize = 33;
+
+/// 生成元 H 的 x 坐标
+const H_X: [u8; 32] = [
+    0x50, 0x92, 0x9b, 0x74, 0xc1, 0xa0, 0x49, 0x54, 0xb7, 0x8b, 0x4b, 0x60, 0x35, 0xe9, 0x7a, 0x5e,
+    0x07, 0x8a, 0x5a, 0x0f, 0x28, 0xec, 0x96, 0xd5, 0x47, 0xbf, 0xee, 0x9a, 0xce, 0x80, 0x3a, 0xc0,
+];
+
+/// Pedersen 承诺，保存33字节的压缩编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commitment([u8; COMMITMENT_SIZE]);
+
+impl Commitment {
+    /// 压缩编码，0x02 或 0x03 | x
+    pub fn to_bytes(&self) -> [u8; COMMITMENT_SIZE] {
+        self.0
+    }
+
+    /// 解析压缩编码，检查点在曲线上
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        if data.len() != COMMITMENT_SIZE {
+            return Err("commitment: must be 33 bytes".to_string());
+        }
+        let mut out = [0u8; COMMITMENT_SIZE];
+        out.copy_from_slice(data);
+        let c = Commitment(out);
+        c.point()?;
+        Ok(c)
+    }
+
+    /// 两个承诺之和，承诺 v1 + v2 和 r1 + r2
+    pub fn add(&self, other: &Commitment) -> Result<Commitment, String> {
+        from_jacobian(&Jacobian::from_ge(&self.point()?).add_ge(&other.point()?))
+    }
+
+    /// 两个承诺之差，承诺 v1 - v2 和 r1 - r2
+    pub fn sub(&self, other: &Commitment) -> Result<Commitment, String> {
+        from_jacobian(&Jacobian::from_ge(&self.point()?).add_ge(&other.point()?.neg()))
+    }
+
+    /// 检查承诺是否由 value 和 blinding 生成
+    pub fn open(&self, value: u64, blinding: &[u8; 32]) -> bool {
+        matches!(commit(value, blinding), Ok(c) if c == *self)
+    }
+
+    fn point(&self) -> Result<Affine, String> {
+        let p = lift_x(&self.0[1..]).ok_or_else(|| "commitment: invalid point".to_string())?;
+        match self.0[0] {
+            0x02 => Ok(p),
+            0x03 => {
+                let mut p = p.neg();
+                p.y.normalize();
+                Ok(p)
+            }
+            _ => Err("commitment: invalid prefix".to_string()),
+        }
+    }
+}
+
+/// 生成承诺
+///
+/// value: 金额
+/// blinding: 32字节大端盲化因子，必须小于曲线的阶，应当随机生成
+pub fn commit(value: u64, blinding: &[u8; 32]) -> Result<Commitment, String> {
+    let r = blinding_scalar(blinding)?;
+    let mut v = Scalar::default();
+    let mut vb = [0u8; 32];
+    vb[24..].copy_from_slice(&value.to_be_bytes());
+    let _ = v.set_b32(&vb);
+    let mut p = Jacobian::from_ge(&mul_gen(&r));
+    // ecmult_const 不接受零标量，金额为0时只有 r·G
+    if value != 0 {
+        let mut vh = Jacobian::default();
+        ECMULT_CONTEXT.ecmult_const(&mut vh, &generator_h(), &v);
+        p = p.add_var(&vh, None);
+    }
+    from_jacobian(&p)
+}
+
+/// 检查 Σpositive - Σnegative 是否为无穷远点，即金额和盲化因子都平衡
+///
+/// positive: 如交易的输入承诺
+/// negative: 如交易的输出承诺
+pub fn verify_sum(positive: &[Commitment], negative: &[Commitment]) -> bool {
+    let mut acc = Jacobian::default();
+    acc.set_infinity();
+    for (commitments, negate) in [(positive, false), (negative, true)] {
+        for c in commitments {
+            match c.point() {
+                Ok(p) if negate => acc = acc.add_ge(&p.neg()),
+                Ok(p) => acc = acc.add_ge(&p),
+                Err(_) => return false,
+            }
+        }
+    }
+    acc.is_infinity()
+}
+
+/// Σpositive - Σnegative 模曲线的阶，用于计算使承诺平衡的最后一个盲化因子
+///
+/// positive: 需要加上的盲化因子
+/// negative: 需要减去的盲化因子
+pub fn blind_sum(positive: &[[u8; 32]], negative: &[[u8; 32]]) -> Result<[u8; 32], String> {
+    let mut acc = Scalar::default();
+    for b in positive {
+        acc += blinding_scalar(b)?;
+    }
+    for b in negative {
+        acc += -blinding_scalar(b)?;
+    }
+    Ok(acc.b32())
+}
+
+fn blinding_scalar(blinding: &[u8; 32]) -> Result<Scalar, String> {
+    let mut r = Scalar::default();
+    if bool::from(r.set_b32(blinding)) {
+        return Err("commitment: blinding factor out of range".to_string());
+    }
+    Ok(r)
+}
+
+fn generator_h() -> Affine {
+    lift_x(&H_X).expect("commitment: generator h")
+}
+
+fn from_jacobian(p: &Jacobian) -> Result<Commitment, String> {
+    if p.is_infinity() {
+        return Err("commitment: point at infinity".to_string());
+    }
+    let (x, odd) = point_bytes(&Affine::from_gej(p));
+    let mut out = [0u8; COMMITMENT_SIZE];
+    out[0] = if odd { 0x03 } else { 0x02 };
+    out[1..].copy_from_slice(&x);
+    Ok(Commitment(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::ECDSASecp256k1;
+    use ring::digest;
+
+    #[test]
+    fn test_commitment() {
+        // H 的 x 坐标是 G 的未压缩编码的 SHA256
+        let g = ECDSASecp256k1::new()
+            .public_key_from_secret(&[&[0u8; 31][..], &[1]].concat())
+            .unwrap();
+        assert_eq!(
+            digest::digest(&digest::SHA256, &g.uncompressed).as_ref(),
+            &H_X[..]
+        );
+        // 盲化因子为0时承诺就是 v·H，v = 1 时为 H 本身
+        let h = commit(1, &[0u8; 32]).unwrap();
+        assert_eq!(h.to_bytes()[0], 0x02);
+        assert_eq!(&h.to_bytes()[1..], &H_X[..]);
+        assert!(commit(0, &[0u8; 32]).is_err());
+
+        let (r1, r2, r3) = ([1u8; 32], [2u8; 32], [5u8; 32]);
+        let c1 = commit(30, &r1).unwrap();
+        let c2 = commit(12, &r2).unwrap();
+        assert_eq!(
+            c1.add(&c2).unwrap(),
+            commit(42, &blind_sum(&[r1, r2], &[]).unwrap()).unwrap()
+        );
+        assert_eq!(
+            c1.sub(&c2).unwrap(),
+            commit(18, &blind_sum(&[r1], &[r2]).unwrap()).unwrap()
+        );
+        assert!(c1.open(30, &r1));
+        assert!(!c1.open(31, &r1));
+
+        // 两个输入、两个输出，最后一个输出的盲化因子由 blind_sum 计算
+        let c3 = commit(40, &r3).unwrap();
+        let r4 = blind_sum(&[r1, r2], &[r3]).unwrap();
+        let c4 = commit(2, &r4).unwrap();
+        assert!(verify_sum(&[c1, c2], &[c3, c4]));
+        assert!(!verify_sum(&[c1, c2], &[c3, commit(3, &r4).unwrap()]));
+        assert!(!verify_sum(&[c1], &[c3]));
+
+        let decoded = Commitment::from_bytes(&c4.to_bytes()).unwrap();
+        assert_eq!(decoded, c4);
+        assert!(decoded.open(2, &r4));
+        let mut bad = c4.to_bytes();
+        bad[0] = 0x04;
+        assert!(Commitment::from_bytes(&bad).is_err());
+        assert!(Commitment::from_bytes(&bad[1..]).is_err());
+        assert!(commit(1, &[0xff; 32]).is_err());
+    }
+}
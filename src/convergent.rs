@@ -0,0 +1,122 @@
+//! 收敛加密(内容派生密钥)
+//!
+//! 数据密钥 = HMAC-SHA256(收敛密钥, 上下文 | 算法ID | 明文)，再用该密钥和固定 nonce 做认证加密。
+//! 持有同一收敛密钥时，相同的明文总是得到相同的密文，存储端无需解密就能对加密后的资源去重。
+//! 每个数据密钥只加密一份明文，所以固定 nonce 不会造成 nonce 重用
+//!
+//! 代价是文件确认攻击(confirmation-of-file)：任何持有收敛密钥的人都能加密一份猜测的文件，
+//! 与存储中的密文比较，确认某个文件是否存在；对于模板化的低熵文件(如只有几位数字不同的合同)，
+//! 还可以逐个枚举剩余内容。收敛密钥只应在允许互相去重的范围内共享(如单个租户)，
+//! 不知道收敛密钥的存储端和外部攻击者无法做这种确认。需要隐藏文件是否相同时应使用 aead 模块的随机 nonce 加密
+//!
+//! 密文格式: 算法ID(2字节大端) | 密文 | 认证标签
+
+use crate::aead::{self, Aead};
+use crate::hmac::{DigestAlgorithm, Hmac};
+use crate::secret::SecretBytes;
+
+const CONTEXT: &[u8] = b"pi_crypto convergent v1";
+
+/// 收敛密钥的最小长度
+pub const MIN_KEY_SIZE: usize = 16;
+
+/// 收敛加密对象
+pub struct Convergent {
+    key: SecretBytes,
+    alg: &'static dyn Aead,
+}
+
+impl Convergent {
+    /// 构建收敛加密对象
+    ///
+    /// convergence_key: 收敛密钥，至少16字节，共享该密钥的各方可以互相去重
+    /// alg: 认证加密算法，密钥长度不能超过32字节
+    pub fn new(convergence_key: &[u8], alg: &'static dyn Aead) -> Result<Self, String> {
+        if convergence_key.len() < MIN_KEY_SIZE {
+            return Err("convergent: convergence key too short".to_string());
+        }
+        if alg.key_size() > 32 {
+            return Err(format!("convergent: {} key size not supported", alg.name()));
+        }
+        Ok(Convergent {
+            key: SecretBytes::from(convergence_key),
+            alg,
+        })
+    }
+
+    /// 计算明文的数据密钥
+    pub fn data_key(&self, plaintext: &[u8]) -> Vec<u8> {
+        let mut input = CONTEXT.to_vec();
+        input.extend_from_slice(&self.alg.id().to_be_bytes());
+        input.extend_from_slice(plaintext);
+        let mut key = Hmac::sign(DigestAlgorithm::SHA256, &self.key, &input);
+        key.truncate(self.alg.key_size());
+        key
+    }
+
+    /// 加密，返回(数据密钥, 密文)
+    ///
+    /// 数据密钥需要由调用方保存(如用 keyring 包装后存入元数据)，解密时使用
+    pub fn seal(&self, plaintext: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let key = self.data_key(plaintext);
+        let id = self.alg.id();
+        let nonce = vec![0u8; self.alg.nonce_size()];
+        let mut in_out = plaintext.to_vec();
+        self.alg
+            .seal_in_place(&key, &nonce, &id.to_be_bytes(), &mut in_out)?;
+
+        let mut out = id.to_be_bytes().to_vec();
+        out.append(&mut in_out);
+        Ok((key, out))
+    }
+}
+
+/// 使用数据密钥解密，算法按密文头部中的ID选择
+///
+/// data_key: seal 返回的数据密钥
+/// ciphertext: seal 返回的密文
+pub fn open(data_key: &[u8], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+    if ciphertext.len() < 2 {
+        return Err("convergent: ciphertext too short".to_string());
+    }
+    let id = u16::from_be_bytes([ciphertext[0], ciphertext[1]]);
+    let alg = aead::lookup(id).ok_or_else(|| format!("convergent: unknown algorithm id {}", id))?;
+    if ciphertext.len() < 2 + alg.tag_size() {
+        return Err("convergent: ciphertext too short".to_string());
+    }
+    let nonce = vec![0u8; alg.nonce_size()];
+    let mut in_out = ciphertext[2..].to_vec();
+    alg.open_in_place(data_key, &nonce, &ciphertext[..2], &mut in_out)?;
+    Ok(in_out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aead::{AES_256_GCM, SM4_GCM};
+
+    #[test]
+    fn test_convergent() {
+        let tenant = Convergent::new(&[1u8; 32], &AES_256_GCM).unwrap();
+        let (key, sealed) = tenant.seal(b"asset bytes").unwrap();
+        assert_eq!(open(&key, &sealed).unwrap(), b"asset bytes");
+
+        // 相同明文得到相同密文，可以去重
+        let (key2, sealed2) = tenant.seal(b"asset bytes").unwrap();
+        assert_eq!((key2, sealed2), (key.clone(), sealed.clone()));
+        assert_ne!(tenant.seal(b"other bytes").unwrap().1, sealed);
+
+        // 不同的收敛密钥或算法之间不能互相确认
+        let other = Convergent::new(&[2u8; 32], &AES_256_GCM).unwrap();
+        assert_ne!(other.seal(b"asset bytes").unwrap().1, sealed);
+        let sm4 = Convergent::new(&[1u8; 32], &SM4_GCM).unwrap();
+        let (sm4_key, sm4_sealed) = sm4.seal(b"asset bytes").unwrap();
+        assert_eq!(sm4_key.len(), 16);
+        assert_eq!(open(&sm4_key, &sm4_sealed).unwrap(), b"asset bytes");
+
+        let mut tampered = sealed;
+        tampered[5] ^= 1;
+        assert!(open(&key, &tampered).is_err());
+        assert!(Convergent::new(&[1u8; 8], &AES_256_GCM).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! COSE_Sign1 单签名消息 (RFC 9052) 和 CBOR Web Token (RFC 8392)
+//!
+//! 签名覆盖 Sig_structure = ["Signature1", 受保护头部, 外部附加数据, 载荷] 的 CBOR 编码，
+//! 算法标识只从受保护头部读取，验证时必须与调用方期望的算法一致。支持 ES256 (-7) 和 EdDSA (-8，Ed25519)，
+//! ES256 签名为定长的 r | s。编解码使用 cbor 模块，只接受确定性编码
+
+use crate::cbor::CborValue;
+use crate::ed25519;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::signature::{ecdsa_verify_detailed, EcdsaAlg, Signer};
+
+/// COSE_Sign1 的 CBOR 标签
+const TAG_SIGN1: u64 = 18;
+/// CWT 的 CBOR 标签
+const TAG_CWT: u64 = 61;
+/// 头部参数标签
+const HEADER_ALG: i64 = 1;
+const HEADER_CRIT: i64 = 2;
+
+/// COSE 签名算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoseAlg {
+    /// ECDSA P-256 SHA-256
+    ES256,
+    /// Ed25519
+    EdDSA,
+}
+
+impl CoseAlg {
+    /// IANA COSE Algorithms 注册表中的编号
+    pub fn id(self) -> i64 {
+        match self {
+            CoseAlg::ES256 => -7,
+            CoseAlg::EdDSA => -8,
+        }
+    }
+
+    /// 由编号解析
+    pub fn from_id(id: i64) -> Option<Self> {
+        match id {
+            -7 => Some(CoseAlg::ES256),
+            -8 => Some(CoseAlg::EdDSA),
+            _ => None,
+        }
+    }
+}
+
+/// COSE_Sign1 消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoseSign1 {
+    /// 受保护头部 map 的编码，参与签名
+    pub protected: Vec<u8>,
+    /// 不受保护的头部，如 kid (4)，不参与签名
+    pub unprotected: Vec<(CborValue, CborValue)>,
+    /// 载荷，None 表示分离的载荷
+    pub payload: Option<Vec<u8>>,
+    pub signature: Vec<u8>,
+}
+
+impl CoseSign1 {
+    /// 签名，受保护头部只包含 alg
+    ///
+    /// alg: 签名算法
+    /// signer: ES256 为 ECDSA_P256_SHA256_FIXED 的 EcdsaKeyPair，EdDSA 为 ed25519::SecretKey
+    /// payload: 载荷
+    /// external_aad: 外部附加数据，不出现在消息中，验证时必须提供相同的值
+    pub fn sign<S: Signer>(
+        alg: CoseAlg,
+        signer: &S,
+        payload: &[u8],
+        external_aad: &[u8],
+    ) -> Result<Self, CryptoError> {
+        let protected =
+            CborValue::Map(vec![(CborValue::int(HEADER_ALG), CborValue::int(alg.id()))])
+                .to_bytes()
+                .map_err(CryptoError::InvalidInput)?;
+        let signature = signer.sign(&sig_structure(&protected, external_aad, payload)?)?;
+        // 两种算法的签名都是64字节，用 ASN1 编码的 ECDSA 密钥会得到不符合 COSE 的签名
+        if signature.len() != 64 {
+            return Err(CryptoError::InvalidKey(
+                "cose: signer does not produce 64-byte signatures".to_string(),
+            ));
+        }
+        Ok(CoseSign1 {
+            protected,
+            unprotected: Vec::new(),
+            payload: Some(payload.to_vec()),
+            signature,
+        })
+    }
+
+    /// 受保护头部中的算法
+    pub fn alg(&self) -> Result<CoseAlg, CryptoError> {
+        header_value(&self.protected_header()?, HEADER_ALG)
+            .and_then(cbor_int)
+            .and_then(CoseAlg::from_id)
+            .ok_or_else(|| {
+                CryptoError::InvalidInput("cose: missing or unsupported alg".to_string())
+            })
+    }
+
+    /// 验证签名，返回载荷
+    ///
+    /// alg: 期望的算法，与受保护头部中的 alg 不一致时拒绝
+    /// pk: ES256 为65字节未压缩公钥，EdDSA 为32字节公钥
+    /// external_aad: 签名时使用的外部附加数据
+    pub fn verify(
+        &self,
+        alg: CoseAlg,
+        pk: &[u8],
+        external_aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let payload = self.payload.as_ref().ok_or_else(|| {
+            CryptoError::InvalidInput("cose: payload is detached, use verify_detached".to_string())
+        })?;
+        self.verify_detached(alg, pk, payload, external_aad)?;
+        Ok(payload.clone())
+    }
+
+    /// 验证载荷分离的签名
+    ///
+    /// payload: 通过其他途径传输的载荷
+    pub fn verify_detached(
+        &self,
+        alg: CoseAlg,
+        pk: &[u8],
+        payload: &[u8],
+        external_aad: &[u8],
+    ) -> Result<(), CryptoError> {
+        if self.alg()? != alg {
+            return Err(CryptoError::InvalidInput(format!(
+                "cose: alg is not {}",
+                alg.id()
+            )));
+        }
+        // 不认识的关键头部参数必须拒绝
+        if header_value(&self.protected_header()?, HEADER_CRIT).is_some() {
+            return Err(CryptoError::InvalidInput(
+                "cose: unsupported crit header".to_string(),
+            ));
+        }
+        let data = sig_structure(&self.protected, external_aad, payload)?;
+        match alg {
+            CoseAlg::ES256 => ecdsa_verify_detailed(
+                EcdsaAlg::ECDSA_P256_SHA256_FIXED,
+                pk,
+                &data,
+                &self.signature,
+            ),
+            CoseAlg::EdDSA => ed25519::verify_detailed(&data, pk, &self.signature),
+        }?;
+        Ok(())
+    }
+
+    /// 编码为带标签 18 的 COSE_Sign1_Tagged
+    pub fn to_bytes(&self) -> Result<Vec<u8>, CryptoError> {
+        CborValue::Tag(TAG_SIGN1, Box::new(self.to_cbor()))
+            .to_bytes()
+            .map_err(CryptoError::InvalidInput)
+    }
+
+    /// 解码，接受带标签 18 和不带标签的消息
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+        Self::from_cbor(CborValue::from_bytes(data).map_err(CryptoError::InvalidInput)?)
+    }
+
+    fn to_cbor(&self) -> CborValue {
+        CborValue::Array(vec![
+            CborValue::Bytes(self.protected.clone()),
+            CborValue::Map(self.unprotected.clone()),
+            match &self.payload {
+                Some(payload) => CborValue::Bytes(payload.clone()),
+                None => CborValue::Null,
+            },
+            CborValue::Bytes(self.signature.clone()),
+        ])
+    }
+
+    fn from_cbor(value: CborValue) -> Result<Self, CryptoError> {
+        let value = match value {
+            CborValue::Tag(TAG_SIGN1, inner) => *inner,
+            CborValue::Tag(..) => return Err(malformed()),
+            value => value,
+        };
+        let mut items = match value {
+            CborValue::Array(items) if items.len() == 4 => items.into_iter(),
+            _ => return Err(malformed()),
+        };
+        match (items.next(), items.next(), items.next(), items.next()) {
+            (
+                Some(CborValue::Bytes(protected)),
+                Some(CborValue::Map(unprotected)),
+                Some(payload),
+                Some(CborValue::Bytes(signature)),
+            ) => {
+                let payload = match payload {
+                    CborValue::Bytes(payload) => Some(payload),
+                    CborValue::Null => None,
+                    _ => return Err(malformed()),
+                };
+                Ok(CoseSign1 {
+                    protected,
+                    unprotected,
+                    payload,
+                    signature,
+                })
+            }
+            _ => Err(malformed()),
+        }
+    }
+
+    // 受保护头部为空字节串时等同于空 map
+    fn protected_header(&self) -> Result<Vec<(CborValue, CborValue)>, CryptoError> {
+        if self.protected.is_empty() {
+            return Ok(Vec::new());
+        }
+        match CborValue::from_bytes(&self.protected).map_err(CryptoError::InvalidInput)? {
+            CborValue::Map(entries) => Ok(entries),
+            _ => Err(malformed()),
+        }
+    }
+}
+
+/// 签发 CWT，返回带标签 61 和 18 的编码
+///
+/// alg: 签名算法
+/// signer: 签名者，要求同 CoseSign1::sign
+/// claims: 声明 map，如 iss (1)、exp (4)
+pub fn cwt_sign<S: Signer>(
+    alg: CoseAlg,
+    signer: &S,
+    claims: &CborValue,
+) -> Result<Vec<u8>, CryptoError> {
+    if !matches!(claims, CborValue::Map(_)) {
+        return Err(CryptoError::InvalidInput(
+            "cose: cwt claims must be a map".to_string(),
+        ));
+    }
+    let payload = claims.to_bytes().map_err(CryptoError::InvalidInput)?;
+    let msg = CoseSign1::sign(alg, signer, &payload, &[])?;
+    CborValue::Tag(
+        TAG_CWT,
+        Box::new(CborValue::Tag(TAG_SIGN1, Box::new(msg.to_cbor()))),
+    )
+    .to_bytes()
+    .map_err(CryptoError::InvalidInput)
+}
+
+/// 验证 CWT，返回声明 map，不检查 exp 等声明的值
+///
+/// token: CWT 编码，标签 61 可以省略
+/// alg: 期望的算法
+/// pk: 公钥
+pub fn cwt_verify(token: &[u8], alg: CoseAlg, pk: &[u8]) -> Result<CborValue, CryptoError> {
+    let value = match CborValue::from_bytes(token).map_err(CryptoError::InvalidInput)? {
+        CborValue::Tag(TAG_CWT, inner) => *inner,
+        value => value,
+    };
+    let payload = CoseSign1::from_cbor(value)?.verify(alg, pk, &[])?;
+    match CborValue::from_bytes(&payload).map_err(CryptoError::InvalidInput)? {
+        claims @ CborValue::Map(_) => Ok(claims),
+        _ => Err(CryptoError::InvalidInput(
+            "cose: cwt claims must be a map".to_string(),
+        )),
+    }
+}
+
+/// Sig_structure 的编码，即实际签名的数据
+fn sig_structure(
+    protected: &[u8],
+    external_aad: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    CborValue::Array(vec![
+        CborValue::Text("Signature1".to_string()),
+        CborValue::Bytes(protected.to_vec()),
+        CborValue::Bytes(external_aad.to_vec()),
+        CborValue::Bytes(payload.to_vec()),
+    ])
+    .to_bytes()
+    .map_err(CryptoError::InvalidInput)
+}
+
+fn header_value(header: &[(CborValue, CborValue)], label: i64) -> Option<&CborValue> {
+    let label = CborValue::int(label);
+    header.iter().find(|(k, _)| *k == label).map(|(_, v)| v)
+}
+
+fn cbor_int(value: &CborValue) -> Option<i64> {
+    match *value {
+        CborValue::Unsigned(n) if n <= i64::MAX as u64 => Some(n as i64),
+        CborValue::Negative(n) if n <= i64::MAX as u64 => Some(!(n as i64)),
+        _ => None,
+    }
+}
+
+fn malformed() -> CryptoError {
+    CryptoError::Verify(VerifyFailure::MalformedSignature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::base64;
+    use crate::signature::EcdsaKeyPair;
+    use hex::FromHex;
+
+    #[test]
+    fn test_cose() {
+        // RFC 8152 C.2.1，P-256 密钥 "11"
+        let pk = [
+            &[0x04][..],
+            &base64::decode_url("usWxHK2PmfnHKwXPS54m0kTcGJ90UiglWiGahtagnv8").unwrap(),
+            &base64::decode_url("IBOL-C3BttVivg-lSreASjpkttcsz-1rb7btKLv8EX4").unwrap(),
+        ]
+        .concat();
+        let data = Vec::from_hex("d28443a10126a10442313154546869732069732074686520636f6e74656e742e58408eb33e4ca31d1c465ab05aac34cc6b23d58fef5c083106c4d25a91aef0b0117e2af9a291aa32e14ab834dc56ed2a223444547e01f11d3b0916e5a4c345cacb36").unwrap();
+        let msg = CoseSign1::from_bytes(&data).unwrap();
+        assert_eq!(msg.alg().unwrap(), CoseAlg::ES256);
+        assert_eq!(
+            msg.verify(CoseAlg::ES256, &pk, &[]).unwrap(),
+            b"This is the content."
+        );
+        assert_eq!(msg.to_bytes().unwrap(), data);
+        assert!(msg.verify(CoseAlg::ES256, &pk, b"aad").is_err());
+        assert!(msg.verify(CoseAlg::EdDSA, &pk, &[]).is_err());
+
+        let d = base64::decode_url("V8kgd2ZBRuh2dgyVINBUqpPDr7BOMGcF22CQMIUHtNM").unwrap();
+        let key = EcdsaKeyPair::from_private_key_and_public_key(
+            EcdsaAlg::ECDSA_P256_SHA256_FIXED,
+            &d,
+            &pk,
+        )
+        .unwrap();
+        let msg = CoseSign1::sign(CoseAlg::ES256, &key, b"payload", b"aad").unwrap();
+        let decoded = CoseSign1::from_bytes(&msg.to_bytes().unwrap()).unwrap();
+        assert_eq!(
+            decoded.verify(CoseAlg::ES256, &pk, b"aad").unwrap(),
+            b"payload"
+        );
+        let detached = CoseSign1 {
+            payload: None,
+            ..decoded
+        };
+        assert!(detached.verify(CoseAlg::ES256, &pk, b"aad").is_err());
+        assert!(detached
+            .verify_detached(CoseAlg::ES256, &pk, b"payload", b"aad")
+            .is_ok());
+        let asn1 = EcdsaKeyPair::from_private_key_and_public_key(
+            EcdsaAlg::ECDSA_P256_SHA256_ASN1,
+            &d,
+            &pk,
+        )
+        .unwrap();
+        assert!(CoseSign1::sign(CoseAlg::ES256, &asn1, b"payload", &[]).is_err());
+
+        // CWT，RFC 8392 的声明 iss (1) 和 exp (4)
+        let sk = ed25519::SecretKey::from_seed(&[9u8; 32]).unwrap();
+        let claims = CborValue::Map(vec![
+            (
+                CborValue::int(1),
+                CborValue::Text("coap://as.example.com".to_string()),
+            ),
+            (CborValue::int(4), CborValue::int(1444064944)),
+        ]);
+        let token = cwt_sign(CoseAlg::EdDSA, &sk, &claims).unwrap();
+        assert_eq!(&token[..3], &[0xd8, 0x3d, 0xd2]);
+        assert_eq!(
+            cwt_verify(&token, CoseAlg::EdDSA, &sk.public_key()).unwrap(),
+            claims
+        );
+        assert!(cwt_verify(&token[2..], CoseAlg::EdDSA, &sk.public_key()).is_ok());
+        assert!(cwt_verify(&token, CoseAlg::ES256, &pk).is_err());
+        let mut forged = token.clone();
+        let last = forged.len() - 1;
+        forged[last] ^= 1;
+        assert!(cwt_verify(&forged, CoseAlg::EdDSA, &sk.public_key()).is_err());
+    }
+}
@@ -0,0 +1,196 @@
+//! 常量时间比较
+//!
+//! 比较密钥、签名、认证码等秘密数据时，比较耗时不能依赖于数据内容，否则会产生计时侧信道。
+//! 选择和查表函数同样不根据秘密数据分支或访问不同的内存位置
+
+use std::cmp::Ordering;
+use std::sync::atomic::{compiler_fence, Ordering as AtomicOrdering};
+
+/// 常量时间相等比较
+pub trait ConstantTimeEq {
+    /// 比较耗时只与数据长度有关，与数据内容无关
+    fn ct_eq(&self, other: &Self) -> bool;
+}
+
+impl ConstantTimeEq for [u8] {
+    fn ct_eq(&self, other: &Self) -> bool {
+        ct_eq(self, other)
+    }
+}
+
+impl<T: ConstantTimeEq + ?Sized> ConstantTimeEq for &T {
+    fn ct_eq(&self, other: &Self) -> bool {
+        (**self).ct_eq(*other)
+    }
+}
+
+impl ConstantTimeEq for Vec<u8> {
+    fn ct_eq(&self, other: &Self) -> bool {
+        ct_eq(self, other)
+    }
+}
+
+impl<T: ConstantTimeEq> ConstantTimeEq for Option<T> {
+    fn ct_eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.ct_eq(b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// 常量时间比较两段数据是否相等
+///
+/// 长度不同时直接返回false，长度本身不视为秘密
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a
+        .iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y));
+    // 阻止编译器把累积的比较结果优化为提前返回
+    unsafe { std::ptr::read_volatile(&diff) == 0 }
+}
+
+/// 常量时间比较两段等长数据作为大端整数的大小
+///
+/// 长度不同时按长度比较
+pub fn ct_cmp(a: &[u8], b: &[u8]) -> Ordering {
+    if a.len() != b.len() {
+        return a.len().cmp(&b.len());
+    }
+    // gt/lt 记录第一个不相等字节处的比较结果，之后的字节不再改变它们
+    let (mut gt, mut lt) = (0u8, 0u8);
+    for (x, y) in a.iter().zip(b.iter()) {
+        let (x, y) = (u16::from(*x), u16::from(*y));
+        let x_gt = (y.wrapping_sub(x) >> 8) as u8 & 1;
+        let x_lt = (x.wrapping_sub(y) >> 8) as u8 & 1;
+        let undecided = !(gt | lt) & 1;
+        gt |= x_gt & undecided;
+        lt |= x_lt & undecided;
+    }
+    let (gt, lt) = unsafe { (std::ptr::read_volatile(&gt), std::ptr::read_volatile(&lt)) };
+    if gt == 1 {
+        Ordering::Greater
+    } else if lt == 1 {
+        Ordering::Less
+    } else {
+        Ordering::Equal
+    }
+}
+
+/// 条件为真时为 0xff，否则为 0
+pub fn ct_mask(cond: bool) -> u8 {
+    (cond as u8).wrapping_neg()
+}
+
+/// 常量时间选择，choice 为真时返回 a，否则返回 b
+pub fn ct_select(choice: bool, a: u8, b: u8) -> u8 {
+    let mask = ct_mask(choice);
+    (a & mask) | (b & !mask)
+}
+
+/// 字节为0时为 0xff，否则为 0，不经过比较运算
+pub fn ct_is_zero(x: u8) -> u8 {
+    ((x as u16).wrapping_sub(1) >> 8) as u8
+}
+
+/// a < b 时为全1掩码，否则为 0，不经过比较运算
+pub fn ct_lt_usize(a: usize, b: usize) -> usize {
+    // a - b 借位的最高位 (Hacker's Delight 2-12)
+    let borrow = (!a & b) | (!(a ^ b) & a.wrapping_sub(b));
+    (borrow >> (usize::BITS - 1)).wrapping_neg()
+}
+
+/// 按掩码常量时间选择，mask 为全1时返回 a，为0时返回 b
+pub fn ct_select_usize(mask: usize, a: usize, b: usize) -> usize {
+    (a & mask) | (b & !mask)
+}
+
+/// 常量时间条件复制，choice 为真时把 src 复制到 dst，否则 dst 不变
+///
+/// dst 和 src 的长度必须相同
+pub fn ct_assign(choice: bool, dst: &mut [u8], src: &[u8]) {
+    assert_eq!(dst.len(), src.len(), "ct: length mismatch");
+    let mask = ct_mask(choice);
+    for (d, s) in dst.iter_mut().zip(src.iter()) {
+        *d ^= (*d ^ s) & mask;
+    }
+}
+
+/// 常量时间查表，读取表中的每一项，只保留第 index 项
+///
+/// index 超出范围时返回全0
+pub fn ct_lookup<const N: usize>(table: &[[u8; N]], index: usize) -> [u8; N] {
+    let mut out = [0u8; N];
+    for (i, entry) in table.iter().enumerate() {
+        // i ^ index 为0时 diff 为0，不用比较运算避免分支
+        let diff = i ^ index;
+        let hit = ((diff | diff.wrapping_neg()) >> (usize::BITS - 1)) ^ 1;
+        ct_assign(hit == 1, &mut out, entry);
+    }
+    out
+}
+
+/// 擦除内存中的秘密数据，写入不会被编译器优化掉
+///
+/// 开启 zeroize feature 时改用 zeroize 库实现
+pub fn wipe(buf: &mut [u8]) {
+    #[cfg(feature = "zeroize")]
+    zeroize::Zeroize::zeroize(buf);
+    #[cfg(not(feature = "zeroize"))]
+    for b in buf.iter_mut() {
+        unsafe { std::ptr::write_volatile(b, 0) };
+    }
+    compiler_fence(AtomicOrdering::SeqCst);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ct_eq_cmp() {
+        assert!(ct_eq(b"secret", b"secret"));
+        assert!(!ct_eq(b"secret", b"secreT"));
+        assert!(!ct_eq(b"secret", b"secrets"));
+        assert!(b"abc"[..].ct_eq(&b"abc"[..]));
+
+        assert_eq!(ct_cmp(&[1, 2, 3], &[1, 2, 3]), Ordering::Equal);
+        assert_eq!(ct_cmp(&[1, 3, 0], &[1, 2, 255]), Ordering::Greater);
+        assert_eq!(ct_cmp(&[0, 255, 255], &[1, 0, 0]), Ordering::Less);
+        assert_eq!(ct_cmp(&[9], &[0, 0]), Ordering::Less);
+    }
+
+    #[test]
+    fn test_ct_select_lookup() {
+        assert_eq!(ct_select(true, 0x12, 0x34), 0x12);
+        assert_eq!(ct_select(false, 0x12, 0x34), 0x34);
+        assert_eq!(ct_is_zero(0), 0xff);
+        assert_eq!(ct_is_zero(1), 0);
+        assert_eq!(ct_is_zero(0xff), 0);
+        assert_eq!(ct_lt_usize(3, 4), usize::MAX);
+        assert_eq!(ct_lt_usize(4, 4), 0);
+        assert_eq!(ct_lt_usize(usize::MAX, 0), 0);
+        assert_eq!(ct_lt_usize(0, usize::MAX), usize::MAX);
+        assert_eq!(ct_select_usize(usize::MAX, 5, 9), 5);
+        assert_eq!(ct_select_usize(0, 5, 9), 9);
+
+        let mut dst = [1u8, 2, 3];
+        ct_assign(false, &mut dst, &[7, 8, 9]);
+        assert_eq!(dst, [1, 2, 3]);
+        ct_assign(true, &mut dst, &[7, 8, 9]);
+        assert_eq!(dst, [7, 8, 9]);
+
+        let table = [[0u8, 1], [2, 3], [4, 5]];
+        assert_eq!(ct_lookup(&table, 0), [0, 1]);
+        assert_eq!(ct_lookup(&table, 2), [4, 5]);
+        assert_eq!(ct_lookup(&table, 3), [0, 0]);
+
+        wipe(&mut dst);
+        assert_eq!(dst, [0, 0, 0]);
+    }
+}
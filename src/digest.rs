@@ -1,14 +1,47 @@
 //! 常用的哈希算法
+//!
+//! MD5 和 SHA1 已不再安全，只为与旧协议互通而保留，由默认开启的 legacy-hashes 特性提供，
+//! 使用时会有弃用警告，不需要时可以关闭默认特性
+//!
+//! SHA-3 使用 FIPS 202 的填充(0x06)，Keccak-256 使用以太坊采用的原始 Keccak 填充(0x01)，两者结果不同
+//!
+//! 大文件用 Hasher 流式计算，Hasher 和 DigestWriter 实现了 io::Write，可以配合 io::copy 使用
+//!
+//! 大量相互独立的短消息用 sha256_many 批量计算，运行时按 CPU 特性选择 SHA-NI 或 AVX2 实现
 
-use crypto::digest::Digest;
+use std::io;
+
+use crypto::digest::Digest as _;
+#[cfg(feature = "legacy-hashes")]
 use crypto::md5::Md5;
+use crypto::ripemd160::Ripemd160;
+use crypto::sha3::Sha3;
 use ring::digest as rdigest;
 
+use crate::metrics::{Operation, Timer};
+
+pub mod blake2;
+pub mod blake3;
+pub mod checksum;
+pub mod fast;
+pub mod multihash;
+mod sha256_mb;
+pub mod sm3;
+
+pub use blake2::{blake2b, blake2s};
+pub use blake3::{blake3, blake3_derive_key, blake3_keyed};
+pub use multihash::{decode_multihash, multihash, verify_multihash};
+pub use sha256_mb::{sha256_backend, sha256_many, Sha256Backend};
+
 /// SHA哈希算法类型
 pub enum DigestAlgorithm {
     /// md5 哈希算法
+    #[cfg(feature = "legacy-hashes")]
+    #[deprecated(note = "MD5 is broken, use only to interoperate with legacy protocols")]
     MD5,
     /// SHA1 哈希算法
+    #[cfg(feature = "legacy-hashes")]
+    #[deprecated(note = "SHA1 is broken, use only to interoperate with legacy protocols")]
     SHA1,
     /// SHA256 哈希算法
     SHA256,
@@ -16,11 +49,38 @@ pub enum DigestAlgorithm {
     SHA384,
     /// SHA512 哈希算法
     SHA512,
+    /// 国密 SM3 哈希算法
+    SM3,
+    /// SHA3-256 哈希算法 (FIPS 202)
+    SHA3_256,
+    /// SHA3-384 哈希算法 (FIPS 202)
+    SHA3_384,
+    /// SHA3-512 哈希算法 (FIPS 202)
+    SHA3_512,
+    /// 以太坊使用的 Keccak-256，不是标准化后的 SHA3-256
+    KECCAK256,
 }
 
 /// 计算二进制数据的SHA哈希
+#[allow(deprecated)]
 pub fn digest(alg: DigestAlgorithm, data: &[u8]) -> Vec<u8> {
-    match alg {
+    let name = match alg {
+        #[cfg(feature = "legacy-hashes")]
+        DigestAlgorithm::MD5 => "MD5",
+        #[cfg(feature = "legacy-hashes")]
+        DigestAlgorithm::SHA1 => "SHA1",
+        DigestAlgorithm::SHA256 => "SHA256",
+        DigestAlgorithm::SHA384 => "SHA384",
+        DigestAlgorithm::SHA512 => "SHA512",
+        DigestAlgorithm::SM3 => "SM3",
+        DigestAlgorithm::SHA3_256 => "SHA3-256",
+        DigestAlgorithm::SHA3_384 => "SHA3-384",
+        DigestAlgorithm::SHA3_512 => "SHA3-512",
+        DigestAlgorithm::KECCAK256 => "KECCAK256",
+    };
+    let timer = Timer::start(Operation::Digest, name);
+    let out = match alg {
+        #[cfg(feature = "legacy-hashes")]
         DigestAlgorithm::MD5 => {
             let mut md5 = Md5::new();
             md5.input(data);
@@ -28,27 +88,414 @@ pub fn digest(alg: DigestAlgorithm, data: &[u8]) -> Vec<u8> {
             md5.result(&mut out);
             out
         }
+        #[cfg(feature = "legacy-hashes")]
         DigestAlgorithm::SHA1 => rdigest::digest(&rdigest::SHA1_FOR_LEGACY_USE_ONLY, data)
             .as_ref()
             .to_vec(),
         DigestAlgorithm::SHA256 => rdigest::digest(&rdigest::SHA256, data).as_ref().to_vec(),
         DigestAlgorithm::SHA384 => rdigest::digest(&rdigest::SHA384, data).as_ref().to_vec(),
         DigestAlgorithm::SHA512 => rdigest::digest(&rdigest::SHA512, data).as_ref().to_vec(),
+        DigestAlgorithm::SM3 => sm3::sm3(data).to_vec(),
+        DigestAlgorithm::SHA3_256 => sha3(Sha3::sha3_256(), data),
+        DigestAlgorithm::SHA3_384 => sha3(Sha3::sha3_384(), data),
+        DigestAlgorithm::SHA3_512 => sha3(Sha3::sha3_512(), data),
+        DigestAlgorithm::KECCAK256 => sha3(Sha3::keccak256(), data),
+    };
+    timer.done(out, true)
+}
+
+/// 计算以太坊使用的 Keccak-256 哈希
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&sha3(Sha3::keccak256(), data));
+    out
+}
+
+fn sha3(mut hasher: Sha3, data: &[u8]) -> Vec<u8> {
+    hasher.input(data);
+    let mut out = vec![0u8; hasher.output_bytes()];
+    hasher.result(&mut out);
+    out
+}
+
+/// 流式哈希
+pub trait Digest {
+    /// 追加数据
+    fn update(&mut self, data: &[u8]);
+
+    /// 输出已追加数据的哈希，并恢复到初始状态
+    fn finalize(&mut self) -> Vec<u8>;
+
+    /// 丢弃已追加的数据，恢复到初始状态
+    fn reset(&mut self);
+
+    /// 哈希的字节长度
+    fn output_len(&self) -> usize;
+}
+
+/// DigestAlgorithm 中各算法的流式实现
+#[derive(Clone)]
+pub struct Hasher {
+    state: HasherState,
+}
+
+#[derive(Clone)]
+enum HasherState {
+    Ring(rdigest::Context),
+    #[cfg(feature = "legacy-hashes")]
+    Md5(Md5),
+    Sm3(sm3::Sm3),
+    Sha3(Sha3),
+}
+
+impl Hasher {
+    /// 构建指定算法的流式哈希
+    #[allow(deprecated)]
+    pub fn new(alg: DigestAlgorithm) -> Self {
+        let state = match alg {
+            #[cfg(feature = "legacy-hashes")]
+            DigestAlgorithm::MD5 => HasherState::Md5(Md5::new()),
+            #[cfg(feature = "legacy-hashes")]
+            DigestAlgorithm::SHA1 => {
+                HasherState::Ring(rdigest::Context::new(&rdigest::SHA1_FOR_LEGACY_USE_ONLY))
+            }
+            DigestAlgorithm::SHA256 => HasherState::Ring(rdigest::Context::new(&rdigest::SHA256)),
+            DigestAlgorithm::SHA384 => HasherState::Ring(rdigest::Context::new(&rdigest::SHA384)),
+            DigestAlgorithm::SHA512 => HasherState::Ring(rdigest::Context::new(&rdigest::SHA512)),
+            DigestAlgorithm::SM3 => HasherState::Sm3(sm3::Sm3::new()),
+            DigestAlgorithm::SHA3_256 => HasherState::Sha3(Sha3::sha3_256()),
+            DigestAlgorithm::SHA3_384 => HasherState::Sha3(Sha3::sha3_384()),
+            DigestAlgorithm::SHA3_512 => HasherState::Sha3(Sha3::sha3_512()),
+            DigestAlgorithm::KECCAK256 => HasherState::Sha3(Sha3::keccak256()),
+        };
+        Hasher { state }
+    }
+}
+
+impl Digest for Hasher {
+    fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HasherState::Ring(ctx) => ctx.update(data),
+            #[cfg(feature = "legacy-hashes")]
+            HasherState::Md5(md5) => md5.input(data),
+            HasherState::Sm3(sm3) => sm3.update(data),
+            HasherState::Sha3(sha3) => sha3.input(data),
+        }
+    }
+
+    fn finalize(&mut self) -> Vec<u8> {
+        let out = match &mut self.state {
+            HasherState::Ring(ctx) => ctx.clone().finish().as_ref().to_vec(),
+            #[cfg(feature = "legacy-hashes")]
+            HasherState::Md5(md5) => {
+                let mut out = vec![0u8; 16];
+                md5.result(&mut out);
+                out
+            }
+            HasherState::Sm3(sm3) => sm3.clone().finalize().to_vec(),
+            HasherState::Sha3(sha3) => {
+                let mut out = vec![0u8; sha3.output_bytes()];
+                sha3.result(&mut out);
+                out
+            }
+        };
+        self.reset();
+        out
+    }
+
+    fn reset(&mut self) {
+        match &mut self.state {
+            HasherState::Ring(ctx) => *ctx = rdigest::Context::new(ctx.algorithm()),
+            #[cfg(feature = "legacy-hashes")]
+            HasherState::Md5(md5) => md5.reset(),
+            HasherState::Sm3(sm3) => *sm3 = sm3::Sm3::new(),
+            HasherState::Sha3(sha3) => sha3.reset(),
+        }
+    }
+
+    fn output_len(&self) -> usize {
+        match &self.state {
+            HasherState::Ring(ctx) => ctx.algorithm().output_len,
+            #[cfg(feature = "legacy-hashes")]
+            HasherState::Md5(_) => 16,
+            HasherState::Sm3(_) => sm3::OUTPUT_SIZE,
+            HasherState::Sha3(sha3) => sha3.output_bytes(),
+        }
+    }
+}
+
+impl io::Write for Hasher {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
+/// 写入 inner 的同时计算哈希，用于边保存边校验的场合
+pub struct DigestWriter<W, D> {
+    inner: W,
+    digest: D,
+}
+
+impl<W: io::Write, D: Digest> DigestWriter<W, D> {
+    /// inner: 实际写入的目标，digest: 哈希算法
+    pub fn new(inner: W, digest: D) -> Self {
+        DigestWriter { inner, digest }
+    }
+
+    /// 返回 inner 和已写入数据的哈希
+    pub fn finish(mut self) -> (W, Vec<u8>) {
+        let out = self.digest.finalize();
+        (self.inner, out)
+    }
+}
+
+impl<W: io::Write, D: Digest> io::Write for DigestWriter<W, D> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 只对 inner 实际接受的部分计算哈希
+        let n = self.inner.write(buf)?;
+        self.digest.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// 计算 RIPEMD-160 哈希，只用于比特币地址等需要它的场合
+pub fn ripemd160(data: &[u8]) -> [u8; 20] {
+    let mut h = Ripemd160::new();
+    h.input(data);
+    let mut out = [0u8; 20];
+    h.result(&mut out);
+    out
+}
+
+/// 比特币的 HASH160，RIPEMD160(SHA256(data))，用于由公钥或脚本生成地址
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    ripemd160(rdigest::digest(&rdigest::SHA256, data).as_ref())
+}
+
+/// 比特币的双重 SHA256，SHA256(SHA256(data))，用于交易ID、区块头等
+pub fn sha256d(data: &[u8]) -> [u8; 32] {
+    let inner = rdigest::digest(&rdigest::SHA256, data);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(rdigest::digest(&rdigest::SHA256, inner.as_ref()).as_ref());
+    out
+}
+
+/// Base58Check 等使用的4字节校验码，sha256d(data) 的前4字节
+pub fn checksum4(data: &[u8]) -> [u8; 4] {
+    let mut out = [0u8; 4];
+    out.copy_from_slice(&sha256d(data)[..4]);
+    out
+}
+
+/// BIP-340 带标签的哈希，SHA256(SHA256(tag) | SHA256(tag) | data)
+///
+/// 不同用途使用不同的标签，使一个协议中的哈希值不会被另一个协议接受，Schnorr、Taproot 等都依赖它
+///
+/// tag: 标签，如 "BIP0340/challenge"
+/// data: 数据
+pub fn tagged_hash(tag: &[u8], data: &[u8]) -> [u8; 32] {
+    let tag_hash = rdigest::digest(&rdigest::SHA256, tag);
+    let mut ctx = rdigest::Context::new(&rdigest::SHA256);
+    ctx.update(tag_hash.as_ref());
+    ctx.update(tag_hash.as_ref());
+    ctx.update(data);
+    let mut out = [0u8; 32];
+    out.copy_from_slice(ctx.finish().as_ref());
+    out
+}
+
+/// RFC 9380 5.3.1 的 expand_message_xmd，hash_to_curve 各套件用它把消息扩展为均匀的字节串，
+/// 完整的 BLS12-381 哈希到曲线见 bls12381 模块的 hash_to_g1 / hash_to_g2
+///
+/// alg: SHA256、SHA384 或 SHA512，如 BLS12-381 的 G2 套件使用 SHA256
+/// msg: 消息
+/// dst: 域分离标签，1..=255 字节，如 "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_"
+/// len: 输出长度，不超过 255 倍哈希长度且不超过 65535
+pub fn expand_message_xmd(
+    alg: DigestAlgorithm,
+    msg: &[u8],
+    dst: &[u8],
+    len: usize,
+) -> Result<Vec<u8>, String> {
+    let alg = match alg {
+        DigestAlgorithm::SHA256 => &rdigest::SHA256,
+        DigestAlgorithm::SHA384 => &rdigest::SHA384,
+        DigestAlgorithm::SHA512 => &rdigest::SHA512,
+        _ => return Err("digest: expand_message_xmd needs SHA-2".to_string()),
+    };
+    if dst.is_empty() || dst.len() > 255 {
+        return Err("digest: dst must be 1 to 255 bytes".to_string());
+    }
+    let ell = len.div_ceil(alg.output_len);
+    if ell > 255 || len > 0xffff {
+        return Err("digest: expand_message_xmd output too long".to_string());
+    }
+    let dst_prime = [dst, &[dst.len() as u8]].concat();
+    let mut ctx = rdigest::Context::new(alg);
+    ctx.update(&vec![0u8; alg.block_len]);
+    ctx.update(msg);
+    ctx.update(&(len as u16).to_be_bytes());
+    ctx.update(&[0]);
+    ctx.update(&dst_prime);
+    let b0 = ctx.finish();
+    let mut out = Vec::with_capacity(ell * alg.output_len);
+    let mut prev = vec![0u8; alg.output_len];
+    for i in 1..=ell {
+        // b_1 = H(b_0 | 1 | dst')，b_i = H((b_0 ^ b_(i-1)) | i | dst')
+        let mut ctx = rdigest::Context::new(alg);
+        let mixed: Vec<u8> = b0.as_ref().iter().zip(&prev).map(|(a, b)| a ^ b).collect();
+        ctx.update(&mixed);
+        ctx.update(&[i as u8]);
+        ctx.update(&dst_prime);
+        prev = ctx.finish().as_ref().to_vec();
+        out.extend_from_slice(&prev);
+    }
+    out.truncate(len);
+    Ok(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use hex::FromHex;
 
     #[test]
+    fn test_sha256() {
+        let computed = digest(DigestAlgorithm::SHA256, b"abc");
+        let expected =
+            Vec::from_hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+                .unwrap();
+        assert_eq!(computed, expected);
+
+        // RFC 9380 K.1
+        let dst = b"QUUX-V01-CS02-with-expander-SHA256-128";
+        for (msg, len, expected) in [
+            (&b""[..], 0x20, "68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235"),
+            (&b"abc"[..], 0x20, "d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615"),
+            (&b""[..], 0x80, "af84c27ccfd45d41914fdff5df25293e221afc53d8ad2ac06d5e3e29485dadbee0d121587713a3e0dd4d5e69e93eb7cd4f5df4cd103e188cf60cb02edc3edf18eda8576c412b18ffb658e3dd6ec849469b979d444cf7b26911a08e63cf31f9dcc541708d3491184472c2c29bb749d4286b004ceb5ee6b9a7fa5b646c993f0ced"),
+        ] {
+            let computed = expand_message_xmd(DigestAlgorithm::SHA256, msg, dst, len).unwrap();
+            assert_eq!(computed, Vec::from_hex(expected).unwrap());
+        }
+        assert!(expand_message_xmd(DigestAlgorithm::SHA256, b"", b"", 32).is_err());
+        assert!(expand_message_xmd(DigestAlgorithm::SHA256, b"", dst, 256 * 32).is_err());
+
+        let computed = tagged_hash(b"TapLeaf", b"abc");
+        let expected =
+            Vec::from_hex("83a56308a9c56f467e8df293da5ae5fdbc85b871952a83c4bf0575ee948ec230")
+                .unwrap();
+        assert_eq!(computed.to_vec(), expected);
+
+        let computed = digest(DigestAlgorithm::SM3, b"abc");
+        let expected =
+            Vec::from_hex("66c7f0f462eeedd9d1f2d46bdc10e4e24167c4875cf2f7a2297da02b8f4ba8e0")
+                .unwrap();
+        assert_eq!(computed, expected);
+    }
+
+    #[test]
+    fn test_bitcoin_hashes() {
+        // test vectors from: https://homes.esat.kuleuven.be/~bosselae/ripemd160.html
+        assert_eq!(
+            ripemd160(b"abc").to_vec(),
+            Vec::from_hex("8eb208f7e05d987a9b044a8e98c6b087f15a0bfc").unwrap()
+        );
+        // 比特币创世区块头的哈希(字节序与区块浏览器显示的相反)
+        let header = Vec::from_hex("0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c").unwrap();
+        let mut id = sha256d(&header);
+        id.reverse();
+        assert_eq!(
+            id.to_vec(),
+            Vec::from_hex("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f")
+                .unwrap()
+        );
+        // 地址 1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH 的版本字节和 HASH160
+        let payload = Vec::from_hex("00751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        assert_eq!(checksum4(&payload), [0x51, 0x0d, 0x16, 0x34]);
+        // 私钥为1的压缩公钥
+        let pk =
+            Vec::from_hex("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        assert_eq!(
+            hash160(&pk).to_vec(),
+            Vec::from_hex("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_sha3() {
+        // test vectors from: NIST FIPS 202 examples, and the Keccak-256 of "" used by Ethereum
+        let computed = digest(DigestAlgorithm::SHA3_256, b"abc");
+        let expected =
+            Vec::from_hex("3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532")
+                .unwrap();
+        assert_eq!(computed, expected);
+
+        let computed = digest(DigestAlgorithm::SHA3_512, b"abc");
+        let expected = Vec::from_hex("b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0").unwrap();
+        assert_eq!(computed, expected);
+
+        let expected =
+            Vec::from_hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+                .unwrap();
+        assert_eq!(digest(DigestAlgorithm::KECCAK256, b""), expected);
+        assert_eq!(keccak256(b"").to_vec(), expected);
+        assert_ne!(digest(DigestAlgorithm::SHA3_256, b""), expected);
+    }
+
+    #[test]
+    fn test_hasher() {
+        let data = vec![0x5au8; 1000];
+        for alg in [
+            || DigestAlgorithm::SHA256,
+            || DigestAlgorithm::SHA384,
+            || DigestAlgorithm::SHA512,
+            || DigestAlgorithm::SM3,
+            || DigestAlgorithm::SHA3_256,
+            || DigestAlgorithm::KECCAK256,
+        ] {
+            let mut hasher = Hasher::new(alg());
+            io::copy(&mut &data[..], &mut hasher).unwrap();
+            let expected = digest(alg(), &data);
+            assert_eq!(hasher.output_len(), expected.len());
+            assert_eq!(hasher.finalize(), expected);
+            // finalize 之后恢复到初始状态
+            for chunk in data.chunks(7) {
+                hasher.update(chunk);
+            }
+            assert_eq!(hasher.finalize(), expected);
+            hasher.update(b"discarded");
+            hasher.reset();
+            assert_eq!(hasher.finalize(), digest(alg(), b""));
+        }
+
+        let mut writer = DigestWriter::new(Vec::new(), Hasher::new(DigestAlgorithm::SHA256));
+        io::Write::write_all(&mut writer, b"abc").unwrap();
+        let (written, hash) = writer.finish();
+        assert_eq!(written, b"abc");
+        assert_eq!(hash, digest(DigestAlgorithm::SHA256, b"abc"));
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-hashes")]
+    #[allow(deprecated)]
     fn test_sha1() {
         let computed = digest(DigestAlgorithm::SHA1, b"abc");
         let expected = Vec::from_hex("a9993e364706816aba3e25717850c26c9cd0d89d").unwrap();
         assert_eq!(computed, expected);
     }
     #[test]
+    #[cfg(feature = "legacy-hashes")]
+    #[allow(deprecated)]
     fn test_md5() {
         let computed = digest(DigestAlgorithm::MD5, b"abc");
         let expected = Vec::from_hex("900150983CD24FB0D6963F7D28E17F72").unwrap();
@@ -0,0 +1,154 @@
+//! BLAKE2b 和 BLAKE2s 哈希算法 (RFC 7693)
+//!
+//! 输出长度可配置，带密钥时即为MAC，新协议中可以代替HMAC。BLAKE2b 适合64位平台，BLAKE2s 适合32位和嵌入式平台
+
+use crypto::blake2b::Blake2b as CryptoBlake2b;
+use crypto::blake2s::Blake2s as CryptoBlake2s;
+use crypto::digest::Digest as _;
+
+use super::Digest;
+use crate::error::CryptoError;
+
+/// BLAKE2b 最大输出和密钥长度
+pub const BLAKE2B_MAX_LEN: usize = 64;
+/// BLAKE2s 最大输出和密钥长度
+pub const BLAKE2S_MAX_LEN: usize = 32;
+
+/// 流式 BLAKE2b 哈希
+#[derive(Clone)]
+pub struct Blake2b(CryptoBlake2b);
+
+/// 流式 BLAKE2s 哈希
+#[derive(Clone)]
+pub struct Blake2s(CryptoBlake2s);
+
+impl Blake2b {
+    /// out_len: 输出长度，1~64字节
+    /// key: 密钥，最长64字节，为空时不使用密钥
+    pub fn new(out_len: usize, key: &[u8]) -> Result<Self, CryptoError> {
+        check_params("BLAKE2b", out_len, key, BLAKE2B_MAX_LEN)?;
+        Ok(Blake2b(if key.is_empty() {
+            CryptoBlake2b::new(out_len)
+        } else {
+            CryptoBlake2b::new_keyed(out_len, key)
+        }))
+    }
+}
+
+impl Blake2s {
+    /// out_len: 输出长度，1~32字节
+    /// key: 密钥，最长32字节，为空时不使用密钥
+    pub fn new(out_len: usize, key: &[u8]) -> Result<Self, CryptoError> {
+        check_params("BLAKE2s", out_len, key, BLAKE2S_MAX_LEN)?;
+        Ok(Blake2s(if key.is_empty() {
+            CryptoBlake2s::new(out_len)
+        } else {
+            CryptoBlake2s::new_keyed(out_len, key)
+        }))
+    }
+}
+
+macro_rules! impl_digest {
+    ($($name:ident),*) => {$(
+        impl Digest for $name {
+            fn update(&mut self, data: &[u8]) {
+                self.0.input(data);
+            }
+
+            fn finalize(&mut self) -> Vec<u8> {
+                let mut out = vec![0u8; self.0.output_bytes()];
+                self.0.result(&mut out);
+                self.0.reset();
+                out
+            }
+
+            fn reset(&mut self) {
+                self.0.reset();
+            }
+
+            fn output_len(&self) -> usize {
+                self.0.output_bytes()
+            }
+        }
+    )*};
+}
+
+impl_digest!(Blake2b, Blake2s);
+
+/// 计算 BLAKE2b 哈希，key 不为空时为 BLAKE2b-MAC
+///
+/// out_len: 输出长度，1~64字节，key: 密钥，最长64字节，data: 数据
+pub fn blake2b(out_len: usize, key: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut h = Blake2b::new(out_len, key)?;
+    h.update(data);
+    Ok(h.finalize())
+}
+
+/// 计算 BLAKE2s 哈希，key 不为空时为 BLAKE2s-MAC
+///
+/// out_len: 输出长度，1~32字节，key: 密钥，最长32字节，data: 数据
+pub fn blake2s(out_len: usize, key: &[u8], data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut h = Blake2s::new(out_len, key)?;
+    h.update(data);
+    Ok(h.finalize())
+}
+
+fn check_params(name: &str, out_len: usize, key: &[u8], max: usize) -> Result<(), CryptoError> {
+    if out_len == 0 || out_len > max {
+        return Err(CryptoError::InvalidInput(format!(
+            "digest: {} output must be 1 to {} bytes",
+            name, max
+        )));
+    }
+    if key.len() > max {
+        return Err(CryptoError::InvalidKey(format!(
+            "digest: {} key must be at most {} bytes",
+            name, max
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_blake2() {
+        // test vectors from: RFC 7693 Appendix A/B, and the keyed KAT of the BLAKE2 reference implementation
+        assert_eq!(
+            blake2b(64, b"", b"abc").unwrap(),
+            Vec::from_hex("ba80a53f981c4d0d6a2797b69f12f6e94c212f14685ac4b74b12bb6fdbffa2d17d87c5392aab792dc252d5de4533cc9518d38aa8dbf1925ab92386edd4009923").unwrap()
+        );
+        assert_eq!(
+            blake2s(32, b"", b"abc").unwrap(),
+            Vec::from_hex("508c5e8c327c14e2e1a72ba34eeb452f37458b209ed63a294d999b4c86675982")
+                .unwrap()
+        );
+        let key: Vec<u8> = (0..64).collect();
+        assert_eq!(
+            blake2b(64, &key, b"").unwrap(),
+            Vec::from_hex("10ebb67700b1868efb4417987acf4690ae9d972fb7a590c2f02871799aaa4786b5e996e8f0f4eb981fc214b005f42d2ff4233499391653df7aefcbc13fc51568").unwrap()
+        );
+
+        // 生成自 Python hashlib
+        let mut h = Blake2s::new(16, &key[..32]).unwrap();
+        h.update(b"a");
+        h.update(b"bc");
+        let expected = Vec::from_hex("61ba5f165c194692e09d12520cc4c74a").unwrap();
+        assert_eq!(h.finalize(), expected);
+        // finalize 之后恢复到带密钥的初始状态
+        h.update(b"abc");
+        assert_eq!(h.finalize(), expected);
+        assert_eq!(
+            blake2b(32, &key, b"abc").unwrap(),
+            Vec::from_hex("dff38c978666dff5631db35ca15535520d134f5c8060ea569c6a178ad393719f")
+                .unwrap()
+        );
+
+        assert!(blake2b(65, b"", b"abc").is_err());
+        assert!(blake2s(0, b"", b"abc").is_err());
+        assert!(blake2s(32, &key, b"abc").is_err());
+    }
+}
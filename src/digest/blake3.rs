@@ -0,0 +1,561 @@
+//! BLAKE3 哈希算法
+//!
+//! 支持普通哈希、带密钥哈希、密钥派生和任意长度输出。输入按1024字节的块组成二叉树，
+//! 开启 rayon 特性并设置 parallel_threshold 后，不小于该长度的整棵子树用 rayon::join 并行计算；
+//! 树的形状与线程数无关，结果相同
+
+use super::Digest;
+
+/// 默认输出长度
+pub const OUT_LEN: usize = 32;
+/// 密钥长度
+pub const KEY_LEN: usize = 32;
+
+const BLOCK_LEN: usize = 64;
+const CHUNK_LEN: usize = 1024;
+
+const CHUNK_START: u32 = 1 << 0;
+const CHUNK_END: u32 = 1 << 1;
+const PARENT: u32 = 1 << 2;
+const ROOT: u32 = 1 << 3;
+const KEYED_HASH: u32 = 1 << 4;
+const DERIVE_KEY_CONTEXT: u32 = 1 << 5;
+const DERIVE_KEY_MATERIAL: u32 = 1 << 6;
+
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+const MSG_PERMUTATION: [usize; 16] = [2, 6, 3, 10, 7, 0, 4, 13, 1, 11, 12, 5, 9, 14, 15, 8];
+
+fn g(state: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize, mx: u32, my: u32) {
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(mx);
+    state[d] = (state[d] ^ state[a]).rotate_right(16);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(12);
+    state[a] = state[a].wrapping_add(state[b]).wrapping_add(my);
+    state[d] = (state[d] ^ state[a]).rotate_right(8);
+    state[c] = state[c].wrapping_add(state[d]);
+    state[b] = (state[b] ^ state[c]).rotate_right(7);
+}
+
+fn round(state: &mut [u32; 16], m: &[u32; 16]) {
+    g(state, 0, 4, 8, 12, m[0], m[1]);
+    g(state, 1, 5, 9, 13, m[2], m[3]);
+    g(state, 2, 6, 10, 14, m[4], m[5]);
+    g(state, 3, 7, 11, 15, m[6], m[7]);
+    g(state, 0, 5, 10, 15, m[8], m[9]);
+    g(state, 1, 6, 11, 12, m[10], m[11]);
+    g(state, 2, 7, 8, 13, m[12], m[13]);
+    g(state, 3, 4, 9, 14, m[14], m[15]);
+}
+
+fn compress(
+    cv: &[u32; 8],
+    block: &[u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+) -> [u32; 16] {
+    let mut state = [
+        cv[0],
+        cv[1],
+        cv[2],
+        cv[3],
+        cv[4],
+        cv[5],
+        cv[6],
+        cv[7],
+        IV[0],
+        IV[1],
+        IV[2],
+        IV[3],
+        counter as u32,
+        (counter >> 32) as u32,
+        block_len,
+        flags,
+    ];
+    let mut m = *block;
+    for i in 0..7 {
+        round(&mut state, &m);
+        if i < 6 {
+            let mut permuted = [0u32; 16];
+            for (p, &j) in permuted.iter_mut().zip(MSG_PERMUTATION.iter()) {
+                *p = m[j];
+            }
+            m = permuted;
+        }
+    }
+    for i in 0..8 {
+        state[i] ^= state[i + 8];
+        state[i + 8] ^= cv[i];
+    }
+    state
+}
+
+fn words(bytes: &[u8]) -> [u32; 16] {
+    let mut block = [0u8; BLOCK_LEN];
+    block[..bytes.len()].copy_from_slice(bytes);
+    let mut out = [0u32; 16];
+    for (w, b) in out.iter_mut().zip(block.chunks_exact(4)) {
+        *w = u32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    }
+    out
+}
+
+fn first_8(w: [u32; 16]) -> [u32; 8] {
+    let mut out = [0u32; 8];
+    out.copy_from_slice(&w[..8]);
+    out
+}
+
+// 一个节点在计算链接值或根输出之前的全部输入
+#[derive(Clone, Copy)]
+struct Output {
+    input_cv: [u32; 8],
+    block: [u32; 16],
+    counter: u64,
+    block_len: u32,
+    flags: u32,
+}
+
+impl Output {
+    fn chaining_value(&self) -> [u32; 8] {
+        first_8(compress(
+            &self.input_cv,
+            &self.block,
+            self.counter,
+            self.block_len,
+            self.flags,
+        ))
+    }
+
+    fn root_bytes(&self, out: &mut [u8]) {
+        for (counter, block) in out.chunks_mut(2 * OUT_LEN).enumerate() {
+            let w = compress(
+                &self.input_cv,
+                &self.block,
+                counter as u64,
+                self.block_len,
+                self.flags | ROOT,
+            );
+            for (o, b) in block.chunks_mut(4).zip(w.iter().map(|w| w.to_le_bytes())) {
+                o.copy_from_slice(&b[..o.len()]);
+            }
+        }
+    }
+}
+
+fn parent_output(left: &[u32; 8], right: &[u32; 8], key: &[u32; 8], flags: u32) -> Output {
+    let mut block = [0u32; 16];
+    block[..8].copy_from_slice(left);
+    block[8..].copy_from_slice(right);
+    Output {
+        input_cv: *key,
+        block,
+        counter: 0,
+        block_len: BLOCK_LEN as u32,
+        flags: PARENT | flags,
+    }
+}
+
+#[derive(Clone)]
+struct ChunkState {
+    cv: [u32; 8],
+    counter: u64,
+    block: [u8; BLOCK_LEN],
+    block_len: usize,
+    blocks_compressed: usize,
+    flags: u32,
+}
+
+impl ChunkState {
+    fn new(key: &[u32; 8], counter: u64, flags: u32) -> Self {
+        ChunkState {
+            cv: *key,
+            counter,
+            block: [0u8; BLOCK_LEN],
+            block_len: 0,
+            blocks_compressed: 0,
+            flags,
+        }
+    }
+
+    fn len(&self) -> usize {
+        BLOCK_LEN * self.blocks_compressed + self.block_len
+    }
+
+    fn start_flag(&self) -> u32 {
+        if self.blocks_compressed == 0 {
+            CHUNK_START
+        } else {
+            0
+        }
+    }
+
+    fn update(&mut self, mut input: &[u8]) {
+        while !input.is_empty() {
+            // 最后一个分组要带 CHUNK_END 标志，有更多输入时才压缩已满的分组
+            if self.block_len == BLOCK_LEN {
+                self.cv = first_8(compress(
+                    &self.cv,
+                    &words(&self.block),
+                    self.counter,
+                    BLOCK_LEN as u32,
+                    self.flags | self.start_flag(),
+                ));
+                self.blocks_compressed += 1;
+                self.block_len = 0;
+            }
+            let take = (BLOCK_LEN - self.block_len).min(input.len());
+            self.block[self.block_len..self.block_len + take].copy_from_slice(&input[..take]);
+            self.block_len += take;
+            input = &input[take..];
+        }
+    }
+
+    fn output(&self) -> Output {
+        Output {
+            input_cv: self.cv,
+            block: words(&self.block[..self.block_len]),
+            counter: self.counter,
+            block_len: self.block_len as u32,
+            flags: self.flags | self.start_flag() | CHUNK_END,
+        }
+    }
+}
+
+// 计算 2^k 个完整块组成的子树(不是根)的链接值，parallel 时左右子树并行计算
+fn subtree_cv(input: &[u8], key: &[u32; 8], counter: u64, flags: u32, parallel: bool) -> [u32; 8] {
+    if input.len() <= CHUNK_LEN {
+        let mut chunk = ChunkState::new(key, counter, flags);
+        chunk.update(input);
+        return chunk.output().chaining_value();
+    }
+    let (left, right) = subtree_halves(input, key, counter, flags, parallel);
+    parent_output(&left, &right, key, flags).chaining_value()
+}
+
+fn subtree_halves(
+    input: &[u8],
+    key: &[u32; 8],
+    counter: u64,
+    flags: u32,
+    parallel: bool,
+) -> ([u32; 8], [u32; 8]) {
+    let half = input.len() / 2;
+    let right_counter = counter + (half / CHUNK_LEN) as u64;
+    join(
+        parallel,
+        || subtree_cv(&input[..half], key, counter, flags, parallel),
+        || subtree_cv(&input[half..], key, right_counter, flags, parallel),
+    )
+}
+
+// 由 rayon 的线程池调度，递归的每一层都可以被空闲线程窃取
+#[cfg(feature = "rayon")]
+fn join<A, B>(parallel: bool, a: A, b: B) -> ([u32; 8], [u32; 8])
+where
+    A: FnOnce() -> [u32; 8] + Send,
+    B: FnOnce() -> [u32; 8] + Send,
+{
+    if parallel {
+        rayon::join(a, b)
+    } else {
+        (a(), b())
+    }
+}
+
+#[cfg(not(feature = "rayon"))]
+fn join<A, B>(_parallel: bool, a: A, b: B) -> ([u32; 8], [u32; 8])
+where
+    A: FnOnce() -> [u32; 8],
+    B: FnOnce() -> [u32; 8],
+{
+    (a(), b())
+}
+
+/// 流式 BLAKE3 哈希
+#[derive(Clone)]
+pub struct Blake3 {
+    chunk: ChunkState,
+    key: [u32; 8],
+    // 尚未合并的子树链接值，在确定不是根节点之后才合并
+    cv_stack: Vec<[u32; 8]>,
+    flags: u32,
+    parallel_threshold: Option<usize>,
+}
+
+impl Blake3 {
+    /// 普通哈希
+    pub fn new() -> Self {
+        Self::with_key_words(IV, 0)
+    }
+
+    /// 带密钥哈希，可以用作MAC
+    ///
+    /// key: 32字节密钥
+    pub fn new_keyed(key: &[u8; KEY_LEN]) -> Self {
+        Self::with_key_words(first_8(words(key)), KEYED_HASH)
+    }
+
+    /// 密钥派生，输入的数据为密钥材料
+    ///
+    /// context: 全局唯一且固定的上下文字符串，如 "example.com 2024-01-01 session keys"，
+    /// 不能包含运行时数据
+    pub fn new_derive_key(context: &str) -> Self {
+        let mut ctx = Self::with_key_words(IV, DERIVE_KEY_CONTEXT);
+        ctx.update(context.as_bytes());
+        let mut context_key = [0u8; KEY_LEN];
+        ctx.finalize_output().root_bytes(&mut context_key);
+        Self::with_key_words(first_8(words(&context_key)), DERIVE_KEY_MATERIAL)
+    }
+
+    fn with_key_words(key: [u32; 8], flags: u32) -> Self {
+        Blake3 {
+            chunk: ChunkState::new(&key, 0, flags),
+            key,
+            cv_stack: Vec::new(),
+            flags,
+            parallel_threshold: None,
+        }
+    }
+
+    /// 一次 update 中不小于 threshold 字节的整棵子树在 rayon 的全局线程池中并行计算
+    ///
+    /// 任务调度有开销，threshold 一般取几百KB到几MB
+    #[cfg(feature = "rayon")]
+    pub fn parallel_threshold(mut self, threshold: usize) -> Self {
+        self.parallel_threshold = Some(threshold.max(2 * CHUNK_LEN));
+        self
+    }
+
+    fn merge_cv_stack(&mut self, total_chunks: u64) {
+        let post_merge_len = total_chunks.count_ones() as usize;
+        while self.cv_stack.len() > post_merge_len {
+            let right = self.cv_stack.pop().expect("stack not empty");
+            let left = self.cv_stack.pop().expect("stack not empty");
+            let parent = parent_output(&left, &right, &self.key, self.flags).chaining_value();
+            self.cv_stack.push(parent);
+        }
+    }
+
+    fn push_cv(&mut self, cv: [u32; 8], chunk_counter: u64) {
+        self.merge_cv_stack(chunk_counter);
+        self.cv_stack.push(cv);
+    }
+
+    fn is_parallel(&self, len: usize) -> bool {
+        matches!(self.parallel_threshold, Some(threshold) if len >= threshold)
+    }
+
+    fn update_input(&mut self, mut input: &[u8]) {
+        if self.chunk.len() > 0 {
+            let take = (CHUNK_LEN - self.chunk.len()).min(input.len());
+            self.chunk.update(&input[..take]);
+            input = &input[take..];
+            if input.is_empty() {
+                return;
+            }
+            let counter = self.chunk.counter;
+            let cv = self.chunk.output().chaining_value();
+            self.push_cv(cv, counter);
+            self.chunk = ChunkState::new(&self.key, counter + 1, self.flags);
+        }
+
+        // 最后一个块留在 chunk 中，因为它可能是根节点
+        while input.len() > CHUNK_LEN {
+            let counter = self.chunk.counter;
+            let mut subtree_len = 1usize << (usize::BITS - 1 - input.len().leading_zeros());
+            // 子树必须与其大小对齐
+            while (counter * CHUNK_LEN as u64) & (subtree_len as u64 - 1) != 0 {
+                subtree_len /= 2;
+            }
+            let subtree_chunks = (subtree_len / CHUNK_LEN) as u64;
+            if subtree_chunks == 1 {
+                let cv = subtree_cv(&input[..CHUNK_LEN], &self.key, counter, self.flags, false);
+                self.push_cv(cv, counter);
+            } else {
+                // 分两次压栈，整个输入恰好是这棵子树时它的父节点就是根，不能提前合并
+                let parallel = self.is_parallel(subtree_len);
+                let (left, right) = subtree_halves(
+                    &input[..subtree_len],
+                    &self.key,
+                    counter,
+                    self.flags,
+                    parallel,
+                );
+                self.push_cv(left, counter);
+                self.push_cv(right, counter + subtree_chunks / 2);
+            }
+            self.chunk.counter += subtree_chunks;
+            input = &input[subtree_len..];
+        }
+
+        if !input.is_empty() {
+            self.chunk.update(input);
+            self.merge_cv_stack(self.chunk.counter);
+        }
+    }
+
+    fn finalize_output(&self) -> Output {
+        let mut remaining = self.cv_stack.len();
+        if remaining == 0 {
+            return self.chunk.output();
+        }
+        let mut output = if self.chunk.len() > 0 {
+            self.chunk.output()
+        } else {
+            remaining -= 2;
+            parent_output(
+                &self.cv_stack[remaining],
+                &self.cv_stack[remaining + 1],
+                &self.key,
+                self.flags,
+            )
+        };
+        while remaining > 0 {
+            remaining -= 1;
+            output = parent_output(
+                &self.cv_stack[remaining],
+                &output.chaining_value(),
+                &self.key,
+                self.flags,
+            );
+        }
+        output
+    }
+
+    /// 输出任意长度的结果，前32字节与 finalize 相同，不改变当前状态
+    pub fn finalize_xof(&self, out_len: usize) -> Vec<u8> {
+        let mut out = vec![0u8; out_len];
+        self.finalize_output().root_bytes(&mut out);
+        out
+    }
+}
+
+impl Default for Blake3 {
+    fn default() -> Self {
+        Blake3::new()
+    }
+}
+
+impl Digest for Blake3 {
+    fn update(&mut self, data: &[u8]) {
+        self.update_input(data);
+    }
+
+    fn finalize(&mut self) -> Vec<u8> {
+        let out = self.finalize_xof(OUT_LEN);
+        self.reset();
+        out
+    }
+
+    fn reset(&mut self) {
+        self.chunk = ChunkState::new(&self.key, 0, self.flags);
+        self.cv_stack.clear();
+    }
+
+    fn output_len(&self) -> usize {
+        OUT_LEN
+    }
+}
+
+/// 计算 BLAKE3 哈希
+pub fn blake3(data: &[u8]) -> [u8; OUT_LEN] {
+    let mut h = Blake3::new();
+    h.update(data);
+    to_array(&h.finalize_xof(OUT_LEN))
+}
+
+/// 计算带密钥的 BLAKE3 哈希
+///
+/// key: 32字节密钥，data: 数据
+pub fn blake3_keyed(key: &[u8; KEY_LEN], data: &[u8]) -> [u8; OUT_LEN] {
+    let mut h = Blake3::new_keyed(key);
+    h.update(data);
+    to_array(&h.finalize_xof(OUT_LEN))
+}
+
+/// BLAKE3 密钥派生
+///
+/// context: 固定的上下文字符串，material: 密钥材料，out_len: 输出长度
+pub fn blake3_derive_key(context: &str, material: &[u8], out_len: usize) -> Vec<u8> {
+    let mut h = Blake3::new_derive_key(context);
+    h.update(material);
+    h.finalize_xof(out_len)
+}
+
+fn to_array(v: &[u8]) -> [u8; OUT_LEN] {
+    let mut out = [0u8; OUT_LEN];
+    out.copy_from_slice(v);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_blake3() {
+        // test vectors from: BLAKE3 test_vectors.json，输入为 i % 251 的前 len 个字节
+        let input: Vec<u8> = (0..31744).map(|i| (i % 251) as u8).collect();
+        for (len, expected) in [
+            (
+                0,
+                "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262",
+            ),
+            (
+                1,
+                "2d3adedff11b61f14c886e35afa036736dcd87a74d27b5c1510225d0f592e213",
+            ),
+            (
+                1024,
+                "42214739f095a406f3fc83deb889744ac00df831c10daa55189b5d121c855af7",
+            ),
+            (
+                1025,
+                "d00278ae47eb27b34faecf67b4fe263f82d5412916c1ffd97c8cb7fb814b8444",
+            ),
+            (
+                31744,
+                "62b6960e1a44bcc1eb1a611a8d6235b6b4b78f32e7abc4fb4c6cdcce94895c47",
+            ),
+        ] {
+            let expected = Vec::from_hex(expected).unwrap();
+            assert_eq!(blake3(&input[..len]).to_vec(), expected);
+
+            // 流式输入、并行计算的结果相同
+            let mut h = Blake3::new();
+            for chunk in input[..len].chunks(700) {
+                h.update(chunk);
+            }
+            assert_eq!(h.finalize(), expected);
+            #[cfg(feature = "rayon")]
+            {
+                let mut h = Blake3::new().parallel_threshold(0);
+                h.update(&input[..len.min(10)]);
+                h.update(&input[len.min(10)..len]);
+                assert_eq!(h.finalize_xof(64)[..32], expected[..]);
+            }
+        }
+
+        assert_eq!(
+            blake3_derive_key("BLAKE3 2019-12-27 16:29:52 test vectors context", b"", 32),
+            Vec::from_hex("2cc39783c223154fea8dfb7c1b1660f2ac2dcbd1c1de8277b0b0dd39b7e50d7d")
+                .unwrap()
+        );
+        let key = blake3(b"key");
+        let mut h = Blake3::new_keyed(&key);
+        h.update(&input);
+        assert_eq!(h.finalize(), blake3_keyed(&key, &input).to_vec());
+        #[cfg(feature = "rayon")]
+        {
+            let mut h = Blake3::new_keyed(&key).parallel_threshold(0);
+            h.update(&input);
+            assert_eq!(h.finalize(), blake3_keyed(&key, &input).to_vec());
+        }
+        assert_ne!(blake3_keyed(&key, b"abc"), blake3(b"abc"));
+    }
+}
@@ -0,0 +1,110 @@
+//! 非密码学的校验和
+//!
+//! CRC32 (IEEE 802.3) 和 CRC32C (Castagnoli)，用于分帧和存储的完整性校验，
+//! 在支持的平台上使用 SSE4.2/PCLMULQDQ 或 ARM CRC 指令加速。
+//! 不支持的平台自动回退到查表实现，结果相同。
+//! 校验和不能抵抗有意的篡改，需要防篡改时使用 hmac
+
+use std::io;
+
+/// 计算数据的 CRC32 (IEEE) 校验和
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32fast::hash(data)
+}
+
+/// 计算数据的 CRC32C (Castagnoli) 校验和
+pub fn crc32c(data: &[u8]) -> u32 {
+    crc32c::crc32c(data)
+}
+
+/// 在已有的 CRC32 校验和上继续追加数据，crc32_append(crc32(a), b) == crc32(a | b)
+pub fn crc32_append(crc: u32, data: &[u8]) -> u32 {
+    let mut hasher = crc32fast::Hasher::new_with_initial(crc);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// 在已有的 CRC32C 校验和上继续追加数据
+pub fn crc32c_append(crc: u32, data: &[u8]) -> u32 {
+    crc32c::crc32c_append(crc, data)
+}
+
+/// 校验和算法类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    /// CRC32 (IEEE)
+    Crc32,
+    /// CRC32C (Castagnoli)
+    Crc32c,
+}
+
+/// 增量计算校验和，适用于分段到达的数据
+pub struct Checksum {
+    alg: ChecksumAlgorithm,
+    crc32: crc32fast::Hasher,
+    crc32c: u32,
+}
+
+impl Checksum {
+    /// 构建指定算法的校验和计算对象
+    pub fn new(alg: ChecksumAlgorithm) -> Self {
+        Checksum {
+            alg,
+            crc32: crc32fast::Hasher::new(),
+            crc32c: 0,
+        }
+    }
+
+    /// 追加数据
+    pub fn update(&mut self, data: &[u8]) {
+        match self.alg {
+            ChecksumAlgorithm::Crc32 => self.crc32.update(data),
+            ChecksumAlgorithm::Crc32c => self.crc32c = crc32c::crc32c_append(self.crc32c, data),
+        }
+    }
+
+    /// 返回已追加数据的校验和
+    pub fn finalize(self) -> u32 {
+        match self.alg {
+            ChecksumAlgorithm::Crc32 => self.crc32.finalize(),
+            ChecksumAlgorithm::Crc32c => self.crc32c,
+        }
+    }
+}
+
+impl io::Write for Checksum {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc() {
+        // 标准校验值，输入为 "123456789"
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32c(b"123456789"), 0xe306_9283);
+        assert_eq!(crc32_append(crc32(b"1234"), b"56789"), 0xcbf4_3926);
+        assert_eq!(crc32c_append(crc32c(b"1234"), b"56789"), 0xe306_9283);
+
+        for (alg, expected) in [
+            (ChecksumAlgorithm::Crc32, 0xcbf4_3926),
+            (ChecksumAlgorithm::Crc32c, 0xe306_9283),
+        ]
+        .iter()
+        {
+            let mut checksum = Checksum::new(*alg);
+            checksum.update(b"1234");
+            io::Write::write_all(&mut checksum, b"56789").unwrap();
+            assert_eq!(checksum.finalize(), *expected);
+        }
+    }
+}
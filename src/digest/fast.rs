@@ -0,0 +1,228 @@
+//! 快速哈希
+//!
+//! - SipHash-2-4 和 SipHash-1-3：带密钥的短输入伪随机函数，用于防止哈希洪水攻击的哈希表键和短认证标签
+//! - xxHash64 和 XXH3 (64/128位)：非密码学哈希，用于内容校验、去重和不需要密钥安全性的哈希表键，
+//!   大块数据用 XxHash64 和 Xxh3 流式计算
+//!
+//! 这些哈希都不是密码学哈希，不能用于签名、完整性保护或口令存储，此时应使用上级模块中的 digest
+
+use std::hash::Hasher;
+
+use siphasher::sip::{SipHasher13, SipHasher24};
+use xxhash_rust::{xxh3, xxh64};
+
+/// 使用128位密钥计算 SipHash-2-4
+///
+/// key: 16字节密钥，前8字节和后8字节按小端序分别作为 k0 和 k1
+pub fn siphash24(key: &[u8; 16], data: &[u8]) -> u64 {
+    let mut hasher = SipHash::new(SipHashAlgorithm::SipHash24, key);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// 使用128位密钥计算 SipHash-1-3，比 SipHash-2-4 快，Rust 标准库的 HashMap 默认使用这一变体
+///
+/// key: 16字节密钥，同 siphash24
+pub fn siphash13(key: &[u8; 16], data: &[u8]) -> u64 {
+    let mut hasher = SipHash::new(SipHashAlgorithm::SipHash13, key);
+    hasher.update(data);
+    hasher.finalize()
+}
+
+/// SipHash 的轮数变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SipHashAlgorithm {
+    /// SipHash-1-3
+    SipHash13,
+    /// SipHash-2-4
+    SipHash24,
+}
+
+/// 增量计算 SipHash，分段追加的结果与一次性计算相同
+///
+/// 实现了 std::hash::Hasher，可以用于 HashMap 的 BuildHasher
+#[derive(Debug, Clone)]
+pub struct SipHash(SipInner);
+
+#[derive(Debug, Clone)]
+enum SipInner {
+    Sip13(SipHasher13),
+    Sip24(SipHasher24),
+}
+
+impl SipHash {
+    /// 构建指定变体的 SipHash 计算对象
+    ///
+    /// key: 16字节密钥，同 siphash24
+    pub fn new(alg: SipHashAlgorithm, key: &[u8; 16]) -> Self {
+        let mut k0 = [0u8; 8];
+        let mut k1 = [0u8; 8];
+        k0.copy_from_slice(&key[..8]);
+        k1.copy_from_slice(&key[8..]);
+        let (k0, k1) = (u64::from_le_bytes(k0), u64::from_le_bytes(k1));
+        SipHash(match alg {
+            SipHashAlgorithm::SipHash13 => SipInner::Sip13(SipHasher13::new_with_keys(k0, k1)),
+            SipHashAlgorithm::SipHash24 => SipInner::Sip24(SipHasher24::new_with_keys(k0, k1)),
+        })
+    }
+
+    /// 追加数据
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.0 {
+            SipInner::Sip13(h) => h.write(data),
+            SipInner::Sip24(h) => h.write(data),
+        }
+    }
+
+    /// 返回已追加数据的哈希值，不消耗对象，可以继续追加
+    pub fn finalize(&self) -> u64 {
+        match &self.0 {
+            SipInner::Sip13(h) => h.finish(),
+            SipInner::Sip24(h) => h.finish(),
+        }
+    }
+}
+
+impl Hasher for SipHash {
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+
+    fn finish(&self) -> u64 {
+        self.finalize()
+    }
+}
+
+/// 计算 xxHash64
+pub fn xxhash64(seed: u64, data: &[u8]) -> u64 {
+    xxh64::xxh64(data, seed)
+}
+
+/// 计算 64位 XXH3
+pub fn xxh3_64(data: &[u8]) -> u64 {
+    xxh3::xxh3_64(data)
+}
+
+/// 使用种子计算 64位 XXH3
+pub fn xxh3_64_with_seed(seed: u64, data: &[u8]) -> u64 {
+    xxh3::xxh3_64_with_seed(data, seed)
+}
+
+/// 计算 128位 XXH3
+pub fn xxh3_128(data: &[u8]) -> u128 {
+    xxh3::xxh3_128(data)
+}
+
+/// 使用种子计算 128位 XXH3
+pub fn xxh3_128_with_seed(seed: u64, data: &[u8]) -> u128 {
+    xxh3::xxh3_128_with_seed(data, seed)
+}
+
+/// 增量计算 xxHash64
+#[derive(Clone)]
+pub struct XxHash64(xxh64::Xxh64);
+
+impl XxHash64 {
+    /// 使用种子构建计算对象
+    pub fn new(seed: u64) -> Self {
+        XxHash64(xxh64::Xxh64::new(seed))
+    }
+
+    /// 追加数据
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// 返回已追加数据的哈希值，不消耗对象，可以继续追加
+    pub fn finalize(&self) -> u64 {
+        self.0.digest()
+    }
+}
+
+/// 增量计算 XXH3，同一对象可以同时得到64位和128位结果
+#[derive(Clone)]
+pub struct Xxh3(xxh3::Xxh3);
+
+impl Xxh3 {
+    /// 构建不带种子的计算对象，结果与 xxh3_64 和 xxh3_128 相同
+    pub fn new() -> Self {
+        Xxh3(xxh3::Xxh3::new())
+    }
+
+    /// 使用种子构建计算对象
+    pub fn with_seed(seed: u64) -> Self {
+        Xxh3(xxh3::Xxh3::with_seed(seed))
+    }
+
+    /// 追加数据
+    pub fn update(&mut self, data: &[u8]) {
+        self.0.update(data);
+    }
+
+    /// 返回已追加数据的64位哈希值
+    pub fn finalize(&self) -> u64 {
+        self.0.digest()
+    }
+
+    /// 返回已追加数据的128位哈希值
+    pub fn finalize_128(&self) -> u128 {
+        self.0.digest128()
+    }
+}
+
+impl Default for Xxh3 {
+    fn default() -> Self {
+        Xxh3::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_hash() {
+        // test vector from: https://github.com/veorq/SipHash/blob/master/vectors.h
+        let mut key = [0u8; 16];
+        for (i, b) in key.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        assert_eq!(siphash24(&key, b""), 0x726f_db47_dd0e_0e31);
+        // SipHash 论文附录A，15字节消息 00..0e
+        let msg: Vec<u8> = (0..15).collect();
+        assert_eq!(siphash24(&key, &msg), 0xa129_ca61_49be_45e5);
+        for alg in [SipHashAlgorithm::SipHash13, SipHashAlgorithm::SipHash24] {
+            let mut hasher = SipHash::new(alg, &key);
+            hasher.update(&msg[..3]);
+            hasher.update(&msg[3..]);
+            let expected = match alg {
+                SipHashAlgorithm::SipHash13 => siphash13(&key, &msg),
+                SipHashAlgorithm::SipHash24 => siphash24(&key, &msg),
+            };
+            assert_eq!(hasher.finalize(), expected);
+        }
+        assert_ne!(siphash13(&key, &msg), siphash24(&key, &msg));
+
+        // 与 xxhsum 的输出对比
+        assert_eq!(xxhash64(0, b""), 0xef46_db37_51d8_e999);
+        assert_eq!(xxhash64(0, b"abc"), 0x44bc_2cf5_ad77_0999);
+        assert_eq!(xxh3_64(b""), 0x2d06_8005_38d3_94c2);
+        assert_eq!(xxh3_64(b"abc"), 0x78af_5f94_892f_3950);
+        assert_eq!(xxh3_64_with_seed(0, b"abc"), xxh3_64(b"abc"));
+        assert_ne!(xxh3_64_with_seed(1, b"abc"), xxh3_64(b"abc"));
+        assert_eq!(xxh3_128(b""), 0x99aa_06d3_0147_98d8_6001_c324_468d_497f);
+
+        // 跨越内部缓冲区边界的流式计算与一次性计算结果相同
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let mut xxh64 = XxHash64::new(7);
+        let mut x3 = Xxh3::with_seed(7);
+        for chunk in data.chunks(33) {
+            xxh64.update(chunk);
+            x3.update(chunk);
+        }
+        assert_eq!(xxh64.finalize(), xxhash64(7, &data));
+        assert_eq!(x3.finalize(), xxh3_64_with_seed(7, &data));
+        assert_eq!(x3.finalize_128(), xxh3_128_with_seed(7, &data));
+        assert_eq!(Xxh3::new().finalize_128(), xxh3_128(b""));
+    }
+}
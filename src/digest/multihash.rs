@@ -0,0 +1,162 @@
+//! Multihash 格式的哈希输出
+//!
+//! 格式为 varint(算法代码) | varint(哈希长度) | 哈希，算法代码来自 multiformats 的 multicodec 表，
+//! 用于 IPFS CID 等需要自描述哈希的场合
+
+use super::{digest, DigestAlgorithm};
+use crate::ct::ct_eq;
+use crate::error::CryptoError;
+
+// unsigned-varint 规范限制 varint 最多9字节
+const MAX_VARINT_LEN: usize = 9;
+
+/// 计算数据的哈希并编码为 multihash
+///
+/// alg: 哈希算法
+/// data: 数据
+pub fn multihash(alg: DigestAlgorithm, data: &[u8]) -> Vec<u8> {
+    let code = code(&alg);
+    let hash = digest(alg, data);
+    let mut out = Vec::with_capacity(hash.len() + 4);
+    put_varint(&mut out, code);
+    put_varint(&mut out, hash.len() as u64);
+    out.extend_from_slice(&hash);
+    out
+}
+
+/// 解码 multihash，返回 (哈希算法, 哈希值)
+///
+/// 不支持的算法代码、长度与算法不符或有多余数据时返回错误
+pub fn decode_multihash(mh: &[u8]) -> Result<(DigestAlgorithm, Vec<u8>), CryptoError> {
+    let mut rest = mh;
+    let code = varint(&mut rest)?;
+    let len = varint(&mut rest)?;
+    let alg = algorithm(code)?;
+    if len != output_len(&alg) as u64 || rest.len() as u64 != len {
+        return Err(CryptoError::InvalidInput(
+            "multihash: digest length mismatch".to_string(),
+        ));
+    }
+    Ok((alg, rest.to_vec()))
+}
+
+/// 检查 multihash 是否为数据的哈希
+///
+/// mh: multihash 编码的哈希
+/// data: 数据
+pub fn verify_multihash(mh: &[u8], data: &[u8]) -> Result<bool, CryptoError> {
+    let (alg, hash) = decode_multihash(mh)?;
+    Ok(ct_eq(&digest(alg, data), &hash))
+}
+
+#[allow(deprecated)]
+fn code(alg: &DigestAlgorithm) -> u64 {
+    match alg {
+        #[cfg(feature = "legacy-hashes")]
+        DigestAlgorithm::MD5 => 0xd5,
+        #[cfg(feature = "legacy-hashes")]
+        DigestAlgorithm::SHA1 => 0x11,
+        DigestAlgorithm::SHA256 => 0x12,
+        DigestAlgorithm::SHA384 => 0x20,
+        DigestAlgorithm::SHA512 => 0x13,
+        DigestAlgorithm::SM3 => 0x534d,
+        DigestAlgorithm::SHA3_256 => 0x16,
+        DigestAlgorithm::SHA3_384 => 0x15,
+        DigestAlgorithm::SHA3_512 => 0x14,
+        DigestAlgorithm::KECCAK256 => 0x1b,
+    }
+}
+
+#[allow(deprecated)]
+fn algorithm(code: u64) -> Result<DigestAlgorithm, CryptoError> {
+    Ok(match code {
+        #[cfg(feature = "legacy-hashes")]
+        0xd5 => DigestAlgorithm::MD5,
+        #[cfg(feature = "legacy-hashes")]
+        0x11 => DigestAlgorithm::SHA1,
+        0x12 => DigestAlgorithm::SHA256,
+        0x20 => DigestAlgorithm::SHA384,
+        0x13 => DigestAlgorithm::SHA512,
+        0x534d => DigestAlgorithm::SM3,
+        0x16 => DigestAlgorithm::SHA3_256,
+        0x15 => DigestAlgorithm::SHA3_384,
+        0x14 => DigestAlgorithm::SHA3_512,
+        0x1b => DigestAlgorithm::KECCAK256,
+        c => {
+            return Err(CryptoError::InvalidInput(format!(
+                "multihash: unsupported hash code 0x{:x}",
+                c
+            )))
+        }
+    })
+}
+
+#[allow(deprecated)]
+fn output_len(alg: &DigestAlgorithm) -> usize {
+    match alg {
+        #[cfg(feature = "legacy-hashes")]
+        DigestAlgorithm::MD5 => 16,
+        #[cfg(feature = "legacy-hashes")]
+        DigestAlgorithm::SHA1 => 20,
+        DigestAlgorithm::SHA384 | DigestAlgorithm::SHA3_384 => 48,
+        DigestAlgorithm::SHA512 | DigestAlgorithm::SHA3_512 => 64,
+        _ => 32,
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+fn varint(data: &mut &[u8]) -> Result<u64, CryptoError> {
+    let mut value = 0u64;
+    for i in 0..MAX_VARINT_LEN {
+        let b = *data
+            .get(i)
+            .ok_or_else(|| CryptoError::InvalidInput("multihash: truncated input".to_string()))?;
+        value |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err(CryptoError::InvalidInput(
+        "multihash: varint too long".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_multihash() {
+        // test vector from: https://github.com/multiformats/multihash，sha2-256("hello world")
+        let mh = multihash(DigestAlgorithm::SHA256, b"hello world");
+        assert_eq!(
+            mh,
+            Vec::from_hex("1220b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9")
+                .unwrap()
+        );
+        let (alg, hash) = decode_multihash(&mh).unwrap();
+        assert!(matches!(alg, DigestAlgorithm::SHA256));
+        assert_eq!(hash, mh[2..]);
+        assert!(verify_multihash(&mh, b"hello world").unwrap());
+        assert!(!verify_multihash(&mh, b"hello world!").unwrap());
+
+        // SM3 的代码 0x534d 需要两字节 varint
+        let mh = multihash(DigestAlgorithm::SM3, b"abc");
+        assert_eq!(&mh[..3], &[0xcd, 0xa6, 0x01][..]);
+        assert!(matches!(
+            decode_multihash(&mh).unwrap().0,
+            DigestAlgorithm::SM3
+        ));
+        assert!(decode_multihash(&mh[..mh.len() - 1]).is_err());
+        assert!(decode_multihash(&[0x00, 0x00]).is_err());
+    }
+}
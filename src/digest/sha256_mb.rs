@@ -0,0 +1,373 @@
+//! SHA-256 多缓冲计算
+//!
+//! 同时计算多个相互独立的消息的哈希，用于 Merkle 树等需要大量短消息哈希的场合。
+//! x86_64 上运行时检测 CPU 特性：支持 SHA 扩展指令(SHA-NI)时两路交错使用 SHA 指令，
+//! 否则支持 AVX2 时8个消息分别占用一个32位通道同时计算，其他平台逐个使用 ring 计算
+//! (ring 在 aarch64 上已经使用 ARMv8 的 SHA2 指令)
+
+#[cfg(target_arch = "x86_64")]
+use std::convert::TryInto;
+
+use ring::digest as rdigest;
+
+/// SHA-256 多缓冲计算使用的实现
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sha256Backend {
+    /// x86_64 SHA 扩展指令，两路交错
+    ShaNi,
+    /// x86_64 AVX2，8路并行
+    Avx2,
+    /// 逐个消息调用 ring
+    Portable,
+}
+
+/// 当前 CPU 上 sha256_many 使用的实现
+pub fn sha256_backend() -> Sha256Backend {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("sha")
+            && is_x86_feature_detected!("sse2")
+            && is_x86_feature_detected!("ssse3")
+            && is_x86_feature_detected!("sse4.1")
+        {
+            return Sha256Backend::ShaNi;
+        }
+        if is_x86_feature_detected!("avx2") {
+            return Sha256Backend::Avx2;
+        }
+    }
+    Sha256Backend::Portable
+}
+
+/// 计算多个消息的 SHA-256，结果顺序与输入相同，与逐个计算的结果完全一致
+///
+/// inputs: 相互独立的消息，长度可以不同，长度相近时并行效率最高
+pub fn sha256_many(inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+    sha256_many_with(sha256_backend(), inputs)
+}
+
+fn sha256_many_with(backend: Sha256Backend, inputs: &[&[u8]]) -> Vec<[u8; 32]> {
+    let mut out = Vec::with_capacity(inputs.len());
+    match backend {
+        #[cfg(target_arch = "x86_64")]
+        Sha256Backend::ShaNi => {
+            // 调用方已检测过 CPU 支持 SHA-NI
+            hash_lanes::<2>(inputs, &mut out, |states, blocks| unsafe {
+                shani::compress(states, blocks)
+            })
+        }
+        #[cfg(target_arch = "x86_64")]
+        Sha256Backend::Avx2 => {
+            // 调用方已检测过 CPU 支持 AVX2
+            hash_lanes::<8>(inputs, &mut out, |states, blocks| unsafe {
+                avx2::compress(states, blocks)
+            })
+        }
+        _ => {
+            for input in inputs {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(rdigest::digest(&rdigest::SHA256, input).as_ref());
+                out.push(hash);
+            }
+        }
+    }
+    out
+}
+
+#[cfg(target_arch = "x86_64")]
+const IV: [u32; 8] = [
+    0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19,
+];
+
+#[cfg(target_arch = "x86_64")]
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+#[cfg(target_arch = "x86_64")]
+const ZERO_BLOCK: [u8; 64] = [0u8; 64];
+
+/// 按 FIPS 180-4 填充后的消息，完整分组直接引用输入，只复制最后一两个分组
+#[cfg(target_arch = "x86_64")]
+struct Padded<'a> {
+    data: &'a [u8],
+    tail: [u8; 128],
+    tail_len: usize,
+}
+
+#[cfg(target_arch = "x86_64")]
+impl<'a> Padded<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        let full = input.len() / 64 * 64;
+        let rest = &input[full..];
+        let mut tail = [0u8; 128];
+        tail[..rest.len()].copy_from_slice(rest);
+        tail[rest.len()] = 0x80;
+        let tail_len = if rest.len() < 56 { 64 } else { 128 };
+        tail[tail_len - 8..tail_len].copy_from_slice(&(input.len() as u64 * 8).to_be_bytes());
+        Padded {
+            data: &input[..full],
+            tail,
+            tail_len,
+        }
+    }
+
+    fn blocks(&self) -> usize {
+        (self.data.len() + self.tail_len) / 64
+    }
+
+    fn block(&self, i: usize) -> &[u8; 64] {
+        let full = self.data.len() / 64;
+        let block = if i < full {
+            &self.data[i * 64..(i + 1) * 64]
+        } else {
+            &self.tail[(i - full) * 64..(i - full + 1) * 64]
+        };
+        block.try_into().expect("block is 64 bytes")
+    }
+}
+
+/// 每次取 N 个消息，同时压缩各自的第 j 个分组；已经结束的消息和不足 N 个时的空通道输入全0分组，结果丢弃
+#[cfg(target_arch = "x86_64")]
+fn hash_lanes<const N: usize>(
+    inputs: &[&[u8]],
+    out: &mut Vec<[u8; 32]>,
+    compress: impl Fn(&mut [[u32; 8]; N], [&[u8; 64]; N]),
+) {
+    for batch in inputs.chunks(N) {
+        let msgs: Vec<Padded> = batch.iter().map(|input| Padded::new(input)).collect();
+        let blocks = msgs.iter().map(Padded::blocks).max().unwrap_or(0);
+        let mut states = [IV; N];
+        for j in 0..blocks {
+            let active: [bool; N] =
+                std::array::from_fn(|lane| lane < msgs.len() && j < msgs[lane].blocks());
+            let input: [&[u8; 64]; N] = std::array::from_fn(|lane| {
+                if active[lane] {
+                    msgs[lane].block(j)
+                } else {
+                    &ZERO_BLOCK
+                }
+            });
+            let saved = states;
+            compress(&mut states, input);
+            for lane in 0..N {
+                if !active[lane] {
+                    states[lane] = saved[lane];
+                }
+            }
+        }
+        for state in states.iter().take(batch.len()) {
+            let mut hash = [0u8; 32];
+            for (chunk, word) in hash.chunks_mut(4).zip(state.iter()) {
+                chunk.copy_from_slice(&word.to_be_bytes());
+            }
+            out.push(hash);
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod shani {
+    use std::arch::x86_64::*;
+
+    use super::K;
+
+    /// 用 SHA-NI 指令同时压缩 N 个状态，各通道的轮函数在同一循环中交错执行以隐藏指令延迟
+    #[target_feature(enable = "sha,sse2,ssse3,sse4.1")]
+    pub(super) unsafe fn compress<const N: usize>(
+        states: &mut [[u32; 8]; N],
+        blocks: [&[u8; 64]; N],
+    ) {
+        // 大端字节序转换
+        let mask = _mm_set_epi64x(0x0c0d_0e0f_0809_0a0b, 0x0405_0607_0001_0203);
+        let mut abef = [_mm_setzero_si128(); N];
+        let mut cdgh = [_mm_setzero_si128(); N];
+        let mut w = [[_mm_setzero_si128(); 4]; N];
+        for lane in 0..N {
+            let ptr = states[lane].as_ptr() as *const __m128i;
+            let dcba = _mm_loadu_si128(ptr);
+            let hgfe = _mm_loadu_si128(ptr.add(1));
+            let cdab = _mm_shuffle_epi32(dcba, 0xb1);
+            let efgh = _mm_shuffle_epi32(hgfe, 0x1b);
+            abef[lane] = _mm_alignr_epi8(cdab, efgh, 8);
+            cdgh[lane] = _mm_blend_epi16(efgh, cdab, 0xf0);
+            let data = blocks[lane].as_ptr() as *const __m128i;
+            for (i, word) in w[lane].iter_mut().enumerate() {
+                *word = _mm_shuffle_epi8(_mm_loadu_si128(data.add(i)), mask);
+            }
+        }
+        let (abef_saved, cdgh_saved) = (abef, cdgh);
+
+        for i in 0..16 {
+            let k = _mm_loadu_si128(K.as_ptr().add(4 * i) as *const __m128i);
+            for lane in 0..N {
+                let w = &mut w[lane];
+                if i >= 4 {
+                    // W[t..t+4] 由 W[t-16..t] 计算，覆盖最早的4个字
+                    let t1 = _mm_sha256msg1_epu32(w[i % 4], w[(i + 1) % 4]);
+                    let t2 = _mm_alignr_epi8(w[(i + 3) % 4], w[(i + 2) % 4], 4);
+                    w[i % 4] = _mm_sha256msg2_epu32(_mm_add_epi32(t1, t2), w[(i + 3) % 4]);
+                }
+                let wk = _mm_add_epi32(w[i % 4], k);
+                cdgh[lane] = _mm_sha256rnds2_epu32(cdgh[lane], abef[lane], wk);
+                abef[lane] =
+                    _mm_sha256rnds2_epu32(abef[lane], cdgh[lane], _mm_shuffle_epi32(wk, 0x0e));
+            }
+        }
+
+        for lane in 0..N {
+            let abef = _mm_add_epi32(abef[lane], abef_saved[lane]);
+            let cdgh = _mm_add_epi32(cdgh[lane], cdgh_saved[lane]);
+            let feba = _mm_shuffle_epi32(abef, 0x1b);
+            let dchg = _mm_shuffle_epi32(cdgh, 0xb1);
+            let ptr = states[lane].as_mut_ptr() as *mut __m128i;
+            _mm_storeu_si128(ptr, _mm_blend_epi16(feba, dchg, 0xf0));
+            _mm_storeu_si128(ptr.add(1), _mm_alignr_epi8(dchg, feba, 8));
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod avx2 {
+    use std::arch::x86_64::*;
+
+    use super::K;
+
+    macro_rules! rotr {
+        ($x:expr, $n:literal) => {
+            _mm256_or_si256(
+                _mm256_srli_epi32::<$n>($x),
+                _mm256_slli_epi32::<{ 32 - $n }>($x),
+            )
+        };
+    }
+
+    /// 8个消息分别占用一个32位通道，按标准算法逐轮计算
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn compress(states: &mut [[u32; 8]; 8], blocks: [&[u8; 64]; 8]) {
+        let lanes = |f: &dyn Fn(usize) -> u32| {
+            _mm256_setr_epi32(
+                f(0) as i32,
+                f(1) as i32,
+                f(2) as i32,
+                f(3) as i32,
+                f(4) as i32,
+                f(5) as i32,
+                f(6) as i32,
+                f(7) as i32,
+            )
+        };
+        let mut s: [__m256i; 8] = std::array::from_fn(|i| lanes(&|lane| states[lane][i]));
+        let saved = s;
+        let mut w: [__m256i; 16] = std::array::from_fn(|t| {
+            lanes(&|lane| {
+                let b = &blocks[lane][4 * t..4 * t + 4];
+                u32::from_be_bytes([b[0], b[1], b[2], b[3]])
+            })
+        });
+
+        for (t, k) in K.iter().enumerate() {
+            if t >= 16 {
+                let w15 = w[(t + 1) % 16];
+                let w2 = w[(t + 14) % 16];
+                let s0 = _mm256_xor_si256(
+                    _mm256_xor_si256(rotr!(w15, 7), rotr!(w15, 18)),
+                    _mm256_srli_epi32::<3>(w15),
+                );
+                let s1 = _mm256_xor_si256(
+                    _mm256_xor_si256(rotr!(w2, 17), rotr!(w2, 19)),
+                    _mm256_srli_epi32::<10>(w2),
+                );
+                w[t % 16] = _mm256_add_epi32(
+                    _mm256_add_epi32(w[t % 16], s0),
+                    _mm256_add_epi32(w[(t + 9) % 16], s1),
+                );
+            }
+            let [a, b, c, d, e, f, g, h] = s;
+            let s1 = _mm256_xor_si256(_mm256_xor_si256(rotr!(e, 6), rotr!(e, 11)), rotr!(e, 25));
+            let ch = _mm256_xor_si256(_mm256_and_si256(e, f), _mm256_andnot_si256(e, g));
+            let t1 = _mm256_add_epi32(
+                _mm256_add_epi32(_mm256_add_epi32(h, s1), _mm256_add_epi32(ch, w[t % 16])),
+                _mm256_set1_epi32(*k as i32),
+            );
+            let s0 = _mm256_xor_si256(_mm256_xor_si256(rotr!(a, 2), rotr!(a, 13)), rotr!(a, 22));
+            let maj = _mm256_xor_si256(
+                _mm256_and_si256(a, _mm256_xor_si256(b, c)),
+                _mm256_and_si256(b, c),
+            );
+            let t2 = _mm256_add_epi32(s0, maj);
+            s = [
+                _mm256_add_epi32(t1, t2),
+                a,
+                b,
+                c,
+                _mm256_add_epi32(d, t1),
+                e,
+                f,
+                g,
+            ];
+        }
+
+        let mut words = [[0u32; 8]; 8];
+        for (i, word) in words.iter_mut().enumerate() {
+            let v = _mm256_add_epi32(s[i], saved[i]);
+            _mm256_storeu_si256(word.as_mut_ptr() as *mut __m256i, v);
+        }
+        for (lane, state) in states.iter_mut().enumerate() {
+            for (i, word) in state.iter_mut().enumerate() {
+                *word = words[i][lane];
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_sha256_many() {
+        let expected =
+            Vec::from_hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+                .unwrap();
+        assert_eq!(sha256_many(&[b"abc"])[0].to_vec(), expected);
+        assert!(sha256_many(&[]).is_empty());
+
+        // 覆盖填充的边界长度和不同长度混合的批次
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + 3) as u8).collect();
+        let lens = [
+            0, 1, 55, 56, 63, 64, 65, 119, 120, 128, 1000, 3, 200, 64, 0, 511, 17,
+        ];
+        let inputs: Vec<&[u8]> = lens.iter().map(|&n| &data[..n]).collect();
+        let reference = sha256_many_with(Sha256Backend::Portable, &inputs);
+        for (input, hash) in inputs.iter().zip(reference.iter()) {
+            assert_eq!(&hash[..], rdigest::digest(&rdigest::SHA256, input).as_ref());
+        }
+        assert_eq!(sha256_many(&inputs), reference);
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let shani = is_x86_feature_detected!("sha") && is_x86_feature_detected!("sse4.1");
+            let backends = [
+                (Sha256Backend::ShaNi, shani),
+                (Sha256Backend::Avx2, is_x86_feature_detected!("avx2")),
+            ];
+            for (backend, supported) in backends.iter() {
+                if !supported {
+                    continue;
+                }
+                for n in 1..=inputs.len() {
+                    assert_eq!(sha256_many_with(*backend, &inputs[..n]), reference[..n]);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! 经典 DSA 签名验证 (FIPS 186-4)
+//!
+//! 只支持验证，用于校验旧系统仍在发布的 DSA 签名元数据，新的签名应使用 ecdsa 或 ed25519。
+//! SHA1 需要开启 legacy-hashes 特性
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use ring::digest as rdigest;
+use simple_asn1::{from_der, oid, ASN1Block, OID};
+
+/// DSA 签名使用的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DsaHash {
+    #[cfg(feature = "legacy-hashes")]
+    SHA1,
+    SHA256,
+}
+
+/// DSA 公钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DsaPublicKey {
+    p: BigUint,
+    q: BigUint,
+    g: BigUint,
+    y: BigUint,
+}
+
+impl DsaPublicKey {
+    /// 从大端编码的域参数和公钥值构建
+    ///
+    /// p: 素数模数，1024到3072位
+    /// q: p-1 的素因子，160、224或256位
+    /// g: 生成元
+    /// y: 公钥值
+    pub fn new(p: &[u8], q: &[u8], g: &[u8], y: &[u8]) -> Result<Self, String> {
+        let key = DsaPublicKey {
+            p: BigUint::from_bytes_be(p),
+            q: BigUint::from_bytes_be(q),
+            g: BigUint::from_bytes_be(g),
+            y: BigUint::from_bytes_be(y),
+        };
+        let (p_bits, q_bits) = (key.p.bits(), key.q.bits());
+        if !(1024..=3072).contains(&p_bits) || ![160, 224, 256].contains(&q_bits) {
+            return Err("dsa: unsupported parameter sizes".to_string());
+        }
+        let one = BigUint::one();
+        if key.g <= one || key.g >= key.p || key.y <= one || key.y >= key.p {
+            return Err("dsa: invalid public key".to_string());
+        }
+        if !((&key.p - &one) % &key.q).is_zero() {
+            return Err("dsa: q does not divide p-1".to_string());
+        }
+        Ok(key)
+    }
+
+    /// 从 SubjectPublicKeyInfo DER 解析
+    pub fn from_der(spki: &[u8]) -> Result<Self, String> {
+        let malformed = || "dsa: malformed public key".to_string();
+        let blocks = from_der(spki).map_err(|_| malformed())?;
+        let (algorithm, key) = match blocks.as_slice() {
+            [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+                [ASN1Block::Sequence(_, algorithm), ASN1Block::BitString(_, _, key)] => {
+                    (algorithm, key)
+                }
+                _ => return Err(malformed()),
+            },
+            _ => return Err(malformed()),
+        };
+        let params = match algorithm.as_slice() {
+            [ASN1Block::ObjectIdentifier(_, id), ASN1Block::Sequence(_, params)]
+                if *id == oid!(1, 2, 840, 10040, 4, 1) =>
+            {
+                params
+            }
+            _ => return Err("dsa: not a DSA public key".to_string()),
+        };
+        let pqg = params
+            .iter()
+            .map(integer_bytes)
+            .collect::<Option<Vec<_>>>()
+            .filter(|v| v.len() == 3)
+            .ok_or_else(malformed)?;
+        let y = match from_der(key).map_err(|_| malformed())?.as_slice() {
+            [block] => integer_bytes(block).ok_or_else(malformed)?,
+            _ => return Err(malformed()),
+        };
+        DsaPublicKey::new(&pqg[0], &pqg[1], &pqg[2], &y)
+    }
+
+    /// 验证签名
+    ///
+    /// hash: 签名使用的哈希算法
+    /// msg: 已签名的数据
+    /// sig: DER 编码的签名 SEQUENCE { r INTEGER, s INTEGER }
+    pub fn verify(&self, hash: DsaHash, msg: &[u8], sig: &[u8]) -> bool {
+        let (r, s) = match parse_signature(sig) {
+            Some(rs) => rs,
+            None => return false,
+        };
+        let q = &self.q;
+        if r.is_zero() || s.is_zero() || r >= *q || s >= *q {
+            return false;
+        }
+        let digest = match hash {
+            #[cfg(feature = "legacy-hashes")]
+            DsaHash::SHA1 => rdigest::digest(&rdigest::SHA1_FOR_LEGACY_USE_ONLY, msg),
+            DsaHash::SHA256 => rdigest::digest(&rdigest::SHA256, msg),
+        };
+        // z 取哈希最左边的 N 位，N 为 q 的位数，总是8的倍数
+        let digest = digest.as_ref();
+        let z = BigUint::from_bytes_be(&digest[..digest.len().min(q.bits() / 8)]);
+
+        // q 为素数，s^-1 = s^(q-2) mod q
+        let w = s.modpow(&(q - BigUint::from(2u32)), q);
+        let u1 = (z * &w) % q;
+        let u2 = (&r * &w) % q;
+        let v = (self.g.modpow(&u1, &self.p) * self.y.modpow(&u2, &self.p)) % &self.p % q;
+        v == r
+    }
+}
+
+fn integer_bytes(block: &ASN1Block) -> Option<Vec<u8>> {
+    match block {
+        ASN1Block::Integer(_, n) => n.to_biguint().map(|n| n.to_bytes_be()),
+        _ => None,
+    }
+}
+
+fn parse_signature(sig: &[u8]) -> Option<(BigUint, BigUint)> {
+    match from_der(sig).ok()?.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [r, s] => Some((
+                BigUint::from_bytes_be(&integer_bytes(r)?),
+                BigUint::from_bytes_be(&integer_bytes(s)?),
+            )),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_dsa_verify() {
+        // Python cryptography 库生成的 DSA-2048/256 密钥对 "abc" 的 SHA256 签名
+        let key =
+            DsaPublicKey::from_der(include_bytes!("../tests/dsa-2048-public-key.der")).unwrap();
+        let sig = Vec::from_hex("30450220316ad7f437a6767077ff0c6ff9c73b5b92ac0e0a720651ba2336470c7bdeefec022100c4013aebe90e7638981322bcb6940e588a025c8ddb5d854f3031ff74fcb7d1a5").unwrap();
+        assert!(key.verify(DsaHash::SHA256, b"abc", &sig));
+        assert!(!key.verify(DsaHash::SHA256, b"abd", &sig));
+        let mut bad = sig.clone();
+        bad[10] ^= 1;
+        assert!(!key.verify(DsaHash::SHA256, b"abc", &bad));
+        assert!(!key.verify(DsaHash::SHA256, b"abc", &sig[1..]));
+        assert!(DsaPublicKey::from_der(&sig).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "legacy-hashes")]
+    fn test_dsa_sha1_verify() {
+        // DSA-1024/160 密钥对 "abc" 的 SHA1 签名
+        let key =
+            DsaPublicKey::from_der(include_bytes!("../tests/dsa-1024-public-key.der")).unwrap();
+        let sig = Vec::from_hex("302c021455d6f55a72757d3b52766bba287ed12701d2543302141791dc61a396b6ea6772d45971cf8d525b78f945").unwrap();
+        assert!(key.verify(DsaHash::SHA1, b"abc", &sig));
+        assert!(!key.verify(DsaHash::SHA256, b"abc", &sig));
+    }
+}
@@ -0,0 +1,277 @@
+//! DSSE 签名信封 (Dead Simple Signing Envelope)
+//!
+//! 签名覆盖 PAE(载荷类型, 载荷) = "DSSEv1" SP LEN(类型) SP 类型 SP LEN(载荷) SP 载荷，LEN 为十进制长度，
+//! 载荷类型参与签名，同一份载荷不会被当作另一种类型接受。一个信封可以带多个签名，
+//! 按 multisig 的策略验证。支持 in-toto 等供应链工具使用的 JSON 格式和 protobuf 格式
+
+use serde::{Deserialize, Serialize};
+
+use crate::multisig::Policy;
+use crate::proto;
+use crate::signature::{scheme_verify, SignatureScheme};
+
+/// 信封中的单个签名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DsseSignature {
+    /// 密钥ID，可以为空，为空时验证方尝试所有受信任的密钥
+    pub keyid: String,
+    pub sig: Vec<u8>,
+}
+
+/// 受信任的签名密钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrustedKey {
+    pub keyid: String,
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+}
+
+/// DSSE 信封
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DsseEnvelope {
+    pub payload_type: String,
+    pub payload: Vec<u8>,
+    pub signatures: Vec<DsseSignature>,
+}
+
+/// 预认证编码，签名覆盖的数据
+///
+/// payload_type: 载荷类型，如 "application/vnd.in-toto+json"
+/// payload: 载荷
+pub fn pae(payload_type: &str, payload: &[u8]) -> Vec<u8> {
+    let mut out = format!(
+        "DSSEv1 {} {} {} ",
+        payload_type.len(),
+        payload_type,
+        payload.len()
+    )
+    .into_bytes();
+    out.extend_from_slice(payload);
+    out
+}
+
+impl DsseEnvelope {
+    /// 构建未签名的信封
+    pub fn new(payload_type: &str, payload: &[u8]) -> Self {
+        DsseEnvelope {
+            payload_type: payload_type.to_string(),
+            payload: payload.to_vec(),
+            signatures: Vec::new(),
+        }
+    }
+
+    /// 添加一个签名
+    ///
+    /// keyid: 签名密钥的ID
+    /// sign: 签名函数，参数为 pae(载荷类型, 载荷)
+    pub fn sign_with<F>(&mut self, keyid: &str, sign: F)
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        let sig = sign(&pae(&self.payload_type, &self.payload));
+        self.signatures.push(DsseSignature {
+            keyid: keyid.to_string(),
+            sig,
+        });
+    }
+
+    /// 按策略验证签名，成功时返回签名有效的密钥ID
+    ///
+    /// trusted: 受信任的密钥，方案和公钥相同的重复项只保留第一个
+    /// policy: 验证策略，门限按不同的受信任密钥计数
+    pub fn verify(&self, trusted: &[TrustedKey], policy: Policy) -> Result<Vec<String>, String> {
+        let data = pae(&self.payload_type, &self.payload);
+        let mut keys: Vec<&TrustedKey> = Vec::with_capacity(trusted.len());
+        for key in trusted {
+            if !keys
+                .iter()
+                .any(|k| k.scheme == key.scheme && k.public_key == key.public_key)
+            {
+                keys.push(key);
+            }
+        }
+        let accepted: Vec<String> = keys
+            .iter()
+            .filter(|key| {
+                self.signatures.iter().any(|s| {
+                    (s.keyid.is_empty() || s.keyid == key.keyid)
+                        && scheme_verify(key.scheme, &key.public_key, &data, &s.sig)
+                })
+            })
+            .map(|key| key.keyid.clone())
+            .collect();
+        let ok = match policy {
+            Policy::All => !keys.is_empty() && accepted.len() == keys.len(),
+            Policy::Any => !accepted.is_empty(),
+            Policy::Threshold(k) => k > 0 && accepted.len() >= k,
+        };
+        if !ok {
+            return Err(format!(
+                "dsse: {} of {} trusted keys signed, policy not satisfied",
+                accepted.len(),
+                keys.len()
+            ));
+        }
+        Ok(accepted)
+    }
+
+    /// 编码为 JSON 格式，载荷和签名为标准 base64
+    pub fn to_json(&self) -> String {
+        let envelope = JsonEnvelope {
+            payload: base64::encode(&self.payload),
+            payload_type: self.payload_type.clone(),
+            signatures: self
+                .signatures
+                .iter()
+                .map(|s| JsonSignature {
+                    keyid: s.keyid.clone(),
+                    sig: base64::encode(&s.sig),
+                })
+                .collect(),
+        };
+        serde_json::to_string(&envelope).expect("dsse: envelope serializes to json")
+    }
+
+    /// 解析 JSON 格式
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let envelope: JsonEnvelope =
+            serde_json::from_str(json).map_err(|e| format!("dsse: bad json envelope: {}", e))?;
+        let decode = |s: &str| base64::decode(s).map_err(|e| format!("dsse: bad base64: {}", e));
+        Ok(DsseEnvelope {
+            payload: decode(&envelope.payload)?,
+            payload_type: envelope.payload_type,
+            signatures: envelope
+                .signatures
+                .iter()
+                .map(|s| {
+                    Ok(DsseSignature {
+                        keyid: s.keyid.clone(),
+                        sig: decode(&s.sig)?,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        })
+    }
+
+    /// 编码为 protobuf 格式
+    ///
+    /// Envelope { bytes payload = 1; string payloadType = 2; repeated Signature signatures = 3; }
+    /// Signature { bytes sig = 1; string keyid = 2; }
+    pub fn to_proto(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        proto::put_bytes_field(&mut out, 1, &self.payload);
+        proto::put_bytes_field(&mut out, 2, self.payload_type.as_bytes());
+        for s in &self.signatures {
+            let mut sig = Vec::new();
+            proto::put_bytes_field(&mut sig, 1, &s.sig);
+            proto::put_bytes_field(&mut sig, 2, s.keyid.as_bytes());
+            proto::put_bytes_field(&mut out, 3, &sig);
+        }
+        out
+    }
+
+    /// 解析 protobuf 格式
+    pub fn from_proto(data: &[u8]) -> Result<Self, String> {
+        let text = |b: &[u8]| {
+            String::from_utf8(b.to_vec()).map_err(|_| "dsse: invalid utf-8 string".to_string())
+        };
+        let mut envelope = DsseEnvelope::new("", &[]);
+        for field in proto::parse(data)? {
+            match field {
+                (1, proto::Value::Bytes(b)) => envelope.payload = b.to_vec(),
+                (2, proto::Value::Bytes(b)) => envelope.payload_type = text(b)?,
+                (3, proto::Value::Bytes(b)) => {
+                    let mut sig = DsseSignature {
+                        keyid: String::new(),
+                        sig: Vec::new(),
+                    };
+                    for field in proto::parse(b)? {
+                        match field {
+                            (1, proto::Value::Bytes(b)) => sig.sig = b.to_vec(),
+                            (2, proto::Value::Bytes(b)) => sig.keyid = text(b)?,
+                            _ => {}
+                        }
+                    }
+                    envelope.signatures.push(sig);
+                }
+                _ => {}
+            }
+        }
+        Ok(envelope)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonEnvelope {
+    payload: String,
+    payload_type: String,
+    signatures: Vec<JsonSignature>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSignature {
+    #[serde(default)]
+    keyid: String,
+    sig: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ed25519;
+
+    #[test]
+    fn test_dsse() {
+        // https://github.com/secure-systems-lab/dsse/blob/master/protocol.md 中的示例
+        assert_eq!(
+            pae("http://example.com/HelloWorld", b"hello world"),
+            b"DSSEv1 29 http://example.com/HelloWorld 11 hello world".to_vec()
+        );
+
+        let (sk1, pk1) = ed25519::keypair(&[1u8; 32]);
+        let (sk2, pk2) = ed25519::keypair(&[2u8; 32]);
+        let trusted = vec![
+            TrustedKey {
+                keyid: "alice".to_string(),
+                scheme: SignatureScheme::Ed25519,
+                public_key: pk1.to_vec(),
+            },
+            TrustedKey {
+                keyid: "bob".to_string(),
+                scheme: SignatureScheme::Ed25519,
+                public_key: pk2.to_vec(),
+            },
+        ];
+
+        let mut envelope = DsseEnvelope::new("application/vnd.in-toto+json", b"{}");
+        envelope.sign_with("alice", |data| ed25519::signature(data, &sk1).to_vec());
+        assert_eq!(
+            envelope.verify(&trusted, Policy::Any).unwrap(),
+            vec!["alice".to_string()]
+        );
+        assert!(envelope.verify(&trusted, Policy::Threshold(2)).is_err());
+
+        // 同一把密钥以不同ID重复列出，仍只算一个
+        let mut duplicated = trusted.clone();
+        duplicated[1] = TrustedKey {
+            keyid: "alice-again".to_string(),
+            ..trusted[0].clone()
+        };
+        assert!(envelope.verify(&duplicated, Policy::Threshold(2)).is_err());
+        assert_eq!(
+            envelope.verify(&duplicated, Policy::All).unwrap(),
+            vec!["alice".to_string()]
+        );
+
+        envelope.sign_with("", |data| ed25519::signature(data, &sk2).to_vec());
+        let envelope = DsseEnvelope::from_json(&envelope.to_json()).unwrap();
+        let envelope = DsseEnvelope::from_proto(&envelope.to_proto()).unwrap();
+        assert_eq!(envelope.verify(&trusted, Policy::All).unwrap().len(), 2);
+
+        // 载荷类型参与签名
+        let mut retyped = envelope.clone();
+        retyped.payload_type = "text/plain".to_string();
+        assert!(retyped.verify(&trusted, Policy::Any).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! 短 Weierstrass 曲线 y^2 = x^3 + ax + b 上的点运算
+//!
+//! 供 sm2、brainpool 等 ring 不支持的曲线以及 ring 没有公开的点运算使用，曲线参数由调用方给出。
+//! 标量乘法使用固定迭代次数的 Montgomery 阶梯，迭代次数与标量的位长无关，
+//! 但底层的 num-bigint 运算不是常量时间的
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use crate::random::SecureRandom;
+
+/// 阶梯中按位交换两个点
+fn cswap(swap: bool, a: &mut Point, b: &mut Point) {
+    if swap {
+        std::mem::swap(a, b);
+    }
+}
+
+/// 雅可比坐标的点，z 为零表示无穷远点
+#[derive(Clone)]
+pub(crate) struct Point {
+    pub(crate) x: BigUint,
+    pub(crate) y: BigUint,
+    pub(crate) z: BigUint,
+}
+
+/// 曲线参数，size 为域元素的字节长度
+pub(crate) struct Curve {
+    pub(crate) p: BigUint,
+    pub(crate) a: BigUint,
+    pub(crate) b: BigUint,
+    pub(crate) n: BigUint,
+    pub(crate) g: Point,
+    pub(crate) size: usize,
+}
+
+pub(crate) fn hex_uint(s: &str) -> BigUint {
+    BigUint::parse_bytes(s.as_bytes(), 16).expect("ec: curve constant")
+}
+
+impl Curve {
+    /// 从十六进制的曲线参数构建
+    pub(crate) fn new(p: &str, a: &str, b: &str, n: &str, gx: &str, gy: &str) -> Self {
+        let p = hex_uint(p);
+        let size = p.bits().div_ceil(8);
+        Curve {
+            p,
+            a: hex_uint(a),
+            b: hex_uint(b),
+            n: hex_uint(n),
+            g: Point {
+                x: hex_uint(gx),
+                y: hex_uint(gy),
+                z: BigUint::one(),
+            },
+            size,
+        }
+    }
+
+    /// [1, bound-1] 内的随机数
+    pub(crate) fn random_scalar(&self, bound: &BigUint, rng: &dyn SecureRandom) -> BigUint {
+        loop {
+            let k = BigUint::from_bytes_be(&rng.gen_bytes(self.size));
+            if !k.is_zero() && k < *bound {
+                return k;
+            }
+        }
+    }
+
+    /// 模 n 的逆元，n 为素数
+    pub(crate) fn inv_n(&self, x: &BigUint) -> BigUint {
+        x.modpow(&(&self.n - BigUint::from(2u32)), &self.n)
+    }
+
+    /// 定长大端编码
+    pub(crate) fn to_bytes(&self, x: &BigUint) -> Vec<u8> {
+        let bytes = x.to_bytes_be();
+        let mut out = vec![0u8; self.size];
+        out[self.size - bytes.len()..].copy_from_slice(&bytes);
+        out
+    }
+
+    /// 编码为未压缩点 04 | x | y
+    pub(crate) fn encode(&self, p: &Point) -> Vec<u8> {
+        let (x, y) = self.to_affine(p).expect("ec: point is not infinity");
+        let mut out = vec![0x04];
+        out.extend_from_slice(&self.to_bytes(&x));
+        out.extend_from_slice(&self.to_bytes(&y));
+        out
+    }
+
+    /// 解码未压缩点并检查点在曲线上
+    pub(crate) fn decode(&self, pk: &[u8]) -> Result<Point, &'static str> {
+        if pk.len() != 1 + 2 * self.size || pk[0] != 0x04 {
+            return Err("public key must be an uncompressed point");
+        }
+        let x = BigUint::from_bytes_be(&pk[1..1 + self.size]);
+        let y = BigUint::from_bytes_be(&pk[1 + self.size..]);
+        let p = &self.p;
+        if x >= *p || y >= *p || (&y * &y) % p != (&x * &x * &x + &self.a * &x + &self.b) % p {
+            return Err("public key is not on the curve");
+        }
+        Ok(Point {
+            x,
+            y,
+            z: BigUint::one(),
+        })
+    }
+
+    pub(crate) fn to_affine(&self, pt: &Point) -> Option<(BigUint, BigUint)> {
+        if pt.z.is_zero() {
+            return None;
+        }
+        let p = &self.p;
+        let zinv = pt.z.modpow(&(p - BigUint::from(2u32)), p);
+        let zinv2 = (&zinv * &zinv) % p;
+        let x = (&pt.x * &zinv2) % p;
+        let y = (&pt.y * zinv2 * zinv) % p;
+        Some((x, y))
+    }
+
+    fn sub(&self, a: &BigUint, b: &BigUint) -> BigUint {
+        (a + &self.p - b) % &self.p
+    }
+
+    // 任意 a 的倍点公式: M = 3X^2 + aZ^4, S = 4XY^2
+    fn double(&self, pt: &Point) -> Point {
+        let p = &self.p;
+        if pt.z.is_zero() || pt.y.is_zero() {
+            return self.infinity();
+        }
+        let yy = (&pt.y * &pt.y) % p;
+        let zz = (&pt.z * &pt.z) % p;
+        let s = (BigUint::from(4u32) * &pt.x * &yy) % p;
+        let m = (BigUint::from(3u32) * &pt.x * &pt.x + &self.a * &zz * &zz) % p;
+        let x3 = self.sub(&((&m * &m) % p), &((&s + &s) % p));
+        let y3 = self.sub(
+            &((&m * self.sub(&s, &x3)) % p),
+            &((BigUint::from(8u32) * &yy * &yy) % p),
+        );
+        let z3 = (BigUint::from(2u32) * &pt.y * &pt.z) % p;
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    pub(crate) fn add(&self, a: &Point, b: &Point) -> Point {
+        if a.z.is_zero() {
+            return b.clone();
+        }
+        if b.z.is_zero() {
+            return a.clone();
+        }
+        let p = &self.p;
+        let z1z1 = (&a.z * &a.z) % p;
+        let z2z2 = (&b.z * &b.z) % p;
+        let u1 = (&a.x * &z2z2) % p;
+        let u2 = (&b.x * &z1z1) % p;
+        let s1 = (&a.y * &b.z * &z2z2) % p;
+        let s2 = (&b.y * &a.z * &z1z1) % p;
+        let h = self.sub(&u2, &u1);
+        let r = self.sub(&s2, &s1);
+        if h.is_zero() {
+            return if r.is_zero() {
+                self.double(a)
+            } else {
+                self.infinity()
+            };
+        }
+        let hh = (&h * &h) % p;
+        let hhh = (&h * &hh) % p;
+        let v = (&u1 * &hh) % p;
+        let x3 = self.sub(&self.sub(&((&r * &r) % p), &hhh), &((&v + &v) % p));
+        let y3 = self.sub(&((&r * self.sub(&v, &x3)) % p), &((&s1 * &hhh) % p));
+        let z3 = (&a.z * &b.z * &h) % p;
+        Point {
+            x: x3,
+            y: y3,
+            z: z3,
+        }
+    }
+
+    /// k * pt，pt 的阶必须整除 n (余因子为1的曲线上的任意点)
+    ///
+    /// 先把 k 加上 n 或 2n，使其恰好为 n.bits() + 1 位，再做固定 n.bits() + 1 次迭代的阶梯，
+    /// 每次迭代都做一次点加和一次倍点，循环次数不再泄露 k 的前导零个数
+    pub(crate) fn mul(&self, k: &BigUint, pt: &Point) -> Point {
+        let bits = self.n.bits() + 1;
+        let k = k % &self.n + &self.n;
+        let k = if k.bits() < bits { k + &self.n } else { k };
+        let mut r0 = self.infinity();
+        let mut r1 = pt.clone();
+        for i in (0..bits).rev() {
+            let bit = ((&k >> i) & BigUint::one()).is_one();
+            cswap(bit, &mut r0, &mut r1);
+            r1 = self.add(&r0, &r1);
+            r0 = self.double(&r0);
+            cswap(bit, &mut r0, &mut r1);
+        }
+        r0
+    }
+
+    pub(crate) fn mul_g(&self, k: &BigUint) -> Point {
+        self.mul(k, &self.g)
+    }
+
+    pub(crate) fn infinity(&self) -> Point {
+        Point {
+            x: BigUint::one(),
+            y: BigUint::one(),
+            z: BigUint::zero(),
+        }
+    }
+}
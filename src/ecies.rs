@@ -0,0 +1,267 @@
+//! 基于 secp256k1 的 ECIES 公钥加密
+//!
+//! 每次加密生成临时密钥对，与接收方公钥做 ECDH，由共享点派生对称密钥后加密并认证。
+//! 不同实现的密钥派生和封装格式互不兼容，用 EciesMode 选择对端使用的实现
+
+use ring::{digest, hmac};
+use secp256k1::{PublicKey, SecretKey};
+
+use crate::cipher::{aes_ctr_encrypt, encryptor};
+use crate::ct::ct_eq;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::kdf::{hkdf, ConcatKdf, Kdf, KdfHash};
+use crate::random::{OsRng, SecureRandom};
+use crate::secret::SecretBytes;
+use crate::sm4::gf_mul;
+
+const PUBLIC_KEY_SIZE: usize = 65;
+const BLOCK_SIZE: usize = 16;
+
+/// ECIES 的密钥派生和封装格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EciesMode {
+    /// eciespy / eciesjs 的默认配置：HKDF-SHA256(临时公钥 | 共享点) 派生密钥，AES-256-GCM 加密，
+    /// 输出为 65字节临时公钥 | 16字节nonce | 16字节tag | 密文
+    #[default]
+    Eciespy,
+    /// geth (crypto/ecies) 和 devp2p 使用的 eth-ecies：ConcatKDF-SHA256(共享点x) 派生密钥，
+    /// AES-128-CTR 加密，HMAC-SHA256 认证，输出为 65字节临时公钥 | 16字节IV | 密文 | 32字节MAC
+    Geth,
+}
+
+/// 加密到接收方公钥
+///
+/// mode: 封装格式
+/// pk: 接收方公钥，33或65字节
+/// plaintext: 明文
+pub fn ecies_encrypt(mode: EciesMode, pk: &[u8], plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    ecies_encrypt_with_rng(mode, pk, plaintext, &OsRng)
+}
+
+/// 使用指定的随机数生成器产生临时密钥和nonce，参数同 ecies_encrypt
+pub fn ecies_encrypt_with_rng(
+    mode: EciesMode,
+    pk: &[u8],
+    plaintext: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<Vec<u8>, CryptoError> {
+    let recipient = public_key(pk)?;
+    let ephemeral = loop {
+        let mut sk = SecretBytes::zeroed(32);
+        rng.fill(sk.as_mut_bytes());
+        if let Ok(sk) = SecretKey::parse_slice(&sk) {
+            break sk;
+        }
+    };
+    let ephemeral_pk = PublicKey::from_secret_key(&ephemeral).serialize();
+    let shared = shared_point(&recipient, &ephemeral)?;
+    let mut out = ephemeral_pk.to_vec();
+    match mode {
+        EciesMode::Eciespy => {
+            let key = eciespy_key(&ephemeral_pk, &shared)?;
+            let nonce = rng.gen_bytes(BLOCK_SIZE);
+            let mut ct = plaintext.to_vec();
+            let tag = gcm(&key, &nonce, &[], &mut ct, true)?;
+            out.extend_from_slice(&nonce);
+            out.extend_from_slice(&tag);
+            out.extend_from_slice(&ct);
+        }
+        EciesMode::Geth => {
+            let (ke, km) = geth_keys(&shared)?;
+            let iv = rng.gen_bytes(BLOCK_SIZE);
+            let start = out.len();
+            out.extend_from_slice(&iv);
+            out.extend_from_slice(
+                &aes_ctr_encrypt(&ke, &iv, plaintext).map_err(CryptoError::InvalidKey)?,
+            );
+            let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, &km), &out[start..]);
+            out.extend_from_slice(tag.as_ref());
+        }
+    }
+    Ok(out)
+}
+
+/// 用接收方私钥解密
+///
+/// mode: 封装格式，必须与加密时相同
+/// sk: 32字节私钥
+/// ciphertext: ecies_encrypt 的输出
+pub fn ecies_decrypt(
+    mode: EciesMode,
+    sk: &[u8],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let sk = SecretKey::parse_slice(sk).map_err(|_| {
+        CryptoError::InvalidKey("ecies: secret key must be 32 bytes in [1, n-1]".to_string())
+    })?;
+    let overhead = match mode {
+        EciesMode::Eciespy => PUBLIC_KEY_SIZE + 2 * BLOCK_SIZE,
+        EciesMode::Geth => PUBLIC_KEY_SIZE + BLOCK_SIZE + 32,
+    };
+    if ciphertext.len() < overhead {
+        return Err(CryptoError::InvalidInput(
+            "ecies: ciphertext too short".to_string(),
+        ));
+    }
+    let (ephemeral_pk, body) = ciphertext.split_at(PUBLIC_KEY_SIZE);
+    let ephemeral = public_key(ephemeral_pk)?;
+    let shared = shared_point(&ephemeral, &sk)?;
+    let failed = || CryptoError::Verify(VerifyFailure::DigestMismatch);
+    match mode {
+        EciesMode::Eciespy => {
+            let key = eciespy_key(&ephemeral.serialize(), &shared)?;
+            let (nonce, rest) = body.split_at(BLOCK_SIZE);
+            let (tag, ct) = rest.split_at(BLOCK_SIZE);
+            let mut pt = ct.to_vec();
+            if !ct_eq(&gcm(&key, nonce, &[], &mut pt, false)?, tag) {
+                return Err(failed());
+            }
+            Ok(pt)
+        }
+        EciesMode::Geth => {
+            let (ke, km) = geth_keys(&shared)?;
+            let (em, tag) = body.split_at(body.len() - 32);
+            hmac::verify(&hmac::Key::new(hmac::HMAC_SHA256, &km), em, tag).map_err(|_| failed())?;
+            let (iv, ct) = em.split_at(BLOCK_SIZE);
+            aes_ctr_encrypt(&ke, iv, ct).map_err(CryptoError::InvalidKey)
+        }
+    }
+}
+
+fn public_key(pk: &[u8]) -> Result<PublicKey, CryptoError> {
+    if pk.len() != 33 && pk.len() != PUBLIC_KEY_SIZE {
+        return Err(CryptoError::InvalidKey(
+            "ecies: public key must be 33 or 65 bytes".to_string(),
+        ));
+    }
+    PublicKey::parse_slice(pk, None)
+        .map_err(|_| CryptoError::InvalidKey("ecies: invalid public key".to_string()))
+}
+
+/// 未压缩编码的共享点
+fn shared_point(pk: &PublicKey, sk: &SecretKey) -> Result<[u8; PUBLIC_KEY_SIZE], CryptoError> {
+    let mut shared = pk.clone();
+    shared
+        .tweak_mul_assign(sk)
+        .map_err(|_| CryptoError::OperationFailed("ecies: ecdh failed".to_string()))?;
+    Ok(shared.serialize())
+}
+
+fn eciespy_key(ephemeral_pk: &[u8], shared: &[u8]) -> Result<SecretBytes, CryptoError> {
+    hkdf(&[], &[ephemeral_pk, shared].concat(), &[], 32)
+        .map(SecretBytes::new)
+        .map_err(CryptoError::InvalidInput)
+}
+
+/// 加密密钥 Ke 和 MAC 密钥 Km = SHA256(K[16..32])
+fn geth_keys(shared: &[u8]) -> Result<(SecretBytes, SecretBytes), CryptoError> {
+    let k = SecretBytes::new(
+        ConcatKdf {
+            hash: KdfHash::SHA256,
+        }
+        .derive(&shared[1..33], &[], &[], 32)
+        .map_err(CryptoError::InvalidInput)?,
+    );
+    let km = digest::digest(&digest::SHA256, &k[16..]);
+    Ok((SecretBytes::from(&k[..16]), SecretBytes::from(km.as_ref())))
+}
+
+/// 支持任意长度nonce的 AES-GCM (NIST SP 800-38D)，ring 只支持12字节nonce，eciespy 默认使用16字节
+///
+/// 原地加密或解密 data，返回根据密文计算的tag
+fn gcm(
+    key: &[u8],
+    nonce: &[u8],
+    aad: &[u8],
+    data: &mut [u8],
+    encrypt: bool,
+) -> Result<[u8; BLOCK_SIZE], CryptoError> {
+    let cipher = encryptor(key).map_err(CryptoError::InvalidKey)?;
+    let mut h = [0u8; BLOCK_SIZE];
+    cipher.encrypt_block(&[0u8; BLOCK_SIZE], &mut h);
+    let h = u128::from_be_bytes(h);
+    // J0 = GHASH(nonce | 0填充 | 0^64 | [len(nonce)]_64)
+    let j0 = if nonce.len() == 12 {
+        let mut j0 = [0u8; BLOCK_SIZE];
+        j0[..12].copy_from_slice(nonce);
+        j0[BLOCK_SIZE - 1] = 1;
+        j0
+    } else {
+        ghash(h, &[], nonce).to_be_bytes()
+    };
+
+    let ctr = |data: &mut [u8]| {
+        let mut counter = u32::from_be_bytes([j0[12], j0[13], j0[14], j0[15]]);
+        let mut keystream = [0u8; BLOCK_SIZE];
+        for chunk in data.chunks_mut(BLOCK_SIZE) {
+            counter = counter.wrapping_add(1);
+            let mut block = j0;
+            block[12..].copy_from_slice(&counter.to_be_bytes());
+            cipher.encrypt_block(&block, &mut keystream);
+            for (d, k) in chunk.iter_mut().zip(keystream.iter()) {
+                *d ^= k;
+            }
+        }
+    };
+    let y = if encrypt {
+        ctr(data);
+        ghash(h, aad, data)
+    } else {
+        let y = ghash(h, aad, data);
+        ctr(data);
+        y
+    };
+    let mut tag = [0u8; BLOCK_SIZE];
+    cipher.encrypt_block(&j0, &mut tag);
+    Ok((u128::from_be_bytes(tag) ^ y).to_be_bytes())
+}
+
+fn ghash(h: u128, aad: &[u8], data: &[u8]) -> u128 {
+    let mut y = 0u128;
+    for part in [aad, data].iter() {
+        for chunk in part.chunks(BLOCK_SIZE) {
+            let mut block = [0u8; BLOCK_SIZE];
+            block[..chunk.len()].copy_from_slice(chunk);
+            y = gf_mul(y ^ u128::from_be_bytes(block), h);
+        }
+    }
+    let lens = ((aad.len() as u128 * 8) << 64) | (data.len() as u128 * 8);
+    gf_mul(y ^ lens, h)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_ecies() {
+        // GCM spec (McGrew & Viega) Test Case 6，60字节IV，验证非12字节nonce的 J0 计算
+        let key = Vec::from_hex("feffe9928665731c6d6a8f9467308308").unwrap();
+        let iv = Vec::from_hex("9313225df88406e555909c5aff5269aa6a7a9538534f7da1e4c303d2a318a728c3c0c95156809539fcf0e2429a6b525416aedbf5a0de6a57a637b39b").unwrap();
+        let mut data = Vec::from_hex("d9313225f88406e5a55909c5aff5269a86a7a9531534f7da2e4c303d8a318a721c3c0c95956809532fcf0e2449a6b525b16aedf5aa0de657ba637b39").unwrap();
+        let aad = Vec::from_hex("feedfacedeadbeeffeedfacedeadbeefabaddad2").unwrap();
+        let tag = gcm(&key, &iv, &aad, &mut data, true).unwrap();
+        assert_eq!(data, Vec::from_hex("8ce24998625615b603a033aca13fb894be9112a5c3a211a8ba262a3cca7e2ca701e4a9a4fba43c90ccdcb281d48c7c6fd62875d2aca417034c34aee5").unwrap());
+        assert_eq!(
+            tag.to_vec(),
+            Vec::from_hex("619cc5aefffe0bfa462af43c1699d050").unwrap()
+        );
+        assert_eq!(gcm(&key, &iv, &aad, &mut data, false).unwrap(), tag);
+
+        let sk = Vec::from_hex("4646464646464646464646464646464646464646464646464646464646464646")
+            .unwrap();
+        let pk = PublicKey::from_secret_key(&SecretKey::parse_slice(&sk).unwrap());
+        for mode in [EciesMode::Eciespy, EciesMode::Geth] {
+            for pk in [&pk.serialize()[..], &pk.serialize_compressed()[..]] {
+                let sealed = ecies_encrypt(mode, pk, b"hello ecies").unwrap();
+                assert_eq!(ecies_decrypt(mode, &sk, &sealed).unwrap(), b"hello ecies");
+                let mut tampered = sealed.clone();
+                *tampered.last_mut().unwrap() ^= 1;
+                assert!(ecies_decrypt(mode, &sk, &tampered).is_err());
+            }
+        }
+        let sealed = ecies_encrypt(EciesMode::Geth, &pk.serialize(), b"").unwrap();
+        assert!(ecies_decrypt(EciesMode::Eciespy, &sk, &sealed).is_err());
+    }
+}
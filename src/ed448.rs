@@ -0,0 +1,455 @@
+//! Ed448 签名 (RFC 8032 5.2)
+//!
+//! 密钥编码与 ed25519 模块一致，使用 RFC 8032 的原始格式：私钥为57字节种子，公钥为57字节点编码
+//! (小端序 y 坐标，最高位为 x 的符号位)，签名为114字节 R | S。
+//! Ed448 总是带上下文，sign 和 verify 使用空上下文，sign_with_context 用于需要域分离的协议，
+//! Ed448ph 对消息的 SHAKE256 64字节摘要签名。
+//!
+//! 域运算使用 field448 模块的常量时间实现，标量乘法固定迭代448次，每次做一次加法和一次倍点，
+//! 按掩码条件交换；标量模 L 的运算仍基于 num-bigint
+
+use crypto::digest::Digest as _;
+use crypto::sha3::Sha3;
+use num_bigint::BigUint;
+
+use crate::ct::wipe;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::field448::{self, Fe};
+use crate::metrics::{Operation, Timer};
+use crate::random::{OsRng, SecureRandom};
+
+/// 私钥长度
+pub const SECRET_KEY_SIZE: usize = 57;
+
+/// 公钥长度
+pub const PUBLIC_KEY_SIZE: usize = 57;
+
+/// 签名长度
+pub const SIGNATURE_SIZE: usize = 114;
+
+/// Ed448ph 使用的消息摘要长度
+pub const PREHASH_SIZE: usize = 64;
+
+const GX: &str = "4f1970c66bed0ded221d15a622bf36da9e146570470f1767ea6de324a3d3a46412ae1af72ab66511433b80e18b00938e2626a82bc70cc05e";
+const GY: &str = "693f46716eb6bc248876203756c9c7624bea73736ca3984087789c1e05a0c2d73ad3ff1ce67c39c4fdbd132c4ed7c8ad9808795bf230fa14";
+const L: &str = "3fffffffffffffffffffffffffffffffffffffffffffffffffffffff7cca23e9c44edb49aed63690216cc2728dc58f552378c292ab5844f3";
+
+/// 使用系统随机数生成密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair() -> ([u8; SECRET_KEY_SIZE], [u8; PUBLIC_KEY_SIZE]) {
+    generate_keypair_with_rng(&OsRng)
+}
+
+/// 使用指定的随机数生成器生成密钥对，返回值同 generate_keypair
+pub fn generate_keypair_with_rng(
+    rng: &dyn SecureRandom,
+) -> ([u8; SECRET_KEY_SIZE], [u8; PUBLIC_KEY_SIZE]) {
+    let mut sk = [0u8; SECRET_KEY_SIZE];
+    rng.fill(&mut sk);
+    let pk = public_key(&sk).expect("ed448: 57 byte secret key");
+    (sk, pk)
+}
+
+/// 由私钥计算公钥
+///
+/// secret_key: 57字节私钥
+pub fn public_key(secret_key: &[u8]) -> Result<[u8; PUBLIC_KEY_SIZE], CryptoError> {
+    let curve = Curve::new();
+    let (s, _) = expand(secret_key)?;
+    Ok(curve.encode(&curve.mul(&s, &curve.g)))
+}
+
+/// Ed448 签名，上下文为空
+///
+/// message: 待签名的数据，secret_key: 57字节私钥
+pub fn sign(message: &[u8], secret_key: &[u8]) -> Result<[u8; SIGNATURE_SIZE], CryptoError> {
+    sign_with_context(message, secret_key, &[])
+}
+
+/// Ed448 签名验证，上下文为空
+///
+/// message: 签名数据，public_key: 公钥，signature: 签名，返回验证是否成功
+pub fn verify(message: &[u8], public_key: &[u8], signature: &[u8]) -> bool {
+    verify_detailed(message, public_key, signature).is_ok()
+}
+
+/// Ed448 签名验证，上下文为空，失败时返回原因
+pub fn verify_detailed(
+    message: &[u8],
+    public_key: &[u8],
+    signature: &[u8],
+) -> Result<(), VerifyFailure> {
+    verify_dom(
+        &dom4(0, &[]).expect("empty context"),
+        message,
+        public_key,
+        signature,
+    )
+}
+
+/// 带上下文的 Ed448 签名
+///
+/// message: 待签名的数据，secret_key: 57字节私钥，context: 上下文，最多255字节，可以为空
+pub fn sign_with_context(
+    message: &[u8],
+    secret_key: &[u8],
+    context: &[u8],
+) -> Result<[u8; SIGNATURE_SIZE], CryptoError> {
+    sign_dom(&dom4(0, context)?, message, secret_key)
+}
+
+/// 带上下文的 Ed448 签名验证
+///
+/// message: 签名数据，public_key: 公钥，signature: 签名，context: 签名时使用的上下文
+pub fn verify_with_context(
+    message: &[u8],
+    public_key: &[u8],
+    signature: &[u8],
+    context: &[u8],
+) -> bool {
+    dom4(0, context)
+        .map(|dom| verify_dom(&dom, message, public_key, signature).is_ok())
+        .unwrap_or(false)
+}
+
+/// 计算 Ed448ph 使用的消息摘要 SHAKE256(message, 64)
+pub fn prehash(message: &[u8]) -> [u8; PREHASH_SIZE] {
+    let mut out = [0u8; PREHASH_SIZE];
+    shake256(&[message], &mut out);
+    out
+}
+
+/// Ed448ph 签名，对消息的 SHAKE256 摘要签名，适合只对大消息哈希一次的场景
+///
+/// digest: prehash 计算的64字节摘要，secret_key: 57字节私钥，context: 上下文，最多255字节，可以为空
+pub fn sign_prehashed(
+    digest: &[u8],
+    secret_key: &[u8],
+    context: &[u8],
+) -> Result<[u8; SIGNATURE_SIZE], CryptoError> {
+    if digest.len() != PREHASH_SIZE {
+        return Err(CryptoError::InvalidInput(format!(
+            "ed448: prehashed digest must be 64 bytes, got {}",
+            digest.len()
+        )));
+    }
+    sign_dom(&dom4(1, context)?, digest, secret_key)
+}
+
+/// Ed448ph 签名验证
+///
+/// digest: 消息摘要，public_key: 公钥，signature: 签名，context: 签名时使用的上下文
+pub fn verify_prehashed(
+    digest: &[u8],
+    public_key: &[u8],
+    signature: &[u8],
+    context: &[u8],
+) -> bool {
+    digest.len() == PREHASH_SIZE
+        && dom4(1, context)
+            .map(|dom| verify_dom(&dom, digest, public_key, signature).is_ok())
+            .unwrap_or(false)
+}
+
+// RFC 8032 5.2.6
+fn sign_dom(
+    dom: &[u8],
+    message: &[u8],
+    secret_key: &[u8],
+) -> Result<[u8; SIGNATURE_SIZE], CryptoError> {
+    let timer = Timer::start(Operation::Sign, "ed448");
+    let curve = Curve::new();
+    let (s, mut prefix) = expand(secret_key)?;
+    let pk = curve.encode(&curve.mul(&s, &curve.g));
+    let r = curve.hash_scalar(&[dom, &prefix, message]);
+    wipe(&mut prefix);
+    let big_r = curve.encode(&curve.mul(&r, &curve.g));
+    let k = curve.hash_scalar(&[dom, &big_r, &pk, message]);
+    let big_s = (r + k * s) % &curve.l;
+    let mut sig = [0u8; SIGNATURE_SIZE];
+    sig[..PUBLIC_KEY_SIZE].copy_from_slice(&big_r);
+    let bytes = big_s.to_bytes_le();
+    sig[PUBLIC_KEY_SIZE..PUBLIC_KEY_SIZE + bytes.len()].copy_from_slice(&bytes);
+    Ok(timer.done(sig, true))
+}
+
+// RFC 8032 5.2.7，使用乘以余因子4的验证等式
+fn verify_dom(
+    dom: &[u8],
+    message: &[u8],
+    public_key: &[u8],
+    signature: &[u8],
+) -> Result<(), VerifyFailure> {
+    let timer = Timer::start(Operation::Verify, "ed448");
+    for (expected, actual) in [
+        (PUBLIC_KEY_SIZE, public_key.len()),
+        (SIGNATURE_SIZE, signature.len()),
+    ] {
+        if expected != actual {
+            return Err(VerifyFailure::WrongLength { expected, actual });
+        }
+    }
+    let curve = Curve::new();
+    let a = curve
+        .decode(public_key)
+        .ok_or(VerifyFailure::MalformedKey)?;
+    let (big_r, big_s) = signature.split_at(PUBLIC_KEY_SIZE);
+    let r = curve
+        .decode(big_r)
+        .ok_or(VerifyFailure::MalformedSignature)?;
+    let s = BigUint::from_bytes_le(big_s);
+    if s >= curve.l {
+        return Err(VerifyFailure::MalformedSignature);
+    }
+    let k = curve.hash_scalar(&[dom, big_r, public_key, message]);
+    let lhs = curve.mul_by_cofactor(&curve.mul(&s, &curve.g));
+    let rhs = curve.mul_by_cofactor(&curve.add(&r, &curve.mul(&k, &a)));
+    let result = if curve.eq(&lhs, &rhs) {
+        Ok(())
+    } else {
+        Err(VerifyFailure::SignatureInvalid)
+    };
+    timer.done_result(result)
+}
+
+// dom4(phflag, context)，Ed448 和 Ed448ph 的域分离前缀
+fn dom4(flag: u8, context: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if context.len() > 255 {
+        return Err(CryptoError::InvalidInput(
+            "ed448: context must be at most 255 bytes".to_string(),
+        ));
+    }
+    let mut dom = b"SigEd448".to_vec();
+    dom.push(flag);
+    dom.push(context.len() as u8);
+    dom.extend_from_slice(context);
+    Ok(dom)
+}
+
+// 私钥的 SHAKE256 扩展，返回钳位后的标量 s 和用于生成签名随机数的 prefix
+fn expand(secret_key: &[u8]) -> Result<(BigUint, [u8; SECRET_KEY_SIZE]), CryptoError> {
+    if secret_key.len() != SECRET_KEY_SIZE {
+        return Err(CryptoError::InvalidKey(format!(
+            "ed448: secret key must be 57 bytes, got {}",
+            secret_key.len()
+        )));
+    }
+    let mut h = [0u8; 2 * SECRET_KEY_SIZE];
+    shake256(&[secret_key], &mut h);
+    let mut prefix = [0u8; SECRET_KEY_SIZE];
+    prefix.copy_from_slice(&h[SECRET_KEY_SIZE..]);
+    let a = &mut h[..SECRET_KEY_SIZE];
+    a[0] &= 0xfc;
+    a[55] |= 0x80;
+    a[56] = 0;
+    let s = BigUint::from_bytes_le(a);
+    wipe(&mut h);
+    Ok((s, prefix))
+}
+
+fn shake256(parts: &[&[u8]], out: &mut [u8]) {
+    let mut hasher = Sha3::shake_256();
+    for part in parts {
+        hasher.input(part);
+    }
+    hasher.result(out);
+}
+
+// 小于 2^448 的标量或域元素常量的56字节小端序
+fn scalar_bytes(k: &BigUint) -> [u8; field448::SIZE] {
+    let mut out = [0u8; field448::SIZE];
+    let bytes = k.to_bytes_le();
+    out[..bytes.len()].copy_from_slice(&bytes);
+    out
+}
+
+/// 射影坐标 (X : Y : Z)，x = X/Z，y = Y/Z
+#[derive(Clone, Copy)]
+struct Point {
+    x: Fe,
+    y: Fe,
+    z: Fe,
+}
+
+impl Point {
+    const IDENTITY: Point = Point {
+        x: Fe::ZERO,
+        y: Fe::ONE,
+        z: Fe::ONE,
+    };
+
+    fn cswap(a: &mut Point, b: &mut Point, choice: u64) {
+        Fe::cswap(&mut a.x, &mut b.x, choice);
+        Fe::cswap(&mut a.y, &mut b.y, choice);
+        Fe::cswap(&mut a.z, &mut b.z, choice);
+    }
+}
+
+/// 曲线 x^2 + y^2 = 1 + d x^2 y^2，p = 2^448 - 2^224 - 1，d = -39081
+struct Curve {
+    d: Fe,
+    l: BigUint,
+    g: Point,
+}
+
+impl Curve {
+    fn new() -> Self {
+        let hex = |s: &str| BigUint::parse_bytes(s.as_bytes(), 16).expect("valid constant");
+        let fe = |s: &str| Fe::from_bytes(&scalar_bytes(&hex(s)));
+        Curve {
+            d: -Fe::from_u64(39081),
+            l: hex(L),
+            g: Point {
+                x: fe(GX),
+                y: fe(GY),
+                z: Fe::ONE,
+            },
+        }
+    }
+
+    // RFC 8032 5.2.4，对 a = 1 的 Edwards 曲线是完备公式，也用于倍点
+    fn add(&self, p1: &Point, p2: &Point) -> Point {
+        let a = p1.z * p2.z;
+        let b = a.square();
+        let c = p1.x * p2.x;
+        let d = p1.y * p2.y;
+        let e = self.d * c * d;
+        let f = b - e;
+        let g = b + e;
+        let h = (p1.x + p1.y) * (p2.x + p2.y) - c - d;
+        Point {
+            x: a * f * h,
+            y: a * g * (d - c),
+            z: f * g,
+        }
+    }
+
+    // 从最高位开始的阶梯，r0 和 r1 的差始终为 point，标量小于 2^448
+    fn mul(&self, k: &BigUint, point: &Point) -> Point {
+        let mut bytes = scalar_bytes(k);
+        let (mut r0, mut r1) = (Point::IDENTITY, *point);
+        let mut swap = 0u64;
+        for i in (0..field448::SIZE * 8).rev() {
+            let bit = u64::from((bytes[i / 8] >> (i % 8)) & 1);
+            swap ^= bit;
+            Point::cswap(&mut r0, &mut r1, swap);
+            swap = bit;
+            r1 = self.add(&r0, &r1);
+            r0 = self.add(&r0, &r0);
+        }
+        Point::cswap(&mut r0, &mut r1, swap);
+        wipe(&mut bytes);
+        r0
+    }
+
+    fn mul_by_cofactor(&self, point: &Point) -> Point {
+        let double = self.add(point, point);
+        self.add(&double, &double)
+    }
+
+    fn eq(&self, p1: &Point, p2: &Point) -> bool {
+        (p1.x * p2.z - p2.x * p1.z).is_zero() && (p1.y * p2.z - p2.y * p1.z).is_zero()
+    }
+
+    fn encode(&self, point: &Point) -> [u8; PUBLIC_KEY_SIZE] {
+        let zinv = point.z.invert();
+        let x = point.x * zinv;
+        let y = point.y * zinv;
+        let mut out = [0u8; PUBLIC_KEY_SIZE];
+        out[..field448::SIZE].copy_from_slice(&y.to_bytes());
+        if x.is_odd() {
+            out[PUBLIC_KEY_SIZE - 1] |= 0x80;
+        }
+        out
+    }
+
+    // RFC 8032 5.2.3
+    fn decode(&self, bytes: &[u8]) -> Option<Point> {
+        let sign = bytes[PUBLIC_KEY_SIZE - 1] & 0x80 != 0;
+        if bytes[PUBLIC_KEY_SIZE - 1] & 0x7f != 0 {
+            return None;
+        }
+        let mut y_bytes = [0u8; field448::SIZE];
+        y_bytes.copy_from_slice(&bytes[..field448::SIZE]);
+        let y = Fe::from_bytes(&y_bytes);
+        // 拒绝大于等于 p 的非规范编码
+        if y.to_bytes() != y_bytes {
+            return None;
+        }
+        let yy = y.square();
+        let u = yy - Fe::ONE;
+        let v = self.d * yy - Fe::ONE;
+        // p = 3 (mod 4)，x = u^3 v (u^5 v^3)^((p-3)/4)
+        let u3v = u.square() * u * v;
+        let u5v3 = u3v * u.square() * v.square();
+        let mut exp = [0xffu8; field448::SIZE];
+        exp[27] = 0xbf;
+        exp[55] = 0x3f;
+        let mut x = u3v * u5v3.pow(&exp);
+        if !(v * x.square() - u).is_zero() {
+            return None;
+        }
+        if x.is_zero() && sign {
+            return None;
+        }
+        if x.is_odd() != sign {
+            x = -x;
+        }
+        Some(Point { x, y, z: Fe::ONE })
+    }
+
+    fn hash_scalar(&self, parts: &[&[u8]]) -> BigUint {
+        let mut h = [0u8; 2 * SECRET_KEY_SIZE];
+        shake256(parts, &mut h);
+        BigUint::from_bytes_le(&h) % &self.l
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_ed448() {
+        // RFC 8032 7.4 "Blank"
+        let sk = Vec::from_hex("6c82a562cb808d10d632be89c8513ebf6c929f34ddfa8c9f63c9960ef6e348a3528c8a3fcc2f044e39a3fc5b94492f8f032e7549a20098f95b").unwrap();
+        let pk = Vec::from_hex("5fd7449b59b461fd2ce787ec616ad46a1da1342485a70e1f8a0ea75d80e96778edf124769b46c7061bd6783df1e50f6cd1fa1abeafe8256180").unwrap();
+        let expected = Vec::from_hex("533a37f6bbe457251f023c0d88f976ae2dfb504a843e34d2074fd823d41a591f2b233f034f628281f2fd7a22ddd47d7828c59bd0a21bfd3980ff0d2028d4b18a9df63e006c5d1c2d345b925d8dc00b4104852db99ac5c7cdda8530a113a0f4dbb61149f05a7363268c71d95808ff2e652600").unwrap();
+        assert_eq!(public_key(&sk).unwrap().to_vec(), pk);
+        let sig = sign(b"", &sk).unwrap();
+        assert_eq!(sig.to_vec(), expected);
+        assert!(verify(b"", &pk, &sig));
+        assert!(!verify(b"x", &pk, &sig));
+        assert!(!verify_with_context(b"", &pk, &sig, b"ctx"));
+        let spki = Vec::from_hex("3043300506032b6571033a00").unwrap();
+        assert_eq!(
+            crate::spki::encode_spki(crate::spki::PublicKeyType::Ed448, &pk).unwrap(),
+            [&spki[..], &pk[..]].concat()
+        );
+
+        // RFC 8032 7.4 "1 octet (with context)"
+        let sk = Vec::from_hex("c4eab05d357007c632f3dbb48489924d552b08fe0c353a0d4a1f00acda2c463afbea67c5e8d2877c5e3bc397a659949ef8021e954e0a12274e").unwrap();
+        let pk = Vec::from_hex("43ba28f430cdff456ae531545f7ecd0ac834a55d9358c0372bfa0c6c6798c0866aea01eb00742802b8438ea4cb82169c235160627b4c3a9480").unwrap();
+        let expected = Vec::from_hex("d4f8f6131770dd46f40867d6fd5d5055de43541f8c5e35abbcd001b32a89f7d2151f7647f11d8ca2ae279fb842d607217fce6e042f6815ea000c85741de5c8da1144a6a1aba7f96de42505d7a7298524fda538fccbbb754f578c1cad10d54d0d5428407e85dcbc98a49155c13764e66c3c00").unwrap();
+        let sig = sign_with_context(&[0x03], &sk, b"foo").unwrap();
+        assert_eq!(sig.to_vec(), expected);
+        assert!(verify_with_context(&[0x03], &pk, &sig, b"foo"));
+        assert!(!verify(&[0x03], &pk, &sig));
+
+        let mut bad = sig;
+        bad[SIGNATURE_SIZE - 1] = 0xff;
+        assert_eq!(
+            verify_detailed(&[0x03], &pk, &bad),
+            Err(VerifyFailure::MalformedSignature)
+        );
+        assert_eq!(
+            verify_detailed(&[0x03], &pk[1..], &sig),
+            Err(VerifyFailure::WrongLength {
+                expected: 57,
+                actual: 56
+            })
+        );
+        assert!(sign(b"", &sk[1..]).is_err());
+        assert!(sign_with_context(b"", &sk, &[0u8; 256]).is_err());
+    }
+}
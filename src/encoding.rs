@@ -0,0 +1,9 @@
+//! 二进制数据的文本编码
+//!
+//! 密钥、地址和签名在序列化时使用的编码，解码时对格式错误返回明确的错误。
+//! hex 和 base64 是常量时间实现，可以用于秘密数据
+
+pub mod base58;
+pub mod base64;
+pub mod bech32;
+pub mod hex;
@@ -0,0 +1,188 @@
+//! Base58 和 Base58Check 编码
+//!
+//! 使用比特币字母表。Base58Check 在数据后附加 sha256d 的前4字节作为校验码，
+//! 地址、WIF 私钥和 BIP-32 扩展密钥都使用这一格式，版本前缀区分数据的类型和网络
+
+use std::fmt;
+
+use crate::ct::ct_eq;
+use crate::digest::checksum4;
+
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+const CHECKSUM_LEN: usize = 4;
+
+/// Base58 解码错误
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Base58Error {
+    /// 不在字母表中的字符，index 为字节偏移
+    InvalidCharacter { character: char, index: usize },
+    /// 数据短于校验码或版本前缀
+    TooShort,
+    /// 校验码与数据不符，多为输入错误
+    BadChecksum { expected: [u8; 4], actual: [u8; 4] },
+    /// 版本前缀与预期不符，如把测试网地址用于主网
+    UnexpectedVersion { expected: Vec<u8>, actual: Vec<u8> },
+}
+
+impl fmt::Display for Base58Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Base58Error::InvalidCharacter { character, index } => write!(
+                f,
+                "base58: invalid character {:?} at position {}",
+                character, index
+            ),
+            Base58Error::TooShort => f.write_str("base58: data too short"),
+            Base58Error::BadChecksum { .. } => f.write_str("base58: checksum mismatch"),
+            Base58Error::UnexpectedVersion { expected, actual } => write!(
+                f,
+                "base58: unexpected version {:02x?}, expected {:02x?}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Base58Error {}
+
+/// 供使用字符串错误的模块用 ? 传递
+impl From<Base58Error> for String {
+    fn from(e: Base58Error) -> Self {
+        e.to_string()
+    }
+}
+
+/// Base58 编码，每个前导零字节编码为一个 '1'
+pub fn encode(data: &[u8]) -> String {
+    let zeros = data.iter().take_while(|&&b| b == 0).count();
+    // 按 58 进制的小端数字逐字节累加
+    let mut digits: Vec<u8> = Vec::with_capacity(data.len() * 138 / 100 + 1);
+    for &byte in &data[zeros..] {
+        let mut carry = u32::from(byte);
+        for d in digits.iter_mut() {
+            carry += u32::from(*d) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    std::iter::repeat_n(b'1', zeros)
+        .chain(digits.iter().rev().map(|&d| ALPHABET[d as usize]))
+        .map(char::from)
+        .collect()
+}
+
+/// Base58 解码
+pub fn decode(s: &str) -> Result<Vec<u8>, Base58Error> {
+    let zeros = s.bytes().take_while(|&c| c == b'1').count();
+    let mut bytes: Vec<u8> = Vec::with_capacity(s.len());
+    for (index, character) in s.char_indices().skip(zeros) {
+        let mut carry = ALPHABET
+            .iter()
+            .position(|&a| char::from(a) == character)
+            .ok_or(Base58Error::InvalidCharacter { character, index })?
+            as u32;
+        for b in bytes.iter_mut() {
+            carry += u32::from(*b) * 58;
+            *b = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut out = vec![0u8; zeros];
+    out.extend(bytes.iter().rev());
+    Ok(out)
+}
+
+/// Base58Check 编码，data 应已包含版本前缀
+pub fn encode_check(data: &[u8]) -> String {
+    let mut buf = data.to_vec();
+    buf.extend_from_slice(&checksum4(data));
+    encode(&buf)
+}
+
+/// Base58Check 解码，验证并去掉校验码，返回的数据仍包含版本前缀
+pub fn decode_check(s: &str) -> Result<Vec<u8>, Base58Error> {
+    let mut data = decode(s)?;
+    if data.len() < CHECKSUM_LEN {
+        return Err(Base58Error::TooShort);
+    }
+    let mut actual = [0u8; CHECKSUM_LEN];
+    actual.copy_from_slice(&data.split_off(data.len() - CHECKSUM_LEN));
+    let expected = checksum4(&data);
+    if !ct_eq(&expected, &actual) {
+        return Err(Base58Error::BadChecksum { expected, actual });
+    }
+    Ok(data)
+}
+
+/// 带版本前缀的 Base58Check 编码
+///
+/// version: 版本前缀，如比特币主网 P2PKH 地址为 [0x00]，xpub 为 [0x04, 0x88, 0xb2, 0x1e]
+/// payload: 数据
+pub fn encode_check_version(version: &[u8], payload: &[u8]) -> String {
+    encode_check(&[version, payload].concat())
+}
+
+/// 解码带版本前缀的 Base58Check，版本前缀不符时返回错误，成功时返回去掉前缀的数据
+///
+/// s: 编码后的字符串
+/// version: 预期的版本前缀
+pub fn decode_check_version(s: &str, version: &[u8]) -> Result<Vec<u8>, Base58Error> {
+    let mut data = decode_check(s)?;
+    if data.len() < version.len() {
+        return Err(Base58Error::TooShort);
+    }
+    let payload = data.split_off(version.len());
+    if data != version {
+        return Err(Base58Error::UnexpectedVersion {
+            expected: version.to_vec(),
+            actual: data,
+        });
+    }
+    Ok(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_base58() {
+        assert_eq!(encode(b"Hello World!"), "2NEpo7TZRRrLZSi2U");
+        assert_eq!(decode("2NEpo7TZRRrLZSi2U").unwrap(), b"Hello World!");
+        let data = Vec::from_hex("0000287fb4cd").unwrap();
+        assert_eq!(encode(&data), "11233QC4");
+        assert_eq!(decode("11233QC4").unwrap(), data);
+        assert_eq!(encode(&[]), "");
+        assert_eq!(
+            decode("12O4"),
+            Err(Base58Error::InvalidCharacter {
+                character: 'O',
+                index: 2
+            })
+        );
+
+        // test vector from: https://en.bitcoin.it/wiki/Technical_background_of_version_1_Bitcoin_addresses
+        let hash = Vec::from_hex("010966776006953d5567439e5e39f86a0d273bee").unwrap();
+        let address = encode_check_version(&[0x00], &hash);
+        assert_eq!(address, "16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvM");
+        assert_eq!(decode_check_version(&address, &[0x00]).unwrap(), hash);
+        assert!(matches!(
+            decode_check_version(&address, &[0x6f]),
+            Err(Base58Error::UnexpectedVersion { .. })
+        ));
+        assert!(matches!(
+            decode_check("16UwLL9Risc3QfPqBUvKofHmBQ7wMtjvN"),
+            Err(Base58Error::BadChecksum { .. })
+        ));
+        assert_eq!(decode_check("1"), Err(Base58Error::TooShort));
+    }
+}
@@ -0,0 +1,176 @@
+//! Base64 编码 (RFC 4648)
+//!
+//! 标准字母表用于 PEM、tink 等，URL 安全字母表用于 JWT 和 URL 参数。
+//! 编码和解码都是常量时间的，不根据数据内容分支或查表，可以直接用于私钥等秘密数据。
+//! 解码时填充可有可无，但给出的填充必须正确，末尾未使用的位必须为0，不接受空白
+
+use super::hex::in_range;
+use crate::ct::wipe;
+
+/// 字母表
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alphabet {
+    /// 标准字母表，使用 '+' 和 '/'
+    Standard,
+    /// URL 安全字母表，使用 '-' 和 '_'
+    UrlSafe,
+}
+
+impl Alphabet {
+    fn symbols(self) -> (u8, u8) {
+        match self {
+            Alphabet::Standard => (b'+', b'/'),
+            Alphabet::UrlSafe => (b'-', b'_'),
+        }
+    }
+}
+
+/// 使用标准字母表编码，带填充
+pub fn encode(data: &[u8]) -> String {
+    encode_config(data, Alphabet::Standard, true)
+}
+
+/// 使用标准字母表解码
+pub fn decode(s: &str) -> Result<Vec<u8>, String> {
+    decode_config(s, Alphabet::Standard)
+}
+
+/// 使用 URL 安全字母表编码，不带填充
+pub fn encode_url(data: &[u8]) -> String {
+    encode_config(data, Alphabet::UrlSafe, false)
+}
+
+/// 使用 URL 安全字母表解码
+pub fn decode_url(s: &str) -> Result<Vec<u8>, String> {
+    decode_config(s, Alphabet::UrlSafe)
+}
+
+/// 编码
+///
+/// alphabet: 字母表
+/// pad: 是否用 '=' 把输出填充为4的倍数
+pub fn encode_config(data: &[u8], alphabet: Alphabet, pad: bool) -> String {
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let mut block = [0u8; 3];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let n = (u32::from(block[0]) << 16) | (u32::from(block[1]) << 8) | u32::from(block[2]);
+        for i in 0..=chunk.len() {
+            out.push(char::from(encode_char(
+                ((n >> (18 - 6 * i)) & 0x3f) as u8,
+                alphabet,
+            )));
+        }
+        if pad {
+            for _ in chunk.len()..3 {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// 解码
+///
+/// 非法字符、长度或填充错误时返回错误，错误中不包含出错的位置
+pub fn decode_config(s: &str, alphabet: Alphabet) -> Result<Vec<u8>, String> {
+    let mut s = s.as_bytes();
+    // 填充不是秘密，可以按长度处理
+    let padding = s.iter().rev().take(3).take_while(|&&c| c == b'=').count();
+    if padding > 0 {
+        if padding > 2 || !s.len().is_multiple_of(4) {
+            return Err("base64: invalid padding".to_string());
+        }
+        s = &s[..s.len() - padding];
+    }
+    if s.len() % 4 == 1 {
+        return Err("base64: invalid length".to_string());
+    }
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    let mut valid = 0xffu8;
+    for chunk in s.chunks(4) {
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let (v, ok) = decode_char(c, alphabet);
+            valid &= ok;
+            n |= u32::from(v) << (18 - 6 * i);
+        }
+        let bytes = n.to_be_bytes();
+        let len = chunk.len() * 3 / 4;
+        out.extend_from_slice(&bytes[1..1 + len]);
+        // 不完整的最后一组中未使用的位必须为0
+        let unused = n & (0x00ff_ffff >> (8 * len));
+        let nonzero = ((unused | unused.wrapping_neg()) >> 31) as u8;
+        valid &= nonzero.wrapping_sub(1);
+    }
+    if valid != 0xff {
+        wipe(&mut out);
+        return Err("base64: invalid character".to_string());
+    }
+    Ok(out)
+}
+
+fn encode_char(v: u8, alphabet: Alphabet) -> u8 {
+    let (s62, s63) = alphabet.symbols();
+    (in_range(v, 0, 25) & v.wrapping_add(b'A'))
+        | (in_range(v, 26, 51) & v.wrapping_add(b'a' - 26))
+        | (in_range(v, 52, 61) & v.wrapping_sub(52 - b'0'))
+        | (in_range(v, 62, 62) & s62)
+        | (in_range(v, 63, 63) & s63)
+}
+
+/// 返回 (6位值, 合法时为 0xff 否则为 0)
+fn decode_char(c: u8, alphabet: Alphabet) -> (u8, u8) {
+    let (s62, s63) = alphabet.symbols();
+    let upper = in_range(c, b'A', b'Z');
+    let lower = in_range(c, b'a', b'z');
+    let digit = in_range(c, b'0', b'9');
+    let is62 = in_range(c, s62, s62);
+    let is63 = in_range(c, s63, s63);
+    let value = (upper & c.wrapping_sub(b'A'))
+        | (lower & c.wrapping_sub(b'a' - 26))
+        | (digit & c.wrapping_add(52 - b'0'))
+        | (is62 & 62)
+        | (is63 & 63);
+    (value, upper | lower | digit | is62 | is63)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        // test vectors from: RFC 4648 section 10
+        for (plain, encoded) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(encode(plain.as_bytes()), encoded);
+            assert_eq!(decode(encoded).unwrap(), plain.as_bytes());
+            assert_eq!(
+                decode(encoded.trim_end_matches('=')).unwrap(),
+                plain.as_bytes()
+            );
+        }
+
+        let data: Vec<u8> = (0..=255).collect();
+        assert_eq!(encode(&data), base64::encode(&data));
+        assert_eq!(
+            encode_url(&data),
+            base64::encode_config(&data, base64::URL_SAFE_NO_PAD)
+        );
+        assert_eq!(decode_url(&encode_url(&data)).unwrap(), data);
+        assert!(decode_url(&encode(&data)).is_err());
+        for bad in [
+            "Zg=", "Zg===", "Z", "Zh==", "Zm9=", "Zm 9v", "Zm9v=", "=Zm9",
+        ] {
+            assert!(decode(bad).is_err(), "{}", bad);
+        }
+    }
+}
@@ -0,0 +1,268 @@
+//! Bech32 (BIP-173) 和 Bech32m (BIP-350) 编码
+//!
+//! 字符串由人类可读部分(HRP)、分隔符 '1'、5位一组的数据和6个字符的校验码组成。
+//! encode / decode 处理5位数据，encode_bytes / decode_bytes 在内部完成8位和5位的转换，
+//! segwit_encode / segwit_decode 构造和解析隔离见证地址，v0 使用 Bech32，v1(Taproot) 及以上使用 Bech32m
+
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+const CHECKSUM_LEN: usize = 6;
+const BECH32M_CONST: u32 = 0x2bc8_30a3;
+// BIP-173 规定的地址最大长度，只在 segwit 地址上检查，age 等格式不受限制
+const MAX_ADDRESS_LEN: usize = 90;
+
+/// 校验码变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    /// BIP-173，用于 segwit v0 地址、age 密钥和 Lightning 发票
+    Bech32,
+    /// BIP-350，修正了 Bech32 对末尾插入 'q' 不敏感的问题，用于 segwit v1 及以上的地址
+    Bech32m,
+}
+
+impl Variant {
+    fn constant(self) -> u32 {
+        match self {
+            Variant::Bech32 => 1,
+            Variant::Bech32m => BECH32M_CONST,
+        }
+    }
+}
+
+/// 编码5位数据
+///
+/// hrp: 人类可读部分，1到83个可见ASCII字符，输出统一为小写
+/// data: 每个字节为 0..32 的5位值
+/// variant: 校验码变体
+pub fn encode(hrp: &str, data: &[u8], variant: Variant) -> Result<String, String> {
+    if hrp.is_empty() || hrp.len() > 83 || !hrp.bytes().all(|c| (33..=126).contains(&c)) {
+        return Err("bech32: invalid human-readable part".to_string());
+    }
+    if data.iter().any(|&v| v >= 32) {
+        return Err("bech32: data value out of range".to_string());
+    }
+    let hrp = hrp.to_ascii_lowercase();
+    let mut values = hrp_expand(&hrp);
+    values.extend_from_slice(data);
+    values.extend_from_slice(&[0u8; CHECKSUM_LEN]);
+    let polymod = polymod(&values) ^ variant.constant();
+    let mut s = String::with_capacity(hrp.len() + 1 + data.len() + CHECKSUM_LEN);
+    s.push_str(&hrp);
+    s.push('1');
+    s.extend(data.iter().map(|&v| char::from(CHARSET[v as usize])));
+    s.extend(
+        (0..CHECKSUM_LEN).map(|i| {
+            char::from(CHARSET[((polymod >> (5 * (CHECKSUM_LEN - 1 - i))) & 31) as usize])
+        }),
+    );
+    Ok(s)
+}
+
+/// 解码为 (小写的hrp, 5位数据, 变体)
+///
+/// 接受全大写或全小写的输入，混合大小写、非法字符和校验码错误时返回错误
+pub fn decode(s: &str) -> Result<(String, Vec<u8>, Variant), String> {
+    if s.to_ascii_lowercase() != s && s.to_ascii_uppercase() != s {
+        return Err("bech32: mixed case".to_string());
+    }
+    let s = s.to_ascii_lowercase();
+    let pos = s
+        .rfind('1')
+        .ok_or_else(|| "bech32: missing separator".to_string())?;
+    let (hrp, rest) = (&s[..pos], &s[pos + 1..]);
+    if hrp.is_empty() || rest.len() < CHECKSUM_LEN {
+        return Err("bech32: too short".to_string());
+    }
+    if !hrp.bytes().all(|c| (33..=126).contains(&c)) {
+        return Err("bech32: invalid human-readable part".to_string());
+    }
+    let mut data = Vec::with_capacity(rest.len());
+    for c in rest.bytes() {
+        let v = CHARSET
+            .iter()
+            .position(|&x| x == c)
+            .ok_or_else(|| "bech32: invalid character".to_string())?;
+        data.push(v as u8);
+    }
+    let mut values = hrp_expand(hrp);
+    values.extend_from_slice(&data);
+    let variant = match polymod(&values) {
+        1 => Variant::Bech32,
+        BECH32M_CONST => Variant::Bech32m,
+        _ => return Err("bech32: invalid checksum".to_string()),
+    };
+    data.truncate(data.len() - CHECKSUM_LEN);
+    Ok((hrp.to_string(), data, variant))
+}
+
+/// 编码8位数据
+pub fn encode_bytes(hrp: &str, data: &[u8], variant: Variant) -> Result<String, String> {
+    encode(hrp, &convert_bits(data, 8, 5, true)?, variant)
+}
+
+/// 解码为 (小写的hrp, 8位数据, 变体)，填充位不为零时返回错误
+pub fn decode_bytes(s: &str) -> Result<(String, Vec<u8>, Variant), String> {
+    let (hrp, data, variant) = decode(s)?;
+    Ok((hrp, convert_bits(&data, 5, 8, false)?, variant))
+}
+
+/// 在不同位宽的分组之间转换
+///
+/// from / to: 输入和输出每组的位数，最多8
+/// pad: 为 true 时用0补齐最后一组；为 false 时不足一组的剩余位必须为0且少于 from 位
+pub fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Result<Vec<u8>, String> {
+    if !(1..=8).contains(&from) || !(1..=8).contains(&to) {
+        return Err("bech32: group width must be 1 to 8 bits".to_string());
+    }
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let max = (1u32 << to) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+    for &v in data {
+        if u32::from(v) >> from != 0 {
+            return Err("bech32: data value out of range".to_string());
+        }
+        acc = (acc << from) | u32::from(v);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return Err("bech32: invalid padding".to_string());
+    }
+    Ok(out)
+}
+
+/// 构造隔离见证地址
+///
+/// hrp: 网络前缀，如主网 "bc"、测试网 "tb"
+/// version: 见证版本 0..=16，v0 使用 Bech32，其余使用 Bech32m
+/// program: 见证程序，2到40字节，v0 必须为20或32字节
+pub fn segwit_encode(hrp: &str, version: u8, program: &[u8]) -> Result<String, String> {
+    check_program(version, program)?;
+    let variant = if version == 0 {
+        Variant::Bech32
+    } else {
+        Variant::Bech32m
+    };
+    let mut data = vec![version];
+    data.extend(convert_bits(program, 8, 5, true)?);
+    encode(hrp, &data, variant)
+}
+
+/// 解析隔离见证地址，返回 (见证版本, 见证程序)
+///
+/// hrp: 预期的网络前缀，不符时返回错误
+/// address: 地址
+pub fn segwit_decode(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), String> {
+    if address.len() > MAX_ADDRESS_LEN {
+        return Err("bech32: address too long".to_string());
+    }
+    let (actual_hrp, data, variant) = decode(address)?;
+    if actual_hrp != hrp.to_ascii_lowercase() {
+        return Err("bech32: unexpected human-readable part".to_string());
+    }
+    let (&version, program) = data
+        .split_first()
+        .ok_or_else(|| "bech32: missing witness version".to_string())?;
+    let expected = if version == 0 {
+        Variant::Bech32
+    } else {
+        Variant::Bech32m
+    };
+    if variant != expected {
+        return Err("bech32: wrong checksum variant for witness version".to_string());
+    }
+    let program = convert_bits(program, 5, 8, false)?;
+    check_program(version, &program)?;
+    Ok((version, program))
+}
+
+fn check_program(version: u8, program: &[u8]) -> Result<(), String> {
+    if version > 16 {
+        return Err("bech32: invalid witness version".to_string());
+    }
+    if !(2..=40).contains(&program.len()) || (version == 0 && ![20, 32].contains(&program.len())) {
+        return Err("bech32: invalid witness program length".to_string());
+    }
+    Ok(())
+}
+
+fn polymod(values: &[u8]) -> u32 {
+    const GEN: [u32; 5] = [
+        0x3b6a_57b2,
+        0x2650_8e6d,
+        0x1ea1_19fa,
+        0x3d42_33dd,
+        0x2a14_62b3,
+    ];
+    let mut chk = 1u32;
+    for v in values {
+        let b = chk >> 25;
+        chk = ((chk & 0x1ff_ffff) << 5) ^ u32::from(*v);
+        for (i, g) in GEN.iter().enumerate() {
+            if (b >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut v = hrp.bytes().map(|b| b >> 5).collect::<Vec<_>>();
+    v.push(0);
+    v.extend(hrp.bytes().map(|b| b & 31));
+    v
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_bech32() {
+        // test vectors from: BIP-173 和 BIP-350
+        assert_eq!(
+            decode("A12UEL5L").unwrap(),
+            ("a".to_string(), vec![], Variant::Bech32)
+        );
+        assert_eq!(
+            decode("A1LQFN3A").unwrap(),
+            ("a".to_string(), vec![], Variant::Bech32m)
+        );
+        assert_eq!(encode("A", &[], Variant::Bech32).unwrap(), "a12uel5l");
+        assert!(decode("A12UEL5l").is_err());
+        assert!(decode("a12uel5m").is_err());
+
+        let program = Vec::from_hex("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap();
+        let address = "BC1QW508D6QEJXTDG4Y5R3ZARVARY0C5XW7KV8F3T4";
+        assert_eq!(segwit_decode("bc", address).unwrap(), (0, program.clone()));
+        assert_eq!(
+            segwit_encode("bc", 0, &program).unwrap(),
+            address.to_ascii_lowercase()
+        );
+        assert!(segwit_decode("tb", address).is_err());
+
+        // BIP-86 第一个地址，v1 见证程序为输出公钥的x坐标
+        let program =
+            Vec::from_hex("a60869f0dbcf1dc659c9cecbaf8050135ea9e8cdc487053f1dc6880949dc684c")
+                .unwrap();
+        let address = "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr";
+        assert_eq!(segwit_encode("bc", 1, &program).unwrap(), address);
+        assert_eq!(segwit_decode("bc", address).unwrap(), (1, program.clone()));
+        // 用 Bech32 校验码编码的 v1 地址无效
+        let mut data = vec![1u8];
+        data.extend(convert_bits(&program, 8, 5, true).unwrap());
+        let wrong = encode("bc", &data, Variant::Bech32).unwrap();
+        assert!(segwit_decode("bc", &wrong).is_err());
+
+        let s = encode_bytes("age", &[0xff; 32], Variant::Bech32).unwrap();
+        assert_eq!(decode_bytes(&s).unwrap().1, vec![0xff; 32]);
+    }
+}
@@ -0,0 +1,90 @@
+//! 十六进制编码
+//!
+//! 编码和解码都是常量时间的，不根据数据内容分支或查表，可以直接用于私钥等秘密数据。
+//! 解码接受大小写字母，但不接受 "0x" 前缀和空白
+
+use crate::ct::wipe;
+
+/// 小写十六进制编码
+pub fn encode(data: &[u8]) -> String {
+    encode_with(data, 0x27)
+}
+
+/// 大写十六进制编码
+pub fn encode_upper(data: &[u8]) -> String {
+    encode_with(data, 0x07)
+}
+
+/// 十六进制解码
+///
+/// 长度为奇数或有非法字符时返回错误，错误中不包含出错的位置
+pub fn decode(s: &str) -> Result<Vec<u8>, String> {
+    let s = s.as_bytes();
+    if !s.len().is_multiple_of(2) {
+        return Err("hex: odd length".to_string());
+    }
+    let mut out = Vec::with_capacity(s.len() / 2);
+    let mut valid = 0xffu8;
+    for pair in s.chunks(2) {
+        let (hi, hi_ok) = nibble(pair[0]);
+        let (lo, lo_ok) = nibble(pair[1]);
+        valid &= hi_ok & lo_ok;
+        out.push((hi << 4) | lo);
+    }
+    if valid != 0xff {
+        wipe(&mut out);
+        return Err("hex: invalid character".to_string());
+    }
+    Ok(out)
+}
+
+// 0..=9 映射到 '0'..='9'，10..=15 映射到 'a' 或 'A' 开始的字母，alpha_offset 为字母与 '0'+10 的距离
+fn encode_with(data: &[u8], alpha_offset: u8) -> String {
+    let mut out = String::with_capacity(data.len() * 2);
+    for &b in data {
+        for n in [b >> 4, b & 0x0f] {
+            // n > 9 时 mask 为 0xff
+            let mask = (9u8.wrapping_sub(n) >> 7).wrapping_neg();
+            out.push(char::from(n + b'0' + (mask & alpha_offset)));
+        }
+    }
+    out
+}
+
+/// 返回 (4位值, 合法时为 0xff 否则为 0)
+fn nibble(c: u8) -> (u8, u8) {
+    let digit = in_range(c, b'0', b'9');
+    let upper = in_range(c, b'A', b'F');
+    let lower = in_range(c, b'a', b'f');
+    let value = (digit & c.wrapping_sub(b'0'))
+        | (upper & c.wrapping_sub(b'A' - 10))
+        | (lower & c.wrapping_sub(b'a' - 10));
+    (value, digit | upper | lower)
+}
+
+/// c 在 lo..=hi 内时返回 0xff，否则返回 0
+pub(crate) fn in_range(c: u8, lo: u8, hi: u8) -> u8 {
+    let c = i16::from(c);
+    // 两个差都为负时符号位为1
+    (((i16::from(lo) - 1 - c) & (c - i16::from(hi) - 1)) >> 8) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex() {
+        let data: Vec<u8> = (0..=255).collect();
+        let lower = encode(&data);
+        assert_eq!(lower, hex::encode(&data));
+        assert_eq!(encode_upper(&data), hex::encode_upper(&data));
+        assert_eq!(decode(&lower).unwrap(), data);
+        assert_eq!(decode(&encode_upper(&data)).unwrap(), data);
+        assert_eq!(decode("DeadBeef").unwrap(), [0xde, 0xad, 0xbe, 0xef]);
+        assert!(decode("abc").is_err());
+        for bad in ["0g", "g0", "0x", "/0", ":0", "@0", "G0", "`0"] {
+            assert!(decode(bad).is_err(), "{}", bad);
+        }
+    }
+}
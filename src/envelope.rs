@@ -0,0 +1,227 @@
+//! 带签发时间和有效期的签名信封
+//!
+//! 签发时间和过期时间写入签名覆盖的头部，不能被篡改，适合直接签发能力令牌和短期授权。
+//! 验证时允许一定的时钟偏差，时间均为 unix 秒
+//!
+//! 签名覆盖的数据: "pi_crypto signed envelope v1" | 方案(1字节) | 签发时间(u64) | 过期时间(u64) | 载荷
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::framing::{put_bytes16, put_bytes32, Reader};
+use crate::signature::{scheme_verify, SignatureScheme};
+
+const MAGIC: &[u8; 4] = b"PISE";
+const VERSION: u8 = 1;
+const SIGN_CONTEXT: &[u8] = b"pi_crypto signed envelope v1";
+
+/// 默认允许的时钟偏差，60秒
+pub const DEFAULT_CLOCK_SKEW: u64 = 60;
+
+/// 签名信封
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedEnvelope {
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub payload: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedEnvelope {
+    /// 使用签名函数签发信封
+    ///
+    /// scheme: 签名方案
+    /// public_key: 签名者公钥，验证时需要在受信任的密钥中
+    /// payload: 载荷
+    /// issued_at: 签发时间
+    /// expires_at: 过期时间，必须晚于签发时间
+    /// sign: 签名函数，参数为签名覆盖的数据
+    pub fn sign_with<F>(
+        scheme: SignatureScheme,
+        public_key: &[u8],
+        payload: &[u8],
+        issued_at: u64,
+        expires_at: u64,
+        sign: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        if expires_at <= issued_at {
+            return Err("envelope: expiry must be after issuance".to_string());
+        }
+        let signature = sign(&signing_payload(scheme, issued_at, expires_at, payload));
+        Ok(SignedEnvelope {
+            scheme,
+            public_key: public_key.to_vec(),
+            issued_at,
+            expires_at,
+            payload: payload.to_vec(),
+            signature,
+        })
+    }
+
+    /// 以当前时间签发，有效期 ttl 秒
+    pub fn issue<F>(
+        scheme: SignatureScheme,
+        public_key: &[u8],
+        payload: &[u8],
+        ttl: u64,
+        sign: F,
+    ) -> Result<Self, String>
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        let now = unix_now()?;
+        let expires_at = now
+            .checked_add(ttl)
+            .ok_or_else(|| "envelope: ttl overflow".to_string())?;
+        SignedEnvelope::sign_with(scheme, public_key, payload, now, expires_at, sign)
+    }
+
+    /// 验证签名和有效期，成功时返回载荷
+    ///
+    /// trusted: 受信任的签名者（签名方案，公钥）
+    /// now: 当前时间
+    /// skew: 允许的时钟偏差秒数，签发时间最多可以晚于 now 这么多，过期时间最多可以早于 now 这么多
+    pub fn verify_at(
+        &self,
+        trusted: &[(SignatureScheme, Vec<u8>)],
+        now: u64,
+        skew: u64,
+    ) -> Result<&[u8], String> {
+        if !trusted
+            .iter()
+            .any(|(scheme, pk)| *scheme == self.scheme && *pk == self.public_key)
+        {
+            return Err("envelope: untrusted signer".to_string());
+        }
+        let payload = signing_payload(self.scheme, self.issued_at, self.expires_at, &self.payload);
+        if !scheme_verify(self.scheme, &self.public_key, &payload, &self.signature) {
+            return Err("envelope: bad signature".to_string());
+        }
+        if now.saturating_add(skew) < self.issued_at {
+            return Err("envelope: not yet valid".to_string());
+        }
+        if now.saturating_sub(skew) >= self.expires_at {
+            return Err("envelope: expired".to_string());
+        }
+        Ok(&self.payload)
+    }
+
+    /// 按当前时间验证
+    pub fn verify(
+        &self,
+        trusted: &[(SignatureScheme, Vec<u8>)],
+        skew: u64,
+    ) -> Result<&[u8], String> {
+        self.verify_at(trusted, unix_now()?, skew)
+    }
+
+    /// 序列化
+    ///
+    /// 格式: "PISE" | 版本(1字节) | 方案(1字节) | 签发时间(u64) | 过期时间(u64) | 公钥长度(u16) | 公钥 | 载荷长度(u32) | 载荷 | 签名长度(u16) | 签名
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.push(self.scheme.id());
+        out.extend_from_slice(&self.issued_at.to_be_bytes());
+        out.extend_from_slice(&self.expires_at.to_be_bytes());
+        put_bytes16(&mut out, "envelope", &self.public_key)?;
+        put_bytes32(&mut out, "envelope", &self.payload)?;
+        put_bytes16(&mut out, "envelope", &self.signature)?;
+        Ok(out)
+    }
+
+    /// 反序列化，不验证签名
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new("envelope", data);
+        if reader.take(4)? != MAGIC {
+            return Err("envelope: bad magic".to_string());
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(format!("envelope: unsupported version {}", version));
+        }
+        let id = reader.u8()?;
+        let scheme = SignatureScheme::from_id(id)
+            .ok_or_else(|| format!("envelope: unknown signature scheme {}", id))?;
+        let issued_at = reader.u64()?;
+        let expires_at = reader.u64()?;
+        let public_key = reader.bytes16()?.to_vec();
+        let payload = reader.bytes32()?.to_vec();
+        let signature = reader.bytes16()?.to_vec();
+        reader.finish()?;
+        Ok(SignedEnvelope {
+            scheme,
+            public_key,
+            issued_at,
+            expires_at,
+            payload,
+            signature,
+        })
+    }
+}
+
+fn signing_payload(
+    scheme: SignatureScheme,
+    issued_at: u64,
+    expires_at: u64,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut out = SIGN_CONTEXT.to_vec();
+    out.push(scheme.id());
+    out.extend_from_slice(&issued_at.to_be_bytes());
+    out.extend_from_slice(&expires_at.to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+fn unix_now() -> Result<u64, String> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .map_err(|_| "envelope: system clock before unix epoch".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ed25519;
+
+    #[test]
+    fn test_envelope() {
+        let (sk, pk) = ed25519::keypair(&[5u8; 32]);
+        let sign = |data: &[u8]| ed25519::signature(data, &sk).to_vec();
+        let trusted = vec![(SignatureScheme::Ed25519, pk.to_vec())];
+
+        let env =
+            SignedEnvelope::sign_with(SignatureScheme::Ed25519, &pk, b"grant", 1000, 1300, sign)
+                .unwrap();
+        let env = SignedEnvelope::from_bytes(&env.to_bytes().unwrap()).unwrap();
+        assert_eq!(env.verify_at(&trusted, 1100, 0).unwrap(), b"grant");
+
+        // 时钟偏差范围内仍然有效
+        assert!(env.verify_at(&trusted, 950, 60).is_ok());
+        assert!(env.verify_at(&trusted, 1320, 60).is_ok());
+        assert!(env.verify_at(&trusted, 900, 60).is_err());
+        assert!(env.verify_at(&trusted, 1300, 0).is_err());
+        assert!(env.verify_at(&[], 1100, 0).is_err());
+
+        // 篡改过期时间时签名失效
+        let mut tampered = env.clone();
+        tampered.expires_at += 3600;
+        assert_eq!(
+            tampered.verify_at(&trusted, 1100, 0).unwrap_err(),
+            "envelope: bad signature"
+        );
+
+        assert!(
+            SignedEnvelope::sign_with(SignatureScheme::Ed25519, &pk, b"", 10, 10, sign).is_err()
+        );
+        let env = SignedEnvelope::issue(SignatureScheme::Ed25519, &pk, b"now", 300, sign).unwrap();
+        assert!(env.verify(&trusted, DEFAULT_CLOCK_SKEW).is_ok());
+    }
+}
@@ -0,0 +1,84 @@
+//! 错误类型
+
+use std::fmt;
+
+/// 验证失败的原因
+///
+/// 由各模块的 verify_detailed 返回，用于在日志中区分数据损坏(格式和长度错误)和伪造(签名无效)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyFailure {
+    /// 公钥无法解析
+    MalformedKey,
+    /// 签名无法解析
+    MalformedSignature,
+    /// 消息、公钥或签名的长度错误
+    WrongLength { expected: usize, actual: usize },
+    /// 认证码或摘要与计算结果不一致
+    DigestMismatch,
+    /// 格式正确但签名无效
+    SignatureInvalid,
+}
+
+impl fmt::Display for VerifyFailure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VerifyFailure::MalformedKey => f.write_str("malformed public key"),
+            VerifyFailure::MalformedSignature => f.write_str("malformed signature"),
+            VerifyFailure::WrongLength { expected, actual } => {
+                write!(f, "wrong length, expected {} got {}", expected, actual)
+            }
+            VerifyFailure::DigestMismatch => f.write_str("digest mismatch"),
+            VerifyFailure::SignatureInvalid => f.write_str("signature invalid"),
+        }
+    }
+}
+
+impl std::error::Error for VerifyFailure {}
+
+/// 密码运算的错误
+///
+/// 由 signature、ed25519、bls 等模块的公开接口返回，代替对外部输入的 panic，消息以模块名开头
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CryptoError {
+    /// 私钥或公钥无法解析，或长度错误
+    InvalidKey(String),
+    /// 消息、摘要、盐等输入的格式或长度错误
+    InvalidInput(String),
+    /// 输入合法但运算失败，如随机数生成失败或结果为无穷远点
+    OperationFailed(String),
+    /// 签名或认证码验证失败
+    Verify(VerifyFailure),
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CryptoError::InvalidKey(msg) => write!(f, "invalid key: {}", msg),
+            CryptoError::InvalidInput(msg) => write!(f, "invalid input: {}", msg),
+            CryptoError::OperationFailed(msg) => write!(f, "operation failed: {}", msg),
+            CryptoError::Verify(e) => write!(f, "verify failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CryptoError::Verify(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<VerifyFailure> for CryptoError {
+    fn from(e: VerifyFailure) -> Self {
+        CryptoError::Verify(e)
+    }
+}
+
+/// 供仍然使用字符串错误的模块用 ? 传递
+impl From<CryptoError> for String {
+    fn from(e: CryptoError) -> Self {
+        e.to_string()
+    }
+}
@@ -0,0 +1,211 @@
+//! 以太坊签名辅助函数
+//!
+//! 消息哈希使用 Keccak-256，personal_sign 按 EIP-191 在消息前加上
+//! "\x19Ethereum Signed Message:\n" 和十进制长度，交易签名按 EIP-155 把链ID编码进 v
+
+use secp256k1::{recover, sign, Message, PublicKey, RecoveryId, SecretKey, Signature};
+
+use crate::digest::keccak256;
+use crate::error::{CryptoError, VerifyFailure};
+
+/// 带 v 的以太坊签名
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EthSignature {
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+    /// 27 + recovery id，或 EIP-155 的 chain_id * 2 + 35 + recovery id
+    pub v: u64,
+}
+
+impl EthSignature {
+    /// 65字节的 r | s | v，v 超过255时(EIP-155 的大链ID)返回错误
+    pub fn to_bytes(&self) -> Result<[u8; 65], CryptoError> {
+        if self.v > u8::MAX as u64 {
+            return Err(CryptoError::InvalidInput(
+                "eth: v does not fit in one byte".to_string(),
+            ));
+        }
+        let mut out = [0u8; 65];
+        out[..32].copy_from_slice(&self.r);
+        out[32..64].copy_from_slice(&self.s);
+        out[64] = self.v as u8;
+        Ok(out)
+    }
+
+    /// 解析65字节的 r | s | v
+    pub fn from_bytes(sig: &[u8]) -> Result<Self, CryptoError> {
+        if sig.len() != 65 {
+            return Err(CryptoError::Verify(VerifyFailure::WrongLength {
+                expected: 65,
+                actual: sig.len(),
+            }));
+        }
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&sig[..32]);
+        s.copy_from_slice(&sig[32..64]);
+        Ok(EthSignature {
+            r,
+            s,
+            v: sig[64] as u64,
+        })
+    }
+
+    /// 由 v 得到 recovery id，接受 0/1、27/28 和 EIP-155 形式
+    pub fn recovery_id(&self) -> Result<u8, CryptoError> {
+        match self.v {
+            0 | 1 => Ok(self.v as u8),
+            27 | 28 => Ok((self.v - 27) as u8),
+            v if v >= 35 => Ok(((v - 35) % 2) as u8),
+            _ => Err(CryptoError::Verify(VerifyFailure::MalformedSignature)),
+        }
+    }
+
+    /// EIP-155 签名中编码的链ID，v 为 27/28 时返回 None
+    pub fn chain_id(&self) -> Option<u64> {
+        if self.v >= 35 {
+            Some((self.v - 35) / 2)
+        } else {
+            None
+        }
+    }
+}
+
+/// EIP-191 version 0x45 的消息哈希，即 eth_sign 和 personal_sign 签名的内容
+pub fn hash_message(msg: &[u8]) -> [u8; 32] {
+    let mut data = format!("\x19Ethereum Signed Message:\n{}", msg.len()).into_bytes();
+    data.extend_from_slice(msg);
+    keccak256(&data)
+}
+
+/// personal_sign，返回65字节的 r | s | v，v 为27或28，与 MetaMask、web3.js 的输出相同
+///
+/// msg: 消息，签名前按 hash_message 计算哈希
+/// sk: 32字节私钥
+pub fn personal_sign(msg: &[u8], sk: &[u8]) -> Result<[u8; 65], CryptoError> {
+    sign_hash_recoverable(&hash_message(msg), sk, None)?.to_bytes()
+}
+
+/// 对32字节的哈希签名
+///
+/// hash: 消息或交易的 Keccak-256 哈希
+/// sk: 32字节私钥
+/// chain_id: EIP-155 链ID，为 None 时 v 为 27 + recovery id
+pub fn sign_hash_recoverable(
+    hash: &[u8],
+    sk: &[u8],
+    chain_id: Option<u64>,
+) -> Result<EthSignature, CryptoError> {
+    let sk = SecretKey::parse_slice(sk).map_err(|_| {
+        CryptoError::InvalidKey("eth: secret key must be 32 bytes in [1, n-1]".to_string())
+    })?;
+    let (sig, recovery_id) = sign(&message(hash)?, &sk);
+    let id = recovery_id.serialize() as u64;
+    let v = match chain_id {
+        Some(chain_id) => chain_id
+            .checked_mul(2)
+            .and_then(|v| v.checked_add(35 + id))
+            .ok_or_else(|| CryptoError::InvalidInput("eth: chain id too large".to_string()))?,
+        None => 27 + id,
+    };
+    let rs = sig.serialize();
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    r.copy_from_slice(&rs[..32]);
+    s.copy_from_slice(&rs[32..]);
+    Ok(EthSignature { r, s, v })
+}
+
+/// 从签名中恢复签名者的20字节地址
+///
+/// hash: 已签名的32字节哈希
+/// sig: 签名
+pub fn recover_address(hash: &[u8], sig: &EthSignature) -> Result<[u8; 20], CryptoError> {
+    let malformed = |_| CryptoError::Verify(VerifyFailure::MalformedSignature);
+    let recovery_id = RecoveryId::parse(sig.recovery_id()?).map_err(malformed)?;
+    let mut rs = [0u8; 64];
+    rs[..32].copy_from_slice(&sig.r);
+    rs[32..].copy_from_slice(&sig.s);
+    let pk = recover(&message(hash)?, &Signature::parse(&rs), &recovery_id)
+        .map_err(|_| CryptoError::Verify(VerifyFailure::SignatureInvalid))?;
+    Ok(address(&pk))
+}
+
+/// 由公钥计算20字节地址，Keccak-256(未压缩公钥去掉 0x04 前缀) 的后20字节
+///
+/// pk: 33或65字节公钥
+pub fn public_key_to_address(pk: &[u8]) -> Result<[u8; 20], CryptoError> {
+    let pk = PublicKey::parse_slice(pk, None)
+        .map_err(|_| CryptoError::InvalidKey("eth: invalid secp256k1 public key".to_string()))?;
+    Ok(address(&pk))
+}
+
+pub(crate) fn address(pk: &PublicKey) -> [u8; 20] {
+    let mut out = [0u8; 20];
+    out.copy_from_slice(&keccak256(&pk.serialize()[1..])[12..]);
+    out
+}
+
+fn message(hash: &[u8]) -> Result<Message, CryptoError> {
+    Message::parse_slice(hash).map_err(|_| {
+        CryptoError::InvalidInput(format!("eth: hash must be 32 bytes, got {}", hash.len()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_eth() {
+        // web3.js 文档中 web3.eth.accounts.sign('Some data', ...) 的示例
+        let sk = Vec::from_hex("4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318")
+            .unwrap();
+        assert_eq!(
+            hash_message(b"Some data").to_vec(),
+            Vec::from_hex("1da44b586eb0729ff70a73c326926f6ed5a25f5b056e7f47fbc6e58d86871655")
+                .unwrap()
+        );
+        let sig = personal_sign(b"Some data", &sk).unwrap();
+        assert_eq!(sig.to_vec(), Vec::from_hex("b91467e570a6466aa9e9876cbcd013baba02900b8979d43fe208a4a4f339f5fd6007e74cd82e037b800186422fc2da167c747ef045e5d18a5f5d4300f8e1a0291c").unwrap());
+        let parsed = EthSignature::from_bytes(&sig).unwrap();
+        assert_eq!(
+            recover_address(&hash_message(b"Some data"), &parsed)
+                .unwrap()
+                .to_vec(),
+            Vec::from_hex("2c7536e3605d9c16a7a3d7b1898e529396a65c23").unwrap()
+        );
+
+        // EIP-155 规范中的示例交易，chain id 为 1
+        let sk = [0x46u8; 32];
+        let hash =
+            Vec::from_hex("daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53")
+                .unwrap();
+        let sig = sign_hash_recoverable(&hash, &sk, Some(1)).unwrap();
+        assert_eq!(sig.v, 37);
+        assert_eq!(sig.chain_id(), Some(1));
+        assert_eq!(
+            sig.r.to_vec(),
+            Vec::from_hex("28ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa636276")
+                .unwrap()
+        );
+        assert_eq!(
+            sig.s.to_vec(),
+            Vec::from_hex("67cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83")
+                .unwrap()
+        );
+        let addr = Vec::from_hex("9d8a62f656a8d1615c1294fd71e9cfb3e4855a4f").unwrap();
+        assert_eq!(recover_address(&hash, &sig).unwrap().to_vec(), addr);
+        let pk = PublicKey::from_secret_key(&SecretKey::parse(&sk).unwrap());
+        assert_eq!(
+            public_key_to_address(&pk.serialize_compressed())
+                .unwrap()
+                .to_vec(),
+            addr
+        );
+        assert!(sign_hash_recoverable(&hash, &sk, Some(u64::MAX)).is_err());
+        assert!(sign_hash_recoverable(&hash[1..], &sk, None).is_err());
+        assert!(recover_address(&hash, &EthSignature { v: 30, ..sig }).is_err());
+    }
+}
@@ -0,0 +1,759 @@
+//! C 语言接口，开启 capi 特性后导出，供 C、Objective-C 和 JNI 调用
+//!
+//! 头文件可以用 cbindgen 按仓库根目录的 cbindgen.toml 生成。约定如下：
+//! - 所有函数返回 PI_CRYPTO_OK 或负数错误码，验证函数验证失败时返回 PI_CRYPTO_VERIFY_FAILED
+//! - 输入为指针和长度，长度为0时指针可以为空
+//! - 输出写入调用方的缓冲区，*out_len 传入缓冲区长度，返回时为写入的长度；缓冲区不足时返回
+//!   PI_CRYPTO_BUFFER_TOO_SMALL，*out_len 为所需长度
+//! - 流式哈希和 RSA 私钥以不透明句柄传递，必须用对应的 *_free 释放
+//!
+//! 内部的 panic 被捕获并转换为 PI_CRYPTO_INTERNAL_ERROR，不会跨越语言边界
+#![allow(clippy::missing_safety_doc)]
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::{ptr, slice};
+
+use libc::{c_int, size_t};
+
+use crate::digest::{self, Digest, DigestAlgorithm, Hasher};
+use crate::ed25519;
+use crate::error::CryptoError;
+use crate::hmac::{self, Hmac};
+use crate::signature::{ECDSASecp256k1, PaddingAlg, Rsa};
+
+/// 成功
+pub const PI_CRYPTO_OK: c_int = 0;
+/// 空指针、未知的算法或输入长度错误
+pub const PI_CRYPTO_INVALID_ARGUMENT: c_int = -1;
+/// 输出缓冲区不足，*out_len 为所需长度
+pub const PI_CRYPTO_BUFFER_TOO_SMALL: c_int = -2;
+/// 密钥无法解析
+pub const PI_CRYPTO_INVALID_KEY: c_int = -3;
+/// 签名或认证码验证失败
+pub const PI_CRYPTO_VERIFY_FAILED: c_int = -4;
+/// 输入合法但运算失败
+pub const PI_CRYPTO_OPERATION_FAILED: c_int = -5;
+/// 内部错误
+pub const PI_CRYPTO_INTERNAL_ERROR: c_int = -6;
+
+/// 哈希算法，HMAC 只支持 SHA256、SHA384、SHA512 和 SM3
+pub const PI_CRYPTO_SHA256: c_int = 1;
+pub const PI_CRYPTO_SHA384: c_int = 2;
+pub const PI_CRYPTO_SHA512: c_int = 3;
+pub const PI_CRYPTO_SM3: c_int = 4;
+pub const PI_CRYPTO_SHA3_256: c_int = 5;
+pub const PI_CRYPTO_SHA3_384: c_int = 6;
+pub const PI_CRYPTO_SHA3_512: c_int = 7;
+pub const PI_CRYPTO_KECCAK256: c_int = 8;
+
+/// RSA签名填充类型
+pub const PI_CRYPTO_RSA_PKCS1_SHA256: c_int = 1;
+pub const PI_CRYPTO_RSA_PKCS1_SHA384: c_int = 2;
+pub const PI_CRYPTO_RSA_PKCS1_SHA512: c_int = 3;
+pub const PI_CRYPTO_RSA_PSS_SHA256: c_int = 4;
+pub const PI_CRYPTO_RSA_PSS_SHA384: c_int = 5;
+pub const PI_CRYPTO_RSA_PSS_SHA512: c_int = 6;
+
+/// 流式哈希句柄
+pub struct PiCryptoHasher(Hasher);
+
+/// RSA私钥句柄
+pub struct PiCryptoRsa(Rsa);
+
+/// 计算哈希
+///
+/// alg: PI_CRYPTO_SHA256 等
+/// data, data_len: 数据
+/// out, out_len: 输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_digest(
+    alg: c_int,
+    data: *const u8,
+    data_len: size_t,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let hash = digest::digest(digest_alg(alg)?, input(data, data_len)?);
+        output(&hash, out, out_len)
+    })
+}
+
+/// 创建流式哈希
+///
+/// alg: PI_CRYPTO_SHA256 等
+/// hasher: 返回的句柄，用 pi_crypto_hasher_free 释放
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_hasher_new(
+    alg: c_int,
+    hasher: *mut *mut PiCryptoHasher,
+) -> c_int {
+    guard(|| {
+        if hasher.is_null() {
+            return Err(PI_CRYPTO_INVALID_ARGUMENT);
+        }
+        let h = Box::new(PiCryptoHasher(Hasher::new(digest_alg(alg)?)));
+        *hasher = Box::into_raw(h);
+        Ok(())
+    })
+}
+
+/// 追加数据
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_hasher_update(
+    hasher: *mut PiCryptoHasher,
+    data: *const u8,
+    data_len: size_t,
+) -> c_int {
+    guard(|| {
+        let hasher = hasher.as_mut().ok_or(PI_CRYPTO_INVALID_ARGUMENT)?;
+        hasher.0.update(input(data, data_len)?);
+        Ok(())
+    })
+}
+
+/// 输出已追加数据的哈希并恢复到初始状态，缓冲区不足时保留已追加的数据
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_hasher_finalize(
+    hasher: *mut PiCryptoHasher,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let hasher = hasher.as_mut().ok_or(PI_CRYPTO_INVALID_ARGUMENT)?;
+        output(&hasher.0.clone().finalize(), out, out_len)?;
+        hasher.0.reset();
+        Ok(())
+    })
+}
+
+/// 释放流式哈希，hasher 可以为空
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_hasher_free(hasher: *mut PiCryptoHasher) {
+    if !hasher.is_null() {
+        drop(Box::from_raw(hasher));
+    }
+}
+
+/// 计算 HMAC
+///
+/// alg: PI_CRYPTO_SHA256、PI_CRYPTO_SHA384、PI_CRYPTO_SHA512 或 PI_CRYPTO_SM3
+/// key, key_len: 密钥
+/// data, data_len: 数据
+/// out, out_len: 输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_hmac(
+    alg: c_int,
+    key: *const u8,
+    key_len: size_t,
+    data: *const u8,
+    data_len: size_t,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let tag = Hmac::sign(hmac_alg(alg)?, input(key, key_len)?, input(data, data_len)?);
+        output(&tag, out, out_len)
+    })
+}
+
+/// 常量时间验证 HMAC，参数同 pi_crypto_hmac
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_hmac_verify(
+    alg: c_int,
+    key: *const u8,
+    key_len: size_t,
+    data: *const u8,
+    data_len: size_t,
+    tag: *const u8,
+    tag_len: size_t,
+) -> c_int {
+    guard(|| {
+        let ok = Hmac::verify(
+            hmac_alg(alg)?,
+            input(key, key_len)?,
+            input(data, data_len)?,
+            input(tag, tag_len)?,
+        );
+        verified(ok)
+    })
+}
+
+/// 由32字节种子生成 ed25519 密钥对
+///
+/// seed, seed_len: 种子，32字节
+/// secret_key, secret_key_len: 64字节扩展格式私钥的输出缓冲区
+/// public_key, public_key_len: 32字节公钥的输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_ed25519_keypair_from_seed(
+    seed: *const u8,
+    seed_len: size_t,
+    secret_key: *mut u8,
+    secret_key_len: *mut size_t,
+    public_key: *mut u8,
+    public_key_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let (sk, pk) = ed25519::keypair_from_seed(input(seed, seed_len)?).map_err(error_code)?;
+        output(&sk, secret_key, secret_key_len)?;
+        output(&pk, public_key, public_key_len)
+    })
+}
+
+/// ed25519 签名
+///
+/// secret_key, secret_key_len: 32字节种子或64字节扩展格式私钥
+/// msg, msg_len: 待签名数据
+/// out, out_len: 64字节签名的输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_ed25519_sign(
+    secret_key: *const u8,
+    secret_key_len: size_t,
+    msg: *const u8,
+    msg_len: size_t,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let sk = ed25519::SecretKey::from_bytes(input(secret_key, secret_key_len)?)
+            .map_err(error_code)?;
+        output(&sk.sign(input(msg, msg_len)?), out, out_len)
+    })
+}
+
+/// ed25519 验证签名
+///
+/// public_key, public_key_len: 32字节公钥
+/// msg, msg_len: 已签名数据
+/// sig, sig_len: 64字节签名
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_ed25519_verify(
+    public_key: *const u8,
+    public_key_len: size_t,
+    msg: *const u8,
+    msg_len: size_t,
+    sig: *const u8,
+    sig_len: size_t,
+) -> c_int {
+    guard(|| {
+        let ok = ed25519::verify(
+            input(msg, msg_len)?,
+            input(public_key, public_key_len)?,
+            input(sig, sig_len)?,
+        );
+        verified(ok)
+    })
+}
+
+/// 由 secp256k1 私钥计算公钥
+///
+/// secret_key, secret_key_len: 32字节私钥
+/// compressed: 非0时输出33字节压缩编码，否则输出65字节未压缩编码
+/// out, out_len: 输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_secp256k1_public_key(
+    secret_key: *const u8,
+    secret_key_len: size_t,
+    compressed: c_int,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let pk = ECDSASecp256k1::new()
+            .public_key_from_secret(input(secret_key, secret_key_len)?)
+            .map_err(error_code)?;
+        let pk = if compressed != 0 {
+            pk.compressed
+        } else {
+            pk.uncompressed
+        };
+        output(&pk, out, out_len)
+    })
+}
+
+/// secp256k1 ECDSA 签名，输出 DER 编码的 low-s 签名，最长72字节
+///
+/// msg, msg_len: 32字节消息摘要
+/// secret_key, secret_key_len: 32字节私钥
+/// out, out_len: 输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_secp256k1_sign(
+    msg: *const u8,
+    msg_len: size_t,
+    secret_key: *const u8,
+    secret_key_len: size_t,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let sig = ECDSASecp256k1::new()
+            .sign(input(msg, msg_len)?, input(secret_key, secret_key_len)?)
+            .map_err(error_code)?;
+        output(&sig, out, out_len)
+    })
+}
+
+/// secp256k1 ECDSA 验证签名
+///
+/// msg, msg_len: 32字节消息摘要
+/// sig, sig_len: DER 编码的签名
+/// public_key, public_key_len: 33或65字节公钥
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_secp256k1_verify(
+    msg: *const u8,
+    msg_len: size_t,
+    sig: *const u8,
+    sig_len: size_t,
+    public_key: *const u8,
+    public_key_len: size_t,
+) -> c_int {
+    guard(|| {
+        let ok = ECDSASecp256k1::new().verify(
+            input(msg, msg_len)?,
+            input(sig, sig_len)?,
+            input(public_key, public_key_len)?,
+        );
+        verified(ok)
+    })
+}
+
+/// 从 PKCS8 DER 加载 RSA 私钥
+///
+/// pkcs8, pkcs8_len: PKCS8 格式的密钥数据
+/// rsa: 返回的句柄，用 pi_crypto_rsa_free 释放
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_rsa_from_pkcs8(
+    pkcs8: *const u8,
+    pkcs8_len: size_t,
+    rsa: *mut *mut PiCryptoRsa,
+) -> c_int {
+    guard(|| {
+        let key = Rsa::fromPKCS8(input(pkcs8, pkcs8_len)?).map_err(error_code)?;
+        new_rsa(key, rsa)
+    })
+}
+
+/// 生成 RSA 私钥
+///
+/// bits: 模数位数，2048~8192
+/// rsa: 返回的句柄，用 pi_crypto_rsa_free 释放
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_rsa_generate(bits: size_t, rsa: *mut *mut PiCryptoRsa) -> c_int {
+    guard(|| new_rsa(Rsa::generate(bits).map_err(error_code)?, rsa))
+}
+
+/// 导出 PKCS1 DER 编码的 RSA 公钥
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_rsa_public_key(
+    rsa: *const PiCryptoRsa,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let rsa = rsa.as_ref().ok_or(PI_CRYPTO_INVALID_ARGUMENT)?;
+        output(&rsa.0.public_key(), out, out_len)
+    })
+}
+
+/// RSA 签名，签名长度等于模数的字节数
+///
+/// rsa: 私钥句柄
+/// padding: PI_CRYPTO_RSA_PKCS1_SHA256 等
+/// msg, msg_len: 待签名数据
+/// out, out_len: 输出缓冲区
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_rsa_sign(
+    rsa: *const PiCryptoRsa,
+    padding: c_int,
+    msg: *const u8,
+    msg_len: size_t,
+    out: *mut u8,
+    out_len: *mut size_t,
+) -> c_int {
+    guard(|| {
+        let rsa = rsa.as_ref().ok_or(PI_CRYPTO_INVALID_ARGUMENT)?;
+        let sig = rsa
+            .0
+            .sign(padding_alg(padding)?, input(msg, msg_len)?)
+            .map_err(error_code)?;
+        output(&sig, out, out_len)
+    })
+}
+
+/// RSA 验证签名
+///
+/// padding: PI_CRYPTO_RSA_PKCS1_SHA256 等
+/// msg, msg_len: 已签名数据
+/// sig, sig_len: 签名
+/// public_key, public_key_len: PKCS1 DER 编码的公钥
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_rsa_verify(
+    padding: c_int,
+    msg: *const u8,
+    msg_len: size_t,
+    sig: *const u8,
+    sig_len: size_t,
+    public_key: *const u8,
+    public_key_len: size_t,
+) -> c_int {
+    guard(|| {
+        let ok = Rsa::verify(
+            padding_alg(padding)?,
+            input(msg, msg_len)?,
+            input(sig, sig_len)?,
+            input(public_key, public_key_len)?,
+        );
+        verified(ok)
+    })
+}
+
+/// 释放 RSA 私钥，rsa 可以为空
+#[no_mangle]
+pub unsafe extern "C" fn pi_crypto_rsa_free(rsa: *mut PiCryptoRsa) {
+    if !rsa.is_null() {
+        drop(Box::from_raw(rsa));
+    }
+}
+
+fn guard<F: FnOnce() -> Result<(), c_int>>(f: F) -> c_int {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => PI_CRYPTO_OK,
+        Ok(Err(code)) => code,
+        Err(_) => PI_CRYPTO_INTERNAL_ERROR,
+    }
+}
+
+unsafe fn input<'a>(data: *const u8, len: size_t) -> Result<&'a [u8], c_int> {
+    if len == 0 {
+        Ok(&[])
+    } else if data.is_null() {
+        Err(PI_CRYPTO_INVALID_ARGUMENT)
+    } else {
+        Ok(slice::from_raw_parts(data, len))
+    }
+}
+
+unsafe fn output(data: &[u8], out: *mut u8, out_len: *mut size_t) -> Result<(), c_int> {
+    let out_len = out_len.as_mut().ok_or(PI_CRYPTO_INVALID_ARGUMENT)?;
+    let capacity = *out_len;
+    *out_len = data.len();
+    if capacity < data.len() {
+        return Err(PI_CRYPTO_BUFFER_TOO_SMALL);
+    }
+    if !data.is_empty() {
+        if out.is_null() {
+            return Err(PI_CRYPTO_INVALID_ARGUMENT);
+        }
+        ptr::copy_nonoverlapping(data.as_ptr(), out, data.len());
+    }
+    Ok(())
+}
+
+unsafe fn new_rsa(key: Rsa, rsa: *mut *mut PiCryptoRsa) -> Result<(), c_int> {
+    if rsa.is_null() {
+        return Err(PI_CRYPTO_INVALID_ARGUMENT);
+    }
+    *rsa = Box::into_raw(Box::new(PiCryptoRsa(key)));
+    Ok(())
+}
+
+fn verified(ok: bool) -> Result<(), c_int> {
+    if ok {
+        Ok(())
+    } else {
+        Err(PI_CRYPTO_VERIFY_FAILED)
+    }
+}
+
+fn error_code(e: CryptoError) -> c_int {
+    match e {
+        CryptoError::InvalidKey(_) => PI_CRYPTO_INVALID_KEY,
+        CryptoError::InvalidInput(_) => PI_CRYPTO_INVALID_ARGUMENT,
+        CryptoError::OperationFailed(_) => PI_CRYPTO_OPERATION_FAILED,
+        CryptoError::Verify(_) => PI_CRYPTO_VERIFY_FAILED,
+    }
+}
+
+fn digest_alg(alg: c_int) -> Result<DigestAlgorithm, c_int> {
+    match alg {
+        PI_CRYPTO_SHA256 => Ok(DigestAlgorithm::SHA256),
+        PI_CRYPTO_SHA384 => Ok(DigestAlgorithm::SHA384),
+        PI_CRYPTO_SHA512 => Ok(DigestAlgorithm::SHA512),
+        PI_CRYPTO_SM3 => Ok(DigestAlgorithm::SM3),
+        PI_CRYPTO_SHA3_256 => Ok(DigestAlgorithm::SHA3_256),
+        PI_CRYPTO_SHA3_384 => Ok(DigestAlgorithm::SHA3_384),
+        PI_CRYPTO_SHA3_512 => Ok(DigestAlgorithm::SHA3_512),
+        PI_CRYPTO_KECCAK256 => Ok(DigestAlgorithm::KECCAK256),
+        _ => Err(PI_CRYPTO_INVALID_ARGUMENT),
+    }
+}
+
+fn hmac_alg(alg: c_int) -> Result<hmac::DigestAlgorithm, c_int> {
+    match alg {
+        PI_CRYPTO_SHA256 => Ok(hmac::DigestAlgorithm::SHA256),
+        PI_CRYPTO_SHA384 => Ok(hmac::DigestAlgorithm::SHA384),
+        PI_CRYPTO_SHA512 => Ok(hmac::DigestAlgorithm::SHA512),
+        PI_CRYPTO_SM3 => Ok(hmac::DigestAlgorithm::SM3),
+        _ => Err(PI_CRYPTO_INVALID_ARGUMENT),
+    }
+}
+
+fn padding_alg(padding: c_int) -> Result<PaddingAlg, c_int> {
+    match padding {
+        PI_CRYPTO_RSA_PKCS1_SHA256 => Ok(PaddingAlg::RSA_PKCS1_SHA256),
+        PI_CRYPTO_RSA_PKCS1_SHA384 => Ok(PaddingAlg::RSA_PKCS1_SHA384),
+        PI_CRYPTO_RSA_PKCS1_SHA512 => Ok(PaddingAlg::RSA_PKCS1_SHA512),
+        PI_CRYPTO_RSA_PSS_SHA256 => Ok(PaddingAlg::RSA_PSS_SHA256),
+        PI_CRYPTO_RSA_PSS_SHA384 => Ok(PaddingAlg::RSA_PSS_SHA384),
+        PI_CRYPTO_RSA_PSS_SHA512 => Ok(PaddingAlg::RSA_PSS_SHA512),
+        _ => Err(PI_CRYPTO_INVALID_ARGUMENT),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_ffi() {
+        unsafe {
+            // FIPS 180-2 SHA-256("abc")
+            let mut out = [0u8; 64];
+            let mut out_len = 16;
+            assert_eq!(
+                pi_crypto_digest(
+                    PI_CRYPTO_SHA256,
+                    b"abc".as_ptr(),
+                    3,
+                    out.as_mut_ptr(),
+                    &mut out_len
+                ),
+                PI_CRYPTO_BUFFER_TOO_SMALL
+            );
+            assert_eq!(out_len, 32);
+            assert_eq!(
+                pi_crypto_digest(
+                    PI_CRYPTO_SHA256,
+                    b"abc".as_ptr(),
+                    3,
+                    out.as_mut_ptr(),
+                    &mut out_len
+                ),
+                PI_CRYPTO_OK
+            );
+            let abc =
+                Vec::from_hex("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+                    .unwrap();
+            assert_eq!(&out[..out_len], &abc[..]);
+            assert_eq!(
+                pi_crypto_digest(99, ptr::null(), 0, out.as_mut_ptr(), &mut out_len),
+                PI_CRYPTO_INVALID_ARGUMENT
+            );
+
+            let mut hasher = ptr::null_mut();
+            assert_eq!(
+                pi_crypto_hasher_new(PI_CRYPTO_SHA256, &mut hasher),
+                PI_CRYPTO_OK
+            );
+            pi_crypto_hasher_update(hasher, b"a".as_ptr(), 1);
+            pi_crypto_hasher_update(hasher, b"bc".as_ptr(), 2);
+            out_len = out.len();
+            assert_eq!(
+                pi_crypto_hasher_finalize(hasher, out.as_mut_ptr(), &mut out_len),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(&out[..out_len], &abc[..]);
+            pi_crypto_hasher_free(hasher);
+
+            // RFC 4231 Test Case 2
+            out_len = out.len();
+            let key = b"Jefe";
+            let data = b"what do ya want for nothing?";
+            assert_eq!(
+                pi_crypto_hmac(
+                    PI_CRYPTO_SHA256,
+                    key.as_ptr(),
+                    4,
+                    data.as_ptr(),
+                    data.len(),
+                    out.as_mut_ptr(),
+                    &mut out_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                &out[..out_len],
+                &Vec::from_hex("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+                    .unwrap()[..]
+            );
+            assert_eq!(
+                pi_crypto_hmac_verify(
+                    PI_CRYPTO_SHA256,
+                    key.as_ptr(),
+                    4,
+                    data.as_ptr(),
+                    data.len(),
+                    out.as_ptr(),
+                    31
+                ),
+                PI_CRYPTO_VERIFY_FAILED
+            );
+
+            // RFC 8032 7.1 TEST 1
+            let seed =
+                Vec::from_hex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+                    .unwrap();
+            let (mut sk, mut pk) = ([0u8; 64], [0u8; 32]);
+            let (mut sk_len, mut pk_len) = (64, 32);
+            assert_eq!(
+                pi_crypto_ed25519_keypair_from_seed(
+                    seed.as_ptr(),
+                    32,
+                    sk.as_mut_ptr(),
+                    &mut sk_len,
+                    pk.as_mut_ptr(),
+                    &mut pk_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                pk.to_vec(),
+                Vec::from_hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+                    .unwrap()
+            );
+            out_len = out.len();
+            assert_eq!(
+                pi_crypto_ed25519_sign(
+                    sk.as_ptr(),
+                    64,
+                    ptr::null(),
+                    0,
+                    out.as_mut_ptr(),
+                    &mut out_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                &out[..out_len],
+                &Vec::from_hex("e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b").unwrap()[..]
+            );
+            assert_eq!(
+                pi_crypto_ed25519_verify(pk.as_ptr(), 32, ptr::null(), 0, out.as_ptr(), 64),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                pi_crypto_ed25519_verify(pk.as_ptr(), 32, b"x".as_ptr(), 1, out.as_ptr(), 64),
+                PI_CRYPTO_VERIFY_FAILED
+            );
+
+            let sk = [0x46u8; 32];
+            let mut pk = [0u8; 65];
+            let mut pk_len = 65;
+            assert_eq!(
+                pi_crypto_secp256k1_public_key(sk.as_ptr(), 32, 1, pk.as_mut_ptr(), &mut pk_len),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(pk_len, 33);
+            let mut sig = [0u8; 72];
+            let mut sig_len = sig.len();
+            let msg = [7u8; 32];
+            assert_eq!(
+                pi_crypto_secp256k1_sign(
+                    msg.as_ptr(),
+                    32,
+                    sk.as_ptr(),
+                    32,
+                    sig.as_mut_ptr(),
+                    &mut sig_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                pi_crypto_secp256k1_verify(
+                    msg.as_ptr(),
+                    32,
+                    sig.as_ptr(),
+                    sig_len,
+                    pk.as_ptr(),
+                    pk_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                pi_crypto_secp256k1_sign(
+                    msg.as_ptr(),
+                    31,
+                    sk.as_ptr(),
+                    32,
+                    sig.as_mut_ptr(),
+                    &mut sig_len
+                ),
+                PI_CRYPTO_INVALID_ARGUMENT
+            );
+            assert_eq!(
+                pi_crypto_secp256k1_sign(
+                    msg.as_ptr(),
+                    32,
+                    [0u8; 32].as_ptr(),
+                    32,
+                    sig.as_mut_ptr(),
+                    &mut sig_len
+                ),
+                PI_CRYPTO_INVALID_KEY
+            );
+
+            let pkcs8 = include_bytes!("../tests/rsa-2048-private-key.pk8");
+            let mut rsa = ptr::null_mut();
+            assert_eq!(
+                pi_crypto_rsa_from_pkcs8(pkcs8.as_ptr(), pkcs8.len(), &mut rsa),
+                PI_CRYPTO_OK
+            );
+            let mut rsa_pk = [0u8; 512];
+            let mut rsa_pk_len = rsa_pk.len();
+            assert_eq!(
+                pi_crypto_rsa_public_key(rsa, rsa_pk.as_mut_ptr(), &mut rsa_pk_len),
+                PI_CRYPTO_OK
+            );
+            let mut rsa_sig = [0u8; 256];
+            let mut rsa_sig_len = rsa_sig.len();
+            assert_eq!(
+                pi_crypto_rsa_sign(
+                    rsa,
+                    PI_CRYPTO_RSA_PSS_SHA256,
+                    b"abc".as_ptr(),
+                    3,
+                    rsa_sig.as_mut_ptr(),
+                    &mut rsa_sig_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                pi_crypto_rsa_verify(
+                    PI_CRYPTO_RSA_PSS_SHA256,
+                    b"abc".as_ptr(),
+                    3,
+                    rsa_sig.as_ptr(),
+                    rsa_sig_len,
+                    rsa_pk.as_ptr(),
+                    rsa_pk_len
+                ),
+                PI_CRYPTO_OK
+            );
+            assert_eq!(
+                pi_crypto_rsa_verify(
+                    PI_CRYPTO_RSA_PKCS1_SHA256,
+                    b"abc".as_ptr(),
+                    3,
+                    rsa_sig.as_ptr(),
+                    rsa_sig_len,
+                    rsa_pk.as_ptr(),
+                    rsa_pk_len
+                ),
+                PI_CRYPTO_VERIFY_FAILED
+            );
+            pi_crypto_rsa_free(rsa);
+            pi_crypto_rsa_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,236 @@
+//! GF(p)，p = 2^448 - 2^224 - 1 上的常量时间运算
+//!
+//! 供 ed448 和 x448 使用，元素为8个56位的分量，运算中没有依赖数值的分支和查表，
+//! 分量在每次运算后弱约简到略大于56位，输出字节时再完全约简到 [0, p)
+
+use std::ops::{Add, Mul, Neg, Sub};
+
+/// 元素的字节长度
+pub(crate) const SIZE: usize = 56;
+
+const MASK: u64 = (1 << 56) - 1;
+
+// p 的分量，2^224 位于第4个分量的最低位
+const P: [u64; 8] = [MASK, MASK, MASK, MASK, MASK - 1, MASK, MASK, MASK];
+
+/// 域元素，小端序的8个56位分量
+#[derive(Clone, Copy)]
+pub(crate) struct Fe([u64; 8]);
+
+impl Fe {
+    pub(crate) const ZERO: Fe = Fe([0; 8]);
+    pub(crate) const ONE: Fe = Fe([1, 0, 0, 0, 0, 0, 0, 0]);
+
+    pub(crate) fn from_u64(x: u64) -> Fe {
+        let mut r = Fe([x & MASK, x >> 56, 0, 0, 0, 0, 0, 0]);
+        r.carry();
+        r
+    }
+
+    /// 56字节小端序，不要求小于 p
+    pub(crate) fn from_bytes(bytes: &[u8; SIZE]) -> Fe {
+        let mut limbs = [0u64; 8];
+        for (limb, chunk) in limbs.iter_mut().zip(bytes.chunks(7)) {
+            let mut buf = [0u8; 8];
+            buf[..7].copy_from_slice(chunk);
+            *limb = u64::from_le_bytes(buf);
+        }
+        Fe(limbs)
+    }
+
+    /// 完全约简后的56字节小端序
+    pub(crate) fn to_bytes(self) -> [u8; SIZE] {
+        let mut t = self;
+        t.carry();
+        // t < 2p，减去 p，结果为负时再加回 p
+        let mut out = [0u64; 8];
+        let mut borrow = 0i128;
+        for i in 0..8 {
+            let v = t.0[i] as i128 - P[i] as i128 + borrow;
+            out[i] = (v as u64) & MASK;
+            borrow = v >> 56;
+        }
+        let mask = borrow as u64;
+        let mut carry = 0u64;
+        for i in 0..8 {
+            let v = out[i] + (P[i] & mask) + carry;
+            out[i] = v & MASK;
+            carry = v >> 56;
+        }
+        let mut bytes = [0u8; SIZE];
+        for (chunk, limb) in bytes.chunks_mut(7).zip(out.iter()) {
+            chunk.copy_from_slice(&limb.to_le_bytes()[..7]);
+        }
+        bytes
+    }
+
+    pub(crate) fn square(self) -> Fe {
+        self * self
+    }
+
+    /// 指数为公开值，按位平方乘，耗时只与指数有关
+    pub(crate) fn pow(self, exp: &[u8; SIZE]) -> Fe {
+        let mut r = Fe::ONE;
+        for i in (0..SIZE * 8).rev() {
+            r = r.square();
+            if (exp[i / 8] >> (i % 8)) & 1 == 1 {
+                r = r * self;
+            }
+        }
+        r
+    }
+
+    /// 费马小定理求逆，a^(p-2)，零的逆为零
+    pub(crate) fn invert(self) -> Fe {
+        let mut exp = [0xffu8; SIZE];
+        exp[0] = 0xfd;
+        exp[28] = 0xfe;
+        self.pow(&exp)
+    }
+
+    pub(crate) fn is_zero(self) -> bool {
+        self.to_bytes().iter().fold(0, |acc, b| acc | b) == 0
+    }
+
+    pub(crate) fn is_odd(self) -> bool {
+        self.to_bytes()[0] & 1 == 1
+    }
+
+    /// choice 为1时交换 a 和 b，为0时不变
+    pub(crate) fn cswap(a: &mut Fe, b: &mut Fe, choice: u64) {
+        let mask = choice.wrapping_neg();
+        for i in 0..8 {
+            let t = mask & (a.0[i] ^ b.0[i]);
+            a.0[i] ^= t;
+            b.0[i] ^= t;
+        }
+    }
+
+    // 进位传播，最高分量的进位按 2^448 = 2^224 + 1 折回第0和第4个分量
+    fn carry(&mut self) {
+        let l = &mut self.0;
+        for i in 0..7 {
+            l[i + 1] += l[i] >> 56;
+            l[i] &= MASK;
+        }
+        let c = l[7] >> 56;
+        l[7] &= MASK;
+        l[0] += c;
+        l[4] += c;
+    }
+}
+
+impl Add for Fe {
+    type Output = Fe;
+
+    fn add(self, rhs: Fe) -> Fe {
+        let mut r = self;
+        for i in 0..8 {
+            r.0[i] += rhs.0[i];
+        }
+        r.carry();
+        r
+    }
+}
+
+impl Sub for Fe {
+    type Output = Fe;
+
+    // 加上 2p 避免分量下溢
+    fn sub(self, rhs: Fe) -> Fe {
+        let mut r = self;
+        for ((l, p), b) in r.0.iter_mut().zip(P.iter()).zip(rhs.0.iter()) {
+            *l = *l + 2 * p - b;
+        }
+        r.carry();
+        r
+    }
+}
+
+impl Neg for Fe {
+    type Output = Fe;
+
+    fn neg(self) -> Fe {
+        Fe::ZERO - self
+    }
+}
+
+impl Mul for Fe {
+    type Output = Fe;
+
+    fn mul(self, rhs: Fe) -> Fe {
+        let (a, b) = (&self.0, &rhs.0);
+        let mut z = [0u128; 15];
+        for i in 0..8 {
+            for j in 0..8 {
+                z[i + j] += a[i] as u128 * b[j] as u128;
+            }
+        }
+        // 2^(56k) = 2^(56(k-8)) + 2^(56(k-4))，从高到低折叠
+        for k in (8..15).rev() {
+            z[k - 8] += z[k];
+            z[k - 4] += z[k];
+        }
+        for _ in 0..2 {
+            for i in 0..7 {
+                z[i + 1] += z[i] >> 56;
+                z[i] &= MASK as u128;
+            }
+            let c = z[7] >> 56;
+            z[7] &= MASK as u128;
+            z[0] += c;
+            z[4] += c;
+        }
+        let mut r = Fe([0; 8]);
+        for (l, zi) in r.0.iter_mut().zip(z.iter()) {
+            *l = *zi as u64;
+        }
+        r
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use num_bigint::BigUint;
+    use num_traits::One;
+
+    fn to_uint(x: &Fe) -> BigUint {
+        BigUint::from_bytes_le(&x.to_bytes())
+    }
+
+    #[test]
+    fn test_field448() {
+        let p = (BigUint::one() << 448) - (BigUint::one() << 224) - BigUint::one();
+        let mut a_bytes = [0xffu8; SIZE];
+        let mut b_bytes = [0u8; SIZE];
+        for i in 0..SIZE {
+            a_bytes[i] ^= (i as u8).wrapping_mul(37);
+            b_bytes[i] = (i as u8).wrapping_mul(101).wrapping_add(3);
+        }
+        let (a, b) = (Fe::from_bytes(&a_bytes), Fe::from_bytes(&b_bytes));
+        let (x, y) = (
+            BigUint::from_bytes_le(&a_bytes),
+            BigUint::from_bytes_le(&b_bytes),
+        );
+        assert_eq!(to_uint(&a), &x % &p);
+        assert_eq!(to_uint(&(a + b)), (&x + &y) % &p);
+        assert_eq!(to_uint(&(b - a)), (&y + &p - &x % &p) % &p);
+        assert_eq!(to_uint(&(a * b)), (&x * &y) % &p);
+        assert_eq!(to_uint(&(a * a.invert())), BigUint::one());
+        assert!(Fe::ZERO.invert().is_zero());
+
+        // p 和 p+1 的非规范编码
+        let p_bytes = {
+            let mut bytes = [0u8; SIZE];
+            bytes.copy_from_slice(&p.to_bytes_le());
+            bytes
+        };
+        assert!(Fe::from_bytes(&p_bytes).is_zero());
+        assert_eq!(
+            to_uint(&(Fe::from_bytes(&p_bytes) + Fe::ONE)),
+            BigUint::one()
+        );
+        assert_eq!(to_uint(&-Fe::ONE), &p - BigUint::one());
+    }
+}
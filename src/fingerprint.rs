@@ -0,0 +1,258 @@
+//! 公钥指纹
+//!
+//! 统一的公钥指纹计算，便于在日志、配置和审计事件中引用密钥：
+//! - SPKI 指纹：对 SubjectPublicKeyInfo DER 编码做 SHA256
+//! - OpenSSH 指纹：对 SSH 公钥格式做 SHA256，输出形如 "SHA256:..." 的 base64 字符串，与 ssh-keygen -l 一致
+//! - 密钥指纹：fingerprint 对 RSA、secp256k1、Ed25519 和 BLS12-381 公钥统一计算，前8个字节作为密钥ID
+
+use ring::digest;
+
+use crate::encoding::{base58, hex};
+use crate::spki::{encode_spki, normalize_public_key, rsa_components, PublicKeyType};
+
+/// 短指纹取完整指纹的前8个字节
+const SHORT_LEN: usize = 8;
+
+/// BLS12-381 G2 公钥的压缩编码长度
+const BLS_PUBLIC_KEY_LEN: usize = 96;
+
+/// fingerprint 支持的公钥算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyAlgorithm {
+    /// PKCS#1 RSAPublicKey DER 编码的 RSA 公钥
+    Rsa,
+    /// 33字节压缩或65字节未压缩的 secp256k1 公钥
+    Secp256k1,
+    /// 32字节 Ed25519 公钥
+    Ed25519,
+    /// 96字节压缩的 BLS12-381 G2 公钥，与 bls 模块的序列化格式相同
+    Bls12381,
+}
+
+/// SHA256 公钥指纹
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Fingerprint([u8; 32]);
+
+impl Fingerprint {
+    /// 由32字节的 SHA256 指纹构建
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Fingerprint(bytes)
+    }
+
+    /// 获取指纹的原始字节
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// 完整指纹的小写hex编码
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    /// 短指纹的小写hex编码，16个字符
+    pub fn to_short_hex(&self) -> String {
+        hex::encode(&self.0[..SHORT_LEN])
+    }
+
+    /// 密钥ID，完整指纹的前8个字节
+    pub fn key_id(&self) -> [u8; SHORT_LEN] {
+        let mut id = [0u8; SHORT_LEN];
+        id.copy_from_slice(&self.0[..SHORT_LEN]);
+        id
+    }
+
+    /// 完整指纹的 base58 编码
+    pub fn to_base58(&self) -> String {
+        base58::encode(&self.0)
+    }
+
+    /// 密钥ID的 base58 编码
+    pub fn to_short_base58(&self) -> String {
+        base58::encode(&self.0[..SHORT_LEN])
+    }
+
+    /// "SHA256:" 前缀加无填充的 base64 编码
+    pub fn to_base64(&self) -> String {
+        format!(
+            "SHA256:{}",
+            base64::encode_config(self.0, base64::STANDARD_NO_PAD)
+        )
+    }
+}
+
+/// 对公钥的 SubjectPublicKeyInfo DER 编码计算指纹
+///
+/// key_type: 公钥类型
+/// pk: 原始公钥
+pub fn spki_fingerprint(key_type: PublicKeyType, pk: &[u8]) -> Result<Fingerprint, String> {
+    let spki = encode_spki(key_type, pk)?;
+    Ok(sha256(&spki))
+}
+
+/// 计算密钥指纹
+///
+/// RSA、secp256k1 和 Ed25519 公钥与 spki_fingerprint 的结果相同，也接受 SubjectPublicKeyInfo 格式，
+/// secp256k1 的压缩和未压缩编码得到同一个指纹；BLS12-381 没有 SubjectPublicKeyInfo 定义，
+/// 直接对96字节压缩公钥做 SHA256
+pub fn fingerprint(alg: KeyAlgorithm, pk: &[u8]) -> Result<Fingerprint, String> {
+    let key_type = match alg {
+        KeyAlgorithm::Rsa => PublicKeyType::Rsa,
+        KeyAlgorithm::Secp256k1 => PublicKeyType::Secp256k1,
+        KeyAlgorithm::Ed25519 => PublicKeyType::Ed25519,
+        KeyAlgorithm::Bls12381 => {
+            if pk.len() != BLS_PUBLIC_KEY_LEN {
+                return Err("fingerprint: BLS12-381 public key must be 96 bytes".to_string());
+            }
+            return Ok(sha256(pk));
+        }
+    };
+    spki_fingerprint(key_type, &normalize_public_key(key_type, pk)?)
+}
+
+/// 计算 OpenSSH 格式的公钥指纹，形如 "SHA256:..."
+///
+/// 只支持 OpenSSH 定义了公钥格式的 Ed25519, P-256, P-384 和 RSA
+pub fn openssh_fingerprint(key_type: PublicKeyType, pk: &[u8]) -> Result<String, String> {
+    let blob = ssh_public_key_blob(key_type, pk)?;
+    Ok(sha256(&blob).to_base64())
+}
+
+/// 把公钥编码为 SSH 线路格式 (RFC 4253, RFC 5656)
+pub(crate) fn ssh_public_key_blob(key_type: PublicKeyType, pk: &[u8]) -> Result<Vec<u8>, String> {
+    let mut blob = Vec::new();
+    match key_type {
+        PublicKeyType::Ed25519 => {
+            if pk.len() != 32 {
+                return Err("fingerprint: Ed25519 public key must be 32 bytes".to_string());
+            }
+            put_string(&mut blob, b"ssh-ed25519");
+            put_string(&mut blob, pk);
+        }
+        PublicKeyType::EcdsaP256 | PublicKeyType::EcdsaP384 => {
+            let (name, curve, len): (&[u8], &[u8], usize) = match key_type {
+                PublicKeyType::EcdsaP256 => (b"ecdsa-sha2-nistp256", b"nistp256", 65),
+                _ => (b"ecdsa-sha2-nistp384", b"nistp384", 97),
+            };
+            if pk.len() != len || pk[0] != 0x04 {
+                return Err("fingerprint: malformed ECDSA public key".to_string());
+            }
+            put_string(&mut blob, name);
+            put_string(&mut blob, curve);
+            put_string(&mut blob, pk);
+        }
+        PublicKeyType::Rsa => {
+            let (n, e) = rsa_components(pk)?;
+            put_string(&mut blob, b"ssh-rsa");
+            put_mpint(&mut blob, &e);
+            put_mpint(&mut blob, &n);
+        }
+        PublicKeyType::X25519
+        | PublicKeyType::Ed448
+        | PublicKeyType::X448
+        | PublicKeyType::Secp256k1 => {
+            return Err(format!(
+                "fingerprint: OpenSSH has no public key format for {:?}",
+                key_type
+            ))
+        }
+    }
+    Ok(blob)
+}
+
+pub(crate) fn put_string(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+fn put_mpint(out: &mut Vec<u8>, magnitude: &[u8]) {
+    let start = magnitude
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(magnitude.len());
+    let magnitude = &magnitude[start..];
+    if matches!(magnitude.first(), Some(b) if b & 0x80 != 0) {
+        out.extend_from_slice(&(magnitude.len() as u32 + 1).to_be_bytes());
+        out.push(0);
+        out.extend_from_slice(magnitude);
+    } else {
+        put_string(out, magnitude);
+    }
+}
+
+fn sha256(data: &[u8]) -> Fingerprint {
+    let mut fp = [0u8; 32];
+    fp.copy_from_slice(digest::digest(&digest::SHA256, data).as_ref());
+    Fingerprint(fp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::Rsa;
+    use ::hex::FromHex;
+
+    #[test]
+    fn test_ed25519_fingerprint() {
+        // 与 ssh-keygen -lf 的输出对比
+        let pk = Vec::from_hex("61f6393c465e51fc7fa1ccc1db8e45f510b231d63b8bbcf34bae8ce27fb056fd")
+            .unwrap();
+        assert_eq!(
+            openssh_fingerprint(PublicKeyType::Ed25519, &pk).unwrap(),
+            "SHA256:jGIheqAbE2YlcqIkC3Aq5iZZ3KmZOkCtSr1Ytrq2Mjk"
+        );
+
+        let fp = spki_fingerprint(PublicKeyType::Ed25519, &pk).unwrap();
+        assert_eq!(fp.to_short_hex(), fp.to_hex()[..16]);
+        assert!(openssh_fingerprint(PublicKeyType::Secp256k1, &pk).is_err());
+    }
+
+    #[test]
+    fn test_rsa_fingerprint() {
+        // 与 openssl pkey -pubout -outform DER | sha256sum 和 ssh-keygen -lf 的输出对比
+        let sk = include_bytes!("../tests/rsa-2048-private-key.pk8");
+        let pk = Rsa::fromPKCS8(sk).unwrap().public_key();
+        assert_eq!(
+            spki_fingerprint(PublicKeyType::Rsa, &pk).unwrap().to_hex(),
+            "99b6fc1c97bd325962d22dc593e16f656dca0332e4ec13ecc6c6cd53edbe9956"
+        );
+        assert_eq!(
+            openssh_fingerprint(PublicKeyType::Rsa, &pk).unwrap(),
+            "SHA256:6c7VKpmn4FGteGZkiGsZp946EvfEkvKSY3cMmZug9ic"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint() {
+        // secp256k1 生成元的压缩和未压缩编码
+        let compressed =
+            Vec::from_hex("0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap();
+        let uncompressed = Vec::from_hex("0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8").unwrap();
+        let fp = fingerprint(KeyAlgorithm::Secp256k1, &compressed).unwrap();
+        assert_eq!(
+            fp,
+            fingerprint(KeyAlgorithm::Secp256k1, &uncompressed).unwrap()
+        );
+        assert_eq!(
+            fp,
+            spki_fingerprint(PublicKeyType::Secp256k1, &uncompressed).unwrap()
+        );
+        let spki = encode_spki(PublicKeyType::Secp256k1, &compressed).unwrap();
+        assert_eq!(fp, fingerprint(KeyAlgorithm::Secp256k1, &spki).unwrap());
+        assert!(fingerprint(KeyAlgorithm::Ed25519, &spki).is_err());
+
+        assert_eq!(fp.key_id()[..], fp.as_bytes()[..8]);
+        assert_eq!(hex::encode(&fp.key_id()), fp.to_short_hex());
+        assert_eq!(base58::decode(&fp.to_short_base58()).unwrap(), fp.key_id());
+        assert_eq!(base58::decode(&fp.to_base58()).unwrap(), fp.as_bytes());
+
+        let bls = [0xa5u8; 96];
+        assert_eq!(
+            fingerprint(KeyAlgorithm::Bls12381, &bls)
+                .unwrap()
+                .as_bytes()[..],
+            digest::digest(&digest::SHA256, &bls).as_ref()[..]
+        );
+        assert!(fingerprint(KeyAlgorithm::Bls12381, &bls[1..]).is_err());
+    }
+}
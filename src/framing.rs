@@ -0,0 +1,141 @@
+//! 定长整数和长度前缀字段的二进制帧，envelope、merkle、multisig 和 cbor 共用
+//!
+//! 整数均为大端序，写入时长度超出前缀的表示范围返回错误而不是截断，
+//! 错误信息以调用方的模块名开头
+
+use std::convert::TryFrom;
+
+/// 顺序读取字段
+pub(crate) struct Reader<'a> {
+    module: &'static str,
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(module: &'static str, data: &'a [u8]) -> Self {
+        Reader {
+            module,
+            data,
+            pos: 0,
+        }
+    }
+
+    /// 已读取的字节数
+    pub(crate) fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// 剩余的字节数
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    /// 从 start 到当前位置的数据
+    pub(crate) fn since(&self, start: usize) -> &'a [u8] {
+        &self.data[start..self.pos]
+    }
+
+    pub(crate) fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if self.remaining() < len {
+            return Err(format!("{}: unexpected end of data", self.module));
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub(crate) fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub(crate) fn u16(&mut self) -> Result<u16, String> {
+        let b = self.take(2)?;
+        Ok(u16::from_be_bytes([b[0], b[1]]))
+    }
+
+    pub(crate) fn u32(&mut self) -> Result<u32, String> {
+        let b = self.take(4)?;
+        Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, String> {
+        let mut b = [0u8; 8];
+        b.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(b))
+    }
+
+    /// 读取 u16 长度前缀的字段
+    pub(crate) fn bytes16(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u16()?;
+        self.take(usize::from(len))
+    }
+
+    /// 读取 u32 长度前缀的字段
+    pub(crate) fn bytes32(&mut self) -> Result<&'a [u8], String> {
+        let len = self.u32()?;
+        self.take(len as usize)
+    }
+
+    /// 检查数据已全部读完
+    pub(crate) fn finish(&self) -> Result<(), String> {
+        if self.remaining() != 0 {
+            return Err(format!("{}: trailing data", self.module));
+        }
+        Ok(())
+    }
+}
+
+/// 写入 u16 的个数或长度，超出范围时返回错误
+pub(crate) fn put_u16_len(out: &mut Vec<u8>, module: &str, len: usize) -> Result<(), String> {
+    let len = u16::try_from(len).map_err(|_| format!("{}: field too long", module))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    Ok(())
+}
+
+/// 写入 u16 长度前缀的字段
+pub(crate) fn put_bytes16(out: &mut Vec<u8>, module: &str, data: &[u8]) -> Result<(), String> {
+    put_u16_len(out, module, data.len())?;
+    out.extend_from_slice(data);
+    Ok(())
+}
+
+/// 写入 u32 长度前缀的字段
+pub(crate) fn put_bytes32(out: &mut Vec<u8>, module: &str, data: &[u8]) -> Result<(), String> {
+    let len = u32::try_from(data.len()).map_err(|_| format!("{}: field too long", module))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    out.extend_from_slice(data);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_framing() {
+        let mut out = Vec::new();
+        put_bytes16(&mut out, "test", b"key").unwrap();
+        put_bytes32(&mut out, "test", b"payload").unwrap();
+        out.push(7);
+        assert_eq!(&out[..5], b"\x00\x03key");
+
+        let mut reader = Reader::new("test", &out);
+        assert_eq!(reader.bytes16().unwrap(), b"key");
+        assert_eq!(reader.bytes32().unwrap(), b"payload");
+        assert_eq!(reader.finish(), Err("test: trailing data".to_string()));
+        assert_eq!(reader.u8().unwrap(), 7);
+        assert!(reader.finish().is_ok());
+        assert_eq!(
+            reader.u16(),
+            Err("test: unexpected end of data".to_string())
+        );
+
+        // 超出 u16 的字段返回错误，不截断长度
+        assert_eq!(
+            put_bytes16(&mut Vec::new(), "test", &[0u8; 0x10000]),
+            Err("test: field too long".to_string())
+        );
+        assert!(put_u16_len(&mut Vec::new(), "test", 0xffff).is_ok());
+    }
+}
@@ -0,0 +1,626 @@
+//! BIP-32 分层确定性密钥派生 (secp256k1)
+//!
+//! 从种子派生主密钥，再按路径派生子密钥。硬化派生 (index >= 2^31) 只能由私钥进行，
+//! 非硬化派生也可以只用扩展公钥进行。扩展密钥序列化为标准的 Base58Check xprv/xpub 字符串。
+//!
+//! Ed25519ExtendedKey 按 SLIP-0010 从同样的种子派生 ed25519 密钥，只支持硬化派生
+
+use std::fmt;
+use std::str::FromStr;
+
+use ring::hmac;
+use secp256k1::{PublicKey, SecretKey};
+
+use crate::ct::{ct_eq, wipe};
+use crate::digest::{checksum4, hash160};
+use crate::ed25519;
+use crate::encoding::base58;
+use crate::secret::SecretBytes;
+
+/// 硬化派生的起始序号
+pub const HARDENED: u32 = 0x8000_0000;
+
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+const XPUB_VERSION: [u8; 4] = [0x04, 0x88, 0xb2, 0x1e];
+const SERIALIZED_LEN: usize = 78;
+
+/// 派生路径，如 m/44'/0'/0'/0/0，硬化序号可以用 ' 或 h 标记
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DerivationPath(Vec<u32>);
+
+impl DerivationPath {
+    /// 由子密钥序号构建，硬化序号需要加上 HARDENED
+    pub fn new(indexes: Vec<u32>) -> Self {
+        DerivationPath(indexes)
+    }
+
+    pub fn indexes(&self) -> &[u32] {
+        &self.0
+    }
+}
+
+impl FromStr for DerivationPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut parts = s.split('/');
+        if parts.next() != Some("m") {
+            return Err("hd: derivation path must start with m".to_string());
+        }
+        parts
+            .map(|part| {
+                let (num, hardened) = match part.strip_suffix(&['\'', 'h', 'H'][..]) {
+                    Some(num) => (num, true),
+                    None => (part, false),
+                };
+                match num.parse::<u32>() {
+                    Ok(i) if i < HARDENED && !num.starts_with('+') => {
+                        Ok(if hardened { i | HARDENED } else { i })
+                    }
+                    _ => Err(format!("hd: invalid path component {:?}", part)),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(DerivationPath)
+    }
+}
+
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "m")?;
+        for i in &self.0 {
+            if i & HARDENED != 0 {
+                write!(f, "/{}'", i & !HARDENED)?;
+            } else {
+                write!(f, "/{}", i)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 扩展私钥
+#[derive(Clone)]
+pub struct ExtendedPrivateKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: [u8; 32],
+    // libsecp256k1 的 SecretKey 在 drop 时用 volatile 写清零，序列化出的临时副本在使用后擦除
+    secret_key: SecretKey,
+}
+
+/// 扩展公钥
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtendedPublicKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: [u8; 32],
+    public_key: PublicKey,
+}
+
+impl ExtendedPrivateKey {
+    /// 由种子派生主密钥
+    ///
+    /// seed: 种子，16到64字节，一般为 BIP-39 助记词生成的64字节种子
+    pub fn new_master(seed: &[u8]) -> Result<Self, String> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err("hd: seed must be 16 to 64 bytes".to_string());
+        }
+        let mut i = hmac_sha512(b"Bitcoin seed", &[seed]);
+        let master = split_tweak(&i);
+        wipe(&mut i);
+        let (secret_key, chain_code) = master?;
+        Ok(ExtendedPrivateKey {
+            depth: 0,
+            parent_fingerprint: [0; 4],
+            child_number: 0,
+            chain_code,
+            secret_key,
+        })
+    }
+
+    /// 派生子私钥
+    ///
+    /// index: 子密钥序号，不小于 HARDENED 时为硬化派生
+    pub fn derive_child(&self, index: u32) -> Result<Self, String> {
+        let depth = child_depth(self.depth)?;
+        let mut i = if index & HARDENED != 0 {
+            let mut sk = self.secret_key.serialize();
+            let i = hmac_sha512(&self.chain_code, &[&[0], &sk, &index.to_be_bytes()]);
+            wipe(&mut sk);
+            i
+        } else {
+            hmac_sha512(
+                &self.chain_code,
+                &[&self.compressed_public_key(), &index.to_be_bytes()],
+            )
+        };
+        let tweak = split_tweak(&i);
+        wipe(&mut i);
+        let (tweak, chain_code) = tweak?;
+        let mut secret_key = self.secret_key.clone();
+        secret_key
+            .tweak_add_assign(&tweak)
+            .map_err(|_| invalid_child(index))?;
+        Ok(ExtendedPrivateKey {
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            secret_key,
+        })
+    }
+
+    /// 按路径逐级派生，路径相对于当前密钥
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, String> {
+        path.0
+            .iter()
+            .try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+
+    /// 对应的扩展公钥
+    pub fn public_key(&self) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            depth: self.depth,
+            parent_fingerprint: self.parent_fingerprint,
+            child_number: self.child_number,
+            chain_code: self.chain_code,
+            public_key: PublicKey::from_secret_key(&self.secret_key),
+        }
+    }
+
+    /// 32字节私钥，可直接用于 ECDSASecp256k1 签名
+    pub fn secret_key(&self) -> [u8; 32] {
+        self.secret_key.serialize()
+    }
+
+    /// 33字节压缩公钥
+    pub fn compressed_public_key(&self) -> [u8; 33] {
+        PublicKey::from_secret_key(&self.secret_key).serialize_compressed()
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn child_number(&self) -> u32 {
+        self.child_number
+    }
+
+    /// 密钥指纹，压缩公钥 hash160 的前4字节
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.compressed_public_key())
+    }
+
+    /// 序列化为 xprv 字符串
+    pub fn to_xprv(&self) -> String {
+        let mut key = [0u8; 33];
+        let mut sk = self.secret_key.serialize();
+        key[1..].copy_from_slice(&sk);
+        let xprv = serialize(
+            XPRV_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &key,
+        );
+        wipe(&mut sk);
+        wipe(&mut key);
+        xprv
+    }
+
+    /// 解析 xprv 字符串
+    pub fn from_xprv(s: &str) -> Result<Self, String> {
+        let data = SecretBytes::new(deserialize(s, XPRV_VERSION)?);
+        if data[45] != 0 {
+            return Err("hd: malformed extended private key".to_string());
+        }
+        let secret_key = SecretKey::parse_slice(&data[46..])
+            .map_err(|_| "hd: invalid private key".to_string())?;
+        let (depth, parent_fingerprint, child_number, chain_code) = header(&data)?;
+        Ok(ExtendedPrivateKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            secret_key,
+        })
+    }
+}
+
+impl PartialEq for ExtendedPrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        let (mut a, mut b) = (self.secret_key.serialize(), other.secret_key.serialize());
+        let eq = ct_eq(&a, &b);
+        wipe(&mut a);
+        wipe(&mut b);
+        self.public_key() == other.public_key() && eq
+    }
+}
+
+impl Eq for ExtendedPrivateKey {}
+
+impl fmt::Debug for ExtendedPrivateKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ExtendedPrivateKey")
+            .field("depth", &self.depth)
+            .field("child_number", &self.child_number)
+            .field("public_key", &self.compressed_public_key())
+            .finish()
+    }
+}
+
+impl ExtendedPublicKey {
+    /// 派生非硬化子公钥，index 不小于 HARDENED 时返回错误
+    pub fn derive_child(&self, index: u32) -> Result<Self, String> {
+        if index & HARDENED != 0 {
+            return Err("hd: hardened derivation requires the private key".to_string());
+        }
+        let depth = child_depth(self.depth)?;
+        let i = hmac_sha512(
+            &self.chain_code,
+            &[
+                &self.public_key.serialize_compressed(),
+                &index.to_be_bytes(),
+            ],
+        );
+        let (tweak, chain_code) = split_tweak(&i)?;
+        let mut public_key = self.public_key.clone();
+        public_key
+            .tweak_add_assign(&tweak)
+            .map_err(|_| invalid_child(index))?;
+        Ok(ExtendedPublicKey {
+            depth,
+            parent_fingerprint: self.fingerprint(),
+            child_number: index,
+            chain_code,
+            public_key,
+        })
+    }
+
+    /// 按路径逐级派生，路径中不能有硬化序号
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, String> {
+        path.0
+            .iter()
+            .try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+
+    /// 33字节压缩公钥，可直接用于 ECDSASecp256k1 验证
+    pub fn public_key(&self) -> [u8; 33] {
+        self.public_key.serialize_compressed()
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn child_number(&self) -> u32 {
+        self.child_number
+    }
+
+    /// 密钥指纹，压缩公钥 hash160 的前4字节
+    pub fn fingerprint(&self) -> [u8; 4] {
+        fingerprint(&self.public_key())
+    }
+
+    /// 序列化为 xpub 字符串
+    pub fn to_xpub(&self) -> String {
+        serialize(
+            XPUB_VERSION,
+            self.depth,
+            self.parent_fingerprint,
+            self.child_number,
+            &self.chain_code,
+            &self.public_key(),
+        )
+    }
+
+    /// 解析 xpub 字符串
+    pub fn from_xpub(s: &str) -> Result<Self, String> {
+        let data = deserialize(s, XPUB_VERSION)?;
+        let mut key = [0u8; 33];
+        key.copy_from_slice(&data[45..]);
+        let public_key =
+            PublicKey::parse_compressed(&key).map_err(|_| "hd: invalid public key".to_string())?;
+        let (depth, parent_fingerprint, child_number, chain_code) = header(&data)?;
+        Ok(ExtendedPublicKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code,
+            public_key,
+        })
+    }
+}
+
+/// SLIP-0010 ed25519 扩展私钥
+#[derive(Clone)]
+pub struct Ed25519ExtendedKey {
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: [u8; 32],
+    secret_key: [u8; 32],
+}
+
+impl Ed25519ExtendedKey {
+    /// 由种子派生主密钥
+    ///
+    /// seed: 种子，16到64字节，一般为 BIP-39 助记词生成的64字节种子
+    pub fn new_master(seed: &[u8]) -> Result<Self, String> {
+        if seed.len() < 16 || seed.len() > 64 {
+            return Err("hd: seed must be 16 to 64 bytes".to_string());
+        }
+        let mut i = hmac_sha512(b"ed25519 seed", &[seed]);
+        let key = Ed25519ExtendedKey::from_parts(0, [0; 4], 0, &i);
+        wipe(&mut i);
+        Ok(key)
+    }
+
+    /// 派生子私钥，ed25519 只能硬化派生，index 小于 HARDENED 时返回错误
+    pub fn derive_child(&self, index: u32) -> Result<Self, String> {
+        if index & HARDENED == 0 {
+            return Err("hd: ed25519 only supports hardened derivation".to_string());
+        }
+        let depth = child_depth(self.depth)?;
+        let mut i = hmac_sha512(
+            &self.chain_code,
+            &[&[0], &self.secret_key, &index.to_be_bytes()],
+        );
+        let key = Ed25519ExtendedKey::from_parts(depth, self.fingerprint(), index, &i);
+        wipe(&mut i);
+        Ok(key)
+    }
+
+    /// 按路径逐级派生，路径中的序号都必须是硬化的，如 m/44'/501'/0'/0'
+    pub fn derive_path(&self, path: &DerivationPath) -> Result<Self, String> {
+        path.0
+            .iter()
+            .try_fold(self.clone(), |key, &index| key.derive_child(index))
+    }
+
+    /// 32字节 ed25519 私钥种子
+    pub fn secret_key(&self) -> [u8; 32] {
+        self.secret_key
+    }
+
+    /// 32字节 ed25519 公钥
+    pub fn public_key(&self) -> [u8; 32] {
+        self.keypair().1
+    }
+
+    /// 返回64字节扩展格式私钥和32字节公钥，可直接用于 ed25519 模块的签名函数
+    pub fn keypair(&self) -> ([u8; 64], [u8; 32]) {
+        ed25519::keypair_from_seed(&self.secret_key).expect("hd: 32 byte seed")
+    }
+
+    pub fn chain_code(&self) -> [u8; 32] {
+        self.chain_code
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn child_number(&self) -> u32 {
+        self.child_number
+    }
+
+    /// 父密钥指纹，主密钥为全0
+    pub fn parent_fingerprint(&self) -> [u8; 4] {
+        self.parent_fingerprint
+    }
+
+    /// 密钥指纹，0x00 | 公钥 的 hash160 的前4字节
+    pub fn fingerprint(&self) -> [u8; 4] {
+        let mut key = [0u8; 33];
+        key[1..].copy_from_slice(&self.public_key());
+        fingerprint(&key)
+    }
+
+    fn from_parts(depth: u8, parent_fingerprint: [u8; 4], child_number: u32, i: &[u8; 64]) -> Self {
+        let mut key = Ed25519ExtendedKey {
+            depth,
+            parent_fingerprint,
+            child_number,
+            chain_code: [0; 32],
+            secret_key: [0; 32],
+        };
+        key.secret_key.copy_from_slice(&i[..32]);
+        key.chain_code.copy_from_slice(&i[32..]);
+        key
+    }
+}
+
+impl Drop for Ed25519ExtendedKey {
+    fn drop(&mut self) {
+        wipe(&mut self.secret_key);
+    }
+}
+
+impl fmt::Debug for Ed25519ExtendedKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Ed25519ExtendedKey")
+            .field("depth", &self.depth)
+            .field("child_number", &self.child_number)
+            .field("public_key", &self.public_key())
+            .finish()
+    }
+}
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    let mut ctx = hmac::Context::with_key(&hmac::Key::new(hmac::HMAC_SHA512, key));
+    for part in parts {
+        ctx.update(part);
+    }
+    let mut out = [0u8; 64];
+    out.copy_from_slice(ctx.sign().as_ref());
+    out
+}
+
+// 左32字节作为私钥或调整值，右32字节作为链码
+fn split_tweak(i: &[u8; 64]) -> Result<(SecretKey, [u8; 32]), String> {
+    let key = SecretKey::parse_slice(&i[..32])
+        .map_err(|_| "hd: derived key is out of range, use the next index".to_string())?;
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&i[32..]);
+    Ok((key, chain_code))
+}
+
+fn child_depth(depth: u8) -> Result<u8, String> {
+    depth
+        .checked_add(1)
+        .ok_or_else(|| "hd: maximum derivation depth exceeded".to_string())
+}
+
+fn invalid_child(index: u32) -> String {
+    format!("hd: child {} is invalid, use the next index", index)
+}
+
+fn fingerprint(compressed: &[u8; 33]) -> [u8; 4] {
+    let mut out = [0u8; 4];
+    out.copy_from_slice(&hash160(compressed)[..4]);
+    out
+}
+
+fn serialize(
+    version: [u8; 4],
+    depth: u8,
+    parent_fingerprint: [u8; 4],
+    child_number: u32,
+    chain_code: &[u8; 32],
+    key: &[u8; 33],
+) -> String {
+    let mut data = Vec::with_capacity(SERIALIZED_LEN + 4);
+    data.extend_from_slice(&version);
+    data.push(depth);
+    data.extend_from_slice(&parent_fingerprint);
+    data.extend_from_slice(&child_number.to_be_bytes());
+    data.extend_from_slice(chain_code);
+    data.extend_from_slice(key);
+    let checksum = checksum4(&data);
+    data.extend_from_slice(&checksum);
+    let s = base58::encode(&data);
+    wipe(&mut data);
+    s
+}
+
+fn deserialize(s: &str, version: [u8; 4]) -> Result<Vec<u8>, String> {
+    let data = base58::decode_check(s).map_err(|e| format!("hd: {}", e))?;
+    if data.len() != SERIALIZED_LEN {
+        return Err("hd: extended key must be 78 bytes".to_string());
+    }
+    if data[..4] != version {
+        return Err("hd: unexpected extended key version".to_string());
+    }
+    Ok(data)
+}
+
+fn header(data: &[u8]) -> Result<(u8, [u8; 4], u32, [u8; 32]), String> {
+    let depth = data[4];
+    let mut parent_fingerprint = [0u8; 4];
+    parent_fingerprint.copy_from_slice(&data[5..9]);
+    let mut child_number = [0u8; 4];
+    child_number.copy_from_slice(&data[9..13]);
+    let child_number = u32::from_be_bytes(child_number);
+    // 主密钥的父指纹和序号必须为0
+    if depth == 0 && (parent_fingerprint != [0; 4] || child_number != 0) {
+        return Err("hd: malformed master key".to_string());
+    }
+    let mut chain_code = [0u8; 32];
+    chain_code.copy_from_slice(&data[13..45]);
+    Ok((depth, parent_fingerprint, child_number, chain_code))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_bip32() {
+        // BIP-32 test vector 1
+        let seed = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedPrivateKey::new_master(&seed).unwrap();
+        assert_eq!(master.to_xprv(), "xprv9s21ZrQH143K3QTDL4LXw2F7HEK3wJUD2nW2nRk4stbPy6cq3jPPqjiChkVvvNKmPGJxWUtg6LnF5kejMRNNU3TGtRBeJgk33yuGBxrMPHi");
+        assert_eq!(master.public_key().to_xpub(), "xpub661MyMwAqRbcFtXgS5sYJABqqG9YLmC4Q1Rdap9gSE8NqtwybGhePY2gZ29ESFjqJoCu1Rupje8YtGqsefD265TMg7usUDFdp6W1EGMcet8");
+        assert_eq!(
+            master.derive_child(HARDENED).unwrap().to_xprv(),
+            "xprv9uHRZZhk6KAJC1avXpDAp4MDc3sQKNxDiPvvkX8Br5ngLNv1TxvUxt4cV1rGL5hj6KCesnDYUhd7oWgT11eZG7XnxHrnYeSvkzY7d2bhkJ7"
+        );
+        let path: DerivationPath = "m/0H/1/2H/2/1000000000".parse().unwrap();
+        assert_eq!(path.to_string(), "m/0'/1/2'/2/1000000000");
+        let child = master.derive_path(&path).unwrap();
+        assert_eq!(child.to_xprv(), "xprvA41z7zogVVwxVSgdKUHDy1SKmdb533PjDz7J6N6mV6uS3ze1ai8FHa8kmHScGpWmj4WggLyQjgPie1rFSruoUihUZREPSL39UNdE3BBDu76");
+        assert_eq!(child.public_key().to_xpub(), "xpub6H1LXWLaKsWFhvm6RVpEL9P4KfRZSW7abD2ttkWP3SSQvnyA8FSVqNTEcYFgJS2UaFcxupHiYkro49S8yGasTvXEYBVPamhGW6cFJodrTHy");
+
+        // 非硬化派生时，公钥派生与私钥派生结果一致
+        let account = master.derive_path(&"m/0'/1/2'".parse().unwrap()).unwrap();
+        let tail: DerivationPath = "m/2/1000000000".parse().unwrap();
+        assert_eq!(
+            account.public_key().derive_path(&tail).unwrap(),
+            child.public_key()
+        );
+        assert!(account.public_key().derive_child(HARDENED).is_err());
+
+        assert_eq!(
+            ExtendedPrivateKey::from_xprv(&child.to_xprv()).unwrap(),
+            child
+        );
+        let xpub = child.public_key().to_xpub();
+        assert_eq!(
+            ExtendedPublicKey::from_xpub(&xpub).unwrap(),
+            child.public_key()
+        );
+        assert!(ExtendedPublicKey::from_xpub(&xpub.replace('y', "z")).is_err());
+        assert!(ExtendedPrivateKey::from_xprv(&xpub).is_err());
+        assert!("44'/0'".parse::<DerivationPath>().is_err());
+        assert!("m/2147483648".parse::<DerivationPath>().is_err());
+    }
+
+    #[test]
+    fn test_slip10_ed25519() {
+        // SLIP-0010 test vector 1 for ed25519
+        let seed = Vec::from_hex("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = Ed25519ExtendedKey::new_master(&seed).unwrap();
+        assert_eq!(
+            master.chain_code().to_vec(),
+            Vec::from_hex("90046a93de5380a72b5e45010748567d5ea02bbf6522f979e05c0d8d8ca9fffb")
+                .unwrap()
+        );
+        assert_eq!(
+            master.secret_key().to_vec(),
+            Vec::from_hex("2b4be7f19ee27bbf30c667b642d5f4aa69fd169872f8fc3059c08ebae2eb19e7")
+                .unwrap()
+        );
+        assert_eq!(
+            master.public_key().to_vec(),
+            Vec::from_hex("a4b2856bfec510abab89753fac1ac0e1112364e7d250545963f135f2a33188ed")
+                .unwrap()
+        );
+        let child = master.derive_path(&"m/0'".parse().unwrap()).unwrap();
+        assert_eq!(child.parent_fingerprint(), master.fingerprint());
+        assert_eq!(
+            child.secret_key().to_vec(),
+            Vec::from_hex("68e0fe46dfb67e368c75379acec591dad19df3cde26e63b93a8e704f1dade7a3")
+                .unwrap()
+        );
+        assert_eq!(
+            child.public_key().to_vec(),
+            Vec::from_hex("8c8a13df77a28f3445213a0f432fde644acaa215fc72dcdf300d5efaa85d350c")
+                .unwrap()
+        );
+        assert!(master.derive_child(0).is_err());
+    }
+}
@@ -1,24 +1,48 @@
 //! hamc 密钥hash消息
+//!
+//! HMAC-MD5 和 HMAC-SHA1 只为与旧协议互通而保留，由默认开启的 legacy-hashes 特性提供
 
+use std::io;
+
+use crypto::mac::Mac;
 use ring::hmac;
 
+use crate::error::VerifyFailure;
+
+/// 常量时间比较，也可用于其他定长秘密数据，如令牌和密钥指纹
+pub use crate::ct::ct_eq;
+
 /// 密钥hash消息认证码对象
 pub struct Hmac;
 
 /// hamc支持的SHA哈希算法类型
 pub enum DigestAlgorithm {
+    #[cfg(feature = "legacy-hashes")]
+    #[deprecated(note = "HMAC-MD5 is only for legacy protocols")]
+    MD5,
+    #[cfg(feature = "legacy-hashes")]
+    #[deprecated(note = "HMAC-SHA1 is only for legacy protocols")]
     SHA1,
     SHA256,
     SHA384,
     SHA512,
+    SM3,
 }
 
 impl Hmac {
     /// 使用指定的SHA哈希算法和密钥，对数据进行签名
     ///
     /// alg: SHA哈希算法类型, key: 密钥, data: 待签名的数据, 返回签名后的数据
+    #[allow(deprecated)]
     pub fn sign(alg: DigestAlgorithm, key: &[u8], data: &[u8]) -> Vec<u8> {
         match alg {
+            #[cfg(feature = "legacy-hashes")]
+            DigestAlgorithm::MD5 => {
+                let mut mac = crypto::hmac::Hmac::new(crypto::md5::Md5::new(), key);
+                mac.input(data);
+                mac.result().code().to_vec()
+            }
+            #[cfg(feature = "legacy-hashes")]
             DigestAlgorithm::SHA1 => hmac::sign(
                 &hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key),
                 data,
@@ -34,14 +58,47 @@ impl Hmac {
             DigestAlgorithm::SHA512 => hmac::sign(&hmac::Key::new(hmac::HMAC_SHA512, key), data)
                 .as_ref()
                 .to_vec(),
+            DigestAlgorithm::SM3 => {
+                let mut mac = crypto::hmac::Hmac::new(crate::digest::sm3::Sm3::new(), key);
+                mac.input(data);
+                mac.result().code().to_vec()
+            }
+        }
+    }
+
+    /// 验证签名，失败时返回原因
+    ///
+    /// alg: SHA哈希算法类型, data: 已签名的数据, signature: 签名
+    pub fn verify_detailed(
+        alg: DigestAlgorithm,
+        key: &[u8],
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), VerifyFailure> {
+        let expected = Hmac::sign(alg, key, data);
+        if expected.len() != signature.len() {
+            return Err(VerifyFailure::WrongLength {
+                expected: expected.len(),
+                actual: signature.len(),
+            });
         }
+        if !crate::ct::ct_eq(&expected, signature) {
+            return Err(VerifyFailure::DigestMismatch);
+        }
+        Ok(())
     }
 
     /// 验证通过指定SHA哈希算法和密钥进行加密的签名
     ///
     /// alg: SHA哈希算法类型, data: 已签名的数据, signature: 签名, 返回验证签名是否成功
+    #[allow(deprecated)]
     pub fn verify(alg: DigestAlgorithm, key: &[u8], data: &[u8], signature: &[u8]) -> bool {
         match alg {
+            #[cfg(feature = "legacy-hashes")]
+            DigestAlgorithm::MD5 => {
+                crate::ct::ct_eq(&Hmac::sign(DigestAlgorithm::MD5, key, data), signature)
+            }
+            #[cfg(feature = "legacy-hashes")]
             DigestAlgorithm::SHA1 => hmac::verify(
                 &hmac::Key::new(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY, key),
                 data,
@@ -57,10 +114,93 @@ impl Hmac {
             DigestAlgorithm::SHA512 => {
                 hmac::verify(&hmac::Key::new(hmac::HMAC_SHA512, key), data, signature).is_ok()
             }
+            DigestAlgorithm::SM3 => {
+                crate::ct::ct_eq(&Hmac::sign(DigestAlgorithm::SM3, key, data), signature)
+            }
         }
     }
 }
 
+/// 验证HMAC认证码，内部使用常量时间比较，不要用 == 比较认证码
+///
+/// alg: SHA哈希算法类型, key: 密钥, msg: 已认证的数据, expected_tag: 收到的认证码
+pub fn verify(alg: DigestAlgorithm, key: &[u8], msg: &[u8], expected_tag: &[u8]) -> bool {
+    ct_eq(&Hmac::sign(alg, key, msg), expected_tag)
+}
+
+/// 流式HMAC，用于无法一次读入内存的长数据
+pub struct HmacCtx {
+    state: HmacState,
+}
+
+enum HmacState {
+    Ring(hmac::Context),
+    #[cfg(feature = "legacy-hashes")]
+    Md5(crypto::hmac::Hmac<crypto::md5::Md5>),
+    Sm3(crypto::hmac::Hmac<crate::digest::sm3::Sm3>),
+}
+
+impl HmacCtx {
+    /// 使用指定的SHA哈希算法和密钥构建
+    ///
+    /// alg: SHA哈希算法类型, key: 密钥
+    #[allow(deprecated)]
+    pub fn new(alg: DigestAlgorithm, key: &[u8]) -> Self {
+        let ring = |alg| HmacState::Ring(hmac::Context::with_key(&hmac::Key::new(alg, key)));
+        let state = match alg {
+            #[cfg(feature = "legacy-hashes")]
+            DigestAlgorithm::MD5 => {
+                HmacState::Md5(crypto::hmac::Hmac::new(crypto::md5::Md5::new(), key))
+            }
+            #[cfg(feature = "legacy-hashes")]
+            DigestAlgorithm::SHA1 => ring(hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY),
+            DigestAlgorithm::SHA256 => ring(hmac::HMAC_SHA256),
+            DigestAlgorithm::SHA384 => ring(hmac::HMAC_SHA384),
+            DigestAlgorithm::SHA512 => ring(hmac::HMAC_SHA512),
+            DigestAlgorithm::SM3 => {
+                HmacState::Sm3(crypto::hmac::Hmac::new(crate::digest::sm3::Sm3::new(), key))
+            }
+        };
+        HmacCtx { state }
+    }
+
+    /// 追加数据
+    pub fn update(&mut self, data: &[u8]) {
+        match &mut self.state {
+            HmacState::Ring(ctx) => ctx.update(data),
+            #[cfg(feature = "legacy-hashes")]
+            HmacState::Md5(mac) => mac.input(data),
+            HmacState::Sm3(mac) => mac.input(data),
+        }
+    }
+
+    /// 返回已追加数据的认证码
+    pub fn finalize(self) -> Vec<u8> {
+        match self.state {
+            HmacState::Ring(ctx) => ctx.sign().as_ref().to_vec(),
+            #[cfg(feature = "legacy-hashes")]
+            HmacState::Md5(mut mac) => mac.result().code().to_vec(),
+            HmacState::Sm3(mut mac) => mac.result().code().to_vec(),
+        }
+    }
+
+    /// 常量时间比较已追加数据的认证码和 tag
+    pub fn verify(self, tag: &[u8]) -> bool {
+        crate::ct::ct_eq(&self.finalize(), tag)
+    }
+}
+
+impl io::Write for HmacCtx {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -93,5 +233,99 @@ mod tests {
             data.as_ref(),
             sig.as_ref()
         ));
+        assert_eq!(
+            Hmac::verify_detailed(DigestAlgorithm::SHA256, key.as_ref(), b"", sig.as_ref()),
+            Err(VerifyFailure::DigestMismatch)
+        );
+        assert_eq!(
+            Hmac::verify_detailed(DigestAlgorithm::SHA256, key.as_ref(), b"", &sig[..16]),
+            Err(VerifyFailure::WrongLength {
+                expected: 32,
+                actual: 16
+            })
+        );
+    }
+
+    #[test]
+    fn test_hmacCtx() {
+        // test vectors from: https://tools.ietf.org/html/rfc4231 test case 2
+        let key = b"Jefe";
+        let data: &[u8] = b"what do ya want for nothing?";
+        type Case = (fn() -> DigestAlgorithm, &'static str);
+        let cases: [Case; 4] = [
+            (|| DigestAlgorithm::SHA256, "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"),
+            (|| DigestAlgorithm::SHA384, "af45d2e376484031617f78d2b58a6b1b9c7ef464f5a01b47e42ec3736322445e8e2240ca5e69e2c78b3239ecfab21649"),
+            (|| DigestAlgorithm::SHA512, "164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737"),
+            (|| DigestAlgorithm::SM3, "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882"),
+        ];
+        for (alg, expected) in cases {
+            let expected = Vec::from_hex(expected).unwrap();
+            let mut ctx = HmacCtx::new(alg(), key);
+            io::copy(&mut &data[..10], &mut ctx).unwrap();
+            ctx.update(&data[10..]);
+            assert_eq!(ctx.finalize(), expected);
+            assert_eq!(Hmac::sign(alg(), key, data), expected);
+
+            let mut ctx = HmacCtx::new(alg(), key);
+            ctx.update(data);
+            assert!(ctx.verify(&expected));
+            assert!(!HmacCtx::new(alg(), key).verify(&expected));
+            assert!(verify(alg(), key, data, &expected));
+            assert!(!verify(alg(), key, data, &expected[1..]));
+        }
+    }
+
+    #[test]
+    fn test_hmacSm3() {
+        let key = "Jefe";
+        let data = "what do ya want for nothing?";
+        let expected =
+            Vec::from_hex("2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882")
+                .unwrap();
+
+        assert_eq!(
+            Hmac::sign(DigestAlgorithm::SM3, key.as_ref(), data.as_ref()),
+            expected
+        );
+        assert!(Hmac::verify(
+            DigestAlgorithm::SM3,
+            key.as_ref(),
+            data.as_ref(),
+            expected.as_ref()
+        ));
+        assert!(!Hmac::verify(
+            DigestAlgorithm::SM3,
+            key.as_ref(),
+            b"what do ya want for something?",
+            expected.as_ref()
+        ));
+    }
+
+    // test vectors from: https://tools.ietf.org/html/rfc2202
+    #[test]
+    #[cfg(feature = "legacy-hashes")]
+    #[allow(deprecated)]
+    fn test_hmacLegacy() {
+        let key = "Jefe";
+        let data = "what do ya want for nothing?";
+        let md5 = Vec::from_hex("750c783e6ab0b503eaa86e310a5db738").unwrap();
+        let sha1 = Vec::from_hex("effcdf6ae5eb2fa2d27416d5f184df9c259a7c79").unwrap();
+
+        assert_eq!(
+            Hmac::sign(DigestAlgorithm::MD5, key.as_ref(), data.as_ref()),
+            md5
+        );
+        assert!(Hmac::verify(
+            DigestAlgorithm::MD5,
+            key.as_ref(),
+            data.as_ref(),
+            md5.as_ref()
+        ));
+        assert!(Hmac::verify(
+            DigestAlgorithm::SHA1,
+            key.as_ref(),
+            data.as_ref(),
+            sha1.as_ref()
+        ));
     }
 }
@@ -0,0 +1,271 @@
+//! JSON 规范化 (JCS, RFC 8785) 和规范化 JSON 的签名
+//!
+//! 对象的键按 UTF-16 码元排序，去掉所有空白，字符串和数字按 ECMAScript 的 JSON.stringify 输出，
+//! 生产者可以随意排版和排列字段，签名只覆盖规范化后的字节。
+//! 数字按 IEEE 754 双精度处理(依赖 serde_json 的 float_roundtrip 特性正确舍入)，超过 2^53 的整数会丢失精度；出现重复的键时拒绝，
+//! 避免签名方和验证方的解析器取到不同的值
+
+use std::fmt;
+
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::{Map, Number, Value};
+
+use crate::signature::{scheme_verify, SignatureScheme};
+
+/// 规范化 JSON 文本
+///
+/// json: 任意格式的 JSON 文本
+pub fn canonicalize(json: &str) -> Result<Vec<u8>, String> {
+    let Strict(value) = serde_json::from_str(json).map_err(|e| format!("jcs: bad json: {}", e))?;
+    let mut out = String::new();
+    write_value(&value, &mut out);
+    Ok(out.into_bytes())
+}
+
+/// 对规范化后的文档签名
+///
+/// json: JSON 文本
+/// sign: 签名函数，参数为规范化后的字节
+pub fn sign_with<F>(json: &str, sign: F) -> Result<Vec<u8>, String>
+where
+    F: FnOnce(&[u8]) -> Vec<u8>,
+{
+    Ok(sign(&canonicalize(json)?))
+}
+
+/// 验证规范化后文档的签名
+///
+/// scheme: 签名方案
+/// pk: 公钥
+/// json: JSON 文本，排版和字段顺序可以与签名时不同
+/// sig: 签名
+pub fn verify(scheme: SignatureScheme, pk: &[u8], json: &str, sig: &[u8]) -> bool {
+    match canonicalize(json) {
+        Ok(data) => scheme_verify(scheme, pk, &data, sig),
+        Err(_) => false,
+    }
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => out.push_str(&format_number(n.as_f64().unwrap_or(0.0))),
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_value(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+/// ECMAScript Number::toString，最短的可往返十进制表示
+fn format_number(x: f64) -> String {
+    if x == 0.0 {
+        return "0".to_string();
+    }
+    let sign = if x < 0.0 { "-" } else { "" };
+    // {:e} 输出最短的可往返数字，形如 d.ddde-N
+    let sci = format!("{:e}", x.abs());
+    let (mantissa, exp) = sci.split_at(sci.find('e').unwrap_or(sci.len()));
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let k = digits.len() as i32;
+    let n = exp[1..].parse::<i32>().unwrap_or(0) + 1;
+
+    let body = if k <= n && n <= 21 {
+        format!("{}{}", digits, "0".repeat((n - k) as usize))
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat((-n) as usize), digits)
+    } else {
+        let e = n - 1;
+        let e = if e < 0 {
+            format!("-{}", -e)
+        } else {
+            format!("+{}", e)
+        };
+        if k == 1 {
+            format!("{}e{}", digits, e)
+        } else {
+            format!("{}.{}e{}", &digits[..1], &digits[1..], e)
+        }
+    };
+    format!("{}{}", sign, body)
+}
+
+/// 解析时拒绝重复键的 JSON 值
+struct Strict(Value);
+
+impl<'de> Deserialize<'de> for Strict {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(StrictVisitor).map(Strict)
+    }
+}
+
+struct StrictVisitor;
+
+impl<'de> Visitor<'de> for StrictVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a JSON value")
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_bool<E>(self, v: bool) -> Result<Value, E> {
+        Ok(Value::Bool(v))
+    }
+
+    fn visit_i64<E>(self, v: i64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_u64<E>(self, v: u64) -> Result<Value, E> {
+        Ok(Value::Number(v.into()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+        Number::from_f64(v)
+            .map(Value::Number)
+            .ok_or_else(|| E::custom("number out of range"))
+    }
+
+    fn visit_str<E>(self, v: &str) -> Result<Value, E> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn visit_string<E>(self, v: String) -> Result<Value, E> {
+        Ok(Value::String(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(Strict(item)) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<Value, A::Error> {
+        let mut map = Map::new();
+        while let Some(key) = access.next_key::<String>()? {
+            let Strict(value) = access.next_value()?;
+            if map.insert(key.clone(), value).is_some() {
+                return Err(de::Error::custom(format!("duplicate key {:?}", key)));
+            }
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ed25519;
+
+    #[test]
+    fn test_canonicalize() {
+        // test vectors from: https://tools.ietf.org/html/rfc8785#section-3.2.2
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "\u20ac$\u000F\u000aA'\u0042\u0022\u005c\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        let expected = "{\"literals\":[null,true,false],\"numbers\":[333333333.3333333,1e+30,4.5,0.002,1e-27],\"string\":\"€$\\u000f\\nA'B\\\"\\\\\\\\\\\"/\"}";
+        assert_eq!(
+            String::from_utf8(canonicalize(input).unwrap()).unwrap(),
+            expected
+        );
+
+        // https://tools.ietf.org/html/rfc8785#section-3.2.3 按 UTF-16 码元排序
+        let input =
+            r#"{"\u20ac":1,"\r":2,"\ufb33":3,"1":4,"\ud83d\ude00":5,"\u0080":6,"\u00f6":7}"#;
+        let expected = "{\"\\r\":2,\"1\":4,\"\u{80}\":6,\"ö\":7,\"€\":1,\"😀\":5,\"\u{fb33}\":3}";
+        assert_eq!(
+            String::from_utf8(canonicalize(input).unwrap()).unwrap(),
+            expected
+        );
+
+        // https://tools.ietf.org/html/rfc8785#appendix-B
+        for (x, s) in [
+            (1e21, "1e+21"),
+            (1e20, "100000000000000000000"),
+            (1e-7, "1e-7"),
+            (0.000001, "0.000001"),
+            (-0.0, "0"),
+            (9007199254740992.0, "9007199254740992"),
+            (5e-324, "5e-324"),
+            (-1.7976931348623157e308, "-1.7976931348623157e+308"),
+            (295147905179352830000.0, "295147905179352830000"),
+        ]
+        .iter()
+        {
+            assert_eq!(format_number(*x), *s);
+        }
+
+        assert!(canonicalize(r#"{"a":1,"a":2}"#).is_err());
+    }
+
+    #[test]
+    fn test_jcs_signature() {
+        let (sk, pk) = ed25519::keypair(&[9u8; 32]);
+        let sig = sign_with(r#"{"b": [1, 2.0], "a": "x"}"#, |data| {
+            ed25519::signature(data, &sk).to_vec()
+        })
+        .unwrap();
+        assert!(verify(
+            SignatureScheme::Ed25519,
+            &pk,
+            "{\n  \"a\": \"x\",\n  \"b\": [1, 2]\n}",
+            &sig
+        ));
+        assert!(!verify(
+            SignatureScheme::Ed25519,
+            &pk,
+            r#"{"a": "y", "b": [1, 2]}"#,
+            &sig
+        ));
+    }
+}
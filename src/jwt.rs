@@ -3,6 +3,8 @@
 use jsonwebtoken::crypto::{sign, verify};
 use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey};
 
+use crate::ct::ConstantTimeEq;
+
 /// jwt 支持的签名算法种类
 #[derive(Debug)]
 pub enum JwtAlg {
@@ -27,6 +29,27 @@ pub struct SignKey {
     string: Option<String>,
 }
 
+impl PartialEq for SignKey {
+    /// 常量时间比较密钥内容
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+impl Eq for SignKey {}
+
+impl ConstantTimeEq for SignKey {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.jwt_key_type == other.jwt_key_type
+            && self.bin.ct_eq(&other.bin)
+            && self
+                .string
+                .as_ref()
+                .map(|s| s.as_bytes())
+                .ct_eq(&other.string.as_ref().map(|s| s.as_bytes()))
+    }
+}
+
 impl SignKey {
     /// 从一个私密二进制数据构造签名的密钥
     pub fn from_secret(secret: &[u8]) -> Self {
@@ -91,6 +114,27 @@ pub struct VerifyKey {
     string: Option<String>,
 }
 
+impl PartialEq for VerifyKey {
+    /// 常量时间比较密钥内容
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+impl Eq for VerifyKey {}
+
+impl ConstantTimeEq for VerifyKey {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.jwt_key_type == other.jwt_key_type
+            && self.bin.ct_eq(&other.bin)
+            && self
+                .string
+                .as_ref()
+                .map(|s| s.as_bytes())
+                .ct_eq(&other.string.as_ref().map(|s| s.as_bytes()))
+    }
+}
+
 impl VerifyKey {
     /// 从一个私密二进制数据构造验证密钥
     pub fn from_secret(secret: &[u8]) -> Self {
@@ -263,7 +307,6 @@ fn verify_internal(sig: &str, msg: &str, vk: &VerifyKey, alg: Algorithm) -> Resu
 /// msg: base64 url safe 编码的待签名字符串, 形式为 "header.payload"
 /// vk: 验证签名的公钥
 /// alg: 指定使用的签名算法，当前可以使用 Hmac, Rsa 和 Ec 算法
-
 pub fn jwt_verify(sig: &str, msg: &str, vk: &VerifyKey, alg: JwtAlg) -> bool {
     let res = match alg {
         JwtAlg::HS256 => verify_internal(sig, msg, vk, Algorithm::HS256),
@@ -288,7 +331,7 @@ pub fn jwt_verify(sig: &str, msg: &str, vk: &VerifyKey, alg: JwtAlg) -> bool {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum JwtKeyType {
     RSA_PEM,
     RSA_DER,
@@ -303,10 +346,26 @@ enum JwtKeyType {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_key_eq() {
+        assert_eq!(
+            SignKey::from_secret(b"secret"),
+            SignKey::from_secret(b"secret")
+        );
+        assert_ne!(
+            SignKey::from_secret(b"secret"),
+            SignKey::from_secret(b"secreT")
+        );
+        assert_ne!(
+            VerifyKey::from_secret(b"c2VjcmV0"),
+            VerifyKey::from_base64_secret("c2VjcmV0")
+        );
+    }
+
     #[test]
     fn test_hmac_key() {
         let secret = "c2VjcmV0";
-        let sign_key = SignKey::from_base64_secret(&secret);
+        let sign_key = SignKey::from_base64_secret(secret);
         let header = r#"{"alg":"HS256","typ":"JWT"}"#;
         let payload = r#"{"sub":"1234567890","name":"John Doe","iat":1516239022}"#;
 
@@ -328,12 +387,12 @@ mod tests {
             [header_encoded, payload_encoded, sig.clone()].join(".")
         );
 
-        let verify_key = VerifyKey::from_base64_secret(&secret);
+        let verify_key = VerifyKey::from_base64_secret(secret);
 
         let verify_result = jwt_verify(&sig, &to_be_signed, &verify_key, JwtAlg::HS256);
 
         println!("verify result = {:?}", verify_result);
-        assert_eq!(verify_result, true);
+        assert!(verify_result);
     }
 
     #[test]
@@ -376,7 +435,7 @@ mod tests {
         let verify_result = jwt_verify(&sig, &to_be_signed, &verify_key, JwtAlg::RS256);
 
         println!("verify result = {:?}", verify_result);
-        assert_eq!(verify_result, true);
+        assert!(verify_result);
     }
 
     #[test]
@@ -419,6 +478,6 @@ mod tests {
         let verify_result = jwt_verify(&sig, &to_be_signed, &verify_key, JwtAlg::ES256);
 
         println!("verify result = {:?}", verify_result);
-        assert_eq!(verify_result, true);
+        assert!(verify_result);
     }
 }
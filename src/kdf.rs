@@ -0,0 +1,755 @@
+//! 密钥派生函数
+//!
+//! 统一 HKDF, KBKDF, X9.63, ConcatKDF, PBKDF2, scrypt 和 Argon2 的调用方式，上层模块(密钥库加密、信封加密、口令哈希迁移等)
+//! 可以通过配置选择KDF，而不必写死某一种。从口令派生密钥时使用 derive 和 PasswordParams，
+//! 需要分开 HKDF 的提取和扩展步骤时使用 hkdf_extract 和 hkdf_expand
+
+use std::marker::PhantomData;
+use std::num::NonZeroU32;
+
+use crypto::scrypt::{scrypt, ScryptParams};
+use ring::{digest, hkdf, hmac, pbkdf2};
+
+use crate::mac::{mac, Cmac, HmacSha256, HmacSha384, HmacSha512, HmacSm3, Mac};
+use crate::metrics::{Operation, Timer};
+
+/// 密钥派生函数
+pub trait Kdf {
+    /// 从秘密数据派生指定长度的密钥
+    ///
+    /// secret: 输入的秘密数据，如共享密钥或口令
+    /// salt: 盐
+    /// info: 上下文信息，不支持上下文的KDF要求为空
+    /// out_len: 输出的密钥长度
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String>;
+}
+
+/// KDF使用的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KdfHash {
+    SHA256,
+    SHA384,
+    SHA512,
+}
+
+/// HKDF (RFC 5869)，输出长度最多为哈希长度的255倍
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hkdf {
+    pub hash: KdfHash,
+}
+
+impl Hkdf {
+    /// 构建使用SHA256的HKDF
+    pub fn sha256() -> Self {
+        Hkdf {
+            hash: KdfHash::SHA256,
+        }
+    }
+}
+
+struct OutLen(usize);
+
+impl hkdf::KeyType for OutLen {
+    fn len(&self) -> usize {
+        self.0
+    }
+}
+
+impl Kdf for Hkdf {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let (alg, name) = match self.hash {
+            KdfHash::SHA256 => (hkdf::HKDF_SHA256, "HKDF-SHA256"),
+            KdfHash::SHA384 => (hkdf::HKDF_SHA384, "HKDF-SHA384"),
+            KdfHash::SHA512 => (hkdf::HKDF_SHA512, "HKDF-SHA512"),
+        };
+        let timer = Timer::start(Operation::Kdf, name);
+        let mut out = vec![0u8; out_len];
+        let info = [info];
+        hkdf::Salt::new(alg, salt)
+            .extract(secret)
+            .expand(&info, OutLen(out_len))
+            .and_then(|okm| okm.fill(&mut out))
+            .map_err(|_| "kdf: HKDF output too long".to_string())?;
+        timer.done(Ok(out), true)
+    }
+}
+
+fn hmac_alg(hash: KdfHash) -> hmac::Algorithm {
+    match hash {
+        KdfHash::SHA256 => hmac::HMAC_SHA256,
+        KdfHash::SHA384 => hmac::HMAC_SHA384,
+        KdfHash::SHA512 => hmac::HMAC_SHA512,
+    }
+}
+
+/// HKDF-Extract，返回伪随机密钥PRK，长度等于哈希长度
+///
+/// hash: 哈希算法
+/// salt: 盐，为空时使用哈希长度的全0
+/// ikm: 输入的秘密数据，如ECDH的共享密钥
+pub fn hkdf_extract(hash: KdfHash, salt: &[u8], ikm: &[u8]) -> Vec<u8> {
+    let alg = hmac_alg(hash);
+    let zeros = vec![0u8; alg.digest_algorithm().output_len];
+    let salt = if salt.is_empty() { &zeros[..] } else { salt };
+    hmac::sign(&hmac::Key::new(alg, salt), ikm)
+        .as_ref()
+        .to_vec()
+}
+
+/// HKDF-Expand，从PRK派生指定长度的密钥
+///
+/// hash: 哈希算法，必须与 hkdf_extract 相同
+/// prk: 伪随机密钥，至少为哈希长度
+/// info: 上下文信息
+/// out_len: 输出长度，最多为哈希长度的255倍
+pub fn hkdf_expand(
+    hash: KdfHash,
+    prk: &[u8],
+    info: &[u8],
+    out_len: usize,
+) -> Result<Vec<u8>, String> {
+    let alg = hmac_alg(hash);
+    let hash_len = alg.digest_algorithm().output_len;
+    if prk.len() < hash_len {
+        return Err(format!("kdf: HKDF prk must be at least {} bytes", hash_len));
+    }
+    if out_len > 255 * hash_len {
+        return Err("kdf: HKDF output too long".to_string());
+    }
+    let key = hmac::Key::new(alg, prk);
+    let mut out = Vec::with_capacity(out_len + hash_len);
+    let mut t: Vec<u8> = Vec::new();
+    let mut counter = 1u8;
+    while out.len() < out_len {
+        let mut ctx = hmac::Context::with_key(&key);
+        ctx.update(&t);
+        ctx.update(info);
+        ctx.update(&[counter]);
+        t = ctx.sign().as_ref().to_vec();
+        out.extend_from_slice(&t);
+        counter = counter.wrapping_add(1);
+    }
+    out.truncate(out_len);
+    Ok(out)
+}
+
+/// 使用SHA256的一次性HKDF，与 Hkdf::sha256().derive 相同
+///
+/// salt: 盐
+/// ikm: 输入的秘密数据
+/// info: 上下文信息
+/// out_len: 输出长度
+pub fn hkdf(salt: &[u8], ikm: &[u8], info: &[u8], out_len: usize) -> Result<Vec<u8>, String> {
+    hkdf_expand(
+        KdfHash::SHA256,
+        &hkdf_extract(KdfHash::SHA256, salt, ikm),
+        info,
+        out_len,
+    )
+}
+
+/// PBKDF2 (RFC 8018)，使用HMAC作为伪随机函数，不支持上下文信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pbkdf2 {
+    pub hash: KdfHash,
+    pub iterations: u32,
+}
+
+impl Kdf for Pbkdf2 {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let (alg, name) = match self.hash {
+            KdfHash::SHA256 => (pbkdf2::PBKDF2_HMAC_SHA256, "PBKDF2-SHA256"),
+            KdfHash::SHA384 => (pbkdf2::PBKDF2_HMAC_SHA384, "PBKDF2-SHA384"),
+            KdfHash::SHA512 => (pbkdf2::PBKDF2_HMAC_SHA512, "PBKDF2-SHA512"),
+        };
+        let timer = Timer::start(Operation::Kdf, name);
+        no_info("PBKDF2", info)?;
+        let iterations = NonZeroU32::new(self.iterations)
+            .ok_or_else(|| "kdf: PBKDF2 iterations must not be zero".to_string())?;
+        let mut out = vec![0u8; out_len];
+        pbkdf2::derive(alg, iterations, salt, secret, &mut out);
+        timer.done(Ok(out), true)
+    }
+}
+
+/// scrypt (RFC 7914)，log_n 为参数N的以2为底的对数，不支持上下文信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Scrypt {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+/// 解析不可信的 scrypt 参数时允许的最大内存，1GiB 足够 N = 2^20、r = 8
+pub(crate) const MAX_SCRYPT_MEMORY: u64 = 1 << 30;
+
+impl Scrypt {
+    /// 派生所需的内存字节数 128 * r * (N + p)，溢出时为 u64::MAX
+    pub fn memory_cost(&self) -> u64 {
+        let n = 1u128
+            .checked_shl(u32::from(self.log_n))
+            .unwrap_or(u128::MAX);
+        let bytes = (128 * u128::from(self.r)).saturating_mul(n.saturating_add(u128::from(self.p)));
+        bytes.min(u128::from(u64::MAX)) as u64
+    }
+}
+
+impl Kdf for Scrypt {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let timer = Timer::start(Operation::Kdf, "scrypt");
+        no_info("scrypt", info)?;
+        // ScryptParams::new 对非法参数直接panic，这里先检查
+        if self.log_n == 0
+            || u32::from(self.log_n) >= self.r.saturating_mul(16)
+            || self.r == 0
+            || self.p == 0
+            || u64::from(self.r) * u64::from(self.p) >= 1 << 30
+        {
+            return Err("kdf: invalid scrypt parameters".to_string());
+        }
+        let mut out = vec![0u8; out_len];
+        scrypt(
+            secret,
+            salt,
+            &ScryptParams::new(self.log_n, self.r, self.p),
+            &mut out,
+        );
+        timer.done(Ok(out), true)
+    }
+}
+
+/// Argon2 的变体
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Argon2Variant {
+    Argon2d,
+    Argon2i,
+    Argon2id,
+}
+
+/// Argon2 (RFC 9106)，mem_cost 单位为KiB，上下文信息作为关联数据参与计算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Argon2 {
+    pub variant: Argon2Variant,
+    pub mem_cost: u32,
+    pub time_cost: u32,
+    pub lanes: u32,
+}
+
+impl Kdf for Argon2 {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let timer = Timer::start(Operation::Kdf, "argon2");
+        let config = argon2::Config {
+            variant: match self.variant {
+                Argon2Variant::Argon2d => argon2::Variant::Argon2d,
+                Argon2Variant::Argon2i => argon2::Variant::Argon2i,
+                Argon2Variant::Argon2id => argon2::Variant::Argon2id,
+            },
+            version: argon2::Version::Version13,
+            mem_cost: self.mem_cost,
+            time_cost: self.time_cost,
+            lanes: self.lanes,
+            thread_mode: argon2::ThreadMode::Sequential,
+            secret: &[],
+            ad: info,
+            hash_length: out_len as u32,
+        };
+        timer
+            .done_result(argon2::hash_raw(secret, salt, &config).map_err(|e| format!("kdf: {}", e)))
+    }
+}
+
+/// 口令派生密钥的算法和代价参数
+///
+/// 推荐参数取自 OWASP Password Storage Cheat Sheet，可以按硬件调整，调整后必须随密文保存
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasswordParams {
+    Pbkdf2(Pbkdf2),
+    Scrypt(Scrypt),
+    Argon2(Argon2),
+}
+
+impl PasswordParams {
+    /// PBKDF2-HMAC-SHA256，600000次迭代
+    pub fn pbkdf2_sha256() -> Self {
+        PasswordParams::Pbkdf2(Pbkdf2 {
+            hash: KdfHash::SHA256,
+            iterations: 600_000,
+        })
+    }
+
+    /// PBKDF2-HMAC-SHA512，210000次迭代
+    pub fn pbkdf2_sha512() -> Self {
+        PasswordParams::Pbkdf2(Pbkdf2 {
+            hash: KdfHash::SHA512,
+            iterations: 210_000,
+        })
+    }
+
+    /// scrypt，N=2^17, r=8, p=1，约占用128MiB内存
+    pub fn scrypt() -> Self {
+        PasswordParams::Scrypt(Scrypt {
+            log_n: 17,
+            r: 8,
+            p: 1,
+        })
+    }
+
+    /// Argon2id，19MiB内存，2次迭代，单线程
+    pub fn argon2id() -> Self {
+        PasswordParams::Argon2(Argon2 {
+            variant: Argon2Variant::Argon2id,
+            mem_cost: 19 * 1024,
+            time_cost: 2,
+            lanes: 1,
+        })
+    }
+}
+
+/// 从口令派生密钥
+///
+/// password: 口令
+/// salt: 盐，每个口令使用不同的随机值，建议至少16字节
+/// params: 算法和代价参数
+/// out_len: 输出的密钥长度
+pub fn derive(
+    password: &[u8],
+    salt: &[u8],
+    params: &PasswordParams,
+    out_len: usize,
+) -> Result<Vec<u8>, String> {
+    match params {
+        PasswordParams::Pbkdf2(kdf) => kdf.derive(password, salt, &[], out_len),
+        PasswordParams::Scrypt(kdf) => kdf.derive(password, salt, &[], out_len),
+        PasswordParams::Argon2(kdf) => kdf.derive(password, salt, &[], out_len),
+    }
+}
+
+/// 伪随机函数，KBKDF 等基于PRF的KDF使用
+///
+/// 只为可以作为PRF的MAC实现，Poly1305 这类一次性MAC不能使用
+pub trait Prf {
+    /// 输出长度
+    const OUTPUT_SIZE: usize;
+
+    /// 计算 PRF(key, data)
+    fn prf(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String>;
+}
+
+macro_rules! impl_prf {
+    ($($mac:ty),*) => {
+        $(
+            impl Prf for $mac {
+                const OUTPUT_SIZE: usize = <$mac as Mac>::TAG_SIZE;
+
+                fn prf(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+                    mac::<$mac>(key, data)
+                }
+            }
+        )*
+    };
+}
+
+impl_prf!(HmacSha256, HmacSha384, HmacSha512, HmacSm3, Cmac);
+
+/// 计数器模式的 KBKDF (NIST SP 800-108)
+///
+/// K(i) = PRF(密钥, [i] | 固定输入)，计数器从1开始，大端编码，位于固定输入之前。
+/// 通过 Kdf 接口调用时 salt 作为 Label，info 作为 Context，
+/// 固定输入为 Label | 0x00 | Context | [输出比特数]_32
+pub struct Kbkdf<P> {
+    counter_bits: u8,
+    prf: PhantomData<fn() -> P>,
+}
+
+impl<P: Prf> Kbkdf<P> {
+    /// 构建使用32位计数器的KBKDF
+    pub fn new() -> Self {
+        Kbkdf {
+            counter_bits: 32,
+            prf: PhantomData,
+        }
+    }
+
+    /// 构建指定计数器位数的KBKDF
+    ///
+    /// counter_bits: 计数器位数，8、16、24或32
+    pub fn with_counter_bits(counter_bits: u8) -> Result<Self, String> {
+        match counter_bits {
+            8 | 16 | 24 | 32 => Ok(Kbkdf {
+                counter_bits,
+                prf: PhantomData,
+            }),
+            _ => Err("kdf: KBKDF counter must be 8, 16, 24 or 32 bits".to_string()),
+        }
+    }
+
+    /// 使用调用方构造的固定输入派生，用于与自定义标签格式的系统(如HSM导出的标签)互通
+    ///
+    /// key: 密钥
+    /// fixed_input: 固定输入数据
+    /// out_len: 输出的密钥长度
+    pub fn derive_fixed(
+        &self,
+        key: &[u8],
+        fixed_input: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let timer = Timer::start(Operation::Kdf, "KBKDF");
+        let blocks = out_len.div_ceil(P::OUTPUT_SIZE);
+        if blocks as u64 >= 1u64 << self.counter_bits {
+            return Err("kdf: KBKDF output too long".to_string());
+        }
+        let counter_len = usize::from(self.counter_bits / 8);
+        let mut out = Vec::with_capacity(blocks * P::OUTPUT_SIZE);
+        for i in 1..=blocks as u32 {
+            let mut input = i.to_be_bytes()[4 - counter_len..].to_vec();
+            input.extend_from_slice(fixed_input);
+            out.extend_from_slice(&P::prf(key, &input)?);
+        }
+        out.truncate(out_len);
+        timer.done(Ok(out), true)
+    }
+}
+
+impl<P: Prf> Default for Kbkdf<P> {
+    fn default() -> Self {
+        Kbkdf::new()
+    }
+}
+
+impl<P: Prf> Kdf for Kbkdf<P> {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let bits = (out_len as u64)
+            .checked_mul(8)
+            .filter(|bits| *bits <= u64::from(u32::MAX))
+            .ok_or_else(|| "kdf: KBKDF output too long".to_string())?;
+        let mut fixed = salt.to_vec();
+        fixed.push(0);
+        fixed.extend_from_slice(info);
+        fixed.extend_from_slice(&(bits as u32).to_be_bytes());
+        self.derive_fixed(secret, &fixed, out_len)
+    }
+}
+
+/// ANSI X9.63 KDF (SEC 1 3.6.1)，ECIES 常用
+///
+/// K(i) = Hash(共享密钥 | [i]_32 | SharedInfo)，计数器从1开始，info 作为 SharedInfo，不支持盐
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct X963Kdf {
+    pub hash: KdfHash,
+}
+
+impl Kdf for X963Kdf {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let (alg, name) = match self.hash {
+            KdfHash::SHA256 => (&digest::SHA256, "X963-SHA256"),
+            KdfHash::SHA384 => (&digest::SHA384, "X963-SHA384"),
+            KdfHash::SHA512 => (&digest::SHA512, "X963-SHA512"),
+        };
+        let timer = Timer::start(Operation::Kdf, name);
+        if !salt.is_empty() {
+            return Err("kdf: X9.63 KDF does not take a salt".to_string());
+        }
+        let blocks = out_len.div_ceil(alg.output_len);
+        if blocks as u64 > u64::from(u32::MAX) {
+            return Err("kdf: X9.63 KDF output too long".to_string());
+        }
+        let mut out = Vec::with_capacity(blocks * alg.output_len);
+        for i in 1..=blocks as u32 {
+            let mut ctx = digest::Context::new(alg);
+            ctx.update(secret);
+            ctx.update(&i.to_be_bytes());
+            ctx.update(info);
+            out.extend_from_slice(ctx.finish().as_ref());
+        }
+        out.truncate(out_len);
+        timer.done(Ok(out), true)
+    }
+}
+
+/// 单步 ConcatKDF (NIST SP 800-56A 5.8.1)，JWE 的 ECDH-ES 使用
+///
+/// K(i) = Hash([i]_32 | 共享密钥 | OtherInfo)，计数器在共享密钥之前，这是与 X9.63 KDF 唯一的区别。
+/// info 作为 OtherInfo，JOSE 中用 jose_other_info 构造，不支持盐
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConcatKdf {
+    pub hash: KdfHash,
+}
+
+impl Kdf for ConcatKdf {
+    fn derive(
+        &self,
+        secret: &[u8],
+        salt: &[u8],
+        info: &[u8],
+        out_len: usize,
+    ) -> Result<Vec<u8>, String> {
+        let (alg, name) = match self.hash {
+            KdfHash::SHA256 => (&digest::SHA256, "ConcatKDF-SHA256"),
+            KdfHash::SHA384 => (&digest::SHA384, "ConcatKDF-SHA384"),
+            KdfHash::SHA512 => (&digest::SHA512, "ConcatKDF-SHA512"),
+        };
+        let timer = Timer::start(Operation::Kdf, name);
+        if !salt.is_empty() {
+            return Err("kdf: ConcatKDF does not take a salt".to_string());
+        }
+        let blocks = out_len.div_ceil(alg.output_len);
+        if blocks as u64 > u64::from(u32::MAX) {
+            return Err("kdf: ConcatKDF output too long".to_string());
+        }
+        let mut out = Vec::with_capacity(blocks * alg.output_len);
+        for i in 1..=blocks as u32 {
+            let mut ctx = digest::Context::new(alg);
+            ctx.update(&i.to_be_bytes());
+            ctx.update(secret);
+            ctx.update(info);
+            out.extend_from_slice(ctx.finish().as_ref());
+        }
+        out.truncate(out_len);
+        timer.done(Ok(out), true)
+    }
+}
+
+/// 构造 JWE ECDH-ES 的 OtherInfo (RFC 7518 4.6.2)
+///
+/// alg: 直接密钥协商时为 "enc" 的值(如 "A128GCM")，密钥包装时为 "alg" 的值(如 "ECDH-ES+A128KW")
+/// apu: "apu" 头部 base64url 解码后的数据，没有时为空
+/// apv: "apv" 头部 base64url 解码后的数据，没有时为空
+/// key_len: 派生的密钥字节数
+pub fn jose_other_info(alg: &str, apu: &[u8], apv: &[u8], key_len: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    for field in [alg.as_bytes(), apu, apv] {
+        out.extend_from_slice(&(field.len() as u32).to_be_bytes());
+        out.extend_from_slice(field);
+    }
+    out.extend_from_slice(&((key_len * 8) as u32).to_be_bytes());
+    out
+}
+
+fn no_info(name: &str, info: &[u8]) -> Result<(), String> {
+    if !info.is_empty() {
+        return Err(format!("kdf: {} does not take context info", name));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_kdf() {
+        // test vector from: https://tools.ietf.org/html/rfc5869#appendix-A.1
+        let ikm = Vec::from_hex("0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b0b").unwrap();
+        let salt = Vec::from_hex("000102030405060708090a0b0c").unwrap();
+        let info = Vec::from_hex("f0f1f2f3f4f5f6f7f8f9").unwrap();
+        let okm = Vec::from_hex(
+            "3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865",
+        )
+        .unwrap();
+        assert_eq!(Hkdf::sha256().derive(&ikm, &salt, &info, 42).unwrap(), okm);
+        assert!(Hkdf::sha256()
+            .derive(&ikm, &salt, &info, 255 * 32 + 1)
+            .is_err());
+        let prk = hkdf_extract(KdfHash::SHA256, &salt, &ikm);
+        assert_eq!(
+            prk,
+            Vec::from_hex("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5")
+                .unwrap()
+        );
+        assert_eq!(hkdf_expand(KdfHash::SHA256, &prk, &info, 42).unwrap(), okm);
+        assert!(hkdf_expand(KdfHash::SHA256, &prk[1..], &info, 42).is_err());
+        // test vector from: https://tools.ietf.org/html/rfc5869#appendix-A.3
+        assert_eq!(
+            hkdf(&[], &ikm, &[], 42).unwrap(),
+            Vec::from_hex(
+                "8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8"
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            hkdf_expand(
+                KdfHash::SHA512,
+                &hkdf_extract(KdfHash::SHA512, &salt, &ikm),
+                &info,
+                100
+            )
+            .unwrap(),
+            Hkdf {
+                hash: KdfHash::SHA512
+            }
+            .derive(&ikm, &salt, &info, 100)
+            .unwrap()
+        );
+
+        // test vector from: https://tools.ietf.org/html/rfc7914#section-11
+        let pbkdf2 = Pbkdf2 {
+            hash: KdfHash::SHA256,
+            iterations: 1,
+        };
+        let expected = Vec::from_hex("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783").unwrap();
+        assert_eq!(
+            pbkdf2.derive(b"passwd", b"salt", &[], 64).unwrap(),
+            expected
+        );
+        assert!(pbkdf2.derive(b"passwd", b"salt", b"info", 64).is_err());
+        assert_eq!(
+            derive(b"passwd", b"salt", &PasswordParams::Pbkdf2(pbkdf2), 64).unwrap(),
+            expected
+        );
+
+        // test vector from: https://tools.ietf.org/html/rfc7914#section-12
+        let scrypt = Scrypt {
+            log_n: 10,
+            r: 8,
+            p: 16,
+        };
+        let expected = Vec::from_hex("fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640").unwrap();
+        assert_eq!(
+            scrypt.derive(b"password", b"NaCl", &[], 64).unwrap(),
+            expected
+        );
+
+        let argon2 = Argon2 {
+            variant: Argon2Variant::Argon2id,
+            mem_cost: 64,
+            time_cost: 1,
+            lanes: 1,
+        };
+        let a = argon2
+            .derive(b"password", b"somesaltsomesalt", b"a", 32)
+            .unwrap();
+        let b = argon2
+            .derive(b"password", b"somesaltsomesalt", b"b", 32)
+            .unwrap();
+        assert_ne!(a, b);
+        assert_eq!(
+            derive(
+                b"password",
+                b"somesaltsomesalt",
+                &PasswordParams::Argon2(argon2),
+                32
+            )
+            .unwrap(),
+            argon2
+                .derive(b"password", b"somesaltsomesalt", &[], 32)
+                .unwrap()
+        );
+        assert!(derive(
+            b"password",
+            b"NaCl",
+            &PasswordParams::Scrypt(Scrypt {
+                log_n: 0,
+                r: 8,
+                p: 1
+            }),
+            32
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_kbkdf() {
+        // 与 pyca/cryptography 的 KBKDFHMAC/KBKDFCMAC 交叉验证
+        let key: Vec<u8> = (0u8..32).collect();
+        let expected = Vec::from_hex(
+            "b9cd5f6323f01f4680650855f1ebea9b4c54c08131b506fc28c856364a38a2f4fb680c12ea51696887d9",
+        )
+        .unwrap();
+        let kbkdf = Kbkdf::<HmacSha256>::new();
+        assert_eq!(
+            kbkdf.derive(&key, b"label", b"context", 42).unwrap(),
+            expected
+        );
+
+        let kbkdf = Kbkdf::<Cmac>::with_counter_bits(8).unwrap();
+        assert_eq!(
+            kbkdf.derive(&key[..16], b"label", b"context", 20).unwrap(),
+            Vec::from_hex("53605439d52d501620e5652c5a1295e68ab97b0e").unwrap()
+        );
+        assert!(kbkdf.derive(&key[..16], b"", b"", 256 * 16).is_err());
+        assert!(Kbkdf::<Cmac>::with_counter_bits(12).is_err());
+    }
+
+    #[test]
+    fn test_x963_kdf() {
+        // test vectors from: NIST CAVP ansx963_2001.rsp, SHA-256
+        let kdf = X963Kdf {
+            hash: KdfHash::SHA256,
+        };
+        let z = Vec::from_hex("96c05619d56c328ab95fe84b18264b08725b85e33fd34f08").unwrap();
+        assert_eq!(
+            kdf.derive(&z, &[], &[], 16).unwrap(),
+            Vec::from_hex("443024c3dae66b95e6f5670601558f71").unwrap()
+        );
+        let z = Vec::from_hex("22518b10e70f2a3f243810ae3254139efbee04aa57c7af7d").unwrap();
+        let shared_info = Vec::from_hex("75eef81aa3041e33b80971203d2c0c52").unwrap();
+        assert_eq!(
+            kdf.derive(&z, &[], &shared_info, 32).unwrap(),
+            Vec::from_hex("c498af77161cc59f2962b9a713e2b215152d139766ce34a776df11866a69bf2e")
+                .unwrap()
+        );
+        assert!(kdf.derive(&z, b"salt", &[], 32).is_err());
+    }
+
+    #[test]
+    fn test_concat_kdf() {
+        // test vector from: https://tools.ietf.org/html/rfc7518#appendix-C
+        let z = Vec::from_hex("9e56d91d817135d372834283bf84269cfb316ea3da806a48f6daa7798cfe90c4")
+            .unwrap();
+        let other_info = jose_other_info("A128GCM", b"Alice", b"Bob", 16);
+        let kdf = ConcatKdf {
+            hash: KdfHash::SHA256,
+        };
+        assert_eq!(
+            base64::encode_config(
+                kdf.derive(&z, &[], &other_info, 16).unwrap(),
+                base64::URL_SAFE_NO_PAD
+            ),
+            "VqqN6vgjbSBcIijNcacQGg"
+        );
+    }
+}
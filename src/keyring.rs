@@ -0,0 +1,184 @@
+//! 带版本的对称密钥环
+//!
+//! 加密总是使用主密钥，密文以密钥版本号开头，解密时按版本号选择密钥，
+//! 轮换密钥时先加入新密钥并设为主密钥，旧数据仍可解密，重新加密完成后再移除旧密钥。
+//!
+//! 密文格式: 密钥版本(4字节大端) | aead::AeadRegistry 输出的带算法头部的密文，版本号参与认证
+
+use crate::aead::AeadRegistry;
+use crate::secret::SecretBytes;
+
+/// 密钥环
+pub struct KeyRing {
+    keys: Vec<VersionedKey>,
+    primary: Option<u32>,
+    registry: AeadRegistry,
+}
+
+struct VersionedKey {
+    version: u32,
+    alg: u16,
+    key: SecretBytes,
+}
+
+impl KeyRing {
+    /// 构建空的密钥环
+    pub fn new() -> Self {
+        KeyRing {
+            keys: Vec::new(),
+            primary: None,
+            registry: AeadRegistry::new(),
+        }
+    }
+
+    /// 加入指定版本的密钥，第一个加入的密钥成为主密钥
+    ///
+    /// version: 密钥版本
+    /// alg: aead 模块中的算法ID
+    /// key: 密钥
+    pub fn add_key(&mut self, version: u32, alg: u16, key: &[u8]) -> Result<(), String> {
+        let aead = self
+            .registry
+            .get(alg)
+            .ok_or_else(|| format!("keyring: unknown algorithm id {}", alg))?;
+        if key.len() != aead.key_size() {
+            return Err(format!(
+                "keyring: {} key must be {} bytes",
+                aead.name(),
+                aead.key_size()
+            ));
+        }
+        if self.find(version).is_some() {
+            return Err(format!("keyring: key version {} already exists", version));
+        }
+        self.keys.push(VersionedKey {
+            version,
+            alg,
+            key: SecretBytes::from(key),
+        });
+        if self.primary.is_none() {
+            self.primary = Some(version);
+        }
+        Ok(())
+    }
+
+    /// 设置主密钥
+    pub fn set_primary(&mut self, version: u32) -> Result<(), String> {
+        if self.find(version).is_none() {
+            return Err(format!("keyring: unknown key version {}", version));
+        }
+        self.primary = Some(version);
+        Ok(())
+    }
+
+    /// 移除密钥，主密钥不能移除
+    pub fn remove_key(&mut self, version: u32) -> Result<(), String> {
+        if self.primary == Some(version) {
+            return Err("keyring: can not remove the primary key".to_string());
+        }
+        let len = self.keys.len();
+        self.keys.retain(|k| k.version != version);
+        if self.keys.len() == len {
+            return Err(format!("keyring: unknown key version {}", version));
+        }
+        Ok(())
+    }
+
+    /// 主密钥版本
+    pub fn primary_version(&self) -> Option<u32> {
+        self.primary
+    }
+
+    /// 所有密钥版本
+    pub fn versions(&self) -> Vec<u32> {
+        self.keys.iter().map(|k| k.version).collect()
+    }
+
+    /// 使用主密钥加密
+    ///
+    /// plaintext: 明文
+    /// aad: 辅助数据，解密时需要相同
+    pub fn encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let key = self
+            .primary
+            .and_then(|v| self.find(v))
+            .ok_or_else(|| "keyring: no primary key".to_string())?;
+        let mut out = key.version.to_be_bytes().to_vec();
+        let sealed =
+            self.registry
+                .seal(key.alg, &key.key, &version_aad(key.version, aad), plaintext)?;
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    /// 按密文中的版本号选择密钥解密
+    pub fn decrypt(&self, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let version =
+            key_version(ciphertext).ok_or_else(|| "keyring: ciphertext too short".to_string())?;
+        let key = self
+            .find(version)
+            .ok_or_else(|| format!("keyring: unknown key version {}", version))?;
+        self.registry
+            .open(&key.key, &version_aad(version, aad), &ciphertext[4..])
+    }
+
+    fn find(&self, version: u32) -> Option<&VersionedKey> {
+        self.keys.iter().find(|k| k.version == version)
+    }
+}
+
+/// 读取密文使用的密钥版本，可用于找出需要重新加密的数据
+pub fn key_version(ciphertext: &[u8]) -> Option<u32> {
+    if ciphertext.len() < 4 {
+        return None;
+    }
+    Some(u32::from_be_bytes([
+        ciphertext[0],
+        ciphertext[1],
+        ciphertext[2],
+        ciphertext[3],
+    ]))
+}
+
+fn version_aad(version: u32, aad: &[u8]) -> Vec<u8> {
+    let mut out = version.to_be_bytes().to_vec();
+    out.extend_from_slice(aad);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aead::{AES_128_GCM_ID, AES_256_GCM_ID};
+
+    #[test]
+    fn test_key_rotation() {
+        let mut ring = KeyRing::new();
+        assert!(ring.encrypt(b"data", b"").is_err());
+        ring.add_key(1, AES_128_GCM_ID, &[1u8; 16]).unwrap();
+        assert!(ring.add_key(1, AES_256_GCM_ID, &[2u8; 32]).is_err());
+        assert!(ring.add_key(2, AES_256_GCM_ID, &[2u8; 16]).is_err());
+
+        let old = ring.encrypt(b"data", b"row 1").unwrap();
+        assert_eq!(key_version(&old), Some(1));
+
+        // 轮换到新密钥
+        ring.add_key(2, AES_256_GCM_ID, &[2u8; 32]).unwrap();
+        ring.set_primary(2).unwrap();
+        let new = ring.encrypt(b"data", b"row 1").unwrap();
+        assert_eq!(key_version(&new), Some(2));
+        assert_eq!(ring.decrypt(&old, b"row 1").unwrap(), b"data");
+        assert_eq!(ring.decrypt(&new, b"row 1").unwrap(), b"data");
+        assert!(ring.decrypt(&new, b"row 2").is_err());
+
+        assert!(ring.remove_key(2).is_err());
+        ring.remove_key(1).unwrap();
+        assert!(ring.decrypt(&old, b"row 1").is_err());
+
+        // 篡改版本号无法解密
+        let mut tampered = new;
+        tampered[3] = 1;
+        ring.add_key(1, AES_256_GCM_ID, &[2u8; 32]).unwrap();
+        assert!(ring.decrypt(&tampered, b"row 1").is_err());
+    }
+}
@@ -0,0 +1,306 @@
+//! 以太坊密钥库 (Web3 Secret Storage Definition, version 3)
+//!
+//! 口令经 scrypt 或 PBKDF2-HMAC-SHA256 派生32字节密钥，前16字节作为 AES-128-CTR 的密钥加密 secp256k1 私钥，
+//! 后16字节与密文拼接后计算 Keccak-256 作为MAC。与 geth、ethers 等生成的 keystore JSON 兼容
+
+use secp256k1::{PublicKey, SecretKey};
+use serde_json::{json, Value};
+
+use crate::cipher::aes_ctr_encrypt;
+use crate::ct::ct_eq;
+use crate::digest::keccak256;
+use crate::encoding::hex;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::eth;
+use crate::kdf::{Kdf, KdfHash, PasswordParams, Pbkdf2, Scrypt, MAX_SCRYPT_MEMORY};
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+
+// 解密时接受的最大代价参数，防止恶意文件消耗大量CPU和内存
+const MAX_PBKDF2_ITERATIONS: u64 = 10_000_000;
+const MAX_SCRYPT_LOG_N: u8 = 20;
+const DK_LEN: usize = 32;
+
+/// 用口令解密 keystore JSON，返回32字节 secp256k1 私钥，离开作用域时擦除
+///
+/// json: keystore JSON
+/// password: 口令
+pub fn decrypt_keystore(json: &str, password: &[u8]) -> Result<SecretBytes, CryptoError> {
+    let keystore: Value = serde_json::from_str(json)
+        .map_err(|e| CryptoError::InvalidInput(format!("keystore: bad json: {}", e)))?;
+    if keystore["version"].as_u64() != Some(3) {
+        return Err(CryptoError::InvalidInput(
+            "keystore: only version 3 is supported".to_string(),
+        ));
+    }
+    // 旧版本的 geth 使用大写的 Crypto
+    let crypto = match &keystore["crypto"] {
+        Value::Null => &keystore["Crypto"],
+        crypto => crypto,
+    };
+    if crypto["cipher"] != "aes-128-ctr" {
+        return Err(CryptoError::InvalidInput(
+            "keystore: only aes-128-ctr is supported".to_string(),
+        ));
+    }
+    let iv = hex_field(&crypto["cipherparams"]["iv"], "iv")?;
+    let ciphertext = hex_field(&crypto["ciphertext"], "ciphertext")?;
+    let mac = hex_field(&crypto["mac"], "mac")?;
+
+    let params = &crypto["kdfparams"];
+    let salt = hex_field(&params["salt"], "salt")?;
+    if uint_field(&params["dklen"], "dklen")? != DK_LEN as u64 {
+        return Err(CryptoError::InvalidInput(
+            "keystore: dklen must be 32".to_string(),
+        ));
+    }
+    let kdf = match crypto["kdf"].as_str() {
+        Some("scrypt") => {
+            let n = uint_field(&params["n"], "n")?;
+            if !n.is_power_of_two() || !(2..=1 << MAX_SCRYPT_LOG_N).contains(&n) {
+                return Err(CryptoError::InvalidInput(
+                    "keystore: scrypt n out of range".to_string(),
+                ));
+            }
+            let scrypt = Scrypt {
+                log_n: n.trailing_zeros() as u8,
+                r: small_field(&params["r"], "r")?,
+                p: small_field(&params["p"], "p")?,
+            };
+            if scrypt.memory_cost() > MAX_SCRYPT_MEMORY {
+                return Err(CryptoError::InvalidInput(
+                    "keystore: scrypt parameters need too much memory".to_string(),
+                ));
+            }
+            PasswordParams::Scrypt(scrypt)
+        }
+        Some("pbkdf2") => {
+            if params["prf"] != "hmac-sha256" {
+                return Err(CryptoError::InvalidInput(
+                    "keystore: only hmac-sha256 prf is supported".to_string(),
+                ));
+            }
+            let c = uint_field(&params["c"], "c")?;
+            if c == 0 || c > MAX_PBKDF2_ITERATIONS {
+                return Err(CryptoError::InvalidInput(
+                    "keystore: pbkdf2 iteration count out of range".to_string(),
+                ));
+            }
+            PasswordParams::Pbkdf2(Pbkdf2 {
+                hash: KdfHash::SHA256,
+                iterations: c as u32,
+            })
+        }
+        _ => {
+            return Err(CryptoError::InvalidInput(
+                "keystore: unsupported kdf".to_string(),
+            ))
+        }
+    };
+
+    let dk = derive_key(kdf, password, &salt)?;
+    if !ct_eq(&keystore_mac(&dk, &ciphertext), &mac) {
+        return Err(CryptoError::Verify(VerifyFailure::DigestMismatch));
+    }
+    let sk = SecretBytes::new(
+        aes_ctr_encrypt(&dk[..16], &iv, &ciphertext).map_err(CryptoError::InvalidKey)?,
+    );
+    SecretKey::parse_slice(&sk).map_err(|_| {
+        CryptoError::InvalidKey("keystore: invalid secp256k1 private key".to_string())
+    })?;
+    Ok(sk)
+}
+
+/// 用口令加密 secp256k1 私钥，返回 keystore JSON，盐、IV和id随机生成
+///
+/// sk: 32字节私钥
+/// password: 口令
+/// params: 派生参数，只支持 scrypt 和 PBKDF2-HMAC-SHA256，如 PasswordParams::scrypt()
+pub fn encrypt_keystore(
+    sk: &[u8],
+    password: &[u8],
+    params: PasswordParams,
+) -> Result<String, CryptoError> {
+    let key = SecretKey::parse_slice(sk).map_err(|_| {
+        CryptoError::InvalidKey("keystore: invalid secp256k1 private key".to_string())
+    })?;
+    let salt = genSecureRandBytes(32);
+    let kdfparams = match params {
+        PasswordParams::Scrypt(s) => json!({
+            "dklen": DK_LEN,
+            "n": 1u64 << s.log_n,
+            "r": s.r,
+            "p": s.p,
+            "salt": hex::encode(&salt),
+        }),
+        PasswordParams::Pbkdf2(Pbkdf2 {
+            hash: KdfHash::SHA256,
+            iterations,
+        }) => json!({
+            "c": iterations,
+            "dklen": DK_LEN,
+            "prf": "hmac-sha256",
+            "salt": hex::encode(&salt),
+        }),
+        _ => {
+            return Err(CryptoError::InvalidInput(
+                "keystore: only scrypt and PBKDF2-HMAC-SHA256 are supported".to_string(),
+            ))
+        }
+    };
+    let kdf = match params {
+        PasswordParams::Scrypt(_) => "scrypt",
+        _ => "pbkdf2",
+    };
+    let dk = derive_key(params, password, &salt)?;
+    let iv = genSecureRandBytes(16);
+    let ciphertext = aes_ctr_encrypt(&dk[..16], &iv, sk).map_err(CryptoError::InvalidKey)?;
+    let keystore = json!({
+        "address": hex::encode(&address(&key)),
+        "crypto": {
+            "cipher": "aes-128-ctr",
+            "cipherparams": { "iv": hex::encode(&iv) },
+            "ciphertext": hex::encode(&ciphertext),
+            "kdf": kdf,
+            "kdfparams": kdfparams,
+            "mac": hex::encode(&keystore_mac(&dk, &ciphertext)),
+        },
+        "id": uuid_v4(),
+        "version": 3,
+    });
+    Ok(keystore.to_string())
+}
+
+/// 私钥对应的20字节以太坊地址
+fn address(key: &SecretKey) -> [u8; 20] {
+    eth::address(&PublicKey::from_secret_key(key))
+}
+
+fn derive_key(
+    params: PasswordParams,
+    password: &[u8],
+    salt: &[u8],
+) -> Result<SecretBytes, CryptoError> {
+    let dk = match params {
+        PasswordParams::Scrypt(s) => s
+            .derive(password, salt, &[], DK_LEN)
+            .map_err(CryptoError::InvalidInput),
+        PasswordParams::Pbkdf2(p) => p
+            .derive(password, salt, &[], DK_LEN)
+            .map_err(CryptoError::InvalidInput),
+        PasswordParams::Argon2(_) => Err(CryptoError::InvalidInput(
+            "keystore: argon2 is not supported".to_string(),
+        )),
+    }?;
+    Ok(SecretBytes::new(dk))
+}
+
+fn keystore_mac(dk: &[u8], ciphertext: &[u8]) -> [u8; 32] {
+    keccak256(&[&dk[16..32], ciphertext].concat())
+}
+
+fn uuid_v4() -> String {
+    let mut b = genSecureRandBytes(16);
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let h = hex::encode(&b);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &h[..8],
+        &h[8..12],
+        &h[12..16],
+        &h[16..20],
+        &h[20..]
+    )
+}
+
+fn hex_field(value: &Value, name: &str) -> Result<Vec<u8>, CryptoError> {
+    value
+        .as_str()
+        .and_then(|s| hex::decode(s.trim_start_matches("0x")).ok())
+        .ok_or_else(|| {
+            CryptoError::InvalidInput(format!("keystore: missing or malformed {}", name))
+        })
+}
+
+fn uint_field(value: &Value, name: &str) -> Result<u64, CryptoError> {
+    value.as_u64().ok_or_else(|| {
+        CryptoError::InvalidInput(format!("keystore: missing or malformed {}", name))
+    })
+}
+
+fn small_field(value: &Value, name: &str) -> Result<u32, CryptoError> {
+    match uint_field(value, name)? {
+        v @ 1..=255 => Ok(v as u32),
+        _ => Err(CryptoError::InvalidInput(format!(
+            "keystore: scrypt {} out of range",
+            name
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::hex::FromHex;
+
+    #[test]
+    fn test_keystore() {
+        // test vector from: Web3 Secret Storage Definition, PBKDF2-SHA-256
+        let json = r#"{
+            "crypto": {
+                "cipher": "aes-128-ctr",
+                "cipherparams": { "iv": "6087dab2f9fdbbfaddc31a909735c1e6" },
+                "ciphertext": "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+                "kdf": "pbkdf2",
+                "kdfparams": {
+                    "c": 262144,
+                    "dklen": 32,
+                    "prf": "hmac-sha256",
+                    "salt": "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd"
+                },
+                "mac": "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2"
+            },
+            "id": "3198bc9c-6672-5ab3-d995-4942343ae5b6",
+            "version": 3
+        }"#;
+        let sk = Vec::from_hex("7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d")
+            .unwrap();
+        assert_eq!(
+            decrypt_keystore(json, b"testpassword").unwrap().as_bytes(),
+            &sk[..]
+        );
+        assert!(decrypt_keystore(json, b"wrongpassword").is_err());
+
+        let params = PasswordParams::Scrypt(Scrypt {
+            log_n: 10,
+            r: 8,
+            p: 1,
+        });
+        let sealed = encrypt_keystore(&sk, b"testpassword", params).unwrap();
+        let value: Value = serde_json::from_str(&sealed).unwrap();
+        // 私钥 0x7a28... 对应的地址
+        assert_eq!(
+            value["address"],
+            hex::encode(&address(&SecretKey::parse_slice(&sk).unwrap()))
+        );
+        assert_eq!(
+            decrypt_keystore(&sealed, b"testpassword")
+                .unwrap()
+                .as_bytes(),
+            &sk[..]
+        );
+        assert!(encrypt_keystore(&sk, b"", PasswordParams::argon2id()).is_err());
+
+        // n = 2^20, r = 255 需要 32GiB 内存
+        let mut value: Value = serde_json::from_str(&sealed).unwrap();
+        value["crypto"]["kdfparams"]["n"] = json!(1 << 20);
+        value["crypto"]["kdfparams"]["r"] = json!(255);
+        assert_eq!(
+            decrypt_keystore(&value.to_string(), b"testpassword"),
+            Err(CryptoError::InvalidInput(
+                "keystore: scrypt parameters need too much memory".to_string()
+            ))
+        );
+    }
+}
@@ -0,0 +1,238 @@
+//! AES 密钥封装 AES-KW (RFC 3394) 和带填充的 AES-KWP (RFC 5649)
+//!
+//! 用主密钥(KEK)封装数据密钥，封装结果比原密钥长8字节，解封时检查完整性。
+//! AES-KW 要求密钥长度是8的倍数且至少16字节，AES-KWP 接受任意非空长度
+
+use crypto::symmetriccipher::{BlockDecryptor, BlockEncryptor};
+
+use crate::cipher::{decryptor, encryptor};
+use crate::ct::ct_eq;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::secret::SecretBytes;
+
+const KW_IV: [u8; 8] = [0xa6; 8];
+const KWP_IV_PREFIX: [u8; 4] = [0xa6, 0x59, 0x59, 0xa6];
+
+/// AES-KW 封装密钥
+///
+/// kek: 16、24或32字节的主密钥
+/// key: 待封装的密钥，长度是8的倍数且至少16字节
+pub fn wrap_key(kek: &[u8], key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if key.len() < 16 || !key.len().is_multiple_of(8) {
+        return Err(CryptoError::InvalidInput(
+            "keywrap: key must be a multiple of 8 bytes and at least 16 bytes".to_string(),
+        ));
+    }
+    Ok(wrap(&*kek_encryptor(kek)?, KW_IV, key))
+}
+
+/// AES-KW 解封密钥，完整性检查失败时返回 CryptoError::Verify
+pub fn unwrap_key(kek: &[u8], wrapped: &[u8]) -> Result<SecretBytes, CryptoError> {
+    if wrapped.len() < 24 || !wrapped.len().is_multiple_of(8) {
+        return Err(CryptoError::InvalidInput(
+            "keywrap: wrapped key must be a multiple of 8 bytes and at least 24 bytes".to_string(),
+        ));
+    }
+    let (iv, key) = unwrap(&*kek_decryptor(kek)?, wrapped);
+    if !ct_eq(&iv, &KW_IV) {
+        return Err(VerifyFailure::DigestMismatch.into());
+    }
+    Ok(key)
+}
+
+/// AES-KWP 封装密钥，密钥长度不限，补0到8的倍数
+///
+/// kek: 16、24或32字节的主密钥
+/// key: 待封装的密钥，不能为空
+pub fn wrap_key_with_padding(kek: &[u8], key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if key.is_empty() || key.len() > u32::MAX as usize {
+        return Err(CryptoError::InvalidInput(
+            "keywrap: key must be 1 to 2^32 - 1 bytes".to_string(),
+        ));
+    }
+    let enc = kek_encryptor(kek)?;
+    let mut iv = [0u8; 8];
+    iv[..4].copy_from_slice(&KWP_IV_PREFIX);
+    iv[4..].copy_from_slice(&(key.len() as u32).to_be_bytes());
+    let mut padded = SecretBytes::zeroed(key.len().div_ceil(8) * 8);
+    padded.as_mut_bytes()[..key.len()].copy_from_slice(key);
+    // 补齐后只有一个分组时直接用 AES 加密 IV | P
+    if padded.len() == 8 {
+        let block = SecretBytes::new([&iv[..], &padded[..]].concat());
+        let mut out = vec![0u8; 16];
+        enc.encrypt_block(&block, &mut out);
+        return Ok(out);
+    }
+    Ok(wrap(&*enc, iv, &padded))
+}
+
+/// AES-KWP 解封密钥，完整性检查失败时返回 CryptoError::Verify
+pub fn unwrap_key_with_padding(kek: &[u8], wrapped: &[u8]) -> Result<SecretBytes, CryptoError> {
+    if wrapped.len() < 16 || !wrapped.len().is_multiple_of(8) {
+        return Err(CryptoError::InvalidInput(
+            "keywrap: wrapped key must be a multiple of 8 bytes and at least 16 bytes".to_string(),
+        ));
+    }
+    let dec = kek_decryptor(kek)?;
+    let (iv, padded) = if wrapped.len() == 16 {
+        let mut block = SecretBytes::zeroed(16);
+        dec.decrypt_block(wrapped, block.as_mut_bytes());
+        let mut iv = [0u8; 8];
+        iv.copy_from_slice(&block[..8]);
+        (iv, SecretBytes::new(block[8..].to_vec()))
+    } else {
+        unwrap(&*dec, wrapped)
+    };
+    // 检查 IV 前缀、消息长度和填充，失败原因不区分
+    let mut len = [0u8; 4];
+    len.copy_from_slice(&iv[4..]);
+    let len = u32::from_be_bytes(len) as usize;
+    let valid = ct_eq(&iv[..4], &KWP_IV_PREFIX)
+        && len <= padded.len()
+        && len + 8 > padded.len()
+        && padded[len..].iter().all(|b| *b == 0);
+    if !valid {
+        return Err(VerifyFailure::DigestMismatch.into());
+    }
+    Ok(SecretBytes::new(padded[..len].to_vec()))
+}
+
+fn kek_encryptor(kek: &[u8]) -> Result<Box<dyn BlockEncryptor>, CryptoError> {
+    encryptor(kek)
+        .map_err(|_| CryptoError::InvalidKey("keywrap: kek must be 16, 24 or 32 bytes".to_string()))
+}
+
+fn kek_decryptor(kek: &[u8]) -> Result<Box<dyn BlockDecryptor>, CryptoError> {
+    decryptor(kek)
+        .map_err(|_| CryptoError::InvalidKey("keywrap: kek must be 16, 24 or 32 bytes".to_string()))
+}
+
+// RFC 3394 2.2.1，按索引方式计算，n 为64位分组数
+fn wrap(enc: &dyn BlockEncryptor, iv: [u8; 8], plaintext: &[u8]) -> Vec<u8> {
+    let n = plaintext.len() / 8;
+    let mut a = iv;
+    let mut r = plaintext.to_vec();
+    let mut block = [0u8; 16];
+    let mut out = [0u8; 16];
+    for j in 0..6 {
+        for i in 0..n {
+            block[..8].copy_from_slice(&a);
+            block[8..].copy_from_slice(&r[i * 8..(i + 1) * 8]);
+            enc.encrypt_block(&block, &mut out);
+            let t = (n * j + i + 1) as u64;
+            a.copy_from_slice(&out[..8]);
+            for (x, y) in a.iter_mut().zip(t.to_be_bytes()) {
+                *x ^= y;
+            }
+            r[i * 8..(i + 1) * 8].copy_from_slice(&out[8..]);
+        }
+    }
+    block.fill(0);
+    out.fill(0);
+    [&a[..], &r[..]].concat()
+}
+
+// RFC 3394 2.2.2，返回解出的 IV 和明文，由调用方检查 IV
+fn unwrap(dec: &dyn BlockDecryptor, ciphertext: &[u8]) -> ([u8; 8], SecretBytes) {
+    let n = ciphertext.len() / 8 - 1;
+    let mut a = [0u8; 8];
+    a.copy_from_slice(&ciphertext[..8]);
+    let mut r = SecretBytes::new(ciphertext[8..].to_vec());
+    let mut block = [0u8; 16];
+    let mut out = [0u8; 16];
+    for j in (0..6).rev() {
+        for i in (0..n).rev() {
+            let t = (n * j + i + 1) as u64;
+            for (x, y) in a.iter_mut().zip(t.to_be_bytes()) {
+                *x ^= y;
+            }
+            block[..8].copy_from_slice(&a);
+            block[8..].copy_from_slice(&r[i * 8..(i + 1) * 8]);
+            dec.decrypt_block(&block, &mut out);
+            a.copy_from_slice(&out[..8]);
+            r.as_mut_bytes()[i * 8..(i + 1) * 8].copy_from_slice(&out[8..]);
+        }
+    }
+    block.fill(0);
+    out.fill(0);
+    (a, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_keywrap() {
+        // RFC 3394 4.1 和 4.6
+        let kek = Vec::from_hex("000102030405060708090A0B0C0D0E0F").unwrap();
+        let key = Vec::from_hex("00112233445566778899AABBCCDDEEFF").unwrap();
+        let wrapped = wrap_key(&kek, &key).unwrap();
+        assert_eq!(
+            wrapped,
+            Vec::from_hex("1FA68B0A8112B447AEF34BD8FB5A7B829D3E862371D2CFE5").unwrap()
+        );
+        assert_eq!(unwrap_key(&kek, &wrapped).unwrap().as_bytes(), &key[..]);
+
+        let kek = Vec::from_hex("000102030405060708090A0B0C0D0E0F101112131415161718191A1B1C1D1E1F")
+            .unwrap();
+        let key = Vec::from_hex("00112233445566778899AABBCCDDEEFF000102030405060708090A0B0C0D0E0F")
+            .unwrap();
+        let wrapped = wrap_key(&kek, &key).unwrap();
+        assert_eq!(
+            wrapped,
+            Vec::from_hex(
+                "28C9F404C4B810F4CBCCB35CFB87F8263F5786E2D80ED326CBC7F0E71A99F43BFB988B9B7A02DD21"
+            )
+            .unwrap()
+        );
+        assert_eq!(unwrap_key(&kek, &wrapped).unwrap().as_bytes(), &key[..]);
+
+        let mut bad = wrapped.clone();
+        bad[10] ^= 1;
+        assert_eq!(
+            unwrap_key(&kek, &bad),
+            Err(CryptoError::Verify(VerifyFailure::DigestMismatch))
+        );
+        assert!(matches!(
+            unwrap_key(&kek[..8], &wrapped),
+            Err(CryptoError::InvalidKey(_))
+        ));
+        assert!(wrap_key(&kek, &key[..12]).is_err());
+
+        // RFC 5649 6
+        let kek = Vec::from_hex("5840df6e29b02af1ab493b705bf16ea1ae8338f4dcc176a8").unwrap();
+        let key = Vec::from_hex("c37b7e6492584340bed12207808941155068f738").unwrap();
+        let wrapped = wrap_key_with_padding(&kek, &key).unwrap();
+        assert_eq!(
+            wrapped,
+            Vec::from_hex("138bdeaa9b8fa7fc61f97742e72248ee5ae6ae5360d1ae6a5f54f373fa543b6a")
+                .unwrap()
+        );
+        assert_eq!(
+            unwrap_key_with_padding(&kek, &wrapped).unwrap().as_bytes(),
+            &key[..]
+        );
+        let key = Vec::from_hex("466f7250617369").unwrap();
+        let wrapped = wrap_key_with_padding(&kek, &key).unwrap();
+        assert_eq!(
+            wrapped,
+            Vec::from_hex("afbeb0f07dfbf5419200f2ccb50bb24f").unwrap()
+        );
+        assert_eq!(
+            unwrap_key_with_padding(&kek, &wrapped).unwrap().as_bytes(),
+            &key[..]
+        );
+        let mut bad = wrapped;
+        bad[0] ^= 1;
+        assert_eq!(
+            unwrap_key_with_padding(&kek, &bad),
+            Err(CryptoError::Verify(VerifyFailure::DigestMismatch))
+        );
+        // AES-KW 的结果不能作为 AES-KWP 解封
+        let wrapped = wrap_key(&kek, &[7u8; 16]).unwrap();
+        assert!(unwrap_key_with_padding(&kek, &wrapped).is_err());
+        assert!(wrap_key_with_padding(&kek, b"").is_err());
+    }
+}
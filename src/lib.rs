@@ -5,13 +5,84 @@
 
 //! 常用的密码学算法
 //! 包括哈希，椭圆曲线，hmac, jwt, 密码学安全的随机数生成等
+//!
+//! 只支持 std 环境：rust-crypto、jsonwebtoken、serde_json 等依赖没有 no_std 模式，
+//! 哈希、hmac、ed25519 和 secp256k1 也依赖其中的 rust-crypto，暂不提供 no_std 构建
 
+pub mod aead;
 pub mod aes;
+pub mod age;
 #[cfg(feature = "bls")]
 pub mod bls;
+#[cfg(feature = "bls12381")]
+pub mod bls12381;
+pub mod brainpool;
+pub mod cbor;
+pub mod cipher;
+pub mod commitment;
+pub mod convergent;
+pub mod cose;
+pub mod ct;
 pub mod digest;
+pub mod dsa;
+pub mod dsse;
+mod ec;
+pub mod ecies;
 pub mod ed25519;
+pub mod ed448;
+pub mod encoding;
+pub mod envelope;
+pub mod error;
+pub mod eth;
+#[cfg(feature = "capi")]
+pub mod ffi;
+mod field448;
+pub mod fingerprint;
+pub mod fpe;
+mod framing;
+pub mod hd;
 pub mod hmac;
+pub mod jcs;
+pub mod jose;
 pub mod jwt;
+pub mod kdf;
+pub mod keyring;
+pub mod keystore;
+pub mod keywrap;
+pub mod mac;
+pub mod merkle;
+pub mod metrics;
+pub mod multisig;
+pub mod musig;
+pub mod nacl;
+pub mod nonce;
+pub mod otp;
+pub mod password;
+pub mod pbe;
+pub mod pem;
+pub mod pkcs8;
+mod proto;
 pub mod random;
+pub mod schnorr;
+pub mod secret;
+#[cfg(feature = "serde")]
+pub mod serialization;
 pub mod signature;
+pub mod signmessage;
+pub mod siv;
+pub mod sm2;
+pub mod sm4;
+#[cfg(feature = "sm9")]
+pub mod sm9;
+pub mod spki;
+pub mod ssh;
+pub mod sss;
+pub mod stream;
+pub mod taproot;
+pub mod testkey;
+pub mod tink;
+pub mod vrf;
+pub mod x25519;
+pub mod x448;
+pub mod x509;
+pub mod zuc;
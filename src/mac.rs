@@ -0,0 +1,341 @@
+//! 消息认证码
+//!
+//! 统一 HMAC, HMAC-SM3, AES-CMAC, Poly1305, KMAC 和带密钥的 BLAKE2 的调用方式，协议代码可以对MAC算法泛型
+
+use crypto::aessafe::{AesSafe128Encryptor, AesSafe192Encryptor, AesSafe256Encryptor};
+use crypto::blake2b::Blake2b;
+use crypto::blake2s::Blake2s;
+use crypto::hmac::Hmac;
+use crypto::mac::Mac as _;
+use crypto::poly1305::Poly1305 as Poly1305Impl;
+use crypto::symmetriccipher::BlockEncryptor;
+use ring::hmac;
+use tiny_keccak::{Hasher, Kmac};
+
+use crate::ct::ct_eq;
+use crate::digest::sm3::Sm3;
+
+/// 消息认证码
+pub trait Mac: Sized {
+    /// 认证码长度
+    const TAG_SIZE: usize;
+
+    /// 使用密钥构建MAC对象，密钥长度不合法时返回错误
+    fn new_with_key(key: &[u8]) -> Result<Self, String>;
+
+    /// 追加数据
+    fn update(&mut self, data: &[u8]);
+
+    /// 计算认证码
+    fn finalize(self) -> Vec<u8>;
+
+    /// 常量时间比较认证码
+    fn verify(self, tag: &[u8]) -> bool {
+        ct_eq(&self.finalize(), tag)
+    }
+}
+
+/// 一次性计算数据的认证码
+pub fn mac<M: Mac>(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut m = M::new_with_key(key)?;
+    m.update(data);
+    Ok(m.finalize())
+}
+
+macro_rules! impl_hmac {
+    ($name:ident, $alg:expr, $size:expr, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name(hmac::Context);
+
+        impl Mac for $name {
+            const TAG_SIZE: usize = $size;
+
+            fn new_with_key(key: &[u8]) -> Result<Self, String> {
+                Ok($name(hmac::Context::with_key(&hmac::Key::new($alg, key))))
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.0.update(data);
+            }
+
+            fn finalize(self) -> Vec<u8> {
+                self.0.sign().as_ref().to_vec()
+            }
+        }
+    };
+}
+
+impl_hmac!(HmacSha256, hmac::HMAC_SHA256, 32, "HMAC-SHA256");
+impl_hmac!(HmacSha384, hmac::HMAC_SHA384, 48, "HMAC-SHA384");
+impl_hmac!(HmacSha512, hmac::HMAC_SHA512, 64, "HMAC-SHA512");
+
+/// HMAC-SM3，用于国密合规的协议
+pub struct HmacSm3(Hmac<Sm3>);
+
+impl Mac for HmacSm3 {
+    const TAG_SIZE: usize = 32;
+
+    fn new_with_key(key: &[u8]) -> Result<Self, String> {
+        Ok(HmacSm3(Hmac::new(Sm3::new(), key)))
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.input(data);
+    }
+
+    fn finalize(mut self) -> Vec<u8> {
+        self.0.result().code().to_vec()
+    }
+}
+
+const BLOCK_SIZE: usize = 16;
+
+/// AES-CMAC (RFC 4493)，按密钥长度16、24或32字节选择AES-128、AES-192或AES-256
+pub struct Cmac {
+    cipher: Box<dyn BlockEncryptor>,
+    state: [u8; BLOCK_SIZE],
+    // 最后一个分组要在finalize时特殊处理，所以总是保留在缓冲中
+    buf: [u8; BLOCK_SIZE],
+    buf_len: usize,
+}
+
+impl Cmac {
+    fn encrypt(&self, block: &mut [u8; BLOCK_SIZE]) {
+        let input = *block;
+        self.cipher.encrypt_block(&input, block);
+    }
+}
+
+/// GF(2^128) 上乘以x，用于派生CMAC子密钥和SIV的S2V
+pub(crate) fn dbl(block: &[u8; BLOCK_SIZE]) -> [u8; BLOCK_SIZE] {
+    let mut out = [0u8; BLOCK_SIZE];
+    for i in 0..BLOCK_SIZE {
+        let next = if i + 1 < BLOCK_SIZE {
+            block[i + 1] >> 7
+        } else {
+            0
+        };
+        out[i] = (block[i] << 1) | next;
+    }
+    if block[0] & 0x80 != 0 {
+        out[BLOCK_SIZE - 1] ^= 0x87;
+    }
+    out
+}
+
+impl Mac for Cmac {
+    const TAG_SIZE: usize = BLOCK_SIZE;
+
+    fn new_with_key(key: &[u8]) -> Result<Self, String> {
+        let cipher: Box<dyn BlockEncryptor> = match key.len() {
+            16 => Box::new(AesSafe128Encryptor::new(key)),
+            24 => Box::new(AesSafe192Encryptor::new(key)),
+            32 => Box::new(AesSafe256Encryptor::new(key)),
+            _ => return Err("mac: CMAC key must be 16, 24 or 32 bytes".to_string()),
+        };
+        Ok(Cmac {
+            cipher,
+            state: [0u8; BLOCK_SIZE],
+            buf: [0u8; BLOCK_SIZE],
+            buf_len: 0,
+        })
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if self.buf_len == BLOCK_SIZE {
+                for (s, b) in self.state.iter_mut().zip(self.buf.iter()) {
+                    *s ^= b;
+                }
+                let mut state = self.state;
+                self.encrypt(&mut state);
+                self.state = state;
+                self.buf_len = 0;
+            }
+            let n = (BLOCK_SIZE - self.buf_len).min(data.len());
+            self.buf[self.buf_len..self.buf_len + n].copy_from_slice(&data[..n]);
+            self.buf_len += n;
+            data = &data[n..];
+        }
+    }
+
+    fn finalize(self) -> Vec<u8> {
+        let mut l = [0u8; BLOCK_SIZE];
+        self.encrypt(&mut l);
+        let k1 = dbl(&l);
+        let mut last = self.buf;
+        let subkey = if self.buf_len == BLOCK_SIZE {
+            k1
+        } else {
+            last[self.buf_len] = 0x80;
+            for b in last[self.buf_len + 1..].iter_mut() {
+                *b = 0;
+            }
+            dbl(&k1)
+        };
+        let mut block = self.state;
+        for i in 0..BLOCK_SIZE {
+            block[i] ^= last[i] ^ subkey[i];
+        }
+        self.encrypt(&mut block);
+        block.to_vec()
+    }
+}
+
+/// Poly1305 (RFC 8439)，32字节一次性密钥，同一密钥不能用于两条消息
+pub struct Poly1305(Poly1305Impl);
+
+impl Mac for Poly1305 {
+    const TAG_SIZE: usize = 16;
+
+    fn new_with_key(key: &[u8]) -> Result<Self, String> {
+        if key.len() != 32 {
+            return Err("mac: Poly1305 key must be 32 bytes".to_string());
+        }
+        Ok(Poly1305(Poly1305Impl::new(key)))
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.0.input(data);
+    }
+
+    fn finalize(mut self) -> Vec<u8> {
+        self.0.result().code().to_vec()
+    }
+}
+
+macro_rules! impl_kmac {
+    ($name:ident, $ctor:ident, $size:expr, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name(Kmac);
+
+        impl $name {
+            /// 使用密钥和定制串构建
+            pub fn with_customization(key: &[u8], custom: &[u8]) -> Self {
+                $name(Kmac::$ctor(key, custom))
+            }
+        }
+
+        impl Mac for $name {
+            const TAG_SIZE: usize = $size;
+
+            fn new_with_key(key: &[u8]) -> Result<Self, String> {
+                Ok($name::with_customization(key, &[]))
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.0.update(data);
+            }
+
+            fn finalize(self) -> Vec<u8> {
+                let mut out = vec![0u8; $size];
+                self.0.finalize(&mut out);
+                out
+            }
+        }
+    };
+}
+
+impl_kmac!(Kmac128, v128, 32, "KMAC128 (NIST SP 800-185)，输出32字节");
+impl_kmac!(Kmac256, v256, 64, "KMAC256 (NIST SP 800-185)，输出64字节");
+
+macro_rules! impl_blake2 {
+    ($name:ident, $impl:ident, $size:expr, $doc:expr) => {
+        #[doc = $doc]
+        pub struct $name($impl);
+
+        impl Mac for $name {
+            const TAG_SIZE: usize = $size;
+
+            fn new_with_key(key: &[u8]) -> Result<Self, String> {
+                if key.is_empty() || key.len() > $size {
+                    return Err(format!("mac: BLAKE2 key must be 1 to {} bytes", $size));
+                }
+                Ok($name($impl::new_keyed($size, key)))
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                self.0.input(data);
+            }
+
+            fn finalize(mut self) -> Vec<u8> {
+                self.0.result().code().to_vec()
+            }
+        }
+    };
+}
+
+impl_blake2!(Blake2bMac, Blake2b, 64, "带密钥的 BLAKE2b，输出64字节");
+impl_blake2!(Blake2sMac, Blake2s, 32, "带密钥的 BLAKE2s，输出32字节");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    fn check<M: Mac>(key: &str, data: &[u8], tag: &str) {
+        let key = Vec::from_hex(key).unwrap();
+        let tag = Vec::from_hex(tag).unwrap();
+        assert_eq!(tag.len(), M::TAG_SIZE);
+        assert_eq!(mac::<M>(&key, data).unwrap(), tag);
+
+        // 分段追加与一次性计算结果相同
+        let mut m = M::new_with_key(&key).unwrap();
+        for chunk in data.chunks(7) {
+            m.update(chunk);
+        }
+        assert!(m.verify(&tag));
+    }
+
+    #[test]
+    fn test_mac() {
+        // https://tools.ietf.org/html/rfc4231#section-4.3
+        check::<HmacSha256>(
+            "4a656665",
+            b"what do ya want for nothing?",
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843",
+        );
+        check::<HmacSm3>(
+            "4a656665",
+            b"what do ya want for nothing?",
+            "2e87f1d16862e6d964b50a5200bf2b10b764faa9680a296a2405f24bec39f882",
+        );
+
+        // https://tools.ietf.org/html/rfc4493#section-4
+        let key = "2b7e151628aed2a6abf7158809cf4f3c";
+        check::<Cmac>(key, b"", "bb1d6929e95937287fa37d129b756746");
+        let msg = Vec::from_hex(
+            "6bc1bee22e409f96e93d7e117393172aae2d8a571e03ac9c9eb76fac45af8e5130c81c46a35ce411",
+        )
+        .unwrap();
+        check::<Cmac>(key, &msg[..16], "070a16b46b4d4144f79bdd9dd04a287c");
+        check::<Cmac>(key, &msg, "dfa66747de9ae63030ca32611497c827");
+
+        // https://tools.ietf.org/html/rfc8439#section-2.5.2
+        check::<Poly1305>(
+            "85d6be7857556d337f4452fe42d506a80103808afb0db2fd4abff6af4149f51b",
+            b"Cryptographic Forum Research Group",
+            "a8061dc1305136c6c22b8baf0c0127a9",
+        );
+
+        // NIST SP 800-185 KMAC_samples.pdf, sample #1
+        check::<Kmac128>(
+            "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f",
+            &[0, 1, 2, 3],
+            "e5780b0d3ea6f7d3a429c5706aa43a00fadbd7d49628839e3187243f456ee14e",
+        );
+
+        // 与 python hashlib.blake2b/blake2s(key=b"key") 的输出对比
+        check::<Blake2bMac>("6b6579", b"abc", "5c6a9a4ae911c02fb7e71a991eb9aea371ae993d4842d206e6020d46f5e41358c6d5c277c110ef86c959ed63e6ecaaaceaaff38019a43264ae06acf73b9550b1");
+        check::<Blake2sMac>(
+            "6b6579",
+            b"abc",
+            "3f9723437b033bf0c1f4df43cafd0776068cb0a95912de13f3b2952a3aba764d",
+        );
+
+        assert!(Cmac::new_with_key(&[0u8; 10]).is_err());
+        assert!(!Poly1305::new_with_key(&[1u8; 32])
+            .unwrap()
+            .verify(&[0u8; 16]));
+    }
+}
@@ -0,0 +1,598 @@
+//! 分块 Merkle 文件签名
+//!
+//! 大文件按固定大小分块，每块的哈希作为叶子构建 Merkle 树，只对根签名。
+//! 下载方先校验清单(根、分块大小、文件大小)上的签名，之后每收到一块就可以用证明单独校验，
+//! 支持断点续传和边下载边校验，不需要等整个文件下载完成。
+//!
+//! 叶子哈希 = SHA256(0x00 | 分块)，内部节点 = SHA256(0x01 | 左 | 右)，
+//! 某一层节点个数为奇数时，最后一个节点直接提升到上一层
+//!
+//! HashTree 是通用的 Merkle 树，哈希算法可以是 digest 模块中任意实现了 Digest 的类型，奇数节点的处理方式可选
+
+use std::io::Read;
+
+use ring::digest::{Context, SHA256};
+
+use crate::digest::{sha256_many, Digest};
+use crate::framing::{put_bytes16, Reader};
+use crate::signature::{scheme_verify, SignatureScheme};
+
+const MAGIC: &[u8; 4] = b"PICF";
+const VERSION: u8 = 1;
+const SIGN_CONTEXT: &[u8] = b"pi_crypto chunked file v1";
+
+/// 哈希长度
+pub const HASH_SIZE: usize = 32;
+
+/// 默认分块大小，1MB
+pub const DEFAULT_CHUNK_SIZE: u32 = 1 << 20;
+
+/// Merkle 树
+pub struct MerkleTree {
+    chunk_size: u32,
+    file_size: u64,
+    // levels[0] 为叶子，最后一层为根
+    levels: Vec<Vec<[u8; HASH_SIZE]>>,
+}
+
+impl MerkleTree {
+    /// 从内存中的数据构建
+    ///
+    /// data: 文件内容
+    /// chunk_size: 分块大小
+    pub fn from_bytes(data: &[u8], chunk_size: u32) -> Result<Self, String> {
+        Self::from_reader(data, chunk_size)
+    }
+
+    /// 从流中逐块读取并构建，内存中只保留哈希
+    pub fn from_reader<R: Read>(mut reader: R, chunk_size: u32) -> Result<Self, String> {
+        if chunk_size == 0 {
+            return Err("merkle: chunk size must not be zero".to_string());
+        }
+        let mut leaves = Vec::new();
+        let mut file_size = 0u64;
+        let mut buf = vec![0u8; chunk_size as usize];
+        loop {
+            let n = read_full(&mut reader, &mut buf)?;
+            if n == 0 && !leaves.is_empty() {
+                break;
+            }
+            leaves.push(leaf_hash(&buf[..n]));
+            file_size += n as u64;
+            if n < buf.len() {
+                break;
+            }
+        }
+        let mut levels = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            // 同一层的内部节点相互独立，批量计算
+            let level = &levels[levels.len() - 1];
+            let nodes: Vec<[u8; 2 * HASH_SIZE + 1]> = level
+                .chunks_exact(2)
+                .map(|pair| {
+                    let mut node = [1u8; 2 * HASH_SIZE + 1];
+                    node[1..HASH_SIZE + 1].copy_from_slice(&pair[0]);
+                    node[HASH_SIZE + 1..].copy_from_slice(&pair[1]);
+                    node
+                })
+                .collect();
+            let inputs: Vec<&[u8]> = nodes.iter().map(|node| &node[..]).collect();
+            let mut next = sha256_many(&inputs);
+            if level.len() % 2 == 1 {
+                next.push(level[level.len() - 1]);
+            }
+            levels.push(next);
+        }
+        Ok(MerkleTree {
+            chunk_size,
+            file_size,
+            levels,
+        })
+    }
+
+    /// 根哈希
+    pub fn root(&self) -> [u8; HASH_SIZE] {
+        self.levels[self.levels.len() - 1][0]
+    }
+
+    /// 分块个数，空文件为一个空分块
+    pub fn chunk_count(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// 生成第 index 块的证明，即从叶子到根路径上的兄弟节点
+    pub fn proof(&self, index: u64) -> Result<Vec<[u8; HASH_SIZE]>, String> {
+        if index >= self.chunk_count() {
+            return Err("merkle: chunk index out of range".to_string());
+        }
+        let mut proof = Vec::new();
+        let mut i = index as usize;
+        for level in &self.levels[..self.levels.len() - 1] {
+            let sibling = i ^ 1;
+            if sibling < level.len() {
+                proof.push(level[sibling]);
+            }
+            i /= 2;
+        }
+        Ok(proof)
+    }
+
+    /// 生成待签名的清单，签名由调用方使用自己的密钥完成
+    pub fn manifest(&self) -> Manifest {
+        Manifest {
+            chunk_size: self.chunk_size,
+            file_size: self.file_size,
+            root: self.root(),
+        }
+    }
+}
+
+/// 文件清单，签名的对象
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Manifest {
+    pub chunk_size: u32,
+    pub file_size: u64,
+    pub root: [u8; HASH_SIZE],
+}
+
+impl Manifest {
+    /// 待签名的数据: 上下文 | 分块大小(u32) | 文件大小(u64) | 根
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut out = SIGN_CONTEXT.to_vec();
+        out.extend_from_slice(&self.chunk_size.to_be_bytes());
+        out.extend_from_slice(&self.file_size.to_be_bytes());
+        out.extend_from_slice(&self.root);
+        out
+    }
+
+    /// 分块个数，分块大小为0的清单无效，返回0
+    pub fn chunk_count(&self) -> u64 {
+        if self.chunk_size == 0 {
+            return 0;
+        }
+        if self.file_size == 0 {
+            return 1;
+        }
+        self.file_size.div_ceil(u64::from(self.chunk_size))
+    }
+
+    /// 使用签名函数对清单签名
+    ///
+    /// scheme: 签名方案
+    /// public_key: 签名者公钥，验证时需要在受信任的密钥中
+    /// sign: 签名函数，参数为 signing_payload()
+    pub fn sign_with<F>(
+        &self,
+        scheme: SignatureScheme,
+        public_key: &[u8],
+        sign: F,
+    ) -> SignedManifest
+    where
+        F: FnOnce(&[u8]) -> Vec<u8>,
+    {
+        SignedManifest {
+            manifest: *self,
+            scheme,
+            public_key: public_key.to_vec(),
+            signature: sign(&self.signing_payload()),
+        }
+    }
+
+    /// 校验分块，清单的签名需要事先验证
+    ///
+    /// index: 分块序号
+    /// chunk: 分块内容
+    /// proof: MerkleTree::proof 生成的证明
+    pub fn verify_chunk(&self, index: u64, chunk: &[u8], proof: &[[u8; HASH_SIZE]]) -> bool {
+        if self.chunk_size == 0 {
+            return false;
+        }
+        let count = self.chunk_count();
+        if index >= count {
+            return false;
+        }
+        let expected_len = if index == count - 1 {
+            self.file_size - index * u64::from(self.chunk_size)
+        } else {
+            u64::from(self.chunk_size)
+        };
+        if chunk.len() as u64 != expected_len {
+            return false;
+        }
+
+        let mut hash = leaf_hash(chunk);
+        let mut proof = proof.iter();
+        let (mut i, mut width) = (index, count);
+        while width > 1 {
+            let sibling = i ^ 1;
+            if sibling < width {
+                let node = match proof.next() {
+                    Some(node) => node,
+                    None => return false,
+                };
+                hash = if i % 2 == 0 {
+                    node_hash(&hash, node)
+                } else {
+                    node_hash(node, &hash)
+                };
+            }
+            i /= 2;
+            width = width.div_ceil(2);
+        }
+        proof.next().is_none() && hash == self.root
+    }
+}
+
+/// 已签名的文件清单
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedManifest {
+    pub manifest: Manifest,
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+impl SignedManifest {
+    /// 验证清单签名
+    ///
+    /// trusted: 受信任的签名者（签名方案，公钥）
+    pub fn verify(&self, trusted: &[(SignatureScheme, Vec<u8>)]) -> bool {
+        trusted
+            .iter()
+            .any(|(scheme, pk)| *scheme == self.scheme && *pk == self.public_key)
+            && scheme_verify(
+                self.scheme,
+                &self.public_key,
+                &self.manifest.signing_payload(),
+                &self.signature,
+            )
+    }
+
+    /// 序列化
+    ///
+    /// 格式: "PICF" | 版本(1字节) | 分块大小(u32) | 文件大小(u64) | 根(32字节) | 方案(1字节) | 公钥长度(u16) | 公钥 | 签名长度(u16) | 签名
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&self.manifest.chunk_size.to_be_bytes());
+        out.extend_from_slice(&self.manifest.file_size.to_be_bytes());
+        out.extend_from_slice(&self.manifest.root);
+        out.push(self.scheme.id());
+        put_bytes16(&mut out, "merkle", &self.public_key)?;
+        put_bytes16(&mut out, "merkle", &self.signature)?;
+        Ok(out)
+    }
+
+    /// 反序列化
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new("merkle", data);
+        if reader.take(4)? != MAGIC {
+            return Err("merkle: bad magic".to_string());
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(format!("merkle: unsupported version {}", version));
+        }
+        let mut chunk_size = [0u8; 4];
+        chunk_size.copy_from_slice(reader.take(4)?);
+        if chunk_size == [0u8; 4] {
+            return Err("merkle: chunk size must not be zero".to_string());
+        }
+        let mut file_size = [0u8; 8];
+        file_size.copy_from_slice(reader.take(8)?);
+        let mut root = [0u8; HASH_SIZE];
+        root.copy_from_slice(reader.take(HASH_SIZE)?);
+        let id = reader.u8()?;
+        let scheme = SignatureScheme::from_id(id)
+            .ok_or_else(|| format!("merkle: unknown signature scheme {}", id))?;
+        let public_key = reader.bytes16()?.to_vec();
+        let signature = reader.bytes16()?.to_vec();
+        reader.finish()?;
+        Ok(SignedManifest {
+            manifest: Manifest {
+                chunk_size: u32::from_be_bytes(chunk_size),
+                file_size: u64::from_be_bytes(file_size),
+                root,
+            },
+            scheme,
+            public_key,
+            signature,
+        })
+    }
+}
+
+/// 某一层节点个数为奇数时最后一个节点的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OddNodePolicy {
+    /// 直接提升到上一层，与 MerkleTree 相同
+    Promote,
+    /// 与自身组合成父节点，即比特币的做法。[a, b, c] 和 [a, b, c, c] 的根相同 (CVE-2012-2459)，
+    /// 只在需要兼容时使用，并且由调用方拒绝重复的叶子
+    Duplicate,
+}
+
+/// 通用 Merkle 树，叶子哈希 = H(0x00 | 叶子)，内部节点 = H(0x01 | 左 | 右)
+pub struct HashTree<D> {
+    digest: D,
+    policy: OddNodePolicy,
+    // levels[0] 为叶子哈希，最后一层为根
+    levels: Vec<Vec<Vec<u8>>>,
+}
+
+/// HashTree 中一个叶子的包含证明
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    /// 叶子序号
+    pub index: u64,
+    /// 叶子个数
+    pub leaf_count: u64,
+    /// 从叶子到根路径上的兄弟节点
+    pub siblings: Vec<Vec<u8>>,
+}
+
+impl<D: Digest + Clone> HashTree<D> {
+    /// 构建 Merkle 树
+    ///
+    /// digest: 哈希算法，如 Hasher::new(DigestAlgorithm::SHA256)
+    /// policy: 奇数节点的处理方式
+    /// leaves: 叶子数据，不能为空
+    pub fn new<T: AsRef<[u8]>>(
+        digest: D,
+        policy: OddNodePolicy,
+        leaves: &[T],
+    ) -> Result<Self, String> {
+        if leaves.is_empty() {
+            return Err("merkle: at least one leaf is required".to_string());
+        }
+        let leaves = leaves
+            .iter()
+            .map(|leaf| digest_parts(&digest, &[&[0], leaf.as_ref()]))
+            .collect();
+        let mut levels: Vec<Vec<Vec<u8>>> = vec![leaves];
+        while levels[levels.len() - 1].len() > 1 {
+            let next = levels[levels.len() - 1]
+                .chunks(2)
+                .map(|pair| match (pair, policy) {
+                    ([left, right], _) => digest_parts(&digest, &[&[1], left, right]),
+                    ([node], OddNodePolicy::Promote) => node.clone(),
+                    ([node], OddNodePolicy::Duplicate) => {
+                        digest_parts(&digest, &[&[1], node, node])
+                    }
+                    _ => unreachable!(),
+                })
+                .collect();
+            levels.push(next);
+        }
+        Ok(HashTree {
+            digest,
+            policy,
+            levels,
+        })
+    }
+
+    /// 根哈希
+    pub fn root(&self) -> &[u8] {
+        &self.levels[self.levels.len() - 1][0]
+    }
+
+    /// 叶子个数
+    pub fn leaf_count(&self) -> u64 {
+        self.levels[0].len() as u64
+    }
+
+    /// 生成第 index 个叶子的包含证明
+    pub fn proof(&self, index: u64) -> Result<MerkleProof, String> {
+        if index >= self.leaf_count() {
+            return Err("merkle: leaf index out of range".to_string());
+        }
+        let mut siblings = Vec::new();
+        let mut i = index as usize;
+        for level in &self.levels[..self.levels.len() - 1] {
+            // Duplicate 时缺少的兄弟就是节点自身，由验证方计算，不放入证明
+            if let Some(sibling) = level.get(i ^ 1) {
+                siblings.push(sibling.clone());
+            }
+            i /= 2;
+        }
+        Ok(MerkleProof {
+            index,
+            leaf_count: self.leaf_count(),
+            siblings,
+        })
+    }
+
+    /// 验证包含证明
+    ///
+    /// root: 可信的根哈希，leaf: 叶子数据，proof: 证明
+    pub fn verify_proof(&self, root: &[u8], leaf: &[u8], proof: &MerkleProof) -> bool {
+        verify_proof(self.digest.clone(), self.policy, root, leaf, proof)
+    }
+}
+
+/// 不需要构建整棵树的包含证明验证，参数与构建时相同
+///
+/// digest: 哈希算法，policy: 奇数节点的处理方式，root: 可信的根哈希，leaf: 叶子数据，proof: 证明
+pub fn verify_proof<D: Digest + Clone>(
+    digest: D,
+    policy: OddNodePolicy,
+    root: &[u8],
+    leaf: &[u8],
+    proof: &MerkleProof,
+) -> bool {
+    if proof.index >= proof.leaf_count {
+        return false;
+    }
+    let mut hash = digest_parts(&digest, &[&[0], leaf]);
+    let mut siblings = proof.siblings.iter();
+    let (mut i, mut width) = (proof.index, proof.leaf_count);
+    while width > 1 {
+        if i ^ 1 < width {
+            let node = match siblings.next() {
+                Some(node) => node,
+                None => return false,
+            };
+            hash = if i % 2 == 0 {
+                digest_parts(&digest, &[&[1], &hash, node])
+            } else {
+                digest_parts(&digest, &[&[1], node, &hash])
+            };
+        } else if policy == OddNodePolicy::Duplicate {
+            hash = digest_parts(&digest, &[&[1], &hash, &hash]);
+        }
+        i /= 2;
+        width = width.div_ceil(2);
+    }
+    siblings.next().is_none() && crate::ct::ct_eq(&hash, root)
+}
+
+fn digest_parts<D: Digest + Clone>(digest: &D, parts: &[&[u8]]) -> Vec<u8> {
+    let mut h = digest.clone();
+    h.reset();
+    for part in parts {
+        h.update(part);
+    }
+    h.finalize()
+}
+
+fn leaf_hash(chunk: &[u8]) -> [u8; HASH_SIZE] {
+    hash(&[&[0], chunk])
+}
+
+fn node_hash(left: &[u8; HASH_SIZE], right: &[u8; HASH_SIZE]) -> [u8; HASH_SIZE] {
+    hash(&[&[1], left, right])
+}
+
+fn hash(parts: &[&[u8]]) -> [u8; HASH_SIZE] {
+    let mut ctx = Context::new(&SHA256);
+    for part in parts {
+        ctx.update(part);
+    }
+    let mut out = [0u8; HASH_SIZE];
+    out.copy_from_slice(ctx.finish().as_ref());
+    out
+}
+
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, String> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(len) => n += len,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(format!("merkle: read failed: {}", e)),
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crypto::ed25519;
+
+    #[test]
+    fn test_chunked_signature() {
+        let data: Vec<u8> = (0..1000u32).map(|i| i as u8).collect();
+        let tree = MerkleTree::from_bytes(&data, 64).unwrap();
+        assert_eq!(tree.chunk_count(), 16);
+
+        let (sk, pk) = ed25519::keypair(&[1u8; 32]);
+        let signed = tree
+            .manifest()
+            .sign_with(SignatureScheme::Ed25519, &pk, |payload| {
+                ed25519::signature(payload, &sk).to_vec()
+            });
+        let signed = SignedManifest::from_bytes(&signed.to_bytes().unwrap()).unwrap();
+        let trusted = vec![(SignatureScheme::Ed25519, pk.to_vec())];
+        assert!(signed.verify(&trusted));
+        assert!(!signed.verify(&[]));
+
+        // 乱序逐块校验
+        let manifest = signed.manifest;
+        for index in (0..16u64).rev() {
+            let chunk = &data[index as usize * 64..((index as usize + 1) * 64).min(data.len())];
+            let proof = tree.proof(index).unwrap();
+            assert!(manifest.verify_chunk(index, chunk, &proof));
+            assert!(!manifest.verify_chunk(index ^ 1, chunk, &proof));
+        }
+        let mut bad = data[..64].to_vec();
+        bad[0] ^= 1;
+        assert!(!manifest.verify_chunk(0, &bad, &tree.proof(0).unwrap()));
+
+        // 奇数个分块和空文件
+        let tree = MerkleTree::from_bytes(&data[..300], 64).unwrap();
+        assert_eq!(tree.chunk_count(), 5);
+        let proof = tree.proof(4).unwrap();
+        assert_eq!(proof.len(), 1);
+        assert!(tree.manifest().verify_chunk(4, &data[256..300], &proof));
+        let tree = MerkleTree::from_bytes(&[], 64).unwrap();
+        assert!(tree.manifest().verify_chunk(0, &[], &[]));
+
+        // 分块大小为0的清单不能解析，直接构造的也不会除零
+        let zero = Manifest {
+            chunk_size: 0,
+            ..manifest
+        };
+        assert_eq!(zero.chunk_count(), 0);
+        assert!(!zero.verify_chunk(0, &data[..64], &tree.proof(0).unwrap()));
+        let mut bytes = signed.to_bytes().unwrap();
+        bytes[5..9].copy_from_slice(&[0; 4]);
+        assert!(SignedManifest::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_hash_tree() {
+        use crate::digest::blake2::Blake2b;
+        use crate::digest::{DigestAlgorithm, Hasher};
+
+        // 与 MerkleTree 的叶子和节点哈希相同
+        let data: Vec<u8> = (0..300u32).map(|i| i as u8).collect();
+        let chunks: Vec<&[u8]> = data.chunks(64).collect();
+        let sha256 = Hasher::new(DigestAlgorithm::SHA256);
+        let tree = HashTree::new(sha256.clone(), OddNodePolicy::Promote, &chunks).unwrap();
+        let file = MerkleTree::from_bytes(&data, 64).unwrap();
+        assert_eq!(tree.root(), &file.root()[..]);
+
+        for policy in [OddNodePolicy::Promote, OddNodePolicy::Duplicate] {
+            let blake2b = Blake2b::new(32, b"").unwrap();
+            let tree = HashTree::new(blake2b.clone(), policy, &chunks).unwrap();
+            for (index, chunk) in chunks.iter().enumerate() {
+                let proof = tree.proof(index as u64).unwrap();
+                assert!(tree.verify_proof(tree.root(), chunk, &proof));
+                assert!(verify_proof(
+                    blake2b.clone(),
+                    policy,
+                    tree.root(),
+                    chunk,
+                    &proof
+                ));
+                assert!(!tree.verify_proof(tree.root(), b"other", &proof));
+            }
+            // 5个叶子时最后一个叶子只有一个兄弟节点，其余由提升或自身组合得到
+            let proof = tree.proof(4).unwrap();
+            assert_eq!(proof.siblings.len(), 1);
+            // 根取决于策略，用另一种策略验证会失败
+            assert_eq!(
+                verify_proof(
+                    blake2b,
+                    OddNodePolicy::Promote,
+                    tree.root(),
+                    chunks[4],
+                    &proof
+                ),
+                policy == OddNodePolicy::Promote
+            );
+        }
+
+        // Duplicate 策略下重复最后一个叶子得到相同的根
+        let dup = |leaves: &[&[u8]]| {
+            HashTree::new(sha256.clone(), OddNodePolicy::Duplicate, leaves)
+                .unwrap()
+                .root()
+                .to_vec()
+        };
+        assert_eq!(dup(&[b"a", b"b", b"c"]), dup(&[b"a", b"b", b"c", b"c"]));
+        assert!(HashTree::new(sha256, OddNodePolicy::Promote, &[] as &[&[u8]]).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! 性能和失败率监控钩子
+//!
+//! 安装 Instrument 后，签名、验签、哈希和密钥派生调用结束时会上报操作名、算法、耗时和是否成功，
+//! 运维可以导出延迟和失败率指标而不必包装每个调用点。未安装时只有一次原子读的开销。
+//! 回调在调用线程中同步执行，实现应尽快返回，不能再调用被监控的函数
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// 被监控的操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    Sign,
+    Verify,
+    Digest,
+    Kdf,
+}
+
+impl Operation {
+    /// 操作名，可直接作为指标标签
+    pub fn name(self) -> &'static str {
+        match self {
+            Operation::Sign => "sign",
+            Operation::Verify => "verify",
+            Operation::Digest => "digest",
+            Operation::Kdf => "kdf",
+        }
+    }
+}
+
+/// 一次调用的监控数据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Event {
+    pub operation: Operation,
+    /// 算法名，如 "SHA256"、"ed25519"
+    pub algorithm: &'static str,
+    pub duration: Duration,
+    /// 签名为空、验签失败、派生出错时为 false
+    pub success: bool,
+}
+
+/// 监控回调
+pub trait Instrument: Send + Sync {
+    fn record(&self, event: &Event);
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static INSTRUMENT: RwLock<Option<Arc<dyn Instrument>>> = RwLock::new(None);
+
+/// 安装全局监控回调，替换已安装的回调
+pub fn set_instrument(instrument: Arc<dyn Instrument>) {
+    *INSTRUMENT.write().unwrap_or_else(|e| e.into_inner()) = Some(instrument);
+    ENABLED.store(true, Ordering::Release);
+}
+
+/// 移除全局监控回调
+pub fn clear_instrument() {
+    ENABLED.store(false, Ordering::Release);
+    *INSTRUMENT.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// 计时器，调用 done 上报结果，提前返回时在 drop 中按失败上报
+pub(crate) struct Timer {
+    operation: Operation,
+    algorithm: &'static str,
+    start: Option<Instant>,
+}
+
+impl Timer {
+    pub(crate) fn start(operation: Operation, algorithm: &'static str) -> Self {
+        let start = if ENABLED.load(Ordering::Acquire) {
+            Some(Instant::now())
+        } else {
+            None
+        };
+        Timer {
+            operation,
+            algorithm,
+            start,
+        }
+    }
+
+    /// 上报并原样返回结果
+    pub(crate) fn done<T>(mut self, value: T, success: bool) -> T {
+        self.report(success);
+        value
+    }
+
+    /// 上报 Result，Ok 为成功
+    pub(crate) fn done_result<T, E>(self, result: Result<T, E>) -> Result<T, E> {
+        let ok = result.is_ok();
+        self.done(result, ok)
+    }
+
+    fn report(&mut self, success: bool) {
+        let start = match self.start.take() {
+            Some(start) => start,
+            None => return,
+        };
+        let event = Event {
+            operation: self.operation,
+            algorithm: self.algorithm,
+            duration: start.elapsed(),
+            success,
+        };
+        let instrument = INSTRUMENT.read().unwrap_or_else(|e| e.into_inner()).clone();
+        if let Some(instrument) = instrument {
+            instrument.record(&event);
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        self.report(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::digest::{digest, DigestAlgorithm};
+    use crate::kdf::{Hkdf, Kdf};
+    use std::sync::Mutex;
+
+    struct Recorder(Mutex<Vec<Event>>);
+
+    impl Instrument for Recorder {
+        fn record(&self, event: &Event) {
+            self.0.lock().unwrap().push(*event);
+        }
+    }
+
+    #[test]
+    fn test_instrument() {
+        let recorder = Arc::new(Recorder(Mutex::new(Vec::new())));
+        set_instrument(recorder.clone());
+
+        digest(DigestAlgorithm::SHA384, b"abc");
+        Hkdf::sha256().derive(b"secret", b"", b"", 32).unwrap();
+        assert!(Hkdf::sha256()
+            .derive(b"secret", b"", b"", 255 * 32 + 1)
+            .is_err());
+        let (sk, pk) = crate::ed25519::keypair(&[1u8; 32]);
+        let sig = crate::ed25519::sign(b"msg", sk.as_ref()).unwrap();
+        assert!(!crate::ed25519::verify(b"other", pk.as_ref(), sig.as_ref()));
+        clear_instrument();
+        digest(DigestAlgorithm::SHA384, b"abc");
+
+        // 其他测试并发运行时也会上报，按本测试用到的算法过滤
+        let events = recorder.0.lock().unwrap();
+        let find = |op: Operation, alg: &str| {
+            events
+                .iter()
+                .filter(|e| e.operation == op && e.algorithm == alg)
+                .map(|e| e.success)
+                .collect::<Vec<_>>()
+        };
+        assert!(find(Operation::Digest, "SHA384").contains(&true));
+        let hkdf = find(Operation::Kdf, "HKDF-SHA256");
+        assert!(hkdf.contains(&true) && hkdf.contains(&false));
+        assert!(find(Operation::Sign, "ed25519").contains(&true));
+        assert!(find(Operation::Verify, "ed25519").contains(&false));
+    }
+}
@@ -0,0 +1,221 @@
+//! 多签名容器
+//!
+//! 同一份数据可以由多把不同算法的密钥分别签名，签名集中保存在一个容器中，
+//! 验证时按策略（全部、任意一个、k-of-n 门限）判断是否通过，适用于需要多位维护者共同签署的发布包
+
+use crate::framing::{put_bytes16, put_u16_len, Reader};
+use crate::signature::{scheme_verify, SignatureScheme};
+
+const MAGIC: &[u8; 4] = b"PIMS";
+const VERSION: u8 = 1;
+
+/// 多签名验证策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// 所有受信任的签名者都必须签名
+    All,
+    /// 至少一个受信任的签名者签名
+    Any,
+    /// 至少k个不同的受信任签名者签名
+    Threshold(usize),
+}
+
+/// 单个签名者的签名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureEntry {
+    pub scheme: SignatureScheme,
+    pub public_key: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
+/// 多签名容器
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiSignature {
+    entries: Vec<SignatureEntry>,
+}
+
+impl MultiSignature {
+    /// 构建空的多签名容器
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// 添加一个签名
+    ///
+    /// scheme: 签名方案
+    /// public_key: 签名者公钥
+    /// signature: 签名者对数据的签名
+    pub fn add(&mut self, scheme: SignatureScheme, public_key: &[u8], signature: &[u8]) {
+        self.entries.push(SignatureEntry {
+            scheme,
+            public_key: public_key.to_vec(),
+            signature: signature.to_vec(),
+        });
+    }
+
+    /// 获取所有签名
+    pub fn entries(&self) -> &[SignatureEntry] {
+        &self.entries
+    }
+
+    /// 统计在受信任签名者中，对数据签名有效的不同签名者个数
+    ///
+    /// payload: 已签名的数据
+    /// trusted: 受信任的签名者（签名方案，公钥），重复的签名者只计一次
+    pub fn count_valid(&self, payload: &[u8], trusted: &[(SignatureScheme, Vec<u8>)]) -> usize {
+        distinct(trusted)
+            .into_iter()
+            .filter(|(scheme, pk)| {
+                self.entries.iter().any(|e| {
+                    e.scheme == *scheme
+                        && e.public_key == *pk
+                        && scheme_verify(e.scheme, &e.public_key, payload, &e.signature)
+                })
+            })
+            .count()
+    }
+
+    /// 按策略验证多签名
+    ///
+    /// payload: 已签名的数据
+    /// trusted: 受信任的签名者（签名方案，公钥），不在其中的签名将被忽略
+    /// policy: 验证策略
+    pub fn verify(
+        &self,
+        payload: &[u8],
+        trusted: &[(SignatureScheme, Vec<u8>)],
+        policy: Policy,
+    ) -> bool {
+        if trusted.is_empty() {
+            return false;
+        }
+        let valid = self.count_valid(payload, trusted);
+        match policy {
+            Policy::All => valid == distinct(trusted).len(),
+            Policy::Any => valid >= 1,
+            Policy::Threshold(k) => k > 0 && valid >= k,
+        }
+    }
+
+    /// 序列化多签名容器
+    ///
+    /// 格式: "PIMS" | 版本(1字节) | 签名个数(u16) | { 方案(1字节) | 公钥长度(u16) | 公钥 | 签名长度(u16) | 签名 }*，
+    /// 签名个数或字段长度超过 u16 时返回错误
+    pub fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        put_u16_len(&mut out, "multisig", self.entries.len())?;
+        for e in &self.entries {
+            out.push(e.scheme.id());
+            put_bytes16(&mut out, "multisig", &e.public_key)?;
+            put_bytes16(&mut out, "multisig", &e.signature)?;
+        }
+        Ok(out)
+    }
+
+    /// 反序列化多签名容器
+    pub fn from_bytes(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new("multisig", data);
+        if reader.take(4)? != MAGIC {
+            return Err("multisig: bad magic".to_string());
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(format!("multisig: unsupported version {}", version));
+        }
+        let count = reader.u16()?;
+        let mut entries = Vec::with_capacity(count as usize);
+        for _ in 0..count {
+            let id = reader.u8()?;
+            let scheme = SignatureScheme::from_id(id)
+                .ok_or_else(|| format!("multisig: unknown signature scheme {}", id))?;
+            let public_key = reader.bytes16()?.to_vec();
+            let signature = reader.bytes16()?.to_vec();
+            entries.push(SignatureEntry {
+                scheme,
+                public_key,
+                signature,
+            });
+        }
+        reader.finish()?;
+        Ok(Self { entries })
+    }
+}
+
+fn distinct(trusted: &[(SignatureScheme, Vec<u8>)]) -> Vec<&(SignatureScheme, Vec<u8>)> {
+    let mut out: Vec<&(SignatureScheme, Vec<u8>)> = Vec::with_capacity(trusted.len());
+    for key in trusted {
+        if !out.contains(&key) {
+            out.push(key);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::ECDSASecp256k1;
+    use crypto::ed25519;
+    use hex::FromHex;
+
+    #[test]
+    fn test_multisig_policy() {
+        let payload = b"release-1.0.0.tar.gz";
+
+        let (sk1, pk1) = ed25519::keypair(&[1u8; 32]);
+        let (sk2, pk2) = ed25519::keypair(&[2u8; 32]);
+        let (_, pk3) = ed25519::keypair(&[3u8; 32]);
+        let secp_sk =
+            Vec::from_hex("16346fd1da236f810202853a3dc505d92b6b8597c15fd463e4e4494d8fc6a708")
+                .unwrap();
+        let secp_pk = Vec::from_hex("044fbd4994b6c1d5790000fa0fdfe3afb1f5f3d2a4e78c3daac4c9176d020c5ca85c9a683154c43d0ce4ea0a43c3863875e27c0ea4a087dd5ef6615d41fc9c5b40").unwrap();
+
+        let mut ms = MultiSignature::new();
+        ms.add(
+            SignatureScheme::Ed25519,
+            &pk1,
+            &ed25519::signature(payload, &sk1),
+        );
+        ms.add(
+            SignatureScheme::Ed25519,
+            &pk2,
+            &ed25519::signature(payload, &sk2),
+        );
+        let hash = ring::digest::digest(&ring::digest::SHA256, payload);
+        ms.add(
+            SignatureScheme::Secp256k1,
+            &secp_pk,
+            &ECDSASecp256k1::new().sign(hash.as_ref(), &secp_sk).unwrap(),
+        );
+
+        let trusted = vec![
+            (SignatureScheme::Ed25519, pk1.to_vec()),
+            (SignatureScheme::Ed25519, pk2.to_vec()),
+            (SignatureScheme::Ed25519, pk3.to_vec()),
+            (SignatureScheme::Secp256k1, secp_pk.clone()),
+        ];
+
+        let ms = MultiSignature::from_bytes(&ms.to_bytes().unwrap()).unwrap();
+        assert_eq!(ms.count_valid(payload, &trusted), 3);
+        assert!(ms.verify(payload, &trusted, Policy::Any));
+        assert!(ms.verify(payload, &trusted, Policy::Threshold(3)));
+        assert!(!ms.verify(payload, &trusted, Policy::Threshold(4)));
+        assert!(!ms.verify(payload, &trusted, Policy::All));
+        assert!(ms.verify(payload, &trusted[..2], Policy::All));
+        assert!(!ms.verify(b"tampered", &trusted, Policy::Any));
+
+        // 重复列出同一个签名者不能凑够门限
+        let duplicated = vec![trusted[0].clone(), trusted[0].clone(), trusted[2].clone()];
+        assert_eq!(ms.count_valid(payload, &duplicated), 1);
+        assert!(!ms.verify(payload, &duplicated, Policy::Threshold(2)));
+        assert!(ms.verify(payload, &duplicated[..2], Policy::All));
+
+        let mut long = MultiSignature::new();
+        long.add(SignatureScheme::RsaPkcs1Sha256, &[0u8; 0x10000], b"");
+        assert!(long.to_bytes().is_err());
+    }
+}
@@ -0,0 +1,595 @@
+//! MuSig2 n-of-n 多签名 (BIP-327)
+//!
+//! 多个签名者的公钥聚合为一个 x-only 公钥，两轮交互后得到普通的 BIP-340 Schnorr 签名，
+//! 链上与单签名无法区分。第一轮各方交换公开 nonce，第二轮各方对会话生成部分签名，
+//! 任何一方都可以验证部分签名并聚合。秘密 nonce 只能使用一次，Session::sign 消耗它，
+//! 序列化秘密 nonce 后重复使用会泄露私钥
+
+use secp256k1::curve::{Affine, Jacobian, Scalar, ECMULT_CONTEXT};
+use secp256k1::{PublicKey, PublicKeyFormat};
+
+use crate::ct::wipe;
+use crate::digest::tagged_hash;
+use crate::error::CryptoError;
+use crate::random::{OsRng, SecureRandom};
+use crate::schnorr::{mul_gen, point_bytes, reduce, secret_scalar};
+
+/// 秘密 nonce 的编码长度，k1 | k2 | 签名者公钥
+pub const SECNONCE_SIZE: usize = 97;
+
+/// 公开 nonce 和聚合 nonce 的编码长度
+pub const PUBNONCE_SIZE: usize = 66;
+
+/// 聚合公钥，以及按顺序应用过的调整值
+#[derive(Clone)]
+pub struct KeyAggContext {
+    pubkeys: Vec<[u8; 33]>,
+    tweaks: Vec<([u8; 32], bool)>,
+    q: Affine,
+    gacc: Scalar,
+    tacc: Scalar,
+}
+
+impl KeyAggContext {
+    /// 聚合公钥，公钥的顺序影响结果
+    ///
+    /// pubkeys: 各签名者的33字节压缩公钥
+    pub fn new(pubkeys: &[[u8; 33]]) -> Result<Self, CryptoError> {
+        if pubkeys.is_empty() {
+            return Err(CryptoError::InvalidInput(
+                "musig: no public keys".to_string(),
+            ));
+        }
+        let points = pubkeys
+            .iter()
+            .map(|pk| {
+                parse_point(pk)
+                    .ok_or_else(|| CryptoError::InvalidKey("musig: invalid public key".to_string()))
+            })
+            .collect::<Result<Vec<_>, CryptoError>>()?;
+        let mut q = Jacobian::default();
+        q.set_infinity();
+        for (pk, p) in pubkeys.iter().zip(points.iter()) {
+            q = q.add_var(&mul(p, &key_agg_coeff(pubkeys, pk)), None);
+        }
+        if q.is_infinity() {
+            return Err(CryptoError::OperationFailed(
+                "musig: aggregate key is infinity".to_string(),
+            ));
+        }
+        Ok(KeyAggContext {
+            pubkeys: pubkeys.to_vec(),
+            tweaks: Vec::new(),
+            q: to_affine(&q),
+            gacc: Scalar::from_int(1),
+            tacc: Scalar::default(),
+        })
+    }
+
+    /// 32字节 x-only 聚合公钥，即最终签名对应的 BIP-340 公钥
+    pub fn aggregate_public_key(&self) -> [u8; 32] {
+        point_bytes(&self.q).0
+    }
+
+    /// 调整聚合公钥，如 BIP-32 派生(普通调整)或 Taproot(x-only 调整)
+    ///
+    /// tweak: 32字节大端调整值，必须小于曲线的阶
+    /// xonly: 是否按 x-only 公钥调整
+    pub fn apply_tweak(&mut self, tweak: &[u8; 32], xonly: bool) -> Result<(), CryptoError> {
+        let t = reduce(tweak);
+        if t.b32() != *tweak {
+            return Err(CryptoError::InvalidInput(
+                "musig: tweak out of range".to_string(),
+            ));
+        }
+        let g = if xonly && !has_even_y(&self.q) {
+            -Scalar::from_int(1)
+        } else {
+            Scalar::from_int(1)
+        };
+        let mut q = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(&mut q, &Jacobian::from_ge(&self.q), &g, &t);
+        if q.is_infinity() {
+            return Err(CryptoError::OperationFailed(
+                "musig: tweaked key is infinity".to_string(),
+            ));
+        }
+        self.q = to_affine(&q);
+        self.gacc = &g * &self.gacc;
+        self.tacc = t + &g * &self.tacc;
+        self.tweaks.push((*tweak, xonly));
+        Ok(())
+    }
+}
+
+/// 秘密 nonce，离开作用域时擦除
+pub struct SecNonce([u8; SECNONCE_SIZE]);
+
+impl SecNonce {
+    /// 编码，只用于在两轮之间持久化，同一个秘密 nonce 不能签名两次
+    pub fn to_bytes(&self) -> [u8; SECNONCE_SIZE] {
+        self.0
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+        if data.len() != SECNONCE_SIZE {
+            return Err(CryptoError::InvalidInput(
+                "musig: secnonce must be 97 bytes".to_string(),
+            ));
+        }
+        let mut out = [0u8; SECNONCE_SIZE];
+        out.copy_from_slice(data);
+        Ok(SecNonce(out))
+    }
+}
+
+impl Drop for SecNonce {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+/// 公开 nonce 或聚合 nonce，两个33字节压缩点，聚合 nonce 中的无穷远点编码为33字节0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PubNonce(pub [u8; PUBNONCE_SIZE]);
+
+/// 生成一对 nonce，第一轮把返回的 PubNonce 发给其他签名者
+///
+/// sk: 签名者私钥，可选，提供时即使系统随机数有缺陷 nonce 也不会重复
+/// pk: 签名者的33字节压缩公钥
+/// aggpk: 聚合公钥，可选
+/// msg: 待签名的消息，可选
+/// extra: 额外输入，可以为空
+pub fn nonce_gen(
+    sk: Option<&[u8]>,
+    pk: &[u8; 33],
+    aggpk: Option<&[u8; 32]>,
+    msg: Option<&[u8]>,
+    extra: &[u8],
+) -> Result<(SecNonce, PubNonce), CryptoError> {
+    nonce_gen_with_rng(sk, pk, aggpk, msg, extra, &OsRng)
+}
+
+/// 使用指定的随机数生成器生成 nonce，参数同 nonce_gen
+pub fn nonce_gen_with_rng(
+    sk: Option<&[u8]>,
+    pk: &[u8; 33],
+    aggpk: Option<&[u8; 32]>,
+    msg: Option<&[u8]>,
+    extra: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<(SecNonce, PubNonce), CryptoError> {
+    let mut rand = [0u8; 32];
+    rng.fill(&mut rand);
+    if let Some(sk) = sk {
+        if sk.len() != 32 {
+            return Err(CryptoError::InvalidKey(
+                "musig: secret key must be 32 bytes".to_string(),
+            ));
+        }
+        let aux = tagged_hash(b"MuSig/aux", &rand);
+        for ((r, a), s) in rand.iter_mut().zip(aux.iter()).zip(sk.iter()) {
+            *r = a ^ s;
+        }
+    }
+    let aggpk = aggpk.map(|a| &a[..]).unwrap_or(&[]);
+    let mut data = rand.to_vec();
+    data.push(33);
+    data.extend_from_slice(pk);
+    data.push(aggpk.len() as u8);
+    data.extend_from_slice(aggpk);
+    match msg {
+        Some(msg) => {
+            data.push(1);
+            data.extend_from_slice(&(msg.len() as u64).to_be_bytes());
+            data.extend_from_slice(msg);
+        }
+        None => data.push(0),
+    }
+    data.extend_from_slice(&(extra.len() as u32).to_be_bytes());
+    data.extend_from_slice(extra);
+    let mut secnonce = [0u8; SECNONCE_SIZE];
+    let mut pubnonce = [0u8; PUBNONCE_SIZE];
+    for i in 0..2 {
+        data.push(i as u8);
+        let k = reduce(&tagged_hash(b"MuSig/nonce", &data));
+        data.pop();
+        if k.is_zero() {
+            return Err(CryptoError::OperationFailed(
+                "musig: nonce is zero".to_string(),
+            ));
+        }
+        secnonce[32 * i..32 * (i + 1)].copy_from_slice(&k.b32());
+        pubnonce[33 * i..33 * (i + 1)].copy_from_slice(&cbytes(&mul_gen(&k)));
+    }
+    secnonce[64..].copy_from_slice(pk);
+    wipe(&mut rand);
+    wipe(&mut data);
+    Ok((SecNonce(secnonce), PubNonce(pubnonce)))
+}
+
+/// 聚合所有签名者的公开 nonce
+pub fn nonce_agg(pubnonces: &[PubNonce]) -> Result<PubNonce, CryptoError> {
+    let mut out = [0u8; PUBNONCE_SIZE];
+    for j in 0..2 {
+        let mut r = Jacobian::default();
+        r.set_infinity();
+        for nonce in pubnonces {
+            let p = parse_point(&nonce.0[33 * j..33 * (j + 1)]).ok_or_else(|| {
+                CryptoError::InvalidInput("musig: invalid public nonce".to_string())
+            })?;
+            r = r.add_ge(&p);
+        }
+        if !r.is_infinity() {
+            out[33 * j..33 * (j + 1)].copy_from_slice(&cbytes(&to_affine(&r)));
+        }
+    }
+    Ok(PubNonce(out))
+}
+
+/// 签名会话：聚合公钥、聚合 nonce 和消息，所有签名者必须使用相同的会话
+#[derive(Clone)]
+pub struct Session {
+    key_ctx: KeyAggContext,
+    aggnonce: PubNonce,
+    msg: Vec<u8>,
+    // 以下由前三项计算
+    b: Scalar,
+    r: Affine,
+    e: Scalar,
+}
+
+impl Session {
+    /// 建立会话
+    ///
+    /// key_ctx: 聚合公钥，包括调整值
+    /// aggnonce: nonce_agg 的结果
+    /// msg: 待签名的消息
+    pub fn new(
+        key_ctx: &KeyAggContext,
+        aggnonce: &PubNonce,
+        msg: &[u8],
+    ) -> Result<Self, CryptoError> {
+        let qx = key_ctx.aggregate_public_key();
+        let b = reduce(&tagged_hash(
+            b"MuSig/noncecoef",
+            &[&aggnonce.0[..], &qx, msg].concat(),
+        ));
+        let r1 = parse_point_ext(&aggnonce.0[..33])?;
+        let r2 = parse_point_ext(&aggnonce.0[33..])?;
+        let mut r = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(&mut r, &r2, &b, &Scalar::default());
+        let r = r.add_var(&r1, None);
+        // 极小概率下 R 为无穷远点，按规范用 G 代替
+        let r = if r.is_infinity() {
+            mul_gen(&Scalar::from_int(1))
+        } else {
+            to_affine(&r)
+        };
+        let rx = point_bytes(&r).0;
+        let e = reduce(&tagged_hash(
+            b"BIP0340/challenge",
+            &[&rx[..], &qx, msg].concat(),
+        ));
+        Ok(Session {
+            key_ctx: key_ctx.clone(),
+            aggnonce: *aggnonce,
+            msg: msg.to_vec(),
+            b,
+            r,
+            e,
+        })
+    }
+
+    /// 生成部分签名，消耗秘密 nonce
+    ///
+    /// secnonce: 本方第一轮生成的秘密 nonce
+    /// sk: 本方32字节私钥
+    pub fn sign(&self, secnonce: SecNonce, sk: &[u8]) -> Result<[u8; 32], CryptoError> {
+        let k1 = reduce(&to_array(&secnonce.0[..32]));
+        let k2 = reduce(&to_array(&secnonce.0[32..64]));
+        if k1.is_zero() || k2.is_zero() {
+            return Err(CryptoError::InvalidInput(
+                "musig: invalid secnonce".to_string(),
+            ));
+        }
+        let (k1, k2) = if has_even_y(&self.r) {
+            (k1, k2)
+        } else {
+            (-k1, -k2)
+        };
+        let d0 = secret_scalar(sk)
+            .map_err(|_| CryptoError::InvalidKey("musig: invalid secret key".to_string()))?;
+        let pk = cbytes(&mul_gen(&d0));
+        if pk[..] != secnonce.0[64..] {
+            return Err(CryptoError::InvalidInput(
+                "musig: secnonce does not belong to this key".to_string(),
+            ));
+        }
+        if !self.key_ctx.pubkeys.contains(&pk) {
+            return Err(CryptoError::InvalidKey(
+                "musig: key is not part of the aggregate".to_string(),
+            ));
+        }
+        let a = key_agg_coeff(&self.key_ctx.pubkeys, &pk);
+        let d = &(self.g() * d0) * &self.key_ctx.gacc;
+        let s = k1 + &self.b * &k2 + &(&self.e * &a) * &d;
+        Ok(s.b32())
+    }
+
+    /// 验证其他签名者的部分签名
+    ///
+    /// psig: 部分签名
+    /// pubnonce: 该签名者第一轮发送的公开 nonce
+    /// pk: 该签名者的压缩公钥
+    pub fn partial_verify(&self, psig: &[u8; 32], pubnonce: &PubNonce, pk: &[u8; 33]) -> bool {
+        let s = reduce(psig);
+        if s.b32() != *psig || !self.key_ctx.pubkeys.contains(pk) {
+            return false;
+        }
+        let (r1, r2, p) = match (
+            parse_point(&pubnonce.0[..33]),
+            parse_point(&pubnonce.0[33..]),
+            parse_point(pk),
+        ) {
+            (Some(r1), Some(r2), Some(p)) => (r1, r2, p),
+            _ => return false,
+        };
+        // R_i = R_i1 + b·R_i2，聚合 R 的 y 为奇数时取负
+        let mut re = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(
+            &mut re,
+            &Jacobian::from_ge(&r2),
+            &self.b,
+            &Scalar::default(),
+        );
+        let mut re = re.add_ge(&r1);
+        if !has_even_y(&self.r) {
+            re = re.neg();
+        }
+        // s·G - e·a·g·gacc·P 应当等于 R_i
+        let coeff = &(&self.e * &key_agg_coeff(&self.key_ctx.pubkeys, pk))
+            * &(&self.g() * &self.key_ctx.gacc);
+        let mut check = Jacobian::default();
+        ECMULT_CONTEXT.ecmult(&mut check, &Jacobian::from_ge(&p), &-coeff, &s);
+        let diff = check.add_var(&re.neg(), None);
+        diff.is_infinity()
+    }
+
+    /// 聚合部分签名，得到64字节 BIP-340 签名
+    pub fn aggregate(&self, psigs: &[[u8; 32]]) -> Result<[u8; 64], CryptoError> {
+        let mut s = &(&self.e * &self.g()) * &self.key_ctx.tacc;
+        for psig in psigs {
+            let si = reduce(psig);
+            if si.b32() != *psig {
+                return Err(CryptoError::InvalidInput(
+                    "musig: partial signature out of range".to_string(),
+                ));
+            }
+            s += si;
+        }
+        let mut sig = [0u8; 64];
+        sig[..32].copy_from_slice(&point_bytes(&self.r).0);
+        sig[32..].copy_from_slice(&s.b32());
+        Ok(sig)
+    }
+
+    /// 编码会话，格式为 聚合nonce(66) | 公钥数(4) | 公钥 | 调整数(4) | (调整值(32) | 是否 x-only(1)) | 消息
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ctx = &self.key_ctx;
+        let mut out = self.aggnonce.0.to_vec();
+        out.extend_from_slice(&(ctx.pubkeys.len() as u32).to_be_bytes());
+        for pk in &ctx.pubkeys {
+            out.extend_from_slice(pk);
+        }
+        out.extend_from_slice(&(ctx.tweaks.len() as u32).to_be_bytes());
+        for (tweak, xonly) in &ctx.tweaks {
+            out.extend_from_slice(tweak);
+            out.push(*xonly as u8);
+        }
+        out.extend_from_slice(&self.msg);
+        out
+    }
+
+    /// 解码会话，重新计算聚合公钥和会话参数
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+        let mut rest = data;
+        let mut take = |len: usize| {
+            if rest.len() < len {
+                return Err(CryptoError::InvalidInput(
+                    "musig: truncated session".to_string(),
+                ));
+            }
+            let (head, tail) = rest.split_at(len);
+            rest = tail;
+            Ok(head)
+        };
+        let mut aggnonce = [0u8; PUBNONCE_SIZE];
+        aggnonce.copy_from_slice(take(PUBNONCE_SIZE)?);
+        let n = u32::from_be_bytes(to_array4(take(4)?)) as usize;
+        let pubkeys = (0..n)
+            .map(|_| {
+                let mut pk = [0u8; 33];
+                pk.copy_from_slice(take(33)?);
+                Ok(pk)
+            })
+            .collect::<Result<Vec<_>, CryptoError>>()?;
+        let mut key_ctx = KeyAggContext::new(&pubkeys)?;
+        let tweaks = u32::from_be_bytes(to_array4(take(4)?));
+        for _ in 0..tweaks {
+            let tweak = to_array(take(32)?);
+            let xonly = match take(1)?[0] {
+                0 => false,
+                1 => true,
+                _ => {
+                    return Err(CryptoError::InvalidInput(
+                        "musig: invalid tweak flag".to_string(),
+                    ))
+                }
+            };
+            key_ctx.apply_tweak(&tweak, xonly)?;
+        }
+        let msg = rest;
+        Session::new(&key_ctx, &PubNonce(aggnonce), msg)
+    }
+
+    // 聚合公钥 y 为奇数时为 -1
+    fn g(&self) -> Scalar {
+        if has_even_y(&self.key_ctx.q) {
+            Scalar::from_int(1)
+        } else {
+            -Scalar::from_int(1)
+        }
+    }
+}
+
+// KeyAgg 系数，列表中第二个不同于第一个的公钥系数为1
+fn key_agg_coeff(pubkeys: &[[u8; 33]], pk: &[u8; 33]) -> Scalar {
+    let second = pubkeys.iter().find(|p| **p != pubkeys[0]);
+    if second == Some(pk) {
+        return Scalar::from_int(1);
+    }
+    let l = tagged_hash(b"KeyAgg list", &pubkeys.concat());
+    reduce(&tagged_hash(b"KeyAgg coefficient", &[&l[..], pk].concat()))
+}
+
+fn parse_point(data: &[u8]) -> Option<Affine> {
+    PublicKey::parse_slice(data, Some(PublicKeyFormat::Compressed))
+        .ok()
+        .map(Into::into)
+}
+
+// 33字节0表示无穷远点
+fn parse_point_ext(data: &[u8]) -> Result<Jacobian, CryptoError> {
+    if data.iter().all(|b| *b == 0) {
+        let mut r = Jacobian::default();
+        r.set_infinity();
+        return Ok(r);
+    }
+    parse_point(data)
+        .map(|p| Jacobian::from_ge(&p))
+        .ok_or_else(|| CryptoError::InvalidInput("musig: invalid aggregate nonce".to_string()))
+}
+
+fn mul(p: &Affine, k: &Scalar) -> Jacobian {
+    let mut r = Jacobian::default();
+    ECMULT_CONTEXT.ecmult(&mut r, &Jacobian::from_ge(p), k, &Scalar::default());
+    r
+}
+
+fn to_affine(p: &Jacobian) -> Affine {
+    let mut a = Affine::from_gej(p);
+    a.x.normalize();
+    a.y.normalize();
+    a
+}
+
+fn has_even_y(p: &Affine) -> bool {
+    !point_bytes(p).1
+}
+
+fn cbytes(p: &Affine) -> [u8; 33] {
+    let (x, odd) = point_bytes(p);
+    let mut out = [0u8; 33];
+    out[0] = if odd { 0x03 } else { 0x02 };
+    out[1..].copy_from_slice(&x);
+    out
+}
+
+fn to_array(bytes: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(bytes);
+    out
+}
+
+fn to_array4(bytes: &[u8]) -> [u8; 4] {
+    let mut out = [0u8; 4];
+    out.copy_from_slice(bytes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schnorr;
+    use hex::FromHex;
+
+    fn pk33(s: &str) -> [u8; 33] {
+        let mut out = [0u8; 33];
+        out.copy_from_slice(&Vec::from_hex(s).unwrap());
+        out
+    }
+
+    #[test]
+    fn test_musig() {
+        // BIP-327 key_agg_vectors.json
+        let x = [
+            pk33("02F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9"),
+            pk33("03DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659"),
+            pk33("023590A94E768F8E1815C2F24B4D80A8E3149316C3518CE7B7AD338368D038CA66"),
+        ];
+        for (keys, expected) in [
+            (
+                vec![x[0], x[1], x[2]],
+                "90539EEDE565F5D054F32CC0C220126889ED1E5D193BAF15AEF344FE59D4610C",
+            ),
+            (
+                vec![x[2], x[1], x[0]],
+                "6204DE8B083426DC6EAF9502D27024D53FC826BF7D2012148A0575435DF54B2B",
+            ),
+            (
+                vec![x[0], x[0], x[0]],
+                "B436E3BAD62B8CD409969A224731C193D051162D8C5AE8B109306127DA3AA935",
+            ),
+            (
+                vec![x[0], x[0], x[1], x[1]],
+                "69BC22BFA5D106306E48A20679DE1D7389386124D07571D0D872686028C26A3E",
+            ),
+        ] {
+            let ctx = KeyAggContext::new(&keys).unwrap();
+            assert_eq!(
+                ctx.aggregate_public_key().to_vec(),
+                Vec::from_hex(expected).unwrap()
+            );
+        }
+
+        // 三方签名，聚合密钥先做一次 x-only 调整
+        let sks: Vec<[u8; 32]> = (1..=3u8).map(|i| [i; 32]).collect();
+        let pks: Vec<[u8; 33]> = sks
+            .iter()
+            .map(|sk| cbytes(&mul_gen(&secret_scalar(sk).unwrap())))
+            .collect();
+        let mut ctx = KeyAggContext::new(&pks).unwrap();
+        ctx.apply_tweak(&[7u8; 32], true).unwrap();
+        let aggpk = ctx.aggregate_public_key();
+        let msg = b"musig2 message";
+        let nonces: Vec<(SecNonce, PubNonce)> = sks
+            .iter()
+            .zip(pks.iter())
+            .map(|(sk, pk)| nonce_gen(Some(sk), pk, Some(&aggpk), Some(msg), b"").unwrap())
+            .collect();
+        let pubnonces: Vec<PubNonce> = nonces.iter().map(|n| n.1).collect();
+        let aggnonce = nonce_agg(&pubnonces).unwrap();
+        let session = Session::new(&ctx, &aggnonce, msg).unwrap();
+        // 会话可以序列化后在另一处恢复
+        let session = Session::from_bytes(&session.to_bytes()).unwrap();
+        let mut psigs = Vec::new();
+        for (i, (secnonce, _)) in nonces.into_iter().enumerate() {
+            let secnonce = SecNonce::from_bytes(&secnonce.to_bytes()).unwrap();
+            let psig = session.sign(secnonce, &sks[i]).unwrap();
+            assert!(session.partial_verify(&psig, &pubnonces[i], &pks[i]));
+            assert!(!session.partial_verify(&psig, &pubnonces[(i + 1) % 3], &pks[i]));
+            psigs.push(psig);
+        }
+        let sig = session.aggregate(&psigs).unwrap();
+        assert!(schnorr::verify(msg, &aggpk, &sig));
+        assert!(!schnorr::verify(b"other", &aggpk, &sig));
+        let partial = session.aggregate(&psigs[..2]).unwrap();
+        assert!(!schnorr::verify(msg, &aggpk, &partial));
+
+        let (secnonce, _) = nonce_gen(None, &pks[0], None, None, b"").unwrap();
+        assert!(session.sign(secnonce, &sks[1]).is_err());
+        assert!(KeyAggContext::new(&[]).is_err());
+        assert!(Session::from_bytes(&[0u8; 10]).is_err());
+    }
+}
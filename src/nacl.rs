@@ -0,0 +1,247 @@
+//! 与 libsodium 兼容的 crypto_box 和 crypto_secretbox
+//!
+//! secretbox 使用 XSalsa20-Poly1305，box 先用 X25519 协商共享密钥，经 HSalsa20 派生后同样使用
+//! XSalsa20-Poly1305。输出格式与 libsodium 的 *_easy 接口相同，即 16字节tag | 密文。
+//! 同一密钥下的 nonce 绝不能重复，可以用 random::genSecureRandBytes 随机生成24字节 nonce。
+//! box_seal 为匿名发送方的 crypto_box_seal，输出 临时公钥 | 16字节tag | 密文
+
+use crypto::salsa20::{hsalsa20, Salsa20};
+use crypto::symmetriccipher::SynchronousStreamCipher;
+
+use crate::ct::wipe;
+use crate::digest::blake2::blake2b;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::mac::{mac, Mac, Poly1305};
+use crate::random::{OsRng, SecureRandom};
+use crate::secret::SecretBytes;
+use crate::x25519;
+
+/// 密钥长度
+pub const KEY_SIZE: usize = 32;
+/// nonce 长度
+pub const NONCE_SIZE: usize = 24;
+/// 认证tag长度，也是密文比明文多出的长度
+pub const TAG_SIZE: usize = 16;
+/// box_seal 的密文比明文多出的长度，临时公钥 | tag
+pub const SEAL_BYTES: usize = KEY_SIZE + TAG_SIZE;
+
+/// crypto_secretbox_easy，对称加密并认证
+///
+/// key: 32字节密钥
+/// nonce: 24字节nonce
+/// plaintext: 明文
+pub fn secretbox_seal(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE], plaintext: &[u8]) -> Vec<u8> {
+    let (mut cipher, auth_key) = xsalsa20(key, nonce);
+    let mut out = vec![0u8; TAG_SIZE + plaintext.len()];
+    cipher.process(plaintext, &mut out[TAG_SIZE..]);
+    let tag = mac::<Poly1305>(&auth_key, &out[TAG_SIZE..]).expect("nacl: 32 byte poly1305 key");
+    out[..TAG_SIZE].copy_from_slice(&tag);
+    out
+}
+
+/// crypto_secretbox_open_easy，验证并解密
+///
+/// key: 32字节密钥
+/// nonce: 加密时使用的nonce
+/// ciphertext: secretbox_seal 的输出
+pub fn secretbox_open(
+    key: &[u8; KEY_SIZE],
+    nonce: &[u8; NONCE_SIZE],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    if ciphertext.len() < TAG_SIZE {
+        return Err(CryptoError::InvalidInput(
+            "nacl: ciphertext too short".to_string(),
+        ));
+    }
+    let (tag, ct) = ciphertext.split_at(TAG_SIZE);
+    let (mut cipher, auth_key) = xsalsa20(key, nonce);
+    let mut poly = Poly1305::new_with_key(&auth_key).map_err(CryptoError::InvalidKey)?;
+    poly.update(ct);
+    if !poly.verify(tag) {
+        return Err(CryptoError::Verify(VerifyFailure::DigestMismatch));
+    }
+    let mut out = vec![0u8; ct.len()];
+    cipher.process(ct, &mut out);
+    Ok(out)
+}
+
+/// crypto_box_easy，加密并认证给接收方
+///
+/// nonce: 24字节nonce，同一对密钥之间不能重复
+/// their_public_key: 接收方 X25519 公钥
+/// my_secret_key: 发送方 X25519 私钥
+/// plaintext: 明文
+pub fn box_easy(
+    nonce: &[u8; NONCE_SIZE],
+    their_public_key: &[u8; KEY_SIZE],
+    my_secret_key: &[u8; KEY_SIZE],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let key = box_beforenm(their_public_key, my_secret_key)?;
+    Ok(secretbox_seal(&key, nonce, plaintext))
+}
+
+/// crypto_box_open_easy，验证并解密
+///
+/// nonce: 加密时使用的nonce
+/// their_public_key: 发送方 X25519 公钥
+/// my_secret_key: 接收方 X25519 私钥
+/// ciphertext: box_easy 的输出
+pub fn box_open_easy(
+    nonce: &[u8; NONCE_SIZE],
+    their_public_key: &[u8; KEY_SIZE],
+    my_secret_key: &[u8; KEY_SIZE],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let key = box_beforenm(their_public_key, my_secret_key)?;
+    secretbox_open(&key, nonce, ciphertext)
+}
+
+/// crypto_box_seal，匿名加密给接收方
+///
+/// 每次生成临时密钥对，nonce = BLAKE2b-24(临时公钥 | 接收方公钥)，接收方无法得知发送方身份。
+/// 只保证机密性，不认证发送方
+/// their_public_key: 接收方 X25519 公钥
+/// plaintext: 明文
+pub fn box_seal(
+    their_public_key: &[u8; KEY_SIZE],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    box_seal_with_rng(their_public_key, plaintext, &OsRng)
+}
+
+/// 使用指定的随机数生成器生成临时密钥对，参数同 box_seal
+pub fn box_seal_with_rng(
+    their_public_key: &[u8; KEY_SIZE],
+    plaintext: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<Vec<u8>, CryptoError> {
+    let (mut ephemeral_sk, ephemeral_pk) = x25519::generate_keypair_with_rng(rng);
+    let nonce = seal_nonce(&ephemeral_pk, their_public_key);
+    let sealed = box_easy(&nonce, their_public_key, &ephemeral_sk, plaintext);
+    wipe(&mut ephemeral_sk);
+    Ok([&ephemeral_pk[..], &sealed?].concat())
+}
+
+/// crypto_box_seal_open，解密 box_seal 的输出
+///
+/// my_public_key: 接收方 X25519 公钥
+/// my_secret_key: 接收方 X25519 私钥
+/// ciphertext: box_seal 的输出
+pub fn box_seal_open(
+    my_public_key: &[u8; KEY_SIZE],
+    my_secret_key: &[u8; KEY_SIZE],
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    if ciphertext.len() < SEAL_BYTES {
+        return Err(CryptoError::InvalidInput(
+            "nacl: ciphertext too short".to_string(),
+        ));
+    }
+    let mut ephemeral_pk = [0u8; KEY_SIZE];
+    ephemeral_pk.copy_from_slice(&ciphertext[..KEY_SIZE]);
+    let nonce = seal_nonce(&ephemeral_pk, my_public_key);
+    box_open_easy(
+        &nonce,
+        &ephemeral_pk,
+        my_secret_key,
+        &ciphertext[KEY_SIZE..],
+    )
+}
+
+fn seal_nonce(
+    ephemeral_pk: &[u8; KEY_SIZE],
+    their_public_key: &[u8; KEY_SIZE],
+) -> [u8; NONCE_SIZE] {
+    let h = blake2b(
+        NONCE_SIZE,
+        b"",
+        &[&ephemeral_pk[..], their_public_key].concat(),
+    )
+    .expect("nacl: 24 byte blake2b");
+    let mut nonce = [0u8; NONCE_SIZE];
+    nonce.copy_from_slice(&h);
+    nonce
+}
+
+/// crypto_box_beforenm，HSalsa20(X25519共享密钥, 0^16)
+fn box_beforenm(
+    their_public_key: &[u8; KEY_SIZE],
+    my_secret_key: &[u8; KEY_SIZE],
+) -> Result<[u8; KEY_SIZE], CryptoError> {
+    let shared = SecretBytes::from(
+        &x25519::scalarmult(my_secret_key, their_public_key).map_err(CryptoError::InvalidKey)?[..],
+    );
+    let mut key = [0u8; KEY_SIZE];
+    hsalsa20(&shared, &[0u8; 16], &mut key);
+    Ok(key)
+}
+
+/// 返回加密用的 XSalsa20 和 Poly1305 一次性密钥，后者为密钥流的前32字节
+fn xsalsa20(key: &[u8; KEY_SIZE], nonce: &[u8; NONCE_SIZE]) -> (Salsa20, SecretBytes) {
+    let mut cipher = Salsa20::new_xsalsa20(key, nonce);
+    let mut auth_key = SecretBytes::zeroed(32);
+    cipher.process(&[0u8; 32], auth_key.as_mut_bytes());
+    (cipher, auth_key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_nacl() {
+        // test vector from: Bernstein, "Cryptography in NaCl", box.c / secretbox.c
+        let alice_sk =
+            Vec::from_hex("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a")
+                .unwrap();
+        let bob_sk =
+            Vec::from_hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb")
+                .unwrap();
+        let mut a = [0u8; KEY_SIZE];
+        a.copy_from_slice(&alice_sk);
+        let mut b = [0u8; KEY_SIZE];
+        b.copy_from_slice(&bob_sk);
+        let alice_pk = x25519::scalarmult_base(&a);
+        let bob_pk = x25519::scalarmult_base(&b);
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce.copy_from_slice(
+            &Vec::from_hex("69696ee955b62b73cd62bda875fc73d68219e0036b7a0b37").unwrap(),
+        );
+        let m = Vec::from_hex("be075fc53c81f2d5cf141316ebeb0c7b5228c52a4c62cbd44b66849b64244ffce5ecbaaf33bd751a1ac728d45e6c61296cdc3c01233561f41db66cce314adb310e3be8250c46f06dceea3a7fa1348057e2f6556ad6b1318a024a838f21af1fde048977eb48f59ffd4924ca1c60902e52f0a089bc76897040e082f937763848645e0705").unwrap();
+        let c = Vec::from_hex("f3ffc7703f9400e52a7dfb4b3d3305d98e993b9f48681273c29650ba32fc76ce48332ea7164d96a4476fb8c531a1186ac0dfc17c98dce87b4da7f011ec48c97271d2c20f9b928fe2270d6fb863d51738b48eeee314a7cc8ab932164548e526ae90224368517acfeabd6bb3732bc0e9da99832b61ca01b6de56244a9e88d5f9b37973f622a43d14a6599b1f654cb45a74e355a5").unwrap();
+
+        let key = box_beforenm(&bob_pk, &a).unwrap();
+        assert_eq!(
+            key.to_vec(),
+            Vec::from_hex("1b27556473e985d462cd51197a9a46c76009549eac6474f206c4ee0844f68389")
+                .unwrap()
+        );
+        assert_eq!(secretbox_seal(&key, &nonce, &m), c);
+        assert_eq!(box_easy(&nonce, &bob_pk, &a, &m).unwrap(), c);
+        assert_eq!(box_open_easy(&nonce, &alice_pk, &b, &c).unwrap(), m);
+
+        let mut tampered = c.clone();
+        tampered[20] ^= 1;
+        assert!(box_open_easy(&nonce, &alice_pk, &b, &tampered).is_err());
+        assert!(secretbox_open(&key, &nonce, &c[..TAG_SIZE - 1]).is_err());
+        assert_eq!(
+            secretbox_open(&key, &nonce, &secretbox_seal(&key, &nonce, b"")).unwrap(),
+            b""
+        );
+
+        // libsodium crypto_box_seal 生成
+        let sealed = Vec::from_hex("1684b5d5aed51e2e8a04713b6b647ba80b76326d0a335203a9b5b89720d8345918b6859af27edd26824747e7d11804acf0d1373bd6e10e1e088e17792c5843584a216d7e7c19a7c93d").unwrap();
+        assert_eq!(
+            box_seal_open(&bob_pk, &b, &sealed).unwrap(),
+            b"sealed box from libsodium"
+        );
+        let sealed = box_seal(&bob_pk, &m).unwrap();
+        assert_eq!(sealed.len(), m.len() + SEAL_BYTES);
+        assert_eq!(box_seal_open(&bob_pk, &b, &sealed).unwrap(), m);
+        assert!(box_seal_open(&alice_pk, &a, &sealed).is_err());
+        assert!(box_seal_open(&bob_pk, &b, &sealed[..SEAL_BYTES - 1]).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! AEAD nonce 管理
+//!
+//! 手工维护 nonce 是使用底层AEAD接口时最常见的错误，这里提供三种辅助工具：
+//! - CounterNonceSequence：固定前缀加单调递增计数器，前缀由调用方持久化，重启后换用新前缀或从保存的位置继续
+//! - RandomNonce：随机 nonce，按生日界限制同一密钥下生成的数量
+//! - NonceReuseDetector：记录外部传入的 nonce，拒绝重复使用
+
+use std::collections::HashSet;
+
+use ring::aead::{Nonce, NonceSequence};
+use ring::error::Unspecified;
+
+use crate::random::genSecureRandBytes;
+
+/// nonce长度，96位
+pub const NONCE_SIZE: usize = 12;
+
+/// 计数器 nonce 的前缀长度
+pub const PREFIX_SIZE: usize = 4;
+
+/// 随机 nonce 的默认数量上限，NIST SP 800-38D 规定同一密钥下随机96位 nonce 不超过2^32个
+pub const RANDOM_NONCE_LIMIT: u64 = 1 << 32;
+
+/// 计数器 nonce 序列
+///
+/// nonce = 4字节前缀 | 8字节大端计数器，计数器用尽后拒绝继续生成
+#[derive(Debug)]
+pub struct CounterNonceSequence {
+    prefix: [u8; PREFIX_SIZE],
+    counter: u64,
+    exhausted: bool,
+}
+
+impl CounterNonceSequence {
+    /// 使用前缀从0开始计数
+    ///
+    /// prefix: 同一密钥下每个序列必须使用不同的前缀
+    pub fn new(prefix: [u8; PREFIX_SIZE]) -> Self {
+        CounterNonceSequence::resume(prefix, 0)
+    }
+
+    /// 从持久化的位置继续计数
+    ///
+    /// prefix: 序列前缀
+    /// position: 之前保存的 position() 值
+    pub fn resume(prefix: [u8; PREFIX_SIZE], position: u64) -> Self {
+        CounterNonceSequence {
+            prefix,
+            counter: position,
+            exhausted: false,
+        }
+    }
+
+    /// 序列前缀
+    pub fn prefix(&self) -> [u8; PREFIX_SIZE] {
+        self.prefix
+    }
+
+    /// 下一个计数器值，需要在使用 nonce 之前持久化
+    pub fn position(&self) -> u64 {
+        self.counter
+    }
+
+    /// 生成下一个 nonce
+    pub fn next_nonce(&mut self) -> Result<[u8; NONCE_SIZE], String> {
+        if self.exhausted {
+            return Err("nonce: counter exhausted".to_string());
+        }
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce[..PREFIX_SIZE].copy_from_slice(&self.prefix);
+        nonce[PREFIX_SIZE..].copy_from_slice(&self.counter.to_be_bytes());
+        match self.counter.checked_add(1) {
+            Some(next) => self.counter = next,
+            None => self.exhausted = true,
+        }
+        Ok(nonce)
+    }
+}
+
+impl NonceSequence for CounterNonceSequence {
+    fn advance(&mut self) -> Result<Nonce, Unspecified> {
+        let nonce = self.next_nonce().map_err(|_| Unspecified)?;
+        Ok(Nonce::assume_unique_for_key(nonce))
+    }
+}
+
+/// 随机 nonce 生成器
+///
+/// 统计同一密钥下已生成的数量，超过上限后拒绝继续生成，此时应当更换密钥
+#[derive(Debug)]
+pub struct RandomNonce {
+    count: u64,
+    limit: u64,
+}
+
+impl RandomNonce {
+    /// 使用默认上限 RANDOM_NONCE_LIMIT
+    pub fn new() -> Self {
+        RandomNonce::with_limit(RANDOM_NONCE_LIMIT)
+    }
+
+    /// 使用指定的上限，用于需要更低碰撞概率的场景
+    pub fn with_limit(limit: u64) -> Self {
+        RandomNonce { count: 0, limit }
+    }
+
+    /// 已生成的数量
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// 还能生成的数量
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.count)
+    }
+
+    /// 生成下一个随机 nonce
+    pub fn next_nonce(&mut self) -> Result<[u8; NONCE_SIZE], String> {
+        if self.count >= self.limit {
+            return Err("nonce: random nonce limit reached, rotate the key".to_string());
+        }
+        self.count += 1;
+        let mut nonce = [0u8; NONCE_SIZE];
+        nonce.copy_from_slice(&genSecureRandBytes(NONCE_SIZE));
+        Ok(nonce)
+    }
+}
+
+impl NonceSequence for RandomNonce {
+    fn advance(&mut self) -> Result<Nonce, Unspecified> {
+        let nonce = self.next_nonce().map_err(|_| Unspecified)?;
+        Ok(Nonce::assume_unique_for_key(nonce))
+    }
+}
+
+/// nonce 重用检测
+///
+/// 记录同一密钥下已使用过的 nonce，适用于 nonce 由外部传入的场景，内存占用随记录数增长
+#[derive(Debug, Default)]
+pub struct NonceReuseDetector {
+    seen: HashSet<Vec<u8>>,
+}
+
+impl NonceReuseDetector {
+    pub fn new() -> Self {
+        NonceReuseDetector::default()
+    }
+
+    /// 记录 nonce，重复使用时返回错误
+    pub fn check(&mut self, nonce: &[u8]) -> Result<(), String> {
+        if !self.seen.insert(nonce.to_vec()) {
+            return Err("nonce: nonce reused".to_string());
+        }
+        Ok(())
+    }
+
+    /// 已记录的数量
+    pub fn len(&self) -> usize {
+        self.seen.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.seen.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonce() {
+        let mut seq = CounterNonceSequence::new([1, 2, 3, 4]);
+        assert_eq!(
+            seq.next_nonce().unwrap(),
+            [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            seq.next_nonce().unwrap(),
+            [1, 2, 3, 4, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(seq.position(), 2);
+
+        let mut seq = CounterNonceSequence::resume([1, 2, 3, 4], u64::MAX);
+        assert_eq!(seq.next_nonce().unwrap()[PREFIX_SIZE..], [0xff; 8]);
+        assert!(seq.next_nonce().is_err());
+
+        let mut random = RandomNonce::with_limit(2);
+        let a = random.next_nonce().unwrap();
+        let b = random.next_nonce().unwrap();
+        assert_ne!(a, b);
+        assert_eq!(random.remaining(), 0);
+        assert!(random.next_nonce().is_err());
+
+        let mut detector = NonceReuseDetector::new();
+        detector.check(&a).unwrap();
+        detector.check(&b).unwrap();
+        assert!(detector.check(&a).is_err());
+        assert_eq!(detector.len(), 2);
+    }
+}
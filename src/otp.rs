@@ -0,0 +1,318 @@
+//! 一次性口令 HOTP (RFC 4226) 和 TOTP (RFC 6238)
+//!
+//! 支持 HMAC-SHA1/SHA256/SHA512，6到9位口令，可以生成 otpauth:// URI 供身份验证器应用扫码导入。
+//! HOTP 只依赖 HMAC 的伪随机性，不受 SHA1 碰撞攻击影响，且是身份验证器应用的默认算法，
+//! 所以 SHA1 不需要开启 legacy-hashes 特性
+
+use ring::hmac;
+
+use crate::ct::ct_eq;
+use crate::error::CryptoError;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// 一次性口令使用的 HMAC 算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtpAlgorithm {
+    SHA1,
+    SHA256,
+    SHA512,
+}
+
+impl OtpAlgorithm {
+    fn name(self) -> &'static str {
+        match self {
+            OtpAlgorithm::SHA1 => "SHA1",
+            OtpAlgorithm::SHA256 => "SHA256",
+            OtpAlgorithm::SHA512 => "SHA512",
+        }
+    }
+
+    fn ring_algorithm(self) -> hmac::Algorithm {
+        match self {
+            OtpAlgorithm::SHA1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+            OtpAlgorithm::SHA256 => hmac::HMAC_SHA256,
+            OtpAlgorithm::SHA512 => hmac::HMAC_SHA512,
+        }
+    }
+}
+
+/// TOTP 参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TotpParams {
+    /// HMAC 算法
+    pub algorithm: OtpAlgorithm,
+    /// 口令位数，6到9
+    pub digits: u32,
+    /// 时间步长，单位为秒
+    pub period: u64,
+    /// 验证时前后各允许偏差的时间步数
+    pub skew: u64,
+}
+
+impl Default for TotpParams {
+    /// 默认与常见身份验证器应用相同：SHA1，6位，30秒，允许前后各偏差1个时间步
+    fn default() -> Self {
+        TotpParams {
+            algorithm: OtpAlgorithm::SHA1,
+            digits: 6,
+            period: 30,
+            skew: 1,
+        }
+    }
+}
+
+/// 生成 HOTP 口令
+///
+/// alg: HMAC 算法
+/// key: 共享密钥
+/// counter: 计数器
+/// digits: 口令位数，6到9
+pub fn hotp(
+    alg: OtpAlgorithm,
+    key: &[u8],
+    counter: u64,
+    digits: u32,
+) -> Result<String, CryptoError> {
+    if !(6..=9).contains(&digits) {
+        return Err(CryptoError::InvalidInput(
+            "otp: digits must be in [6, 9]".to_string(),
+        ));
+    }
+    let tag = hmac::sign(
+        &hmac::Key::new(alg.ring_algorithm(), key),
+        &counter.to_be_bytes(),
+    );
+    let tag = tag.as_ref();
+    // RFC 4226 5.3 动态截断
+    let offset = (tag[tag.len() - 1] & 0x0f) as usize;
+    let mut code = [0u8; 4];
+    code.copy_from_slice(&tag[offset..offset + 4]);
+    let code = u32::from_be_bytes(code) & 0x7fff_ffff;
+    Ok(format!(
+        "{:0width$}",
+        code % 10u32.pow(digits),
+        width = digits as usize
+    ))
+}
+
+/// 验证 HOTP 口令，成功时返回匹配的计数器，调用方应把计数器更新为返回值加1
+///
+/// counter: 服务端保存的下一个计数器
+/// look_ahead: 允许客户端计数器超前的最大步数，用于重新同步
+pub fn hotp_verify(
+    alg: OtpAlgorithm,
+    key: &[u8],
+    counter: u64,
+    digits: u32,
+    code: &str,
+    look_ahead: u64,
+) -> Option<u64> {
+    (counter..=counter.saturating_add(look_ahead)).find(|&c| {
+        matches!(hotp(alg, key, c, digits), Ok(expected) if ct_eq(expected.as_bytes(), code.as_bytes()))
+    })
+}
+
+/// 生成 TOTP 口令
+///
+/// key: 共享密钥
+/// params: TOTP 参数
+/// unix_time: 当前的 Unix 时间，单位为秒
+pub fn totp(key: &[u8], params: &TotpParams, unix_time: u64) -> Result<String, CryptoError> {
+    if params.period == 0 {
+        return Err(CryptoError::InvalidInput(
+            "otp: period must not be zero".to_string(),
+        ));
+    }
+    hotp(
+        params.algorithm,
+        key,
+        unix_time / params.period,
+        params.digits,
+    )
+}
+
+/// 验证 TOTP 口令，成功时返回匹配的时间步
+///
+/// 调用方应记录每个密钥最后一次成功的时间步，拒绝不大于它的时间步，防止口令被重放
+pub fn totp_verify(key: &[u8], params: &TotpParams, code: &str, unix_time: u64) -> Option<u64> {
+    if params.period == 0 {
+        return None;
+    }
+    let step = unix_time / params.period;
+    hotp_verify(
+        params.algorithm,
+        key,
+        step.saturating_sub(params.skew),
+        params.digits,
+        code,
+        step.min(params.skew).saturating_add(params.skew),
+    )
+}
+
+/// 生成 TOTP 的 otpauth:// URI
+///
+/// issuer: 服务名称，同时作为标签前缀和 issuer 参数
+/// account: 账户名称，如邮箱
+pub fn totp_uri(key: &[u8], params: &TotpParams, issuer: &str, account: &str) -> String {
+    format!(
+        "{}&period={}",
+        otpauth_uri(
+            "totp",
+            key,
+            params.algorithm,
+            params.digits,
+            issuer,
+            account
+        ),
+        params.period
+    )
+}
+
+/// 生成 HOTP 的 otpauth:// URI，参数同 totp_uri
+///
+/// counter: 客户端的初始计数器
+pub fn hotp_uri(
+    alg: OtpAlgorithm,
+    key: &[u8],
+    digits: u32,
+    counter: u64,
+    issuer: &str,
+    account: &str,
+) -> String {
+    format!(
+        "{}&counter={}",
+        otpauth_uri("hotp", key, alg, digits, issuer, account),
+        counter
+    )
+}
+
+fn otpauth_uri(
+    kind: &str,
+    key: &[u8],
+    alg: OtpAlgorithm,
+    digits: u32,
+    issuer: &str,
+    account: &str,
+) -> String {
+    format!(
+        "otpauth://{}/{}:{}?secret={}&issuer={}&algorithm={}&digits={}",
+        kind,
+        percent_encode(issuer),
+        percent_encode(account),
+        base32_encode(key),
+        percent_encode(issuer),
+        alg.name(),
+        digits
+    )
+}
+
+/// RFC 3986 百分号编码，只保留非保留字符
+fn percent_encode(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for b in s.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'.' | b'_' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+/// RFC 4648 base32 编码，不加填充，与身份验证器应用的密钥格式一致
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &b in data {
+        buffer = (buffer << 8) | b as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_otp() {
+        // RFC 4226 附录 D
+        let key = b"12345678901234567890";
+        let expected = [
+            "755224", "287082", "359152", "969429", "338314", "254676", "287922", "162583",
+            "399871", "520489",
+        ];
+        for (counter, code) in expected.iter().enumerate() {
+            assert_eq!(
+                hotp(OtpAlgorithm::SHA1, key, counter as u64, 6).unwrap(),
+                *code
+            );
+        }
+        assert_eq!(
+            hotp_verify(OtpAlgorithm::SHA1, key, 2, 6, "399871", 8),
+            Some(8)
+        );
+        assert_eq!(
+            hotp_verify(OtpAlgorithm::SHA1, key, 2, 6, "399871", 5),
+            None
+        );
+        assert!(hotp(OtpAlgorithm::SHA1, key, 0, 5).is_err());
+
+        // RFC 6238 附录 B，各算法使用的密钥长度不同
+        let keys: [(OtpAlgorithm, &[u8]); 3] = [
+            (OtpAlgorithm::SHA1, key),
+            (OtpAlgorithm::SHA256, b"12345678901234567890123456789012"),
+            (
+                OtpAlgorithm::SHA512,
+                b"1234567890123456789012345678901234567890123456789012345678901234",
+            ),
+        ];
+        let vectors = [
+            (59, ["94287082", "46119246", "90693936"]),
+            (1111111109, ["07081804", "68084774", "25091201"]),
+            (20000000000, ["65353130", "77737706", "47863826"]),
+        ];
+        for (time, codes) in vectors.iter() {
+            for ((alg, key), code) in keys.iter().zip(codes.iter()) {
+                let params = TotpParams {
+                    algorithm: *alg,
+                    digits: 8,
+                    ..TotpParams::default()
+                };
+                assert_eq!(totp(key, &params, *time).unwrap(), *code);
+                assert_eq!(totp_verify(key, &params, code, *time), Some(time / 30));
+            }
+        }
+
+        // 默认允许前后各一个时间步
+        let params = TotpParams::default();
+        let code = totp(key, &params, 1111111109).unwrap();
+        assert_eq!(code, "081804");
+        assert!(totp_verify(key, &params, &code, 1111111109 + 30).is_some());
+        assert!(totp_verify(key, &params, &code, 1111111109 - 30).is_some());
+        assert!(totp_verify(key, &params, &code, 1111111109 + 60).is_none());
+        assert!(totp_verify(key, &params, "000000", 1111111109).is_none());
+        assert_eq!(
+            totp_verify(key, &params, &totp(key, &params, 0).unwrap(), 0),
+            Some(0)
+        );
+
+        assert_eq!(
+            totp_uri(key, &params, "Example Co", "alice@example.com"),
+            "otpauth://totp/Example%20Co:alice%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Example%20Co&algorithm=SHA1&digits=6&period=30"
+        );
+        assert_eq!(
+            hotp_uri(OtpAlgorithm::SHA256, b"hi", 8, 3, "a", "b"),
+            "otpauth://hotp/a:b?secret=NBUQ&issuer=a&algorithm=SHA256&digits=8&counter=3"
+        );
+    }
+}
@@ -0,0 +1,286 @@
+//! 口令哈希和验证
+//!
+//! 生成的哈希串与其他语言的实现兼容：bcrypt 为 "$2b$cost$盐和哈希"，Argon2id 为 PHC 格式
+//! "$argon2id$v=19$m=..,t=..,p=..$盐$哈希"。参数随哈希串保存，调整参数后用 needs_rehash
+//! 在用户下次登录时发现旧哈希并重新计算
+
+use crate::ct::ct_eq;
+use crate::error::CryptoError;
+use crate::random::genSecureRandBytes;
+
+const SALT_SIZE: usize = 16;
+const ARGON2_HASH_LEN: u32 = 32;
+// bcrypt 只使用口令的前72字节
+const BCRYPT_MAX_PASSWORD: usize = 72;
+const BCRYPT_HASH_LEN: usize = 23;
+const BCRYPT_ALPHABET: &[u8; 64] =
+    b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789";
+
+/// 口令哈希算法和代价参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashParams {
+    /// bcrypt，cost 为迭代次数以2为底的对数，4到31
+    Bcrypt { cost: u32 },
+    /// Argon2id，mem_cost 单位为KiB
+    Argon2id {
+        mem_cost: u32,
+        time_cost: u32,
+        lanes: u32,
+    },
+}
+
+impl Default for HashParams {
+    /// 默认使用 Argon2id，19MiB内存，2次迭代，1个通道
+    fn default() -> Self {
+        HashParams::Argon2id {
+            mem_cost: 19 * 1024,
+            time_cost: 2,
+            lanes: 1,
+        }
+    }
+}
+
+impl HashParams {
+    /// bcrypt，cost 为12
+    pub fn bcrypt() -> Self {
+        HashParams::Bcrypt { cost: 12 }
+    }
+
+    /// 从哈希串中解析参数
+    pub fn from_phc(phc: &str) -> Result<Self, CryptoError> {
+        match parse(phc)? {
+            Parsed::Bcrypt { cost, .. } => Ok(HashParams::Bcrypt { cost }),
+            Parsed::Argon2id(params) => Ok(params),
+        }
+    }
+}
+
+/// 计算口令哈希，使用随机盐
+///
+/// password: 口令，bcrypt 只使用前72字节
+/// params: 算法和代价参数
+pub fn hash(password: &[u8], params: &HashParams) -> Result<String, CryptoError> {
+    let salt = genSecureRandBytes(SALT_SIZE);
+    match *params {
+        HashParams::Bcrypt { cost } => {
+            if !(4..=31).contains(&cost) {
+                return Err(CryptoError::InvalidInput(
+                    "password: bcrypt cost must be in [4, 31]".to_string(),
+                ));
+            }
+            let mut salt16 = [0u8; SALT_SIZE];
+            salt16.copy_from_slice(&salt);
+            let out = bcrypt_raw(cost, &salt16, password);
+            Ok(format!(
+                "$2b${:02}${}{}",
+                cost,
+                bcrypt_base64(&salt16),
+                bcrypt_base64(&out)
+            ))
+        }
+        HashParams::Argon2id {
+            mem_cost,
+            time_cost,
+            lanes,
+        } => argon2::hash_encoded(password, &salt, &argon2_config(mem_cost, time_cost, lanes))
+            .map_err(|e| CryptoError::OperationFailed(format!("password: {}", e))),
+    }
+}
+
+/// 验证口令，哈希串格式错误时返回 false
+///
+/// bcrypt 接受 $2a$, $2b$ 和 $2y$ 前缀
+pub fn verify(password: &[u8], phc: &str) -> bool {
+    match parse(phc) {
+        Ok(Parsed::Bcrypt { cost, salt, hash }) => ct_eq(&bcrypt_raw(cost, &salt, password), &hash),
+        Ok(Parsed::Argon2id(_)) => argon2::verify_encoded(phc, password).unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+/// 哈希串的算法或参数与 params 不同时返回 true，调用方应在验证成功后用新参数重新计算
+pub fn needs_rehash(phc: &str, params: &HashParams) -> bool {
+    !matches!(HashParams::from_phc(phc), Ok(p) if p == *params)
+}
+
+enum Parsed {
+    Bcrypt {
+        cost: u32,
+        salt: [u8; SALT_SIZE],
+        hash: [u8; BCRYPT_HASH_LEN],
+    },
+    Argon2id(HashParams),
+}
+
+fn parse(phc: &str) -> Result<Parsed, CryptoError> {
+    let malformed = || CryptoError::InvalidInput("password: malformed hash string".to_string());
+    let parts: Vec<&str> = phc.split('$').collect();
+    match parts.as_slice() {
+        ["", "2a" | "2b" | "2y", cost, rest] => {
+            let cost: u32 = match cost.len() {
+                2 => cost.parse().map_err(|_| malformed())?,
+                _ => return Err(malformed()),
+            };
+            if !(4..=31).contains(&cost) || rest.len() != 53 {
+                return Err(malformed());
+            }
+            let salt = bcrypt_base64_decode(&rest[..22], SALT_SIZE).ok_or_else(malformed)?;
+            let hash = bcrypt_base64_decode(&rest[22..], BCRYPT_HASH_LEN).ok_or_else(malformed)?;
+            let mut s = [0u8; SALT_SIZE];
+            s.copy_from_slice(&salt);
+            let mut h = [0u8; BCRYPT_HASH_LEN];
+            h.copy_from_slice(&hash);
+            Ok(Parsed::Bcrypt {
+                cost,
+                salt: s,
+                hash: h,
+            })
+        }
+        ["", "argon2id", "v=19", params, salt, hash] if !salt.is_empty() && !hash.is_empty() => {
+            let items: Vec<&str> = params.split(',').collect();
+            let values = items
+                .iter()
+                .zip(["m=", "t=", "p="])
+                .map(|(item, key)| item.strip_prefix(key)?.parse::<u32>().ok())
+                .collect::<Option<Vec<u32>>>();
+            match (items.len(), values.as_deref()) {
+                (3, Some(&[mem_cost, time_cost, lanes])) => {
+                    Ok(Parsed::Argon2id(HashParams::Argon2id {
+                        mem_cost,
+                        time_cost,
+                        lanes,
+                    }))
+                }
+                _ => Err(malformed()),
+            }
+        }
+        _ => Err(malformed()),
+    }
+}
+
+fn argon2_config<'a>(mem_cost: u32, time_cost: u32, lanes: u32) -> argon2::Config<'a> {
+    argon2::Config {
+        variant: argon2::Variant::Argon2id,
+        version: argon2::Version::Version13,
+        mem_cost,
+        time_cost,
+        lanes,
+        thread_mode: argon2::ThreadMode::Sequential,
+        secret: &[],
+        ad: &[],
+        hash_length: ARGON2_HASH_LEN,
+    }
+}
+
+/// $2b$ 的口令处理：末尾加0字节，截断到72字节，输出只保留前23字节
+fn bcrypt_raw(cost: u32, salt: &[u8; SALT_SIZE], password: &[u8]) -> [u8; BCRYPT_HASH_LEN] {
+    let mut key = password[..password.len().min(BCRYPT_MAX_PASSWORD)].to_vec();
+    if key.len() < BCRYPT_MAX_PASSWORD {
+        key.push(0);
+    }
+    let mut out = [0u8; 24];
+    crypto::bcrypt::bcrypt(cost, salt, &key, &mut out);
+    crate::ct::wipe(&mut key);
+    let mut hash = [0u8; BCRYPT_HASH_LEN];
+    hash.copy_from_slice(&out[..BCRYPT_HASH_LEN]);
+    hash
+}
+
+/// bcrypt 使用的 base64 变体，字母表不同，不加填充
+fn bcrypt_base64(data: &[u8]) -> String {
+    let mut out = String::with_capacity((data.len() * 4).div_ceil(3));
+    for chunk in data.chunks(3) {
+        let mut b = [0u8; 3];
+        b[..chunk.len()].copy_from_slice(chunk);
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..=chunk.len() {
+            out.push(BCRYPT_ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
+fn bcrypt_base64_decode(s: &str, len: usize) -> Option<Vec<u8>> {
+    let mut out = Vec::with_capacity(len + 2);
+    for chunk in s.as_bytes().chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            let v = BCRYPT_ALPHABET.iter().position(|a| a == c)? as u32;
+            n |= v << (18 - 6 * i);
+        }
+        out.extend_from_slice(&n.to_be_bytes()[1..chunk.len()]);
+    }
+    if out.len() != len {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password() {
+        // OpenBSD / John the Ripper 的 bcrypt 测试向量
+        let vectors = [
+            (
+                "U*U",
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW",
+            ),
+            (
+                "U*U*",
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.VGOzA784oUp/Z0DY336zx7pLYAy0lwK",
+            ),
+            (
+                "",
+                "$2a$05$CCCCCCCCCCCCCCCCCCCCC.7uG0VCzI2bS7j6ymqJi9CdcdxiRTWNy",
+            ),
+        ];
+        for (password, phc) in vectors.iter() {
+            assert!(verify(password.as_bytes(), phc));
+            assert!(!verify(b"wrong", phc));
+            assert_eq!(
+                HashParams::from_phc(phc).unwrap(),
+                HashParams::Bcrypt { cost: 5 }
+            );
+        }
+
+        // Argon2 参考实现的测试向量
+        let phc =
+            "$argon2id$v=19$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR/uIPnA4rEsF5h7TKyQwu9U1bMCHGi/4";
+        assert!(verify(b"password", phc));
+        assert!(!verify(b"Password", phc));
+        let params = HashParams::Argon2id {
+            mem_cost: 256,
+            time_cost: 2,
+            lanes: 1,
+        };
+        assert_eq!(HashParams::from_phc(phc).unwrap(), params);
+        assert!(!needs_rehash(phc, &params));
+        assert!(needs_rehash(phc, &HashParams::default()));
+        assert!(needs_rehash(vectors[0].1, &params));
+
+        let bcrypt = HashParams::Bcrypt { cost: 4 };
+        let hashed = hash(b"correct horse", &bcrypt).unwrap();
+        assert!(hashed.starts_with("$2b$04$"));
+        assert!(verify(b"correct horse", &hashed));
+        assert!(!needs_rehash(&hashed, &bcrypt));
+        assert!(needs_rehash(&hashed, &HashParams::bcrypt()));
+        assert_ne!(hash(b"correct horse", &bcrypt).unwrap(), hashed);
+        // 超过72字节的部分被忽略
+        let long = [b'a'; 80];
+        let hashed = hash(&long, &bcrypt).unwrap();
+        assert!(verify(&long[..72], &hashed));
+
+        let hashed = hash(b"correct horse", &params).unwrap();
+        assert!(hashed.starts_with("$argon2id$v=19$m=256,t=2,p=1$"));
+        assert!(verify(b"correct horse", &hashed));
+        assert!(!verify(
+            b"correct horse",
+            "$argon2i$v=19$m=256,t=2,p=1$c29tZXNhbHQ$nf65EOgLrQMR"
+        ));
+        assert!(!verify(b"", "$2b$05$short"));
+        assert!(hash(b"x", &HashParams::Bcrypt { cost: 3 }).is_err());
+    }
+}
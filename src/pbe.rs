@@ -0,0 +1,279 @@
+//! 基于口令的文件加密
+//!
+//! 用 scrypt 或 Argon2id 从口令派生密钥，再用分段流式AEAD加密数据，
+//! 适用于用口令加密备份文件之类的场景。
+//!
+//! 密文格式: "PIPB" | 版本(1字节) | KDF类型(1字节) | KDF参数 | 盐(16字节) | 分段密文，
+//! 头部作为每一段的辅助数据参与认证
+
+use std::io::{Read, Write};
+
+use crate::kdf::{self, Kdf};
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+use crate::stream;
+
+const MAGIC: &[u8; 4] = b"PIPB";
+const VERSION: u8 = 1;
+const SALT_SIZE: usize = 16;
+const KEY_SIZE: usize = 32;
+
+const KDF_SCRYPT: u8 = 1;
+const KDF_ARGON2ID: u8 = 2;
+
+// 解密时允许的最大KDF参数，防止恶意文件消耗过多资源，scrypt 的内存另外受 MAX_SCRYPT_MEMORY 限制
+const MAX_SCRYPT_LOG_N: u8 = 22;
+const MAX_SCRYPT_R: u32 = 32;
+const MAX_SCRYPT_P: u32 = 16;
+const MAX_ARGON2_MEM_COST: u32 = 1024 * 1024;
+const MAX_ARGON2_TIME_COST: u32 = 32;
+const MAX_ARGON2_LANES: u32 = 16;
+
+/// 口令派生密钥使用的算法和参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PasswordKdf {
+    /// scrypt，log_n 为参数N的以2为底的对数
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// Argon2id，mem_cost 单位为KiB
+    Argon2id {
+        mem_cost: u32,
+        time_cost: u32,
+        lanes: u32,
+    },
+}
+
+impl Default for PasswordKdf {
+    /// 默认使用 Argon2id，19MiB内存，2次迭代，1个通道
+    fn default() -> Self {
+        PasswordKdf::Argon2id {
+            mem_cost: 19 * 1024,
+            time_cost: 2,
+            lanes: 1,
+        }
+    }
+}
+
+impl PasswordKdf {
+    fn check(&self) -> Result<(), String> {
+        let ok = match *self {
+            PasswordKdf::Scrypt { log_n, r, p } => {
+                log_n > 0
+                    && log_n <= MAX_SCRYPT_LOG_N
+                    && r > 0
+                    && r <= MAX_SCRYPT_R
+                    && p > 0
+                    && p <= MAX_SCRYPT_P
+                    && kdf::Scrypt { log_n, r, p }.memory_cost() <= kdf::MAX_SCRYPT_MEMORY
+            }
+            PasswordKdf::Argon2id {
+                mem_cost,
+                time_cost,
+                lanes,
+            } => {
+                lanes > 0
+                    && lanes <= MAX_ARGON2_LANES
+                    && time_cost > 0
+                    && time_cost <= MAX_ARGON2_TIME_COST
+                    && mem_cost >= 8 * lanes
+                    && mem_cost <= MAX_ARGON2_MEM_COST
+            }
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err("pbe: kdf parameters out of range".to_string())
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            PasswordKdf::Scrypt { log_n, r, p } => {
+                out.push(KDF_SCRYPT);
+                out.push(log_n);
+                out.extend_from_slice(&r.to_be_bytes());
+                out.extend_from_slice(&p.to_be_bytes());
+            }
+            PasswordKdf::Argon2id {
+                mem_cost,
+                time_cost,
+                lanes,
+            } => {
+                out.push(KDF_ARGON2ID);
+                out.extend_from_slice(&mem_cost.to_be_bytes());
+                out.extend_from_slice(&time_cost.to_be_bytes());
+                out.extend_from_slice(&lanes.to_be_bytes());
+            }
+        }
+    }
+
+    fn derive(&self, password: &[u8], salt: &[u8]) -> Result<SecretBytes, String> {
+        let key = match *self {
+            PasswordKdf::Scrypt { log_n, r, p } => {
+                kdf::Scrypt { log_n, r, p }.derive(password, salt, &[], KEY_SIZE)
+            }
+            PasswordKdf::Argon2id {
+                mem_cost,
+                time_cost,
+                lanes,
+            } => kdf::Argon2 {
+                variant: kdf::Argon2Variant::Argon2id,
+                mem_cost,
+                time_cost,
+                lanes,
+            }
+            .derive(password, salt, &[], KEY_SIZE),
+        }?;
+        Ok(SecretBytes::new(key))
+    }
+}
+
+/// 使用口令加密数据，KDF使用默认参数
+///
+/// reader: 明文
+/// writer: 输出的密文
+/// password: 口令
+pub fn encrypt_with_password<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    password: &[u8],
+) -> Result<(), String> {
+    encrypt_with_password_kdf(reader, writer, password, &PasswordKdf::default())
+}
+
+/// 使用口令和指定的KDF参数加密数据
+///
+/// reader: 明文
+/// writer: 输出的密文
+/// password: 口令
+/// kdf: 口令派生密钥使用的算法和参数
+pub fn encrypt_with_password_kdf<R: Read, W: Write>(
+    reader: R,
+    mut writer: W,
+    password: &[u8],
+    kdf: &PasswordKdf,
+) -> Result<(), String> {
+    kdf.check()?;
+    let salt = genSecureRandBytes(SALT_SIZE);
+
+    let mut header = MAGIC.to_vec();
+    header.push(VERSION);
+    kdf.encode(&mut header);
+    header.extend_from_slice(&salt);
+
+    let key = kdf.derive(password, &salt)?;
+    writer.write_all(&header).map_err(|e| e.to_string())?;
+    stream::seal(&key, &header, reader, writer)
+}
+
+/// 使用口令解密数据
+///
+/// reader: 密文
+/// writer: 输出的明文，出错时已写入的内容应当丢弃
+/// password: 口令
+pub fn decrypt_with_password<R: Read, W: Write>(
+    mut reader: R,
+    writer: W,
+    password: &[u8],
+) -> Result<(), String> {
+    let mut header = read_exact(&mut reader, 6)?;
+    if &header[..4] != MAGIC {
+        return Err("pbe: bad magic".to_string());
+    }
+    if header[4] != VERSION {
+        return Err(format!("pbe: unsupported version {}", header[4]));
+    }
+    let kdf = match header[5] {
+        KDF_SCRYPT => {
+            let params = read_exact(&mut reader, 9)?;
+            header.extend_from_slice(&params);
+            PasswordKdf::Scrypt {
+                log_n: params[0],
+                r: be_u32(&params[1..5]),
+                p: be_u32(&params[5..9]),
+            }
+        }
+        KDF_ARGON2ID => {
+            let params = read_exact(&mut reader, 12)?;
+            header.extend_from_slice(&params);
+            PasswordKdf::Argon2id {
+                mem_cost: be_u32(&params[0..4]),
+                time_cost: be_u32(&params[4..8]),
+                lanes: be_u32(&params[8..12]),
+            }
+        }
+        id => return Err(format!("pbe: unknown kdf {}", id)),
+    };
+    kdf.check()?;
+    let salt = read_exact(&mut reader, SALT_SIZE)?;
+    header.extend_from_slice(&salt);
+
+    let key = kdf.derive(password, &salt)?;
+    stream::open(&key, &header, reader, writer)
+        .map_err(|_| "pbe: wrong password or corrupted data".to_string())
+}
+
+fn read_exact<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, String> {
+    let mut buf = vec![0u8; len];
+    reader
+        .read_exact(&mut buf)
+        .map_err(|_| "pbe: truncated header".to_string())?;
+    Ok(buf)
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_password_roundtrip() {
+        let plain = vec![0x5au8; stream::CHUNK_SIZE + 100];
+        let kdfs = [
+            PasswordKdf::Scrypt {
+                log_n: 10,
+                r: 8,
+                p: 1,
+            },
+            PasswordKdf::Argon2id {
+                mem_cost: 64,
+                time_cost: 1,
+                lanes: 1,
+            },
+        ];
+        for kdf in kdfs.iter() {
+            let mut encrypted = Vec::new();
+            encrypt_with_password_kdf(&plain[..], &mut encrypted, b"backup password", kdf).unwrap();
+
+            let mut decrypted = Vec::new();
+            decrypt_with_password(&encrypted[..], &mut decrypted, b"backup password").unwrap();
+            assert_eq!(plain, decrypted);
+
+            assert!(decrypt_with_password(&encrypted[..], &mut Vec::new(), b"wrong").is_err());
+
+            // 篡改头部中的KDF参数会导致认证失败
+            encrypted[6] ^= 1;
+            assert!(
+                decrypt_with_password(&encrypted[..], &mut Vec::new(), b"backup password").is_err()
+            );
+        }
+
+        // 各参数都在范围内，但合计需要 16GiB 内存，头部解析时就拒绝
+        let big = PasswordKdf::Scrypt {
+            log_n: 22,
+            r: 32,
+            p: 16,
+        };
+        assert!(encrypt_with_password_kdf(&plain[..], &mut Vec::new(), b"pw", &big).is_err());
+        let mut header = MAGIC.to_vec();
+        header.push(VERSION);
+        big.encode(&mut header);
+        header.extend_from_slice(&[0u8; SALT_SIZE]);
+        assert_eq!(
+            decrypt_with_password(&header[..], &mut Vec::new(), b"pw"),
+            Err("pbe: kdf parameters out of range".to_string())
+        );
+    }
+}
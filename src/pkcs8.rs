@@ -0,0 +1,239 @@
+//! 加密的 PKCS#8 私钥 (RFC 5958 EncryptedPrivateKeyInfo)
+//!
+//! 只支持 PBES2 (RFC 8018)：PBKDF2-HMAC-SHA1/SHA256/SHA384/SHA512 派生密钥，AES-128/192/256-CBC 加密，
+//! 与 `openssl pkcs8 -topk8 -v2 aes-256-cbc` 的输出兼容。不支持 PBES1 和 scrypt
+
+use std::num::NonZeroU32;
+
+use num_traits::ToPrimitive;
+use ring::pbkdf2;
+use simple_asn1::{oid, to_der, ASN1Block, BigInt, BigUint, OID};
+
+use crate::cipher::{aes_cbc_decrypt, aes_cbc_encrypt};
+use crate::error::CryptoError;
+use crate::kdf::{Kdf, KdfHash, Pbkdf2};
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+
+// 解密时接受的最大迭代次数，防止恶意文件消耗大量CPU
+const MAX_ITERATIONS: u32 = 10_000_000;
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 16;
+
+/// 用口令加密 PKCS#8 PrivateKeyInfo，使用 PBES2 和 AES-256-CBC，盐和IV随机生成
+///
+/// pkcs8: PrivateKeyInfo DER
+/// passphrase: 口令
+/// params: PBKDF2 参数，推荐 SHA256 和不少于600000次迭代
+pub fn encrypt_pkcs8(
+    pkcs8: &[u8],
+    passphrase: &[u8],
+    params: Pbkdf2,
+) -> Result<Vec<u8>, CryptoError> {
+    let salt = genSecureRandBytes(SALT_LEN);
+    let iv = genSecureRandBytes(IV_LEN);
+    let key = SecretBytes::new(
+        params
+            .derive(passphrase, &salt, &[], 32)
+            .map_err(CryptoError::InvalidInput)?,
+    );
+    let encrypted = aes_cbc_encrypt(&key, &iv, pkcs8).map_err(CryptoError::OperationFailed)?;
+    let prf = match params.hash {
+        KdfHash::SHA256 => oid!(1, 2, 840, 113549, 2, 9),
+        KdfHash::SHA384 => oid!(1, 2, 840, 113549, 2, 10),
+        KdfHash::SHA512 => oid!(1, 2, 840, 113549, 2, 11),
+    };
+    let kdf = ASN1Block::Sequence(
+        0,
+        vec![
+            ASN1Block::ObjectIdentifier(0, oid!(1, 2, 840, 113549, 1, 5, 12)),
+            ASN1Block::Sequence(
+                0,
+                vec![
+                    ASN1Block::OctetString(0, salt),
+                    ASN1Block::Integer(0, BigInt::from(params.iterations)),
+                    ASN1Block::Sequence(
+                        0,
+                        vec![ASN1Block::ObjectIdentifier(0, prf), ASN1Block::Null(0)],
+                    ),
+                ],
+            ),
+        ],
+    );
+    let scheme = ASN1Block::Sequence(
+        0,
+        vec![
+            ASN1Block::ObjectIdentifier(0, oid!(2, 16, 840, 1, 101, 3, 4, 1, 42)),
+            ASN1Block::OctetString(0, iv),
+        ],
+    );
+    to_der(&ASN1Block::Sequence(
+        0,
+        vec![
+            ASN1Block::Sequence(
+                0,
+                vec![
+                    ASN1Block::ObjectIdentifier(0, oid!(1, 2, 840, 113549, 1, 5, 13)),
+                    ASN1Block::Sequence(0, vec![kdf, scheme]),
+                ],
+            ),
+            ASN1Block::OctetString(0, encrypted),
+        ],
+    ))
+    .map_err(|e| CryptoError::OperationFailed(format!("pkcs8: encode error: {:?}", e)))
+}
+
+/// 用口令解密 EncryptedPrivateKeyInfo，返回 PrivateKeyInfo DER
+///
+/// der: EncryptedPrivateKeyInfo DER
+/// passphrase: 口令
+pub fn decrypt_pkcs8(der: &[u8], passphrase: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let blocks = simple_asn1::from_der(der).map_err(|_| malformed())?;
+    let (alg, encrypted) = match blocks.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [ASN1Block::Sequence(_, alg), ASN1Block::OctetString(_, data)] => (alg, data),
+            _ => return Err(malformed()),
+        },
+        _ => return Err(malformed()),
+    };
+    let (kdf, scheme) = match alg.as_slice() {
+        [ASN1Block::ObjectIdentifier(_, id), ASN1Block::Sequence(_, params)]
+            if *id == oid!(1, 2, 840, 113549, 1, 5, 13) =>
+        {
+            match params.as_slice() {
+                [ASN1Block::Sequence(_, kdf), ASN1Block::Sequence(_, scheme)] => (kdf, scheme),
+                _ => return Err(malformed()),
+            }
+        }
+        _ => return Err(unsupported("only PBES2 is supported")),
+    };
+
+    let (cipher, iv) = match scheme.as_slice() {
+        [ASN1Block::ObjectIdentifier(_, id), ASN1Block::OctetString(_, iv)] => (id, iv),
+        _ => return Err(malformed()),
+    };
+    let key_len = if *cipher == oid!(2, 16, 840, 1, 101, 3, 4, 1, 2) {
+        16
+    } else if *cipher == oid!(2, 16, 840, 1, 101, 3, 4, 1, 22) {
+        24
+    } else if *cipher == oid!(2, 16, 840, 1, 101, 3, 4, 1, 42) {
+        32
+    } else {
+        return Err(unsupported("only AES-CBC encryption is supported"));
+    };
+
+    let params = match kdf.as_slice() {
+        [ASN1Block::ObjectIdentifier(_, id), ASN1Block::Sequence(_, params)]
+            if *id == oid!(1, 2, 840, 113549, 1, 5, 12) =>
+        {
+            params
+        }
+        _ => return Err(unsupported("only PBKDF2 is supported")),
+    };
+    // PBKDF2-params ::= SEQUENCE { salt, iterationCount, keyLength OPTIONAL, prf DEFAULT hmacWithSHA1 }
+    let (salt, iterations, rest) = match params.as_slice() {
+        [ASN1Block::OctetString(_, salt), ASN1Block::Integer(_, n), rest @ ..] => (salt, n, rest),
+        _ => return Err(malformed()),
+    };
+    let iterations = iterations
+        .to_u32()
+        .filter(|n| *n <= MAX_ITERATIONS)
+        .and_then(NonZeroU32::new)
+        .ok_or_else(|| unsupported("PBKDF2 iteration count out of range"))?;
+    let rest = match rest {
+        [ASN1Block::Integer(_, len), rest @ ..] => {
+            if len.to_usize() != Some(key_len) {
+                return Err(malformed());
+            }
+            rest
+        }
+        _ => rest,
+    };
+    let prf = match rest {
+        [] => pbkdf2::PBKDF2_HMAC_SHA1,
+        [ASN1Block::Sequence(_, prf)] => match prf.as_slice() {
+            [ASN1Block::ObjectIdentifier(_, id)]
+            | [ASN1Block::ObjectIdentifier(_, id), ASN1Block::Null(_)] => hmac_prf(id)?,
+            _ => return Err(malformed()),
+        },
+        _ => return Err(malformed()),
+    };
+
+    let mut key = SecretBytes::zeroed(key_len);
+    pbkdf2::derive(prf, iterations, salt, passphrase, key.as_mut_bytes());
+    let pkcs8 =
+        SecretBytes::new(aes_cbc_decrypt(&key, iv, encrypted).map_err(|_| wrong_passphrase())?);
+    // 填充碰巧正确的概率约为1/256，再检查明文是否为DER序列
+    match simple_asn1::from_der(&pkcs8)
+        .map_err(|_| wrong_passphrase())?
+        .as_slice()
+    {
+        [ASN1Block::Sequence(_, _)] => Ok(pkcs8.to_vec()),
+        _ => Err(wrong_passphrase()),
+    }
+}
+
+fn hmac_prf(id: &OID) -> Result<pbkdf2::Algorithm, CryptoError> {
+    if *id == oid!(1, 2, 840, 113549, 2, 7) {
+        Ok(pbkdf2::PBKDF2_HMAC_SHA1)
+    } else if *id == oid!(1, 2, 840, 113549, 2, 9) {
+        Ok(pbkdf2::PBKDF2_HMAC_SHA256)
+    } else if *id == oid!(1, 2, 840, 113549, 2, 10) {
+        Ok(pbkdf2::PBKDF2_HMAC_SHA384)
+    } else if *id == oid!(1, 2, 840, 113549, 2, 11) {
+        Ok(pbkdf2::PBKDF2_HMAC_SHA512)
+    } else {
+        Err(unsupported("unsupported PBKDF2 prf"))
+    }
+}
+
+fn malformed() -> CryptoError {
+    CryptoError::InvalidKey("pkcs8: malformed encrypted private key".to_string())
+}
+
+fn unsupported(msg: &str) -> CryptoError {
+    CryptoError::InvalidKey(format!("pkcs8: {}", msg))
+}
+
+fn wrong_passphrase() -> CryptoError {
+    CryptoError::InvalidKey("pkcs8: wrong passphrase or corrupted key".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_encrypted_pkcs8() {
+        // 由 openssl pkcs8 -topk8 -v2 aes-128-cbc -iter 1000 -v2prf hmacWithSHA256 生成，
+        // 明文是 RFC 8032 7.1 test 1 的 Ed25519 私钥
+        let der = base64::decode(
+            "MIGbMFcGCSqGSIb3DQEFDTBKMCkGCSqGSIb3DQEFDDAcBAjvqjbqLZFHowICA+gw\
+             DAYIKoZIhvcNAgkFADAdBglghkgBZQMEAQIEEAtu5wKY9ezJs3h2wGG4V+EEQFXc\
+             9lD4hjElPdd/XiTwl0bErr5wqEY3PeAWL6Tt4owfpPl1DAQOoLOaPuy5PdEEePTZ\
+             +0XEtloH5jDRFqPM2Z0=",
+        )
+        .unwrap();
+        let pkcs8 = Vec::from_hex(
+            "302e020100300506032b657004220420\
+             9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60",
+        )
+        .unwrap();
+        assert_eq!(decrypt_pkcs8(&der, b"correct horse").unwrap(), pkcs8);
+        assert_eq!(decrypt_pkcs8(&der, b"wrong horse"), Err(wrong_passphrase()));
+        assert!(decrypt_pkcs8(&der[..der.len() - 1], b"correct horse").is_err());
+
+        let params = Pbkdf2 {
+            hash: KdfHash::SHA512,
+            iterations: 1000,
+        };
+        let sealed = encrypt_pkcs8(&pkcs8, b"correct horse", params).unwrap();
+        assert_ne!(
+            sealed,
+            encrypt_pkcs8(&pkcs8, b"correct horse", params).unwrap()
+        );
+        assert_eq!(decrypt_pkcs8(&sealed, b"correct horse").unwrap(), pkcs8);
+        assert!(decrypt_pkcs8(&sealed, b"").is_err());
+    }
+}
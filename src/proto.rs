@@ -0,0 +1,74 @@
+//! 最小的 protobuf 编解码，只支持 varint 和 length-delimited 字段
+//!
+//! 供 tink 密钥集和 dsse 信封等需要与 protobuf 格式互通的模块使用
+
+pub enum Value<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+/// 解析一条消息的所有字段，跳过 fixed32/fixed64 字段
+pub fn parse(mut data: &[u8]) -> Result<Vec<(u64, Value<'_>)>, String> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let tag = varint(&mut data)?;
+        let number = tag >> 3;
+        match tag & 7 {
+            0 => fields.push((number, Value::Varint(varint(&mut data)?))),
+            1 => data = skip(data, 8)?,
+            2 => {
+                let len = varint(&mut data)? as usize;
+                if len > data.len() {
+                    return Err("proto: truncated message".to_string());
+                }
+                let (value, rest) = data.split_at(len);
+                fields.push((number, Value::Bytes(value)));
+                data = rest;
+            }
+            5 => data = skip(data, 4)?,
+            t => return Err(format!("proto: unsupported wire type {}", t)),
+        }
+    }
+    Ok(fields)
+}
+
+fn skip(data: &[u8], len: usize) -> Result<&[u8], String> {
+    if data.len() < len {
+        return Err("proto: truncated message".to_string());
+    }
+    Ok(&data[len..])
+}
+
+fn varint(data: &mut &[u8]) -> Result<u64, String> {
+    let mut value = 0u64;
+    for i in 0..10 {
+        let b = *data
+            .get(i)
+            .ok_or_else(|| "proto: truncated message".to_string())?;
+        value |= u64::from(b & 0x7f) << (7 * i);
+        if b & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Ok(value);
+        }
+    }
+    Err("proto: varint too long".to_string())
+}
+
+fn put_varint(out: &mut Vec<u8>, mut v: u64) {
+    while v >= 0x80 {
+        out.push((v as u8) | 0x80);
+        v >>= 7;
+    }
+    out.push(v as u8);
+}
+
+pub fn put_varint_field(out: &mut Vec<u8>, number: u64, v: u64) {
+    put_varint(out, number << 3);
+    put_varint(out, v);
+}
+
+pub fn put_bytes_field(out: &mut Vec<u8>, number: u64, v: &[u8]) {
+    put_varint(out, (number << 3) | 2);
+    put_varint(out, v.len() as u64);
+    out.extend_from_slice(v);
+}
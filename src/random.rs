@@ -1,15 +1,58 @@
 //! 安全的随机数生成器
+//!
+//! 需要随机数的密钥生成和签名函数都有接受 SecureRandom 的版本，测试时可以注入确定性的随机数源，
+//! 不带随机数参数的版本使用 OsRng。需要可复现的输出时使用 HmacDrbg
+//!
+//! 开启 wasm 特性后 OsRng 改用 getrandom，在浏览器和 Node.js 中由 crypto.getRandomValues 提供随机数
 
-use ring::rand::{SecureRandom, SystemRandom};
+#[cfg(not(feature = "wasm"))]
+use ring::rand::SystemRandom;
+
+pub mod drbg;
+
+pub use drbg::HmacDrbg;
+
+/// 密码学安全的随机数生成器
+///
+/// ring 的随机数生成器(如 SystemRandom)也实现了这个trait
+pub trait SecureRandom {
+    /// 用随机数据填满 dest，无法获取随机数时 panic
+    fn fill(&self, dest: &mut [u8]);
+
+    /// 生成指定长度的随机数据
+    fn gen_bytes(&self, len: usize) -> Vec<u8> {
+        let mut dst = vec![0; len];
+        self.fill(&mut dst);
+        dst
+    }
+}
+
+impl<T: ring::rand::SecureRandom + ?Sized> SecureRandom for T {
+    fn fill(&self, dest: &mut [u8]) {
+        ring::rand::SecureRandom::fill(self, dest)
+            .expect("Fatal error: can't get rand bytes from system");
+    }
+}
+
+/// 操作系统提供的随机数生成器
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OsRng;
+
+impl SecureRandom for OsRng {
+    #[cfg(not(feature = "wasm"))]
+    fn fill(&self, dest: &mut [u8]) {
+        SecureRandom::fill(&SystemRandom::new(), dest);
+    }
+
+    #[cfg(feature = "wasm")]
+    fn fill(&self, dest: &mut [u8]) {
+        getrandom::getrandom(dest).expect("Fatal error: can't get rand bytes from system");
+    }
+}
 
 /// 获取指定长度的密码学安全随机数据
 pub fn genSecureRandBytes(len: usize) -> Vec<u8> {
-    let mut dst = vec![0; len];
-    let r = SystemRandom::new();
-    r.fill(&mut dst)
-        .expect("Fatal error: can't get rand bytes from system");
-
-    dst
+    OsRng.gen_bytes(len)
 }
 
 #[cfg(test)]
@@ -21,4 +64,25 @@ mod tests {
         let b = genSecureRandBytes(32);
         println!("{:?}", b);
     }
+
+    struct Repeat(u8);
+
+    impl SecureRandom for Repeat {
+        fn fill(&self, dest: &mut [u8]) {
+            dest.iter_mut().for_each(|b| *b = self.0);
+        }
+    }
+
+    #[test]
+    fn injected_rng() {
+        let rng = Repeat(7);
+        assert_eq!(rng.gen_bytes(3), [7, 7, 7]);
+        assert_eq!(
+            crate::ed25519::generate_keypair_with_rng(&rng),
+            crate::ed25519::keypair_from_seed(&[7; 32]).unwrap()
+        );
+        assert_eq!(crate::x25519::generate_keypair_with_rng(&rng).0, [7; 32]);
+        assert_eq!(ring::rand::SystemRandom::new().gen_bytes(16).len(), 16);
+        assert_eq!(OsRng.gen_bytes(16).len(), 16);
+    }
 }
@@ -0,0 +1,185 @@
+//! HMAC-DRBG (NIST SP 800-90A Rev.1 10.1.2)，使用 HMAC-SHA256
+//!
+//! 相同的种子总是产生相同的输出，用于可复现的测试和从种子确定性地派生密钥。
+//! 输出的安全性完全取决于种子，种子必须保密且至少有256位熵
+
+use std::cell::RefCell;
+
+use ring::hmac;
+
+use super::SecureRandom;
+use crate::ct::wipe;
+
+const OUT_LEN: usize = 32;
+/// 种子的最小长度，对应256位安全强度
+pub const MIN_ENTROPY_LEN: usize = 32;
+/// 单次生成的最大字节数 (2^19 位)
+pub const MAX_REQUEST_LEN: usize = 1 << 16;
+// 两次重新播种之间允许的最大生成次数
+const RESEED_INTERVAL: u64 = 1 << 48;
+
+/// 可播种的确定性随机数生成器
+///
+/// 实现了 SecureRandom，可以传给各个 *_with_rng 函数。内部状态用 RefCell 保存，不能跨线程共享
+pub struct HmacDrbg {
+    state: RefCell<State>,
+}
+
+struct State {
+    k: [u8; OUT_LEN],
+    v: [u8; OUT_LEN],
+    reseed_counter: u64,
+}
+
+impl HmacDrbg {
+    /// 实例化
+    ///
+    /// entropy: 熵输入，至少32字节
+    /// nonce: 随机数，可以为空
+    /// personalization: 个性化字符串，可以为空，用于区分不同用途
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self, String> {
+        check_entropy(entropy)?;
+        let mut state = State {
+            k: [0u8; OUT_LEN],
+            v: [1u8; OUT_LEN],
+            reseed_counter: 1,
+        };
+        state.update(&[entropy, nonce, personalization]);
+        Ok(HmacDrbg {
+            state: RefCell::new(state),
+        })
+    }
+
+    /// 从32字节种子实例化，nonce 和个性化字符串为空
+    pub fn from_seed(seed: &[u8; 32]) -> Self {
+        HmacDrbg::new(seed, &[], &[]).expect("random: 32 byte seed")
+    }
+
+    /// 重新播种
+    ///
+    /// entropy: 新的熵输入，至少32字节
+    /// additional: 附加输入，可以为空
+    pub fn reseed(&mut self, entropy: &[u8], additional: &[u8]) -> Result<(), String> {
+        check_entropy(entropy)?;
+        let state = self.state.get_mut();
+        state.update(&[entropy, additional]);
+        state.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// 生成随机数据填满 out
+    ///
+    /// out: 输出，最长 MAX_REQUEST_LEN 字节
+    /// additional: 附加输入，可以为空
+    pub fn generate(&self, out: &mut [u8], additional: &[u8]) -> Result<(), String> {
+        if out.len() > MAX_REQUEST_LEN {
+            return Err(format!(
+                "random: drbg request must be at most {} bytes",
+                MAX_REQUEST_LEN
+            ));
+        }
+        let mut state = self.state.borrow_mut();
+        if state.reseed_counter > RESEED_INTERVAL {
+            return Err("random: drbg reseed required".to_string());
+        }
+        if !additional.is_empty() {
+            state.update(&[additional]);
+        }
+        for chunk in out.chunks_mut(OUT_LEN) {
+            let v = state.hmac(&[&state.v]);
+            state.v = v;
+            chunk.copy_from_slice(&v[..chunk.len()]);
+        }
+        state.update(&[additional]);
+        state.reseed_counter += 1;
+        Ok(())
+    }
+}
+
+impl SecureRandom for HmacDrbg {
+    /// 超过 MAX_REQUEST_LEN 时分多次生成，需要重新播种时 panic
+    fn fill(&self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(MAX_REQUEST_LEN) {
+            self.generate(chunk, &[])
+                .expect("random: drbg generate failed");
+        }
+    }
+}
+
+impl State {
+    fn hmac(&self, parts: &[&[u8]]) -> [u8; OUT_LEN] {
+        let mut ctx = hmac::Context::with_key(&hmac::Key::new(hmac::HMAC_SHA256, &self.k));
+        for part in parts {
+            ctx.update(part);
+        }
+        let mut out = [0u8; OUT_LEN];
+        out.copy_from_slice(ctx.sign().as_ref());
+        out
+    }
+
+    /// HMAC_DRBG_Update，provided 为各部分拼接后的数据
+    fn update(&mut self, provided: &[&[u8]]) {
+        self.update_round(0x00, provided);
+        if provided.iter().any(|p| !p.is_empty()) {
+            self.update_round(0x01, provided);
+        }
+    }
+
+    // K = HMAC(K, V | round | provided)，V = HMAC(K, V)
+    fn update_round(&mut self, round: u8, provided: &[&[u8]]) {
+        let (v, round) = (self.v, [round]);
+        let mut parts: Vec<&[u8]> = vec![&v, &round];
+        parts.extend_from_slice(provided);
+        self.k = self.hmac(&parts);
+        self.v = self.hmac(&[&self.v]);
+    }
+}
+
+impl Drop for State {
+    fn drop(&mut self) {
+        wipe(&mut self.k);
+        wipe(&mut self.v);
+    }
+}
+
+fn check_entropy(entropy: &[u8]) -> Result<(), String> {
+    if entropy.len() < MIN_ENTROPY_LEN {
+        return Err(format!(
+            "random: drbg entropy must be at least {} bytes",
+            MIN_ENTROPY_LEN
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_hmac_drbg() {
+        // NIST CAVP HMAC_DRBG.rsp, SHA-256, PredictionResistance = False, COUNT = 0
+        let entropy =
+            Vec::from_hex("ca851911349384bffe89de1cbdc46e6831e44d34a4fb935ee285dd14b71a7488")
+                .unwrap();
+        let nonce = Vec::from_hex("659ba96c601dc69fc902940805ec0ca8").unwrap();
+        let drbg = HmacDrbg::new(&entropy, &nonce, &[]).unwrap();
+        let mut out = [0u8; 128];
+        drbg.generate(&mut out, &[]).unwrap();
+        drbg.generate(&mut out, &[]).unwrap();
+        assert_eq!(
+            out.to_vec(),
+            Vec::from_hex("e528e9abf2dece54d47c7e75e5fe302149f817ea9fb4bee6f4199697d04d5b89d54fbb978a15b5c443c9ec21036d2460b6f73ebad0dc2aba6e624abf07745bc107694bb7547bb0995f70de25d6b29e2d3011bb19d27676c07162c8b5ccde0668961df86803482cb37ed6d5c0bb8d50cf1f50d476aa0458bdaba806f48be9dcb8").unwrap()
+        );
+
+        // 相同种子得到相同的密钥
+        let a = crate::ed25519::generate_keypair_with_rng(&HmacDrbg::from_seed(&[9; 32]));
+        let b = crate::ed25519::generate_keypair_with_rng(&HmacDrbg::from_seed(&[9; 32]));
+        assert_eq!(a, b);
+        assert!(HmacDrbg::new(&[0; 31], &[], &[]).is_err());
+        let mut big = vec![0; MAX_REQUEST_LEN + 1];
+        assert!(drbg.generate(&mut big, &[]).is_err());
+        drbg.fill(&mut big);
+    }
+}
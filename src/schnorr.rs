@@ -0,0 +1,423 @@
+//! secp256k1 Schnorr 签名 (BIP-340)
+//!
+//! 公钥为32字节的 x-only 格式(隐含 y 为偶数)，签名为64字节 R.x | s。
+//! 签名方调用 sign_randomized 使用系统随机数，需要可复现的签名时向 sign 传入固定的辅助随机数
+
+use secp256k1::curve::{Affine, Field, Jacobian, Scalar, ECMULT_CONTEXT, ECMULT_GEN_CONTEXT};
+
+use crate::digest::tagged_hash;
+use crate::random::{genSecureRandBytes, OsRng, SecureRandom};
+
+/// x-only 公钥长度
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+/// 签名长度
+pub const SIGNATURE_SIZE: usize = 64;
+
+/// 从私钥计算 x-only 公钥
+pub fn xonly_public_key(sk: &[u8]) -> Result<[u8; PUBLIC_KEY_SIZE], String> {
+    let d = secret_scalar(sk)?;
+    Ok(point_bytes(&mul_gen(&d)).0)
+}
+
+/// 签名
+///
+/// msg: 消息，BIP-340 不做预哈希
+/// sk: 32字节私钥
+/// aux_rand: 32字节辅助随机数，全零时签名是确定性的
+pub fn sign(msg: &[u8], sk: &[u8], aux_rand: &[u8; 32]) -> Result<[u8; SIGNATURE_SIZE], String> {
+    let d0 = secret_scalar(sk)?;
+    let (px, p_odd) = point_bytes(&mul_gen(&d0));
+    let d = if p_odd { -d0 } else { d0 };
+
+    let mut t = d.b32();
+    for (a, b) in t
+        .iter_mut()
+        .zip(tagged_hash(b"BIP0340/aux", aux_rand).iter())
+    {
+        *a ^= b;
+    }
+    let rand = tagged_hash(b"BIP0340/nonce", &[&t, &px, msg].concat());
+    let k0 = reduce(&rand);
+    if k0.is_zero() {
+        return Err("schnorr: bad nonce".to_string());
+    }
+    let (rx, r_odd) = point_bytes(&mul_gen(&k0));
+    let k = if r_odd { -k0 } else { k0 };
+    let e = challenge(&rx, &px, msg);
+
+    let mut sig = [0u8; SIGNATURE_SIZE];
+    sig[..32].copy_from_slice(&rx);
+    sig[32..].copy_from_slice(&(k + e * d).b32());
+    Ok(sig)
+}
+
+/// 使用系统随机数作为辅助随机数签名，BIP-340 推荐的默认用法，可以减轻侧信道和故障注入攻击
+///
+/// msg: 消息
+/// sk: 32字节私钥
+pub fn sign_randomized(msg: &[u8], sk: &[u8]) -> Result<[u8; SIGNATURE_SIZE], String> {
+    sign_with_rng(msg, sk, &OsRng)
+}
+
+/// 使用指定的随机数生成器产生辅助随机数签名
+///
+/// msg: 消息
+/// sk: 32字节私钥
+/// rng: 随机数生成器
+pub fn sign_with_rng(
+    msg: &[u8],
+    sk: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<[u8; SIGNATURE_SIZE], String> {
+    let mut aux_rand = [0u8; 32];
+    rng.fill(&mut aux_rand);
+    sign(msg, sk, &aux_rand)
+}
+
+/// 验证签名
+///
+/// msg: 消息
+/// pk: 32字节 x-only 公钥
+/// sig: 64字节签名
+pub fn verify(msg: &[u8], pk: &[u8], sig: &[u8]) -> bool {
+    if pk.len() != PUBLIC_KEY_SIZE || sig.len() != SIGNATURE_SIZE {
+        return false;
+    }
+    let p = match lift_x(pk) {
+        Some(p) => p,
+        None => return false,
+    };
+    let mut r = Field::default();
+    if !r.set_b32(&to_array(&sig[..32])) {
+        return false;
+    }
+    let mut s = Scalar::default();
+    if bool::from(s.set_b32(&to_array(&sig[32..]))) {
+        return false;
+    }
+    let e = challenge(&sig[..32], pk, msg);
+
+    // R = sG - eP
+    let mut point = Jacobian::default();
+    ECMULT_CONTEXT.ecmult(&mut point, &Jacobian::from_ge(&p), &-e, &s);
+    if point.is_infinity() {
+        return false;
+    }
+    let (rx, r_odd) = point_bytes(&Affine::from_gej(&point));
+    !r_odd && rx == sig[..32]
+}
+
+/// 批量验证签名
+///
+/// 使用随机线性组合检查 (a1*s1 + ... + an*sn)G = Σ ai*Ri + Σ ai*ei*Pi，ai 为128位随机数，
+/// 所有签名都有效时返回true；返回false时无法得知是哪个签名无效，需要逐个调用 verify
+///
+/// items: (消息, x-only 公钥, 签名)
+pub fn verify_batch(items: &[(&[u8], &[u8], &[u8])]) -> bool {
+    let mut terms = Vec::with_capacity(items.len() * 2);
+    let mut s_sum = Scalar::default();
+    for (i, (msg, pk, sig)) in items.iter().enumerate() {
+        if pk.len() != PUBLIC_KEY_SIZE || sig.len() != SIGNATURE_SIZE {
+            return false;
+        }
+        let (p, r) = match (lift_x(pk), lift_x(&sig[..32])) {
+            (Some(p), Some(r)) => (p, r),
+            _ => return false,
+        };
+        let mut s = Scalar::default();
+        if bool::from(s.set_b32(&to_array(&sig[32..]))) {
+            return false;
+        }
+        let e = challenge(&sig[..32], pk, msg);
+
+        // 第一个系数固定为1
+        let a = if i == 0 {
+            Scalar::from_int(1)
+        } else {
+            let mut bytes = [0u8; 32];
+            bytes[16..].copy_from_slice(&genSecureRandBytes(16));
+            bytes[16] |= 0x80;
+            reduce(&bytes)
+        };
+        s_sum += &(a.clone() * s);
+        terms.push((r, a.clone()));
+        terms.push((p, a * e));
+    }
+    if terms.is_empty() {
+        return true;
+    }
+    let mut sum = multi_mul(&terms);
+    let mut sg = Jacobian::default();
+    ECMULT_GEN_CONTEXT.ecmult_gen(&mut sg, &s_sum);
+    sum = sum.add_var(&sg.neg(), None);
+    sum.is_infinity()
+}
+
+// 以下为 taproot 等模块共用的曲线运算
+
+/// 解析私钥，必须在 [1, n-1] 范围内
+pub(crate) fn secret_scalar(sk: &[u8]) -> Result<Scalar, String> {
+    if sk.len() != 32 {
+        return Err("schnorr: secret key must be 32 bytes".to_string());
+    }
+    let mut d = Scalar::default();
+    if bool::from(d.set_b32(&to_array(sk))) || d.is_zero() {
+        return Err("schnorr: invalid secret key".to_string());
+    }
+    Ok(d)
+}
+
+/// 32字节大端整数模 n
+pub(crate) fn reduce(bytes: &[u8; 32]) -> Scalar {
+    let mut s = Scalar::default();
+    let _ = s.set_b32(bytes);
+    s
+}
+
+/// 由 x 坐标恢复 y 为偶数的点
+pub(crate) fn lift_x(x: &[u8]) -> Option<Affine> {
+    let mut fx = Field::default();
+    if x.len() != 32 || !fx.set_b32(&to_array(x)) {
+        return None;
+    }
+    let mut p = Affine::default();
+    if !p.set_xo_var(&fx, false) {
+        return None;
+    }
+    p.x.normalize();
+    p.y.normalize();
+    Some(p)
+}
+
+pub(crate) fn mul_gen(k: &Scalar) -> Affine {
+    let mut point = Jacobian::default();
+    ECMULT_GEN_CONTEXT.ecmult_gen(&mut point, k);
+    Affine::from_gej(&point)
+}
+
+/// 点的 x 坐标和 y 是否为奇数
+pub(crate) fn point_bytes(p: &Affine) -> ([u8; 32], bool) {
+    let mut x = p.x.clone();
+    let mut y = p.y.clone();
+    x.normalize();
+    y.normalize();
+    (x.b32(), y.is_odd())
+}
+
+fn challenge(rx: &[u8], px: &[u8], msg: &[u8]) -> Scalar {
+    reduce(&tagged_hash(b"BIP0340/challenge", &[rx, px, msg].concat()))
+}
+
+const WINDOW: usize = 5;
+
+// Strauss 多标量乘法，所有点共用倍点运算
+fn multi_mul(terms: &[(Affine, Scalar)]) -> Jacobian {
+    let tables: Vec<Vec<Jacobian>> = terms.iter().map(|(p, _)| odd_multiples(p)).collect();
+    let digits: Vec<[i32; 257]> = terms.iter().map(|(_, k)| wnaf(&k.b32())).collect();
+    let mut r = Jacobian::default();
+    r.set_infinity();
+    for i in (0..257).rev() {
+        r = r.double_var(None);
+        for (table, digits) in tables.iter().zip(digits.iter()) {
+            let d = digits[i];
+            if d > 0 {
+                r = r.add_var(&table[(d as usize - 1) / 2], None);
+            } else if d < 0 {
+                r = r.add_var(&table[((-d) as usize - 1) / 2].neg(), None);
+            }
+        }
+    }
+    r
+}
+
+// P, 3P, 5P, ..., 15P
+fn odd_multiples(p: &Affine) -> Vec<Jacobian> {
+    let p = Jacobian::from_ge(p);
+    let p2 = p.double_var(None);
+    let mut table = vec![p];
+    for i in 1..1 << (WINDOW - 2) {
+        let next = table[i - 1].add_var(&p2, None);
+        table.push(next);
+    }
+    table
+}
+
+// 宽度为 WINDOW 的非相邻形式，每个非零位都是奇数且绝对值小于 2^(WINDOW-1)
+fn wnaf(bytes: &[u8; 32]) -> [i32; 257] {
+    let mut limbs = [0u64; 5];
+    for (i, chunk) in bytes.rchunks(8).enumerate() {
+        limbs[i] = u64::from_be_bytes(to_array8(chunk));
+    }
+    let mut digits = [0i32; 257];
+    for d in digits.iter_mut() {
+        if limbs.iter().all(|l| *l == 0) {
+            break;
+        }
+        if limbs[0] & 1 == 1 {
+            let mut v = (limbs[0] & ((1 << WINDOW) - 1)) as i32;
+            if v >= 1 << (WINDOW - 1) {
+                v -= 1 << WINDOW;
+            }
+            *d = v;
+            if v > 0 {
+                limbs[0] -= v as u64;
+            } else {
+                add_small(&mut limbs, (-v) as u64);
+            }
+        }
+        for i in 0..4 {
+            limbs[i] = (limbs[i] >> 1) | (limbs[i + 1] << 63);
+        }
+        limbs[4] >>= 1;
+    }
+    digits
+}
+
+fn add_small(limbs: &mut [u64; 5], v: u64) {
+    let mut carry = v;
+    for l in limbs.iter_mut() {
+        let (sum, overflow) = l.overflowing_add(carry);
+        *l = sum;
+        carry = overflow as u64;
+        if carry == 0 {
+            break;
+        }
+    }
+}
+
+fn to_array8(bytes: &[u8]) -> [u8; 8] {
+    let mut out = [0u8; 8];
+    out.copy_from_slice(bytes);
+    out
+}
+
+fn to_array(bytes: &[u8]) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out.copy_from_slice(bytes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    // test vectors from: https://github.com/bitcoin/bips/blob/master/bip-0340/test-vectors.csv
+    // (index, 私钥, 公钥, 辅助随机数, 消息, 签名, 验证结果)，私钥为空的行只用于验证
+    const BIP340_VECTORS: &[(usize, &str, &str, &str, &str, &str, bool)] = &[
+        (0, "0000000000000000000000000000000000000000000000000000000000000003", "F9308A019258C31049344F85F89D5229B531C845836F99B08601F113BCE036F9", "0000000000000000000000000000000000000000000000000000000000000000", "0000000000000000000000000000000000000000000000000000000000000000", "E907831F80848D1069A5371B402410364BDF1C5F8307B0084C55F1CE2DCA821525F66A4A85EA8B71E482A74F382D2CE5EBEEE8FDB2172F477DF4900D310536C0", true),
+        (1, "B7E151628AED2A6ABF7158809CF4F3C762E7160F38B4DA56A784D9045190CFEF", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "0000000000000000000000000000000000000000000000000000000000000001", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "6896BD60EEAE296DB48A229FF71DFE071BDE413E6D43F917DC8DCF8C78DE33418906D11AC976ABCCB20B091292BFF4EA897EFCB639EA871CFA95F6DE339E4B0A", true),
+        (2, "C90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74020BBEA63B14E5C9", "DD308AFEC5777E13121FA72B9CC1B7CC0139715309B086C960E18FD969774EB8", "C87AA53824B4D7AE2EB035A2B5BBBCCC080E76CDC6D1692C4B0B62D798E6D906", "7E2D58D8B3BCDF1ABADEC7829054F90DDA9805AAB56C77333024B9D0A508B75C", "5831AAEED7B44BB74E5EAB94BA9D4294C49BCF2A60728D8B4C200F50DD313C1BAB745879A5AD954A72C45A91C3A51D3C7ADEA98D82F8481E0E1E03674A6F3FB7", true),
+        (3, "0B432B2677937381AEF05BB02A66ECD012773062CF3FA2549E44F58ED2401710", "25D1DFF95105F5253C4022F628A996AD3A0D95FBF21D468A1B33F8C160D8F517", "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF", "7EB0509757E246F19449885651611CB965ECC1A187DD51B64FDA1EDC9637D5EC97582B9CB13DB3933705B32BA982AF5AF25FD78881EBB32771FC5922EFC66EA3", true),
+        (4, "", "D69C3509BB99E412E68B0FE8544E72837DFA30746D8BE2AA65975F29D22DC7B9", "", "4DF3C3F68FCC83B27E9D42C90431A72499F17875C81A599B566C9889B9696703", "00000000000000000000003B78CE563F89A0ED9414F5AA28AD0D96D6795F9C6376AFB1548AF603B3EB45C9F8207DEE1060CB71C04E80F593060B07D28308D7F4", true),
+        (5, "", "EEFDEA4CDB677750A420FEE807EACF21EB9898AE79B9768766E4FAA04A2D4A34", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B", false),
+        (6, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "FFF97BD5755EEEA420453A14355235D382F6472F8568A18B2F057A14602975563CC27944640AC607CD107AE10923D9EF7A73C643E166BE5EBEAFA34B1AC553E2", false),
+        (7, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "1FA62E331EDBC21C394792D2AB1100A7B432B013DF3F6FF4F99FCB33E0E1515F28890B3EDB6E7189B630448B515CE4F8622A954CFE545735AAEA5134FCCDB2BD", false),
+        (8, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769961764B3AA9B2FFCB6EF947B6887A226E8D7C93E00C5ED0C1834FF0D0C2E6DA6", false),
+        (9, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "0000000000000000000000000000000000000000000000000000000000000000123DDA8328AF9C23A94C1FEECFD123BA4FB73476F0D594DCB65C6425BD186051", false),
+        (10, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "00000000000000000000000000000000000000000000000000000000000000017615FBAF5AE28864013C099742DEADB4DBA87F11AC6754F93780D5A1837CF197", false),
+        (11, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "4A298DACAE57395A15D0795DDBFD1DCB564DA82B0F269BC70A74F8220429BA1D69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B", false),
+        (12, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC2F69E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B", false),
+        (13, "", "DFF1D77F2A671C5F36183726DB2341BE58FEAE1DA2DECED843240F7B502BA659", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E177769FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141", false),
+        (14, "", "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFC30", "", "243F6A8885A308D313198A2E03707344A4093822299F31D0082EFA98EC4E6C89", "6CFF5C3BA86C69EA4B7376F31A9BCB4F74C1976089B2D9963DA2E5543E17776969E89B4C5564D00349106B8497785DD7D1D713A8AE82B32FA79D5F7FC407D39B", false),
+        (15, "0340034003400340034003400340034003400340034003400340034003400340", "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117", "0000000000000000000000000000000000000000000000000000000000000000", "", "71535DB165ECD9FBBC046E5FFAEA61186BB6AD436732FCCC25291A55895464CF6069CE26BF03466228F19A3A62DB8A649F2D560FAC652827D1AF0574E427AB63", true),
+        (16, "0340034003400340034003400340034003400340034003400340034003400340", "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117", "0000000000000000000000000000000000000000000000000000000000000000", "11", "08A20A0AFEF64124649232E0693C583AB1B9934AE63B4C3511F3AE1134C6A303EA3173BFEA6683BD101FA5AA5DBC1996FE7CACFC5A577D33EC14564CEC2BACBF", true),
+        (17, "0340034003400340034003400340034003400340034003400340034003400340", "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117", "0000000000000000000000000000000000000000000000000000000000000000", "0102030405060708090A0B0C0D0E0F1011", "5130F39A4059B43BC7CAC09A19ECE52B5D8699D1A71E3C52DA9AFDB6B50AC370C4A482B77BF960F8681540E25B6771ECE1E5A37FD80E5A51897C5566A97EA5A5", true),
+        (18, "0340034003400340034003400340034003400340034003400340034003400340", "778CAA53B4393AC467774D09497A87224BF9FAB6F6E68B23086497324D6FD117", "0000000000000000000000000000000000000000000000000000000000000000", "99999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999999", "403B12B0D8555A344175EA7EC746566303321E5DBFA8BE6F091635163ECA79A8585ED3E3170807E7C03B720FC54C7B23897FCBA0E9D0B4A06894CFD249F22367", true),
+    ];
+
+    #[test]
+    fn test_bip340() {
+        for &(index, sk, pk, aux, msg, sig, valid) in BIP340_VECTORS {
+            let pk = Vec::from_hex(pk).unwrap();
+            let msg = Vec::from_hex(msg).unwrap();
+            let sig = Vec::from_hex(sig).unwrap();
+            if !sk.is_empty() {
+                let sk = Vec::from_hex(sk).unwrap();
+                let mut aux_rand = [0u8; 32];
+                aux_rand.copy_from_slice(&Vec::from_hex(aux).unwrap());
+                assert_eq!(
+                    xonly_public_key(&sk).unwrap().to_vec(),
+                    pk,
+                    "index {}",
+                    index
+                );
+                assert_eq!(
+                    sign(&msg, &sk, &aux_rand).unwrap().to_vec(),
+                    sig,
+                    "index {}",
+                    index
+                );
+            }
+            assert_eq!(verify(&msg, &pk, &sig), valid, "index {}", index);
+        }
+
+        let sk = Vec::from_hex(BIP340_VECTORS[1].1).unwrap();
+        let pk = Vec::from_hex(BIP340_VECTORS[1].2).unwrap();
+        let msg = Vec::from_hex(BIP340_VECTORS[1].4).unwrap();
+        let mut aux = [0u8; 32];
+        aux[31] = 1;
+        let sig = sign(&msg, &sk, &aux).unwrap();
+        assert!(!verify(&msg[1..], &pk, &sig));
+
+        let mut bad = sig;
+        bad[63] ^= 1;
+        assert!(!verify(&msg, &pk, &bad));
+        assert!(sign(&msg, &[0u8; 32], &aux).is_err());
+
+        let randomized = sign_randomized(&msg, &sk).unwrap();
+        assert_ne!(randomized, sig);
+        assert!(verify(&msg, &pk, &randomized));
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let msgs: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i; 32]).collect();
+        let mut items = Vec::new();
+        for (i, msg) in msgs.iter().enumerate() {
+            let sk = [i as u8 + 1; 32];
+            let pk = xonly_public_key(&sk).unwrap();
+            let sig = sign(msg, &sk, &[0u8; 32]).unwrap();
+            items.push((msg.clone(), pk.to_vec(), sig.to_vec()));
+        }
+        let batch = |items: &[(Vec<u8>, Vec<u8>, Vec<u8>)]| {
+            let refs: Vec<(&[u8], &[u8], &[u8])> = items
+                .iter()
+                .map(|(m, p, s)| (&m[..], &p[..], &s[..]))
+                .collect();
+            verify_batch(&refs)
+        };
+        assert!(verify_batch(&[]));
+        assert!(batch(&items[..1]));
+        assert!(batch(&items));
+
+        // 任意一个签名无效则整批失败
+        let mut bad = items.clone();
+        bad[5].0[0] ^= 1;
+        assert!(!batch(&bad));
+        let mut bad = items.clone();
+        bad[7].2[40] ^= 1;
+        assert!(!batch(&bad));
+        let mut bad = items;
+        bad.swap(0, 1);
+        bad[0].0 = bad[1].0.clone();
+        assert!(!batch(&bad));
+        // BIP-340 的有效向量可以整批通过，混入任意一个无效向量则整批失败
+        let decode = |&(_, _, pk, _, msg, sig, _): &(usize, &str, &str, &str, &str, &str, bool)| {
+            (
+                Vec::from_hex(msg).unwrap(),
+                Vec::from_hex(pk).unwrap(),
+                Vec::from_hex(sig).unwrap(),
+            )
+        };
+        let (valid, invalid): (Vec<_>, Vec<_>) = BIP340_VECTORS.iter().partition(|v| v.6);
+        let valid: Vec<_> = valid.into_iter().map(decode).collect();
+        assert!(batch(&valid));
+        for vector in invalid {
+            let mut mixed = valid.clone();
+            mixed.insert(vector.0 % valid.len(), decode(vector));
+            assert!(!batch(&mixed), "index {}", vector.0);
+        }
+    }
+}
@@ -0,0 +1,96 @@
+//! 秘密数据的内存擦除
+//!
+//! SecretBytes 在离开作用域时擦除保存的数据，用来承载私钥、对称密钥、解密出的明文密钥等。
+//! 只能擦除自己持有的内存，调用者复制出去的数据需要自行处理
+
+use std::fmt;
+use std::ops::Deref;
+
+use crate::ct::{ct_eq, wipe};
+
+/// 离开作用域时自动擦除的字节缓冲
+///
+/// Debug 不输出内容，比较为常量时间
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// 接管数据，不会复制
+    pub fn new(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+
+    /// 长度为 len 的全0缓冲，用于写入派生出的密钥
+    pub fn zeroed(len: usize) -> Self {
+        SecretBytes(vec![0u8; len])
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn as_mut_bytes(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl From<&[u8]> for SecretBytes {
+    fn from(bytes: &[u8]) -> Self {
+        SecretBytes(bytes.to_vec())
+    }
+}
+
+impl Deref for SecretBytes {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for SecretBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl PartialEq for SecretBytes {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SecretBytes {}
+
+impl fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes([REDACTED; {}])", self.0.len())
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        wipe(&mut self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_bytes() {
+        let mut key = SecretBytes::zeroed(4);
+        key.as_mut_bytes().copy_from_slice(b"abcd");
+        assert_eq!(&*key, b"abcd");
+        assert_eq!(key, SecretBytes::from(&b"abcd"[..]));
+        assert_ne!(key, SecretBytes::new(b"abce".to_vec()));
+        assert_eq!(format!("{:?}", key), "SecretBytes([REDACTED; 4])");
+    }
+}
@@ -0,0 +1,265 @@
+//! serde 支持，需要开启 serde 特性
+//!
+//! 密钥、签名和指纹在 JSON 等人类可读格式中序列化为小写hex字符串，在 CBOR、bincode 等二进制格式中序列化为字节串。
+//! 需要固定表示时在字段上使用 #[serde(with = "pi_crypto::serialization::hex")] 或 "pi_crypto::serialization::bytes"，
+//! 二者也可以用于 Vec<u8> 字段。BIP-32 扩展密钥总是序列化为 xprv / xpub 字符串
+//!
+//! 私钥序列化时的中间缓冲会被擦除，但序列化器输出的字符串或字节由调用方负责保护
+
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::ed25519;
+use crate::fingerprint::Fingerprint;
+use crate::hd::{ExtendedPrivateKey, ExtendedPublicKey};
+use crate::secret::SecretBytes;
+use crate::signature::{AnyPublicKey, AnySignature, Secp256k1PublicKey};
+
+/// 可以表示为字节串的类型
+pub trait ByteRepr: Sized {
+    /// 序列化使用的字节
+    fn to_repr(&self) -> SecretBytes;
+
+    /// 从字节恢复，格式或长度错误时返回错误
+    fn from_repr(bytes: &[u8]) -> Result<Self, String>;
+}
+
+/// 序列化为hex字符串
+pub mod hex {
+    use super::*;
+
+    pub fn serialize<T: ByteRepr, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&crate::encoding::hex::encode(&value.to_repr()))
+    }
+
+    pub fn deserialize<'de, T: ByteRepr, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let s = String::deserialize(d)?;
+        let bytes = SecretBytes::new(crate::encoding::hex::decode(&s).map_err(de::Error::custom)?);
+        T::from_repr(&bytes).map_err(de::Error::custom)
+    }
+}
+
+/// 序列化为字节串，不支持字节串的格式(如 JSON)中为整数数组
+pub mod bytes {
+    use super::*;
+
+    pub fn serialize<T: ByteRepr, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_bytes(&value.to_repr())
+    }
+
+    pub fn deserialize<'de, T: ByteRepr, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let bytes = d.deserialize_byte_buf(BytesVisitor)?;
+        T::from_repr(&bytes).map_err(de::Error::custom)
+    }
+
+    struct BytesVisitor;
+
+    impl<'de> Visitor<'de> for BytesVisitor {
+        type Value = SecretBytes;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a byte string")
+        }
+
+        fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<SecretBytes, E> {
+            Ok(SecretBytes::from(v))
+        }
+
+        fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<SecretBytes, E> {
+            Ok(SecretBytes::new(v))
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<SecretBytes, A::Error> {
+            let mut out = Vec::with_capacity(seq.size_hint().unwrap_or(0));
+            while let Some(b) = seq.next_element::<u8>()? {
+                out.push(b);
+            }
+            Ok(SecretBytes::new(out))
+        }
+    }
+}
+
+impl ByteRepr for Vec<u8> {
+    fn to_repr(&self) -> SecretBytes {
+        SecretBytes::from(&self[..])
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl ByteRepr for SecretBytes {
+    fn to_repr(&self) -> SecretBytes {
+        self.clone()
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        Ok(SecretBytes::from(bytes))
+    }
+}
+
+impl ByteRepr for Fingerprint {
+    fn to_repr(&self) -> SecretBytes {
+        SecretBytes::from(&self.as_bytes()[..])
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != 32 {
+            return Err("serialization: fingerprint must be 32 bytes".to_string());
+        }
+        let mut fp = [0u8; 32];
+        fp.copy_from_slice(bytes);
+        Ok(Fingerprint::from_bytes(fp))
+    }
+}
+
+/// 使用33字节压缩编码，解析时也接受65字节未压缩编码
+impl ByteRepr for Secp256k1PublicKey {
+    fn to_repr(&self) -> SecretBytes {
+        SecretBytes::from(&self.compressed[..])
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != 33 && bytes.len() != 65 {
+            return Err("serialization: secp256k1 public key must be 33 or 65 bytes".to_string());
+        }
+        secp256k1::PublicKey::parse_slice(bytes, None)
+            .map(Secp256k1PublicKey::from)
+            .map_err(|_| "serialization: invalid secp256k1 public key".to_string())
+    }
+}
+
+/// 使用 AnyPublicKey::encode 的带标签格式
+impl ByteRepr for AnyPublicKey {
+    fn to_repr(&self) -> SecretBytes {
+        SecretBytes::new(self.encode())
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        Ok(AnyPublicKey::decode(bytes)?)
+    }
+}
+
+/// 使用 AnySignature::encode 的带标签格式
+impl ByteRepr for AnySignature {
+    fn to_repr(&self) -> SecretBytes {
+        SecretBytes::new(self.encode())
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        Ok(AnySignature::decode(bytes)?)
+    }
+}
+
+/// 使用32字节种子，解析时也接受64字节扩展格式
+impl ByteRepr for ed25519::SecretKey {
+    fn to_repr(&self) -> SecretBytes {
+        SecretBytes::from(&self.seed()[..])
+    }
+
+    fn from_repr(bytes: &[u8]) -> Result<Self, String> {
+        Ok(ed25519::SecretKey::from_bytes(bytes)?)
+    }
+}
+
+macro_rules! impl_serde {
+    ($($t:ty),*) => {$(
+        impl Serialize for $t {
+            fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+                if s.is_human_readable() {
+                    hex::serialize(self, s)
+                } else {
+                    bytes::serialize(self, s)
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $t {
+            fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+                if d.is_human_readable() {
+                    hex::deserialize(d)
+                } else {
+                    bytes::deserialize(d)
+                }
+            }
+        }
+    )*};
+}
+
+impl_serde!(
+    SecretBytes,
+    Fingerprint,
+    Secp256k1PublicKey,
+    AnyPublicKey,
+    AnySignature,
+    ed25519::SecretKey
+);
+
+impl Serialize for ExtendedPrivateKey {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_xprv())
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedPrivateKey {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        ExtendedPrivateKey::from_xprv(&String::deserialize(d)?).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ExtendedPublicKey {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&self.to_xpub())
+    }
+}
+
+impl<'de> Deserialize<'de> for ExtendedPublicKey {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        ExtendedPublicKey::from_xpub(&String::deserialize(d)?).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::SignatureScheme;
+
+    #[derive(Serialize, Deserialize)]
+    struct Config {
+        key: ed25519::SecretKey,
+        #[serde(with = "bytes")]
+        public_key: AnyPublicKey,
+        #[serde(with = "hex")]
+        raw: Vec<u8>,
+    }
+
+    #[test]
+    fn test_serde() {
+        let key = ed25519::SecretKey::from_seed(&[7u8; 32]).unwrap();
+        let public_key = AnyPublicKey::new(SignatureScheme::Ed25519, &key.public_key()).unwrap();
+        let config = Config {
+            key: key.clone(),
+            public_key: public_key.clone(),
+            raw: vec![0xde, 0xad],
+        };
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["key"], "07".repeat(32));
+        assert_eq!(json["public_key"][0], 2);
+        assert_eq!(json["raw"], "dead");
+        let decoded: Config = serde_json::from_value(json).unwrap();
+        assert!(decoded.key == key);
+        assert_eq!(decoded.public_key, public_key);
+        assert_eq!(decoded.raw, [0xde, 0xad]);
+
+        let xprv = crate::hd::ExtendedPrivateKey::new_master(&[1u8; 16]).unwrap();
+        let json = serde_json::to_string(&xprv.public_key()).unwrap();
+        assert!(json.starts_with("\"xpub"));
+        let xpub: ExtendedPublicKey = serde_json::from_str(&json).unwrap();
+        assert_eq!(xpub, xprv.public_key());
+        assert!(serde_json::from_str::<Fingerprint>("\"00\"").is_err());
+        assert!(serde_json::from_str::<SecretBytes>("\"0g\"").is_err());
+    }
+}
@@ -1,80 +1,557 @@
 //! ecdsa, rsa 签名算法
 
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crypto::digest::Digest;
+use crypto::sha3::Sha3;
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Zero};
 use ring::signature::{
     EcdsaKeyPair as EcKeyPair, KeyPair, RsaKeyPair, ECDSA_P256_SHA256_ASN1,
-    ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P384_SHA384_ASN1, ECDSA_P384_SHA384_ASN1_SIGNING,
+    ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P256_SHA256_FIXED, ECDSA_P256_SHA256_FIXED_SIGNING,
+    ECDSA_P384_SHA384_ASN1, ECDSA_P384_SHA384_ASN1_SIGNING, ECDSA_P384_SHA384_FIXED,
+    ECDSA_P384_SHA384_FIXED_SIGNING,
 };
 use ring::{rand, signature};
-use secp256k1::{sign, verify, Message, PublicKey, SecretKey, Signature};
-use simple_asn1::ASN1Block;
+use secp256k1::{recover, sign, verify, Message, PublicKey, RecoveryId, SecretKey, Signature};
+use simple_asn1::{oid, to_der, ASN1Block, OID};
+
+use crate::ct::{ct_eq, ct_is_zero, ct_lt_usize, ct_select_usize};
+use crate::error::{CryptoError, VerifyFailure};
+use crate::kdf::Pbkdf2;
+use crate::metrics::{Operation, Timer};
+use crate::pkcs8::{decrypt_pkcs8, encrypt_pkcs8};
+use crate::random::{genSecureRandBytes, OsRng, SecureRandom};
+use crate::secret::SecretBytes;
+use crate::spki::{normalize_public_key, PublicKeyType};
+
+/// secp256k1 对任意长度消息签名前使用的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MsgDigest {
+    /// SHA256，默认
+    #[default]
+    Sha256,
+    /// 以太坊使用的 Keccak-256(不是标准化后的 SHA3-256)
+    Keccak256,
+    /// 比特币使用的 SHA256(SHA256(msg))
+    DoubleSha256,
+}
+
+impl MsgDigest {
+    /// 计算32字节的消息哈希
+    pub fn hash(self, msg: &[u8]) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        match self {
+            MsgDigest::Sha256 => {
+                out.copy_from_slice(ring::digest::digest(&ring::digest::SHA256, msg).as_ref())
+            }
+            MsgDigest::Keccak256 => {
+                let mut hasher = Sha3::keccak256();
+                hasher.input(msg);
+                hasher.result(&mut out);
+            }
+            MsgDigest::DoubleSha256 => out = crate::digest::sha256d(msg),
+        }
+        out
+    }
+}
+
+/// secp256k1 ECDH 对共享点的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EcdhHash {
+    /// SHA256(压缩编码的共享点)，与 libsecp256k1 和 bitcoin-core 的 ECDH 相同
+    #[default]
+    Sha256,
+    /// 共享点的32字节 x 坐标，由调用方自行派生密钥
+    RawX,
+    /// 33字节压缩编码的共享点
+    Compressed,
+}
+
+/// secp256k1 公钥的压缩(33字节)和未压缩(65字节)编码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Secp256k1PublicKey {
+    pub compressed: Vec<u8>,
+    pub uncompressed: Vec<u8>,
+}
+
+impl From<PublicKey> for Secp256k1PublicKey {
+    fn from(pk: PublicKey) -> Self {
+        Secp256k1PublicKey {
+            compressed: pk.serialize_compressed().to_vec(),
+            uncompressed: pk.serialize().to_vec(),
+        }
+    }
+}
 
 /// 基于secp256k1的签名算法对象
+///
+/// 签名的随机数按 RFC 6979 由私钥和消息确定性地生成，同一私钥对同一消息总是得到相同的签名，
+/// 签名的 s 总是小于 n/2 (low-s)
+///
+/// libsecp256k1 的签名和验证预计算表是编译期生成的静态数据，所有实例共享同一份上下文，
+/// 对象本身不占内存，可以随意复制或跨线程共享，不需要缓存全局实例
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct ECDSASecp256k1 {}
 
+/// secp256k1 阶的一半，大于它的 s 是可延展的 high-s 签名
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
 impl ECDSASecp256k1 {
     /// 构建基于secp256k1的签名算法对象
     pub fn new() -> Self {
         ECDSASecp256k1 {}
     }
 
+    /// 只用于签名的对象，与 new 相同，静态上下文无法按用途裁剪
+    pub fn signing_only() -> Self {
+        ECDSASecp256k1 {}
+    }
+
+    /// 只用于验证的对象，与 new 相同，静态上下文无法按用途裁剪
+    pub fn verification_only() -> Self {
+        ECDSASecp256k1 {}
+    }
+
+    /// 生成密钥对，返回32字节私钥和公钥
+    ///
+    /// rng: 随机数生成器，一般为 OsRng 或 ring::rand::SystemRandom
+    pub fn generate_keypair(
+        &self,
+        rng: &dyn SecureRandom,
+    ) -> Result<(Vec<u8>, Secp256k1PublicKey), CryptoError> {
+        // 随机数不在 [1, n-1] 内的概率约为 2^-128，多次失败说明随机数生成器有问题
+        for _ in 0..16 {
+            let mut sk = [0u8; 32];
+            rng.fill(&mut sk);
+            if let Ok(key) = SecretKey::parse(&sk) {
+                return Ok((sk.to_vec(), PublicKey::from_secret_key(&key).into()));
+            }
+        }
+        Err(CryptoError::OperationFailed(
+            "signature: random generator keeps producing invalid secp256k1 keys".to_string(),
+        ))
+    }
+
+    /// 由私钥计算公钥
+    ///
+    /// sk: 私钥，长度为32字节，范围 [1, n-1]
+    pub fn public_key_from_secret(&self, sk: &[u8]) -> Result<Secp256k1PublicKey, CryptoError> {
+        Ok(PublicKey::from_secret_key(&secp256k1_secret_key(sk)?).into())
+    }
+
     /// 签名
     ///
     /// msg: 待签名数据，长度为32字节
     /// sk: 私钥，长度为32字节
-    pub fn sign(&self, msg: &[u8], sk: &[u8]) -> Vec<u8> {
-        let sk = match SecretKey::parse_slice(sk) {
-            Ok(sk) => sk,
-            Err(e) => {
-                println!("decode secret key error = {:?}", e);
-                return vec![];
-            }
-        };
-        let msg = match Message::parse_slice(msg) {
-            Ok(msg) => msg,
-            Err(e) => {
-                println!("ecdsa decode msg error = {:?}, msg = {:?}", e, msg);
-                return vec![];
-            }
-        };
+    pub fn sign(&self, msg: &[u8], sk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let timer = Timer::start(Operation::Sign, "secp256k1");
+        let sk = secp256k1_secret_key(sk)?;
+        let msg = secp256k1_message(msg)?;
 
         let sig = sign(&msg, &sk);
 
-        sig.0.serialize_der().as_ref().to_vec()
+        Ok(timer.done(sig.0.serialize_der().as_ref().to_vec(), true))
     }
 
     /// 验证签名
     ///
     /// msg: 已签名数据，长度为32字节
     /// sig: 签名，长度为65~72字节
-    /// pk: 公钥，长度为33或65字节
+    /// pk: 公钥，长度为33或65字节，或 SubjectPublicKeyInfo DER
     pub fn verify(&self, msg: &[u8], sig: &[u8], pk: &[u8]) -> bool {
-        let msg = match Message::parse_slice(msg) {
-            Ok(msg) => msg,
-            Err(e) => {
-                println!("ecdsa decode msg error = {:?}, msg = {:?}", e, msg);
-                return false;
-            }
+        self.verify_detailed(msg, sig, pk).is_ok()
+    }
+
+    /// 验证签名，失败时返回原因
+    pub fn verify_detailed(&self, msg: &[u8], sig: &[u8], pk: &[u8]) -> Result<(), VerifyFailure> {
+        let timer = Timer::start(Operation::Verify, "secp256k1");
+        let wrong_length = VerifyFailure::WrongLength {
+            expected: 32,
+            actual: msg.len(),
         };
-        let pk = match PublicKey::parse_slice(pk, None) {
-            Ok(pk) => pk,
-            Err(e) => {
-                println!("ecdsa decode publick key error = {:?}, pk = {:?}", e, pk);
-                return false;
-            }
+        let msg = Message::parse_slice(msg).map_err(|_| wrong_length)?;
+        let pk = public_key(PublicKeyType::Secp256k1, pk)?;
+        let pk = PublicKey::parse_slice(&pk, None).map_err(|_| VerifyFailure::MalformedKey)?;
+        let sig = Signature::parse_der(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+
+        let result = if verify(&msg, &sig, &pk) {
+            Ok(())
+        } else {
+            Err(VerifyFailure::SignatureInvalid)
         };
-        let sig = match Signature::parse_der(sig) {
-            Ok(sig) => sig,
-            Err(e) => {
-                println!("ecdsa decode sig error = {:?}, sig = {:?}", e, sig);
-                return false;
-            }
+        timer.done_result(result)
+    }
+
+    /// 按共识规则验证签名：要求严格 DER 编码 (BIP 66) 且 s 不大于 n/2 (BIP 62 low-s)，
+    /// 拒绝可延展的签名。参数同 verify
+    pub fn verify_strict(&self, msg: &[u8], sig: &[u8], pk: &[u8]) -> bool {
+        self.verify_strict_detailed(msg, sig, pk).is_ok()
+    }
+
+    /// 严格验证签名，失败时返回原因，非规范编码和 high-s 签名作为格式错误拒绝
+    pub fn verify_strict_detailed(
+        &self,
+        msg: &[u8],
+        sig: &[u8],
+        pk: &[u8],
+    ) -> Result<(), VerifyFailure> {
+        let parsed = Signature::parse_der(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+        // 重新编码后与输入不同说明有多余的前导0、长度字段不规范或 r、s 超出曲线阶
+        let compact = parsed.serialize();
+        if parsed.serialize_der().as_ref() != sig || compact[32..] > SECP256K1_HALF_ORDER[..] {
+            return Err(VerifyFailure::MalformedSignature);
+        }
+        self.verify_detailed(msg, sig, pk)
+    }
+
+    /// 把签名转换为 low-s 形式，输出格式与输入相同
+    ///
+    /// 这里生成的签名本来就是 low-s 的，用于处理其他实现产生的签名
+    /// sig: DER 编码(接受不严格的旧式编码，输出严格 DER)或64字节紧凑格式的签名
+    pub fn normalize_s(&self, sig: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let malformed =
+            |_| CryptoError::InvalidInput("signature: malformed secp256k1 signature".to_string());
+        if sig.len() == 64 {
+            let mut parsed = Signature::parse_slice(sig).map_err(malformed)?;
+            parsed.normalize_s();
+            Ok(parsed.serialize().to_vec())
+        } else {
+            let mut parsed = Signature::parse_der_lax(sig).map_err(malformed)?;
+            parsed.normalize_s();
+            Ok(parsed.serialize_der().as_ref().to_vec())
+        }
+    }
+
+    /// 对任意长度的消息签名，先用指定的哈希算法计算消息哈希
+    ///
+    /// digest: 消息哈希算法，验证方必须使用相同的算法
+    /// msg: 待签名数据
+    /// sk: 私钥，长度为32字节
+    pub fn sign_msg(
+        &self,
+        digest: MsgDigest,
+        msg: &[u8],
+        sk: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        self.sign(&digest.hash(msg), sk)
+    }
+
+    /// 验证 sign_msg 生成的签名
+    ///
+    /// digest: 消息哈希算法
+    /// msg: 已签名数据
+    /// sig: 签名
+    /// pk: 公钥，长度为33或65字节，或 SubjectPublicKeyInfo DER
+    pub fn verify_msg(&self, digest: MsgDigest, msg: &[u8], sig: &[u8], pk: &[u8]) -> bool {
+        self.verify(&digest.hash(msg), sig, pk)
+    }
+
+    /// 验证 sign_msg 生成的签名，失败时返回原因
+    pub fn verify_msg_detailed(
+        &self,
+        digest: MsgDigest,
+        msg: &[u8],
+        sig: &[u8],
+        pk: &[u8],
+    ) -> Result<(), VerifyFailure> {
+        self.verify_detailed(&digest.hash(msg), sig, pk)
+    }
+
+    /// 签名，返回64字节的 r | s 紧凑格式
+    ///
+    /// msg: 待签名数据，长度为32字节
+    /// sk: 私钥，长度为32字节
+    pub fn sign_compact(&self, msg: &[u8], sk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let timer = Timer::start(Operation::Sign, "secp256k1");
+        let sk = secp256k1_secret_key(sk)?;
+        let msg = secp256k1_message(msg)?;
+        let (sig, _) = sign(&msg, &sk);
+        Ok(timer.done(sig.serialize().to_vec(), true))
+    }
+
+    /// 验证64字节的紧凑格式签名
+    ///
+    /// msg: 已签名数据，长度为32字节
+    /// sig: 签名，长度为64字节
+    /// pk: 公钥，长度为33或65字节，或 SubjectPublicKeyInfo DER
+    pub fn verify_compact(&self, msg: &[u8], sig: &[u8], pk: &[u8]) -> bool {
+        self.verify_compact_detailed(msg, sig, pk).is_ok()
+    }
+
+    /// 验证紧凑格式签名，失败时返回原因
+    ///
+    /// 为防止签名延展，s 大于 n/2 的签名作为格式错误拒绝
+    pub fn verify_compact_detailed(
+        &self,
+        msg: &[u8],
+        sig: &[u8],
+        pk: &[u8],
+    ) -> Result<(), VerifyFailure> {
+        let timer = Timer::start(Operation::Verify, "secp256k1");
+        if sig.len() != 64 {
+            return Err(VerifyFailure::WrongLength {
+                expected: 64,
+                actual: sig.len(),
+            });
+        }
+        let wrong_length = VerifyFailure::WrongLength {
+            expected: 32,
+            actual: msg.len(),
+        };
+        let msg = Message::parse_slice(msg).map_err(|_| wrong_length)?;
+        let pk = public_key(PublicKeyType::Secp256k1, pk)?;
+        let pk = PublicKey::parse_slice(&pk, None).map_err(|_| VerifyFailure::MalformedKey)?;
+        if sig[32..] > SECP256K1_HALF_ORDER[..] {
+            return Err(VerifyFailure::MalformedSignature);
+        }
+        let sig = Signature::parse_slice(sig).map_err(|_| VerifyFailure::MalformedSignature)?;
+
+        let result = if verify(&msg, &sig, &pk) {
+            Ok(())
+        } else {
+            Err(VerifyFailure::SignatureInvalid)
+        };
+        timer.done_result(result)
+    }
+
+    /// 可恢复签名，返回65字节的 r | s | v，v = 27 + recovery id，与以太坊的签名格式相同
+    ///
+    /// msg: 待签名数据，长度为32字节
+    /// sk: 私钥，长度为32字节
+    pub fn sign_recoverable(&self, msg: &[u8], sk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let timer = Timer::start(Operation::Sign, "secp256k1");
+        let sk = secp256k1_secret_key(sk)?;
+        let msg = secp256k1_message(msg)?;
+        let (sig, recovery_id) = sign(&msg, &sk);
+        let mut out = sig.serialize().to_vec();
+        out.push(27 + recovery_id.serialize());
+        Ok(timer.done(out, true))
+    }
+
+    /// 从可恢复签名中恢复公钥，即以太坊的 ecrecover
+    ///
+    /// v 为 0~3 或 27~30 时返回65字节未压缩公钥；为 31~34 时按比特币的紧凑签名格式返回33字节压缩公钥
+    ///
+    /// msg: 已签名数据，长度为32字节
+    /// sig: 65字节的 r | s | v
+    pub fn recover_public_key(&self, msg: &[u8], sig: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if sig.len() != 65 {
+            return Err(CryptoError::Verify(VerifyFailure::WrongLength {
+                expected: 65,
+                actual: sig.len(),
+            }));
+        }
+        let msg = secp256k1_message(msg)?;
+        let (id, compressed) = match sig[64] {
+            v @ 0..=3 => (v, false),
+            v @ 27..=30 => (v - 27, false),
+            v @ 31..=34 => (v - 31, true),
+            _ => return Err(CryptoError::Verify(VerifyFailure::MalformedSignature)),
         };
+        let recovery_id = RecoveryId::parse(id)
+            .map_err(|_| CryptoError::Verify(VerifyFailure::MalformedSignature))?;
+        let mut rs = [0u8; 64];
+        rs.copy_from_slice(&sig[..64]);
+        let pk = recover(&msg, &Signature::parse(&rs), &recovery_id)
+            .map_err(|_| CryptoError::Verify(VerifyFailure::SignatureInvalid))?;
+        Ok(if compressed {
+            pk.serialize_compressed().to_vec()
+        } else {
+            pk.serialize().to_vec()
+        })
+    }
+
+    /// ECDH 密钥协商
+    ///
+    /// sk: 本地私钥，长度为32字节
+    /// pk: 对端公钥，长度为33或65字节
+    /// hash: 共享点的处理方式
+    pub fn ecdh(&self, sk: &[u8], pk: &[u8], hash: EcdhHash) -> Result<Vec<u8>, CryptoError> {
+        let sk = secp256k1_secret_key(sk)?;
+        let mut shared = secp256k1_public_key(pk)?;
+        shared.tweak_mul_assign(&sk).map_err(|_| {
+            CryptoError::OperationFailed("signature: secp256k1 ecdh failed".to_string())
+        })?;
+        let compressed = shared.serialize_compressed();
+        Ok(match hash {
+            EcdhHash::Sha256 => ring::digest::digest(&ring::digest::SHA256, &compressed)
+                .as_ref()
+                .to_vec(),
+            EcdhHash::RawX => compressed[1..].to_vec(),
+            EcdhHash::Compressed => compressed.to_vec(),
+        })
+    }
+
+    /// 多线程验证一批互相独立的签名，返回每个签名是否有效
+    ///
+    /// 线程数为系统的可用并行度，各线程共享 libsecp256k1 静态的预计算表，不额外分配验证上下文
+    ///
+    /// items: (消息, 签名, 公钥)，格式同 verify
+    pub fn verify_batch(&self, items: &[(&[u8], &[u8], &[u8])]) -> Vec<bool> {
+        let mut results = vec![false; items.len()];
+        let chunk = batch_chunk(items.len());
+        std::thread::scope(|scope| {
+            for (items, results) in items.chunks(chunk).zip(results.chunks_mut(chunk)) {
+                scope.spawn(move || {
+                    for ((msg, sig, pk), ok) in items.iter().zip(results.iter_mut()) {
+                        *ok = self.verify(msg, sig, pk);
+                    }
+                });
+            }
+        });
+        results
+    }
 
-        verify(&msg, &sig, &pk)
+    /// 多线程验证一批签名，全部有效时返回 true，遇到无效签名后其余线程尽早停止
+    ///
+    /// items: (消息, 签名, 公钥)，格式同 verify
+    pub fn verify_batch_all(&self, items: &[(&[u8], &[u8], &[u8])]) -> bool {
+        let failed = AtomicBool::new(false);
+        let chunk = batch_chunk(items.len());
+        std::thread::scope(|scope| {
+            for items in items.chunks(chunk) {
+                let failed = &failed;
+                scope.spawn(move || {
+                    for (msg, sig, pk) in items {
+                        if failed.load(Ordering::Relaxed) {
+                            return;
+                        }
+                        if !self.verify(msg, sig, pk) {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                    }
+                });
+            }
+        });
+        !failed.into_inner()
     }
 }
 
+// 按系统的可用并行度把批量均分给各线程
+fn batch_chunk(n: usize) -> usize {
+    let threads = std::thread::available_parallelism().map_or(1, |t| t.get());
+    n.div_ceil(threads).max(1)
+}
+
+// 验证接口同时接受原始公钥和 SubjectPublicKeyInfo DER
+fn public_key(key_type: PublicKeyType, pk: &[u8]) -> Result<Cow<'_, [u8]>, VerifyFailure> {
+    normalize_public_key(key_type, pk).map_err(|_| VerifyFailure::MalformedKey)
+}
+
+fn secp256k1_public_key(pk: &[u8]) -> Result<PublicKey, CryptoError> {
+    PublicKey::parse_slice(pk, None)
+        .map_err(|_| CryptoError::InvalidKey("signature: invalid secp256k1 public key".to_string()))
+}
+
+fn secp256k1_secret_key(sk: &[u8]) -> Result<SecretKey, CryptoError> {
+    SecretKey::parse_slice(sk).map_err(|_| {
+        CryptoError::InvalidKey(
+            "signature: secp256k1 secret key must be 32 bytes in [1, n-1]".to_string(),
+        )
+    })
+}
+
+fn secp256k1_message(msg: &[u8]) -> Result<Message, CryptoError> {
+    Message::parse_slice(msg).map_err(|_| {
+        CryptoError::InvalidInput(format!(
+            "signature: secp256k1 message must be 32 bytes, got {}",
+            msg.len()
+        ))
+    })
+}
+
+fn secp256k1_scalar(scalar: &[u8]) -> Result<SecretKey, CryptoError> {
+    SecretKey::parse_slice(scalar).map_err(|_| {
+        CryptoError::InvalidInput(
+            "signature: secp256k1 scalar must be 32 bytes in [1, n-1]".to_string(),
+        )
+    })
+}
+
+/// secp256k1 公钥相加，用于 MuSig 密钥聚合、Pedersen 承诺等
+///
+/// keys: 公钥，每个33或65字节
+/// 返回65字节未压缩公钥，结果为无穷远点时返回错误
+pub fn secp256k1_combine(keys: &[&[u8]]) -> Result<Vec<u8>, CryptoError> {
+    let keys = keys
+        .iter()
+        .map(|pk| secp256k1_public_key(pk))
+        .collect::<Result<Vec<_>, CryptoError>>()?;
+    PublicKey::combine(&keys)
+        .map(|pk| pk.serialize().to_vec())
+        .map_err(|_| {
+            CryptoError::OperationFailed(
+                "signature: secp256k1 public keys sum to infinity".to_string(),
+            )
+        })
+}
+
+/// secp256k1 公钥取负，返回65字节未压缩公钥
+pub fn secp256k1_negate(pk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    // 压缩编码的前缀表示 y 的奇偶，-P 与 P 只有 y 不同
+    let mut compressed = secp256k1_public_key(pk)?.serialize_compressed();
+    compressed[0] ^= 0x01;
+    let negated = PublicKey::parse_compressed(&compressed).map_err(|_| {
+        CryptoError::InvalidKey("signature: invalid secp256k1 public key".to_string())
+    })?;
+    Ok(negated.serialize().to_vec())
+}
+
+/// secp256k1 公钥数乘 scalar * P，用于隐身地址、ECDH等
+///
+/// pk: 公钥
+/// scalar: 32字节大端标量，范围 [1, n-1]
+pub fn secp256k1_mul(pk: &[u8], scalar: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut pk = secp256k1_public_key(pk)?;
+    pk.tweak_mul_assign(&secp256k1_scalar(scalar)?)
+        .map_err(|_| {
+            CryptoError::OperationFailed("signature: secp256k1 multiplication failed".to_string())
+        })?;
+    Ok(pk.serialize().to_vec())
+}
+
+/// secp256k1 公钥加上标量对应的点 P + scalar * G，用于 BIP32 派生和隐身地址
+///
+/// pk: 公钥
+/// scalar: 32字节大端标量，范围 [1, n-1]
+pub fn secp256k1_add_scalar(pk: &[u8], scalar: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let mut pk = secp256k1_public_key(pk)?;
+    pk.tweak_add_assign(&secp256k1_scalar(scalar)?)
+        .map_err(|_| {
+            CryptoError::OperationFailed(
+                "signature: secp256k1 addition results in infinity".to_string(),
+            )
+        })?;
+    Ok(pk.serialize().to_vec())
+}
+
+/// secp256k1 公钥转换为33字节压缩编码
+///
+/// pk: 33或65字节公钥
+pub fn secp256k1_compress_pubkey(pk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    Ok(secp256k1_public_key(pk)?.serialize_compressed().to_vec())
+}
+
+/// secp256k1 公钥转换为65字节未压缩编码，由 x 和前缀的奇偶恢复 y
+///
+/// pk: 33或65字节公钥
+pub fn secp256k1_decompress_pubkey(pk: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    Ok(secp256k1_public_key(pk)?.serialize().to_vec())
+}
+
+/// 检查是否为合法的 secp256k1 公钥：长度和前缀正确，坐标小于域的阶，且点在曲线上
+pub fn secp256k1_validate_pubkey(pk: &[u8]) -> bool {
+    (pk.len() == 33 || pk.len() == 65) && secp256k1_public_key(pk).is_ok()
+}
+
 /// RSA签名算法填充类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PaddingAlg {
     /// PKCS
     RSA_PKCS1_SHA256,
@@ -87,19 +564,96 @@ pub enum PaddingAlg {
     RSA_PSS_SHA512,
 }
 
+fn padding_name(padAlg: PaddingAlg) -> &'static str {
+    match padAlg {
+        PaddingAlg::RSA_PKCS1_SHA256 => "RSA-PKCS1-SHA256",
+        PaddingAlg::RSA_PKCS1_SHA384 => "RSA-PKCS1-SHA384",
+        PaddingAlg::RSA_PKCS1_SHA512 => "RSA-PKCS1-SHA512",
+        PaddingAlg::RSA_PSS_SHA256 => "RSA-PSS-SHA256",
+        PaddingAlg::RSA_PSS_SHA384 => "RSA-PSS-SHA384",
+        PaddingAlg::RSA_PSS_SHA512 => "RSA-PSS-SHA512",
+    }
+}
+
 /// RSA签名算法对象
 pub struct Rsa {
     ctx: RsaKeyPair,
+    key: RsaPrivateKey,
+}
+
+impl PartialEq for Rsa {
+    /// 按公钥常量时间比较
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.public_key(), &other.public_key())
+    }
 }
 
+impl Eq for Rsa {}
+
 impl Rsa {
     /// 从PKCS8格式的密钥数据生成RSA签名算法对象
     ///
     /// input: PKCS8格式的密钥数据
-    pub fn fromPKCS8(input: &[u8]) -> Rsa {
-        Rsa {
-            ctx: RsaKeyPair::from_pkcs8(input).unwrap(),
+    pub fn fromPKCS8(input: &[u8]) -> Result<Rsa, CryptoError> {
+        let invalid =
+            || CryptoError::InvalidKey("signature: malformed rsa private key".to_string());
+        let ctx = RsaKeyPair::from_pkcs8(input).map_err(|_| invalid())?;
+        let key = RsaPrivateKey::from_pkcs8(input).ok_or_else(invalid)?;
+        Ok(Rsa { ctx, key })
+    }
+
+    /// 从口令加密的 PKCS#8 (PBES2) 密钥数据生成RSA签名算法对象
+    ///
+    /// input: EncryptedPrivateKeyInfo DER
+    /// passphrase: 口令
+    pub fn from_encrypted_pkcs8(input: &[u8], passphrase: &[u8]) -> Result<Rsa, CryptoError> {
+        Rsa::fromPKCS8(&SecretBytes::new(decrypt_pkcs8(input, passphrase)?))
+    }
+
+    /// 生成新的RSA密钥，公钥指数为65537
+    ///
+    /// bits: 模数的位数，2048、3072或4096
+    pub fn generate(bits: usize) -> Result<Rsa, CryptoError> {
+        Rsa::generate_with_rng(bits, &OsRng)
+    }
+
+    /// 使用指定的随机数生成器生成新的RSA密钥
+    ///
+    /// bits: 模数的位数，2048、3072或4096
+    /// rng: 随机数生成器，用于生成素数和 Miller-Rabin 测试的底数
+    pub fn generate_with_rng(bits: usize, rng: &dyn SecureRandom) -> Result<Rsa, CryptoError> {
+        if !matches!(bits, 2048 | 3072 | 4096) {
+            return Err(CryptoError::InvalidInput(format!(
+                "signature: rsa key size must be 2048, 3072 or 4096 bits, got {}",
+                bits
+            )));
         }
+        let key = RsaPrivateKey::generate(bits, rng).ok_or_else(|| {
+            CryptoError::OperationFailed("signature: rsa key generation failed".to_string())
+        })?;
+        Rsa::fromPKCS8(&SecretBytes::new(key.to_pkcs8()?))
+    }
+
+    /// 导出为 PKCS#8 PrivateKeyInfo DER
+    pub fn to_pkcs8(&self) -> Result<Vec<u8>, CryptoError> {
+        self.key.to_pkcs8()
+    }
+
+    /// 导出为口令加密的 PKCS#8 EncryptedPrivateKeyInfo DER
+    ///
+    /// passphrase: 口令
+    /// params: PBKDF2 参数
+    pub fn to_encrypted_pkcs8(
+        &self,
+        passphrase: &[u8],
+        params: Pbkdf2,
+    ) -> Result<Vec<u8>, CryptoError> {
+        encrypt_pkcs8(&SecretBytes::new(self.key.to_pkcs8()?), passphrase, params)
+    }
+
+    /// 导出为 PKCS#1 RSAPrivateKey DER
+    pub fn to_pkcs1(&self) -> Result<Vec<u8>, CryptoError> {
+        self.key.to_pkcs1()
     }
 
     /**
@@ -110,49 +664,121 @@ impl Rsa {
         self.ctx.public_key().as_ref().to_vec()
     }
 
+    /// 私钥参数 n, e, d, p, q, dp, dq, qinv，大端无符号整数，用于 JWK 导出
+    pub(crate) fn components(&self) -> [Vec<u8>; 8] {
+        let k = &self.key;
+        [&k.n, &k.e, &k.d, &k.p, &k.q, &k.dp, &k.dq, &k.qinv].map(|i| i.to_bytes_be())
+    }
+
+    /// 由 components 顺序的私钥参数构造，参数的一致性由 ring 检查
+    pub(crate) fn from_components(c: &[Vec<u8>; 8]) -> Result<Rsa, CryptoError> {
+        let int = |i: usize| BigUint::from_bytes_be(&c[i]);
+        let key = RsaPrivateKey {
+            n: int(0),
+            e: int(1),
+            d: int(2),
+            p: int(3),
+            q: int(4),
+            dp: int(5),
+            dq: int(6),
+            qinv: int(7),
+        };
+        Rsa::fromPKCS8(&SecretBytes::new(key.to_pkcs8()?))
+    }
+
     /// 使用当前RSA签名算法对象和指定的RSA签名算法填充类型，对指定的数据进行签名
     ///
     /// padAlg: RSA签名算法填充类型
     /// msg: 待签名的数据
-    pub fn sign(&self, padAlg: PaddingAlg, msg: &[u8]) -> Vec<u8> {
+    pub fn sign(&self, padAlg: PaddingAlg, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let timer = Timer::start(Operation::Sign, padding_name(padAlg));
         let mut signature = vec![0; self.ctx.public_modulus_len()];
         let rng = rand::SystemRandom::new();
+        let alg: &'static dyn signature::RsaEncoding = match padAlg {
+            PaddingAlg::RSA_PKCS1_SHA256 => &signature::RSA_PKCS1_SHA256,
+            PaddingAlg::RSA_PKCS1_SHA384 => &signature::RSA_PKCS1_SHA384,
+            PaddingAlg::RSA_PKCS1_SHA512 => &signature::RSA_PKCS1_SHA512,
 
-        match padAlg {
-            PaddingAlg::RSA_PKCS1_SHA256 => {
-                let _ = self
-                    .ctx
-                    .sign(&signature::RSA_PKCS1_SHA256, &rng, msg, &mut signature)
-                    .unwrap();
-            }
-            PaddingAlg::RSA_PKCS1_SHA384 => {
-                let _ = self
-                    .ctx
-                    .sign(&signature::RSA_PKCS1_SHA384, &rng, msg, &mut signature);
-            }
-            PaddingAlg::RSA_PKCS1_SHA512 => {
-                let _ = self
-                    .ctx
-                    .sign(&signature::RSA_PKCS1_SHA512, &rng, msg, &mut signature);
-            }
+            PaddingAlg::RSA_PSS_SHA256 => &signature::RSA_PSS_SHA256,
+            PaddingAlg::RSA_PSS_SHA384 => &signature::RSA_PSS_SHA384,
+            PaddingAlg::RSA_PSS_SHA512 => &signature::RSA_PSS_SHA512,
+        };
+        let result = self
+            .ctx
+            .sign(alg, &rng, msg, &mut signature)
+            .map(|_| signature)
+            .map_err(|_| CryptoError::OperationFailed("signature: rsa sign failed".to_string()));
+        timer.done_result(result)
+    }
 
-            PaddingAlg::RSA_PSS_SHA256 => {
-                let _ = self
-                    .ctx
-                    .sign(&signature::RSA_PSS_SHA256, &rng, msg, &mut signature);
-            }
-            PaddingAlg::RSA_PSS_SHA384 => {
-                let _ = self
-                    .ctx
-                    .sign(&signature::RSA_PSS_SHA384, &rng, msg, &mut signature);
-            }
-            PaddingAlg::RSA_PSS_SHA512 => {
-                let _ = self
-                    .ctx
-                    .sign(&signature::RSA_PSS_SHA512, &rng, msg, &mut signature);
+    /// 使用指定的随机数生成器产生PSS的盐，PKCS#1 v1.5 填充是确定性的，不使用随机数
+    ///
+    /// padAlg: RSA签名算法填充类型
+    /// msg: 待签名的数据
+    /// rng: 随机数生成器
+    pub fn sign_with_rng(
+        &self,
+        padAlg: PaddingAlg,
+        msg: &[u8],
+        rng: &dyn SecureRandom,
+    ) -> Result<Vec<u8>, CryptoError> {
+        match pss_alg(padAlg) {
+            Ok((_, digest_alg)) => {
+                self.sign_pss_with_salt(padAlg, msg, &rng.gen_bytes(digest_alg.output_len))
             }
+            Err(_) => self.sign(padAlg, msg),
+        }
+    }
+
+    /// 使用指定的盐进行RSA-PSS签名，相同的盐得到相同的签名，用于生成可复现的测试向量
+    ///
+    /// padAlg: PSS 填充类型
+    /// msg: 待签名的数据
+    /// salt: 盐，长度必须等于哈希长度
+    pub fn sign_pss_with_salt(
+        &self,
+        padAlg: PaddingAlg,
+        msg: &[u8],
+        salt: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let (_, digest_alg) = pss_alg(padAlg)?;
+        if salt.len() != digest_alg.output_len {
+            return Err(CryptoError::InvalidInput(format!(
+                "signature: pss salt must be {} bytes",
+                digest_alg.output_len
+            )));
         }
-        signature
+        // ring 的签名接口只接受封闭的 SecureRandom，这里自己完成 EMSA-PSS 编码，再做私钥运算
+        let failed = || CryptoError::OperationFailed("signature: rsa sign failed".to_string());
+        let m_hash = ring::digest::digest(digest_alg, msg);
+        let em = emsa_pss_encode(digest_alg, m_hash.as_ref(), salt, self.key.n.bits() - 1)
+            .ok_or_else(|| {
+                CryptoError::InvalidKey("signature: rsa modulus too small".to_string())
+            })?;
+        let s = self
+            .key
+            .private_op(&BigUint::from_bytes_be(&em), &OsRng)
+            .ok_or_else(failed)?;
+        to_fixed_bytes(&s, self.ctx.public_modulus_len()).ok_or_else(failed)
+    }
+
+    /// 确定性RSA-PSS签名，盐由公钥和消息哈希得到，同一密钥对同一消息总是得到相同的签名
+    ///
+    /// 盐不再保密也不再随机，安全性退化为与 PKCS#1 v1.5 相当，可以被任何标准的PSS验证方验证
+    ///
+    /// padAlg: PSS 填充类型
+    /// msg: 待签名的数据
+    pub fn sign_pss_deterministic(
+        &self,
+        padAlg: PaddingAlg,
+        msg: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let (_, digest_alg) = pss_alg(padAlg)?;
+        let mut ctx = ring::digest::Context::new(digest_alg);
+        ctx.update(b"pi_crypto rsa-pss salt");
+        ctx.update(&self.public_key());
+        ctx.update(msg);
+        self.sign_pss_with_salt(padAlg, msg, ctx.finish().as_ref())
     }
 
     /// 验证使用指定的RSA签名算法填充类型和指定的RSA公钥的签名
@@ -160,9 +786,30 @@ impl Rsa {
     /// padAlg: RSA签名算法填充类型
     /// msg: 已签名的数据
     /// sig: 签名
-    /// pk: RSA公钥
+    /// pk: RSA公钥，PKCS#1 RSAPublicKey 或 SubjectPublicKeyInfo DER
     pub fn verify(padAlg: PaddingAlg, msg: &[u8], sig: &[u8], pk: &[u8]) -> bool {
-        match padAlg {
+        Rsa::verify_detailed(padAlg, msg, sig, pk).is_ok()
+    }
+
+    /// 验证签名，失败时返回原因
+    ///
+    /// pk 必须是 PKCS#1 RSAPublicKey 或 SubjectPublicKeyInfo 的DER编码，签名长度必须等于模数长度
+    pub fn verify_detailed(
+        padAlg: PaddingAlg,
+        msg: &[u8],
+        sig: &[u8],
+        pk: &[u8],
+    ) -> Result<(), VerifyFailure> {
+        let timer = Timer::start(Operation::Verify, padding_name(padAlg));
+        let pk = &public_key(PublicKeyType::Rsa, pk)?[..];
+        let modulus_len = rsa_modulus_len(pk).ok_or(VerifyFailure::MalformedKey)?;
+        if sig.len() != modulus_len {
+            return Err(VerifyFailure::WrongLength {
+                expected: modulus_len,
+                actual: sig.len(),
+            });
+        }
+        let ok = match padAlg {
             PaddingAlg::RSA_PKCS1_SHA256 => {
                 signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, pk)
                     .verify(msg, sig)
@@ -194,7 +841,267 @@ impl Rsa {
                     .verify(msg, sig)
                     .is_ok()
             }
+        };
+        let result = if ok {
+            Ok(())
+        } else {
+            Err(VerifyFailure::SignatureInvalid)
+        };
+        timer.done_result(result)
+    }
+
+    /// 对调用方计算好的摘要签名，结果与 sign 对原始消息的签名相同
+    ///
+    /// PKCS#1 v1.5 在摘要前加上对应哈希算法的 DigestInfo，PSS 使用随机盐，盐长度等于哈希长度。
+    /// 私钥运算基于 num-bigint，不是常量时间实现
+    ///
+    /// padAlg: RSA签名算法填充类型
+    /// digest: 消息的摘要，长度必须等于填充类型对应的哈希长度
+    pub fn sign_prehashed(
+        &self,
+        padAlg: PaddingAlg,
+        digest: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let timer = Timer::start(Operation::Sign, padding_name(padAlg));
+        timer.done_result(self.sign_digest(padAlg, digest))
+    }
+
+    fn sign_digest(&self, padAlg: PaddingAlg, digest: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let failed = || CryptoError::OperationFailed("signature: rsa sign failed".to_string());
+        let (alg, prefix) = prehash_alg(padAlg);
+        if digest.len() != alg.output_len {
+            return Err(CryptoError::InvalidInput(format!(
+                "signature: digest must be {} bytes",
+                alg.output_len
+            )));
+        }
+        let n = &self.key.n;
+        let em = match prefix {
+            Some(prefix) => emsa_pkcs1_encode(prefix, digest, self.ctx.public_modulus_len()),
+            None => emsa_pss_encode(
+                alg,
+                digest,
+                &genSecureRandBytes(alg.output_len),
+                n.bits() - 1,
+            ),
+        }
+        .ok_or_else(|| CryptoError::InvalidKey("signature: rsa modulus too small".to_string()))?;
+        let s = self
+            .key
+            .private_op(&BigUint::from_bytes_be(&em), &OsRng)
+            .ok_or_else(failed)?;
+        to_fixed_bytes(&s, self.ctx.public_modulus_len()).ok_or_else(failed)
+    }
+
+    /// 使用调用方计算好的摘要验证签名
+    ///
+    /// padAlg: RSA签名算法填充类型
+    /// digest: 已签名消息的摘要
+    /// sig: 签名
+    /// pk: RSA公钥，PKCS#1 RSAPublicKey 或 SubjectPublicKeyInfo DER
+    pub fn verify_prehashed(padAlg: PaddingAlg, digest: &[u8], sig: &[u8], pk: &[u8]) -> bool {
+        Rsa::verify_prehashed_detailed(padAlg, digest, sig, pk).is_ok()
+    }
+
+    /// 使用摘要验证签名，失败时返回原因
+    ///
+    /// 与 verify 相同，只接受 2048 到 8192 位的模数
+    pub fn verify_prehashed_detailed(
+        padAlg: PaddingAlg,
+        digest: &[u8],
+        sig: &[u8],
+        pk: &[u8],
+    ) -> Result<(), VerifyFailure> {
+        let timer = Timer::start(Operation::Verify, padding_name(padAlg));
+        let pk = public_key(PublicKeyType::Rsa, pk)?;
+        timer.done_result(verify_digest(padAlg, digest, sig, &pk))
+    }
+
+    /// RSAES-OAEP 加密 (RFC 8017 7.1.1)，MGF1 使用与标签相同的哈希算法
+    ///
+    /// hash: 哈希算法，pk: PKCS#1 RSAPublicKey DER 编码的公钥，msg: 明文，
+    /// 最长为模数长度 - 2 * 哈希长度 - 2，label: 标签，通常为空
+    pub fn encrypt_oaep(
+        hash: OaepHash,
+        pk: &[u8],
+        msg: &[u8],
+        label: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let (n, e, k) = rsa_encryption_key(pk)?;
+        let alg = hash.algorithm();
+        let h_len = alg.output_len;
+        if msg.len() + 2 * h_len + 2 > k {
+            return Err(CryptoError::InvalidInput(format!(
+                "signature: oaep message too long, at most {} bytes",
+                k.saturating_sub(2 * h_len + 2)
+            )));
+        }
+        let db_len = k - h_len - 1;
+        let mut db = ring::digest::digest(alg, label).as_ref().to_vec();
+        db.resize(db_len - msg.len() - 1, 0);
+        db.push(0x01);
+        db.extend_from_slice(msg);
+        let seed = genSecureRandBytes(h_len);
+        for (b, m) in db.iter_mut().zip(mgf1(alg, &seed, db_len)) {
+            *b ^= m;
+        }
+        let mut em = vec![0u8];
+        em.extend(seed.iter().zip(mgf1(alg, &db, h_len)).map(|(s, m)| s ^ m));
+        em.extend_from_slice(&db);
+        let c = BigUint::from_bytes_be(&em).modpow(&e, &n);
+        Ok(to_fixed_bytes(&c, k).expect("c < n"))
+    }
+
+    /// RSAES-OAEP 解密
+    ///
+    /// 所有解码错误返回相同的错误信息，检查过程不提前退出，避免 Manger 攻击
+    ///
+    /// hash: 加密时使用的哈希算法，ciphertext: 密文，label: 加密时使用的标签
+    pub fn decrypt_oaep(
+        &self,
+        hash: OaepHash,
+        ciphertext: &[u8],
+        label: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let alg = hash.algorithm();
+        let h_len = alg.output_len;
+        let em = self.decrypt_raw(ciphertext, 2 * h_len + 2)?;
+        let (masked_seed, masked_db) = em[1..].split_at(h_len);
+        let seed: Vec<u8> = masked_seed
+            .iter()
+            .zip(mgf1(alg, masked_db, h_len))
+            .map(|(s, m)| s ^ m)
+            .collect();
+        let db: Vec<u8> = masked_db
+            .iter()
+            .zip(mgf1(alg, &seed, masked_db.len()))
+            .map(|(b, m)| b ^ m)
+            .collect();
+
+        // good 为 0xff 表示到目前为止格式正确，Y 字节、lHash 和分隔符的检查都不提前返回、不经过比较运算，
+        // 避免 Manger 攻击通过错误类型或耗时区分失败原因
+        let lhash = ring::digest::digest(alg, label);
+        let diff = db[..h_len]
+            .iter()
+            .zip(lhash.as_ref())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b));
+        let mut good = ct_is_zero(diff) & ct_is_zero(em[0]);
+        let mut looking = 0xffu8;
+        let mut index = 0usize;
+        for (i, &b) in db[h_len..].iter().enumerate() {
+            let is_one = ct_is_zero(b ^ 0x01);
+            let is_zero = ct_is_zero(b);
+            let found = looking & is_one;
+            index = ct_select_usize(
+                (usize::from(found) & 1).wrapping_neg(),
+                i + h_len + 1,
+                index,
+            );
+            // 找到分隔符之前只能是0
+            good &= !looking | is_zero | is_one;
+            looking &= !found;
+        }
+        good &= !looking;
+        if good != 0xff {
+            return Err(decryption_error());
+        }
+        Ok(db[index..].to_vec())
+    }
+
+    /// RSAES-PKCS1-v1_5 加密 (RFC 8017 7.2.1)，只用于与旧系统互通，新协议应使用 OAEP
+    ///
+    /// pk: PKCS#1 RSAPublicKey DER 编码的公钥，msg: 明文，最长为模数长度 - 11
+    pub fn encrypt_pkcs1v15(pk: &[u8], msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let (n, e, k) = rsa_encryption_key(pk)?;
+        if msg.len() + 11 > k {
+            return Err(CryptoError::InvalidInput(format!(
+                "signature: pkcs1 message too long, at most {} bytes",
+                k - 11
+            )));
+        }
+        let mut em = vec![0x00, 0x02];
+        while em.len() < k - msg.len() - 1 {
+            let need = k - msg.len() - 1 - em.len();
+            em.extend(genSecureRandBytes(need).into_iter().filter(|&b| b != 0));
+        }
+        em.push(0x00);
+        em.extend_from_slice(msg);
+        let c = BigUint::from_bytes_be(&em).modpow(&e, &n);
+        Ok(to_fixed_bytes(&c, k).expect("c < n"))
+    }
+
+    /// RSAES-PKCS1-v1_5 解密
+    ///
+    /// 填充错误时不返回错误，而是返回由私钥和密文确定的伪随机消息 (隐式拒绝，
+    /// 与 OpenSSL 3.2 起的默认行为相同)，同一密文总是得到同一结果。填充检查和结果选择都是常量时间的，
+    /// 调用方无法从返回值、错误或耗时区分填充是否正确，避免 Bleichenbacher / Marvin 攻击。
+    /// 只有密文长度错误时返回错误
+    ///
+    /// ciphertext: 密文
+    pub fn decrypt_pkcs1v15(&self, ciphertext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let em = self.decrypt_raw(ciphertext, 11)?;
+        let k = em.len();
+        let kdk = self.key.implicit_rejection_kdk(ciphertext);
+        let synthetic = implicit_rejection_prf(&kdk, b"message", k);
+
+        // 从128个候选长度中选出最后一个小于上限的，避免取模造成的偏差和非常量时间的除法
+        let max_len = k - 10;
+        let mut len_mask = max_len;
+        for shift in [1, 2, 4, 8] {
+            len_mask |= len_mask >> shift;
+        }
+        let mut synthetic_len = 0usize;
+        for pair in implicit_rejection_prf(&kdk, b"length", 256).chunks(2) {
+            let candidate = (usize::from(pair[0]) << 8 | usize::from(pair[1])) & len_mask;
+            synthetic_len =
+                ct_select_usize(ct_lt_usize(candidate, max_len), candidate, synthetic_len);
+        }
+
+        let (good, index) = pkcs1v15_unpad(&em);
+        let good_mask = (usize::from(good) & 1).wrapping_neg();
+        let index = ct_select_usize(good_mask, index, k - synthetic_len);
+        Ok(em[index..]
+            .iter()
+            .zip(&synthetic[index..])
+            .map(|(&m, &r)| (m & good) | (r & !good))
+            .collect())
+    }
+
+    /// 解密 PKCS#1 v1.5 封装的对称密钥，填充错误或长度不符时返回随机密钥而不是错误 (RFC 5246 7.4.7.1)，
+    /// 后续使用该密钥的解密或MAC检查自然失败，攻击者无法区分失败原因
+    ///
+    /// ciphertext: 密文，key_len: 期望的密钥长度
+    pub fn decrypt_pkcs1v15_key(
+        &self,
+        ciphertext: &[u8],
+        key_len: usize,
+    ) -> Result<Vec<u8>, CryptoError> {
+        let random = genSecureRandBytes(key_len);
+        // 密文长度和范围是公开信息，可以直接返回错误
+        let em = self.decrypt_raw(ciphertext, key_len + 11)?;
+        let (mut good, index) = pkcs1v15_unpad(&em);
+        let len = em.len() - index;
+        good &= !((ct_lt_usize(len, key_len) | ct_lt_usize(key_len, len)) as u8);
+        let key = &em[em.len() - key_len..];
+        Ok(key
+            .iter()
+            .zip(random.iter())
+            .map(|(k, r)| (k & good) | (r & !good))
+            .collect())
+    }
+
+    /// c^d mod n，结果按模数长度编码，模数长度小于 min_len 或密文长度错误时返回错误
+    ///
+    /// 私钥运算经过盲化，结果用 to_fixed_bytes_ct 编码，前导零的个数不影响耗时
+    fn decrypt_raw(&self, ciphertext: &[u8], min_len: usize) -> Result<Vec<u8>, CryptoError> {
+        let k = self.key.n.bits().div_ceil(8);
+        if ciphertext.len() != k || k < min_len {
+            return Err(decryption_error());
         }
+        self.key
+            .private_op(&BigUint::from_bytes_be(ciphertext), &OsRng)
+            .map(|m| to_fixed_bytes_ct(&m, k))
+            .ok_or_else(decryption_error)
     }
 
     /// 验证alipy签名
@@ -203,18 +1110,15 @@ impl Rsa {
     /// sig: 签名
     /// pk: 公钥
     pub fn alipay_verify(msg: &[u8], sig: &[u8], pk: &[u8]) -> bool {
-        let blocks = match simple_asn1::from_der(&pk) {
+        let blocks = match simple_asn1::from_der(pk) {
             Ok(blocks) => blocks,
-            Err(_) => {
-                println!("malformed public key");
-                return false;
-            }
+            Err(_) => return false,
         };
         let mut bit_strings = Vec::new();
         find_bit_string(&blocks, &mut bit_strings);
         if let Some(bs) = bit_strings.first() {
             signature::UnparsedPublicKey::new(&signature::RSA_PKCS1_2048_8192_SHA256, bs)
-                .verify(msg, &sig)
+                .verify(msg, sig)
                 .is_ok()
         } else {
             false
@@ -222,49 +1126,596 @@ impl Rsa {
     }
 }
 
-fn find_bit_string(blocks: &[ASN1Block], mut result: &mut Vec<Vec<u8>>) {
-    for block in blocks.iter() {
-        match block {
-            ASN1Block::BitString(_, _, bytes) => result.push(bytes.to_vec()),
-            ASN1Block::Sequence(_, blocks) => find_bit_string(&blocks[..], &mut result),
-            _ => (),
+/// RSA-OAEP 的哈希算法，同时用于 MGF1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OaepHash {
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl OaepHash {
+    fn algorithm(self) -> &'static ring::digest::Algorithm {
+        match self {
+            OaepHash::Sha256 => &ring::digest::SHA256,
+            OaepHash::Sha384 => &ring::digest::SHA384,
+            OaepHash::Sha512 => &ring::digest::SHA512,
         }
     }
 }
 
-/// NIST曲线, P256 和 p384
-#[derive(Debug)]
-pub enum EcdsaAlg {
-    // Signing of ASN.1 DER-encoded ECDSA signatures using the P-256 curve and SHA-256.
+fn decryption_error() -> CryptoError {
+    CryptoError::InvalidInput("signature: rsa decryption error".to_string())
+}
+
+/// EM = 00 | 02 | PS | 00 | M，PS 至少8字节，返回 (正确时为 0xff 的掩码, M 的起始位置)
+fn pkcs1v15_unpad(em: &[u8]) -> (u8, usize) {
+    let mut good = ct_is_zero(em[0]) & ct_is_zero(em[1] ^ 2);
+    let mut looking = 0xffu8;
+    let mut index = 0usize;
+    for (i, &b) in em.iter().enumerate().skip(2) {
+        let found = looking & ct_is_zero(b);
+        index = ct_select_usize((usize::from(found) & 1).wrapping_neg(), i + 1, index);
+        looking &= !found;
+    }
+    good &= !looking & !(ct_lt_usize(index, 11) as u8);
+    (good, index)
+}
+
+/// 加密使用的公钥 (n, e, 模数字节长度)，模数必须在 2048 到 8192 位之间
+fn rsa_encryption_key(pk: &[u8]) -> Result<(BigUint, BigUint, usize), CryptoError> {
+    match rsa_public_key(pk) {
+        Some((n, e)) if n.bits() >= 2048 && n.bits() <= 8192 => {
+            let k = n.bits().div_ceil(8);
+            Ok((n, e, k))
+        }
+        _ => Err(CryptoError::InvalidKey(
+            "signature: malformed rsa public key".to_string(),
+        )),
+    }
+}
+
+fn pss_alg(
+    padAlg: PaddingAlg,
+) -> Result<
+    (
+        &'static dyn signature::RsaEncoding,
+        &'static ring::digest::Algorithm,
+    ),
+    CryptoError,
+> {
+    match padAlg {
+        PaddingAlg::RSA_PSS_SHA256 => Ok((&signature::RSA_PSS_SHA256, &ring::digest::SHA256)),
+        PaddingAlg::RSA_PSS_SHA384 => Ok((&signature::RSA_PSS_SHA384, &ring::digest::SHA384)),
+        PaddingAlg::RSA_PSS_SHA512 => Ok((&signature::RSA_PSS_SHA512, &ring::digest::SHA512)),
+        _ => Err(CryptoError::InvalidInput(
+            "signature: not a pss padding algorithm".to_string(),
+        )),
+    }
+}
+
+/// 摘要算法，PKCS#1 v1.5 时同时返回 DigestInfo 的DER前缀 (RFC 8017 9.2)
+fn prehash_alg(padAlg: PaddingAlg) -> (&'static ring::digest::Algorithm, Option<&'static [u8]>) {
+    const SHA256_PREFIX: &[u8] = &[
+        0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01,
+        0x05, 0x00, 0x04, 0x20,
+    ];
+    const SHA384_PREFIX: &[u8] = &[
+        0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02,
+        0x05, 0x00, 0x04, 0x30,
+    ];
+    const SHA512_PREFIX: &[u8] = &[
+        0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x03,
+        0x05, 0x00, 0x04, 0x40,
+    ];
+    match padAlg {
+        PaddingAlg::RSA_PKCS1_SHA256 => (&ring::digest::SHA256, Some(SHA256_PREFIX)),
+        PaddingAlg::RSA_PKCS1_SHA384 => (&ring::digest::SHA384, Some(SHA384_PREFIX)),
+        PaddingAlg::RSA_PKCS1_SHA512 => (&ring::digest::SHA512, Some(SHA512_PREFIX)),
+        PaddingAlg::RSA_PSS_SHA256 => (&ring::digest::SHA256, None),
+        PaddingAlg::RSA_PSS_SHA384 => (&ring::digest::SHA384, None),
+        PaddingAlg::RSA_PSS_SHA512 => (&ring::digest::SHA512, None),
+    }
+}
+
+fn verify_digest(
+    padAlg: PaddingAlg,
+    digest: &[u8],
+    sig: &[u8],
+    pk: &[u8],
+) -> Result<(), VerifyFailure> {
+    let (n, e) = rsa_public_key(pk).ok_or(VerifyFailure::MalformedKey)?;
+    if n.bits() < 2048 || n.bits() > 8192 {
+        return Err(VerifyFailure::MalformedKey);
+    }
+    let modulus_len = n.bits().div_ceil(8);
+    if sig.len() != modulus_len {
+        return Err(VerifyFailure::WrongLength {
+            expected: modulus_len,
+            actual: sig.len(),
+        });
+    }
+    let (alg, prefix) = prehash_alg(padAlg);
+    if digest.len() != alg.output_len {
+        return Err(VerifyFailure::WrongLength {
+            expected: alg.output_len,
+            actual: digest.len(),
+        });
+    }
+    let s = BigUint::from_bytes_be(sig);
+    if s >= n {
+        return Err(VerifyFailure::SignatureInvalid);
+    }
+    let m = s.modpow(&e, &n);
+    let em_bits = n.bits() - 1;
+    let ok = match prefix {
+        Some(prefix) => match (
+            to_fixed_bytes(&m, modulus_len),
+            emsa_pkcs1_encode(prefix, digest, modulus_len),
+        ) {
+            (Some(em), Some(expected)) => ct_eq(&em, &expected),
+            _ => false,
+        },
+        None => match to_fixed_bytes(&m, em_bits.div_ceil(8)) {
+            Some(em) => emsa_pss_verify(alg, digest, &em, em_bits),
+            None => false,
+        },
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(VerifyFailure::SignatureInvalid)
+    }
+}
+
+/// EM = 00 | 01 | FF..FF | 00 | DigestInfo | H
+fn emsa_pkcs1_encode(prefix: &[u8], digest: &[u8], em_len: usize) -> Option<Vec<u8>> {
+    let t_len = prefix.len() + digest.len();
+    if em_len < t_len + 11 {
+        return None;
+    }
+    let mut em = vec![0xff; em_len];
+    em[0] = 0x00;
+    em[1] = 0x01;
+    em[em_len - t_len - 1] = 0x00;
+    em[em_len - t_len..em_len - digest.len()].copy_from_slice(prefix);
+    em[em_len - digest.len()..].copy_from_slice(digest);
+    Some(em)
+}
+
+/// EMSA-PSS-ENCODE (RFC 8017 9.1.1)，MGF1 使用与消息相同的哈希算法
+fn emsa_pss_encode(
+    alg: &'static ring::digest::Algorithm,
+    m_hash: &[u8],
+    salt: &[u8],
+    em_bits: usize,
+) -> Option<Vec<u8>> {
+    let h_len = alg.output_len;
+    let em_len = em_bits.div_ceil(8);
+    if em_len < h_len + salt.len() + 2 {
+        return None;
+    }
+    let h = pss_hash(alg, m_hash, salt);
+    let db_len = em_len - h_len - 1;
+    let mut em = vec![0u8; db_len];
+    em[db_len - salt.len() - 1] = 0x01;
+    em[db_len - salt.len()..].copy_from_slice(salt);
+    for (b, m) in em.iter_mut().zip(mgf1(alg, &h, db_len)) {
+        *b ^= m;
+    }
+    em[0] &= 0xff >> (8 * em_len - em_bits);
+    em.extend_from_slice(&h);
+    em.push(0xbc);
+    Some(em)
+}
+
+/// EMSA-PSS-VERIFY (RFC 8017 9.1.2)，盐长度必须等于哈希长度
+fn emsa_pss_verify(
+    alg: &'static ring::digest::Algorithm,
+    m_hash: &[u8],
+    em: &[u8],
+    em_bits: usize,
+) -> bool {
+    let h_len = alg.output_len;
+    let em_len = em.len();
+    let top_bits = 8 * em_len - em_bits;
+    if em_len < 2 * h_len + 2 || em[em_len - 1] != 0xbc || em[0] & !(0xff >> top_bits) != 0 {
+        return false;
+    }
+    let (masked_db, h) = em[..em_len - 1].split_at(em_len - h_len - 1);
+    let mut db: Vec<u8> = masked_db
+        .iter()
+        .zip(mgf1(alg, h, masked_db.len()))
+        .map(|(b, m)| b ^ m)
+        .collect();
+    db[0] &= 0xff >> top_bits;
+    let ps_len = db.len() - h_len - 1;
+    if db[..ps_len].iter().any(|&b| b != 0) || db[ps_len] != 0x01 {
+        return false;
+    }
+    ct_eq(&pss_hash(alg, m_hash, &db[ps_len + 1..]), h)
+}
+
+/// H = Hash(00 * 8 | mHash | salt)
+fn pss_hash(alg: &'static ring::digest::Algorithm, m_hash: &[u8], salt: &[u8]) -> Vec<u8> {
+    let mut ctx = ring::digest::Context::new(alg);
+    ctx.update(&[0u8; 8]);
+    ctx.update(m_hash);
+    ctx.update(salt);
+    ctx.finish().as_ref().to_vec()
+}
+
+fn mgf1(alg: &'static ring::digest::Algorithm, seed: &[u8], len: usize) -> Vec<u8> {
+    let mut mask = Vec::with_capacity(len + alg.output_len);
+    let mut counter = 0u32;
+    while mask.len() < len {
+        let mut ctx = ring::digest::Context::new(alg);
+        ctx.update(seed);
+        ctx.update(&counter.to_be_bytes());
+        mask.extend_from_slice(ctx.finish().as_ref());
+        counter += 1;
+    }
+    mask.truncate(len);
+    mask
+}
+
+/// 按固定长度编码秘密整数 x < 2^(8 * len)
+///
+/// 先加上 2^(8 * len) 使整数的长度固定，再去掉最高字节，to_bytes_be 的输出长度不随 x 的前导零变化
+fn to_fixed_bytes_ct(x: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = (x + (BigUint::one() << (8 * len))).to_bytes_be();
+    bytes[1..].to_vec()
+}
+
+/// 隐式拒绝的伪随机函数，HMAC-SHA256(kdk, I2OSP(i, 2) | label | I2OSP(输出位数, 2)) 依次连接
+fn implicit_rejection_prf(kdk: &[u8], label: &[u8], len: usize) -> Vec<u8> {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, kdk);
+    let bits = (len * 8) as u16;
+    let mut out = Vec::with_capacity(len + 32);
+    let mut i = 0u16;
+    while out.len() < len {
+        let mut ctx = ring::hmac::Context::with_key(&key);
+        ctx.update(&i.to_be_bytes());
+        ctx.update(label);
+        ctx.update(&bits.to_be_bytes());
+        out.extend_from_slice(ctx.sign().as_ref());
+        i += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn to_fixed_bytes(x: &BigUint, len: usize) -> Option<Vec<u8>> {
+    let bytes = x.to_bytes_be();
+    if bytes.len() > len {
+        return None;
+    }
+    let mut out = vec![0u8; len];
+    out[len - bytes.len()..].copy_from_slice(&bytes);
+    Some(out)
+}
+
+/// RSA私钥参数
+///
+/// BigUint 不提供对内部存储的写访问，释放时无法擦除，导出的 DER 由调用方用 SecretBytes 承载
+struct RsaPrivateKey {
+    n: BigUint,
+    e: BigUint,
+    d: BigUint,
+    p: BigUint,
+    q: BigUint,
+    dp: BigUint,
+    dq: BigUint,
+    qinv: BigUint,
+}
+
+impl RsaPrivateKey {
+    /// PrivateKeyInfo ::= SEQUENCE { version, algorithm, privateKey OCTET STRING }，
+    /// privateKey 为 RSAPrivateKey ::= SEQUENCE { version, n, e, d, p, q, dp, dq, qinv }
+    fn from_pkcs8(input: &[u8]) -> Option<Self> {
+        let blocks = simple_asn1::from_der(input).ok()?;
+        let key = match blocks.as_slice() {
+            [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+                [_, _, ASN1Block::OctetString(_, key), ..] => simple_asn1::from_der(key).ok()?,
+                _ => return None,
+            },
+            _ => return None,
+        };
+        let ints = match key.as_slice() {
+            [ASN1Block::Sequence(_, items)] => items
+                .iter()
+                .map(|item| match item {
+                    ASN1Block::Integer(_, i) => i.to_biguint(),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?,
+            _ => return None,
+        };
+        match ints.as_slice() {
+            [_, n, e, d, p, q, dp, dq, qinv] => Some(RsaPrivateKey {
+                n: n.clone(),
+                e: e.clone(),
+                d: d.clone(),
+                p: p.clone(),
+                q: q.clone(),
+                dp: dp.clone(),
+                dq: dq.clone(),
+                qinv: qinv.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// 按 FIPS 186-5 A.1.3 生成两个 bits/2 位的素数，d = e^-1 mod lcm(p-1, q-1)
+    fn generate(bits: usize, rng: &dyn SecureRandom) -> Option<Self> {
+        let e = BigUint::from(65537u32);
+        let one = BigUint::one();
+        loop {
+            let p = random_prime(bits / 2, &e, rng);
+            let q = random_prime(bits / 2, &e, rng);
+            // |p - q| 过小时可以用费马方法分解
+            let diff = if p > q { &p - &q } else { &q - &p };
+            if diff.bits() <= bits / 2 - 100 {
+                continue;
+            }
+            let (p, q) = if p > q { (p, q) } else { (q, p) };
+            let (p1, q1) = (&p - &one, &q - &one);
+            let lambda = &p1 * &q1 / gcd(&p1, &q1);
+            let d = mod_inverse(&e, &lambda)?;
+            // d 过小时容易受到 Wiener 攻击
+            if d.bits() <= bits / 2 {
+                continue;
+            }
+            return Some(RsaPrivateKey {
+                n: &p * &q,
+                dp: &d % &p1,
+                dq: &d % &q1,
+                qinv: q.modpow(&(&p - BigUint::from(2u32)), &p),
+                e,
+                d,
+                p,
+                q,
+            });
+        }
+    }
+
+    /// RSAPrivateKey ::= SEQUENCE { version, n, e, d, p, q, dp, dq, qinv }
+    fn to_pkcs1(&self) -> Result<Vec<u8>, CryptoError> {
+        let ints = [
+            &BigUint::zero(),
+            &self.n,
+            &self.e,
+            &self.d,
+            &self.p,
+            &self.q,
+            &self.dp,
+            &self.dq,
+            &self.qinv,
+        ];
+        let items = ints
+            .iter()
+            .map(|i| ASN1Block::Integer(0, BigInt::from((*i).clone())))
+            .collect();
+        encode_der(&ASN1Block::Sequence(0, items))
+    }
+
+    /// PrivateKeyInfo ::= SEQUENCE { version, algorithm rsaEncryption, privateKey OCTET STRING }
+    fn to_pkcs8(&self) -> Result<Vec<u8>, CryptoError> {
+        let info = ASN1Block::Sequence(
+            0,
+            vec![
+                ASN1Block::Integer(0, BigInt::zero()),
+                ASN1Block::Sequence(
+                    0,
+                    vec![
+                        ASN1Block::ObjectIdentifier(0, oid!(1, 2, 840, 113549, 1, 1, 1)),
+                        ASN1Block::Null(0),
+                    ],
+                ),
+                ASN1Block::OctetString(0, self.to_pkcs1()?),
+            ],
+        );
+        encode_der(&info)
+    }
+
+    /// m^d mod n，使用CRT计算，并用公钥检查结果，避免计算错误的签名泄露私钥因子
+    ///
+    /// num-bigint 的模幂不是常量时间实现，运算前用随机数 r 把输入盲化为 m * r^e mod n，
+    /// 结果再乘以 r^-1，模幂的耗时与攻击者选择的输入无关
+    fn private_op(&self, m: &BigUint, rng: &dyn SecureRandom) -> Option<BigUint> {
+        if *m >= self.n {
+            return None;
+        }
+        let len = self.n.bits().div_ceil(8);
+        let (r, r_inv) = loop {
+            let r = BigUint::from_bytes_be(&rng.gen_bytes(len)) % &self.n;
+            if let Some(r_inv) = mod_inverse(&r, &self.n) {
+                break (r, r_inv);
+            }
+        };
+        let blinded = m * r.modpow(&self.e, &self.n) % &self.n;
+        let s1 = blinded.modpow(&self.dp, &self.p);
+        let s2 = blinded.modpow(&self.dq, &self.q);
+        let h = (&self.qinv * ((&s1 + &self.p - &s2 % &self.p) % &self.p)) % &self.p;
+        let s = s2 + h * &self.q;
+        if s.modpow(&self.e, &self.n) != blinded {
+            return None;
+        }
+        Some(s * r_inv % &self.n)
+    }
+
+    /// 隐式拒绝的密钥 KDK = HMAC-SHA256(SHA256(I2OSP(d, k)), 密文)，密文长度已检查为 k
+    fn implicit_rejection_kdk(&self, ciphertext: &[u8]) -> Vec<u8> {
+        let d = SecretBytes::new(to_fixed_bytes_ct(&self.d, ciphertext.len()));
+        let d_hash = ring::digest::digest(&ring::digest::SHA256, &d);
+        let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, d_hash.as_ref());
+        ring::hmac::sign(&key, ciphertext).as_ref().to_vec()
+    }
+}
+
+fn encode_der(block: &ASN1Block) -> Result<Vec<u8>, CryptoError> {
+    to_der(block).map_err(|e| {
+        CryptoError::OperationFailed(format!("signature: rsa key encode error: {:?}", e))
+    })
+}
+
+/// 最高两位为1的随机素数，保证两个素数的乘积恰好为 2*bits 位，且 p-1 与 e 互素
+fn random_prime(bits: usize, e: &BigUint, rng: &dyn SecureRandom) -> BigUint {
+    let top = BigUint::from(3u32) << (bits - 2);
+    loop {
+        let mut bytes = rng.gen_bytes(bits.div_ceil(8));
+        bytes[0] &= 0xff >> (8 * bytes.len() - bits);
+        let candidate = BigUint::from_bytes_be(&bytes) | &top | BigUint::one();
+        if (&candidate % e).is_one() {
+            continue;
+        }
+        if is_probable_prime(&candidate, 5, rng) {
+            return candidate;
+        }
+    }
+}
+
+/// 小素数试除后做 Miller-Rabin 测试，rounds 为随机底数的个数
+fn is_probable_prime(n: &BigUint, rounds: usize, rng: &dyn SecureRandom) -> bool {
+    let small_primes = (3u32..2000).step_by(2).filter(|p| {
+        (3..*p)
+            .step_by(2)
+            .take_while(|d| d * d <= *p)
+            .all(|d| p % d != 0)
+    });
+    for p in std::iter::once(2).chain(small_primes) {
+        let p = BigUint::from(p);
+        if *n == p {
+            return true;
+        }
+        if (n % &p).is_zero() {
+            return false;
+        }
+    }
+    let one = BigUint::one();
+    let n1 = n - &one;
+    let mut r = n1.clone();
+    let mut s = 0;
+    while (&r % 2u32).is_zero() {
+        r >>= 1;
+        s += 1;
+    }
+    let len = n.bits().div_ceil(8);
+    'witness: for _ in 0..rounds {
+        let a = BigUint::from_bytes_be(&rng.gen_bytes(len)) % (n - 3u32) + 2u32;
+        let mut x = a.modpow(&r, n);
+        if x == one || x == n1 {
+            continue;
+        }
+        for _ in 1..s {
+            x = &x * &x % n;
+            if x == n1 {
+                continue 'witness;
+            }
+        }
+        return false;
+    }
+    true
+}
+
+fn gcd(a: &BigUint, b: &BigUint) -> BigUint {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    while !b.is_zero() {
+        let r = &a % &b;
+        a = b;
+        b = r;
+    }
+    a
+}
+
+/// 扩展欧几里得算法求 a^-1 mod m
+fn mod_inverse(a: &BigUint, m: &BigUint) -> Option<BigUint> {
+    let modulus = BigInt::from(m.clone());
+    let (mut t, mut new_t) = (BigInt::zero(), BigInt::one());
+    let (mut r, mut new_r) = (modulus.clone(), BigInt::from(a.clone()));
+    while !new_r.is_zero() {
+        let q = &r / &new_r;
+        let next_t = &t - &q * &new_t;
+        t = std::mem::replace(&mut new_t, next_t);
+        let next_r = &r - &q * &new_r;
+        r = std::mem::replace(&mut new_r, next_r);
+    }
+    if !r.is_one() {
+        return None;
+    }
+    if t < BigInt::zero() {
+        t += &modulus;
+    }
+    t.to_biguint()
+}
+
+/// RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+fn rsa_public_key(pk: &[u8]) -> Option<(BigUint, BigUint)> {
+    let blocks = simple_asn1::from_der(pk).ok()?;
+    match blocks.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => {
+                Some((n.to_biguint()?, e.to_biguint()?))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 模数的字节长度
+fn rsa_modulus_len(pk: &[u8]) -> Option<usize> {
+    rsa_public_key(pk).map(|(n, _)| n.to_bytes_be().len())
+}
+
+fn find_bit_string(blocks: &[ASN1Block], result: &mut Vec<Vec<u8>>) {
+    for block in blocks.iter() {
+        match block {
+            ASN1Block::BitString(_, _, bytes) => result.push(bytes.to_vec()),
+            ASN1Block::Sequence(_, blocks) => find_bit_string(&blocks[..], result),
+            _ => (),
+        }
+    }
+}
+
+/// NIST曲线 P256 和 P384，签名为DER编码或定长的 r | s (TPM、JWS 等使用)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EcdsaAlg {
+    // Signing of ASN.1 DER-encoded ECDSA signatures using the P-256 curve and SHA-256.
     ECDSA_P256_SHA256_ASN1,
     // Signing of ASN.1 DER-encoded ECDSA signatures using the P-384 curve and SHA-384.
     ECDSA_P384_SHA384_ASN1,
+    // Signing of fixed-length (r | s) ECDSA signatures using the P-256 curve and SHA-256.
+    ECDSA_P256_SHA256_FIXED,
+    // Signing of fixed-length (r | s) ECDSA signatures using the P-384 curve and SHA-384.
+    ECDSA_P384_SHA384_FIXED,
 }
 
 pub struct EcdsaKeyPair {
     key_pair: EcKeyPair,
+    alg: EcdsaAlg,
 }
 
+impl PartialEq for EcdsaKeyPair {
+    /// 按公钥常量时间比较
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.public_key(), &other.public_key())
+    }
+}
+
+impl Eq for EcdsaKeyPair {}
+
 impl EcdsaKeyPair {
     /// 产生pkcs8格式的密钥对
     ///
     /// alg: 产生密钥对的曲线类型
-    pub fn generate_pkcs8(alg: EcdsaAlg) -> Vec<u8> {
+    pub fn generate_pkcs8(alg: EcdsaAlg) -> Result<Vec<u8>, CryptoError> {
         let rng = rand::SystemRandom::new();
-        match alg {
-            EcdsaAlg::ECDSA_P256_SHA256_ASN1 => {
-                signature::EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng)
-                    .unwrap()
-                    .as_ref()
-                    .to_vec()
-            }
-            EcdsaAlg::ECDSA_P384_SHA384_ASN1 => {
-                signature::EcdsaKeyPair::generate_pkcs8(&ECDSA_P384_SHA384_ASN1_SIGNING, &rng)
-                    .unwrap()
-                    .as_ref()
-                    .to_vec()
-            }
-        }
+        signature::EcdsaKeyPair::generate_pkcs8(signing_alg(&alg), &rng)
+            .map(|pkcs8| pkcs8.as_ref().to_vec())
+            .map_err(|_| {
+                CryptoError::OperationFailed("signature: ecdsa key generation failed".to_string())
+            })
     }
 
     /// 从私钥和公钥构建密钥对
@@ -272,51 +1723,56 @@ impl EcdsaKeyPair {
     /// alg: 产生密钥对的曲线类型
     /// priv_key: 私钥
     /// pub_key: 公钥
-    pub fn from_private_key_and_public_key(alg: EcdsaAlg, priv_key: &[u8], pub_key: &[u8]) -> Self {
-        match alg {
-            EcdsaAlg::ECDSA_P256_SHA256_ASN1 => {
-                let key_pair = EcKeyPair::from_private_key_and_public_key(
-                    &ECDSA_P256_SHA256_ASN1_SIGNING,
-                    priv_key,
-                    pub_key,
-                )
-                .unwrap();
-                Self { key_pair }
-            }
-            EcdsaAlg::ECDSA_P384_SHA384_ASN1 => {
-                let key_pair = EcKeyPair::from_private_key_and_public_key(
-                    &ECDSA_P384_SHA384_ASN1_SIGNING,
-                    priv_key,
-                    pub_key,
-                )
-                .unwrap();
-                Self { key_pair }
-            }
-        }
+    pub fn from_private_key_and_public_key(
+        alg: EcdsaAlg,
+        priv_key: &[u8],
+        pub_key: &[u8],
+    ) -> Result<Self, CryptoError> {
+        EcKeyPair::from_private_key_and_public_key(signing_alg(&alg), priv_key, pub_key)
+            .map(|key_pair| Self { key_pair, alg })
+            .map_err(|_| CryptoError::InvalidKey("signature: invalid ecdsa key pair".to_string()))
     }
 
     /// 从pkcs8格式构建密钥对
     ///
     /// alg: 产生密钥对的曲线类型
     /// pkcs: pkcs格式的私钥
-    pub fn from_pkcs8(alg: EcdsaAlg, pkcs8: &[u8]) -> Self {
-        let key_pair = match alg {
-            EcdsaAlg::ECDSA_P256_SHA256_ASN1 => {
-                signature::EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8).unwrap()
-            }
-            EcdsaAlg::ECDSA_P384_SHA384_ASN1 => {
-                signature::EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_ASN1_SIGNING, pkcs8).unwrap()
-            }
-        };
-        Self { key_pair }
+    pub fn from_pkcs8(alg: EcdsaAlg, pkcs8: &[u8]) -> Result<Self, CryptoError> {
+        EcKeyPair::from_pkcs8(signing_alg(&alg), pkcs8)
+            .map(|key_pair| Self { key_pair, alg })
+            .map_err(|_| CryptoError::InvalidKey("signature: malformed ecdsa pkcs8".to_string()))
+    }
+
+    /// 从口令加密的 PKCS#8 (PBES2) 构建密钥对
+    ///
+    /// alg: 产生密钥对的曲线类型
+    /// der: EncryptedPrivateKeyInfo DER
+    /// passphrase: 口令
+    pub fn from_encrypted_pkcs8(
+        alg: EcdsaAlg,
+        der: &[u8],
+        passphrase: &[u8],
+    ) -> Result<Self, CryptoError> {
+        Self::from_pkcs8(alg, &SecretBytes::new(decrypt_pkcs8(der, passphrase)?))
     }
 
     /// 签名
     ///
     /// msg: 签名数据
-    pub fn sign(&self, msg: &[u8]) -> Vec<u8> {
+    pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        // P-256 未压缩公钥65字节，P-384 为97字节
+        let name = match self.key_pair.public_key().as_ref().len() {
+            65 => "ECDSA-P256",
+            _ => "ECDSA-P384",
+        };
+        let timer = Timer::start(Operation::Sign, name);
         let rng = rand::SystemRandom::new();
-        self.key_pair.sign(&rng, msg).unwrap().as_ref().to_vec()
+        let result = self
+            .key_pair
+            .sign(&rng, msg)
+            .map(|sig| sig.as_ref().to_vec())
+            .map_err(|_| CryptoError::OperationFailed("signature: ecdsa sign failed".to_string()));
+        timer.done_result(result)
     }
 
     /// 获取公钥
@@ -325,6 +1781,15 @@ impl EcdsaKeyPair {
     }
 }
 
+fn signing_alg(alg: &EcdsaAlg) -> &'static signature::EcdsaSigningAlgorithm {
+    match alg {
+        EcdsaAlg::ECDSA_P256_SHA256_ASN1 => &ECDSA_P256_SHA256_ASN1_SIGNING,
+        EcdsaAlg::ECDSA_P384_SHA384_ASN1 => &ECDSA_P384_SHA384_ASN1_SIGNING,
+        EcdsaAlg::ECDSA_P256_SHA256_FIXED => &ECDSA_P256_SHA256_FIXED_SIGNING,
+        EcdsaAlg::ECDSA_P384_SHA384_FIXED => &ECDSA_P384_SHA384_FIXED_SIGNING,
+    }
+}
+
 /// 验证ecdsa签名
 ///
 /// alg: 产生密钥对的曲线类型
@@ -332,18 +1797,566 @@ impl EcdsaKeyPair {
 /// msg: 签名的数据
 /// sig: 签名
 pub fn ecdsa_verify(alg: EcdsaAlg, pub_key: &[u8], msg: &[u8], sig: &[u8]) -> bool {
-    let public_key = match alg {
-        EcdsaAlg::ECDSA_P256_SHA256_ASN1 => {
-            signature::UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, pub_key)
+    ecdsa_verify_detailed(alg, pub_key, msg, sig).is_ok()
+}
+
+/// 验证ECDSA签名，失败时返回原因
+///
+/// pub_key 必须是未压缩的公钥(0x04 | x | y)或 SubjectPublicKeyInfo DER，sig 按 alg 为DER编码的 (r, s) 或定长的 r | s
+pub fn ecdsa_verify_detailed(
+    alg: EcdsaAlg,
+    pub_key: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<(), VerifyFailure> {
+    let (verify_alg, name, key_len, fixed_len): (
+        &'static signature::EcdsaVerificationAlgorithm,
+        _,
+        _,
+        _,
+    ) = match alg {
+        EcdsaAlg::ECDSA_P256_SHA256_ASN1 => (&ECDSA_P256_SHA256_ASN1, "ECDSA-P256", 65, None),
+        EcdsaAlg::ECDSA_P384_SHA384_ASN1 => (&ECDSA_P384_SHA384_ASN1, "ECDSA-P384", 97, None),
+        EcdsaAlg::ECDSA_P256_SHA256_FIXED => (&ECDSA_P256_SHA256_FIXED, "ECDSA-P256", 65, Some(64)),
+        EcdsaAlg::ECDSA_P384_SHA384_FIXED => (&ECDSA_P384_SHA384_FIXED, "ECDSA-P384", 97, Some(96)),
+    };
+    let key_type = if key_len == 65 {
+        PublicKeyType::EcdsaP256
+    } else {
+        PublicKeyType::EcdsaP384
+    };
+    let pub_key = &public_key(key_type, pub_key)?[..];
+    let public_key = signature::UnparsedPublicKey::new(verify_alg, pub_key);
+
+    let timer = Timer::start(Operation::Verify, name);
+    if pub_key.len() != key_len {
+        return Err(VerifyFailure::WrongLength {
+            expected: key_len,
+            actual: pub_key.len(),
+        });
+    }
+    if pub_key[0] != 0x04 {
+        return Err(VerifyFailure::MalformedKey);
+    }
+    match fixed_len {
+        Some(expected) if sig.len() != expected => {
+            return Err(VerifyFailure::WrongLength {
+                expected,
+                actual: sig.len(),
+            });
         }
-        EcdsaAlg::ECDSA_P384_SHA384_ASN1 => {
-            signature::UnparsedPublicKey::new(&ECDSA_P384_SHA384_ASN1, pub_key)
+        Some(_) => {}
+        None => match simple_asn1::from_der(sig).as_ref().map(|b| b.as_slice()) {
+            Ok([ASN1Block::Sequence(_, items)])
+                if matches!(
+                    items.as_slice(),
+                    [ASN1Block::Integer(_, _), ASN1Block::Integer(_, _)]
+                ) => {}
+            _ => return Err(VerifyFailure::MalformedSignature),
+        },
+    }
+    let result = public_key
+        .verify(msg, sig)
+        .map_err(|_| VerifyFailure::SignatureInvalid);
+    timer.done_result(result)
+}
+
+/// 签名方案类型，用于需要在运行时区分签名算法的场合
+///
+/// Secp256k1 方案对任意长度的消息先做 SHA256 哈希再签名，签名为DER编码；
+/// Rsa 方案的公钥为 PKCS#1 RSAPublicKey 的DER编码；
+/// Sm2 方案使用 sm2::DEFAULT_ID 作为签名者身份，签名为64字节 r | s；
+/// Ed448 方案使用空上下文
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureScheme {
+    Secp256k1,
+    Ed25519,
+    EcdsaP256,
+    EcdsaP384,
+    RsaPkcs1Sha256,
+    RsaPssSha256,
+    Sm2,
+    Ed448,
+}
+
+impl SignatureScheme {
+    /// 获取签名方案的1字节编号
+    pub fn id(self) -> u8 {
+        match self {
+            SignatureScheme::Secp256k1 => 1,
+            SignatureScheme::Ed25519 => 2,
+            SignatureScheme::EcdsaP256 => 3,
+            SignatureScheme::EcdsaP384 => 4,
+            SignatureScheme::RsaPkcs1Sha256 => 5,
+            SignatureScheme::RsaPssSha256 => 6,
+            SignatureScheme::Sm2 => 7,
+            SignatureScheme::Ed448 => 8,
         }
-    };
+    }
 
-    match public_key.verify(msg, sig) {
-        Ok(()) => true,
-        Err(_) => false,
+    /// 从1字节编号获取签名方案
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(SignatureScheme::Secp256k1),
+            2 => Some(SignatureScheme::Ed25519),
+            3 => Some(SignatureScheme::EcdsaP256),
+            4 => Some(SignatureScheme::EcdsaP384),
+            5 => Some(SignatureScheme::RsaPkcs1Sha256),
+            6 => Some(SignatureScheme::RsaPssSha256),
+            7 => Some(SignatureScheme::Sm2),
+            8 => Some(SignatureScheme::Ed448),
+            _ => None,
+        }
+    }
+}
+
+/// 按签名方案验证签名
+///
+/// scheme: 签名方案
+/// pk: 公钥
+/// msg: 已签名的数据
+/// sig: 签名
+pub fn scheme_verify(scheme: SignatureScheme, pk: &[u8], msg: &[u8], sig: &[u8]) -> bool {
+    scheme_verify_detailed(scheme, pk, msg, sig).is_ok()
+}
+
+/// 按签名方案验证签名，失败时返回原因
+pub fn scheme_verify_detailed(
+    scheme: SignatureScheme,
+    pk: &[u8],
+    msg: &[u8],
+    sig: &[u8],
+) -> Result<(), VerifyFailure> {
+    match scheme {
+        SignatureScheme::Secp256k1 => {
+            ECDSASecp256k1::new().verify_msg_detailed(MsgDigest::Sha256, msg, sig, pk)
+        }
+        SignatureScheme::Ed25519 => crate::ed25519::verify_detailed(msg, pk, sig),
+        SignatureScheme::EcdsaP256 => {
+            ecdsa_verify_detailed(EcdsaAlg::ECDSA_P256_SHA256_ASN1, pk, msg, sig)
+        }
+        SignatureScheme::EcdsaP384 => {
+            ecdsa_verify_detailed(EcdsaAlg::ECDSA_P384_SHA384_ASN1, pk, msg, sig)
+        }
+        SignatureScheme::RsaPkcs1Sha256 => {
+            Rsa::verify_detailed(PaddingAlg::RSA_PKCS1_SHA256, msg, sig, pk)
+        }
+        SignatureScheme::RsaPssSha256 => {
+            Rsa::verify_detailed(PaddingAlg::RSA_PSS_SHA256, msg, sig, pk)
+        }
+        SignatureScheme::Sm2 => crate::sm2::verify_detailed(msg, sig, pk, crate::sm2::DEFAULT_ID),
+        SignatureScheme::Ed448 => crate::ed448::verify_detailed(msg, pk, sig),
+    }
+}
+
+/// 带签名方案标签的公钥，用于在运行时才知道签名方案的网络协议
+///
+/// 编码为 方案编号(1字节) | 公钥，编号与 SignatureScheme::id 相同
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnyPublicKey {
+    /// 33或65字节公钥
+    Secp256k1(Vec<u8>),
+    /// 32字节公钥
+    Ed25519(Vec<u8>),
+    /// 65字节未压缩公钥
+    EcdsaP256(Vec<u8>),
+    /// 97字节未压缩公钥
+    EcdsaP384(Vec<u8>),
+    /// PKCS#1 RSAPublicKey DER
+    RsaPkcs1Sha256(Vec<u8>),
+    /// PKCS#1 RSAPublicKey DER
+    RsaPssSha256(Vec<u8>),
+    /// 65字节未压缩公钥
+    Sm2(Vec<u8>),
+    /// 57字节公钥
+    Ed448(Vec<u8>),
+}
+
+impl AnyPublicKey {
+    /// 由签名方案和原始公钥构建，公钥长度与方案不符时返回错误
+    pub fn new(scheme: SignatureScheme, pk: &[u8]) -> Result<Self, CryptoError> {
+        let valid = match scheme {
+            SignatureScheme::Secp256k1 => pk.len() == 33 || pk.len() == 65,
+            SignatureScheme::Ed25519 => pk.len() == 32,
+            SignatureScheme::EcdsaP256 | SignatureScheme::Sm2 => pk.len() == 65,
+            SignatureScheme::EcdsaP384 => pk.len() == 97,
+            SignatureScheme::Ed448 => pk.len() == 57,
+            SignatureScheme::RsaPkcs1Sha256 | SignatureScheme::RsaPssSha256 => !pk.is_empty(),
+        };
+        if !valid {
+            return Err(CryptoError::InvalidKey(format!(
+                "signature: wrong public key length {} for {:?}",
+                pk.len(),
+                scheme
+            )));
+        }
+        let pk = pk.to_vec();
+        Ok(match scheme {
+            SignatureScheme::Secp256k1 => AnyPublicKey::Secp256k1(pk),
+            SignatureScheme::Ed25519 => AnyPublicKey::Ed25519(pk),
+            SignatureScheme::EcdsaP256 => AnyPublicKey::EcdsaP256(pk),
+            SignatureScheme::EcdsaP384 => AnyPublicKey::EcdsaP384(pk),
+            SignatureScheme::RsaPkcs1Sha256 => AnyPublicKey::RsaPkcs1Sha256(pk),
+            SignatureScheme::RsaPssSha256 => AnyPublicKey::RsaPssSha256(pk),
+            SignatureScheme::Sm2 => AnyPublicKey::Sm2(pk),
+            SignatureScheme::Ed448 => AnyPublicKey::Ed448(pk),
+        })
+    }
+
+    /// 获取签名方案
+    pub fn scheme(&self) -> SignatureScheme {
+        match self {
+            AnyPublicKey::Secp256k1(_) => SignatureScheme::Secp256k1,
+            AnyPublicKey::Ed25519(_) => SignatureScheme::Ed25519,
+            AnyPublicKey::EcdsaP256(_) => SignatureScheme::EcdsaP256,
+            AnyPublicKey::EcdsaP384(_) => SignatureScheme::EcdsaP384,
+            AnyPublicKey::RsaPkcs1Sha256(_) => SignatureScheme::RsaPkcs1Sha256,
+            AnyPublicKey::RsaPssSha256(_) => SignatureScheme::RsaPssSha256,
+            AnyPublicKey::Sm2(_) => SignatureScheme::Sm2,
+            AnyPublicKey::Ed448(_) => SignatureScheme::Ed448,
+        }
+    }
+
+    /// 获取原始公钥
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            AnyPublicKey::Secp256k1(pk)
+            | AnyPublicKey::Ed25519(pk)
+            | AnyPublicKey::EcdsaP256(pk)
+            | AnyPublicKey::EcdsaP384(pk)
+            | AnyPublicKey::RsaPkcs1Sha256(pk)
+            | AnyPublicKey::RsaPssSha256(pk)
+            | AnyPublicKey::Sm2(pk)
+            | AnyPublicKey::Ed448(pk) => pk,
+        }
+    }
+
+    /// 编码为 方案编号 | 公钥
+    pub fn encode(&self) -> Vec<u8> {
+        tagged(self.scheme(), self.as_bytes())
+    }
+
+    /// 解码 encode 的输出
+    pub fn decode(data: &[u8]) -> Result<Self, CryptoError> {
+        let (scheme, pk) = split_tag(data)?;
+        AnyPublicKey::new(scheme, pk)
+    }
+
+    /// 验证带标签的签名，签名方案与公钥不一致时返回 MalformedSignature
+    pub fn verify_any(&self, msg: &[u8], sig: &AnySignature) -> Result<(), VerifyFailure> {
+        if sig.scheme != self.scheme() {
+            return Err(VerifyFailure::MalformedSignature);
+        }
+        self.verify(msg, &sig.signature)
+    }
+}
+
+impl Verifier for AnyPublicKey {
+    /// 按公钥的签名方案验证原始签名
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+        scheme_verify_detailed(self.scheme(), self.as_bytes(), msg, sig)
+    }
+}
+
+/// 带签名方案标签的签名，编码为 方案编号(1字节) | 签名
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnySignature {
+    scheme: SignatureScheme,
+    signature: Vec<u8>,
+}
+
+impl AnySignature {
+    /// 由签名方案和原始签名构建
+    pub fn new(scheme: SignatureScheme, sig: &[u8]) -> Self {
+        AnySignature {
+            scheme,
+            signature: sig.to_vec(),
+        }
+    }
+
+    /// 获取签名方案
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    /// 获取原始签名
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.signature
+    }
+
+    /// 编码为 方案编号 | 签名
+    pub fn encode(&self) -> Vec<u8> {
+        tagged(self.scheme, &self.signature)
+    }
+
+    /// 解码 encode 的输出
+    pub fn decode(data: &[u8]) -> Result<Self, CryptoError> {
+        let (scheme, sig) = split_tag(data)?;
+        Ok(AnySignature::new(scheme, sig))
+    }
+
+    /// 连同公钥一起编码，格式为 方案编号 | 公钥长度(u16) | 公钥 | 签名，公钥必须属于同一方案
+    pub fn encode_with_public_key(&self, pk: &AnyPublicKey) -> Result<Vec<u8>, CryptoError> {
+        if pk.scheme() != self.scheme {
+            return Err(CryptoError::InvalidInput(
+                "signature: public key and signature use different schemes".to_string(),
+            ));
+        }
+        let pk = pk.as_bytes();
+        let len = u16::try_from(pk.len()).map_err(|_| {
+            CryptoError::InvalidKey("signature: public key longer than 65535 bytes".to_string())
+        })?;
+        let mut out = vec![self.scheme.id()];
+        out.extend_from_slice(&len.to_be_bytes());
+        out.extend_from_slice(pk);
+        out.extend_from_slice(&self.signature);
+        Ok(out)
+    }
+
+    /// 解码 encode_with_public_key 的输出，返回 (公钥, 签名)
+    pub fn decode_with_public_key(data: &[u8]) -> Result<(AnyPublicKey, Self), CryptoError> {
+        let (scheme, rest) = split_tag(data)?;
+        if rest.len() < 2 {
+            return Err(CryptoError::InvalidInput(
+                "signature: truncated public key length".to_string(),
+            ));
+        }
+        let (len, rest) = rest.split_at(2);
+        let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+        if rest.len() < len {
+            return Err(CryptoError::InvalidInput(
+                "signature: truncated public key".to_string(),
+            ));
+        }
+        let (pk, sig) = rest.split_at(len);
+        Ok((
+            AnyPublicKey::new(scheme, pk)?,
+            AnySignature::new(scheme, sig),
+        ))
+    }
+}
+
+fn tagged(scheme: SignatureScheme, data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(1 + data.len());
+    out.push(scheme.id());
+    out.extend_from_slice(data);
+    out
+}
+
+fn split_tag(data: &[u8]) -> Result<(SignatureScheme, &[u8]), CryptoError> {
+    let (&id, rest) = data
+        .split_first()
+        .ok_or_else(|| CryptoError::InvalidInput("signature: empty input".to_string()))?;
+    let scheme = SignatureScheme::from_id(id).ok_or_else(|| {
+        CryptoError::InvalidInput(format!("signature: unknown signature scheme {}", id))
+    })?;
+    Ok((scheme, rest))
+}
+
+/// 签名者，泛型代码通过它使用任意签名方案签名，签名方案由具体类型在配置时决定
+///
+/// 哈希算法、填充方式等参数在构建签名者时确定，sign 只需要消息
+pub trait Signer {
+    /// 验证该签名者生成的签名所用的公钥类型
+    type PublicKey: Verifier;
+
+    /// 对任意长度的消息签名
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError>;
+
+    /// 获取对应的公钥，参数与签名者相同
+    fn public_key(&self) -> Result<Self::PublicKey, CryptoError>;
+}
+
+/// 验证者，与 Signer 配对使用
+pub trait Verifier {
+    /// 验证签名，失败时返回原因
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure>;
+}
+
+/// secp256k1 签名者，消息先按 digest 哈希，签名为DER编码
+pub struct Secp256k1Signer {
+    secret: SecretBytes,
+    digest: MsgDigest,
+}
+
+impl Secp256k1Signer {
+    /// 构建签名者
+    ///
+    /// sk: 32字节私钥
+    /// digest: 消息哈希算法
+    pub fn new(sk: &[u8], digest: MsgDigest) -> Result<Self, CryptoError> {
+        secp256k1_secret_key(sk)?;
+        Ok(Secp256k1Signer {
+            secret: SecretBytes::from(sk),
+            digest,
+        })
+    }
+}
+
+impl Signer for Secp256k1Signer {
+    type PublicKey = Secp256k1Verifier;
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        ECDSASecp256k1::new().sign_msg(self.digest, msg, &self.secret)
+    }
+
+    fn public_key(&self) -> Result<Secp256k1Verifier, CryptoError> {
+        let pk = ECDSASecp256k1::new().public_key_from_secret(&self.secret)?;
+        Ok(Secp256k1Verifier::new(&pk.compressed, self.digest))
+    }
+}
+
+/// secp256k1 验证者
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Secp256k1Verifier {
+    public_key: Vec<u8>,
+    digest: MsgDigest,
+}
+
+impl Secp256k1Verifier {
+    /// 构建验证者
+    ///
+    /// pk: 公钥，长度为33或65字节，或 SubjectPublicKeyInfo DER
+    /// digest: 消息哈希算法，必须与签名方相同
+    pub fn new(pk: &[u8], digest: MsgDigest) -> Self {
+        Secp256k1Verifier {
+            public_key: pk.to_vec(),
+            digest,
+        }
+    }
+}
+
+impl Verifier for Secp256k1Verifier {
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+        ECDSASecp256k1::new().verify_msg_detailed(self.digest, msg, sig, &self.public_key)
+    }
+}
+
+impl Signer for crate::ed25519::SecretKey {
+    type PublicKey = Ed25519Verifier;
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        Ok(crate::ed25519::SecretKey::sign(self, msg).to_vec())
+    }
+
+    fn public_key(&self) -> Result<Ed25519Verifier, CryptoError> {
+        Ok(Ed25519Verifier::new(crate::ed25519::SecretKey::public_key(
+            self,
+        )))
+    }
+}
+
+/// ed25519 验证者
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ed25519Verifier {
+    public_key: [u8; 32],
+}
+
+impl Ed25519Verifier {
+    /// 由32字节公钥构建验证者
+    pub fn new(public_key: [u8; 32]) -> Self {
+        Ed25519Verifier { public_key }
+    }
+}
+
+impl Verifier for Ed25519Verifier {
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+        crate::ed25519::verify_detailed(msg, &self.public_key, sig)
+    }
+}
+
+impl Signer for EcdsaKeyPair {
+    type PublicKey = EcdsaVerifier;
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        EcdsaKeyPair::sign(self, msg)
+    }
+
+    fn public_key(&self) -> Result<EcdsaVerifier, CryptoError> {
+        Ok(EcdsaVerifier::new(
+            self.alg,
+            &EcdsaKeyPair::public_key(self),
+        ))
+    }
+}
+
+/// P-256 / P-384 ECDSA 验证者
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcdsaVerifier {
+    alg: EcdsaAlg,
+    public_key: Vec<u8>,
+}
+
+impl EcdsaVerifier {
+    /// 构建验证者
+    ///
+    /// alg: 曲线和签名编码
+    /// pk: 未压缩的公钥或 SubjectPublicKeyInfo DER
+    pub fn new(alg: EcdsaAlg, pk: &[u8]) -> Self {
+        EcdsaVerifier {
+            alg,
+            public_key: pk.to_vec(),
+        }
+    }
+}
+
+impl Verifier for EcdsaVerifier {
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+        ecdsa_verify_detailed(self.alg, &self.public_key, msg, sig)
+    }
+}
+
+/// RSA 签名者，使用固定的填充类型
+pub struct RsaSigner {
+    key: Rsa,
+    padding: PaddingAlg,
+}
+
+impl RsaSigner {
+    /// 构建签名者
+    ///
+    /// key: RSA私钥
+    /// padding: 签名填充类型
+    pub fn new(key: Rsa, padding: PaddingAlg) -> Self {
+        RsaSigner { key, padding }
+    }
+}
+
+impl Signer for RsaSigner {
+    type PublicKey = RsaVerifier;
+
+    fn sign(&self, msg: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        self.key.sign(self.padding, msg)
+    }
+
+    fn public_key(&self) -> Result<RsaVerifier, CryptoError> {
+        Ok(RsaVerifier::new(&self.key.public_key(), self.padding))
+    }
+}
+
+/// RSA 验证者
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RsaVerifier {
+    public_key: Vec<u8>,
+    padding: PaddingAlg,
+}
+
+impl RsaVerifier {
+    /// 构建验证者
+    ///
+    /// pk: PKCS#1 RSAPublicKey 或 SubjectPublicKeyInfo DER
+    /// padding: 签名填充类型，必须与签名方相同
+    pub fn new(pk: &[u8], padding: PaddingAlg) -> Self {
+        RsaVerifier {
+            public_key: pk.to_vec(),
+            padding,
+        }
+    }
+}
+
+impl Verifier for RsaVerifier {
+    fn verify(&self, msg: &[u8], sig: &[u8]) -> Result<(), VerifyFailure> {
+        Rsa::verify_detailed(self.padding, msg, sig, &self.public_key)
     }
 }
 
@@ -360,33 +2373,452 @@ mod tests {
         let msg = [0xcd; 32];
 
         let secp = ECDSASecp256k1::new();
+        fn shareable<T: Send + Sync + Copy>(_: &T) {}
+        shareable(&secp);
+        assert_eq!(std::mem::size_of::<ECDSASecp256k1>(), 0);
 
-        let sig = secp.sign(&msg, &sk);
+        let sig = ECDSASecp256k1::signing_only().sign(&msg, &sk).unwrap();
+        assert!(ECDSASecp256k1::verification_only().verify(&msg, &sig, &pk));
         assert!(secp.verify(&msg, &sig, pk.as_ref()));
+
+        assert!(secp.verify_strict(&msg, &sig, &pk));
+        assert_eq!(secp.normalize_s(&sig).unwrap(), sig);
+        // s 取 n - s 得到同样有效的 high-s 签名
+        let mut high = Signature::parse_der(&sig).unwrap();
+        high.s = -high.s;
+        let high_der = high.serialize_der().as_ref().to_vec();
+        assert!(secp.verify(&msg, &high_der, &pk));
+        assert_eq!(
+            secp.verify_strict_detailed(&msg, &high_der, &pk),
+            Err(VerifyFailure::MalformedSignature)
+        );
+        assert_eq!(secp.normalize_s(&high_der).unwrap(), sig);
+        let compact = secp.normalize_s(&high.serialize()).unwrap();
+        assert!(secp.verify_compact(&msg, &compact, &pk));
+        // r 多一个前导0的非规范编码
+        let mut padded = sig.clone();
+        let rlen = padded[3] as usize;
+        padded.insert(4, 0);
+        padded[3] = rlen as u8 + 1;
+        padded[1] += 1;
+        assert!(!secp.verify_strict(&msg, &padded, &pk));
+
+        let bad = [0xce; 32];
+        let mut items: Vec<(&[u8], &[u8], &[u8])> = vec![(&msg, &sig, &pk); 9];
+        assert!(secp.verify_batch_all(&items));
+        items[5].0 = &bad;
+        let mut expected = vec![true; 9];
+        expected[5] = false;
+        assert_eq!(secp.verify_batch(&items), expected);
+        assert!(!secp.verify_batch_all(&items));
+        assert!(secp.verify_batch(&[]).is_empty());
+        assert!(secp.verify_batch_all(&[]));
+
+        let public_key = secp.public_key_from_secret(&sk).unwrap();
+        assert_eq!(public_key.uncompressed, pk);
+        assert_eq!(&public_key.compressed[1..], &pk[1..33]);
+        assert!(secp.public_key_from_secret(&[0xff; 32]).is_err());
+        let (sk2, pk2) = secp.generate_keypair(&rand::SystemRandom::new()).unwrap();
+        assert_eq!(secp.public_key_from_secret(&sk2).unwrap(), pk2);
+        assert!(secp
+            .generate_keypair(&ring::test::rand::FixedSliceRandom { bytes: &[0u8; 32] })
+            .is_err());
+        assert!(matches!(
+            secp.sign(&msg[1..], &sk),
+            Err(CryptoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            secp.sign(&msg, &[0u8; 32]),
+            Err(CryptoError::InvalidKey(_))
+        ));
+
+        // RFC 6979 确定性签名，私钥为1，消息为 SHA256("Satoshi Nakamoto")
+        let mut one = [0u8; 32];
+        one[31] = 1;
+        let digest = ring::digest::digest(&ring::digest::SHA256, b"Satoshi Nakamoto");
+        let compact = secp.sign_compact(digest.as_ref(), &one).unwrap();
+        assert_eq!(compact, Vec::from_hex("934b1ea10a4b3c1757e2b0c017d0b6143ce3c9a7e6a4a49860d7a6ab210ee3d82442ce9d2b916064108014783e923ec36b49743e2ffa1c4496f01a512aafd9e5").unwrap());
+        let compact = secp.sign_compact(&msg, &sk).unwrap();
+        assert!(secp.verify_compact(&msg, &compact, &pk));
+        // n - s 也是有效签名，但作为可延展签名拒绝
+        let n = BigUint::parse_bytes(
+            b"FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEBAAEDCE6AF48A03BBFD25E8CD0364141",
+            16,
+        )
+        .unwrap();
+        let high_s = (n - BigUint::from_bytes_be(&compact[32..])).to_bytes_be();
+        let mut malleated = compact[..32].to_vec();
+        malleated.extend_from_slice(&high_s);
+        assert_eq!(
+            secp.verify_compact_detailed(&msg, &malleated, &pk),
+            Err(VerifyFailure::MalformedSignature)
+        );
+        assert_eq!(
+            secp.sign(&msg, &sk).unwrap(),
+            Signature::parse_slice(&compact)
+                .unwrap()
+                .serialize_der()
+                .as_ref()
+                .to_vec()
+        );
+        assert_eq!(
+            secp.verify_compact_detailed(&msg, &sig, &pk),
+            Err(VerifyFailure::WrongLength {
+                expected: 64,
+                actual: sig.len()
+            })
+        );
+
+        let mut recoverable = secp.sign_recoverable(&msg, &sk).unwrap();
+        assert_eq!(recoverable.len(), 65);
+        assert!(recoverable[64] == 27 || recoverable[64] == 28);
+        assert_eq!(secp.recover_public_key(&msg, &recoverable).unwrap(), pk);
+        recoverable[64] += 4;
+        let compressed = secp.recover_public_key(&msg, &recoverable).unwrap();
+        assert_eq!(compressed.len(), 33);
+        assert_eq!(&compressed[1..], &pk[1..33]);
+        assert_ne!(
+            secp.recover_public_key(&[0xce; 32], &recoverable).unwrap(),
+            compressed
+        );
+        recoverable[64] = 35;
+        assert_eq!(
+            secp.recover_public_key(&msg, &recoverable),
+            Err(CryptoError::Verify(VerifyFailure::MalformedSignature))
+        );
+
+        assert_eq!(
+            MsgDigest::Keccak256.hash(b"").to_vec(),
+            Vec::from_hex("c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470")
+                .unwrap()
+        );
+        let sig = secp.sign_msg(MsgDigest::default(), b"hello", &sk).unwrap();
+        assert!(scheme_verify(
+            SignatureScheme::Secp256k1,
+            &pk,
+            b"hello",
+            &sig
+        ));
+        assert_eq!(
+            MsgDigest::DoubleSha256.hash(b"hello").to_vec(),
+            Vec::from_hex("9595c9df90075148eb06860365df33584b75bff782a510c6cd4883a419833d50")
+                .unwrap()
+        );
+        let sig = secp
+            .sign_msg(MsgDigest::DoubleSha256, b"hello", &sk)
+            .unwrap();
+        assert!(secp.verify_msg(MsgDigest::DoubleSha256, b"hello", &sig, &pk));
+        assert!(!secp.verify_msg(MsgDigest::Sha256, b"hello", &sig, &pk));
+        let sig = secp.sign_msg(MsgDigest::Keccak256, b"hello", &sk).unwrap();
+        assert!(secp.verify_msg(MsgDigest::Keccak256, b"hello", &sig, &pk));
+        assert!(!secp.verify_msg(MsgDigest::Sha256, b"hello", &sig, &pk));
+
+        assert_eq!(
+            secp.verify_msg_detailed(MsgDigest::Sha256, b"hello", &sig, &pk),
+            Err(VerifyFailure::SignatureInvalid)
+        );
+        assert_eq!(
+            secp.verify_detailed(&msg[1..], &sig, &pk),
+            Err(VerifyFailure::WrongLength {
+                expected: 32,
+                actual: 31
+            })
+        );
+        assert_eq!(
+            secp.verify_detailed(&msg, &sig, &pk[..33]),
+            Err(VerifyFailure::MalformedKey)
+        );
+        assert_eq!(
+            secp.verify_detailed(&msg, &sig[1..], &pk),
+            Err(VerifyFailure::MalformedSignature)
+        );
+    }
+
+    #[test]
+    fn test_secp256k1_arithmetic() {
+        let point = |k: u8| {
+            let mut sk = [0u8; 32];
+            sk[31] = k;
+            PublicKey::from_secret_key(&SecretKey::parse(&sk).unwrap())
+                .serialize()
+                .to_vec()
+        };
+        let scalar = |k: u8| {
+            let mut sk = [0u8; 32];
+            sk[31] = k;
+            sk
+        };
+        let (g2, g3, g5) = (point(2), point(3), point(5));
+
+        assert_eq!(secp256k1_combine(&[&g2, &g3]).unwrap(), g5);
+        assert_eq!(secp256k1_mul(&g2, &scalar(3)).unwrap(), point(6));
+        assert_eq!(secp256k1_add_scalar(&g2, &scalar(3)).unwrap(), g5);
+
+        let neg = secp256k1_negate(&g2).unwrap();
+        assert_eq!(secp256k1_combine(&[&g5, &neg]).unwrap(), g3);
+        assert!(secp256k1_combine(&[&g2, &neg]).is_err());
+        assert!(secp256k1_mul(&g2, &[0u8; 32]).is_err());
+        assert!(secp256k1_negate(&g2[..33]).is_err());
+
+        let secp = ECDSASecp256k1::new();
+        let shared = secp.ecdh(&scalar(2), &g3, EcdhHash::Compressed).unwrap();
+        let g6 = PublicKey::parse_slice(&point(6), None).unwrap();
+        assert_eq!(shared, g6.serialize_compressed().to_vec());
+        assert_eq!(
+            secp.ecdh(&scalar(3), &g2, EcdhHash::RawX).unwrap(),
+            &point(6)[1..33]
+        );
+        assert_eq!(
+            secp.ecdh(&scalar(3), &g2, EcdhHash::default()).unwrap(),
+            ring::digest::digest(&ring::digest::SHA256, &shared).as_ref()
+        );
+        assert!(secp.ecdh(&[0u8; 32], &g2, EcdhHash::RawX).is_err());
+
+        let compressed = secp256k1_compress_pubkey(&point(6)).unwrap();
+        assert_eq!(compressed, g6.serialize_compressed().to_vec());
+        assert_eq!(secp256k1_decompress_pubkey(&compressed).unwrap(), point(6));
+        assert_eq!(secp256k1_compress_pubkey(&compressed).unwrap(), compressed);
+        assert!(secp256k1_validate_pubkey(&compressed));
+        assert!(secp256k1_validate_pubkey(&point(6)));
+        // y 改变后不在曲线上
+        let mut off_curve = point(6);
+        off_curve[64] ^= 1;
+        assert!(!secp256k1_validate_pubkey(&off_curve));
+        assert!(secp256k1_decompress_pubkey(&off_curve).is_err());
+        // x = 0 没有对应的 y
+        let mut no_y = [0u8; 33];
+        no_y[0] = 0x02;
+        assert!(!secp256k1_validate_pubkey(&no_y));
+        assert!(!secp256k1_validate_pubkey(&point(6)[1..]));
+    }
+
+    #[test]
+    fn test_rsa_oaep() {
+        let sk = include_bytes!("../tests/rsa-2048-private-key.pk8");
+        let rsa = Rsa::fromPKCS8(sk).unwrap();
+        let msg = b"wrapped key material";
+        // 密文由 Python cryptography 生成
+        let c256 = Vec::from_hex("c8ec6937649e0c81c9de8d2a5a967bec2f19edf604aea7c9a5f7060702cf9211abcb4e223538f13ef8130b793157c654f6f8fcd4743b0badc3406be5d9f05e9c7406fdde26539a9a91e20c7bc291107e4daec56e67b34cc3a5a1fc15c3e322f160e8de437f9f056a4f1360f079a0c3c3880aed70024ede930c2fa37b4e64d97274a7eddc46dbdd57a615a8b76ea3defc3a2fcecbde18072cd06c718bf2308c75dc7ca74beacca93c020c1af6d028e2cd92749bc5804d97ffe2cfcacd34e66790f85256c688e4462f9fd7578fb5c00a4f8ccb5b0832616291f342db0df73bdda5063c41d3587f165323b15eb3cb2ebf2f3ddf2f1e4b13fe1a6a988a81ea927b45").unwrap();
+        let c512 = Vec::from_hex("6c77ad1d9dcf9a5692271afbd91d8db7191721d833d8f23fae754bcc13a3b7faeaac29add0539608a479a01cdf8bcc027f729d9fddfb72ed2ddc62d3b17524040faa1d4c4dec65c4aad63a7e6b7b9e58845684dca0e594e85931547eee379e9e3159b36ce2c885fca71abbde264e6639e26c56e2dc63a7d988427abdd89bb26022c4bb3f5727ab75b36e30526160b02030bb15fbd0feec46e8fdbf06684e51aceea49766db4172b3edb59b8bab167c8c243897058e88fea4c04ff48c7440d6c589f950f39ef1f1c980e9f3c43c2520f4db00346558315892e3791c5e5f4362c643d5ebad67dd67f2bbf0b164a02ab8c22cc4833a511d4a3feed3633b5975cc4c").unwrap();
+        assert_eq!(
+            rsa.decrypt_oaep(OaepHash::Sha256, &c256, b"").unwrap(),
+            &msg[..]
+        );
+        assert_eq!(
+            rsa.decrypt_oaep(OaepHash::Sha512, &c512, b"label").unwrap(),
+            &msg[..]
+        );
+        assert!(rsa.decrypt_oaep(OaepHash::Sha512, &c512, b"").is_err());
+        assert!(rsa.decrypt_oaep(OaepHash::Sha384, &c256, b"").is_err());
+        assert!(rsa.decrypt_oaep(OaepHash::Sha256, &c256[1..], b"").is_err());
+
+        let pk = rsa.public_key();
+        let c = Rsa::encrypt_oaep(OaepHash::Sha384, &pk, b"", b"ctx").unwrap();
+        assert_eq!(rsa.decrypt_oaep(OaepHash::Sha384, &c, b"ctx").unwrap(), b"");
+        // lHash 不符和 Y 字节非零 (PKCS#1 v1.5 密文) 都只返回同一种错误
+        let e = rsa
+            .decrypt_oaep(OaepHash::Sha384, &c, b"other")
+            .unwrap_err();
+        let c = Rsa::encrypt_pkcs1v15(&pk, b"abc").unwrap();
+        assert_eq!(rsa.decrypt_oaep(OaepHash::Sha384, &c, b"").unwrap_err(), e);
+        let max = vec![7u8; 256 - 2 * 48 - 2];
+        let c = Rsa::encrypt_oaep(OaepHash::Sha384, &pk, &max, b"").unwrap();
+        assert_eq!(rsa.decrypt_oaep(OaepHash::Sha384, &c, b"").unwrap(), max);
+        assert!(Rsa::encrypt_oaep(OaepHash::Sha384, &pk, &[0u8; 159], b"").is_err());
+    }
+
+    #[test]
+    fn test_rsa_pkcs1v15_encryption() {
+        let sk = include_bytes!("../tests/rsa-2048-private-key.pk8");
+        let rsa = Rsa::fromPKCS8(sk).unwrap();
+        // 密文由 Python cryptography 生成
+        let c = Vec::from_hex("2e6edc24e3bb6670a6754f525d757e345c1c444fb4ea3b4b4c57e6da501789faebea16ce32243d2f40cb9744efe541bbfa4b603b7a2f572ab2bb8aa72c94f398178345bafda72243fe7ce875f54f961077c70bd4f1b0202ec7aead2a1261b6436bf56a99476ca4a21b30a74a3f798b315ef65146759098d1e59b907bd65b58236e196f1bc8c15befa06e4a9b9a0bf9634f74596c7afbe9231cb3d8160ef5d2064c5af80c698c700b61c3b69be201fede46dd8eb566433913e85979779c803ec632a5af0e3109636ad2e0e9dd65eabd791a4cd97e219dfcbd6313a4aaf7eafcaea4ecf52693598f1d59248ae4bfcbf5cb75e483c629f246ad0046282fdae8e426").unwrap();
+        let key = b"0123456789abcdef";
+        assert_eq!(rsa.decrypt_pkcs1v15(&c).unwrap(), key);
+        assert_eq!(rsa.decrypt_pkcs1v15_key(&c, 16).unwrap(), key);
+        // 长度不符时返回随机密钥
+        let wrong = rsa.decrypt_pkcs1v15_key(&c, 24).unwrap();
+        assert_eq!(wrong.len(), 24);
+        assert_ne!(&wrong[8..], key);
+
+        let pk = rsa.public_key();
+        let c = Rsa::encrypt_pkcs1v15(&pk, &[0u8; 245]).unwrap();
+        assert_eq!(rsa.decrypt_pkcs1v15(&c).unwrap(), vec![0u8; 245]);
+        assert!(Rsa::encrypt_pkcs1v15(&pk, &[0u8; 246]).is_err());
+        // OAEP 密文不是合法的 PKCS#1 v1.5 填充，隐式拒绝返回确定的伪随机消息
+        let c = Rsa::encrypt_oaep(OaepHash::Sha256, &pk, key, b"").unwrap();
+        let synthetic = rsa.decrypt_pkcs1v15(&c).unwrap();
+        assert_ne!(synthetic, key);
+        assert_eq!(rsa.decrypt_pkcs1v15(&c).unwrap(), synthetic);
+        assert_ne!(rsa.decrypt_pkcs1v15_key(&c, 16).unwrap(), key);
+        // 与 OpenSSL 3.5 `pkeyutl -decrypt -pkeyopt rsa_padding_mode:pkcs1` 的隐式拒绝结果一致
+        let c = Vec::from_hex("c8ec6937649e0c81c9de8d2a5a967bec2f19edf604aea7c9a5f7060702cf9211abcb4e223538f13ef8130b793157c654f6f8fcd4743b0badc3406be5d9f05e9c7406fdde26539a9a91e20c7bc291107e4daec56e67b34cc3a5a1fc15c3e322f160e8de437f9f056a4f1360f079a0c3c3880aed70024ede930c2fa37b4e64d97274a7eddc46dbdd57a615a8b76ea3defc3a2fcecbde18072cd06c718bf2308c75dc7ca74beacca93c020c1af6d028e2cd92749bc5804d97ffe2cfcacd34e66790f85256c688e4462f9fd7578fb5c00a4f8ccb5b0832616291f342db0df73bdda5063c41d3587f165323b15eb3cb2ebf2f3ddf2f1e4b13fe1a6a988a81ea927b45").unwrap();
+        assert_eq!(rsa.decrypt_pkcs1v15(&c).unwrap(), Vec::from_hex("23a722caa073c5be3dc2b8a80ffc94c0d002929e7b9b1afe9c8a1b88e3e152d0228df069f8d98d74c4aa039a4a2aed2ab9392a1692").unwrap());
+    }
+
+    #[test]
+    fn test_rsa_generate() {
+        let sk = include_bytes!("../tests/rsa-2048-private-key.pk8");
+        assert_eq!(Rsa::fromPKCS8(sk).unwrap().to_pkcs8().unwrap(), &sk[..]);
+
+        let rsa = Rsa::generate(2048).unwrap();
+        let pk = rsa.public_key();
+        assert_eq!(rsa_modulus_len(&pk), Some(256));
+        let sig = rsa.sign(PaddingAlg::RSA_PSS_SHA256, b"abc").unwrap();
+        assert!(Rsa::verify(PaddingAlg::RSA_PSS_SHA256, b"abc", &sig, &pk));
+
+        let restored = Rsa::fromPKCS8(&rsa.to_pkcs8().unwrap()).unwrap();
+        assert!(restored == rsa);
+        // PKCS#8 中的 privateKey 即 PKCS#1 编码
+        let pkcs8 = rsa.to_pkcs8().unwrap();
+        let pkcs1 = rsa.to_pkcs1().unwrap();
+        assert_eq!(&pkcs8[pkcs8.len() - pkcs1.len()..], &pkcs1[..]);
+        assert!(Rsa::generate(1024).is_err());
+
+        let params = crate::kdf::Pbkdf2 {
+            hash: crate::kdf::KdfHash::SHA256,
+            iterations: 1000,
+        };
+        let sealed = rsa.to_encrypted_pkcs8(b"passphrase", params).unwrap();
+        assert!(Rsa::from_encrypted_pkcs8(&sealed, b"passphrase").unwrap() == rsa);
+        assert!(Rsa::from_encrypted_pkcs8(&sealed, b"Passphrase").is_err());
     }
 
     #[test]
     fn test_rsa() {
         const MESSAGE: &[u8] = b"hello, world";
         let sk = include_bytes!("../tests/rsa-2048-private-key.pk8");
-        let rsa = Rsa::fromPKCS8(sk);
+        let rsa = Rsa::fromPKCS8(sk).unwrap();
+        assert!(Rsa::fromPKCS8(&sk[1..]).is_err());
         let pk = rsa.public_key();
-        let sig = rsa.sign(PaddingAlg::RSA_PKCS1_SHA256, MESSAGE);
+        let sig = rsa.sign(PaddingAlg::RSA_PKCS1_SHA256, MESSAGE).unwrap();
+        assert!(Rsa::verify(
+            PaddingAlg::RSA_PKCS1_SHA256,
+            MESSAGE,
+            &sig,
+            &pk
+        ));
+
+        // 确定性PSS
+        let sig = rsa
+            .sign_pss_deterministic(PaddingAlg::RSA_PSS_SHA256, MESSAGE)
+            .unwrap();
+        assert_eq!(
+            sig,
+            rsa.sign_pss_deterministic(PaddingAlg::RSA_PSS_SHA256, MESSAGE)
+                .unwrap()
+        );
+        assert!(Rsa::verify(PaddingAlg::RSA_PSS_SHA256, MESSAGE, &sig, &pk));
+        let spki = crate::spki::encode_spki(PublicKeyType::Rsa, &pk).unwrap();
+        assert!(Rsa::verify(
+            PaddingAlg::RSA_PSS_SHA256,
+            MESSAGE,
+            &sig,
+            &spki
+        ));
+        assert_eq!(
+            Rsa::verify_detailed(PaddingAlg::RSA_PSS_SHA256, b"other", &sig, &pk),
+            Err(VerifyFailure::SignatureInvalid)
+        );
+        assert_eq!(
+            Rsa::verify_detailed(PaddingAlg::RSA_PSS_SHA256, MESSAGE, &sig[1..], &pk),
+            Err(VerifyFailure::WrongLength {
+                expected: 256,
+                actual: 255
+            })
+        );
+        assert_eq!(
+            Rsa::verify_detailed(PaddingAlg::RSA_PSS_SHA256, MESSAGE, &sig, &pk[1..]),
+            Err(VerifyFailure::MalformedKey)
+        );
+        let salted = rsa
+            .sign_pss_with_salt(PaddingAlg::RSA_PSS_SHA256, MESSAGE, &[1u8; 32])
+            .unwrap();
+        assert_ne!(sig, salted);
+        // 由独立的 EMSA-PSS 实现计算，openssl dgst -sigopt rsa_pss_saltlen:32 验证通过
+        assert_eq!(salted, Vec::from_hex("13ab3ade4218799bc501561da23576004e1146b0417201c848df62fd639be66bbfe697bb47d03e46609cdcda300b98d3d6d7d9cb6816a01e6681bd5cf8fb99ee46e7896008d9589e8d9be40820fb834bd43fd75b348250e4bf15f8da8912b87811639ba1f6da2a7e87685fc17af4365d5080ce1ae98e79514130549423f7f519d0fae08af832cecccd7d5d2c3ca050b96bbccc245c35be6dbe6edc360d08c39641bd29d0a49c410f007f987e5fbe6e0aeb80b0e48f57eea0450275332f1cbff4038342f5b40468fe32eca35beec806028725ee7bf12a6ff9b4bbdd86a8971fa579f0d1b605066a2c4c5064ca8bede33f054d510abf435aa8edbfb6800fcb72a5").unwrap());
         assert!(Rsa::verify(
+            PaddingAlg::RSA_PSS_SHA256,
+            MESSAGE,
+            &salted,
+            &pk
+        ));
+        assert!(rsa
+            .sign_pss_with_salt(PaddingAlg::RSA_PSS_SHA256, MESSAGE, &[1u8; 16])
+            .is_err());
+        assert!(rsa
+            .sign_pss_deterministic(PaddingAlg::RSA_PKCS1_SHA256, MESSAGE)
+            .is_err());
+
+        // 预先计算摘要
+        let digest = ring::digest::digest(&ring::digest::SHA256, MESSAGE);
+        let prehashed = rsa
+            .sign_prehashed(PaddingAlg::RSA_PKCS1_SHA256, digest.as_ref())
+            .unwrap();
+        assert_eq!(
+            prehashed,
+            rsa.sign(PaddingAlg::RSA_PKCS1_SHA256, MESSAGE).unwrap()
+        );
+        assert!(Rsa::verify_prehashed(
             PaddingAlg::RSA_PKCS1_SHA256,
+            digest.as_ref(),
+            &prehashed,
+            &pk
+        ));
+        let prehashed = rsa
+            .sign_prehashed(PaddingAlg::RSA_PSS_SHA256, digest.as_ref())
+            .unwrap();
+        assert!(Rsa::verify(
+            PaddingAlg::RSA_PSS_SHA256,
             MESSAGE,
+            &prehashed,
+            &pk
+        ));
+        assert!(Rsa::verify_prehashed(
+            PaddingAlg::RSA_PSS_SHA256,
+            digest.as_ref(),
+            &salted,
+            &pk
+        ));
+        assert_eq!(
+            Rsa::verify_prehashed_detailed(
+                PaddingAlg::RSA_PSS_SHA384,
+                digest.as_ref(),
+                &salted,
+                &pk
+            ),
+            Err(VerifyFailure::WrongLength {
+                expected: 48,
+                actual: 32
+            })
+        );
+        let digest = ring::digest::digest(&ring::digest::SHA512, MESSAGE);
+        let sig = rsa.sign(PaddingAlg::RSA_PKCS1_SHA512, MESSAGE).unwrap();
+        assert!(Rsa::verify_prehashed(
+            PaddingAlg::RSA_PKCS1_SHA512,
+            digest.as_ref(),
             &sig,
             &pk
         ));
+        assert!(!Rsa::verify_prehashed(
+            PaddingAlg::RSA_PKCS1_SHA512,
+            &[0u8; 64],
+            &sig,
+            &pk
+        ));
+        assert!(rsa
+            .sign_prehashed(PaddingAlg::RSA_PSS_SHA512, &[0u8; 32])
+            .is_err());
     }
 
     #[test]
     fn test_ecdsa() {
         let msg = [97, 98, 99]; // "abc"
-        let pkcs8_bytes = EcdsaKeyPair::generate_pkcs8(EcdsaAlg::ECDSA_P256_SHA256_ASN1);
+        let pkcs8_bytes = EcdsaKeyPair::generate_pkcs8(EcdsaAlg::ECDSA_P256_SHA256_ASN1).unwrap();
         println!("pkcs8 bytes = {:?}", pkcs8_bytes);
-        let key_pair = EcdsaKeyPair::from_pkcs8(EcdsaAlg::ECDSA_P256_SHA256_ASN1, &pkcs8_bytes);
-        let sig = key_pair.sign(&msg);
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(EcdsaAlg::ECDSA_P256_SHA256_ASN1, &pkcs8_bytes).unwrap();
+        let sig = key_pair.sign(&msg).unwrap();
         println!("sig = {:?}", sig);
         println!("pub key = {:?}", key_pair.public_key());
 
@@ -396,7 +2828,126 @@ mod tests {
             &msg,
             &sig,
         );
-        assert_eq!(verify_result, true);
+        assert!(verify_result);
+
+        // 同一个 PKCS#8 密钥也可以生成定长签名
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(EcdsaAlg::ECDSA_P256_SHA256_FIXED, &pkcs8_bytes).unwrap();
+        let fixed = key_pair.sign(&msg).unwrap();
+        assert_eq!(fixed.len(), 64);
+        let pk = key_pair.public_key();
+        assert!(ecdsa_verify(
+            EcdsaAlg::ECDSA_P256_SHA256_FIXED,
+            &pk,
+            &msg,
+            &fixed
+        ));
+        let spki = crate::spki::encode_spki(PublicKeyType::EcdsaP256, &pk).unwrap();
+        assert!(ecdsa_verify(
+            EcdsaAlg::ECDSA_P256_SHA256_FIXED,
+            &spki,
+            &msg,
+            &fixed
+        ));
+        assert_eq!(
+            ecdsa_verify_detailed(EcdsaAlg::ECDSA_P384_SHA384_FIXED, &spki, &msg, &fixed),
+            Err(VerifyFailure::MalformedKey)
+        );
+        assert!(!ecdsa_verify(
+            EcdsaAlg::ECDSA_P256_SHA256_ASN1,
+            &pk,
+            &msg,
+            &fixed
+        ));
+        assert!(!ecdsa_verify(
+            EcdsaAlg::ECDSA_P256_SHA256_FIXED,
+            &pk,
+            &msg,
+            &sig
+        ));
+
+        let pkcs8_bytes = EcdsaKeyPair::generate_pkcs8(EcdsaAlg::ECDSA_P384_SHA384_FIXED).unwrap();
+        let key_pair =
+            EcdsaKeyPair::from_pkcs8(EcdsaAlg::ECDSA_P384_SHA384_FIXED, &pkcs8_bytes).unwrap();
+        let fixed = key_pair.sign(&msg).unwrap();
+        let pk = key_pair.public_key();
+        assert!(ecdsa_verify(
+            EcdsaAlg::ECDSA_P384_SHA384_FIXED,
+            &pk,
+            &msg,
+            &fixed
+        ));
+        assert_eq!(
+            ecdsa_verify_detailed(EcdsaAlg::ECDSA_P384_SHA384_FIXED, &pk, &msg, &fixed[1..]),
+            Err(VerifyFailure::WrongLength {
+                expected: 96,
+                actual: 95
+            })
+        );
+    }
+
+    fn check_signer<S: Signer>(signer: &S) {
+        let sig = signer.sign(b"generic message").unwrap();
+        let verifier = signer.public_key().unwrap();
+        assert!(verifier.verify(b"generic message", &sig).is_ok());
+        assert!(verifier.verify(b"other message", &sig).is_err());
+    }
+
+    #[test]
+    fn test_signer_traits() {
+        check_signer(&Secp256k1Signer::new(&[7u8; 32], MsgDigest::Keccak256).unwrap());
+        assert!(Secp256k1Signer::new(&[0u8; 32], MsgDigest::Sha256).is_err());
+        check_signer(&crate::ed25519::SecretKey::from_seed(&[7u8; 32]).unwrap());
+        let alg = EcdsaAlg::ECDSA_P256_SHA256_FIXED;
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(alg).unwrap();
+        check_signer(&EcdsaKeyPair::from_pkcs8(alg, &pkcs8).unwrap());
+        let rsa = Rsa::fromPKCS8(include_bytes!("../tests/rsa-2048-private-key.pk8")).unwrap();
+        check_signer(&RsaSigner::new(rsa, PaddingAlg::RSA_PSS_SHA256));
+    }
+
+    #[test]
+    fn test_any_signature() {
+        let sk = crate::ed25519::SecretKey::from_seed(&[7u8; 32]).unwrap();
+        let pk = AnyPublicKey::new(SignatureScheme::Ed25519, &sk.public_key()).unwrap();
+        let sig = AnySignature::new(SignatureScheme::Ed25519, &sk.sign(b"msg"));
+        assert_eq!(pk.encode()[0], 2);
+
+        let pk = AnyPublicKey::decode(&pk.encode()).unwrap();
+        let sig = AnySignature::decode(&sig.encode()).unwrap();
+        assert!(pk.verify_any(b"msg", &sig).is_ok());
+        assert!(pk.verify_any(b"other", &sig).is_err());
+
+        let bundle = sig.encode_with_public_key(&pk).unwrap();
+        let (pk2, sig2) = AnySignature::decode_with_public_key(&bundle).unwrap();
+        assert_eq!((&pk2, &sig2), (&pk, &sig));
+
+        let signer = Secp256k1Signer::new(&[7u8; 32], MsgDigest::Sha256).unwrap();
+        let secp_pk = AnyPublicKey::new(
+            SignatureScheme::Secp256k1,
+            &signer.public_key().unwrap().public_key,
+        )
+        .unwrap();
+        let secp_sig = AnySignature::new(SignatureScheme::Secp256k1, &signer.sign(b"msg").unwrap());
+        assert!(secp_pk.verify_any(b"msg", &secp_sig).is_ok());
+        assert_eq!(
+            pk.verify_any(b"msg", &secp_sig),
+            Err(VerifyFailure::MalformedSignature)
+        );
+        assert!(sig.encode_with_public_key(&secp_pk).is_err());
+
+        let (sm2_sk, sm2_pk) = crate::sm2::generate_keypair();
+        let sm2_pk = AnyPublicKey::new(SignatureScheme::Sm2, &sm2_pk).unwrap();
+        let sm2_sig = AnySignature::new(
+            SignatureScheme::Sm2,
+            &crate::sm2::sign(b"msg", &sm2_sk, crate::sm2::DEFAULT_ID).unwrap(),
+        );
+        assert_eq!(sm2_pk.encode()[0], 7);
+        let sm2_pk = AnyPublicKey::decode(&sm2_pk.encode()).unwrap();
+        assert!(sm2_pk.verify_any(b"msg", &sm2_sig).is_ok());
+        assert!(sm2_pk.verify_any(b"other", &sm2_sig).is_err());
+        assert!(AnyPublicKey::new(SignatureScheme::Ed25519, &[0u8; 33]).is_err());
+        assert!(AnyPublicKey::decode(&[99, 0]).is_err());
+        assert!(AnySignature::decode_with_public_key(&bundle[..10]).is_err());
     }
 
     #[test]
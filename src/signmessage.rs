@@ -0,0 +1,186 @@
+//! 比特币消息签名，与 Bitcoin Core 的 signmessage / verifymessage 兼容
+//!
+//! 消息哈希为 sha256d(varint(24) | "Bitcoin Signed Message:\n" | varint(len) | 消息)，
+//! 签名为 base64 编码的65字节 头字节 | r | s，头字节为 27 + recovery id，公钥为压缩编码时再加4。
+//! 验证时从签名恢复公钥，再与地址中的公钥哈希比较，支持 P2PKH 和 P2WPKH 地址
+
+use secp256k1::{recover, sign, Message, PublicKey, RecoveryId, SecretKey, Signature};
+
+use crate::ct::ct_eq;
+use crate::digest::{hash160, sha256d};
+use crate::encoding::{base58, base64, bech32};
+
+const MAGIC: &[u8] = b"Bitcoin Signed Message:\n";
+// P2PKH 地址的版本字节：主网、测试网
+const P2PKH_VERSIONS: [u8; 2] = [0x00, 0x6f];
+// P2WPKH 地址的 hrp：主网、测试网、regtest
+const SEGWIT_HRPS: [&str; 3] = ["bc", "tb", "bcrt"];
+
+/// 计算签名消息的哈希
+pub fn message_hash(msg: &[u8]) -> [u8; 32] {
+    let mut data = Vec::with_capacity(MAGIC.len() + msg.len() + 10);
+    write_varint(&mut data, MAGIC.len() as u64);
+    data.extend_from_slice(MAGIC);
+    write_varint(&mut data, msg.len() as u64);
+    data.extend_from_slice(msg);
+    sha256d(&data)
+}
+
+/// 签名消息，返回 base64 编码的签名
+///
+/// msg: 消息
+/// sk: 32字节私钥
+/// compressed: 地址是否使用压缩公钥，现代钱包均为 true
+pub fn sign_message(msg: &[u8], sk: &[u8], compressed: bool) -> Result<String, String> {
+    let sk = SecretKey::parse_slice(sk)
+        .map_err(|_| "signmessage: secret key must be 32 bytes in [1, n-1]".to_string())?;
+    let msg = Message::parse(&message_hash(msg));
+    let (sig, recovery_id) = sign(&msg, &sk);
+    let mut out = [0u8; 65];
+    out[0] = 27 + recovery_id.serialize() + if compressed { 4 } else { 0 };
+    out[1..].copy_from_slice(&sig.serialize());
+    Ok(base64::encode(&out))
+}
+
+/// 从签名中恢复签名者的公钥，按头字节返回33字节压缩或65字节未压缩编码
+///
+/// msg: 消息
+/// signature: base64 编码的签名
+pub fn recover_message_public_key(msg: &[u8], signature: &str) -> Result<Vec<u8>, String> {
+    let sig = base64::decode(signature)?;
+    if sig.len() != 65 {
+        return Err("signmessage: signature must be 65 bytes".to_string());
+    }
+    let (id, compressed) = match sig[0] {
+        h @ 27..=30 => (h - 27, false),
+        h @ 31..=34 => (h - 31, true),
+        _ => return Err("signmessage: invalid signature header".to_string()),
+    };
+    let recovery_id =
+        RecoveryId::parse(id).map_err(|_| "signmessage: invalid recovery id".to_string())?;
+    let mut rs = [0u8; 64];
+    rs.copy_from_slice(&sig[1..]);
+    let msg = Message::parse(&message_hash(msg));
+    let pk = recover(&msg, &Signature::parse(&rs), &recovery_id)
+        .map_err(|_| "signmessage: public key recovery failed".to_string())?;
+    Ok(if compressed {
+        pk.serialize_compressed().to_vec()
+    } else {
+        pk.serialize().to_vec()
+    })
+}
+
+/// 验证签名是否由地址的所有者生成
+///
+/// address: P2PKH (1.../m.../n...) 或 P2WPKH (bc1q...) 地址
+/// signature: base64 编码的签名
+/// msg: 消息
+pub fn verify_message(address: &str, signature: &str, msg: &[u8]) -> bool {
+    let pk = match recover_message_public_key(msg, signature) {
+        Ok(pk) => pk,
+        Err(_) => return false,
+    };
+    match address_hash(address) {
+        // P2WPKH 只能使用压缩公钥
+        Some((hash, segwit)) => (!segwit || pk.len() == 33) && ct_eq(&hash160(&pk), &hash),
+        None => false,
+    }
+}
+
+/// 由公钥生成 P2PKH 地址
+///
+/// pk: 33或65字节公钥，地址与编码方式有关
+/// testnet: 是否为测试网地址
+pub fn p2pkh_address(pk: &[u8], testnet: bool) -> Result<String, String> {
+    if pk.len() != 33 && pk.len() != 65 || PublicKey::parse_slice(pk, None).is_err() {
+        return Err("signmessage: invalid secp256k1 public key".to_string());
+    }
+    let version = P2PKH_VERSIONS[testnet as usize];
+    Ok(base58::encode_check_version(&[version], &hash160(pk)))
+}
+
+// 地址中的20字节公钥哈希，以及是否为 segwit 地址
+fn address_hash(address: &str) -> Option<([u8; 20], bool)> {
+    let mut hash = [0u8; 20];
+    if let Ok(payload) = base58::decode_check(address) {
+        if payload.len() == 21 && P2PKH_VERSIONS.contains(&payload[0]) {
+            hash.copy_from_slice(&payload[1..]);
+            return Some((hash, false));
+        }
+        return None;
+    }
+    let hrp = address.rsplit_once('1')?.0.to_ascii_lowercase();
+    if !SEGWIT_HRPS.contains(&hrp.as_str()) {
+        return None;
+    }
+    match bech32::segwit_decode(&hrp, address) {
+        Ok((0, program)) if program.len() == 20 => {
+            hash.copy_from_slice(&program);
+            Some((hash, true))
+        }
+        _ => None,
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, n: u64) {
+    match n {
+        0..=0xfc => out.push(n as u8),
+        0xfd..=0xffff => {
+            out.push(0xfd);
+            out.extend_from_slice(&(n as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            out.push(0xfe);
+            out.extend_from_slice(&(n as u32).to_le_bytes());
+        }
+        _ => {
+            out.push(0xff);
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ::hex::FromHex;
+
+    #[test]
+    fn test_signmessage() {
+        // bitcoinjs-message 文档中的示例，私钥 L4rK1yDtCWekvXuE6oXD9jCYfFNV2cWRpVuPLBcCU2z8TrisoyY1
+        let sk = Vec::from_hex("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+            .unwrap();
+        let msg = b"This is an example of a signed message.";
+        let address = "1F3sAm6ZtwLAUnj7d38pGFxtP3RVEvtsbV";
+        let expected = "H9L5yLFjti0QTHhPyFrZCT1V/MMnBtXKmoiKDZ78NDBjERki6ZTQZdSMCtkgoNmp17By9ItJr8o7ChX0XxY91nk=";
+        assert_eq!(sign_message(msg, &sk, true).unwrap(), expected);
+        assert!(verify_message(address, expected, msg));
+        assert!(!verify_message(address, expected, b"another message"));
+
+        let pk = recover_message_public_key(msg, expected).unwrap();
+        assert_eq!(p2pkh_address(&pk, false).unwrap(), address);
+        let hash = hash160(&pk);
+        let p2wpkh = bech32::segwit_encode("bc", 0, &hash).unwrap();
+        assert!(verify_message(&p2wpkh, expected, msg));
+
+        // 未压缩公钥的签名对应另一个地址
+        let uncompressed = sign_message(msg, &sk, false).unwrap();
+        assert!(!verify_message(address, &uncompressed, msg));
+        let pk = recover_message_public_key(msg, &uncompressed).unwrap();
+        assert!(verify_message(
+            &p2pkh_address(&pk, true).unwrap(),
+            &uncompressed,
+            msg
+        ));
+        assert!(!verify_message(&p2wpkh, &uncompressed, msg));
+        assert!(!verify_message("not an address", expected, msg));
+
+        let mut long = vec![0u8; 300];
+        long[..MAGIC.len()].copy_from_slice(MAGIC);
+        let mut data = vec![24];
+        data.extend_from_slice(MAGIC);
+        data.extend_from_slice(&[0xfd, 0x2c, 0x01]);
+        data.extend_from_slice(&long);
+        assert_eq!(message_hash(&long), sha256d(&data));
+    }
+}
@@ -0,0 +1,169 @@
+//! 确定性认证加密 AES-SIV (RFC 5297)
+//!
+//! 同一密钥下相同的明文和辅助数据总是得到相同的密文，可以对加密后的数据库字段建立等值索引(盲索引)。
+//! 代价是泄露了明文是否相等：攻击者能看出哪些记录的字段值相同，并能统计取值的频率，
+//! 低熵字段(如性别、状态)不应使用；需要语义安全时应使用 aead 模块的随机 nonce 加密。
+//!
+//! 密文格式: 合成IV(16字节) | 密文
+
+use crypto::aessafe::{AesSafe128Encryptor, AesSafe256Encryptor};
+use crypto::symmetriccipher::BlockEncryptor;
+
+use crate::ct::ct_eq;
+use crate::mac::{dbl, Cmac, Mac};
+
+const BLOCK_SIZE: usize = 16;
+
+/// 合成IV的长度
+pub const SIV_SIZE: usize = BLOCK_SIZE;
+
+/// AES-SIV 密钥，32字节为 AES-128-SIV，64字节为 AES-256-SIV
+pub struct AesSiv {
+    mac_key: Vec<u8>,
+    ctr: Box<dyn BlockEncryptor>,
+}
+
+impl AesSiv {
+    /// 从密钥构建，前半部分用于S2V，后半部分用于CTR加密
+    pub fn new(key: &[u8]) -> Result<Self, String> {
+        let half = key.len() / 2;
+        let ctr: Box<dyn BlockEncryptor> = match key.len() {
+            32 => Box::new(AesSafe128Encryptor::new(&key[half..])),
+            64 => Box::new(AesSafe256Encryptor::new(&key[half..])),
+            _ => return Err("siv: key must be 32 or 64 bytes".to_string()),
+        };
+        Ok(AesSiv {
+            mac_key: key[..half].to_vec(),
+            ctr,
+        })
+    }
+
+    /// 加密
+    ///
+    /// ad: 辅助数据的各个分量，最多126个，需要随机化时可以把 nonce 作为最后一个分量
+    /// plaintext: 明文
+    pub fn encrypt(&self, ad: &[&[u8]], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let v = self.s2v(ad, plaintext)?;
+        let mut out = v.to_vec();
+        out.extend_from_slice(plaintext);
+        self.apply_ctr(&v, &mut out[SIV_SIZE..]);
+        Ok(out)
+    }
+
+    /// 解密并验证合成IV
+    pub fn decrypt(&self, ad: &[&[u8]], ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        if ciphertext.len() < SIV_SIZE {
+            return Err("siv: ciphertext too short".to_string());
+        }
+        let mut v = [0u8; SIV_SIZE];
+        v.copy_from_slice(&ciphertext[..SIV_SIZE]);
+        let mut plaintext = ciphertext[SIV_SIZE..].to_vec();
+        self.apply_ctr(&v, &mut plaintext);
+        if !ct_eq(&self.s2v(ad, &plaintext)?, &v) {
+            return Err("siv: authentication failed".to_string());
+        }
+        Ok(plaintext)
+    }
+
+    fn cmac(&self, data: &[u8]) -> [u8; BLOCK_SIZE] {
+        let mut mac = Cmac::new_with_key(&self.mac_key).expect("siv: mac key size checked");
+        mac.update(data);
+        let mut out = [0u8; BLOCK_SIZE];
+        out.copy_from_slice(&mac.finalize());
+        out
+    }
+
+    fn s2v(&self, ad: &[&[u8]], plaintext: &[u8]) -> Result<[u8; BLOCK_SIZE], String> {
+        if ad.len() > 126 {
+            return Err("siv: too many associated data components".to_string());
+        }
+        let mut d = self.cmac(&[0u8; BLOCK_SIZE]);
+        for s in ad {
+            let mac = self.cmac(s);
+            d = dbl(&d);
+            xor(&mut d, &mac);
+        }
+        let t = if plaintext.len() >= BLOCK_SIZE {
+            let mut t = plaintext.to_vec();
+            let offset = t.len() - BLOCK_SIZE;
+            xor(&mut t[offset..], &d);
+            t
+        } else {
+            let mut padded = [0u8; BLOCK_SIZE];
+            padded[..plaintext.len()].copy_from_slice(plaintext);
+            padded[plaintext.len()] = 0x80;
+            let mut t = dbl(&d);
+            xor(&mut t, &padded);
+            t.to_vec()
+        };
+        Ok(self.cmac(&t))
+    }
+
+    fn apply_ctr(&self, v: &[u8; BLOCK_SIZE], data: &mut [u8]) {
+        // RFC 5297 要求清除第31和63位，使实现可以只用32位或64位整数递增计数器
+        let mut counter = *v;
+        counter[8] &= 0x7f;
+        counter[12] &= 0x7f;
+        let mut counter = u128::from_be_bytes(counter);
+        let mut keystream = [0u8; BLOCK_SIZE];
+        for chunk in data.chunks_mut(BLOCK_SIZE) {
+            self.ctr
+                .encrypt_block(&counter.to_be_bytes(), &mut keystream);
+            xor(chunk, &keystream);
+            counter = counter.wrapping_add(1);
+        }
+    }
+}
+
+fn xor(a: &mut [u8], b: &[u8]) {
+    for (x, y) in a.iter_mut().zip(b.iter()) {
+        *x ^= y;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_aes_siv() {
+        // test vector from: https://tools.ietf.org/html/rfc5297#appendix-A.1
+        let key = Vec::from_hex("fffefdfcfbfaf9f8f7f6f5f4f3f2f1f0f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff")
+            .unwrap();
+        let ad = Vec::from_hex("101112131415161718191a1b1c1d1e1f2021222324252627").unwrap();
+        let plain = Vec::from_hex("112233445566778899aabbccddee").unwrap();
+        let expected =
+            Vec::from_hex("85632d07c6e8f37f950acd320a2ecc9340c02b9690c4dc04daef7f6afe5c").unwrap();
+
+        let siv = AesSiv::new(&key).unwrap();
+        let sealed = siv.encrypt(&[&ad], &plain).unwrap();
+        assert_eq!(sealed, expected);
+        assert_eq!(siv.decrypt(&[&ad], &sealed).unwrap(), plain);
+        assert!(siv.decrypt(&[], &sealed).is_err());
+
+        // https://tools.ietf.org/html/rfc5297#appendix-A.2
+        let key = Vec::from_hex("7f7e7d7c7b7a79787776757473727170404142434445464748494a4b4c4d4e4f")
+            .unwrap();
+        let ad1 = Vec::from_hex(
+            "00112233445566778899aabbccddeeffdeaddadadeaddadaffeeddccbbaa99887766554433221100",
+        )
+        .unwrap();
+        let ad2 = Vec::from_hex("102030405060708090a0").unwrap();
+        let nonce = Vec::from_hex("09f911029d74e35bd84156c5635688c0").unwrap();
+        let plain = Vec::from_hex("7468697320697320736f6d6520706c61696e7465787420746f20656e6372797074207573696e67205349562d414553").unwrap();
+        let expected = Vec::from_hex("7bdb6e3b432667eb06f4d14bff2fbd0fcb900f2fddbe404326601965c889bf17dba77ceb094fa663b7a3f748ba8af829ea64ad544a272e9c485b62a3fd5c0d").unwrap();
+        let siv = AesSiv::new(&key).unwrap();
+        assert_eq!(
+            siv.encrypt(&[&ad1, &ad2, &nonce], &plain).unwrap(),
+            expected
+        );
+
+        // AES-256-SIV
+        let siv = AesSiv::new(&[3u8; 64]).unwrap();
+        let plain = vec![9u8; 100];
+        let sealed = siv.encrypt(&[b"column"], &plain).unwrap();
+        assert_eq!(sealed, siv.encrypt(&[b"column"], &plain).unwrap());
+        assert_eq!(siv.decrypt(&[b"column"], &sealed).unwrap(), plain);
+    }
+}
@@ -0,0 +1,521 @@
+//! 国密 SM2 椭圆曲线公钥密码 (GB/T 32918)
+//!
+//! 提供推荐曲线上的密钥生成、数字签名和密钥交换，哈希使用 SM3。
+//! 私钥为32字节大端整数，公钥为65字节未压缩点 04 | x | y，签名为64字节 r | s。
+//!
+//! 标量乘法使用 ec 模块固定迭代次数的阶梯，签名随机数和私钥的位长不影响循环次数，
+//! 但底层的 num-bigint 运算不是常量时间的
+
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use crate::ct::ct_eq;
+use crate::digest::sm3::{sm3, Sm3};
+use crate::ec::{Curve, Point};
+use crate::error::VerifyFailure;
+use crate::random::{OsRng, SecureRandom};
+
+/// 未指定用户身份时使用的默认ID
+pub const DEFAULT_ID: &[u8] = b"1234567812345678";
+
+// ENTL 为2字节的比特长度，ID 最长8191字节
+const MAX_ID_LEN: usize = 8191;
+
+/// 私钥长度
+pub const SECRET_KEY_SIZE: usize = 32;
+
+/// 公钥长度
+pub const PUBLIC_KEY_SIZE: usize = 65;
+
+/// 签名长度
+pub const SIGNATURE_SIZE: usize = 64;
+
+const P: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFF";
+const A: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFF00000000FFFFFFFFFFFFFFFC";
+const B: &str = "28E9FA9E9D9F5E344D5A9E4BCF6509A7F39789F515AB8F92DDBCBD414D940E93";
+const N: &str = "FFFFFFFEFFFFFFFFFFFFFFFFFFFFFFFF7203DF6B21C6052B53BBF40939D54123";
+const GX: &str = "32C4AE2C1F1981195F9904466A39C9948FE30BBFF2660BE1715A4589334C74C7";
+const GY: &str = "BC3736A2F4F6779C59BDCEE36B692153D0A9877CC62A474002DF32E52139F0A0";
+
+/// 生成密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair() -> (Vec<u8>, Vec<u8>) {
+    generate_keypair_with_rng(&OsRng)
+}
+
+/// 使用指定的随机数生成器生成密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair_with_rng(rng: &dyn SecureRandom) -> (Vec<u8>, Vec<u8>) {
+    let curve = curve();
+    // 私钥范围为 [1, n-2]
+    let d = curve.random_scalar(&(&curve.n - BigUint::one()), rng);
+    let pk = curve.encode(&curve.mul_g(&d));
+    (to_bytes32(&d).to_vec(), pk)
+}
+
+/// 由私钥计算公钥
+pub fn public_key(sk: &[u8]) -> Result<Vec<u8>, String> {
+    let curve = curve();
+    let d = secret(&curve, sk)?;
+    Ok(curve.encode(&curve.mul_g(&d)))
+}
+
+/// 签名
+///
+/// msg: 待签名的数据，内部按标准计算 e = SM3(Z | msg)
+/// sk: 私钥
+/// id: 签名者身份，一般为 DEFAULT_ID
+pub fn sign(msg: &[u8], sk: &[u8], id: &[u8]) -> Result<Vec<u8>, String> {
+    sign_with_rng(msg, sk, id, &OsRng)
+}
+
+/// 使用指定的随机数生成器产生签名随机数 k
+///
+/// 参数同 sign，rng: 随机数生成器
+pub fn sign_with_rng(
+    msg: &[u8],
+    sk: &[u8],
+    id: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<Vec<u8>, String> {
+    let curve = curve();
+    let d = secret(&curve, sk)?;
+    loop {
+        let k = curve.random_scalar(&curve.n, rng);
+        if let Some(sig) = sign_with_k(&curve, msg, &d, id, &k)? {
+            return Ok(sig);
+        }
+    }
+}
+
+/// 验证签名
+///
+/// msg: 已签名的数据
+/// sig: 64字节签名
+/// pk: 65字节公钥
+/// id: 签名者身份
+pub fn verify(msg: &[u8], sig: &[u8], pk: &[u8], id: &[u8]) -> bool {
+    verify_detailed(msg, sig, pk, id).is_ok()
+}
+
+/// 验证签名，失败时返回原因
+pub fn verify_detailed(msg: &[u8], sig: &[u8], pk: &[u8], id: &[u8]) -> Result<(), VerifyFailure> {
+    let curve = curve();
+    let p = decode(&curve, pk).map_err(|_| VerifyFailure::MalformedKey)?;
+    if sig.len() != SIGNATURE_SIZE {
+        return Err(VerifyFailure::WrongLength {
+            expected: SIGNATURE_SIZE,
+            actual: sig.len(),
+        });
+    }
+    let n = &curve.n;
+    let r = BigUint::from_bytes_be(&sig[..32]);
+    let s = BigUint::from_bytes_be(&sig[32..]);
+    if r.is_zero() || s.is_zero() || r >= *n || s >= *n {
+        return Err(VerifyFailure::MalformedSignature);
+    }
+    let e = hash_msg(&curve, msg, id, &p).map_err(|_| VerifyFailure::SignatureInvalid)?;
+    let t = (&r + &s) % n;
+    if t.is_zero() {
+        return Err(VerifyFailure::SignatureInvalid);
+    }
+    let point = curve.add(&curve.mul_g(&s), &curve.mul(&t, &p));
+    match curve.to_affine(&point) {
+        Some((x1, _)) if (e + &x1) % n == r => Ok(()),
+        _ => Err(VerifyFailure::SignatureInvalid),
+    }
+}
+
+/// 计算签名者身份的杂凑值 ZA = SM3(ENTL | ID | a | b | xG | yG | xA | yA)
+///
+/// 签名时先计算 e = SM3(ZA | msg)，需要自行预处理消息的调用方(如与硬件密码模块对接时)使用
+/// pk: 65字节公钥
+/// id: 签名者身份，最长8191字节
+pub fn za(pk: &[u8], id: &[u8]) -> Result<[u8; 32], String> {
+    let curve = curve();
+    let p = decode(&curve, pk)?;
+    z(&curve, id, &p)
+}
+
+fn sign_with_k(
+    curve: &Curve,
+    msg: &[u8],
+    d: &BigUint,
+    id: &[u8],
+    k: &BigUint,
+) -> Result<Option<Vec<u8>>, String> {
+    let n = &curve.n;
+    let p = curve.mul_g(d);
+    let e = hash_msg(curve, msg, id, &p)?;
+    let (x1, _) = curve
+        .to_affine(&curve.mul_g(k))
+        .ok_or_else(|| "sm2: bad nonce".to_string())?;
+    let r = (e + x1) % n;
+    if r.is_zero() || &r + k == *n {
+        return Ok(None);
+    }
+    // s = (1 + d)^-1 * (k - r*d) mod n
+    let inv = curve.inv_n(&((d + BigUint::one()) % n));
+    let rd = (&r * d) % n;
+    let s = (inv * ((k + n - rd) % n)) % n;
+    if s.is_zero() {
+        return Ok(None);
+    }
+    let mut sig = to_bytes32(&r).to_vec();
+    sig.extend_from_slice(&to_bytes32(&s));
+    Ok(Some(sig))
+}
+
+/// 密钥交换的一方
+///
+/// 发起方(A)和响应方(B)各自创建对象并交换临时公钥，计算出相同的共享密钥；
+/// 确认值用于证明双方得到了相同的密钥：B 把自己的确认值发给 A 校验，A 再把自己的确认值发给 B 校验
+pub struct KeyExchange {
+    curve: Curve,
+    initiator: bool,
+    d: BigUint,
+    z: [u8; 32],
+    r: BigUint,
+    ephemeral: Point,
+}
+
+/// 密钥交换的结果
+pub struct SharedKey {
+    /// 共享密钥
+    pub key: Vec<u8>,
+    /// 发送给对方的确认值
+    pub confirmation: [u8; 32],
+    /// 期望从对方收到的确认值
+    pub expected_confirmation: [u8; 32],
+}
+
+impl SharedKey {
+    /// 常量时间校验对方发来的确认值
+    pub fn check_confirmation(&self, confirmation: &[u8]) -> bool {
+        ct_eq(&self.expected_confirmation, confirmation)
+    }
+}
+
+impl KeyExchange {
+    /// 创建密钥交换对象并生成临时密钥
+    ///
+    /// initiator: 是否为发起方
+    /// sk: 本方长期私钥
+    /// id: 本方身份
+    /// id: 本方身份，最长8191字节
+    pub fn new(initiator: bool, sk: &[u8], id: &[u8]) -> Result<Self, String> {
+        KeyExchange::new_with_rng(initiator, sk, id, &OsRng)
+    }
+
+    /// 使用指定的随机数生成器生成临时密钥
+    ///
+    /// 参数同 new，rng: 随机数生成器
+    pub fn new_with_rng(
+        initiator: bool,
+        sk: &[u8],
+        id: &[u8],
+        rng: &dyn SecureRandom,
+    ) -> Result<Self, String> {
+        let curve = curve();
+        let r = curve.random_scalar(&curve.n, rng);
+        KeyExchange::with_ephemeral(curve, initiator, sk, id, r)
+    }
+
+    fn with_ephemeral(
+        curve: Curve,
+        initiator: bool,
+        sk: &[u8],
+        id: &[u8],
+        r: BigUint,
+    ) -> Result<Self, String> {
+        let d = secret(&curve, sk)?;
+        let z = z(&curve, id, &curve.mul_g(&d))?;
+        let ephemeral = curve.mul_g(&r);
+        Ok(KeyExchange {
+            curve,
+            initiator,
+            d,
+            z,
+            r,
+            ephemeral,
+        })
+    }
+
+    /// 发送给对方的临时公钥
+    pub fn ephemeral_public_key(&self) -> Vec<u8> {
+        self.curve.encode(&self.ephemeral)
+    }
+
+    /// 计算共享密钥
+    ///
+    /// peer_pk: 对方长期公钥
+    /// peer_id: 对方身份，最长8191字节
+    /// peer_ephemeral: 对方临时公钥
+    /// key_len: 共享密钥长度
+    pub fn compute(
+        &self,
+        peer_pk: &[u8],
+        peer_id: &[u8],
+        peer_ephemeral: &[u8],
+        key_len: usize,
+    ) -> Result<SharedKey, String> {
+        let curve = &self.curve;
+        let n = &curve.n;
+        let peer = decode(curve, peer_pk)?;
+        let peer_r = decode(curve, peer_ephemeral)?;
+        let peer_z = z(curve, peer_id, &peer)?;
+
+        let (x1, y1) = curve
+            .to_affine(&self.ephemeral)
+            .expect("sm2: ephemeral key");
+        let (x2, y2) = curve.to_affine(&peer_r).expect("sm2: decoded point");
+        // t = d + x̄ * r mod n，x̄ = 2^w + (x & (2^w - 1))，w = 127
+        let t = (&self.d + reduce_x(&x1) * &self.r) % n;
+        let v = curve.mul(&t, &curve.add(&peer, &curve.mul(&reduce_x(&x2), &peer_r)));
+        let (xv, yv) = curve
+            .to_affine(&v)
+            .ok_or_else(|| "sm2: key exchange failed".to_string())?;
+
+        // 发起方为 A，按 Z_A | Z_B 和 R_A、R_B 的顺序计算
+        let ((za, zb), (ra, rb)) = if self.initiator {
+            ((self.z, peer_z), ((x1, y1), (x2, y2)))
+        } else {
+            ((peer_z, self.z), ((x2, y2), (x1, y1)))
+        };
+        let (xv, yv) = (to_bytes32(&xv), to_bytes32(&yv));
+        let key = kdf(&[&xv[..], &yv, &za, &zb].concat(), key_len);
+
+        let mut inner = Sm3::new();
+        for part in [&xv[..], &za, &zb] {
+            inner.update(part);
+        }
+        for coord in [&ra.0, &ra.1, &rb.0, &rb.1] {
+            inner.update(&to_bytes32(coord));
+        }
+        let inner = inner.finalize();
+        let confirm = |tag: u8| sm3(&[&[tag][..], &yv, &inner].concat());
+        // B 发送 S_B (0x02)，A 发送 S_A (0x03)
+        let (confirmation, expected_confirmation) = if self.initiator {
+            (confirm(0x03), confirm(0x02))
+        } else {
+            (confirm(0x02), confirm(0x03))
+        };
+        Ok(SharedKey {
+            key,
+            confirmation,
+            expected_confirmation,
+        })
+    }
+}
+
+fn reduce_x(x: &BigUint) -> BigUint {
+    let w = BigUint::one() << 127;
+    &w + (x & (&w - BigUint::one()))
+}
+
+/// GB/T 32918 中基于 SM3 的密钥派生函数
+fn kdf(z: &[u8], len: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(len);
+    let mut counter = 1u32;
+    while out.len() < len {
+        let mut h = Sm3::new();
+        h.update(z);
+        h.update(&counter.to_be_bytes());
+        out.extend_from_slice(&h.finalize());
+        counter += 1;
+    }
+    out.truncate(len);
+    out
+}
+
+fn to_bytes32(x: &BigUint) -> [u8; 32] {
+    let bytes = x.to_bytes_be();
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+fn curve() -> Curve {
+    Curve::new(P, A, B, N, GX, GY)
+}
+
+fn secret(curve: &Curve, sk: &[u8]) -> Result<BigUint, String> {
+    let d = BigUint::from_bytes_be(sk);
+    if sk.len() != SECRET_KEY_SIZE || d.is_zero() || d >= &curve.n - BigUint::one() {
+        return Err("sm2: invalid secret key".to_string());
+    }
+    Ok(d)
+}
+
+fn decode(curve: &Curve, pk: &[u8]) -> Result<Point, String> {
+    curve.decode(pk).map_err(|e| format!("sm2: {}", e))
+}
+
+/// Z = SM3(ENTL | ID | a | b | xG | yG | x | y)，ID 的比特长度超出 ENTL 的两个字节时返回错误
+fn z(curve: &Curve, id: &[u8], p: &Point) -> Result<[u8; 32], String> {
+    if id.len() > MAX_ID_LEN {
+        return Err("sm2: id too long".to_string());
+    }
+    let (x, y) = curve.to_affine(p).expect("sm2: public key is not infinity");
+    let mut h = Sm3::new();
+    h.update(&((id.len() * 8) as u16).to_be_bytes());
+    h.update(id);
+    for v in [&curve.a, &curve.b, &curve.g.x, &curve.g.y, &x, &y] {
+        h.update(&to_bytes32(v));
+    }
+    Ok(h.finalize())
+}
+
+fn hash_msg(curve: &Curve, msg: &[u8], id: &[u8], p: &Point) -> Result<BigUint, String> {
+    let mut h = Sm3::new();
+    h.update(&z(curve, id, p)?);
+    h.update(msg);
+    Ok(BigUint::from_bytes_be(&h.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_sm2_sign() {
+        // GB/T 32918.2-2016 附录A 推荐曲线上的签名示例
+        let sk = Vec::from_hex("3945208F7B2144B13F36E38AC6D39F95889393692860B51A42FB81EF4DF7C5B8")
+            .unwrap();
+        let pk = Vec::from_hex("0409F9DF311E5421A150DD7D161E4BC5C672179FAD1833FC076BB08FF356F35020CCEA490CE26775A52DC6EA718CC1AA600AED05FBF35E084A6632F6072DA9AD13").unwrap();
+        let k = Vec::from_hex("59276E27D506861A16680F3AD9C02DCCEF3CC1FA3CDBE4CE6D54B80DEAC1BC21")
+            .unwrap();
+        let expected = Vec::from_hex("F5A03B0648D2C4630EEAC513E1BB81A15944DA3827D5B74143AC7EACEEE720B3B1B6AA29DF212FD8763182BC0D421CA1BB9038FD1F7F42D4840B69C485BBC1AA").unwrap();
+        let msg = b"message digest";
+
+        assert_eq!(public_key(&sk).unwrap(), pk);
+        let curve = curve();
+        let d = secret(&curve, &sk).unwrap();
+        let sig = sign_with_k(&curve, msg, &d, DEFAULT_ID, &BigUint::from_bytes_be(&k))
+            .unwrap()
+            .unwrap();
+        assert_eq!(sig, expected);
+        assert!(verify(msg, &sig, &pk, DEFAULT_ID));
+        assert!(!verify(msg, &sig, &pk, b"ALICE123@YAHOO.COM"));
+        assert!(!verify(b"message digesT", &sig, &pk, DEFAULT_ID));
+        assert_eq!(
+            verify_detailed(msg, &sig[1..], &pk, DEFAULT_ID),
+            Err(VerifyFailure::WrongLength {
+                expected: 64,
+                actual: 63
+            })
+        );
+
+        // 附录A 中的 ZA 和 e = SM3(ZA | M)
+        let z = za(&pk, DEFAULT_ID).unwrap();
+        assert_eq!(
+            z.to_vec(),
+            Vec::from_hex("B2E14C5C79C6DF5B85F4FE7ED8DB7A262B9DA7E07CCB0EA9F4747B8CCDA8A4F3")
+                .unwrap()
+        );
+        assert_eq!(
+            sm3(&[&z[..], msg].concat()).to_vec(),
+            Vec::from_hex("F0B43E94BA45ACCAACE692ED534382EB17E6AB5A19CE7B31F4486FDFC0D28640")
+                .unwrap()
+        );
+        assert!(za(&pk[1..], DEFAULT_ID).is_err());
+
+        let (sk, pk) = generate_keypair();
+        let sig = sign(msg, &sk, DEFAULT_ID).unwrap();
+        assert!(verify(msg, &sig, &pk, DEFAULT_ID));
+    }
+
+    #[test]
+    fn test_sm2_key_exchange() {
+        let (sk_a, pk_a) = generate_keypair();
+        let (sk_b, pk_b) = generate_keypair();
+        let a = KeyExchange::new(true, &sk_a, b"ALICE123@YAHOO.COM").unwrap();
+        let b = KeyExchange::new(false, &sk_b, b"BILL456@YAHOO.COM").unwrap();
+
+        let key_b = b
+            .compute(&pk_a, b"ALICE123@YAHOO.COM", &a.ephemeral_public_key(), 16)
+            .unwrap();
+        let key_a = a
+            .compute(&pk_b, b"BILL456@YAHOO.COM", &b.ephemeral_public_key(), 16)
+            .unwrap();
+        assert_eq!(key_a.key, key_b.key);
+        assert_eq!(key_a.key.len(), 16);
+        assert!(key_a.check_confirmation(&key_b.confirmation));
+        assert!(key_b.check_confirmation(&key_a.confirmation));
+        assert!(!key_a.check_confirmation(&key_a.confirmation));
+
+        // 身份不一致时得到不同的密钥
+        let key_a = a
+            .compute(&pk_b, b"BILL", &b.ephemeral_public_key(), 16)
+            .unwrap();
+        assert_ne!(key_a.key, key_b.key);
+        // ENTL 只有两个字节，8192字节及以上的身份被拒绝
+        let long_id = vec![b'a'; MAX_ID_LEN + 1];
+        assert!(a
+            .compute(&pk_b, &long_id, &b.ephemeral_public_key(), 16)
+            .is_err());
+        assert!(KeyExchange::new(true, &sk_a, &long_id).is_err());
+        assert!(sign(b"abc", &sk_a, &long_id).is_err());
+        assert!(!verify(b"abc", &[1u8; 64], &pk_a, &long_id));
+        let rng = ring::test::rand::FixedByteRandom { byte: 7 };
+        let a = KeyExchange::new_with_rng(true, &sk_a, &long_id[..MAX_ID_LEN], &rng).unwrap();
+        let again = KeyExchange::new_with_rng(true, &sk_a, &long_id[..MAX_ID_LEN], &rng).unwrap();
+        assert_eq!(a.ephemeral_public_key(), again.ephemeral_public_key());
+    }
+
+    #[test]
+    fn test_sm2_key_exchange_vector() {
+        // GB/T 32918.3-2016 附录A.2，示例曲线 Fp-256 上的密钥交换，klen = 128 比特
+        let curve = || {
+            Curve::new(
+                "8542D69E4C044F18E8B92435BF6FF7DE457283915C45517D722EDB8B08F1DFC3",
+                "787968B4FA32C3FD2417842E73BBFEFF2F3C848B6831D7E0EC65228B3937E498",
+                "63E4C6D3B23B0C849CF84241484BFE48F61D59A5B16BA06E6E12D1DA27C5249A",
+                "8542D69E4C044F18E8B92435BF6FF7DD297720630485628D5AE74EE7C32E79B7",
+                "421DEBD61B62EAB6746434EBC3CC315E32220B3BADD50BDC4C4E6C147FEDD43D",
+                "0680512BCBB42C07D47349D2153B70C4E5D7FDFCBFA36EA1A85841B9E46E09A2",
+            )
+        };
+        let uint = |s: &str| BigUint::from_bytes_be(&Vec::from_hex(s).unwrap());
+        let sk_a =
+            Vec::from_hex("6FCBA2EF9AE0AB902BC3BDE3FF915D44BA4CC78F88E2F8E7F8996D3B8CCEEDEE")
+                .unwrap();
+        let pk_a = Vec::from_hex("043099093BF3C137D8FCBBCDF4A2AE50F3B0F216C3122D79425FE03A45DBFE16553DF79E8DAC1CF0ECBAA2F2B49D51A4B387F2EFAF482339086A27A8E05BAED98B").unwrap();
+        let sk_b =
+            Vec::from_hex("5E35D7D3F3C54DBAC72E61819E730B019A84208CA3A35E4C2E353DFCCB2A3B53")
+                .unwrap();
+        let pk_b = Vec::from_hex("04245493D446C38D8CC0F118374690E7DF633A8A4BFB3329B5ECE604B2B4F37F4353C0869F4B9E17773DE68FEC45E14904E0DEA45BF6CECF9918C85EA047C60A4C").unwrap();
+        let (id_a, id_b) = (b"ALICE123@YAHOO.COM", b"BILL456@YAHOO.COM");
+        let r_a = uint("83A2C9C8B96E5AF70BD480B472409A9A327257F1EBB73F5B073354B248668563");
+        let r_b = uint("33FE21940342161C55619C4A0C060293D543C80AF19748CE176D83477DE71C80");
+        let a = KeyExchange::with_ephemeral(curve(), true, &sk_a, id_a, r_a).unwrap();
+        let b = KeyExchange::with_ephemeral(curve(), false, &sk_b, id_b, r_b).unwrap();
+        assert_eq!(
+            a.z.to_vec(),
+            Vec::from_hex("E4D1D0C3CA4C7F11BC8FF8CB3F4C02A78F108FA098E51A668487240F75E20F31")
+                .unwrap()
+        );
+        assert_eq!(
+            b.z.to_vec(),
+            Vec::from_hex("6B4B6D0E276691BD4A11BF72F4FB501AE309FDACB72FA6CC336E6656119ABD67")
+                .unwrap()
+        );
+
+        let key_b = b
+            .compute(&pk_a, id_a, &a.ephemeral_public_key(), 16)
+            .unwrap();
+        let key_a = a
+            .compute(&pk_b, id_b, &b.ephemeral_public_key(), 16)
+            .unwrap();
+        let key = Vec::from_hex("55B0AC62A6B927BA23703832C853DED4").unwrap();
+        assert_eq!(key_a.key, key);
+        assert_eq!(key_b.key, key);
+        let s_b = Vec::from_hex("284C8F198F141B502E81250F1581C7E9EEB4CA6990F9E02DF388B45471F5BC5C")
+            .unwrap();
+        let s_a = Vec::from_hex("23444DAF8ED7534366CB901C84B3BDBB63504F4065C1116C91A4C00697E6CF7A")
+            .unwrap();
+        assert_eq!(key_b.confirmation.to_vec(), s_b);
+        assert_eq!(key_a.confirmation.to_vec(), s_a);
+        assert!(key_a.check_confirmation(&s_b));
+        assert!(key_b.check_confirmation(&s_a));
+    }
+}
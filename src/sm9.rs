@@ -0,0 +1,460 @@
+//! 国密 SM9 标识密码 (GM/T 0044-2016)
+//!
+//! 提供 KGC 的主密钥生成和用户私钥提取、数字签名以及基于 KDF 序列密码的公钥加密，哈希使用 SM3。
+//! SM9 使用 256 位 BN 曲线上的 R-ate 配对，bls 模块的 blsc 绑定和 bls12381 模块的 BLS12-381
+//! 都不能用于该曲线，配对运算由 sm9_core 库提供。
+//!
+//! 主私钥为32字节大端整数；G1 点为65字节未压缩格式 04 | x | y，G2 点为129字节 04 | x | y，
+//! Fp2 元素的字节顺序与 GM/T 0044.5 的示例一致。签名主公钥和加密用户私钥在 G2，加密主公钥和签名用户私钥在 G1，
+//! 签名为97字节 h | S，密文为 C1 | C3 | C2，C1 为65字节 G1 点，C3 为32字节 MAC
+
+use sm9_core::{pairing, Fr, Group, Gt, G1, G2};
+
+use crate::ct::ct_eq;
+use crate::digest::sm3::Sm3;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::random::{OsRng, SecureRandom};
+use crate::secret::SecretBytes;
+
+/// 主私钥长度
+pub const SECRET_KEY_SIZE: usize = 32;
+
+/// G1 点的长度，加密主公钥、签名用户私钥和密文的 C1
+pub const G1_SIZE: usize = 65;
+
+/// G2 点的长度，签名主公钥和加密用户私钥
+pub const G2_SIZE: usize = 129;
+
+/// 签名长度
+pub const SIGNATURE_SIZE: usize = 32 + G1_SIZE;
+
+/// 签名私钥生成函数识别符
+pub const HID_SIGN: u8 = 0x01;
+
+/// 加密私钥生成函数识别符
+pub const HID_ENC: u8 = 0x03;
+
+// 群阶 N，大端序
+const N: [u8; 32] = [
+    0xb6, 0x40, 0x00, 0x00, 0x02, 0xa3, 0xa6, 0xf1, 0xd6, 0x03, 0xab, 0x4f, 0xf5, 0x8e, 0xc7, 0x44,
+    0x49, 0xf2, 0x93, 0x4b, 0x18, 0xea, 0x8b, 0xee, 0xe5, 0x6e, 0xe1, 0x9c, 0xd6, 0x9e, 0xcf, 0x25,
+];
+
+/// 签名主密钥，由 KGC 保管，内存在释放时擦除
+pub struct SignMasterKey(SecretBytes);
+
+impl SignMasterKey {
+    /// 使用系统随机数生成主密钥
+    pub fn generate() -> Self {
+        Self::generate_with_rng(&OsRng)
+    }
+
+    /// 使用指定的随机数生成器生成主密钥
+    pub fn generate_with_rng(rng: &dyn SecureRandom) -> Self {
+        SignMasterKey(random_scalar_bytes(rng))
+    }
+
+    /// 解析32字节大端序的主私钥，范围为 [1, N-1]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        scalar(bytes)?;
+        Ok(SignMasterKey(SecretBytes::from(bytes)))
+    }
+
+    /// 32字节大端序的主私钥
+    pub fn to_bytes(&self) -> SecretBytes {
+        self.0.clone()
+    }
+
+    /// 签名主公钥 Ppub-s = [ks]P2，129字节
+    pub fn public_key(&self) -> Vec<u8> {
+        (G2::one() * self.scalar()).to_uncompressed().to_vec()
+    }
+
+    /// 为用户标识 id 提取签名私钥 dsA = [ks / (H1(ID | hid) + ks)]P1，65字节
+    ///
+    /// t1 为零时返回错误，此时 KGC 应更换主密钥
+    pub fn extract(&self, id: &[u8]) -> Result<SecretBytes, CryptoError> {
+        let t2 = extract_scalar(self.scalar(), id, HID_SIGN)?;
+        Ok(SecretBytes::from(&(G1::one() * t2).to_uncompressed()[..]))
+    }
+
+    fn scalar(&self) -> Fr {
+        scalar(&self.0).expect("sm9: checked master key")
+    }
+}
+
+/// 加密主密钥，由 KGC 保管，内存在释放时擦除
+pub struct EncryptMasterKey(SecretBytes);
+
+impl EncryptMasterKey {
+    /// 使用系统随机数生成主密钥
+    pub fn generate() -> Self {
+        Self::generate_with_rng(&OsRng)
+    }
+
+    /// 使用指定的随机数生成器生成主密钥
+    pub fn generate_with_rng(rng: &dyn SecureRandom) -> Self {
+        EncryptMasterKey(random_scalar_bytes(rng))
+    }
+
+    /// 解析32字节大端序的主私钥，范围为 [1, N-1]
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        scalar(bytes)?;
+        Ok(EncryptMasterKey(SecretBytes::from(bytes)))
+    }
+
+    /// 32字节大端序的主私钥
+    pub fn to_bytes(&self) -> SecretBytes {
+        self.0.clone()
+    }
+
+    /// 加密主公钥 Ppub-e = [ke]P1，65字节
+    pub fn public_key(&self) -> Vec<u8> {
+        (G1::one() * self.scalar()).to_uncompressed().to_vec()
+    }
+
+    /// 为用户标识 id 提取加密私钥 deB = [ke / (H1(ID | hid) + ke)]P2，129字节
+    ///
+    /// t1 为零时返回错误，此时 KGC 应更换主密钥
+    pub fn extract(&self, id: &[u8]) -> Result<SecretBytes, CryptoError> {
+        let t2 = extract_scalar(self.scalar(), id, HID_ENC)?;
+        Ok(SecretBytes::from(&(G2::one() * t2).to_uncompressed()[..]))
+    }
+
+    fn scalar(&self) -> Fr {
+        scalar(&self.0).expect("sm9: checked master key")
+    }
+}
+
+/// 签名
+///
+/// msg: 待签名的数据
+/// user_key: KGC 为签名者提取的65字节签名私钥
+/// master_public_key: 129字节签名主公钥
+pub fn sign(msg: &[u8], user_key: &[u8], master_public_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    sign_with_rng(msg, user_key, master_public_key, &OsRng)
+}
+
+/// 使用指定的随机数生成器产生签名随机数 r
+///
+/// 参数同 sign，rng: 随机数生成器
+pub fn sign_with_rng(
+    msg: &[u8],
+    user_key: &[u8],
+    master_public_key: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<Vec<u8>, CryptoError> {
+    let ds = G1::from_uncompressed(user_key)
+        .map_err(|_| CryptoError::InvalidKey("sm9: invalid signature private key".to_string()))?;
+    let ppub = G2::from_uncompressed(master_public_key)
+        .map_err(|_| CryptoError::InvalidKey("sm9: invalid master public key".to_string()))?;
+    if ds.is_zero() || ppub.is_zero() {
+        return Err(CryptoError::InvalidKey(
+            "sm9: key is the point at infinity".to_string(),
+        ));
+    }
+    // GM/T 0044.2 签名生成，g = e(P1, Ppub-s)
+    let g = pairing(G1::one(), ppub);
+    loop {
+        let r = scalar(&random_scalar_bytes(rng)).expect("sm9: random scalar in range");
+        let w = g.pow(r);
+        let h = h2(msg, &w);
+        let l = r - h;
+        if l.is_zero() {
+            continue;
+        }
+        let mut sig = h.to_slice().to_vec();
+        sig.extend_from_slice(&(ds * l).to_uncompressed());
+        return Ok(sig);
+    }
+}
+
+/// 验证签名
+///
+/// msg: 已签名的数据
+/// sig: 97字节签名
+/// master_public_key: 129字节签名主公钥
+/// id: 签名者标识
+pub fn verify(msg: &[u8], sig: &[u8], master_public_key: &[u8], id: &[u8]) -> bool {
+    verify_detailed(msg, sig, master_public_key, id).is_ok()
+}
+
+/// 验证签名，失败时返回原因
+pub fn verify_detailed(
+    msg: &[u8],
+    sig: &[u8],
+    master_public_key: &[u8],
+    id: &[u8],
+) -> Result<(), VerifyFailure> {
+    let ppub = G2::from_uncompressed(master_public_key).map_err(|_| VerifyFailure::MalformedKey)?;
+    if ppub.is_zero() {
+        return Err(VerifyFailure::MalformedKey);
+    }
+    if sig.len() != SIGNATURE_SIZE {
+        return Err(VerifyFailure::WrongLength {
+            expected: SIGNATURE_SIZE,
+            actual: sig.len(),
+        });
+    }
+    // GM/T 0044.2 签名验证，h 属于 [1, N-1]，S 属于 G1
+    let h = scalar(&sig[..32]).map_err(|_| VerifyFailure::MalformedSignature)?;
+    let s = G1::from_uncompressed(&sig[32..]).map_err(|_| VerifyFailure::MalformedSignature)?;
+    let t = pairing(G1::one(), ppub).pow(h);
+    let h1 = h1(id, HID_SIGN);
+    let p = G2::one() * h1 + ppub;
+    let w = pairing(s, p) * t;
+    if ct_eq(&h2(msg, &w).to_slice(), &sig[..32]) {
+        Ok(())
+    } else {
+        Err(VerifyFailure::SignatureInvalid)
+    }
+}
+
+/// 加密
+///
+/// msg: 明文
+/// master_public_key: 65字节加密主公钥
+/// id: 接收者标识
+pub fn encrypt(msg: &[u8], master_public_key: &[u8], id: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    encrypt_with_rng(msg, master_public_key, id, &OsRng)
+}
+
+/// 使用指定的随机数生成器产生加密随机数 r
+///
+/// 参数同 encrypt，rng: 随机数生成器
+pub fn encrypt_with_rng(
+    msg: &[u8],
+    master_public_key: &[u8],
+    id: &[u8],
+    rng: &dyn SecureRandom,
+) -> Result<Vec<u8>, CryptoError> {
+    let ppub = G1::from_uncompressed(master_public_key)
+        .map_err(|_| CryptoError::InvalidKey("sm9: invalid master public key".to_string()))?;
+    // GM/T 0044.4 加密，QB = [H1(IDB | hid)]P1 + Ppub-e
+    let q = G1::one() * h1(id, HID_ENC) + ppub;
+    if ppub.is_zero() || q.is_zero() {
+        return Err(CryptoError::InvalidKey(
+            "sm9: key is the point at infinity".to_string(),
+        ));
+    }
+    let g = pairing(ppub, G2::one());
+    loop {
+        let r = scalar(&random_scalar_bytes(rng)).expect("sm9: random scalar in range");
+        let c1 = q * r;
+        let w = g.pow(r);
+        let k = kdf(&c1, &w, id, msg.len() + 32);
+        let (k1, k2) = k.split_at(msg.len());
+        if !msg.is_empty() && k1.iter().all(|&b| b == 0) {
+            continue;
+        }
+        let c2: Vec<u8> = msg.iter().zip(k1).map(|(m, k)| m ^ k).collect();
+        let mut out = c1.to_uncompressed().to_vec();
+        out.extend_from_slice(&mac(k2, &c2));
+        out.extend_from_slice(&c2);
+        return Ok(out);
+    }
+}
+
+/// 解密
+///
+/// ciphertext: C1 | C3 | C2
+/// user_key: KGC 为接收者提取的129字节加密私钥
+/// id: 接收者标识
+pub fn decrypt(ciphertext: &[u8], user_key: &[u8], id: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let de = G2::from_uncompressed(user_key)
+        .map_err(|_| CryptoError::InvalidKey("sm9: invalid encryption private key".to_string()))?;
+    if ciphertext.len() < G1_SIZE + 32 {
+        return Err(CryptoError::InvalidInput(
+            "sm9: ciphertext too short".to_string(),
+        ));
+    }
+    let (c1, rest) = ciphertext.split_at(G1_SIZE);
+    let (c3, c2) = rest.split_at(32);
+    let c1 = G1::from_uncompressed(c1)
+        .map_err(|_| CryptoError::InvalidInput("sm9: C1 is not on the curve".to_string()))?;
+    if c1.is_zero() {
+        return Err(CryptoError::InvalidInput(
+            "sm9: C1 is the point at infinity".to_string(),
+        ));
+    }
+    // GM/T 0044.4 解密，w' = e(C1, deB)
+    let w = pairing(c1, de);
+    let k = kdf(&c1, &w, id, c2.len() + 32);
+    let (k1, k2) = k.split_at(c2.len());
+    let zero_key = !c2.is_empty() && k1.iter().all(|&b| b == 0);
+    if zero_key || !ct_eq(&mac(k2, c2), c3) {
+        return Err(CryptoError::Verify(VerifyFailure::DigestMismatch));
+    }
+    Ok(c2.iter().zip(k1).map(|(c, k)| c ^ k).collect())
+}
+
+// t1 = H1(ID | hid) + s，t2 = s / t1
+fn extract_scalar(s: Fr, id: &[u8], hid: u8) -> Result<Fr, CryptoError> {
+    let t1 = h1(id, hid) + s;
+    let inv = t1.inverse().filter(|_| !t1.is_zero()).ok_or_else(|| {
+        CryptoError::OperationFailed("sm9: t1 is zero, regenerate the master key".to_string())
+    })?;
+    Ok(s * inv)
+}
+
+// 32字节大端序、范围 [1, N-1] 的标量
+fn scalar(bytes: &[u8]) -> Result<Fr, CryptoError> {
+    if bytes.len() != 32 || bytes >= &N[..] || bytes.iter().all(|&b| b == 0) {
+        return Err(CryptoError::InvalidInput(
+            "sm9: scalar must be in [1, N-1]".to_string(),
+        ));
+    }
+    Fr::from_slice(bytes).ok_or_else(|| CryptoError::InvalidInput("sm9: bad scalar".to_string()))
+}
+
+// 拒绝采样 [1, N-1] 内的随机数
+fn random_scalar_bytes(rng: &dyn SecureRandom) -> SecretBytes {
+    let mut bytes = SecretBytes::zeroed(32);
+    loop {
+        rng.fill(bytes.as_mut_bytes());
+        if scalar(&bytes).is_ok() {
+            return bytes;
+        }
+    }
+}
+
+// 密码函数 H1 和 H2，Ha = SM3(tag | Z | 1) | SM3(tag | Z | 2) 的前40字节，h = Ha mod (N-1) + 1
+fn hash_to_range(tag: u8, parts: &[&[u8]]) -> Fr {
+    let mut ha = [0u8; 64];
+    for (ct, chunk) in [1u32, 2].iter().zip(ha.chunks_mut(32)) {
+        let mut h = Sm3::new();
+        h.update(&[tag]);
+        for part in parts {
+            h.update(part);
+        }
+        h.update(&ct.to_be_bytes());
+        chunk.copy_from_slice(&h.finalize());
+    }
+    Fr::from_hash(&ha[..40]).expect("sm9: 40 byte hash")
+}
+
+fn h1(id: &[u8], hid: u8) -> Fr {
+    hash_to_range(0x01, &[id, &[hid]])
+}
+
+fn h2(msg: &[u8], w: &Gt) -> Fr {
+    hash_to_range(0x02, &[msg, &w.to_slice()])
+}
+
+// 密钥派生函数，K = SM3(C1 | w | ID | ct) | ...，C1 不含 04 前缀
+fn kdf(c1: &G1, w: &Gt, id: &[u8], len: usize) -> SecretBytes {
+    let (c, w) = (c1.to_slice(), w.to_slice());
+    let mut out = SecretBytes::zeroed(len);
+    for (i, chunk) in out.as_mut_bytes().chunks_mut(32).enumerate() {
+        let mut h = Sm3::new();
+        h.update(&c);
+        h.update(&w);
+        h.update(id);
+        h.update(&(i as u32 + 1).to_be_bytes());
+        chunk.copy_from_slice(&h.finalize()[..chunk.len()]);
+    }
+    out
+}
+
+// 消息认证码函数 MAC(K2, Z) = SM3(Z | K2)
+fn mac(k2: &[u8], z: &[u8]) -> [u8; 32] {
+    let mut h = Sm3::new();
+    h.update(z);
+    h.update(k2);
+    h.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_sm9_sign() {
+        // test vectors from: GM/T 0044.5-2016 附录A
+        let master = SignMasterKey::from_bytes(
+            &Vec::from_hex("000130E78459D78545CB54C587E02CF480CE0B66340F319F348A1D5B1F2DC5F4")
+                .unwrap(),
+        )
+        .unwrap();
+        let ppub = master.public_key();
+        let ds = master.extract(b"Alice").unwrap();
+        assert_eq!(
+            ds.as_bytes().to_vec(),
+            Vec::from_hex("04A5702F05CF1315305E2D6EB64B0DEB923DB1A0BCF0CAFF90523AC8754AA6982078559A844411F9825C109F5EE3F52D720DD01785392A727BB1556952B2B013D3").unwrap()
+        );
+        let msg = b"Chinese IBS standard";
+        let r = Vec::from_hex("00033C8616B06704813203DFD00965022ED15975C662337AED648835DC4B1CBE")
+            .unwrap();
+        let rng = ring::test::rand::FixedSliceRandom { bytes: &r };
+        let sig = sign_with_rng(msg, &ds, &ppub, &rng).unwrap();
+        assert_eq!(
+            sig,
+            Vec::from_hex("823C4B21E4BD2DFE1ED92C606653E996668563152FC33F55D7BFBB9BD9705ADB0473BF96923CE58B6AD0E13E9643A406D8EB98417C50EF1B29CEF9ADB48B6D598C856712F1C2E0968AB7769F42A99586AED139D5B8B3E15891827CC2ACED9BAA05").unwrap()
+        );
+        assert!(verify(msg, &sig, &ppub, b"Alice"));
+        assert_eq!(
+            verify_detailed(msg, &sig, &ppub, b"Bob"),
+            Err(VerifyFailure::SignatureInvalid)
+        );
+        assert!(!verify(b"Chinese IBS standarD", &sig, &ppub, b"Alice"));
+        assert_eq!(
+            verify_detailed(msg, &sig[1..], &ppub, b"Alice"),
+            Err(VerifyFailure::WrongLength {
+                expected: SIGNATURE_SIZE,
+                actual: SIGNATURE_SIZE - 1
+            })
+        );
+        let mut bad = sig.clone();
+        bad[..32].copy_from_slice(&N);
+        assert_eq!(
+            verify_detailed(msg, &bad, &ppub, b"Alice"),
+            Err(VerifyFailure::MalformedSignature)
+        );
+
+        let sig = sign(msg, &ds, &ppub).unwrap();
+        assert!(verify(msg, &sig, &ppub, b"Alice"));
+    }
+
+    #[test]
+    fn test_sm9_encrypt() {
+        // test vectors from: GM/T 0044.5-2016 附录D，基于 KDF 的序列密码
+        let master = EncryptMasterKey::from_bytes(
+            &Vec::from_hex("0001EDEE3778F441F8DEA3D9FA0ACC4E07EE36C93F9A08618AF4AD85CEDE1C22")
+                .unwrap(),
+        )
+        .unwrap();
+        let ppub = master.public_key();
+        let de = master.extract(b"Bob").unwrap();
+        let msg = b"Chinese IBE standard";
+        let r = Vec::from_hex("0000AAC0541779C8FC45E3E2CB25C12B5D2576B2129AE8BB5EE2CBE5EC9E785C")
+            .unwrap();
+        let rng = ring::test::rand::FixedSliceRandom { bytes: &r };
+        let ct = encrypt_with_rng(msg, &ppub, b"Bob", &rng).unwrap();
+        assert_eq!(
+            ct,
+            Vec::from_hex("042445471164490618E1EE20528FF1D545B0F14C8BCAA44544F03DAB5DAC07D8FF42FFCA97D57CDDC05EA405F2E586FEB3A6930715532B8000759F13059ED59AC0BA672387BCD6DE5016A158A52BB2E7FC429197BCAB70B25AFEE37A2B9DB9F3671B5F5B0E951489682F3E64E1378CDD5DA9513B1C").unwrap()
+        );
+        assert_eq!(decrypt(&ct, &de, b"Bob").unwrap(), msg.to_vec());
+
+        let mut bad = ct.clone();
+        let last = bad.len() - 1;
+        bad[last] ^= 1;
+        assert_eq!(
+            decrypt(&bad, &de, b"Bob"),
+            Err(CryptoError::Verify(VerifyFailure::DigestMismatch))
+        );
+        assert!(decrypt(&ct, &master.extract(b"Alice").unwrap(), b"Alice").is_err());
+        assert!(decrypt(&ct[..G1_SIZE + 31], &de, b"Bob").is_err());
+
+        let ct = encrypt(b"", &ppub, b"Bob").unwrap();
+        assert_eq!(decrypt(&ct, &de, b"Bob").unwrap(), Vec::<u8>::new());
+
+        let generated = EncryptMasterKey::generate();
+        let ct = encrypt(msg, &generated.public_key(), b"Carol").unwrap();
+        let key = generated.extract(b"Carol").unwrap();
+        assert_eq!(decrypt(&ct, &key, b"Carol").unwrap(), msg.to_vec());
+        assert!(SignMasterKey::from_bytes(&N).is_err());
+        assert!(SignMasterKey::from_bytes(&[0u8; 32]).is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! SubjectPublicKeyInfo 公钥编码
+//!
+//! 在各模块使用的原始公钥和标准的 X.509 SubjectPublicKeyInfo DER 格式之间转换
+
+use std::borrow::Cow;
+
+use secp256k1::PublicKey;
+use simple_asn1::{oid, to_der, ASN1Block, BigUint, OID};
+
+/// 公钥类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PublicKeyType {
+    /// 32字节 Ed25519 公钥
+    Ed25519,
+    /// 32字节 X25519 公钥
+    X25519,
+    /// 57字节 Ed448 公钥
+    Ed448,
+    /// 56字节 X448 公钥
+    X448,
+    /// 33字节压缩或65字节未压缩的 secp256k1 公钥
+    Secp256k1,
+    /// 65字节未压缩的 P-256 公钥
+    EcdsaP256,
+    /// 97字节未压缩的 P-384 公钥
+    EcdsaP384,
+    /// PKCS#1 RSAPublicKey DER 编码的 RSA 公钥
+    Rsa,
+}
+
+/// 把原始公钥编码为 SubjectPublicKeyInfo DER
+///
+/// key_type: 公钥类型
+/// pk: 原始公钥
+pub fn encode_spki(key_type: PublicKeyType, pk: &[u8]) -> Result<Vec<u8>, String> {
+    let (algorithm, key) = match key_type {
+        PublicKeyType::Ed25519 => {
+            check_len(pk, 32)?;
+            (vec![oid_block(oid!(1, 3, 101, 112))], pk.to_vec())
+        }
+        PublicKeyType::X25519 => {
+            check_len(pk, 32)?;
+            (vec![oid_block(oid!(1, 3, 101, 110))], pk.to_vec())
+        }
+        PublicKeyType::Ed448 => {
+            check_len(pk, 57)?;
+            (vec![oid_block(oid!(1, 3, 101, 113))], pk.to_vec())
+        }
+        PublicKeyType::X448 => {
+            check_len(pk, 56)?;
+            (vec![oid_block(oid!(1, 3, 101, 111))], pk.to_vec())
+        }
+        PublicKeyType::Secp256k1 => {
+            let pk = PublicKey::parse_slice(pk, None)
+                .map_err(|e| format!("spki: malformed secp256k1 public key: {:?}", e))?;
+            (
+                vec![
+                    oid_block(oid!(1, 2, 840, 10045, 2, 1)),
+                    oid_block(oid!(1, 3, 132, 0, 10)),
+                ],
+                pk.serialize().to_vec(),
+            )
+        }
+        PublicKeyType::EcdsaP256 => {
+            check_point(pk, 65)?;
+            (
+                vec![
+                    oid_block(oid!(1, 2, 840, 10045, 2, 1)),
+                    oid_block(oid!(1, 2, 840, 10045, 3, 1, 7)),
+                ],
+                pk.to_vec(),
+            )
+        }
+        PublicKeyType::EcdsaP384 => {
+            check_point(pk, 97)?;
+            (
+                vec![
+                    oid_block(oid!(1, 2, 840, 10045, 2, 1)),
+                    oid_block(oid!(1, 3, 132, 0, 34)),
+                ],
+                pk.to_vec(),
+            )
+        }
+        PublicKeyType::Rsa => {
+            rsa_components(pk)?;
+            (
+                vec![
+                    oid_block(oid!(1, 2, 840, 113549, 1, 1, 1)),
+                    ASN1Block::Null(0),
+                ],
+                pk.to_vec(),
+            )
+        }
+    };
+
+    let spki = ASN1Block::Sequence(
+        0,
+        vec![
+            ASN1Block::Sequence(0, algorithm),
+            ASN1Block::BitString(0, key.len() * 8, key),
+        ],
+    );
+    to_der(&spki).map_err(|e| format!("spki: encode error: {:?}", e))
+}
+
+/// 解析 SubjectPublicKeyInfo DER，返回公钥类型和各模块使用的原始公钥
+///
+/// RSA 公钥返回 PKCS#1 RSAPublicKey DER，椭圆曲线公钥按原样返回点编码
+pub fn decode_spki(der: &[u8]) -> Result<(PublicKeyType, Vec<u8>), String> {
+    let malformed = || "spki: malformed SubjectPublicKeyInfo".to_string();
+    let blocks = simple_asn1::from_der(der).map_err(|_| malformed())?;
+    let (algorithm, key) = match blocks.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [ASN1Block::Sequence(_, algorithm), ASN1Block::BitString(_, bits, key)]
+                if *bits == key.len() * 8 =>
+            {
+                (algorithm, key)
+            }
+            _ => return Err(malformed()),
+        },
+        _ => return Err(malformed()),
+    };
+    let key_type = match algorithm.as_slice() {
+        [ASN1Block::ObjectIdentifier(_, id)] if *id == oid!(1, 3, 101, 112) => {
+            PublicKeyType::Ed25519
+        }
+        [ASN1Block::ObjectIdentifier(_, id)] if *id == oid!(1, 3, 101, 110) => {
+            PublicKeyType::X25519
+        }
+        [ASN1Block::ObjectIdentifier(_, id)] if *id == oid!(1, 3, 101, 113) => PublicKeyType::Ed448,
+        [ASN1Block::ObjectIdentifier(_, id)] if *id == oid!(1, 3, 101, 111) => PublicKeyType::X448,
+        [ASN1Block::ObjectIdentifier(_, id), ASN1Block::ObjectIdentifier(_, curve)]
+            if *id == oid!(1, 2, 840, 10045, 2, 1) =>
+        {
+            if *curve == oid!(1, 3, 132, 0, 10) {
+                PublicKeyType::Secp256k1
+            } else if *curve == oid!(1, 2, 840, 10045, 3, 1, 7) {
+                PublicKeyType::EcdsaP256
+            } else if *curve == oid!(1, 3, 132, 0, 34) {
+                PublicKeyType::EcdsaP384
+            } else {
+                return Err("spki: unsupported elliptic curve".to_string());
+            }
+        }
+        [ASN1Block::ObjectIdentifier(_, id), rest @ ..]
+            if *id == oid!(1, 2, 840, 113549, 1, 1, 1)
+                && matches!(rest, [] | [ASN1Block::Null(_)]) =>
+        {
+            PublicKeyType::Rsa
+        }
+        _ => return Err("spki: unsupported public key algorithm".to_string()),
+    };
+    // 与 encode_spki 做相同的检查
+    encode_spki(key_type, key)?;
+    Ok((key_type, key.clone()))
+}
+
+/// 公钥是 SubjectPublicKeyInfo 时取出 key_type 类型的原始公钥，否则原样返回
+///
+/// 原始公钥(包括 RSA 的 PKCS#1 RSAPublicKey)不能解析为 SubjectPublicKeyInfo，两种格式不会混淆；
+/// SubjectPublicKeyInfo 的类型与 key_type 不同时返回错误
+pub fn normalize_public_key(key_type: PublicKeyType, pk: &[u8]) -> Result<Cow<'_, [u8]>, String> {
+    match decode_spki(pk) {
+        Ok((found, key)) if found == key_type => Ok(Cow::Owned(key)),
+        Ok((found, _)) => Err(format!(
+            "spki: expected {:?} key, found {:?}",
+            key_type, found
+        )),
+        Err(_) => Ok(Cow::Borrowed(pk)),
+    }
+}
+
+/// 从 PKCS#1 RSAPublicKey DER 中取出大端编码的模数n和指数e
+pub(crate) fn rsa_components(pk: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let malformed = || "spki: malformed RSA public key".to_string();
+    let blocks = simple_asn1::from_der(pk).map_err(|_| malformed())?;
+    match blocks.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => {
+                let n = n.to_biguint().ok_or_else(malformed)?;
+                let e = e.to_biguint().ok_or_else(malformed)?;
+                Ok((n.to_bytes_be(), e.to_bytes_be()))
+            }
+            _ => Err(malformed()),
+        },
+        _ => Err(malformed()),
+    }
+}
+
+fn oid_block(oid: OID) -> ASN1Block {
+    ASN1Block::ObjectIdentifier(0, oid)
+}
+
+fn check_len(pk: &[u8], len: usize) -> Result<(), String> {
+    if pk.len() != len {
+        return Err(format!("spki: public key must be {} bytes", len));
+    }
+    Ok(())
+}
+
+fn check_point(pk: &[u8], len: usize) -> Result<(), String> {
+    check_len(pk, len)?;
+    if pk[0] != 0x04 {
+        return Err("spki: elliptic curve point must be uncompressed".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_ed25519_spki() {
+        // test vector from: https://tools.ietf.org/html/rfc8410#section-10.1
+        let pk = Vec::from_hex("19bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1")
+            .unwrap();
+        let expected = Vec::from_hex("302a300506032b657003210019bf44096984cdfe8541bac167dc3b96c85086aa30b6b6cb0c5c38ad703166e1").unwrap();
+        assert_eq!(encode_spki(PublicKeyType::Ed25519, &pk).unwrap(), expected);
+        assert_eq!(
+            decode_spki(&expected).unwrap(),
+            (PublicKeyType::Ed25519, pk.clone())
+        );
+        assert_eq!(
+            normalize_public_key(PublicKeyType::Ed25519, &expected).unwrap(),
+            &pk[..]
+        );
+        assert_eq!(
+            normalize_public_key(PublicKeyType::Ed25519, &pk).unwrap(),
+            &pk[..]
+        );
+        assert!(normalize_public_key(PublicKeyType::X25519, &expected).is_err());
+        assert!(decode_spki(&expected[..expected.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+//! Shamir 秘密共享，GF(256) 上的 t-of-n 拆分
+//!
+//! 秘密的每个字节作为常数项，各自生成 t-1 次随机多项式，份额 x 取 1..=n，任意 t 个份额插值得到秘密，
+//! 少于 t 个份额不泄露秘密的任何信息。拆分前在秘密后附加4字节校验码，份额编码自带校验码，
+//! 损坏的份额和来自不同拆分的份额都能被发现。域运算不查表、不根据秘密数据分支
+
+use crate::ct::ct_eq;
+use crate::digest::checksum4;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::random::{OsRng, SecureRandom};
+use crate::secret::SecretBytes;
+
+/// 一个份额，编码为 门限(1字节) | x(1字节) | y | 校验码(4字节)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Share {
+    threshold: u8,
+    x: u8,
+    y: SecretBytes,
+}
+
+impl Share {
+    /// 恢复秘密需要的份额数
+    pub fn threshold(&self) -> u8 {
+        self.threshold
+    }
+
+    /// 份额编号，1..=255
+    pub fn x(&self) -> u8 {
+        self.x
+    }
+
+    /// 编码，结果与秘密同样需要保密
+    pub fn to_bytes(&self) -> SecretBytes {
+        let mut out = Vec::with_capacity(self.y.len() + 6);
+        out.push(self.threshold);
+        out.push(self.x);
+        out.extend_from_slice(&self.y);
+        let checksum = checksum4(&out);
+        out.extend_from_slice(&checksum);
+        SecretBytes::new(out)
+    }
+
+    /// 解码并检查校验码
+    pub fn from_bytes(data: &[u8]) -> Result<Self, CryptoError> {
+        // 秘密至少1字节，加上秘密的校验码 y 至少5字节
+        if data.len() < 11 {
+            return Err(CryptoError::InvalidInput(
+                "sss: share too short".to_string(),
+            ));
+        }
+        let (body, checksum) = data.split_at(data.len() - 4);
+        if !ct_eq(&checksum4(body), checksum) {
+            return Err(CryptoError::Verify(VerifyFailure::DigestMismatch));
+        }
+        if body[0] == 0 || body[1] == 0 {
+            return Err(CryptoError::InvalidInput(
+                "sss: invalid share threshold or index".to_string(),
+            ));
+        }
+        Ok(Share {
+            threshold: body[0],
+            x: body[1],
+            y: SecretBytes::new(body[2..].to_vec()),
+        })
+    }
+}
+
+/// 把秘密拆分为 n 个份额，任意 t 个可以恢复
+///
+/// secret: 秘密，不能为空
+/// t: 门限，1 <= t <= n
+/// n: 份额数，不超过255
+pub fn split(secret: &[u8], t: u8, n: u8) -> Result<Vec<Share>, CryptoError> {
+    split_with_rng(secret, t, n, &OsRng)
+}
+
+/// 使用指定的随机数生成器拆分，参数同 split
+pub fn split_with_rng(
+    secret: &[u8],
+    t: u8,
+    n: u8,
+    rng: &dyn SecureRandom,
+) -> Result<Vec<Share>, CryptoError> {
+    if secret.is_empty() {
+        return Err(CryptoError::InvalidInput(
+            "sss: secret must not be empty".to_string(),
+        ));
+    }
+    if t == 0 || t > n {
+        return Err(CryptoError::InvalidInput(
+            "sss: threshold must be in [1, n]".to_string(),
+        ));
+    }
+    let data = SecretBytes::new([secret, &checksum4(secret)[..]].concat());
+    // 每个字节一个多项式，coeffs[i * (t - 1)..] 是第 i 个字节的高次系数
+    let mut coeffs = SecretBytes::zeroed(data.len() * (t as usize - 1));
+    rng.fill(coeffs.as_mut_bytes());
+    let shares = (1..=n)
+        .map(|x| {
+            let mut y = SecretBytes::zeroed(data.len());
+            for (i, out) in y.as_mut_bytes().iter_mut().enumerate() {
+                let poly = &coeffs[i * (t as usize - 1)..(i + 1) * (t as usize - 1)];
+                // Horner 法，从最高次系数开始
+                let mut acc = 0;
+                for &c in poly.iter().rev() {
+                    acc = gf_mul(acc, x) ^ c;
+                }
+                *out = gf_mul(acc, x) ^ data[i];
+            }
+            Share { threshold: t, x, y }
+        })
+        .collect();
+    Ok(shares)
+}
+
+/// 由份额恢复秘密，份额数不少于门限，多余的份额被忽略
+pub fn combine(shares: &[Share]) -> Result<SecretBytes, CryptoError> {
+    let first = shares
+        .first()
+        .ok_or_else(|| CryptoError::InvalidInput("sss: no shares".to_string()))?;
+    let t = first.threshold as usize;
+    if shares
+        .iter()
+        .any(|s| s.threshold != first.threshold || s.y.len() != first.y.len())
+    {
+        return Err(CryptoError::InvalidInput(
+            "sss: shares are from different splits".to_string(),
+        ));
+    }
+    if shares.len() < t {
+        return Err(CryptoError::InvalidInput(format!(
+            "sss: need {} shares, got {}",
+            t,
+            shares.len()
+        )));
+    }
+    let shares = &shares[..t];
+    for (i, a) in shares.iter().enumerate() {
+        if shares[..i].iter().any(|b| b.x == a.x) {
+            return Err(CryptoError::InvalidInput(
+                "sss: duplicate share index".to_string(),
+            ));
+        }
+    }
+    // 在 x = 0 处的拉格朗日基，l_i = Π x_j / (x_j - x_i)，GF(2^8) 中减法即异或
+    let basis: Vec<u8> = shares
+        .iter()
+        .map(|a| {
+            shares
+                .iter()
+                .filter(|b| b.x != a.x)
+                .fold(1, |acc, b| gf_mul(acc, gf_mul(b.x, gf_inv(b.x ^ a.x))))
+        })
+        .collect();
+    let mut data = SecretBytes::zeroed(first.y.len());
+    for (share, l) in shares.iter().zip(basis) {
+        for (out, y) in data.as_mut_bytes().iter_mut().zip(share.y.iter()) {
+            *out ^= gf_mul(*y, l);
+        }
+    }
+    let (secret, checksum) = data.split_at(data.len() - 4);
+    if !ct_eq(&checksum4(secret), checksum) {
+        return Err(CryptoError::OperationFailed(
+            "sss: shares do not reconstruct a valid secret".to_string(),
+        ));
+    }
+    Ok(SecretBytes::new(secret.to_vec()))
+}
+
+/// GF(2^8) 乘法，模多项式 x^8 + x^4 + x^3 + x + 1 (与 AES 相同)，固定8轮不分支
+fn gf_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut out = 0;
+    for _ in 0..8 {
+        out ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    out
+}
+
+/// GF(2^8) 求逆，a^254，0 的逆为 0
+fn gf_inv(a: u8) -> u8 {
+    let mut out = 1;
+    let mut base = a;
+    // 254 = 0b11111110
+    for _ in 1..8 {
+        base = gf_mul(base, base);
+        out = gf_mul(out, base);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sss() {
+        // FIPS-197 4.2 的乘法示例，以及 {53}^-1 = {ca}
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x57, 0x13), 0xfe);
+        assert_eq!(gf_inv(0x53), 0xca);
+        assert!((1..=255u8).all(|a| gf_mul(a, gf_inv(a)) == 1));
+
+        let secret = b"correct horse battery staple";
+        let shares = split(secret, 3, 5).unwrap();
+        assert_eq!(shares.len(), 5);
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = [shares[c].clone(), shares[a].clone(), shares[b].clone()];
+                    assert_eq!(combine(&subset).unwrap().as_bytes(), secret);
+                }
+            }
+        }
+        assert!(combine(&shares[..2]).is_err());
+        assert_eq!(combine(&shares).unwrap().as_bytes(), secret);
+        let duplicate = [shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert!(combine(&duplicate).is_err());
+
+        // 编码往返，篡改被校验码发现
+        let encoded = shares[1].to_bytes();
+        assert_eq!(encoded.len(), secret.len() + 10);
+        assert_eq!(Share::from_bytes(&encoded).unwrap(), shares[1]);
+        let mut bad = encoded.to_vec();
+        bad[5] ^= 1;
+        assert!(Share::from_bytes(&bad).is_err());
+
+        // 混用两次拆分的份额
+        let other = split(secret, 3, 5).unwrap();
+        let mixed = [shares[0].clone(), shares[1].clone(), other[2].clone()];
+        assert!(combine(&mixed).is_err());
+
+        let single = split(secret, 1, 1).unwrap();
+        assert_eq!(combine(&single).unwrap().as_bytes(), secret);
+        assert!(split(secret, 0, 3).is_err());
+        assert!(split(secret, 4, 3).is_err());
+        assert!(split(b"", 2, 3).is_err());
+    }
+}
@@ -0,0 +1,339 @@
+//! 分段流式认证加密
+//!
+//! 采用 STREAM 构造：明文按64KiB分段，每段使用 ChaCha20-Poly1305 单独加密，
+//! nonce 由11字节的大端段序号和1字节的结尾标志组成，可以检测分段被截断、重排和删除。
+//!
+//! StreamEncryptor 和 StreamDecryptor 是 io::Write / io::Read 适配器，可以增量加解密大文件。
+//! 密文格式: 随机盐(16字节) | 分段密文，段密钥由 HKDF-SHA256 从密钥和盐派生，同一密钥可以加密多个流
+
+use std::io::{self, Read, Write};
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305};
+
+use crate::kdf::hkdf;
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+
+/// 明文分段长度
+pub const CHUNK_SIZE: usize = 64 * 1024;
+/// 每段密文附加的认证标签长度
+pub const TAG_SIZE: usize = 16;
+/// 密文头部的随机盐长度
+pub const HEADER_SIZE: usize = 16;
+
+const STREAM_INFO: &[u8] = b"pi_crypto stream v1";
+
+fn stream_key(key: &[u8]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&CHACHA20_POLY1305, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| "stream: key must be 32 bytes".to_string())
+}
+
+fn derive_key(key: &[u8], salt: &[u8]) -> Result<LessSafeKey, String> {
+    if key.len() != 32 {
+        return Err("stream: key must be 32 bytes".to_string());
+    }
+    stream_key(&SecretBytes::new(hkdf(salt, key, STREAM_INFO, 32)?))
+}
+
+fn stream_nonce(counter: u64, last: bool) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[3..11].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = last as u8;
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// 读满缓冲区或直到读到结尾，返回读取的字节数
+pub(crate) fn fill<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, String> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(m) => n += m,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+    Ok(n)
+}
+
+/// 流式加密，写入的明文满一段后加密写出，必须调用 finish 写出最后一段
+pub struct StreamEncryptor<W: Write> {
+    key: LessSafeKey,
+    aad: Vec<u8>,
+    writer: W,
+    buf: Vec<u8>,
+    counter: u64,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    /// 生成随机盐并写入 writer
+    ///
+    /// key: 32字节密钥
+    /// aad: 每一段都要认证的辅助数据
+    pub fn new(key: &[u8], aad: &[u8], mut writer: W) -> Result<Self, String> {
+        let salt = genSecureRandBytes(HEADER_SIZE);
+        let key = derive_key(key, &salt)?;
+        writer.write_all(&salt).map_err(|e| e.to_string())?;
+        Ok(Self::with_key(key, aad, writer))
+    }
+
+    /// 不写头部，直接用 key 作为段密钥，key 只能用于加密一个流
+    pub(crate) fn without_header(key: &[u8], aad: &[u8], writer: W) -> Result<Self, String> {
+        Ok(Self::with_key(stream_key(key)?, aad, writer))
+    }
+
+    fn with_key(key: LessSafeKey, aad: &[u8], writer: W) -> Self {
+        StreamEncryptor {
+            key,
+            aad: aad.to_vec(),
+            writer,
+            buf: Vec::with_capacity(CHUNK_SIZE + TAG_SIZE),
+            counter: 0,
+        }
+    }
+
+    /// 加密并写出最后一段，返回 writer；不调用时密文会被解密方视为截断
+    pub fn finish(mut self) -> Result<W, String> {
+        self.seal_chunk(true).map_err(|e| e.to_string())?;
+        self.writer.flush().map_err(|e| e.to_string())?;
+        Ok(self.writer)
+    }
+
+    fn seal_chunk(&mut self, last: bool) -> io::Result<()> {
+        let mut chunk = std::mem::replace(&mut self.buf, Vec::with_capacity(CHUNK_SIZE + TAG_SIZE));
+        self.key
+            .seal_in_place_append_tag(
+                stream_nonce(self.counter, last),
+                Aad::from(&self.aad),
+                &mut chunk,
+            )
+            .map_err(|_| io::Error::other("stream: encryption failed"))?;
+        self.writer.write_all(&chunk)?;
+        self.counter += 1;
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        // 满一段时等到还有数据才加密，这样才能确定它不是最后一段
+        if self.buf.len() == CHUNK_SIZE && !data.is_empty() {
+            self.seal_chunk(false)?;
+        }
+        let n = data.len().min(CHUNK_SIZE - self.buf.len());
+        self.buf.extend_from_slice(&data[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// 流式解密，每一段通过认证后才返回其明文，读到结尾时检查最后一段的结尾标志
+///
+/// 注意：截断和篡改在读到出错的段时才会发现，之前返回的明文需要在出错时丢弃
+pub struct StreamDecryptor<R: Read> {
+    key: LessSafeKey,
+    aad: Vec<u8>,
+    reader: R,
+    // 预读的下一段密文，用于判断当前段是否为最后一段
+    pending: Option<Vec<u8>>,
+    plain: Vec<u8>,
+    pos: usize,
+    counter: u64,
+    done: bool,
+    error: Option<String>,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    /// 从 reader 读取随机盐，参数同 StreamEncryptor::new
+    pub fn new(key: &[u8], aad: &[u8], mut reader: R) -> Result<Self, String> {
+        let mut salt = [0u8; HEADER_SIZE];
+        if fill(&mut reader, &mut salt)? != HEADER_SIZE {
+            return Err("stream: truncated header".to_string());
+        }
+        Ok(Self::with_key(derive_key(key, &salt)?, aad, reader))
+    }
+
+    /// 没有头部，直接用 key 作为段密钥
+    pub(crate) fn without_header(key: &[u8], aad: &[u8], reader: R) -> Result<Self, String> {
+        Ok(Self::with_key(stream_key(key)?, aad, reader))
+    }
+
+    fn with_key(key: LessSafeKey, aad: &[u8], reader: R) -> Self {
+        StreamDecryptor {
+            key,
+            aad: aad.to_vec(),
+            reader,
+            pending: None,
+            plain: Vec::new(),
+            pos: 0,
+            counter: 0,
+            done: false,
+            error: None,
+        }
+    }
+
+    fn read_chunk(&mut self) -> Result<Vec<u8>, String> {
+        let mut chunk = vec![0u8; CHUNK_SIZE + TAG_SIZE];
+        let n = fill(&mut self.reader, &mut chunk)?;
+        chunk.truncate(n);
+        Ok(chunk)
+    }
+
+    fn open_chunk(&mut self) -> Result<(), String> {
+        let mut chunk = match self.pending.take() {
+            Some(chunk) => chunk,
+            None => self.read_chunk()?,
+        };
+        let next = if chunk.len() == CHUNK_SIZE + TAG_SIZE {
+            self.read_chunk()?
+        } else {
+            Vec::new()
+        };
+        let last = next.is_empty();
+        // 只有整个流为空时，最后一段才允许没有明文
+        if chunk.len() < TAG_SIZE || (last && chunk.len() == TAG_SIZE && self.counter > 0) {
+            return Err("stream: truncated ciphertext".to_string());
+        }
+        let len = self
+            .key
+            .open_in_place(
+                stream_nonce(self.counter, last),
+                Aad::from(&self.aad),
+                &mut chunk,
+            )
+            .map_err(|_| "stream: authentication failed".to_string())?
+            .len();
+        chunk.truncate(len);
+        self.plain = chunk;
+        self.pos = 0;
+        self.counter += 1;
+        if last {
+            self.done = true;
+        } else {
+            self.pending = Some(next);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plain.len() {
+            if let Some(e) = &self.error {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, e.clone()));
+            }
+            if self.done {
+                return Ok(0);
+            }
+            if let Err(e) = self.open_chunk() {
+                self.plain.clear();
+                self.pos = 0;
+                self.error = Some(e);
+            }
+        }
+        let n = out.len().min(self.plain.len() - self.pos);
+        out[..n].copy_from_slice(&self.plain[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// 分段加密，不写头部
+///
+/// key: 32字节密钥，同一密钥只能用于加密一个流
+/// aad: 每一段都要认证的辅助数据
+pub(crate) fn seal<R: Read, W: Write>(
+    key: &[u8],
+    aad: &[u8],
+    mut reader: R,
+    writer: W,
+) -> Result<(), String> {
+    let mut enc = StreamEncryptor::without_header(key, aad, writer)?;
+    io::copy(&mut reader, &mut enc).map_err(|e| e.to_string())?;
+    enc.finish().map(|_| ())
+}
+
+/// 分段解密，认证失败或密文被截断时返回错误
+///
+/// 注意：出错前已经通过认证的分段会写入 writer，调用者需要在出错时丢弃输出
+pub(crate) fn open<R: Read, W: Write>(
+    key: &[u8],
+    aad: &[u8],
+    reader: R,
+    mut writer: W,
+) -> Result<(), String> {
+    let mut dec = StreamDecryptor::without_header(key, aad, reader)?;
+    io::copy(&mut dec, &mut writer).map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stream() {
+        let key = [9u8; 32];
+        for len in [0, 1, CHUNK_SIZE, CHUNK_SIZE * 2 + 7] {
+            let plain: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            // 分多次小块写入
+            let mut enc = StreamEncryptor::new(&key, b"aad", Vec::new()).unwrap();
+            for part in plain.chunks(1000) {
+                enc.write_all(part).unwrap();
+            }
+            let sealed = enc.finish().unwrap();
+            let chunks = len.div_ceil(CHUNK_SIZE).max(1);
+            assert_eq!(sealed.len(), HEADER_SIZE + len + chunks * TAG_SIZE);
+
+            let mut out = Vec::new();
+            StreamDecryptor::new(&key, b"aad", &sealed[..])
+                .unwrap()
+                .read_to_end(&mut out)
+                .unwrap();
+            assert_eq!(out, plain);
+
+            // 不带头部的内部接口
+            let mut raw = Vec::new();
+            seal(&key, &[], &plain[..], &mut raw).unwrap();
+            let mut out = Vec::new();
+            open(&key, &[], &raw[..], &mut out).unwrap();
+            assert_eq!(out, plain);
+            assert_eq!(raw.len(), len + chunks * TAG_SIZE);
+
+            let mut out = Vec::new();
+            assert!(StreamDecryptor::new(&key, b"other", &sealed[..])
+                .unwrap()
+                .read_to_end(&mut out)
+                .is_err());
+        }
+
+        let plain = vec![3u8; CHUNK_SIZE * 2 + 7];
+        let mut enc = StreamEncryptor::new(&key, b"", Vec::new()).unwrap();
+        enc.write_all(&plain).unwrap();
+        let sealed = enc.finish().unwrap();
+        // 在段边界截断，删掉最后一段
+        let cut = HEADER_SIZE + 2 * (CHUNK_SIZE + TAG_SIZE);
+        let mut dec = StreamDecryptor::new(&key, b"", &sealed[..cut]).unwrap();
+        let mut out = Vec::new();
+        let err = dec.read_to_end(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(dec.read(&mut [0u8; 16]).is_err());
+        // 篡改
+        let mut bad = sealed.clone();
+        bad[HEADER_SIZE + 5] ^= 1;
+        assert!(StreamDecryptor::new(&key, b"", &bad[..])
+            .unwrap()
+            .read_to_end(&mut Vec::new())
+            .is_err());
+        // 同一密钥加密两次得到不同的密文
+        let mut enc = StreamEncryptor::new(&key, b"", Vec::new()).unwrap();
+        enc.write_all(&plain).unwrap();
+        assert_ne!(enc.finish().unwrap(), sealed);
+        assert!(StreamDecryptor::new(&key, b"", &sealed[..8]).is_err());
+        assert!(StreamEncryptor::new(&key[..16], b"", Vec::new()).is_err());
+    }
+}
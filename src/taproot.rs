@@ -0,0 +1,110 @@
+//! Taproot 输出密钥调整 (BIP-341)
+//!
+//! 输出密钥 Q = P + tG，其中 P 为内部密钥，t = tagged_hash("TapTweak", P | merkle_root)，
+//! 只有密钥路径的输出 merkle_root 为空。用 Q 作为 witness v1 的输出密钥，
+//! 走密钥路径花费时用 tweak_secret_key 调整后的私钥做 schnorr 签名
+
+use secp256k1::curve::{Affine, Jacobian};
+
+use crate::digest::tagged_hash;
+use crate::schnorr::{lift_x, mul_gen, point_bytes, reduce, secret_scalar};
+
+/// 调整结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaprootTweak {
+    /// x-only 输出密钥
+    pub output_key: [u8; 32],
+    /// 输出密钥的 y 是否为奇数，脚本路径花费的控制块需要
+    pub output_key_odd: bool,
+    /// 标量 t，用于调整私钥
+    pub tweak: [u8; 32],
+}
+
+/// 计算 Taproot 输出密钥
+///
+/// internal_key: 32字节 x-only 内部公钥
+/// merkle_root: 脚本树的根，没有脚本路径时为 None
+pub fn taproot_tweak(
+    internal_key: &[u8],
+    merkle_root: Option<&[u8; 32]>,
+) -> Result<TaprootTweak, String> {
+    let p = lift_x(internal_key).ok_or_else(|| "taproot: invalid internal key".to_string())?;
+    let tweak = tweak_hash(internal_key, merkle_root);
+    let t = reduce(&tweak);
+    if t.b32() != tweak {
+        return Err("taproot: tweak out of range".to_string());
+    }
+    let q = Jacobian::from_ge(&mul_gen(&t)).add_ge(&p);
+    if q.is_infinity() {
+        return Err("taproot: output key is infinity".to_string());
+    }
+    let (output_key, output_key_odd) = point_bytes(&Affine::from_gej(&q));
+    Ok(TaprootTweak {
+        output_key,
+        output_key_odd,
+        tweak,
+    })
+}
+
+/// 调整内部私钥，得到可以对输出密钥签名的私钥
+///
+/// internal_sk: 内部私钥
+/// tweak: taproot_tweak 的结果
+pub fn tweak_secret_key(internal_sk: &[u8], tweak: &TaprootTweak) -> Result<[u8; 32], String> {
+    let d0 = secret_scalar(internal_sk)?;
+    let (_, odd) = point_bytes(&mul_gen(&d0));
+    let d = if odd { -d0 } else { d0 };
+    let tweaked = d + reduce(&tweak.tweak);
+    if tweaked.is_zero() {
+        return Err("taproot: tweaked secret key is zero".to_string());
+    }
+    Ok(tweaked.b32())
+}
+
+fn tweak_hash(internal_key: &[u8], merkle_root: Option<&[u8; 32]>) -> [u8; 32] {
+    match merkle_root {
+        Some(root) => tagged_hash(b"TapTweak", &[internal_key, root].concat()),
+        None => tagged_hash(b"TapTweak", internal_key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schnorr;
+    use hex::FromHex;
+
+    #[test]
+    fn test_taproot_tweak() {
+        // test vectors from: https://github.com/bitcoin/bips/blob/master/bip-0341/wallet-test-vectors.json
+        let internal =
+            Vec::from_hex("d6889cb081036e0faefa3a35157ad71086b123b2b144b649798b494c300a961d")
+                .unwrap();
+        let tweaked = taproot_tweak(&internal, None).unwrap();
+        assert_eq!(
+            tweaked.tweak.to_vec(),
+            Vec::from_hex("b86e7be8f39bab32a6f2c0443abbc210f0edac0e2c53d501b36b64437d9c6c70")
+                .unwrap()
+        );
+        assert_eq!(
+            tweaked.output_key.to_vec(),
+            Vec::from_hex("53a1f6e454df1aa2776a2814a721372d6258050de330b3c6d10ee8f4e0dda343")
+                .unwrap()
+        );
+
+        // 调整后的私钥签名可以用输出密钥验证
+        let sk = [7u8; 32];
+        let internal = schnorr::xonly_public_key(&sk).unwrap();
+        let root = [1u8; 32];
+        let tweaked = taproot_tweak(&internal, Some(&root)).unwrap();
+        let tweaked_sk = tweak_secret_key(&sk, &tweaked).unwrap();
+        assert_eq!(
+            schnorr::xonly_public_key(&tweaked_sk).unwrap(),
+            tweaked.output_key
+        );
+        let msg = [3u8; 32];
+        let sig = schnorr::sign(&msg, &tweaked_sk, &[0u8; 32]).unwrap();
+        assert!(schnorr::verify(&msg, &tweaked.output_key, &sig));
+        assert!(!schnorr::verify(&msg, &internal, &sig));
+    }
+}
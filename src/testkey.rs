@@ -0,0 +1,208 @@
+//! 测试环境使用的确定性密钥
+//!
+//! 由种子字符串和序号稳定地派生出各算法的密钥对，便于多节点集成测试和开发网络得到可复现的
+//! 验证者和账户密钥，而不必把密钥文件提交到代码仓库。
+//! 派生出的私钥只取决于公开的种子，绝不能用于生产环境
+
+use num_traits::Zero;
+use ring::signature::{
+    EcdsaKeyPair, ECDSA_P256_SHA256_ASN1_SIGNING, ECDSA_P384_SHA384_ASN1_SIGNING,
+};
+use secp256k1::{PublicKey, SecretKey};
+use simple_asn1::{oid, to_der, ASN1Block, ASN1Class, BigInt, BigUint, OID};
+
+use crate::ec::Curve;
+use crate::kdf::{Hkdf, Kdf};
+use crate::pem::wrap_pkcs8;
+use crate::signature::SignatureScheme;
+
+const SALT: &[u8] = b"pi_crypto test keypair";
+
+// NIST P-256 和 P-384 (FIPS 186-4 D.1.2)
+const P256_P: &str = "FFFFFFFF00000001000000000000000000000000FFFFFFFFFFFFFFFFFFFFFFFF";
+const P256_A: &str = "FFFFFFFF00000001000000000000000000000000FFFFFFFFFFFFFFFFFFFFFFFC";
+const P256_B: &str = "5AC635D8AA3A93E7B3EBBD55769886BC651D06B0CC53B0F63BCE3C3E27D2604B";
+const P256_N: &str = "FFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551";
+const P256_GX: &str = "6B17D1F2E12C4247F8BCE6E563A440F277037D812DEB33A0F4A13945D898C296";
+const P256_GY: &str = "4FE342E2FE1A7F9B8EE7EB4A7C0F9E162BCE33576B315ECECBB6406837BF51F5";
+const P384_P: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFFFF0000000000000000FFFFFFFF";
+const P384_A: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFEFFFFFFFF0000000000000000FFFFFFFC";
+const P384_B: &str = "B3312FA7E23EE7E4988E056BE3F82D19181D9C6EFE8141120314088F5013875AC656398D8A2ED19D2A85C8EDD3EC2AEF";
+const P384_N: &str = "FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFC7634D81F4372DDF581A0DB248B0A77AECEC196ACCC52973";
+const P384_GX: &str = "AA87CA22BE8B05378EB1C71EF320AD746E1D3B628BA79B9859F741E082542A385502F25DBF55296C3A545E3872760AB7";
+const P384_GY: &str = "3617DE4A96262C6F5D9E98BF9292DC29F8F41DBD289A147CE9DA3113B5F0B8C00A60B1CE1D7E819D7A431D7C90EA0E5F";
+
+/// 由种子和序号派生测试密钥对，返回 (私钥, 公钥)
+///
+/// 私钥格式与对应签名函数的输入一致：secp256k1 为32字节私钥，Ed25519 为64字节扩展私钥，
+/// ECDSA 为 PKCS#8 DER，SM2 为32字节私钥，Ed448 为57字节私钥；公钥格式与 scheme_verify 的输入一致，secp256k1 为33字节压缩公钥。
+/// RSA 密钥无法确定性生成，返回错误
+/// scheme: 签名算法
+/// seed: 种子字符串
+/// index: 密钥序号
+pub fn derive_test_keypair(
+    scheme: SignatureScheme,
+    seed: &str,
+    index: u32,
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    match scheme {
+        SignatureScheme::Secp256k1 => {
+            // 派生结果超出曲线阶的概率可以忽略，仍然按计数器重试
+            for counter in 0..=u8::MAX {
+                let sk = expand(scheme, seed, index, counter, 32);
+                if let Ok(sk) = SecretKey::parse_slice(&sk) {
+                    let pk = PublicKey::from_secret_key(&sk);
+                    return Ok((sk.serialize().to_vec(), pk.serialize_compressed().to_vec()));
+                }
+            }
+            Err("testkey: failed to derive secp256k1 key".to_string())
+        }
+        SignatureScheme::Ed25519 => {
+            let (sk, pk) = crypto::ed25519::keypair(&expand(scheme, seed, index, 0, 32));
+            Ok((sk.to_vec(), pk.to_vec()))
+        }
+        SignatureScheme::EcdsaP256 => ecdsa(&ECDSA_P256_SHA256_ASN1_SIGNING, scheme, seed, index),
+        SignatureScheme::EcdsaP384 => ecdsa(&ECDSA_P384_SHA384_ASN1_SIGNING, scheme, seed, index),
+        SignatureScheme::Sm2 => {
+            for counter in 0..=u8::MAX {
+                let sk = expand(scheme, seed, index, counter, 32);
+                if let Ok(pk) = crate::sm2::public_key(&sk) {
+                    return Ok((sk, pk));
+                }
+            }
+            Err("testkey: failed to derive sm2 key".to_string())
+        }
+        SignatureScheme::Ed448 => {
+            let sk = expand(scheme, seed, index, 0, crate::ed448::SECRET_KEY_SIZE);
+            let pk = crate::ed448::public_key(&sk).map_err(|e| format!("testkey: {}", e))?;
+            Ok((sk, pk.to_vec()))
+        }
+        SignatureScheme::RsaPkcs1Sha256 | SignatureScheme::RsaPssSha256 => {
+            Err("testkey: RSA keys can not be derived from a seed".to_string())
+        }
+    }
+}
+
+fn ecdsa(
+    alg: &'static ring::signature::EcdsaSigningAlgorithm,
+    scheme: SignatureScheme,
+    seed: &str,
+    index: u32,
+) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let (curve, curve_oid) = match scheme {
+        SignatureScheme::EcdsaP256 => (
+            Curve::new(P256_P, P256_A, P256_B, P256_N, P256_GX, P256_GY),
+            oid!(1, 2, 840, 10045, 3, 1, 7),
+        ),
+        _ => (
+            Curve::new(P384_P, P384_A, P384_B, P384_N, P384_GX, P384_GY),
+            oid!(1, 3, 132, 0, 34),
+        ),
+    };
+    let bytes = expand(scheme, seed, index, 0, curve.size);
+    let d = BigUint::from_bytes_be(&bytes);
+    if d.is_zero() || d >= curve.n {
+        return Err("testkey: failed to derive ECDSA key".to_string());
+    }
+    let public_key = curve.encode(&curve.mul_g(&d));
+
+    // ECPrivateKey ::= SEQUENCE { version 1, privateKey OCTET STRING, publicKey [1] BIT STRING }
+    let ec_private_key = to_der(&ASN1Block::Sequence(
+        0,
+        vec![
+            ASN1Block::Integer(0, BigInt::from(1)),
+            ASN1Block::OctetString(0, bytes),
+            ASN1Block::Explicit(
+                ASN1Class::ContextSpecific,
+                0,
+                BigUint::from(1u32),
+                Box::new(ASN1Block::BitString(
+                    0,
+                    public_key.len() * 8,
+                    public_key.clone(),
+                )),
+            ),
+        ],
+    ))
+    .map_err(|e| format!("testkey: encode error: {:?}", e))?;
+    let pkcs8 = wrap_pkcs8(
+        vec![
+            ASN1Block::ObjectIdentifier(0, oid!(1, 2, 840, 10045, 2, 1)),
+            ASN1Block::ObjectIdentifier(0, curve_oid),
+        ],
+        ec_private_key,
+    )
+    .map_err(|e| format!("testkey: {}", e))?;
+    // 由 ring 检查编码和公钥是否匹配
+    EcdsaKeyPair::from_pkcs8(alg, &pkcs8)
+        .map_err(|_| "testkey: failed to derive ECDSA key".to_string())?;
+    Ok((pkcs8, public_key))
+}
+
+fn expand(scheme: SignatureScheme, seed: &str, index: u32, counter: u8, len: usize) -> Vec<u8> {
+    let mut info = vec![scheme.id()];
+    info.extend_from_slice(&index.to_be_bytes());
+    info.push(counter);
+    Hkdf::sha256()
+        .derive(seed.as_bytes(), SALT, &info, len)
+        .expect("testkey: hkdf output length is valid")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signature::{scheme_verify, ECDSASecp256k1, EcdsaAlg};
+    use hex::FromHex;
+
+    #[test]
+    fn test_derive_test_keypair() {
+        let msg = b"devnet";
+        for scheme in [
+            SignatureScheme::Secp256k1,
+            SignatureScheme::Ed25519,
+            SignatureScheme::EcdsaP256,
+            SignatureScheme::EcdsaP384,
+            SignatureScheme::Sm2,
+            SignatureScheme::Ed448,
+        ]
+        .iter()
+        {
+            let (sk, pk) = derive_test_keypair(*scheme, "validators", 0).unwrap();
+            let (sk2, pk2) = derive_test_keypair(*scheme, "validators", 0).unwrap();
+            let (_, pk3) = derive_test_keypair(*scheme, "validators", 1).unwrap();
+            assert_eq!((&sk, &pk), (&sk2, &pk2));
+            assert_ne!(pk, pk3);
+
+            let sig = match scheme {
+                SignatureScheme::Secp256k1 => {
+                    let hash = ring::digest::digest(&ring::digest::SHA256, msg);
+                    ECDSASecp256k1::new().sign(hash.as_ref(), &sk).unwrap()
+                }
+                SignatureScheme::Ed25519 => crypto::ed25519::signature(msg, &sk).to_vec(),
+                SignatureScheme::Sm2 => crate::sm2::sign(msg, &sk, crate::sm2::DEFAULT_ID).unwrap(),
+                SignatureScheme::Ed448 => crate::ed448::sign(msg, &sk).unwrap().to_vec(),
+                SignatureScheme::EcdsaP256 => crate::signature::EcdsaKeyPair::from_pkcs8(
+                    EcdsaAlg::ECDSA_P256_SHA256_ASN1,
+                    &sk,
+                )
+                .unwrap()
+                .sign(msg)
+                .unwrap(),
+                _ => crate::signature::EcdsaKeyPair::from_pkcs8(
+                    EcdsaAlg::ECDSA_P384_SHA384_ASN1,
+                    &sk,
+                )
+                .unwrap()
+                .sign(msg)
+                .unwrap(),
+            };
+            assert!(scheme_verify(*scheme, &pk, msg, &sig));
+        }
+
+        // 派生结果在版本之间保持不变
+        let (_, pk) = derive_test_keypair(SignatureScheme::EcdsaP256, "validators", 0).unwrap();
+        assert_eq!(pk, Vec::from_hex("043095a7aec8c4596b4f544cab03ee23a7fa6982f33c6bf52aa7e740d1b05b340dbb32e014900de69aa3bbb12a4dc9c325d883bcebfe3e33959e16e6159889131f").unwrap());
+
+        assert!(derive_test_keypair(SignatureScheme::RsaPssSha256, "validators", 0).is_err());
+    }
+}
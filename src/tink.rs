@@ -0,0 +1,819 @@
+//! Google Tink 密钥集互通
+//!
+//! 读写 Tink 的明文密钥集(二进制 protobuf 和 JSON 格式)以及由 KMS 主密钥加密的密钥集，
+//! 并按 Tink 的密文格式使用其中的 AES-GCM、HMAC 和 Ed25519 密钥，便于从 Tink 迁移的服务继续使用原有密钥。
+//!
+//! Tink 的输出前缀：TINK 为 0x01 | 4字节大端密钥ID，LEGACY 和 CRUNCHY 为 0x00 | 4字节大端密钥ID，RAW 没有前缀
+
+use serde::{Deserialize, Serialize};
+
+use crate::aead::{Aead, AES_128_GCM, AES_256_GCM};
+use crate::ct::ct_eq;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::mac::{HmacSha256, HmacSha384, HmacSha512};
+use crate::proto;
+use crate::random::genSecureRandBytes;
+use crate::secret::SecretBytes;
+
+const TYPE_PREFIX: &str = "type.googleapis.com/google.crypto.tink.";
+const AES_GCM_KEY: &str = "type.googleapis.com/google.crypto.tink.AesGcmKey";
+const HMAC_KEY: &str = "type.googleapis.com/google.crypto.tink.HmacKey";
+const ED25519_PRIVATE_KEY: &str = "type.googleapis.com/google.crypto.tink.Ed25519PrivateKey";
+const ED25519_PUBLIC_KEY: &str = "type.googleapis.com/google.crypto.tink.Ed25519PublicKey";
+
+const AES_GCM_IV_SIZE: usize = 12;
+const PREFIX_SIZE: usize = 5;
+
+/// 密钥状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    Enabled,
+    Disabled,
+    Destroyed,
+}
+
+/// 输出前缀类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputPrefixType {
+    Tink,
+    Legacy,
+    Raw,
+    Crunchy,
+}
+
+/// HMAC 使用的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HmacHash {
+    SHA256,
+    SHA384,
+    SHA512,
+}
+
+/// 密钥内容
+#[derive(Clone, PartialEq, Eq)]
+pub enum KeyMaterial {
+    /// AES-GCM 密钥，16或32字节
+    AesGcm(SecretBytes),
+    /// HMAC 密钥，tag_size 为截断后的认证码长度
+    Hmac {
+        hash: HmacHash,
+        tag_size: usize,
+        key: SecretBytes,
+    },
+    /// Ed25519 私钥，32字节种子和对应的公钥
+    Ed25519Private {
+        seed: SecretBytes,
+        public_key: Vec<u8>,
+    },
+    /// Ed25519 公钥
+    Ed25519Public(Vec<u8>),
+    /// 不支持的密钥类型，原样保留以便导出
+    Unknown {
+        type_url: String,
+        value: Vec<u8>,
+        material_type: u64,
+    },
+}
+
+/// 密钥集中的一个密钥
+#[derive(Clone, PartialEq, Eq)]
+pub struct KeysetKey {
+    pub key_id: u32,
+    pub status: KeyStatus,
+    pub output_prefix: OutputPrefixType,
+    pub material: KeyMaterial,
+}
+
+impl KeysetKey {
+    fn prefix(&self) -> Vec<u8> {
+        let mut prefix = match self.output_prefix {
+            OutputPrefixType::Tink => vec![1],
+            OutputPrefixType::Legacy | OutputPrefixType::Crunchy => vec![0],
+            OutputPrefixType::Raw => return Vec::new(),
+        };
+        prefix.extend_from_slice(&self.key_id.to_be_bytes());
+        prefix
+    }
+
+    // LEGACY 前缀的MAC和签名对数据追加一个零字节
+    fn legacy_data(&self, data: &[u8]) -> Vec<u8> {
+        let mut data = data.to_vec();
+        if self.output_prefix == OutputPrefixType::Legacy {
+            data.push(0);
+        }
+        data
+    }
+}
+
+/// Tink 密钥集
+#[derive(Clone, PartialEq, Eq)]
+pub struct Keyset {
+    pub primary_key_id: u32,
+    pub keys: Vec<KeysetKey>,
+}
+
+impl Keyset {
+    /// 解析二进制 protobuf 格式的明文密钥集
+    pub fn from_binary(data: &[u8]) -> Result<Self, CryptoError> {
+        let mut primary_key_id = 0;
+        let mut keys = Vec::new();
+        for field in proto::parse(data).map_err(CryptoError::InvalidInput)? {
+            match field {
+                (1, proto::Value::Varint(v)) => primary_key_id = v as u32,
+                (2, proto::Value::Bytes(b)) => keys.push(parse_key(b)?),
+                _ => {}
+            }
+        }
+        Ok(Keyset {
+            primary_key_id,
+            keys,
+        })
+    }
+
+    /// 编码为二进制 protobuf 格式
+    pub fn to_binary(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        proto::put_varint_field(&mut out, 1, u64::from(self.primary_key_id));
+        for key in self.keys.iter() {
+            let (type_url, value, material_type) = encode_material(&key.material);
+            let mut key_data = Vec::new();
+            proto::put_bytes_field(&mut key_data, 1, type_url.as_bytes());
+            proto::put_bytes_field(&mut key_data, 2, &value);
+            proto::put_varint_field(&mut key_data, 3, material_type);
+
+            let mut k = Vec::new();
+            proto::put_bytes_field(&mut k, 1, &key_data);
+            proto::put_varint_field(&mut k, 2, status_to_proto(key.status));
+            proto::put_varint_field(&mut k, 3, u64::from(key.key_id));
+            proto::put_varint_field(&mut k, 4, prefix_to_proto(key.output_prefix));
+            proto::put_bytes_field(&mut out, 2, &k);
+        }
+        out
+    }
+
+    /// 解析 JSON 格式的明文密钥集
+    pub fn from_json(json: &str) -> Result<Self, CryptoError> {
+        let keyset: JsonKeyset = serde_json::from_str(json)
+            .map_err(|e| CryptoError::InvalidInput(format!("tink: bad json keyset: {}", e)))?;
+        let mut keys = Vec::new();
+        for key in keyset.key {
+            let value = base64::decode(&key.key_data.value)
+                .map_err(|e| CryptoError::InvalidKey(format!("tink: bad key value: {}", e)))?;
+            keys.push(KeysetKey {
+                key_id: key.key_id,
+                status: status_from_name(&key.status)?,
+                output_prefix: prefix_from_name(&key.output_prefix_type)?,
+                material: decode_material(
+                    &key.key_data.type_url,
+                    &value,
+                    material_type_from_name(&key.key_data.key_material_type),
+                )?,
+            });
+        }
+        Ok(Keyset {
+            primary_key_id: keyset.primary_key_id,
+            keys,
+        })
+    }
+
+    /// 编码为 JSON 格式
+    pub fn to_json(&self) -> String {
+        let keyset = JsonKeyset {
+            primary_key_id: self.primary_key_id,
+            key: self
+                .keys
+                .iter()
+                .map(|key| {
+                    let (type_url, value, material_type) = encode_material(&key.material);
+                    JsonKey {
+                        key_data: JsonKeyData {
+                            type_url,
+                            value: base64::encode(&value),
+                            key_material_type: material_type_name(material_type).to_string(),
+                        },
+                        status: status_name(key.status).to_string(),
+                        key_id: key.key_id,
+                        output_prefix_type: prefix_name(key.output_prefix).to_string(),
+                    }
+                })
+                .collect(),
+        };
+        serde_json::to_string(&keyset).expect("tink: keyset serializes to json")
+    }
+
+    /// 解析由主密钥加密的二进制密钥集(EncryptedKeyset)
+    ///
+    /// data: EncryptedKeyset 的 protobuf 编码
+    /// decrypt: 主密钥的解密函数，通常调用KMS，参数为密文和辅助数据
+    /// associated_data: 加密时使用的辅助数据，Tink 默认为空
+    pub fn from_encrypted<F>(
+        data: &[u8],
+        decrypt: F,
+        associated_data: &[u8],
+    ) -> Result<Self, CryptoError>
+    where
+        F: Fn(&[u8], &[u8]) -> Result<Vec<u8>, CryptoError>,
+    {
+        let encrypted = proto::parse(data)
+            .map_err(CryptoError::InvalidInput)?
+            .into_iter()
+            .find_map(|field| match field {
+                (2, proto::Value::Bytes(b)) => Some(b),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                CryptoError::InvalidInput("tink: missing encrypted keyset".to_string())
+            })?;
+        Keyset::from_binary(&decrypt(encrypted, associated_data)?)
+    }
+
+    /// 使用主密钥加密密钥集，输出 EncryptedKeyset 的 protobuf 编码
+    ///
+    /// encrypt: 主密钥的加密函数，参数为明文和辅助数据
+    pub fn to_encrypted<F>(
+        &self,
+        encrypt: F,
+        associated_data: &[u8],
+    ) -> Result<Vec<u8>, CryptoError>
+    where
+        F: Fn(&[u8], &[u8]) -> Result<Vec<u8>, CryptoError>,
+    {
+        let encrypted = encrypt(&self.to_binary(), associated_data)?;
+        let mut out = Vec::new();
+        proto::put_bytes_field(&mut out, 2, &encrypted);
+        Ok(out)
+    }
+
+    /// 使用主密钥按 Tink 格式加密：前缀 | IV | 密文 | 认证标签
+    pub fn aead_encrypt(&self, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let key = self.primary()?;
+        let (aead, key_bytes) = aes_gcm(&key.material)?;
+        let iv = genSecureRandBytes(AES_GCM_IV_SIZE);
+        let mut in_out = plaintext.to_vec();
+        aead.seal_in_place(key_bytes, &iv, aad, &mut in_out)
+            .map_err(CryptoError::InvalidKey)?;
+        let mut out = key.prefix();
+        out.extend_from_slice(&iv);
+        out.extend_from_slice(&in_out);
+        Ok(out)
+    }
+
+    /// 按密文前缀选择密钥解密，RAW 密钥作为后备依次尝试
+    pub fn aead_decrypt(&self, ciphertext: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        for (key, body) in self.candidates(ciphertext) {
+            if let Ok((aead, key_bytes)) = aes_gcm(&key.material) {
+                if body.len() < AES_GCM_IV_SIZE {
+                    continue;
+                }
+                let mut in_out = body[AES_GCM_IV_SIZE..].to_vec();
+                if aead
+                    .open_in_place(key_bytes, &body[..AES_GCM_IV_SIZE], aad, &mut in_out)
+                    .is_ok()
+                {
+                    return Ok(in_out);
+                }
+            }
+        }
+        Err(CryptoError::Verify(VerifyFailure::DigestMismatch))
+    }
+
+    /// 使用主密钥按 Tink 格式计算认证码
+    pub fn compute_mac(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let key = self.primary()?;
+        let mut out = key.prefix();
+        out.extend_from_slice(&hmac(&key.material, &key.legacy_data(data))?);
+        Ok(out)
+    }
+
+    /// 验证 Tink 格式的认证码
+    pub fn verify_mac(&self, tag: &[u8], data: &[u8]) -> bool {
+        self.candidates(tag).into_iter().any(|(key, body)| {
+            hmac(&key.material, &key.legacy_data(data))
+                .map(|expected| ct_eq(&expected, body))
+                .unwrap_or(false)
+        })
+    }
+
+    /// 使用主密钥按 Tink 格式签名
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let key = self.primary()?;
+        let seed = match &key.material {
+            KeyMaterial::Ed25519Private { seed, .. } => seed,
+            _ => {
+                return Err(CryptoError::InvalidKey(
+                    "tink: primary key is not a signing key".to_string(),
+                ))
+            }
+        };
+        let (secret, _) = crypto::ed25519::keypair(seed);
+        let mut out = key.prefix();
+        out.extend_from_slice(&crypto::ed25519::signature(&key.legacy_data(data), &secret));
+        Ok(out)
+    }
+
+    /// 验证 Tink 格式的签名，私钥和公钥密钥集都可以验证
+    pub fn verify(&self, signature: &[u8], data: &[u8]) -> bool {
+        self.candidates(signature).into_iter().any(|(key, body)| {
+            let public_key = match &key.material {
+                KeyMaterial::Ed25519Private { public_key, .. } => public_key,
+                KeyMaterial::Ed25519Public(public_key) => public_key,
+                _ => return false,
+            };
+            body.len() == 64
+                && public_key.len() == 32
+                && crate::ed25519::verify(&key.legacy_data(data), public_key, body)
+        })
+    }
+
+    /// 导出只包含公钥的密钥集
+    pub fn public_keyset(&self) -> Result<Keyset, CryptoError> {
+        let keys = self
+            .keys
+            .iter()
+            .map(|key| match &key.material {
+                KeyMaterial::Ed25519Private { public_key, .. } => Ok(KeysetKey {
+                    material: KeyMaterial::Ed25519Public(public_key.clone()),
+                    ..key.clone()
+                }),
+                _ => Err(CryptoError::InvalidKey(
+                    "tink: keyset contains non-asymmetric keys".to_string(),
+                )),
+            })
+            .collect::<Result<Vec<_>, CryptoError>>()?;
+        Ok(Keyset {
+            primary_key_id: self.primary_key_id,
+            keys,
+        })
+    }
+
+    fn primary(&self) -> Result<&KeysetKey, CryptoError> {
+        self.keys
+            .iter()
+            .find(|k| k.key_id == self.primary_key_id && k.status == KeyStatus::Enabled)
+            .ok_or_else(|| CryptoError::InvalidKey("tink: no enabled primary key".to_string()))
+    }
+
+    // 前缀匹配的密钥在前，RAW 密钥在后
+    fn candidates<'a>(&'a self, data: &'a [u8]) -> Vec<(&'a KeysetKey, &'a [u8])> {
+        let enabled = self.keys.iter().filter(|k| k.status == KeyStatus::Enabled);
+        let mut out: Vec<_> = enabled
+            .clone()
+            .filter(|k| {
+                k.output_prefix != OutputPrefixType::Raw
+                    && data.len() >= PREFIX_SIZE
+                    && data[..PREFIX_SIZE] == k.prefix()[..]
+            })
+            .map(|k| (k, &data[PREFIX_SIZE..]))
+            .collect();
+        out.extend(
+            enabled
+                .filter(|k| k.output_prefix == OutputPrefixType::Raw)
+                .map(|k| (k, data)),
+        );
+        out
+    }
+}
+
+fn aes_gcm(material: &KeyMaterial) -> Result<(&'static dyn Aead, &[u8]), CryptoError> {
+    match material {
+        KeyMaterial::AesGcm(key) if key.len() == 16 => Ok((&AES_128_GCM, key)),
+        KeyMaterial::AesGcm(key) if key.len() == 32 => Ok((&AES_256_GCM, key)),
+        _ => Err(CryptoError::InvalidKey(
+            "tink: not an AES-GCM key".to_string(),
+        )),
+    }
+}
+
+fn hmac(material: &KeyMaterial, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let (hash, tag_size, key) = match material {
+        KeyMaterial::Hmac {
+            hash,
+            tag_size,
+            key,
+        } => (*hash, *tag_size, key),
+        _ => return Err(CryptoError::InvalidKey("tink: not an HMAC key".to_string())),
+    };
+    let mut tag = match hash {
+        HmacHash::SHA256 => {
+            crate::mac::mac::<HmacSha256>(key, data).map_err(CryptoError::InvalidKey)?
+        }
+        HmacHash::SHA384 => {
+            crate::mac::mac::<HmacSha384>(key, data).map_err(CryptoError::InvalidKey)?
+        }
+        HmacHash::SHA512 => {
+            crate::mac::mac::<HmacSha512>(key, data).map_err(CryptoError::InvalidKey)?
+        }
+    };
+    if tag_size < 10 || tag_size > tag.len() {
+        return Err(CryptoError::InvalidInput(
+            "tink: bad HMAC tag size".to_string(),
+        ));
+    }
+    tag.truncate(tag_size);
+    Ok(tag)
+}
+
+fn parse_key(data: &[u8]) -> Result<KeysetKey, CryptoError> {
+    let mut key_data = None;
+    let mut status = 0;
+    let mut key_id = 0;
+    let mut prefix = 0;
+    for field in proto::parse(data).map_err(CryptoError::InvalidInput)? {
+        match field {
+            (1, proto::Value::Bytes(b)) => key_data = Some(b),
+            (2, proto::Value::Varint(v)) => status = v,
+            (3, proto::Value::Varint(v)) => key_id = v as u32,
+            (4, proto::Value::Varint(v)) => prefix = v,
+            _ => {}
+        }
+    }
+    let key_data =
+        key_data.ok_or_else(|| CryptoError::InvalidKey("tink: missing key data".to_string()))?;
+    let mut type_url = String::new();
+    let mut value: &[u8] = &[];
+    let mut material_type = 0;
+    for field in proto::parse(key_data).map_err(CryptoError::InvalidInput)? {
+        match field {
+            (1, proto::Value::Bytes(b)) => {
+                type_url = String::from_utf8(b.to_vec())
+                    .map_err(|_| CryptoError::InvalidInput("tink: bad type url".to_string()))?
+            }
+            (2, proto::Value::Bytes(b)) => value = b,
+            (3, proto::Value::Varint(v)) => material_type = v,
+            _ => {}
+        }
+    }
+    Ok(KeysetKey {
+        key_id,
+        status: status_from_proto(status)?,
+        output_prefix: prefix_from_proto(prefix)?,
+        material: decode_material(&type_url, value, material_type)?,
+    })
+}
+
+fn decode_material(
+    type_url: &str,
+    value: &[u8],
+    material_type: u64,
+) -> Result<KeyMaterial, CryptoError> {
+    let bytes_field = |fields: &[(u64, proto::Value)], number: u64| -> Vec<u8> {
+        fields
+            .iter()
+            .find_map(|f| match f {
+                (n, proto::Value::Bytes(b)) if *n == number => Some(b.to_vec()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    };
+    let material = match type_url {
+        AES_GCM_KEY => {
+            let fields = proto::parse(value).map_err(CryptoError::InvalidInput)?;
+            KeyMaterial::AesGcm(bytes_field(&fields, 3).into())
+        }
+        HMAC_KEY => {
+            let fields = proto::parse(value).map_err(CryptoError::InvalidInput)?;
+            let params = bytes_field(&fields, 2);
+            let params = proto::parse(&params).map_err(CryptoError::InvalidInput)?;
+            let mut hash = 0;
+            let mut tag_size = 0;
+            for field in params {
+                match field {
+                    (1, proto::Value::Varint(v)) => hash = v,
+                    (2, proto::Value::Varint(v)) => tag_size = v as usize,
+                    _ => {}
+                }
+            }
+            let hash = match hash {
+                2 => HmacHash::SHA384,
+                3 => HmacHash::SHA256,
+                4 => HmacHash::SHA512,
+                _ => {
+                    return Err(CryptoError::InvalidInput(format!(
+                        "tink: unsupported HMAC hash type {}",
+                        hash
+                    )))
+                }
+            };
+            KeyMaterial::Hmac {
+                hash,
+                tag_size,
+                key: bytes_field(&fields, 3).into(),
+            }
+        }
+        ED25519_PRIVATE_KEY => {
+            let fields = proto::parse(value).map_err(CryptoError::InvalidInput)?;
+            let public = bytes_field(&fields, 3);
+            let public = proto::parse(&public).map_err(CryptoError::InvalidInput)?;
+            KeyMaterial::Ed25519Private {
+                seed: bytes_field(&fields, 2).into(),
+                public_key: bytes_field(&public, 2),
+            }
+        }
+        ED25519_PUBLIC_KEY => {
+            let fields = proto::parse(value).map_err(CryptoError::InvalidInput)?;
+            KeyMaterial::Ed25519Public(bytes_field(&fields, 2))
+        }
+        _ if type_url.starts_with(TYPE_PREFIX) => KeyMaterial::Unknown {
+            type_url: type_url.to_string(),
+            value: value.to_vec(),
+            material_type,
+        },
+        _ => {
+            return Err(CryptoError::InvalidInput(format!(
+                "tink: bad type url {}",
+                type_url
+            )))
+        }
+    };
+    Ok(material)
+}
+
+fn encode_material(material: &KeyMaterial) -> (String, Vec<u8>, u64) {
+    let mut value = Vec::new();
+    match material {
+        KeyMaterial::AesGcm(key) => {
+            proto::put_bytes_field(&mut value, 3, key);
+            (AES_GCM_KEY.to_string(), value, 1)
+        }
+        KeyMaterial::Hmac {
+            hash,
+            tag_size,
+            key,
+        } => {
+            let mut params = Vec::new();
+            let hash = match hash {
+                HmacHash::SHA384 => 2,
+                HmacHash::SHA256 => 3,
+                HmacHash::SHA512 => 4,
+            };
+            proto::put_varint_field(&mut params, 1, hash);
+            proto::put_varint_field(&mut params, 2, *tag_size as u64);
+            proto::put_bytes_field(&mut value, 2, &params);
+            proto::put_bytes_field(&mut value, 3, key);
+            (HMAC_KEY.to_string(), value, 1)
+        }
+        KeyMaterial::Ed25519Private { seed, public_key } => {
+            let mut public = Vec::new();
+            proto::put_bytes_field(&mut public, 2, public_key);
+            proto::put_bytes_field(&mut value, 2, seed);
+            proto::put_bytes_field(&mut value, 3, &public);
+            (ED25519_PRIVATE_KEY.to_string(), value, 2)
+        }
+        KeyMaterial::Ed25519Public(public_key) => {
+            proto::put_bytes_field(&mut value, 2, public_key);
+            (ED25519_PUBLIC_KEY.to_string(), value, 3)
+        }
+        KeyMaterial::Unknown {
+            type_url,
+            value,
+            material_type,
+        } => (type_url.clone(), value.clone(), *material_type),
+    }
+}
+
+fn status_from_proto(v: u64) -> Result<KeyStatus, CryptoError> {
+    match v {
+        1 => Ok(KeyStatus::Enabled),
+        2 => Ok(KeyStatus::Disabled),
+        3 => Ok(KeyStatus::Destroyed),
+        _ => Err(CryptoError::InvalidInput(format!(
+            "tink: unknown key status {}",
+            v
+        ))),
+    }
+}
+
+fn status_to_proto(status: KeyStatus) -> u64 {
+    match status {
+        KeyStatus::Enabled => 1,
+        KeyStatus::Disabled => 2,
+        KeyStatus::Destroyed => 3,
+    }
+}
+
+fn status_name(status: KeyStatus) -> &'static str {
+    match status {
+        KeyStatus::Enabled => "ENABLED",
+        KeyStatus::Disabled => "DISABLED",
+        KeyStatus::Destroyed => "DESTROYED",
+    }
+}
+
+fn status_from_name(name: &str) -> Result<KeyStatus, CryptoError> {
+    match name {
+        "ENABLED" => Ok(KeyStatus::Enabled),
+        "DISABLED" => Ok(KeyStatus::Disabled),
+        "DESTROYED" => Ok(KeyStatus::Destroyed),
+        _ => Err(CryptoError::InvalidInput(format!(
+            "tink: unknown key status {}",
+            name
+        ))),
+    }
+}
+
+fn prefix_from_proto(v: u64) -> Result<OutputPrefixType, CryptoError> {
+    match v {
+        1 => Ok(OutputPrefixType::Tink),
+        2 => Ok(OutputPrefixType::Legacy),
+        3 => Ok(OutputPrefixType::Raw),
+        4 => Ok(OutputPrefixType::Crunchy),
+        _ => Err(CryptoError::InvalidInput(format!(
+            "tink: unknown output prefix type {}",
+            v
+        ))),
+    }
+}
+
+fn prefix_to_proto(prefix: OutputPrefixType) -> u64 {
+    match prefix {
+        OutputPrefixType::Tink => 1,
+        OutputPrefixType::Legacy => 2,
+        OutputPrefixType::Raw => 3,
+        OutputPrefixType::Crunchy => 4,
+    }
+}
+
+fn prefix_name(prefix: OutputPrefixType) -> &'static str {
+    match prefix {
+        OutputPrefixType::Tink => "TINK",
+        OutputPrefixType::Legacy => "LEGACY",
+        OutputPrefixType::Raw => "RAW",
+        OutputPrefixType::Crunchy => "CRUNCHY",
+    }
+}
+
+fn prefix_from_name(name: &str) -> Result<OutputPrefixType, CryptoError> {
+    match name {
+        "TINK" => Ok(OutputPrefixType::Tink),
+        "LEGACY" => Ok(OutputPrefixType::Legacy),
+        "RAW" => Ok(OutputPrefixType::Raw),
+        "CRUNCHY" => Ok(OutputPrefixType::Crunchy),
+        _ => Err(CryptoError::InvalidInput(format!(
+            "tink: unknown output prefix type {}",
+            name
+        ))),
+    }
+}
+
+const MATERIAL_TYPE_NAMES: [&str; 5] = [
+    "UNKNOWN_KEYMATERIAL",
+    "SYMMETRIC",
+    "ASYMMETRIC_PRIVATE",
+    "ASYMMETRIC_PUBLIC",
+    "REMOTE",
+];
+
+fn material_type_name(v: u64) -> &'static str {
+    MATERIAL_TYPE_NAMES
+        .get(v as usize)
+        .copied()
+        .unwrap_or(MATERIAL_TYPE_NAMES[0])
+}
+
+fn material_type_from_name(name: &str) -> u64 {
+    MATERIAL_TYPE_NAMES
+        .iter()
+        .position(|n| *n == name)
+        .unwrap_or(0) as u64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonKeyset {
+    #[serde(default)]
+    primary_key_id: u32,
+    key: Vec<JsonKey>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonKey {
+    key_data: JsonKeyData,
+    status: String,
+    key_id: u32,
+    output_prefix_type: String,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonKeyData {
+    type_url: String,
+    value: String,
+    #[serde(default)]
+    key_material_type: String,
+}
+
+/// 密钥集用到的最小 protobuf 编解码
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_tink_keyset() {
+        // tinkey create-keyset --key-template AES128_GCM 输出格式的 JSON 密钥集
+        let json = r#"{
+            "primaryKeyId": 42818733,
+            "key": [{
+                "keyData": {
+                    "typeUrl": "type.googleapis.com/google.crypto.tink.AesGcmKey",
+                    "value": "GhCS/1+ejWpx68NfGt6ziYHd",
+                    "keyMaterialType": "SYMMETRIC"
+                },
+                "status": "ENABLED",
+                "keyId": 42818733,
+                "outputPrefixType": "TINK"
+            }]
+        }"#;
+        let keyset = Keyset::from_json(json).unwrap();
+        assert!(
+            keyset.keys[0].material
+                == KeyMaterial::AesGcm(
+                    Vec::from_hex("92ff5f9e8d6a71ebc35f1adeb38981dd")
+                        .unwrap()
+                        .into()
+                )
+        );
+        assert!(Keyset::from_json(&keyset.to_json()).unwrap() == keyset);
+        assert!(Keyset::from_binary(&keyset.to_binary()).unwrap() == keyset);
+
+        let ciphertext = keyset.aead_encrypt(b"hello", b"aad").unwrap();
+        assert_eq!(ciphertext[0], 1);
+        assert_eq!(&ciphertext[1..5], &42818733u32.to_be_bytes());
+        assert_eq!(keyset.aead_decrypt(&ciphertext, b"aad").unwrap(), b"hello");
+        assert!(keyset.aead_decrypt(&ciphertext, b"").is_err());
+
+        // 用另一个AEAD作为KMS主密钥
+        let master = [9u8; 32];
+        let encrypt = |plain: &[u8], ad: &[u8]| {
+            let nonce = [0u8; 12];
+            let mut in_out = plain.to_vec();
+            AES_256_GCM
+                .seal_in_place(&master, &nonce, ad, &mut in_out)
+                .map_err(CryptoError::InvalidKey)?;
+            Ok(in_out)
+        };
+        let decrypt = |cipher: &[u8], ad: &[u8]| {
+            let mut in_out = cipher.to_vec();
+            AES_256_GCM
+                .open_in_place(&master, &[0u8; 12], ad, &mut in_out)
+                .map_err(CryptoError::InvalidKey)?;
+            Ok(in_out)
+        };
+        let encrypted = keyset.to_encrypted(encrypt, b"").unwrap();
+        assert!(Keyset::from_encrypted(&encrypted, decrypt, b"").unwrap() == keyset);
+    }
+
+    #[test]
+    fn test_tink_mac_and_signature() {
+        let seed = vec![5u8; 32];
+        let (_, public_key) = crypto::ed25519::keypair(&seed);
+        let keyset = Keyset {
+            primary_key_id: 7,
+            keys: vec![
+                KeysetKey {
+                    key_id: 7,
+                    status: KeyStatus::Enabled,
+                    output_prefix: OutputPrefixType::Legacy,
+                    material: KeyMaterial::Ed25519Private {
+                        seed: seed.into(),
+                        public_key: public_key.to_vec(),
+                    },
+                },
+                KeysetKey {
+                    key_id: 8,
+                    status: KeyStatus::Enabled,
+                    output_prefix: OutputPrefixType::Raw,
+                    material: KeyMaterial::Hmac {
+                        hash: HmacHash::SHA256,
+                        tag_size: 16,
+                        key: vec![6u8; 32].into(),
+                    },
+                },
+            ],
+        };
+        let sig = keyset.sign(b"data").unwrap();
+        assert_eq!(sig.len(), PREFIX_SIZE + 64);
+        assert!(keyset.verify(&sig, b"data"));
+        assert!(!keyset.verify(&sig, b"other"));
+
+        assert!(keyset.public_keyset().is_err());
+        let public = Keyset {
+            primary_key_id: 7,
+            keys: keyset.keys[..1].to_vec(),
+        }
+        .public_keyset()
+        .unwrap();
+        assert!(public.verify(&sig, b"data"));
+        assert!(Keyset::from_binary(&public.to_binary()).unwrap() == public);
+
+        let mac_keyset = Keyset {
+            primary_key_id: 8,
+            keys: keyset.keys.clone(),
+        };
+        let tag = mac_keyset.compute_mac(b"data").unwrap();
+        assert_eq!(tag.len(), 16);
+        assert!(mac_keyset.verify_mac(&tag, b"data"));
+        assert!(!mac_keyset.verify_mac(&tag, b"other"));
+    }
+}
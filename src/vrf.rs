@@ -0,0 +1,197 @@
+//! 可验证随机函数 ECVRF-EDWARDS25519-SHA512-TAI (RFC 9381)
+//!
+//! 私钥持有者对输入 alpha 生成80字节的证明 pi = Gamma | c | s，任何人可以用公钥验证证明并得到
+//! 64字节的输出 beta。同一密钥和输入的输出唯一，不知道私钥无法预测输出。
+//! 密钥与 ed25519 签名的密钥格式相同，输入按 try-and-increment 映射到曲线上
+
+use crypto::curve25519::{sc_muladd, sc_reduce};
+use ring::digest;
+
+use crate::ct::wipe;
+use crate::ed25519::{EdPoint, SecretKey};
+use crate::error::{CryptoError, VerifyFailure};
+
+/// 证明的长度
+pub const PROOF_LEN: usize = 80;
+
+const SUITE: u8 = 0x03;
+
+/// 生成证明
+///
+/// sk: 私钥，32字节种子或64字节扩展格式
+/// alpha: 输入
+pub fn prove(sk: &[u8], alpha: &[u8]) -> Result<[u8; PROOF_LEN], CryptoError> {
+    let key = SecretKey::from_bytes(sk)?;
+    let h = digest::digest(&digest::SHA512, &key.seed());
+    let mut x = [0u8; 32];
+    x.copy_from_slice(&h.as_ref()[..32]);
+    x[0] &= 248;
+    x[31] &= 63;
+    x[31] |= 64;
+    let pk = key.public_key();
+    let point_h = encode_to_curve(&pk, alpha)
+        .ok_or_else(|| CryptoError::OperationFailed("vrf: encode to curve failed".to_string()))?;
+    let h_string = point_h.compress();
+    let gamma = point_h.mul(&x);
+    // RFC 8032 方式的确定性 nonce
+    let mut k = sha512(&[&h.as_ref()[32..], &h_string]);
+    sc_reduce(&mut k);
+    let mut k32 = [0u8; 32];
+    k32.copy_from_slice(&k[..32]);
+    let u = EdPoint::base().mul(&k32);
+    let v = point_h.mul(&k32);
+    let c = challenge(
+        &pk,
+        &h_string,
+        &gamma.compress(),
+        &u.compress(),
+        &v.compress(),
+    );
+    let mut c32 = [0u8; 32];
+    c32[..16].copy_from_slice(&c);
+    let mut proof = [0u8; PROOF_LEN];
+    proof[..32].copy_from_slice(&gamma.compress());
+    proof[32..48].copy_from_slice(&c);
+    sc_muladd(&mut proof[48..], &c32, &x, &k32);
+    wipe(&mut x);
+    wipe(&mut k);
+    Ok(proof)
+}
+
+/// 验证证明，成功时返回输出 beta
+///
+/// pk: 32字节公钥，拒绝小阶点
+/// alpha: 输入
+/// proof: 80字节证明
+pub fn verify(pk: &[u8], alpha: &[u8], proof: &[u8]) -> Result<[u8; 64], VerifyFailure> {
+    if pk.len() != 32 {
+        return Err(VerifyFailure::WrongLength {
+            expected: 32,
+            actual: pk.len(),
+        });
+    }
+    let y = match EdPoint::decompress(pk) {
+        Some(y) if !y.mul_by_cofactor().is_identity() => y,
+        _ => return Err(VerifyFailure::MalformedKey),
+    };
+    let (gamma, c, s) = decode_proof(proof)?;
+    let point_h = encode_to_curve(pk, alpha).ok_or(VerifyFailure::SignatureInvalid)?;
+    let mut c32 = [0u8; 32];
+    c32[..16].copy_from_slice(&c);
+    // U = s*B - c*Y，V = s*H - c*Gamma，都是公开数据，可以使用变时间运算
+    let u = EdPoint::multi_mul(&[(s, EdPoint::base()), (c32, y.neg())]);
+    let v = EdPoint::multi_mul(&[(s, point_h), (c32, gamma.neg())]);
+    let expected = challenge(
+        pk,
+        &point_h.compress(),
+        &gamma.compress(),
+        &u.compress(),
+        &v.compress(),
+    );
+    if expected != c {
+        return Err(VerifyFailure::SignatureInvalid);
+    }
+    Ok(hash_gamma(&gamma))
+}
+
+/// 由证明计算输出 beta，不验证证明，只能用于已验证过的证明
+pub fn proof_to_hash(proof: &[u8]) -> Result<[u8; 64], CryptoError> {
+    let (gamma, _, _) = decode_proof(proof)?;
+    Ok(hash_gamma(&gamma))
+}
+
+fn decode_proof(proof: &[u8]) -> Result<(EdPoint, [u8; 16], [u8; 32]), VerifyFailure> {
+    if proof.len() != PROOF_LEN {
+        return Err(VerifyFailure::WrongLength {
+            expected: PROOF_LEN,
+            actual: proof.len(),
+        });
+    }
+    let gamma = EdPoint::decompress(&proof[..32]).ok_or(VerifyFailure::MalformedSignature)?;
+    let mut c = [0u8; 16];
+    c.copy_from_slice(&proof[32..48]);
+    let mut s = [0u8; 32];
+    s.copy_from_slice(&proof[48..]);
+    // s 必须小于群的阶 L
+    let mut wide = [0u8; 64];
+    wide[..32].copy_from_slice(&s);
+    sc_reduce(&mut wide);
+    if wide[..32] != s {
+        return Err(VerifyFailure::MalformedSignature);
+    }
+    Ok((gamma, c, s))
+}
+
+// RFC 9381 5.4.1.1，计数器从0开始，取哈希的前32字节解码为点，再乘以余因子
+fn encode_to_curve(pk: &[u8], alpha: &[u8]) -> Option<EdPoint> {
+    (0..=255u8).find_map(|ctr| {
+        let hash = sha512(&[&[SUITE, 0x01], pk, alpha, &[ctr, 0x00]]);
+        EdPoint::decompress(&hash[..32]).map(|p| p.mul_by_cofactor())
+    })
+}
+
+// RFC 9381 5.4.3，取哈希的前16字节
+fn challenge(pk: &[u8], h: &[u8], gamma: &[u8], u: &[u8], v: &[u8]) -> [u8; 16] {
+    let hash = sha512(&[&[SUITE, 0x02], pk, h, gamma, u, v, &[0x00]]);
+    let mut c = [0u8; 16];
+    c.copy_from_slice(&hash[..16]);
+    c
+}
+
+fn hash_gamma(gamma: &EdPoint) -> [u8; 64] {
+    sha512(&[&[SUITE, 0x03], &gamma.mul_by_cofactor().compress(), &[0x00]])
+}
+
+fn sha512(parts: &[&[u8]]) -> [u8; 64] {
+    let mut ctx = digest::Context::new(&digest::SHA512);
+    for part in parts {
+        ctx.update(part);
+    }
+    let mut out = [0u8; 64];
+    out.copy_from_slice(ctx.finish().as_ref());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    #[test]
+    fn test_vrf() {
+        // RFC 9381 A.3 Example 16
+        let sk = Vec::from_hex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60")
+            .unwrap();
+        let pk = Vec::from_hex("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a")
+            .unwrap();
+        let proof = prove(&sk, b"").unwrap();
+        assert_eq!(proof.to_vec(), Vec::from_hex("8657106690b5526245a92b003bb079ccd1a92130477671f6fc01ad16f26f723f26f8a57ccaed74ee1b190bed1f479d9727d2d0f9b005a6e456a35d4fb0daab1268a1b0db10836d9826a528ca76567805").unwrap());
+        let beta = Vec::from_hex("90cf1df3b703cce59e2a35b925d411164068269d7b2d29f3301c03dd757876ff66b71dda49d2de59d03450451af026798e8f81cd2e333de5cdf4f3e140fdd8ae").unwrap();
+        assert_eq!(verify(&pk, b"", &proof).unwrap().to_vec(), beta);
+        assert_eq!(proof_to_hash(&proof).unwrap().to_vec(), beta);
+
+        assert_eq!(
+            verify(&pk, b"x", &proof),
+            Err(VerifyFailure::SignatureInvalid)
+        );
+        let mut bad = proof;
+        bad[40] ^= 1;
+        assert!(verify(&pk, b"", &bad).is_err());
+        // s >= L
+        let mut bad = proof;
+        bad[79] = 0xff;
+        assert_eq!(
+            verify(&pk, b"", &bad),
+            Err(VerifyFailure::MalformedSignature)
+        );
+        assert!(verify(&pk, b"", &proof[..79]).is_err());
+        let mut identity = [0u8; 32];
+        identity[0] = 1;
+        assert_eq!(
+            verify(&identity, b"", &proof),
+            Err(VerifyFailure::MalformedKey)
+        );
+        let other = prove(&[7u8; 32], b"").unwrap();
+        assert_ne!(proof_to_hash(&other).unwrap().to_vec(), beta);
+    }
+}
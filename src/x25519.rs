@@ -0,0 +1,166 @@
+//! X25519 标量乘法原语
+//!
+//! 提供 RFC 7748 定义的 Curve25519 蒙哥马利曲线标量乘法，供需要直接使用原语的协议实现，
+//! 标量在运算前按 RFC 7748 进行钳位。
+//!
+//! generate_keypair 和 diffie_hellman 用于密钥协商，ed25519 的密钥可以转换为 X25519 密钥，
+//! 同一身份密钥同时用于签名和密钥协商
+
+use crypto::curve25519::{curve25519, curve25519_base};
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use ring::digest;
+
+use crate::ct::ct_eq;
+use crate::random::{OsRng, SecureRandom};
+
+/// 标量、u坐标和输出的长度
+pub const KEY_SIZE: usize = 32;
+
+/// 标准基点 u = 9
+pub const BASEPOINT: [u8; KEY_SIZE] = [
+    9, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
+
+/// 标量与基点相乘，即由私钥计算公钥
+///
+/// scalar: 32字节标量
+pub fn scalarmult_base(scalar: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    curve25519_base(scalar)
+}
+
+/// 标量与任意点相乘
+///
+/// 输出全零说明对端给出的是小阶点，此时返回错误，调用方不应继续使用该结果
+/// scalar: 32字节标量
+/// point: 32字节u坐标
+pub fn scalarmult(
+    scalar: &[u8; KEY_SIZE],
+    point: &[u8; KEY_SIZE],
+) -> Result<[u8; KEY_SIZE], String> {
+    let output = curve25519(scalar, point);
+    if ct_eq(&output, &[0u8; KEY_SIZE]) {
+        return Err("x25519: all-zero output, point has low order".to_string());
+    }
+    Ok(output)
+}
+
+/// 生成随机密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair() -> ([u8; KEY_SIZE], [u8; KEY_SIZE]) {
+    generate_keypair_with_rng(&OsRng)
+}
+
+/// 使用指定的随机数生成器生成密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair_with_rng(rng: &dyn SecureRandom) -> ([u8; KEY_SIZE], [u8; KEY_SIZE]) {
+    let mut secret = [0u8; KEY_SIZE];
+    rng.fill(&mut secret);
+    (secret, scalarmult_base(&secret))
+}
+
+/// X25519 密钥协商，返回32字节共享密钥
+///
+/// 共享密钥不是均匀分布的，应经过 kdf 模块的 HKDF 等派生后再作为密钥使用
+/// secret_key: 本地私钥
+/// peer_public_key: 对端公钥，小阶点时返回错误
+pub fn diffie_hellman(
+    secret_key: &[u8; KEY_SIZE],
+    peer_public_key: &[u8; KEY_SIZE],
+) -> Result<[u8; KEY_SIZE], String> {
+    scalarmult(secret_key, peer_public_key)
+}
+
+/// 由 ed25519 的32字节种子得到 X25519 私钥，即 SHA512(seed) 的前32字节
+pub fn secret_from_ed25519(seed: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let hash = digest::digest(&digest::SHA512, seed);
+    let mut secret = [0u8; KEY_SIZE];
+    secret.copy_from_slice(&hash.as_ref()[..KEY_SIZE]);
+    secret
+}
+
+/// 由 ed25519 公钥得到 X25519 公钥，u = (1 + y) / (1 - y)
+///
+/// 只做坐标转换，不检查公钥是否为曲线上的点
+pub fn public_from_ed25519(public_key: &[u8; KEY_SIZE]) -> Result<[u8; KEY_SIZE], String> {
+    let p = (BigUint::one() << 255) - BigUint::from(19u32);
+    let mut y = *public_key;
+    // 最高位是 x 的符号位
+    y[31] &= 0x7f;
+    let y = BigUint::from_bytes_le(&y);
+    if y >= p {
+        return Err("x25519: ed25519 public key is not canonical".to_string());
+    }
+    let denominator = (&p + BigUint::one() - &y) % &p;
+    if denominator.is_zero() {
+        return Err("x25519: ed25519 public key is the identity".to_string());
+    }
+    let u = ((BigUint::one() + &y) * denominator.modpow(&(&p - BigUint::from(2u32)), &p)) % &p;
+    let bytes = u.to_bytes_le();
+    let mut out = [0u8; KEY_SIZE];
+    out[..bytes.len()].copy_from_slice(&bytes);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    fn key(s: &str) -> [u8; KEY_SIZE] {
+        let mut k = [0u8; KEY_SIZE];
+        k.copy_from_slice(&Vec::from_hex(s).unwrap());
+        k
+    }
+
+    #[test]
+    fn test_scalarmult() {
+        // test vectors from: https://tools.ietf.org/html/rfc7748#section-5.2
+        assert_eq!(
+            scalarmult(
+                &key("a546e36bf0527c9d3b16154b82465edd62144c0ac1fc5a18506a2244ba449ac4"),
+                &key("e6db6867583030db3594c1a424b15f7c726624ec26b3353b10a903a6d0ab1c4c")
+            )
+            .unwrap(),
+            key("c3da55379de9c6908e94ea4df28d084f32eccf03491c71f754b4075577a28552")
+        );
+
+        // https://tools.ietf.org/html/rfc7748#section-6.1
+        let alice = key("77076d0a7318a57d3c16c17251b26645df4c2f87ebc0992ab177fba51db92c2a");
+        let bob = key("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb");
+        assert_eq!(
+            scalarmult_base(&alice),
+            key("8520f0098930a754748b7ddcb43ef75a0dbf3a0d26381af4eba4a98eaa9b4e6a")
+        );
+        assert_eq!(
+            scalarmult_base(&alice),
+            scalarmult(&alice, &BASEPOINT).unwrap()
+        );
+        assert_eq!(
+            scalarmult(&alice, &scalarmult_base(&bob)).unwrap(),
+            key("4a5d9d5ba4ce2de1728e3bf480350f25e07e21c947d19e3376f09b3c1e161742")
+        );
+
+        assert!(scalarmult(&alice, &[0u8; KEY_SIZE]).is_err());
+    }
+
+    #[test]
+    fn test_diffie_hellman() {
+        let (a_sk, a_pk) = generate_keypair();
+        let (b_sk, b_pk) = generate_keypair();
+        assert_eq!(
+            diffie_hellman(&a_sk, &b_pk).unwrap(),
+            diffie_hellman(&b_sk, &a_pk).unwrap()
+        );
+
+        // RFC 8032 test 1 的 ed25519 密钥
+        let seed = key("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+        let ed_pk = key("d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a");
+        let secret = secret_from_ed25519(&seed);
+        assert_eq!(
+            public_from_ed25519(&ed_pk).unwrap(),
+            scalarmult_base(&secret)
+        );
+        let mut identity = [0u8; KEY_SIZE];
+        identity[0] = 1;
+        assert!(public_from_ed25519(&identity).is_err());
+    }
+}
@@ -0,0 +1,154 @@
+//! X448 标量乘法原语
+//!
+//! 提供 RFC 7748 定义的 Curve448 蒙哥马利曲线标量乘法和密钥协商，接口与 x25519 模块一致，
+//! 标量、u坐标和输出均为56字节小端序，标量在运算前按 RFC 7748 进行钳位。
+//!
+//! 域运算使用 field448 模块的常量时间实现，阶梯的交换为按掩码的条件交换
+
+use crate::ct::ct_eq;
+use crate::error::CryptoError;
+use crate::field448::Fe;
+use crate::random::{OsRng, SecureRandom};
+
+/// 标量、u坐标和输出的长度
+pub const KEY_SIZE: usize = 56;
+
+/// 标准基点 u = 5
+pub const BASEPOINT: [u8; KEY_SIZE] = {
+    let mut u = [0u8; KEY_SIZE];
+    u[0] = 5;
+    u
+};
+
+/// 标量与基点相乘，即由私钥计算公钥
+///
+/// scalar: 56字节标量
+pub fn scalarmult_base(scalar: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    ladder(scalar, &BASEPOINT)
+}
+
+/// 标量与任意点相乘
+///
+/// 输出全零说明对端给出的是小阶点，此时返回错误，调用方不应继续使用该结果
+/// scalar: 56字节标量
+/// point: 56字节u坐标
+pub fn scalarmult(
+    scalar: &[u8; KEY_SIZE],
+    point: &[u8; KEY_SIZE],
+) -> Result<[u8; KEY_SIZE], CryptoError> {
+    let output = ladder(scalar, point);
+    if ct_eq(&output, &[0u8; KEY_SIZE]) {
+        return Err(CryptoError::InvalidKey(
+            "x448: all-zero output, point has low order".to_string(),
+        ));
+    }
+    Ok(output)
+}
+
+/// 生成随机密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair() -> ([u8; KEY_SIZE], [u8; KEY_SIZE]) {
+    generate_keypair_with_rng(&OsRng)
+}
+
+/// 使用指定的随机数生成器生成密钥对，返回 (私钥, 公钥)
+pub fn generate_keypair_with_rng(rng: &dyn SecureRandom) -> ([u8; KEY_SIZE], [u8; KEY_SIZE]) {
+    let mut secret = [0u8; KEY_SIZE];
+    rng.fill(&mut secret);
+    (secret, scalarmult_base(&secret))
+}
+
+/// X448 密钥协商，返回56字节共享密钥
+///
+/// 共享密钥不是均匀分布的，应经过 kdf 模块的 HKDF 等派生后再作为密钥使用
+/// secret_key: 本地私钥
+/// peer_public_key: 对端公钥，小阶点时返回错误
+pub fn diffie_hellman(
+    secret_key: &[u8; KEY_SIZE],
+    peer_public_key: &[u8; KEY_SIZE],
+) -> Result<[u8; KEY_SIZE], CryptoError> {
+    scalarmult(secret_key, peer_public_key)
+}
+
+// RFC 7748 5 的蒙哥马利阶梯，p = 2^448 - 2^224 - 1，a24 = 39081
+fn ladder(scalar: &[u8; KEY_SIZE], point: &[u8; KEY_SIZE]) -> [u8; KEY_SIZE] {
+    let a24 = Fe::from_u64(39081);
+
+    let mut k = *scalar;
+    k[0] &= 252;
+    k[55] |= 128;
+    let x1 = Fe::from_bytes(point);
+
+    let (mut x2, mut z2) = (Fe::ONE, Fe::ZERO);
+    let (mut x3, mut z3) = (x1, Fe::ONE);
+    let mut swap = 0u64;
+    for t in (0..448).rev() {
+        let bit = u64::from((k[t / 8] >> (t % 8)) & 1);
+        swap ^= bit;
+        Fe::cswap(&mut x2, &mut x3, swap);
+        Fe::cswap(&mut z2, &mut z3, swap);
+        swap = bit;
+
+        let a = x2 + z2;
+        let aa = a.square();
+        let b = x2 - z2;
+        let bb = b.square();
+        let e = aa - bb;
+        let c = x3 + z3;
+        let d = x3 - z3;
+        let da = d * a;
+        let cb = c * b;
+        x3 = (da + cb).square();
+        z3 = x1 * (da - cb).square();
+        x2 = aa * bb;
+        z2 = e * (aa + a24 * e);
+    }
+    Fe::cswap(&mut x2, &mut x3, swap);
+    Fe::cswap(&mut z2, &mut z3, swap);
+
+    (x2 * z2.invert()).to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex::FromHex;
+
+    fn key(s: &str) -> [u8; KEY_SIZE] {
+        let mut k = [0u8; KEY_SIZE];
+        k.copy_from_slice(&Vec::from_hex(s).unwrap());
+        k
+    }
+
+    #[test]
+    fn test_scalarmult() {
+        // test vectors from: https://tools.ietf.org/html/rfc7748#section-5.2
+        assert_eq!(
+            scalarmult(
+                &key("3d262fddf9ec8e88495266fea19a34d28882acef045104d0d1aae121700a779c984c24f8cdd78fbff44943eba368f54b29259a4f1c600ad3"),
+                &key("06fce640fa3487bfda5f6cf2d5263f8aad88334cbd07437f020f08f9814dc031ddbdc38c19c6da2583fa5429db94ada18aa7a7fb4ef8a086")
+            )
+            .unwrap()
+            .to_vec(),
+            Vec::from_hex("ce3e4ff95a60dc6697da1db1d85e6afbdf79b50a2412d7546d5f239fe14fbaadeb445fc66a01b0779d98223961111e21766282f73dd96b6f").unwrap()
+        );
+
+        // RFC 7748 6.2
+        let alice = key("9a8f4925d1519f5775cf46b04b5800d4ee9ee8bae8bc5565d498c28dd9c9baf574a9419744897391006382a6f127ab1d9ac2d8c0a598726b");
+        let bob = key("1c306a7ac2a0e2e0990b294470cba339e6453772b075811d8fad0d1d6927c120bb5ee8972b0d3e21374c9c921b09d1b0366f10b65173992d");
+        let alice_public = scalarmult_base(&alice);
+        assert_eq!(
+            alice_public.to_vec(),
+            Vec::from_hex("9b08f7cc31b7e3e67d22d5aea121074a273bd2b83de09c63faa73d2c22c5d9bbc836647241d953d40c5b12da88120d53177f80e532c41fa0").unwrap()
+        );
+        let shared = diffie_hellman(&bob, &alice_public).unwrap();
+        assert_eq!(
+            shared.to_vec(),
+            Vec::from_hex("07fff4181ac6cc95ec1c16a94a0f74d12da232ce40a77552281d282bb60c0b56fd2464c335543936521c24403085d59a449a5037514a879d").unwrap()
+        );
+        assert_eq!(
+            diffie_hellman(&alice, &scalarmult_base(&bob)).unwrap(),
+            shared
+        );
+        assert!(scalarmult(&alice, &[0u8; KEY_SIZE]).is_err());
+    }
+}
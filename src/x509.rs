@@ -0,0 +1,584 @@
+//! X.509 证书解析和证书链验证 (RFC 5280)
+//!
+//! 只解析验证签名链需要的字段：签名算法、颁发者和主体名称、有效期、SubjectPublicKeyInfo、
+//! basicConstraints 和 keyUsage。签名支持 RSA PKCS#1 v1.5 (SHA256/384/512)、
+//! ECDSA P-256/SHA256、P-384/SHA384、Ed25519 和 Ed448。名称按 DER 编码逐字节比较，不检查吊销状态，
+//! 主机名等用途检查由调用方完成
+
+use crate::ed25519;
+use crate::ed448;
+use crate::error::{CryptoError, VerifyFailure};
+use crate::pem;
+use crate::signature::{ecdsa_verify_detailed, EcdsaAlg, PaddingAlg, Rsa};
+use crate::spki::{decode_spki, PublicKeyType};
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_BIT_STRING: u8 = 0x03;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_OID: u8 = 0x06;
+const TAG_UTC_TIME: u8 = 0x17;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+const TAG_VERSION: u8 = 0xa0;
+const TAG_EXTENSIONS: u8 = 0xa3;
+
+const OID_RSA_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0b];
+const OID_RSA_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0c];
+const OID_RSA_SHA512: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x0d];
+const OID_ECDSA_SHA256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x02];
+const OID_ECDSA_SHA384: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x04, 0x03, 0x03];
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+const OID_ED448: &[u8] = &[0x2b, 0x65, 0x71];
+const OID_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x0f];
+const OID_SUBJECT_ALT_NAME: &[u8] = &[0x55, 0x1d, 0x11];
+const OID_BASIC_CONSTRAINTS: &[u8] = &[0x55, 0x1d, 0x13];
+const OID_EXT_KEY_USAGE: &[u8] = &[0x55, 0x1d, 0x25];
+
+/// 证书的签名算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    RsaSha256,
+    RsaSha384,
+    RsaSha512,
+    EcdsaSha256,
+    EcdsaSha384,
+    Ed25519,
+    Ed448,
+}
+
+/// 解析后的证书
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    der: Vec<u8>,
+    tbs: Vec<u8>,
+    signature_algorithm: SignatureAlgorithm,
+    signature: Vec<u8>,
+    serial: Vec<u8>,
+    issuer: Vec<u8>,
+    subject: Vec<u8>,
+    not_before: u64,
+    not_after: u64,
+    spki: Vec<u8>,
+    is_ca: bool,
+    path_len: Option<u64>,
+    key_cert_sign: bool,
+    unknown_critical: bool,
+}
+
+impl Certificate {
+    /// 解析 DER 编码的证书
+    pub fn from_der(der: &[u8]) -> Result<Self, CryptoError> {
+        let mut outer = Reader::new(der);
+        let cert = outer.expect(TAG_SEQUENCE)?.0;
+        outer.finish()?;
+        let mut r = Reader::new(cert);
+        let (tbs_content, tbs) = r.expect(TAG_SEQUENCE)?;
+        let (alg_content, alg) = r.expect(TAG_SEQUENCE)?;
+        let signature = bit_string(r.expect(TAG_BIT_STRING)?.0)?;
+        r.finish()?;
+
+        let mut t = Reader::new(tbs_content);
+        if t.peek() == Some(TAG_VERSION) {
+            t.expect(TAG_VERSION)?;
+        }
+        let serial = t.expect(TAG_INTEGER)?.0;
+        // TBSCertificate 中的签名算法必须与外层相同
+        if t.expect(TAG_SEQUENCE)?.1 != alg {
+            return Err(CryptoError::InvalidInput(
+                "x509: signature algorithm mismatch".to_string(),
+            ));
+        }
+        let issuer = t.expect(TAG_SEQUENCE)?.1;
+        let mut validity = Reader::new(t.expect(TAG_SEQUENCE)?.0);
+        let not_before = validity.time()?;
+        let not_after = validity.time()?;
+        validity.finish()?;
+        let subject = t.expect(TAG_SEQUENCE)?.1;
+        let spki = t.expect(TAG_SEQUENCE)?.1;
+        // 跳过 issuerUniqueID 和 subjectUniqueID
+        while matches!(t.peek(), Some(0x81 | 0x82 | 0xa1 | 0xa2)) {
+            t.read()?;
+        }
+        let mut cert = Certificate {
+            der: der.to_vec(),
+            tbs: tbs.to_vec(),
+            signature_algorithm: signature_algorithm(alg_content)?,
+            signature: signature.to_vec(),
+            serial: serial.to_vec(),
+            issuer: issuer.to_vec(),
+            subject: subject.to_vec(),
+            not_before,
+            not_after,
+            spki: spki.to_vec(),
+            is_ca: false,
+            path_len: None,
+            key_cert_sign: true,
+            unknown_critical: false,
+        };
+        if t.peek() == Some(TAG_EXTENSIONS) {
+            let mut explicit = Reader::new(t.expect(TAG_EXTENSIONS)?.0);
+            cert.parse_extensions(explicit.expect(TAG_SEQUENCE)?.0)?;
+            explicit.finish()?;
+        }
+        t.finish()?;
+        Ok(cert)
+    }
+
+    /// 解析文本中的第一个 CERTIFICATE PEM 块
+    pub fn from_pem(text: &str) -> Result<Self, CryptoError> {
+        parse_pem_chain(text)?.into_iter().next().ok_or_else(|| {
+            CryptoError::InvalidInput("x509: no CERTIFICATE block found".to_string())
+        })
+    }
+
+    /// 证书的 DER 编码
+    pub fn der(&self) -> &[u8] {
+        &self.der
+    }
+
+    /// 序列号，大端编码的 INTEGER 内容
+    pub fn serial(&self) -> &[u8] {
+        &self.serial
+    }
+
+    /// 颁发者名称的 DER 编码
+    pub fn issuer(&self) -> &[u8] {
+        &self.issuer
+    }
+
+    /// 主体名称的 DER 编码
+    pub fn subject(&self) -> &[u8] {
+        &self.subject
+    }
+
+    /// 生效时间，Unix 时间，1970年之前为0
+    pub fn not_before(&self) -> u64 {
+        self.not_before
+    }
+
+    /// 过期时间，Unix 时间
+    pub fn not_after(&self) -> u64 {
+        self.not_after
+    }
+
+    /// 证书在 unix_time 时是否在有效期内
+    pub fn is_valid_at(&self, unix_time: u64) -> bool {
+        self.not_before <= unix_time && unix_time <= self.not_after
+    }
+
+    /// SubjectPublicKeyInfo 的 DER 编码
+    pub fn spki(&self) -> &[u8] {
+        &self.spki
+    }
+
+    /// 证书中的公钥类型和原始公钥
+    pub fn public_key(&self) -> Result<(PublicKeyType, Vec<u8>), CryptoError> {
+        decode_spki(&self.spki).map_err(CryptoError::InvalidInput)
+    }
+
+    /// 签名算法
+    pub fn signature_algorithm(&self) -> SignatureAlgorithm {
+        self.signature_algorithm
+    }
+
+    /// basicConstraints 中是否标记为 CA
+    pub fn is_ca(&self) -> bool {
+        self.is_ca
+    }
+
+    /// 用 issuer 证书的公钥验证本证书的签名，不检查名称和有效期
+    pub fn verify_signed_by(&self, issuer: &Certificate) -> Result<(), VerifyFailure> {
+        let (key_type, key) = issuer
+            .public_key()
+            .map_err(|_| VerifyFailure::MalformedKey)?;
+        let (tbs, sig) = (&self.tbs[..], &self.signature[..]);
+        let rsa = |alg| Rsa::verify_detailed(alg, tbs, sig, &key);
+        match (self.signature_algorithm, key_type) {
+            (SignatureAlgorithm::RsaSha256, PublicKeyType::Rsa) => {
+                rsa(PaddingAlg::RSA_PKCS1_SHA256)
+            }
+            (SignatureAlgorithm::RsaSha384, PublicKeyType::Rsa) => {
+                rsa(PaddingAlg::RSA_PKCS1_SHA384)
+            }
+            (SignatureAlgorithm::RsaSha512, PublicKeyType::Rsa) => {
+                rsa(PaddingAlg::RSA_PKCS1_SHA512)
+            }
+            (SignatureAlgorithm::EcdsaSha256, PublicKeyType::EcdsaP256) => {
+                ecdsa_verify_detailed(EcdsaAlg::ECDSA_P256_SHA256_ASN1, &key, tbs, sig)
+            }
+            (SignatureAlgorithm::EcdsaSha384, PublicKeyType::EcdsaP384) => {
+                ecdsa_verify_detailed(EcdsaAlg::ECDSA_P384_SHA384_ASN1, &key, tbs, sig)
+            }
+            (SignatureAlgorithm::Ed25519, PublicKeyType::Ed25519) => {
+                ed25519::verify_detailed(tbs, &key, sig)
+            }
+            (SignatureAlgorithm::Ed448, PublicKeyType::Ed448) => {
+                ed448::verify_detailed(tbs, &key, sig)
+            }
+            _ => Err(VerifyFailure::MalformedKey),
+        }
+    }
+
+    fn parse_extensions(&mut self, data: &[u8]) -> Result<(), CryptoError> {
+        let mut exts = Reader::new(data);
+        while !exts.is_empty() {
+            let mut ext = Reader::new(exts.expect(TAG_SEQUENCE)?.0);
+            let id = ext.expect(TAG_OID)?.0;
+            let critical = if ext.peek() == Some(TAG_BOOLEAN) {
+                boolean(ext.expect(TAG_BOOLEAN)?.0)?
+            } else {
+                false
+            };
+            let value = ext.expect(TAG_OCTET_STRING)?.0;
+            ext.finish()?;
+            match id {
+                OID_BASIC_CONSTRAINTS => {
+                    let mut outer = Reader::new(value);
+                    let mut bc = Reader::new(outer.expect(TAG_SEQUENCE)?.0);
+                    outer.finish()?;
+                    if bc.peek() == Some(TAG_BOOLEAN) {
+                        self.is_ca = boolean(bc.expect(TAG_BOOLEAN)?.0)?;
+                    }
+                    if bc.peek() == Some(TAG_INTEGER) {
+                        self.path_len = Some(small_integer(bc.expect(TAG_INTEGER)?.0)?);
+                    }
+                    bc.finish()?;
+                }
+                OID_KEY_USAGE => {
+                    let mut outer = Reader::new(value);
+                    let bits = outer.expect(TAG_BIT_STRING)?.0;
+                    outer.finish()?;
+                    // keyCertSign 是第5位
+                    self.key_cert_sign = matches!(bits, [_, first, ..] if first & 0x04 != 0);
+                }
+                OID_SUBJECT_ALT_NAME | OID_EXT_KEY_USAGE => {}
+                _ => self.unknown_critical |= critical,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 解析文本中的所有 CERTIFICATE PEM 块，保持原有顺序
+pub fn parse_pem_chain(text: &str) -> Result<Vec<Certificate>, CryptoError> {
+    pem::decode_all(text)?
+        .into_iter()
+        .filter(|block| block.label == "CERTIFICATE")
+        .map(|block| Certificate::from_der(&block.contents))
+        .collect()
+}
+
+/// 验证证书链
+///
+/// chain: 叶子证书在前，之后依次是签发前一个证书的中间证书，可以不包含根证书
+/// roots: 信任的根证书，链中的证书与其中之一相同或由其中之一签发时验证成功
+/// unix_time: 检查有效期使用的 Unix 时间
+///
+/// 中间证书必须是 CA 且允许签发证书，满足 pathLenConstraint；链中证书有无法识别的关键扩展时失败
+pub fn verify_chain(
+    chain: &[Certificate],
+    roots: &[Certificate],
+    unix_time: u64,
+) -> Result<(), CryptoError> {
+    for (i, cert) in chain.iter().enumerate() {
+        check_validity(cert, i, unix_time)?;
+        if cert.unknown_critical {
+            return Err(CryptoError::InvalidInput(format!(
+                "x509: certificate {} has an unrecognized critical extension",
+                i
+            )));
+        }
+        if roots.iter().any(|root| root.der == cert.der) {
+            return Ok(());
+        }
+        let issuer = match chain.get(i + 1) {
+            Some(issuer) => {
+                if issuer.subject != cert.issuer {
+                    return Err(CryptoError::InvalidInput(format!(
+                        "x509: certificate {} issuer name mismatch",
+                        i
+                    )));
+                }
+                if !issuer.is_ca || !issuer.key_cert_sign {
+                    return Err(CryptoError::InvalidInput(format!(
+                        "x509: certificate {} is not a CA",
+                        i + 1
+                    )));
+                }
+                issuer
+            }
+            None => roots
+                .iter()
+                .find(|root| root.subject == cert.issuer && cert.verify_signed_by(root).is_ok())
+                .ok_or_else(|| {
+                    CryptoError::InvalidInput(format!(
+                        "x509: certificate {} is not issued by a trusted root",
+                        i
+                    ))
+                })?,
+        };
+        // i 为 issuer 之下中间证书的个数
+        if matches!(issuer.path_len, Some(n) if (i as u64) > n) {
+            return Err(CryptoError::InvalidInput(format!(
+                "x509: certificate {} path length exceeded",
+                i + 1
+            )));
+        }
+        cert.verify_signed_by(issuer)
+            .map_err(|e| CryptoError::InvalidInput(format!("x509: certificate {} {}", i, e)))?;
+        if i + 1 == chain.len() {
+            return check_validity(issuer, i + 1, unix_time);
+        }
+    }
+    Err(CryptoError::InvalidInput("x509: empty chain".to_string()))
+}
+
+fn check_validity(cert: &Certificate, index: usize, unix_time: u64) -> Result<(), CryptoError> {
+    if !cert.is_valid_at(unix_time) {
+        return Err(CryptoError::InvalidInput(format!(
+            "x509: certificate {} is not valid at {}",
+            index, unix_time
+        )));
+    }
+    Ok(())
+}
+
+fn signature_algorithm(data: &[u8]) -> Result<SignatureAlgorithm, CryptoError> {
+    let mut r = Reader::new(data);
+    let id = r.expect(TAG_OID)?.0;
+    let params = r.rest();
+    // RSA 的参数为 NULL，ECDSA、Ed25519 和 Ed448 没有参数
+    let alg = match (id, params) {
+        (OID_RSA_SHA256, [0x05, 0x00]) => SignatureAlgorithm::RsaSha256,
+        (OID_RSA_SHA384, [0x05, 0x00]) => SignatureAlgorithm::RsaSha384,
+        (OID_RSA_SHA512, [0x05, 0x00]) => SignatureAlgorithm::RsaSha512,
+        (OID_ECDSA_SHA256, []) => SignatureAlgorithm::EcdsaSha256,
+        (OID_ECDSA_SHA384, []) => SignatureAlgorithm::EcdsaSha384,
+        (OID_ED25519, []) => SignatureAlgorithm::Ed25519,
+        (OID_ED448, []) => SignatureAlgorithm::Ed448,
+        _ => {
+            return Err(CryptoError::InvalidInput(
+                "x509: unsupported signature algorithm".to_string(),
+            ))
+        }
+    };
+    Ok(alg)
+}
+
+fn bit_string(data: &[u8]) -> Result<&[u8], CryptoError> {
+    match data {
+        [0, rest @ ..] => Ok(rest),
+        _ => Err(CryptoError::InvalidInput(
+            "x509: malformed bit string".to_string(),
+        )),
+    }
+}
+
+fn boolean(data: &[u8]) -> Result<bool, CryptoError> {
+    match data {
+        [0x00] => Ok(false),
+        [0xff] => Ok(true),
+        _ => Err(CryptoError::InvalidInput(
+            "x509: malformed boolean".to_string(),
+        )),
+    }
+}
+
+fn small_integer(data: &[u8]) -> Result<u64, CryptoError> {
+    if data.is_empty() || data.len() > 8 || data[0] & 0x80 != 0 {
+        return Err(CryptoError::InvalidInput(
+            "x509: malformed integer".to_string(),
+        ));
+    }
+    Ok(data.iter().fold(0, |acc, b| acc << 8 | *b as u64))
+}
+
+// 天数换算见 Howard Hinnant 的 days_from_civil
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// 严格 DER 的 TLV 读取，只支持单字节标签
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader { data, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos == self.data.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.pos).copied()
+    }
+
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.pos..]
+    }
+
+    fn finish(&self) -> Result<(), CryptoError> {
+        if !self.is_empty() {
+            return Err(CryptoError::InvalidInput("x509: trailing data".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 返回 (标签, 内容, 完整的 TLV)
+    fn read(&mut self) -> Result<(u8, &'a [u8], &'a [u8]), CryptoError> {
+        let malformed = || CryptoError::InvalidInput("x509: malformed DER".to_string());
+        let data = self.rest();
+        let (&tag, rest) = data.split_first().ok_or_else(malformed)?;
+        let (&first, rest) = rest.split_first().ok_or_else(malformed)?;
+        let (len, rest) = match first {
+            0..=0x7f => (first as usize, rest),
+            0x81..=0x84 => {
+                let n = (first & 0x7f) as usize;
+                if rest.len() < n || rest[0] == 0 {
+                    return Err(malformed());
+                }
+                let len = rest[..n]
+                    .iter()
+                    .fold(0usize, |acc, b| acc << 8 | *b as usize);
+                // 长度必须使用最短编码
+                if len < 0x80 {
+                    return Err(malformed());
+                }
+                (len, &rest[n..])
+            }
+            _ => return Err(malformed()),
+        };
+        if rest.len() < len {
+            return Err(malformed());
+        }
+        let header = data.len() - rest.len();
+        self.pos += header + len;
+        Ok((tag, &rest[..len], &data[..header + len]))
+    }
+
+    /// 读取指定标签的 TLV，返回 (内容, 完整的 TLV)
+    fn expect(&mut self, tag: u8) -> Result<(&'a [u8], &'a [u8]), CryptoError> {
+        match self.read()? {
+            (found, content, full) if found == tag => Ok((content, full)),
+            (found, _, _) => Err(CryptoError::InvalidInput(format!(
+                "x509: expected tag {:#04x}, found {:#04x}",
+                tag, found
+            ))),
+        }
+    }
+
+    /// 读取 UTCTime (YYMMDDHHMMSSZ) 或 GeneralizedTime (YYYYMMDDHHMMSSZ)
+    fn time(&mut self) -> Result<u64, CryptoError> {
+        let malformed = || CryptoError::InvalidInput("x509: malformed time".to_string());
+        let (tag, content, _) = self.read()?;
+        let digits = match (tag, content.split_last()) {
+            (TAG_UTC_TIME, Some((b'Z', digits))) if digits.len() == 12 => digits,
+            (TAG_GENERALIZED_TIME, Some((b'Z', digits))) if digits.len() == 14 => digits,
+            _ => return Err(malformed()),
+        };
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return Err(malformed());
+        }
+        let num = |s: &[u8]| s.iter().fold(0i64, |acc, d| acc * 10 + (d - b'0') as i64);
+        let (year, rest) = if tag == TAG_UTC_TIME {
+            // RFC 5280 4.1.2.5.1：YY 小于50为20YY，否则为19YY
+            let yy = num(&digits[..2]);
+            (if yy < 50 { 2000 + yy } else { 1900 + yy }, &digits[2..])
+        } else {
+            (num(&digits[..4]), &digits[4..])
+        };
+        let (month, day) = (num(&rest[..2]), num(&rest[2..4]));
+        let (hour, minute, second) = (num(&rest[4..6]), num(&rest[6..8]), num(&rest[8..10]));
+        if !(1..=12).contains(&month)
+            || !(1..=31).contains(&day)
+            || hour > 23
+            || minute > 59
+            || second > 59
+        {
+            return Err(malformed());
+        }
+        let secs = days_from_civil(year, month, day) * 86400 + hour * 3600 + minute * 60 + second;
+        Ok(secs.max(0) as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_chain() {
+        // openssl 生成：P-256 根证书，RSA-2048 中间证书 (pathlen:0)，Ed25519 叶子证书，
+        // 以及用叶子证书的私钥签发的证书
+        let certs = parse_pem_chain(include_str!("../tests/x509-chain.pem")).unwrap();
+        let (root, intermediate, leaf, evil) = (&certs[0], &certs[1], &certs[2], &certs[3]);
+        assert_eq!(root.signature_algorithm(), SignatureAlgorithm::EcdsaSha256);
+        assert_eq!(
+            intermediate.signature_algorithm(),
+            SignatureAlgorithm::EcdsaSha256
+        );
+        assert_eq!(leaf.signature_algorithm(), SignatureAlgorithm::RsaSha256);
+        assert_eq!(evil.signature_algorithm(), SignatureAlgorithm::Ed25519);
+        // UTCTime 和 GeneralizedTime，与 openssl x509 -dates 的输出对比
+        assert_eq!(intermediate.not_before(), 1791981358);
+        assert_eq!(intermediate.not_after(), 4945581358);
+        assert_eq!(leaf.not_after(), 2107341358);
+        assert!(root.is_ca() && intermediate.is_ca() && !leaf.is_ca());
+        assert_eq!(leaf.public_key().unwrap().0, PublicKeyType::Ed25519);
+        assert_eq!(leaf.issuer(), intermediate.subject());
+        assert_eq!(Certificate::from_der(leaf.der()).unwrap(), *leaf);
+
+        assert!(root.verify_signed_by(root).is_ok());
+        assert!(leaf.verify_signed_by(intermediate).is_ok());
+        assert!(evil.verify_signed_by(leaf).is_ok());
+        assert_eq!(
+            leaf.verify_signed_by(root),
+            Err(VerifyFailure::MalformedKey)
+        );
+
+        let now = leaf.not_before() + 60;
+        let chain = [leaf.clone(), intermediate.clone()];
+        let roots = [root.clone()];
+        verify_chain(&chain, &roots, now).unwrap();
+        verify_chain(
+            &[leaf.clone(), intermediate.clone(), root.clone()],
+            &roots,
+            now,
+        )
+        .unwrap();
+        verify_chain(std::slice::from_ref(intermediate), &roots, now).unwrap();
+        // 不在有效期内
+        assert!(verify_chain(&chain, &roots, leaf.not_before() - 1).is_err());
+        assert!(verify_chain(&chain, &roots, leaf.not_after() + 1).is_err());
+        // 缺少中间证书，或根证书不被信任
+        assert!(verify_chain(std::slice::from_ref(leaf), &roots, now).is_err());
+        assert!(verify_chain(&chain, std::slice::from_ref(intermediate), now).is_ok());
+        assert!(verify_chain(&chain, std::slice::from_ref(leaf), now).is_ok());
+        assert!(verify_chain(&chain, &[], now).is_err());
+        assert!(verify_chain(&[], &roots, now).is_err());
+        // 叶子证书不是 CA，不能签发证书
+        let evil_chain = [evil.clone(), leaf.clone(), intermediate.clone()];
+        assert!(verify_chain(&evil_chain, &roots, now).is_err());
+        // 颁发者名称不匹配
+        let wrong_order = [leaf.clone(), intermediate.clone(), intermediate.clone()];
+        assert!(verify_chain(&wrong_order, &roots, now).is_err());
+
+        // 篡改签名值
+        let mut der = leaf.der().to_vec();
+        let last = der.len() - 1;
+        der[last] ^= 1;
+        let forged = Certificate::from_der(&der).unwrap();
+        assert!(verify_chain(&[forged, intermediate.clone()], &roots, now).is_err());
+        assert!(Certificate::from_der(&der[..der.len() - 1]).is_err());
+    }
+}
@@ -1,32 +1,34 @@
 #[cfg(feature = "bls")]
 use pi_crypto::bls::*;
+#[cfg(feature = "bls")]
+use pi_crypto::signature::{Signer, Verifier};
 
 #[cfg(feature = "bls")]
 #[test]
 fn test_bls() {
     {
-        assert!(bls_init(Curve::MclBnCurveFp254BNb));
+        assert!(bls_init(Curve::MclBnCurveFp254BNb).is_ok());
         assert!(bls_get_op_unit_size() == 4);
-        assert!(bls_get_curve_order(256).is_some());
-        assert!(bls_get_field_order(256).is_some());
+        assert!(bls_get_curve_order(256).is_ok());
+        assert!(bls_get_field_order(256).is_ok());
         bls_get_generator_of_g2();
         // let bls_id_set_dec_str = bls_id_set_int(0x7fffffff);
         let dec_str = "23156878976321321325446789".to_string();
         let dec_id = bls_id_set_dec_str(dec_str.clone());
-        assert!(dec_id.is_some());
-        assert!(bls_id_get_dec_str(32, dec_id.as_ref().unwrap()) == Some(dec_str));
+        assert!(dec_id.is_ok());
+        assert!(bls_id_get_dec_str(32, dec_id.as_ref().unwrap()) == Ok(dec_str));
         let hex_str = "aabcdeABCDE0123789".to_string();
         let hex_id = bls_id_set_hex_str(hex_str.clone());
-        assert!(hex_id.is_some());
+        assert!(hex_id.is_ok());
         assert!(
-            bls_id_get_hex_str(32, hex_id.as_ref().unwrap()) == Some(hex_str.to_ascii_lowercase())
+            bls_id_get_hex_str(32, hex_id.as_ref().unwrap()) == Ok(hex_str.to_ascii_lowercase())
         );
 
         let hex_id = bls_id_set_hex_str(hex_str.clone());
         let hex_id_s = bls_id_serialize(32, hex_id.as_ref().unwrap());
-        assert!(hex_id_s.is_some());
+        assert!(hex_id_s.is_ok());
         let copy_hex_id = bls_id_deserialize(hex_id_s.unwrap());
-        assert!(copy_hex_id.is_some());
+        assert!(copy_hex_id.is_ok());
         assert!(bls_id_is_equal(
             hex_id.as_ref().unwrap(),
             copy_hex_id.as_ref().unwrap()
@@ -37,11 +39,11 @@ fn test_bls() {
                 .to_string()
                 .into_bytes(),
         );
-        assert!(sec_key.is_some());
+        assert!(sec_key.is_ok());
         let sec_key_s = bls_secret_key_serialize(32, sec_key.as_ref().unwrap());
-        assert!(sec_key_s.is_some());
+        assert!(sec_key_s.is_ok());
         let copy_sec_key = bls_secret_key_deserialize(sec_key_s.unwrap());
-        assert!(copy_sec_key.is_some());
+        assert!(copy_sec_key.is_ok());
         assert!(bls_secret_key_is_equal(
             sec_key.as_ref().unwrap(),
             copy_sec_key.as_ref().unwrap()
@@ -55,11 +57,11 @@ fn test_bls() {
         bls_add_secret_key_to_vec(&mut secs, sec1.as_ref().unwrap());
 
         let pub_key = bls_get_public_key(bls_get_secret_key_from_vec(&secs, 0).as_ref().unwrap());
-        assert!(pub_key.is_some());
+        assert!(pub_key.is_ok());
         let pub_key_s = bls_public_key_serialize(64, pub_key.as_ref().unwrap());
-        assert!(pub_key_s.is_some());
+        assert!(pub_key_s.is_ok());
         let copy_pub_key = bls_public_key_deserialize(pub_key_s.unwrap());
-        assert!(copy_pub_key.is_some());
+        assert!(copy_pub_key.is_ok());
         assert!(bls_public_key_is_equal(
             pub_key.as_ref().unwrap(),
             copy_pub_key.as_ref().unwrap()
@@ -81,15 +83,15 @@ fn test_bls() {
         );
 
         let sig = bls_get_pop(sec_key.as_ref().unwrap());
-        assert!(sig.is_some());
+        assert!(sig.is_ok());
         assert!(bls_verify_pop(
             sig.as_ref().unwrap(),
             pub_key.as_ref().unwrap()
         ));
         let sig_s = bls_signature_serialize(32, sig.as_ref().unwrap());
-        assert!(sig_s.is_some());
+        assert!(sig_s.is_ok());
         let copy_sig = bls_signature_deserialize(sig_s.unwrap());
-        assert!(copy_sig.is_some());
+        assert!(copy_sig.is_ok());
         assert!(bls_signature_is_equal(
             sig.as_ref().unwrap(),
             copy_sig.as_ref().unwrap()
@@ -118,14 +120,14 @@ fn test_bls() {
         bls_add_secret_key_to_vec(&mut sec_vec, sec_key2.as_ref().unwrap());
 
         let msk = bls_secret_key_recover(&sec_vec, &id_vec, 3);
-        assert!(msk.is_some());
+        assert!(msk.is_ok());
         assert!(bls_secret_key_is_equal(
             msk.as_ref().unwrap(),
             sec_key.as_ref().unwrap()
         ));
 
         let mpk = bls_get_public_key_vec(&pubs);
-        assert!(mpk.is_some());
+        assert!(mpk.is_ok());
         let pub_key0 = bls_public_key_share(mpk.as_ref().unwrap(), 3, &id0);
         let pub_key1 = bls_public_key_share(mpk.as_ref().unwrap(), 3, &id1);
         let pub_key2 = bls_public_key_share(mpk.as_ref().unwrap(), 3, &id2);
@@ -138,7 +140,7 @@ fn test_bls() {
         bls_add_public_key_to_vec(&mut pub_vec, pub_key2.as_ref().unwrap());
 
         let mpk0 = bls_public_key_recover(&pub_vec, &id_vec, 3);
-        assert!(mpk0.is_some());
+        assert!(mpk0.is_ok());
         assert!(bls_public_key_is_equal(
             mpk0.as_ref().unwrap(),
             mpk.as_ref().unwrap()
@@ -146,12 +148,32 @@ fn test_bls() {
 
         let bin = std::sync::Arc::new(vec![10, 10, 10, 10, 10, 10]);
         let sig = bls_sign(sec_key.as_ref().unwrap(), bin.clone());
-        assert!(sig.is_some());
+        assert!(sig.is_ok());
         assert!(bls_verify(
             sig.as_ref().unwrap(),
             pub_key.as_ref().unwrap(),
             bin.clone()
         ));
+        let sig_s = bls_signature_serialize(32, sig.as_ref().unwrap()).unwrap();
+        let pub_key_s = bls_public_key_serialize(64, pub_key.as_ref().unwrap()).unwrap();
+        assert!(bls_verify_serialized(&sig_s, &pub_key_s, bin.clone()));
+        let signer = sec_key.as_ref().unwrap();
+        let generic_sig = Signer::sign(signer, &bin).unwrap();
+        assert!(Signer::public_key(signer)
+            .unwrap()
+            .verify(&bin, &generic_sig)
+            .is_ok());
+        assert!(bls_validate_public_key(&vec![0u8; pub_key_s.len()]).is_err());
+        assert!(bls_validate_signature(&vec![0u8; sig_s.len()]).is_err());
+
+        // BN254 上 x = 2 的点在 G2 所在的扭曲线 y^2 = x^3 + 1 - i 上，但不在素数阶子群中
+        let mut outside = vec![0u8; pub_key_s.len()];
+        outside[0] = 2;
+        bls_set_verify_order(false);
+        assert!(bls_public_key_deserialize(outside.clone()).is_ok());
+        bls_set_verify_order(true);
+        assert!(bls_public_key_deserialize(outside.clone()).is_err());
+        assert!(bls_validate_public_key(&outside).is_err());
 
         let sig0 = bls_sign(sec_key0.as_ref().unwrap(), bin.clone());
         let sig1 = bls_sign(sec_key1.as_ref().unwrap(), bin.clone());
@@ -170,5 +192,52 @@ fn test_bls() {
             pub_key.as_ref().unwrap(),
             bin.clone()
         ));
+
+        let agg_sig = bls_aggregate_signatures(&[
+            sig0.as_ref().unwrap(),
+            sig1.as_ref().unwrap(),
+            sig2.as_ref().unwrap(),
+        ])
+        .unwrap();
+        let agg_pubs = [
+            pub_key0.as_ref().unwrap(),
+            pub_key1.as_ref().unwrap(),
+            pub_key2.as_ref().unwrap(),
+        ];
+        assert!(bls_verify_aggregate(&agg_sig, &agg_pubs, bin.clone()));
+        assert!(!bls_verify_aggregate(&agg_sig, &agg_pubs[..2], bin.clone()));
+        assert!(!bls_verify_aggregate(&agg_sig, &[], bin.clone()));
+        assert!(bls_aggregate_signatures(&[]).is_err());
+        // 聚合不修改输入
+        assert!(bls_verify(
+            sig0.as_ref().unwrap(),
+            pub_key0.as_ref().unwrap(),
+            bin.clone()
+        ));
+
+        let ids: Vec<BlsId> = (1..=5).map(bls_id_set_int).collect();
+        let id_refs: Vec<&BlsId> = ids.iter().collect();
+        let keys = bls_generate_key_shares(3, &id_refs).unwrap();
+        assert!(bls_generate_key_shares(6, &id_refs).is_err());
+        assert!(bls_generate_key_shares(2, &[&ids[0], &ids[0]]).is_err());
+        let partial: Vec<BlsSignature> = [4, 1, 3]
+            .iter()
+            .map(|&i| {
+                let sig = bls_partial_sign(&keys.secret_shares[i], bin.clone()).unwrap();
+                assert!(bls_verify_partial(
+                    &sig,
+                    &keys.public_shares[i],
+                    bin.clone()
+                ));
+                sig
+            })
+            .collect();
+        let partial_refs: Vec<&BlsSignature> = partial.iter().collect();
+        let recovered =
+            bls_recover_signature(&[&ids[4], &ids[1], &ids[3]], &partial_refs, 3).unwrap();
+        assert!(bls_verify(&recovered, &keys.public_key, bin.clone()));
+        assert!(bls_recover_signature(&[&ids[4], &ids[1]], &partial_refs[..2], 3).is_err());
+        let wrong = bls_recover_signature(&[&ids[0], &ids[1], &ids[3]], &partial_refs, 3).unwrap();
+        assert!(!bls_verify(&wrong, &keys.public_key, bin.clone()));
     }
 }